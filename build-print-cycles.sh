@@ -0,0 +1,22 @@
+#!/usr/bin/env bash
+
+set -euo pipefail
+
+export RUSTUP_NO_UPDATE_CHECK=1
+PROFILE="release"
+ROOT="$(git rev-parse --show-toplevel 2>/dev/null || (cd "$(dirname "${BASH_SOURCE[0]}")" >/dev/null 2>&1 && pwd))"
+cd "${ROOT}"
+
+# no-std mode only
+echo "Building print-cycles example in no-std mode ..."
+TARGET_TRIPLE="riscv64imac-unknown-none-elf"
+OUT_DIR="${ROOT}/target/${TARGET_TRIPLE}/$([ "$PROFILE" = "dev" ] && echo debug || echo "$PROFILE")"
+BIN="${OUT_DIR}/print-cycles"
+
+cargo spike build -p print-cycles --target "${TARGET_TRIPLE}" -- --quiet --features=with-spike --profile "${PROFILE}"
+OUT="$(mktemp)"
+trap 'rm -f "${OUT}"' EXIT
+
+cargo spike run "${BIN}" --isa RV64IMAC --instructions 10000000 | tee "${OUT}"
+grep -q "print-cycles: label+2 ints" "${OUT}"
+grep -q "Test PASSED" "${OUT}"
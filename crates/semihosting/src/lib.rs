@@ -0,0 +1,8 @@
+#![no_std]
+
+mod macros;
+pub mod semihosting;
+mod writer;
+
+pub use semihosting::*;
+pub use writer::DebugWriter;
@@ -0,0 +1,14 @@
+// Print macros for semihosting console output
+
+#[macro_export]
+macro_rules! println {
+    ($($arg:tt)*) => {{
+        use core::fmt::Write;
+        let _ = core::writeln!($crate::DebugWriter, $($arg)*);
+    }};
+}
+
+#[macro_export]
+macro_rules! eprintln {
+    ($($arg:tt)*) => { $crate::println!($($arg)*); }
+}
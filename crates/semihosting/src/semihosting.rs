@@ -0,0 +1,121 @@
+// RISC-V semihosting (the Arm semihosting ABI, trapped by the `slli/ebreak/srai` sequence).
+//
+// Protocol notes:
+//   - a0 holds the operation, a1 a pointer to its parameter block (or the parameter itself);
+//     the result comes back in a0.
+//   - The three instructions must be uncompressed so the host recognizes the sequence.
+//   - Without `-semihosting` the `ebreak` traps as an ordinary breakpoint; the trap handler
+//     skips it and a0 still holds the operation number.
+
+use core::arch::asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+pub const SYS_OPEN: usize = 0x01;
+pub const SYS_CLOSE: usize = 0x02;
+pub const SYS_WRITEC: usize = 0x03;
+pub const SYS_WRITE: usize = 0x05;
+pub const SYS_READ: usize = 0x06;
+pub const SYS_EXIT_EXTENDED: usize = 0x20;
+
+/// `SYS_OPEN` modes, as `fopen` strings: `"r"` and `"w"`.
+pub const OPEN_READ: usize = 0;
+pub const OPEN_WRITE: usize = 4;
+
+/// `SYS_EXIT_EXTENDED` reason for a normal application exit.
+const ADP_STOPPED_APPLICATION_EXIT: usize = 0x20026;
+
+/// Issue semihosting operation `op`.
+///
+/// # Safety
+/// `param` must be what `op` expects, e.g. a pointer to a valid parameter block.
+#[inline(always)]
+pub unsafe fn call(op: usize, param: usize) -> usize {
+    let ret;
+    asm!(
+        ".balign 16",
+        ".option push",
+        ".option norvc",
+        "slli x0, x0, 0x1f",
+        "ebreak",
+        "srai x0, x0, 7",
+        ".option pop",
+        inlateout("a0") op => ret,
+        in("a1") param,
+        options(nostack),
+    );
+    ret
+}
+
+/// Handles for the host console, opened on first use.
+static STDIN: AtomicUsize = AtomicUsize::new(usize::MAX);
+static STDOUT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+fn console(handle: &AtomicUsize, mode: usize) -> usize {
+    let h = handle.load(Ordering::Relaxed);
+    if h != usize::MAX {
+        return h;
+    }
+    // ":tt" names the host console.
+    let h = open(c":tt", mode).unwrap_or(usize::MAX);
+    handle.store(h, Ordering::Relaxed);
+    h
+}
+
+pub fn stdin() -> usize {
+    console(&STDIN, OPEN_READ)
+}
+
+pub fn stdout() -> usize {
+    console(&STDOUT, OPEN_WRITE)
+}
+
+/// Open `path` on the host; `None` if the host refuses.
+pub fn open(path: &core::ffi::CStr, mode: usize) -> Option<usize> {
+    let block = [path.as_ptr() as usize, mode, path.to_bytes().len()];
+    // SAFETY: `block` is a valid SYS_OPEN parameter block; `path` is NUL-terminated.
+    let h = unsafe { call(SYS_OPEN, block.as_ptr() as usize) };
+    (h as isize >= 0).then_some(h)
+}
+
+pub fn close(handle: usize) {
+    let block = [handle];
+    // SAFETY: `block` is a valid SYS_CLOSE parameter block.
+    unsafe { call(SYS_CLOSE, block.as_ptr() as usize) };
+}
+
+/// Write `buf` to `handle`; returns the number of bytes written.
+pub fn write(handle: usize, buf: &[u8]) -> usize {
+    if buf.is_empty() {
+        return 0;
+    }
+    let block = [handle, buf.as_ptr() as usize, buf.len()];
+    // SAFETY: `block` describes `buf`, which stays borrowed for the call.
+    let not_written = unsafe { call(SYS_WRITE, block.as_ptr() as usize) };
+    buf.len().saturating_sub(not_written)
+}
+
+/// Read into `buf` from `handle`; returns the number of bytes read (0 at EOF).
+pub fn read(handle: usize, buf: &mut [u8]) -> usize {
+    if buf.is_empty() {
+        return 0;
+    }
+    let block = [handle, buf.as_mut_ptr() as usize, buf.len()];
+    // SAFETY: `block` describes `buf`, which stays mutably borrowed for the call.
+    let not_read = unsafe { call(SYS_READ, block.as_ptr() as usize) };
+    buf.len().saturating_sub(not_read)
+}
+
+pub fn putchar(ch: u8) {
+    // SAFETY: SYS_WRITEC takes a pointer to the character.
+    unsafe { call(SYS_WRITEC, &ch as *const u8 as usize) };
+}
+
+pub fn exit(code: u32) -> ! {
+    let block = [ADP_STOPPED_APPLICATION_EXIT, code as usize];
+    // SAFETY: `block` is a valid SYS_EXIT_EXTENDED parameter block.
+    unsafe { call(SYS_EXIT_EXTENDED, block.as_ptr() as usize) };
+    loop {
+        // The host ends the run; only reached without semihosting.
+        unsafe { asm!("wfi") };
+    }
+}
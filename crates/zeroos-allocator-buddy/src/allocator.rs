@@ -30,6 +30,20 @@ pub(crate) fn dealloc(ptr: *mut u8, layout: Layout) {
     }
 }
 
+pub(crate) fn used() -> usize {
+    HEAP.lock().stats_alloc_actual()
+}
+
+/// Buddy free lists take blocks from anywhere, so any range can be added.
+pub(crate) fn grow(start: usize, len: usize) -> bool {
+    let Some(end) = start.checked_add(len) else {
+        return false;
+    };
+    // SAFETY: the caller hands over `[start, end)`, which nothing else uses.
+    unsafe { HEAP.lock().add_to_heap(start, end) };
+    true
+}
+
 pub(crate) fn realloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
     if ptr.is_null() {
         let new_layout = match Layout::from_size_align(new_size, old_layout.align()) {
@@ -0,0 +1,6 @@
+fn main() {
+    // Call sites are only sampled when the build system forces frame pointers
+    println!(
+        "cargo::rustc-check-cfg=cfg(zeroos_backtrace, values(\"off\", \"dwarf\", \"frame_pointers\"))"
+    );
+}
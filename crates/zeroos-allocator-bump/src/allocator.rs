@@ -6,6 +6,8 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 extern crate alloc;
 
 pub(crate) struct BumpAllocator {
+    start: AtomicUsize,
+
     next: AtomicUsize,
 
     end: AtomicUsize,
@@ -14,12 +16,14 @@ pub(crate) struct BumpAllocator {
 impl BumpAllocator {
     pub(crate) const fn new() -> Self {
         Self {
+            start: AtomicUsize::new(0),
             next: AtomicUsize::new(0),
             end: AtomicUsize::new(0),
         }
     }
 
     pub(crate) fn init(&self, heap_start: usize, heap_size: usize) {
+        self.start.store(heap_start, Ordering::SeqCst);
         self.next.store(heap_start, Ordering::SeqCst);
         let end = heap_start.checked_add(heap_size).unwrap_or(heap_start);
         self.end.store(end, Ordering::SeqCst);
@@ -49,6 +53,22 @@ impl BumpAllocator {
         }
     }
 
+    /// Extend the arena by `len` bytes, if `start` is where it currently ends.
+    pub(crate) fn grow(&self, start: usize, len: usize) -> bool {
+        let Some(end) = start.checked_add(len) else {
+            return false;
+        };
+        self.end
+            .compare_exchange(start, end, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Bytes consumed so far; a bump allocator never gets them back.
+    pub(crate) fn used(&self) -> usize {
+        let start = self.start.load(Ordering::Acquire);
+        self.next.load(Ordering::Acquire).saturating_sub(start)
+    }
+
     #[allow(dead_code)]
     pub unsafe fn reset(&self) {
         let end = self.end.load(Ordering::Acquire);
@@ -82,8 +102,20 @@ pub(crate) fn init(heap_start: usize, heap_size: usize) {
     ALLOCATOR.init(heap_start, heap_size);
 }
 
+#[inline(always)]
 pub(crate) fn alloc(layout: Layout) -> *mut u8 {
-    ALLOCATOR.alloc(layout)
+    let ptr = ALLOCATOR.alloc(layout);
+    #[cfg(feature = "alloc-stats")]
+    crate::stats::record(layout.size(), ptr);
+    ptr
+}
+
+pub(crate) fn used() -> usize {
+    ALLOCATOR.used()
+}
+
+pub(crate) fn grow(start: usize, len: usize) -> bool {
+    ALLOCATOR.grow(start, len)
 }
 
 pub(crate) fn dealloc(_ptr: *mut u8, _layout: Layout) {}
@@ -169,4 +201,32 @@ mod tests {
             assert_eq!(ptr as usize % align, 0, "Alignment {} failed", align);
         }
     }
+
+    #[test]
+    fn test_used_tracks_bump_pointer() {
+        const HEAP_SIZE: usize = 4096;
+        let mut heap_mem = alloc::vec![0u8; HEAP_SIZE];
+        let heap = BumpAllocator::new();
+        heap.init(heap_mem.as_mut_ptr() as usize, HEAP_SIZE);
+        assert_eq!(heap.used(), 0);
+
+        let ptr = heap.alloc(Layout::from_size_align(100, 8).unwrap());
+        assert!(!ptr.is_null());
+        assert!(heap.used() >= 100);
+    }
+
+    #[test]
+    fn test_grow_extends_end() {
+        const HEAP_SIZE: usize = 1024;
+        let mut heap_mem = alloc::vec![0u8; 2 * HEAP_SIZE];
+        let heap_start = heap_mem.as_mut_ptr() as usize;
+        let heap = BumpAllocator::new();
+        heap.init(heap_start, HEAP_SIZE);
+
+        let layout = Layout::from_size_align(HEAP_SIZE + 8, 8).unwrap();
+        assert!(heap.alloc(layout).is_null());
+        assert!(!heap.grow(heap_start + HEAP_SIZE + 8, HEAP_SIZE - 8));
+        assert!(heap.grow(heap_start + HEAP_SIZE, HEAP_SIZE));
+        assert!(!heap.alloc(layout).is_null());
+    }
 }
@@ -1,6 +1,8 @@
 #![no_std]
 
 mod allocator;
+#[cfg(feature = "alloc-stats")]
+pub mod stats;
 
 use foundation::ops::MemoryOps;
 
@@ -9,4 +11,6 @@ pub const BUMP_ALLOCATOR_OPS: MemoryOps = MemoryOps {
     alloc: allocator::alloc,
     dealloc: allocator::dealloc,
     realloc: allocator::realloc,
+    used: allocator::used,
+    grow: allocator::grow,
 };
@@ -0,0 +1,349 @@
+//! Allocation tracking for the bump allocator.
+//!
+//! A bump allocator never frees, so the interesting question is who asked for the bytes. Every
+//! allocation is counted in a power-of-two size class and charged to its call site, sampled
+//! from the return address [`SITE_DEPTH`] frames above the allocator. Sites are only available
+//! when the guest is built with frame pointers (`zeroos_backtrace = "frame_pointers"`); without
+//! them every allocation is counted as unattributed.
+//!
+//! Counters are atomics, so harts allocating concurrently never lose updates. [`dump`] writes a
+//! summary to the debug console.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Size classes: `<= 16` bytes, then each power of two up to 128 KiB, then everything larger.
+pub const SIZE_CLASSES: usize = 15;
+
+/// Upper bound of the smallest size class.
+const MIN_CLASS_SIZE: usize = 16;
+
+/// Distinct call sites tracked; allocations from later sites are counted as dropped.
+pub const MAX_SITES: usize = 16;
+
+/// Frames walked up from the allocator entry point, skipping the kernel and `GlobalAlloc` shims.
+pub const SITE_DEPTH: usize = 4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SizeClass {
+    /// Largest size in the class; `usize::MAX` for the last one.
+    pub max_size: usize,
+    pub calls: usize,
+    pub bytes: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CallSite {
+    /// Return address the allocation was charged to.
+    pub addr: usize,
+    pub calls: usize,
+    pub bytes: usize,
+}
+
+struct Counter {
+    calls: AtomicUsize,
+    bytes: AtomicUsize,
+}
+
+impl Counter {
+    const fn new() -> Self {
+        Self {
+            calls: AtomicUsize::new(0),
+            bytes: AtomicUsize::new(0),
+        }
+    }
+
+    fn add(&self, size: usize) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(size, Ordering::Relaxed);
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+pub struct AllocStats {
+    classes: [Counter; SIZE_CLASSES],
+    /// Claimed by storing a non-zero address; never released.
+    site_addrs: [AtomicUsize; MAX_SITES],
+    sites: [Counter; MAX_SITES],
+    /// Allocations without a return address.
+    unattributed: AtomicUsize,
+    /// Allocations from sites that did not fit.
+    dropped: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+impl AllocStats {
+    pub const fn new() -> Self {
+        Self {
+            classes: [const { Counter::new() }; SIZE_CLASSES],
+            site_addrs: [const { AtomicUsize::new(0) }; MAX_SITES],
+            sites: [const { Counter::new() }; MAX_SITES],
+            unattributed: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Count an allocation of `size` bytes made from `site` (0 if unknown).
+    pub fn record(&self, size: usize, site: usize) {
+        self.classes[class_of(size)].add(size);
+
+        if site == 0 {
+            self.unattributed.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        for (addr, counter) in self.site_addrs.iter().zip(&self.sites) {
+            let current = match addr.compare_exchange(0, site, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => site,
+                Err(current) => current,
+            };
+            if current == site {
+                counter.add(size);
+                return;
+            }
+        }
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count an allocation the heap could not satisfy.
+    pub fn record_failure(&self) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn class(&self, idx: usize) -> SizeClass {
+        let max_size = if idx + 1 == SIZE_CLASSES {
+            usize::MAX
+        } else {
+            MIN_CLASS_SIZE << idx
+        };
+        SizeClass {
+            max_size,
+            calls: self.classes[idx].calls(),
+            bytes: self.classes[idx].bytes(),
+        }
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = SizeClass> + '_ {
+        (0..SIZE_CLASSES).map(|idx| self.class(idx))
+    }
+
+    /// Tracked call sites, in the order they were first seen.
+    pub fn sites(&self) -> impl Iterator<Item = CallSite> + '_ {
+        self.site_addrs
+            .iter()
+            .zip(&self.sites)
+            .map(|(addr, counter)| CallSite {
+                addr: addr.load(Ordering::Acquire),
+                calls: counter.calls(),
+                bytes: counter.bytes(),
+            })
+            .filter(|site| site.addr != 0)
+    }
+
+    pub fn total_calls(&self) -> usize {
+        self.classes.iter().map(Counter::calls).sum()
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.classes.iter().map(Counter::bytes).sum()
+    }
+
+    pub fn unattributed(&self) -> usize {
+        self.unattributed.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for AllocStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn class_of(size: usize) -> usize {
+    if size <= MIN_CLASS_SIZE {
+        return 0;
+    }
+    // ceil(log2(size)) - log2(MIN_CLASS_SIZE)
+    let log2 = (usize::BITS - (size - 1).leading_zeros()) as usize;
+    (log2 - MIN_CLASS_SIZE.trailing_zeros() as usize).min(SIZE_CLASSES - 1)
+}
+
+static STATS: AllocStats = AllocStats::new();
+
+#[inline(always)]
+pub(crate) fn record(size: usize, ptr: *mut u8) {
+    if ptr.is_null() {
+        STATS.record_failure();
+    } else {
+        STATS.record(size, call_site());
+    }
+}
+
+/// Counters for every allocation made through the bump allocator.
+pub fn stats() -> &'static AllocStats {
+    &STATS
+}
+
+/// Write the size-class histogram and the heaviest call sites to the debug console.
+pub fn dump() {
+    debug::writeln!(
+        "[alloc] {} allocations, {} bytes, {} failed",
+        STATS.total_calls(),
+        STATS.total_bytes(),
+        STATS.failed()
+    );
+    for class in STATS.classes().filter(|c| c.calls != 0) {
+        if class.max_size == usize::MAX {
+            debug::writeln!(
+                "[alloc]   > {} B: {} allocs, {} bytes",
+                MIN_CLASS_SIZE << (SIZE_CLASSES - 2),
+                class.calls,
+                class.bytes
+            );
+        } else {
+            debug::writeln!(
+                "[alloc]   <= {} B: {} allocs, {} bytes",
+                class.max_size,
+                class.calls,
+                class.bytes
+            );
+        }
+    }
+
+    let mut sites = [CallSite::default(); MAX_SITES];
+    let mut len = 0;
+    for site in STATS.sites() {
+        sites[len] = site;
+        len += 1;
+    }
+    let sites = &mut sites[..len];
+    sites.sort_unstable_by(|a, b| b.bytes.cmp(&a.bytes));
+    for site in sites.iter() {
+        debug::writeln!(
+            "[alloc] site {:#x}: {} allocs, {} bytes",
+            site.addr,
+            site.calls,
+            site.bytes
+        );
+    }
+    if STATS.dropped() != 0 {
+        debug::writeln!(
+            "[alloc] ... {} allocs from untracked sites",
+            STATS.dropped()
+        );
+    }
+    if STATS.unattributed() != 0 {
+        debug::writeln!(
+            "[alloc] ... {} allocs without a call site",
+            STATS.unattributed()
+        );
+    }
+}
+
+/// Return address `SITE_DEPTH` frames up, or 0 if the chain ends early.
+#[cfg(all(
+    zeroos_backtrace = "frame_pointers",
+    any(target_arch = "riscv32", target_arch = "riscv64")
+))]
+#[inline(always)]
+fn call_site() -> usize {
+    const WORD: usize = core::mem::size_of::<usize>();
+
+    let mut fp: usize;
+    // SAFETY: reads s0, which holds the frame pointer when frame pointers are forced.
+    unsafe { core::arch::asm!("mv {}, s0", out(reg) fp, options(nomem, nostack)) };
+
+    let mut ra = 0;
+    for _ in 0..SITE_DEPTH {
+        if fp < 2 * WORD || !fp.is_multiple_of(WORD) {
+            return 0;
+        }
+        // SAFETY: with frame pointers, fp - WORD holds the saved ra and fp - 2 * WORD the
+        // caller's fp; the chain was checked to be non-null and aligned.
+        unsafe {
+            ra = core::ptr::read_volatile((fp - WORD) as *const usize);
+            fp = core::ptr::read_volatile((fp - 2 * WORD) as *const usize);
+        }
+    }
+    ra
+}
+
+#[cfg(not(all(
+    zeroos_backtrace = "frame_pointers",
+    any(target_arch = "riscv32", target_arch = "riscv64")
+)))]
+#[inline(always)]
+fn call_site() -> usize {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_classes() {
+        assert_eq!(class_of(0), 0);
+        assert_eq!(class_of(16), 0);
+        assert_eq!(class_of(17), 1);
+        assert_eq!(class_of(32), 1);
+        assert_eq!(class_of(4096), 8);
+        assert_eq!(class_of(128 * 1024), SIZE_CLASSES - 2);
+        assert_eq!(class_of(128 * 1024 + 1), SIZE_CLASSES - 1);
+        assert_eq!(class_of(usize::MAX), SIZE_CLASSES - 1);
+
+        let stats = AllocStats::new();
+        assert_eq!(stats.class(8).max_size, 4096);
+        assert_eq!(stats.class(SIZE_CLASSES - 1).max_size, usize::MAX);
+    }
+
+    #[test]
+    fn test_record_sites() {
+        let stats = AllocStats::new();
+        stats.record(8, 0x1000);
+        stats.record(100, 0x2000);
+        stats.record(24, 0x1000);
+        stats.record(40, 0);
+        stats.record_failure();
+
+        assert_eq!(stats.total_calls(), 4);
+        assert_eq!(stats.total_bytes(), 172);
+        assert_eq!(stats.class(0).calls, 1);
+        assert_eq!(stats.class(1).bytes, 24);
+        assert_eq!(stats.unattributed(), 1);
+        assert_eq!(stats.failed(), 1);
+
+        let mut sites = stats.sites();
+        assert_eq!(
+            sites.next(),
+            Some(CallSite {
+                addr: 0x1000,
+                calls: 2,
+                bytes: 32
+            })
+        );
+        assert_eq!(sites.next().map(|s| s.bytes), Some(100));
+        assert_eq!(sites.next(), None);
+
+        for site in 0..MAX_SITES + 2 {
+            stats.record(1, 0x3000 + site);
+        }
+        assert_eq!(stats.sites().count(), MAX_SITES);
+        assert_eq!(stats.dropped(), 4);
+    }
+}
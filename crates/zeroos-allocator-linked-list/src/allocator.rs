@@ -1,6 +1,6 @@
 use core::alloc::Layout;
 use core::ptr;
-use linked_list_allocator::LockedHeap;
+use linked_list_allocator::{Heap, LockedHeap};
 
 #[cfg(test)]
 extern crate alloc;
@@ -29,6 +29,24 @@ pub(crate) fn dealloc(ptr: *mut u8, layout: Layout) {
     }
 }
 
+pub(crate) fn used() -> usize {
+    HEAP.lock().used()
+}
+
+pub(crate) fn grow(start: usize, len: usize) -> bool {
+    grow_heap(&mut HEAP.lock(), start, len)
+}
+
+/// The hole list spans a single range, so only memory right above it can be added.
+fn grow_heap(heap: &mut Heap, start: usize, len: usize) -> bool {
+    if heap.top() as usize != start {
+        return false;
+    }
+    // SAFETY: the caller owns `[start, start + len)`, which begins at the current top.
+    unsafe { heap.extend(len) };
+    true
+}
+
 pub(crate) fn realloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
     if ptr.is_null() {
         let new_layout = match Layout::from_size_align(new_size, old_layout.align()) {
@@ -106,4 +124,24 @@ mod tests {
         let new_layout = Layout::from_size_align(256, 8).unwrap();
         dealloc(new_ptr, new_layout);
     }
+
+    #[test]
+    fn test_grow_contiguous_only() {
+        const HEAP_SIZE: usize = 4096;
+        let mut heap_mem = alloc::vec![0u8; 2 * HEAP_SIZE];
+        let heap_start = heap_mem.as_mut_ptr() as usize;
+        let mut heap = Heap::empty();
+        unsafe { heap.init(heap_start as *mut u8, HEAP_SIZE) };
+
+        let big = Layout::from_size_align(HEAP_SIZE + 1024, 8).unwrap();
+        assert!(heap.allocate_first_fit(big).is_err());
+
+        assert!(!grow_heap(
+            &mut heap,
+            heap_start + HEAP_SIZE + 64,
+            HEAP_SIZE - 64
+        ));
+        assert!(grow_heap(&mut heap, heap_start + HEAP_SIZE, HEAP_SIZE));
+        assert!(heap.allocate_first_fit(big).is_ok());
+    }
 }
@@ -9,4 +9,6 @@ pub const LINKED_LIST_ALLOCATOR_OPS: MemoryOps = MemoryOps {
     alloc: allocator::alloc,
     dealloc: allocator::dealloc,
     realloc: allocator::realloc,
+    used: allocator::used,
+    grow: allocator::grow,
 };
@@ -0,0 +1,59 @@
+use core::alloc::Layout;
+use core::ptr;
+
+use spin::Mutex;
+
+use crate::tlsf::Tlsf;
+
+pub(crate) static HEAP: Mutex<Tlsf> = Mutex::new(Tlsf::new());
+
+pub(crate) fn init(heap_start: usize, heap_size: usize) {
+    let mut heap = HEAP.lock();
+    *heap = Tlsf::new();
+    // SAFETY: the platform hands over `[heap_start, heap_start + heap_size)` for the heap.
+    unsafe { heap.add_pool(heap_start, heap_size) };
+}
+
+pub(crate) fn alloc(layout: Layout) -> *mut u8 {
+    HEAP.lock()
+        .alloc(layout)
+        .map(|nn| nn.as_ptr())
+        .unwrap_or(ptr::null_mut())
+}
+
+pub(crate) fn dealloc(ptr: *mut u8, _layout: Layout) {
+    if !ptr.is_null() {
+        // SAFETY: non-null pointers passed here were returned by `alloc` or `realloc`.
+        unsafe { HEAP.lock().dealloc(ptr) };
+    }
+}
+
+pub(crate) fn used() -> usize {
+    HEAP.lock().used()
+}
+
+/// Memory right above the heap extends it; any other range is added as a separate pool.
+pub(crate) fn grow(start: usize, len: usize) -> bool {
+    // SAFETY: the caller hands over `[start, start + len)`, which nothing else uses.
+    unsafe { HEAP.lock().add_pool(start, len) }
+}
+
+pub(crate) fn realloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
+    if ptr.is_null() {
+        let new_layout = match Layout::from_size_align(new_size, old_layout.align()) {
+            Ok(l) => l,
+            Err(_) => return ptr::null_mut(),
+        };
+        return alloc(new_layout);
+    }
+
+    if new_size == 0 {
+        dealloc(ptr, old_layout);
+        return ptr::null_mut();
+    }
+
+    // SAFETY: `ptr` was returned by `alloc` or `realloc` with `old_layout`.
+    unsafe { HEAP.lock().realloc(ptr, old_layout, new_size) }
+        .map(|nn| nn.as_ptr())
+        .unwrap_or(ptr::null_mut())
+}
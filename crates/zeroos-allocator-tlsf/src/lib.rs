@@ -0,0 +1,15 @@
+#![no_std]
+
+mod allocator;
+mod tlsf;
+
+use foundation::ops::MemoryOps;
+
+pub const TLSF_ALLOCATOR_OPS: MemoryOps = MemoryOps {
+    init: allocator::init,
+    alloc: allocator::alloc,
+    dealloc: allocator::dealloc,
+    realloc: allocator::realloc,
+    used: allocator::used,
+    grow: allocator::grow,
+};
@@ -0,0 +1,513 @@
+//! Two-level segregated fit.
+//!
+//! Free blocks sit in size-class lists indexed on two levels: the first level splits sizes by
+//! power of two, the second splits each power of two into `SL_COUNT` equal steps. One bitmap per
+//! level records which lists are non-empty, so finding a block big enough for a request is two
+//! `trailing_zeros` rather than a list walk, and both malloc and free take constant time however
+//! fragmented the heap gets. Every block links to its physical predecessor and knows its own
+//! size, so a freed block merges with free neighbours on both sides at once.
+//!
+//! A block is a two-word header followed by the payload; free blocks keep their list links in
+//! the first payload words. Each pool ends with a zero-size used sentinel, so merging never runs
+//! past it.
+
+use core::alloc::Layout;
+use core::mem::size_of;
+use core::ptr::{self, NonNull};
+
+const HEADER: usize = 2 * size_of::<usize>();
+/// Block addresses and sizes are multiples of this, so payloads are aligned to it.
+const GRANULE: usize = 2 * size_of::<usize>();
+const GRANULE_LOG2: u32 = GRANULE.trailing_zeros();
+/// Header plus the free-list links.
+const MIN_BLOCK: usize = 4 * size_of::<usize>();
+
+const SL_LOG2: u32 = 4;
+const SL_COUNT: usize = 1 << SL_LOG2;
+/// Sizes below this all map to the first level, in steps of one granule.
+const SMALL: usize = 1 << (SL_LOG2 + GRANULE_LOG2);
+const FL_COUNT: usize = (usize::BITS - SL_LOG2 - GRANULE_LOG2) as usize + 1;
+
+/// Set in `Block::size` while the block is free.
+const FREE: usize = 1;
+
+#[repr(C)]
+struct Block {
+    /// Block physically before this one, or null at the start of a pool.
+    prev_phys: *mut Block,
+    /// Bytes from this header to the next one, plus the `FREE` bit.
+    size: usize,
+    next_free: *mut Block,
+    prev_free: *mut Block,
+}
+
+impl Block {
+    unsafe fn size(this: *mut Block) -> usize {
+        (*this).size & !FREE
+    }
+
+    unsafe fn is_free(this: *mut Block) -> bool {
+        (*this).size & FREE != 0
+    }
+
+    unsafe fn next_phys(this: *mut Block) -> *mut Block {
+        this.byte_add(Self::size(this))
+    }
+
+    unsafe fn payload(this: *mut Block) -> *mut u8 {
+        this.byte_add(HEADER).cast()
+    }
+
+    unsafe fn from_payload(ptr: *mut u8) -> *mut Block {
+        ptr.sub(HEADER).cast()
+    }
+}
+
+/// Block size, header included, that holds `size` payload bytes.
+fn block_size(size: usize) -> Option<usize> {
+    let size = size.checked_add(HEADER + GRANULE - 1)? & !(GRANULE - 1);
+    Some(size.max(MIN_BLOCK))
+}
+
+/// First- and second-level index of the list holding blocks of `size` bytes.
+fn mapping(size: usize) -> (usize, usize) {
+    if size < SMALL {
+        (0, size >> GRANULE_LOG2)
+    } else {
+        let log2 = usize::BITS - 1 - size.leading_zeros();
+        let sl = (size >> (log2 - SL_LOG2)) ^ SL_COUNT;
+        ((log2 - SL_LOG2 - GRANULE_LOG2 + 1) as usize, sl)
+    }
+}
+
+/// Round `size` up to the start of the next size class, so every block in its list fits it.
+fn round_to_class(size: usize) -> Option<usize> {
+    if size < SMALL {
+        return Some(size);
+    }
+    let log2 = usize::BITS - 1 - size.leading_zeros();
+    let step = 1usize << (log2 - SL_LOG2);
+    Some(size.checked_add(step - 1)? & !(step - 1))
+}
+
+pub(crate) struct Tlsf {
+    fl_bitmap: usize,
+    sl_bitmap: [u32; FL_COUNT],
+    heads: [[*mut Block; SL_COUNT]; FL_COUNT],
+    /// Sentinel of the most recently added pool, where a contiguous `add_pool` continues.
+    sentinel: *mut Block,
+    used: usize,
+}
+
+// SAFETY: the block pointers only ever point into pools handed over to this allocator.
+unsafe impl Send for Tlsf {}
+
+impl Tlsf {
+    pub(crate) const fn new() -> Self {
+        Self {
+            fl_bitmap: 0,
+            sl_bitmap: [0; FL_COUNT],
+            heads: [[ptr::null_mut(); SL_COUNT]; FL_COUNT],
+            sentinel: ptr::null_mut(),
+            used: 0,
+        }
+    }
+
+    /// Bytes held by live allocations, block headers and rounding included.
+    pub(crate) fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Hand `[start, start + len)` to the allocator. A range starting where the last pool ends
+    /// extends that pool; any other range becomes a pool of its own. Returns false if the range
+    /// is too small to hold a block.
+    ///
+    /// # Safety
+    /// The range must be valid for reads and writes and used by nothing else.
+    pub(crate) unsafe fn add_pool(&mut self, start: usize, len: usize) -> bool {
+        let Some(end) = start.checked_add(len) else {
+            return false;
+        };
+        let end = end & !(GRANULE - 1);
+        let contiguous = !self.sentinel.is_null() && self.sentinel as usize + HEADER == start;
+        let (block, prev_phys) = if contiguous {
+            (self.sentinel, (*self.sentinel).prev_phys)
+        } else {
+            let Some(first) = start.checked_next_multiple_of(GRANULE) else {
+                return false;
+            };
+            (first as *mut Block, ptr::null_mut())
+        };
+        let Some(size) = end.checked_sub(block as usize + HEADER) else {
+            return false;
+        };
+        if size < MIN_BLOCK {
+            return false;
+        }
+
+        (*block).prev_phys = prev_phys;
+        (*block).size = size;
+        let sentinel = Block::next_phys(block);
+        (*sentinel).prev_phys = block;
+        (*sentinel).size = 0;
+        self.sentinel = sentinel;
+        self.release(block);
+        true
+    }
+
+    pub(crate) fn alloc(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+        let size = block_size(layout.size())?;
+        let align = layout.align();
+        // Room to move the payload up to `align` and free the gap below it as a block.
+        let search = if align <= GRANULE {
+            size
+        } else {
+            size.checked_add(align + MIN_BLOCK)?
+        };
+
+        // SAFETY: every block reached from the lists lies inside a pool.
+        unsafe {
+            let mut block = self.take(search)?;
+            if align > GRANULE {
+                let payload = Block::payload(block) as usize;
+                let mut aligned = payload.next_multiple_of(align);
+                if aligned != payload && aligned - payload < MIN_BLOCK {
+                    aligned += align;
+                }
+                let gap = aligned - payload;
+                if gap != 0 {
+                    let below = block;
+                    block = below.byte_add(gap);
+                    (*block).prev_phys = below;
+                    (*block).size = Block::size(below) - gap;
+                    (*Block::next_phys(block)).prev_phys = block;
+                    (*below).size = gap;
+                    self.release(below);
+                }
+            }
+            if let Some(rest) = Self::split(block, size) {
+                self.release(rest);
+            }
+            self.used += Block::size(block);
+            Some(NonNull::new_unchecked(Block::payload(block)))
+        }
+    }
+
+    /// # Safety
+    /// `ptr` must come from [`alloc`](Self::alloc) or [`realloc`](Self::realloc) on `self` and
+    /// not have been freed.
+    pub(crate) unsafe fn dealloc(&mut self, ptr: *mut u8) {
+        let block = Block::from_payload(ptr);
+        self.used -= Block::size(block);
+        self.release(block);
+    }
+
+    /// Resize in place when the block, or the block and its free successor, can hold
+    /// `new_size`; otherwise move to a new block. On failure the old block is left as it was.
+    ///
+    /// # Safety
+    /// As for [`dealloc`](Self::dealloc), with `layout` the layout `ptr` currently has.
+    pub(crate) unsafe fn realloc(
+        &mut self,
+        ptr: *mut u8,
+        layout: Layout,
+        new_size: usize,
+    ) -> Option<NonNull<u8>> {
+        let block = Block::from_payload(ptr);
+        let want = block_size(new_size)?;
+        let have = Block::size(block);
+        if want > have {
+            let next = Block::next_phys(block);
+            if !Block::is_free(next) || have + Block::size(next) < want {
+                let new = self.alloc(Layout::from_size_align(new_size, layout.align()).ok()?)?;
+                ptr::copy_nonoverlapping(ptr, new.as_ptr(), layout.size().min(new_size));
+                self.dealloc(ptr);
+                return Some(new);
+            }
+            self.unlink(next);
+            (*block).size = have + Block::size(next);
+            (*Block::next_phys(block)).prev_phys = block;
+        }
+        if let Some(rest) = Self::split(block, want) {
+            self.release(rest);
+        }
+        self.used = self.used - have + Block::size(block);
+        Some(NonNull::new_unchecked(ptr))
+    }
+
+    /// Cut `block` down to `size` bytes and return the rest, if it is big enough to be a block.
+    unsafe fn split(block: *mut Block, size: usize) -> Option<*mut Block> {
+        let rest_size = Block::size(block) - size;
+        if rest_size < MIN_BLOCK {
+            return None;
+        }
+        let rest = block.byte_add(size);
+        (*rest).prev_phys = block;
+        (*rest).size = rest_size;
+        (*Block::next_phys(rest)).prev_phys = rest;
+        (*block).size = size;
+        Some(rest)
+    }
+
+    /// Merge a block that is on no list with its free neighbours and list the result.
+    unsafe fn release(&mut self, mut block: *mut Block) {
+        let next = Block::next_phys(block);
+        if Block::is_free(next) {
+            self.unlink(next);
+            (*block).size = Block::size(block) + Block::size(next);
+        }
+        let prev = (*block).prev_phys;
+        if !prev.is_null() && Block::is_free(prev) {
+            self.unlink(prev);
+            (*prev).size = Block::size(prev) + Block::size(block);
+            block = prev;
+        }
+        (*Block::next_phys(block)).prev_phys = block;
+        self.link(block);
+    }
+
+    /// Unlink and return a free block of at least `size` bytes.
+    unsafe fn take(&mut self, size: usize) -> Option<*mut Block> {
+        let (mut fl, sl) = mapping(round_to_class(size)?);
+        if fl >= FL_COUNT {
+            return None;
+        }
+        let mut sl_map = self.sl_bitmap[fl] & (!0u32 << sl);
+        if sl_map == 0 {
+            let fl_map = self.fl_bitmap & (!0usize).checked_shl(fl as u32 + 1).unwrap_or(0);
+            if fl_map == 0 {
+                return None;
+            }
+            fl = fl_map.trailing_zeros() as usize;
+            sl_map = self.sl_bitmap[fl];
+        }
+        let block = self.heads[fl][sl_map.trailing_zeros() as usize];
+        self.unlink(block);
+        Some(block)
+    }
+
+    unsafe fn link(&mut self, block: *mut Block) {
+        let size = Block::size(block);
+        let (fl, sl) = mapping(size);
+        let head = self.heads[fl][sl];
+        (*block).size = size | FREE;
+        (*block).next_free = head;
+        (*block).prev_free = ptr::null_mut();
+        if !head.is_null() {
+            (*head).prev_free = block;
+        }
+        self.heads[fl][sl] = block;
+        self.fl_bitmap |= 1 << fl;
+        self.sl_bitmap[fl] |= 1 << sl;
+    }
+
+    unsafe fn unlink(&mut self, block: *mut Block) {
+        let size = Block::size(block);
+        let (fl, sl) = mapping(size);
+        let (next, prev) = ((*block).next_free, (*block).prev_free);
+        if !next.is_null() {
+            (*next).prev_free = prev;
+        }
+        if prev.is_null() {
+            self.heads[fl][sl] = next;
+            if next.is_null() {
+                self.sl_bitmap[fl] &= !(1 << sl);
+                if self.sl_bitmap[fl] == 0 {
+                    self.fl_bitmap &= !(1 << fl);
+                }
+            }
+        } else {
+            (*prev).next_free = next;
+        }
+        (*block).size = size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// A pool backed by a `u128` buffer, so it starts granule-aligned.
+    fn pool(tlsf: &mut Tlsf, bytes: usize) -> Vec<u128> {
+        let mut mem = alloc::vec![0u128; bytes / 16];
+        assert!(unsafe { tlsf.add_pool(mem.as_mut_ptr() as usize, bytes) });
+        mem
+    }
+
+    fn free_blocks(tlsf: &Tlsf) -> usize {
+        let mut count = 0;
+        for heads in &tlsf.heads {
+            for &head in heads {
+                let mut block = head;
+                while !block.is_null() {
+                    count += 1;
+                    block = unsafe { (*block).next_free };
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn test_mapping_covers_every_size() {
+        assert_eq!(mapping(MIN_BLOCK), (0, MIN_BLOCK / GRANULE));
+        assert_eq!(mapping(SMALL), (1, 0));
+        assert_eq!(mapping(SMALL * 2 - 1), (1, SL_COUNT - 1));
+        assert_eq!(mapping(usize::MAX & !FREE), (FL_COUNT - 1, SL_COUNT - 1));
+        // A rounded size starts its class, so every block listed there is at least as big.
+        for size in (MIN_BLOCK..1 << 20).step_by(GRANULE) {
+            let rounded = round_to_class(size).unwrap();
+            assert!(rounded >= size);
+            assert_eq!(round_to_class(rounded), Some(rounded));
+        }
+    }
+
+    #[test]
+    fn test_free_merges_neighbours() {
+        let mut tlsf = Tlsf::new();
+        let _mem = pool(&mut tlsf, 64 * 1024);
+        let layout = Layout::from_size_align(1000, 8).unwrap();
+        let blocks: Vec<_> = (0..8).map(|_| tlsf.alloc(layout).unwrap()).collect();
+        assert!(tlsf.used() >= 8 * 1000);
+
+        for &i in &[1, 3, 5, 7, 0, 2, 6, 4] {
+            unsafe { tlsf.dealloc(blocks[i].as_ptr()) };
+        }
+        assert_eq!(tlsf.used(), 0);
+        assert_eq!(free_blocks(&tlsf), 1);
+        assert!(tlsf
+            .alloc(Layout::from_size_align(60 * 1024, 8).unwrap())
+            .is_some());
+    }
+
+    #[test]
+    fn test_alignment() {
+        let mut tlsf = Tlsf::new();
+        let _mem = pool(&mut tlsf, 256 * 1024);
+        let mut live = Vec::new();
+        for (i, align) in [1, 8, 16, 32, 64, 256, 4096, 16].into_iter().enumerate() {
+            let layout = Layout::from_size_align(100 + i, align).unwrap();
+            let ptr = tlsf.alloc(layout).unwrap().as_ptr();
+            assert_eq!(ptr as usize % align, 0);
+            unsafe { ptr.write_bytes(i as u8, layout.size()) };
+            live.push((ptr, layout));
+        }
+        for (i, &(ptr, layout)) in live.iter().enumerate() {
+            let bytes = unsafe { core::slice::from_raw_parts(ptr, layout.size()) };
+            assert!(bytes.iter().all(|&b| b == i as u8));
+            unsafe { tlsf.dealloc(ptr) };
+        }
+        assert_eq!(tlsf.used(), 0);
+        assert_eq!(free_blocks(&tlsf), 1);
+    }
+
+    #[test]
+    fn test_realloc_in_place() {
+        let mut tlsf = Tlsf::new();
+        let _mem = pool(&mut tlsf, 64 * 1024);
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let ptr = tlsf.alloc(layout).unwrap().as_ptr();
+        unsafe { ptr.write_bytes(0x42, 64) };
+
+        let grown = unsafe { tlsf.realloc(ptr, layout, 4096) }.unwrap().as_ptr();
+        assert_eq!(grown, ptr);
+        let grown_layout = Layout::from_size_align(4096, 8).unwrap();
+        let shrunk = unsafe { tlsf.realloc(grown, grown_layout, 32) }
+            .unwrap()
+            .as_ptr();
+        assert_eq!(shrunk, ptr);
+        assert!(unsafe { core::slice::from_raw_parts(ptr, 32) }
+            .iter()
+            .all(|&b| b == 0x42));
+
+        // A live neighbour forces a move, which keeps the contents.
+        let blocker = tlsf.alloc(layout).unwrap().as_ptr();
+        let small = Layout::from_size_align(32, 8).unwrap();
+        let moved = unsafe { tlsf.realloc(ptr, small, 1024) }.unwrap().as_ptr();
+        assert_ne!(moved, ptr);
+        assert!(unsafe { core::slice::from_raw_parts(moved, 32) }
+            .iter()
+            .all(|&b| b == 0x42));
+        unsafe {
+            tlsf.dealloc(moved);
+            tlsf.dealloc(blocker);
+        }
+        assert_eq!(tlsf.used(), 0);
+        assert_eq!(free_blocks(&tlsf), 1);
+    }
+
+    #[test]
+    fn test_contiguous_pool_extends_the_last_one() {
+        const HALF: usize = 16 * 1024;
+        let mut tlsf = Tlsf::new();
+        let mut mem = alloc::vec![0u128; 2 * HALF / 16];
+        let start = mem.as_mut_ptr() as usize;
+        assert!(unsafe { tlsf.add_pool(start, HALF) });
+
+        let big = Layout::from_size_align(HALF + 1024, 8).unwrap();
+        assert!(tlsf.alloc(big).is_none());
+        assert!(unsafe { tlsf.add_pool(start + HALF, HALF) });
+        assert_eq!(free_blocks(&tlsf), 1);
+        let ptr = tlsf.alloc(big).unwrap();
+        unsafe { tlsf.dealloc(ptr.as_ptr()) };
+        assert_eq!(free_blocks(&tlsf), 1);
+    }
+
+    #[test]
+    fn test_random_workload() {
+        let mut tlsf = Tlsf::new();
+        let _mem = pool(&mut tlsf, 1024 * 1024);
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as usize
+        };
+
+        let mut live: Vec<(*mut u8, Layout, u8)> = Vec::new();
+        for round in 0..20_000 {
+            let tag = round as u8;
+            match next() % 3 {
+                0 | 1 => {
+                    let layout = Layout::from_size_align(next() % 2048, 1 << (next() % 7)).unwrap();
+                    if let Some(ptr) = tlsf.alloc(layout) {
+                        assert_eq!(ptr.as_ptr() as usize % layout.align(), 0);
+                        unsafe { ptr.as_ptr().write_bytes(tag, layout.size()) };
+                        live.push((ptr.as_ptr(), layout, tag));
+                    }
+                }
+                _ if !live.is_empty() => {
+                    let (ptr, layout, tag) = live.swap_remove(next() % live.len());
+                    let bytes = unsafe { core::slice::from_raw_parts(ptr, layout.size()) };
+                    assert!(bytes.iter().all(|&b| b == tag));
+                    if next() % 2 == 0 {
+                        unsafe { tlsf.dealloc(ptr) };
+                    } else {
+                        let new_size = next() % 4096;
+                        if let Some(new) = unsafe { tlsf.realloc(ptr, layout, new_size) } {
+                            let new_layout =
+                                Layout::from_size_align(new_size, layout.align()).unwrap();
+                            let kept = layout.size().min(new_size);
+                            let bytes = unsafe { core::slice::from_raw_parts(new.as_ptr(), kept) };
+                            assert!(bytes.iter().all(|&b| b == tag));
+                            unsafe { new.as_ptr().write_bytes(tag, new_size) };
+                            live.push((new.as_ptr(), new_layout, tag));
+                        } else {
+                            live.push((ptr, layout, tag));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (ptr, _, _) in live {
+            unsafe { tlsf.dealloc(ptr) };
+        }
+        assert_eq!(tlsf.used(), 0);
+        assert_eq!(free_blocks(&tlsf), 1);
+    }
+}
@@ -15,6 +15,12 @@ pub unsafe extern "C" fn _start() -> ! {
         "   lla     gp, __global_pointer$",
         ".option pop",
 
+        // Only hart 0 boots; the rest wait in `__hart_park` until `smp::start_hart`.
+        #[cfg(feature = "smp")]
+        "   csrr    t0, mhartid",
+        #[cfg(feature = "smp")]
+        "   bnez    t0, {park}",
+
         ".weak __stack_top",
         ".hidden __stack_top",
         "   lla     sp, __stack_top",
@@ -26,6 +32,8 @@ pub unsafe extern "C" fn _start() -> ! {
 
         trace_start = sym __boot_trace_start,
         bootstrap = sym __bootstrap,
+        #[cfg(feature = "smp")]
+        park = sym crate::smp::__hart_park,
     )
 }
 
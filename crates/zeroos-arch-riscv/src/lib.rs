@@ -6,6 +6,8 @@
 pub mod boot;
 pub mod ops;
 pub mod ret_from_fork;
+#[cfg(feature = "smp")]
+pub mod smp;
 pub mod switch_to;
 pub mod thread_ctx;
 pub mod trap;
@@ -23,7 +25,12 @@ mod riscv {
     pub use crate::boot::{__bootstrap, _start};
     pub use crate::ops::ARCH_OPS;
     pub use crate::ret_from_fork::ret_from_fork;
-    pub use crate::trap::{TrapFrame, _default_trap_handler};
+    #[cfg(feature = "smp")]
+    pub use crate::smp::{hart_id, start_hart, HartEntry, MAX_HARTS};
+    pub use crate::trap::{
+        decode_trap, dispatch_trap_hooks, register_trap_hook, trap_depth, trap_record, TrapAction,
+        TrapFilter, TrapFrame, TrapHook, TrapRecord, _default_trap_handler, MAX_TRAP_DEPTH,
+    };
     pub use foundation::kfn::thread::ThreadAnchor;
     pub use riscv::register::mcause::{Exception, Interrupt, Trap};
 }
@@ -51,6 +51,38 @@ unsafe fn trap_frame_set_tp(regs: *mut u8, tp: usize) {
     r.tp = tp;
 }
 
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_set_ra(regs: *mut u8, ra: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    r.ra = ra;
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_set_arg(regs: *mut u8, idx: usize, val: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    match idx {
+        0 => r.a0 = val,
+        1 => r.a1 = val,
+        2 => r.a2 = val,
+        3 => r.a3 = val,
+        4 => r.a4 = val,
+        5 => r.a5 = val,
+        _ => {}
+    }
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_get_sp(regs: *const u8) -> usize {
+    let r = &*(regs as *const TrapFrame);
+    r.sp
+}
+
 /// # Safety
 /// Must be called when `tp` contains a valid `ThreadAnchor` pointer.
 #[inline(always)]
@@ -136,6 +168,9 @@ pub const ARCH_OPS: ArchOps = ArchOps {
     trap_frame_set_retval,
     trap_frame_set_sp,
     trap_frame_set_tp,
+    trap_frame_set_ra,
+    trap_frame_set_arg,
+    trap_frame_get_sp,
     current_trap_frame,
     trap_frame_get_pc,
     trap_frame_set_pc,
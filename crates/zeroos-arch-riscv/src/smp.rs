@@ -0,0 +1,152 @@
+//! Secondary-hart parking and wake-up.
+//!
+//! With the `smp` feature, `_start` diverts every hart except hart 0 into a park loop before it
+//! touches the boot stack. A parked hart sleeps in `wfi` until [`start_hart`] fills its mailbox and
+//! the platform raises a software interrupt via `__platform_hart_wake`. It then switches to the
+//! stack it was given, lets `__platform_hart_woken` clear that interrupt, and jumps to the entry
+//! point.
+//!
+//! Only bring-up lives here: the kernel subsystems (scheduler, allocator, VFS) still assume they
+//! are driven from a single hart, so code running on secondaries must stay self-contained.
+
+use core::arch::naked_asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+extern "C" {
+    /// Raise a software interrupt on `hart` (e.g. CLINT `msip`) so it leaves `wfi`.
+    fn __platform_hart_wake(hart: usize);
+    /// Clear the software interrupt that woke `hart`, called on `hart` itself before its entry
+    /// point; left pending, it would trap as soon as the hart enables interrupts.
+    fn __platform_hart_woken(hart: usize);
+}
+
+/// Harts that can be started; higher hart ids stay parked forever.
+pub const MAX_HARTS: usize = 8;
+
+/// Mailbox polled by a parked hart. Padded to a power of two so the park loop can index it with a
+/// shift.
+#[repr(C, align(16))]
+struct HartSlot {
+    entry: AtomicUsize,
+    arg: AtomicUsize,
+    stack_top: AtomicUsize,
+    _pad: usize,
+}
+
+const SLOT_SHIFT: usize = core::mem::size_of::<HartSlot>().trailing_zeros() as usize;
+
+const _: () = assert!(core::mem::size_of::<HartSlot>() == 1 << SLOT_SHIFT);
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: HartSlot = HartSlot {
+    entry: AtomicUsize::new(0),
+    arg: AtomicUsize::new(0),
+    stack_top: AtomicUsize::new(0),
+    _pad: 0,
+};
+
+static SLOTS: [HartSlot; MAX_HARTS] = [EMPTY_SLOT; MAX_HARTS];
+
+/// Entry point for a secondary hart: receives the `arg` passed to [`start_hart`].
+pub type HartEntry = extern "C" fn(arg: usize) -> !;
+
+/// The id of the hart executing this code.
+#[inline]
+pub fn hart_id() -> usize {
+    riscv::register::mhartid::read()
+}
+
+/// Release parked `hart` into `entry(arg)` with `sp = stack_top`.
+///
+/// Returns `false` if `hart` is the boot hart, out of range, or was already started.
+///
+/// # Safety
+/// `stack_top` must be the 16-byte aligned top of a stack reserved for `hart` alone, and `entry`
+/// must only touch state that is safe to use concurrently with the boot hart.
+pub unsafe fn start_hart(hart: usize, entry: HartEntry, arg: usize, stack_top: usize) -> bool {
+    if hart == 0 || hart >= MAX_HARTS {
+        return false;
+    }
+    let slot = &SLOTS[hart];
+    if slot.entry.load(Ordering::Acquire) != 0 {
+        return false;
+    }
+    slot.arg.store(arg, Ordering::Relaxed);
+    slot.stack_top.store(stack_top & !0xf, Ordering::Relaxed);
+    // Publishing `entry` last releases the hart; the park loop reads it with a fence after.
+    slot.entry.store(entry as usize, Ordering::Release);
+    // SAFETY: the platform hook only pokes the target hart's interrupt line.
+    unsafe { __platform_hart_wake(hart) };
+    true
+}
+
+/// Park loop for harts other than hart 0 (entered from `_start` with `t0 = mhartid`).
+///
+/// # Safety
+/// Must only be entered from `_start`; it never uses the boot stack.
+#[unsafe(naked)]
+#[no_mangle]
+pub unsafe extern "C" fn __hart_park() -> ! {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "riscv64")] {
+            naked_asm!(
+                // Let the machine software interrupt wake `wfi` (MIE stays off, so no trap).
+                "   csrsi   mie, 8",
+                "   li      t1, {max_harts}",
+                "   bgeu    t0, t1, 2f",
+                "   lla     t1, {slots}",
+                "   slli    t2, t0, {slot_shift}",
+                "   add     t1, t1, t2",
+                "1:",
+                "   wfi",
+                "   ld      t2, 0(t1)",
+                "   beqz    t2, 1b",
+                "   fence   r, rw",
+                // Nothing is owed to the caller, so s0/s1 carry entry and arg across the hook.
+                "   mv      s0, t2",
+                "   ld      s1, 8(t1)",
+                "   ld      sp, 16(t1)",
+                "   mv      a0, t0",
+                "   call    {woken}",
+                "   mv      a0, s1",
+                "   jr      s0",
+                "2:",
+                "   wfi",
+                "   j       2b",
+                max_harts = const MAX_HARTS,
+                slots = sym SLOTS,
+                slot_shift = const SLOT_SHIFT,
+                woken = sym __platform_hart_woken,
+            )
+        } else {
+            naked_asm!(
+                "   csrsi   mie, 8",
+                "   li      t1, {max_harts}",
+                "   bgeu    t0, t1, 2f",
+                "   lla     t1, {slots}",
+                "   slli    t2, t0, {slot_shift}",
+                "   add     t1, t1, t2",
+                "1:",
+                "   wfi",
+                "   lw      t2, 0(t1)",
+                "   beqz    t2, 1b",
+                "   fence   r, rw",
+                // Nothing is owed to the caller, so s0/s1 carry entry and arg across the hook.
+                "   mv      s0, t2",
+                "   lw      s1, 4(t1)",
+                "   lw      sp, 8(t1)",
+                "   mv      a0, t0",
+                "   call    {woken}",
+                "   mv      a0, s1",
+                "   jr      s0",
+                "2:",
+                "   wfi",
+                "   j       2b",
+                max_harts = const MAX_HARTS,
+                slots = sym SLOTS,
+                slot_shift = const SLOT_SHIFT,
+                woken = sym __platform_hart_woken,
+            )
+        }
+    }
+}
@@ -170,20 +170,27 @@ mod imp {
         use foundation::kfn::thread::ThreadAnchor;
 
         cfg_if! {
-            if #[cfg(target_arch = "riscv64")] {
+            if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
                 zeroos_macros::asm_block!(
                     "csrrw tp, mscratch, tp",
                     "bnez tp, .Lsave_context",
 
+                        // Kernel trap: mscratch held 0 and now holds the anchor. Take it back
+                        // and leave 0 behind, so another trap taken from here nests again.
                         ".Lrestore_kernel_tpsp:",
-                        "csrr tp, mscratch",
+                        "csrrw tp, mscratch, x0",
                         store!(t6, {ThreadAnchor.stash0}(tp) @k),
+                        store!(tp, {ThreadAnchor.stash1}(tp)),
                         "li t6, 1",
                         store!(sp, {ThreadAnchor.kernel_sp}(tp)),
                         "j .Lcommon_save_context",
 
+                        // User trap: zero mscratch before touching the kernel stack, so a fault
+                        // while saving the frame is taken as a nested kernel trap.
                         ".Lsave_context:",
                         store!(t6, {ThreadAnchor.stash0}(tp) @u),
+                        "csrrw t6, mscratch, x0",
+                        store!(t6, {ThreadAnchor.stash1}(tp)),
                         "li t6, 0",
 
                         ".Lcommon_save_context:",
@@ -230,7 +237,7 @@ mod imp {
                         "csrr s2, mepc",
                         "csrr s3, mtval",
                         "csrr s4, mcause",
-                        "csrr s5, mscratch",
+                        load!(s5, {ThreadAnchor.stash1}(tp)),
                         store!(s0, {TrapFrame.sp}(sp)),
                         store!(s1, {TrapFrame.mstatus}(sp)),
                         store!(s2, {TrapFrame.mepc}(sp)),
@@ -238,19 +245,20 @@ mod imp {
                         store!(s4, {TrapFrame.mcause}(sp)),
                         store!(s5, {TrapFrame.tp}(sp)),
 
-                        "csrw mscratch, x0",
-
                         "mv a0, sp",
-                        "call {trap_handler}",
+                        "call {handle_trap}",
                         "j ret_from_exception",
 
+                        // The value for mscratch goes to stash2 and is written just before
+                        // `mret`: until then mscratch stays 0 and any trap is a kernel trap.
                         "ret_from_exception:",
                         load!(t6, {TrapFrame.from_kernel}(sp)),
+                        store!(x0, {ThreadAnchor.stash2}(tp) @k),
                         "bnez t6, 1f",
 
                         "addi s0, sp, {FRAME_SIZE}",
                         store!(s0, {ThreadAnchor.kernel_sp}(tp)),
-                        "csrw mscratch, tp",
+                        store!(tp, {ThreadAnchor.stash2}(tp) @u),
 
                     "1:",
                     load!(a0, {TrapFrame.mstatus}(sp)),
@@ -260,7 +268,6 @@ mod imp {
 
                     load!(ra, {TrapFrame}(sp)),
                     load!(gp, {TrapFrame}(sp)),
-                    load!(tp, {TrapFrame}(sp)),
                     load!(t0, {TrapFrame}(sp)),
 
                     load!(t1, {TrapFrame}(sp)),
@@ -288,13 +295,17 @@ mod imp {
                     load!(t3, {TrapFrame}(sp)),
                     load!(t4, {TrapFrame}(sp)),
                     load!(t5, {TrapFrame}(sp)),
+
+                    load!(t6, {ThreadAnchor.stash2}(tp) @restore),
+                    "csrw mscratch, t6",
                     load!(t6, {TrapFrame}(sp)),
+                    load!(tp, {TrapFrame}(sp)),
 
                     load!(sp, {TrapFrame}(sp)),
                     "mret",
 
                     FRAME_SIZE = const core::mem::size_of::<TrapFrame>(),
-                    trap_handler = sym crate::trap_handler,
+                    handle_trap = sym super::handle_trap,
                 );
             } else {
                  core::arch::naked_asm!("unimp");
@@ -314,3 +325,266 @@ global_asm!(
     "j {default}",
     default = sym imp::_default_trap_handler,
 );
+
+/// Traps that may be active on one hart at once, counting the outermost one.
+///
+/// A trap taken while another is being handled (a page fault or timer interrupt during a
+/// syscall) is saved below the interrupted handler's frame on the same kernel stack. Exceeding
+/// this depth is treated as a runaway fault loop and panics with the chain of active traps.
+pub const MAX_TRAP_DEPTH: usize = 4;
+
+cfg_if! {
+    if #[cfg(feature = "smp")] {
+        const TRAP_HARTS: usize = crate::smp::MAX_HARTS;
+
+        #[inline(always)]
+        fn trap_hart() -> usize {
+            crate::smp::hart_id().min(TRAP_HARTS - 1)
+        }
+    } else {
+        const TRAP_HARTS: usize = 1;
+
+        #[inline(always)]
+        fn trap_hart() -> usize {
+            0
+        }
+    }
+}
+
+/// What was trapping at one level of a hart's trap stack.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrapRecord {
+    pub mcause: usize,
+    pub mepc: usize,
+    pub mtval: usize,
+    pub from_kernel: bool,
+}
+
+impl TrapRecord {
+    fn of(regs: &TrapFrame) -> Self {
+        Self {
+            mcause: regs.mcause,
+            mepc: regs.mepc,
+            mtval: regs.mtval,
+            from_kernel: regs.from_kernel != 0,
+        }
+    }
+}
+
+/// Traps currently being handled on one hart, outermost first.
+#[derive(Clone, Copy)]
+struct TrapStack {
+    depth: usize,
+    records: [TrapRecord; MAX_TRAP_DEPTH],
+}
+
+impl TrapStack {
+    const fn new() -> Self {
+        Self {
+            depth: 0,
+            records: [TrapRecord {
+                mcause: 0,
+                mepc: 0,
+                mtval: 0,
+                from_kernel: false,
+            }; MAX_TRAP_DEPTH],
+        }
+    }
+
+    /// Push `record` and return its 1-based level, or `None` if the stack is full.
+    fn enter(&mut self, record: TrapRecord) -> Option<usize> {
+        // A trap from user mode cannot be nested in another one. Resetting here keeps the
+        // count right for threads started by `ret_from_fork`, which leave no trap behind.
+        let level = if record.from_kernel { self.depth } else { 0 };
+        if level == MAX_TRAP_DEPTH {
+            return None;
+        }
+        self.records[level] = record;
+        self.depth = level + 1;
+        Some(level + 1)
+    }
+
+    /// Pop back to the level below `level`. The handler may have switched threads in between,
+    /// so the depth is restored rather than decremented.
+    fn leave(&mut self, level: usize) {
+        self.depth = level - 1;
+    }
+}
+
+impl core::fmt::Display for TrapStack {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (level, r) in self.records[..self.depth].iter().enumerate() {
+            writeln!(
+                f,
+                "  #{} {:?} from {} (mcause=0x{:x}, mepc=0x{:x}, mtval=0x{:x})",
+                level,
+                decode_trap(r.mcause),
+                if r.from_kernel { "kernel" } else { "user" },
+                r.mcause,
+                r.mepc,
+                r.mtval
+            )?;
+        }
+        Ok(())
+    }
+}
+
+static TRAP_STACKS: foundation::utils::GlobalCell<[TrapStack; TRAP_HARTS]> =
+    foundation::utils::GlobalCell::new([TrapStack::new(); TRAP_HARTS]);
+
+/// Set while reporting an overflow, so a fault in the panic path exits instead of recursing.
+static TRAP_OVERFLOWED: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+/// Traps being handled on this hart, including the current one (0 outside any trap).
+pub fn trap_depth() -> usize {
+    TRAP_STACKS.with(|stacks| stacks[trap_hart()].depth)
+}
+
+/// The trap at `level` (0 = outermost) of this hart's trap stack.
+pub fn trap_record(level: usize) -> Option<TrapRecord> {
+    TRAP_STACKS.with(|stacks| {
+        let stack = &stacks[trap_hart()];
+        stack.records[..stack.depth].get(level).copied()
+    })
+}
+
+#[cold]
+fn trap_overflow(hart: usize, stack: TrapStack, regs: &TrapFrame) -> ! {
+    if TRAP_OVERFLOWED.swap(true, core::sync::atomic::Ordering::Relaxed) {
+        // SIGABRT, as the panic handler would report.
+        foundation::kfn::kexit(134);
+    }
+    panic!(
+        "nested trap overflow on hart {}: {:?} (mcause=0x{:x}, mepc=0x{:x}, mtval=0x{:x}) while {} traps were active (MAX_TRAP_DEPTH = {}):\n{}",
+        hart,
+        decode_trap(regs.mcause),
+        regs.mcause,
+        regs.mepc,
+        regs.mtval,
+        stack.depth,
+        MAX_TRAP_DEPTH,
+        stack
+    );
+}
+
+/// Called by the trap vector with the frame it just saved; tracks nesting around the
+/// platform's `trap_handler`.
+extern "C" fn handle_trap(regs: *mut TrapFrame) {
+    let hart = trap_hart();
+    // SAFETY: the trap vector passes the frame it just saved on this hart's kernel stack.
+    let record = TrapRecord::of(unsafe { &*regs });
+    let Some(level) = TRAP_STACKS.with_mut(|stacks| stacks[hart].enter(record)) else {
+        let stack = TRAP_STACKS.with(|stacks| stacks[hart]);
+        // SAFETY: as above.
+        trap_overflow(hart, stack, unsafe { &*regs });
+    };
+
+    // SAFETY: `regs` is the live frame for this trap; the platform handler may rewrite it.
+    unsafe { crate::trap_handler(regs) };
+
+    TRAP_STACKS.with_mut(|stacks| stacks[hart].leave(level));
+}
+
+/// Result of a [`TrapHook`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrapAction {
+    /// The hook dealt with the trap; skip lower-priority hooks and the platform default.
+    Handled,
+    /// Try the next hook.
+    Pass,
+}
+
+/// Traps a hook is registered for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrapFilter {
+    Exception(Exception),
+    Interrupt(Interrupt),
+    AnyException,
+    AnyInterrupt,
+    Any,
+}
+
+impl TrapFilter {
+    fn matches(self, trap: Trap) -> bool {
+        match (self, trap) {
+            (Self::Any, _) => true,
+            (Self::AnyException, Trap::Exception(_)) => true,
+            (Self::AnyInterrupt, Trap::Interrupt(_)) => true,
+            (Self::Exception(want), Trap::Exception(got)) => want == got,
+            (Self::Interrupt(want), Trap::Interrupt(got)) => want == got,
+            _ => false,
+        }
+    }
+}
+
+/// Handler for traps matching its [`TrapFilter`]. For exceptions the hook is responsible for
+/// advancing `mepc` past the faulting instruction when it returns [`TrapAction::Handled`].
+pub type TrapHook = fn(regs: &mut TrapFrame, trap: Trap) -> TrapAction;
+
+/// Hooks that can be registered at once.
+pub const MAX_TRAP_HOOKS: usize = 16;
+
+#[derive(Clone, Copy)]
+struct HookEntry {
+    filter: TrapFilter,
+    priority: i32,
+    hook: TrapHook,
+}
+
+/// Sorted by descending priority; registration order breaks ties.
+static TRAP_HOOKS: foundation::utils::GlobalCell<[Option<HookEntry>; MAX_TRAP_HOOKS]> =
+    foundation::utils::GlobalCell::new([None; MAX_TRAP_HOOKS]);
+
+/// Register `hook` for traps matching `filter`; higher `priority` runs first.
+///
+/// Returns false if [`MAX_TRAP_HOOKS`] hooks are already registered. Call during bootstrap,
+/// before traps that the hook should see can fire.
+pub fn register_trap_hook(filter: TrapFilter, priority: i32, hook: TrapHook) -> bool {
+    TRAP_HOOKS.with_mut(|hooks| {
+        let Some(len) = hooks.iter().position(Option::is_none) else {
+            return false;
+        };
+        let at = hooks[..len]
+            .iter()
+            .flatten()
+            .position(|e| e.priority < priority)
+            .unwrap_or(len);
+        hooks[at..=len].rotate_right(1);
+        hooks[at] = Some(HookEntry {
+            filter,
+            priority,
+            hook,
+        });
+        true
+    })
+}
+
+/// Decode `mcause` into a [`Trap`].
+#[inline]
+pub fn decode_trap(mcause: usize) -> Trap {
+    let interrupt_bit = 1usize << (usize::BITS - 1);
+    let code = mcause & !interrupt_bit;
+    if mcause & interrupt_bit != 0 {
+        Trap::Interrupt(Interrupt::from(code))
+    } else {
+        Trap::Exception(Exception::from(code))
+    }
+}
+
+/// Offer the trap in `regs` to the registered hooks, highest priority first.
+///
+/// Returns true if one of them handled it; otherwise the caller applies its default handling.
+/// Platforms call this at the top of `trap_handler`.
+pub fn dispatch_trap_hooks(regs: &mut TrapFrame) -> bool {
+    let trap = decode_trap(regs.mcause);
+    for i in 0..MAX_TRAP_HOOKS {
+        // Copy the entry out so a hook may register further hooks.
+        let Some(entry) = TRAP_HOOKS.with(|hooks| hooks[i]) else {
+            return false;
+        };
+        if entry.filter.matches(trap) && (entry.hook)(regs, trap) == TrapAction::Handled {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,10 @@
+fn main() {
+    let arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    if arch != "x86_64" {
+        panic!(
+            "{} is x86_64-only; build with an x86_64 target (current: {}).",
+            env!("CARGO_PKG_NAME"),
+            arch
+        );
+    }
+}
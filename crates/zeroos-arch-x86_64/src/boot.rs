@@ -0,0 +1,56 @@
+use core::arch::naked_asm;
+
+/// # Safety
+/// Must only be entered by the loader in 64-bit mode with interrupts disabled.
+#[unsafe(naked)]
+#[link_section = ".text.boot"]
+#[no_mangle]
+pub unsafe extern "C" fn _start() -> ! {
+    naked_asm!(
+        ".weak __stack_top",
+        ".hidden __stack_top",
+        "   lea     rsp, [rip + __stack_top]",
+        "   and     rsp, -16",
+        // Terminate frame-pointer chains for backtraces.
+        "   xor     ebp, ebp",
+
+        "   call    {trace_start}",
+
+        "   jmp     {bootstrap}",
+
+        trace_start = sym __boot_trace_start,
+        bootstrap = sym __bootstrap,
+    )
+}
+
+/// # Safety
+/// Must only be entered from `_start` during early boot.
+#[unsafe(naked)]
+#[no_mangle]
+pub unsafe extern "C" fn __bootstrap() -> ! {
+    naked_asm!(
+        "   call    {trace_bootstrap}",
+        "   call    {platform_bootstrap}",
+        // `call` rather than `jmp` keeps the stack aligned as the ABI expects on entry.
+        "   call    {runtime_bootstrap}",
+
+        // Safety: if the runtime returns, halt forever.
+        "2:",
+        "   hlt",
+        "   jmp     2b",
+
+        trace_bootstrap = sym __boot_trace_bootstrap,
+        platform_bootstrap = sym crate::__platform_bootstrap,
+        runtime_bootstrap = sym crate::__runtime_bootstrap,
+    )
+}
+
+#[no_mangle]
+extern "C" fn __boot_trace_start() {
+    debug::writeln!("[BOOT] _start");
+}
+
+#[no_mangle]
+extern "C" fn __boot_trace_bootstrap() {
+    debug::writeln!("[BOOT] __bootstrap");
+}
@@ -0,0 +1,49 @@
+//! x86_64 support, so guests can run under a host-ISA emulator during development.
+//!
+//! The guest runs entirely in ring 0, like machine mode on RISC-V. Syscalls use the `syscall`
+//! instruction, routed by [`install_syscall_entry`] to an entry stub that saves a [`TrapFrame`]
+//! on the current thread's kernel stack and calls `trap_handler`. The current `ThreadAnchor`
+//! lives in the GS base (set with [`set_thread_anchor`]); the FS base is left to libc TLS.
+//!
+//! Boot expects the loader to enter `_start` in 64-bit mode with paging, a flat GDT (data
+//! selector right after the code selector) and interrupts disabled. Exceptions and interrupts
+//! (an IDT) are not wired up yet.
+//!
+//! Platforms MUST provide `trap_handler(regs: *mut TrapFrame)`.
+
+#![no_std]
+
+#[cfg(not(test))]
+pub mod boot;
+pub mod msr;
+pub mod ops;
+pub mod ret_from_fork;
+pub mod switch_to;
+pub mod thread_ctx;
+pub mod trap;
+
+#[cfg(not(test))]
+extern "C" {
+    // Platform bootstrap hook (sets up heap, device fds, etc).
+    fn __platform_bootstrap();
+    // Runtime bootstrap hook (transfers into libc/runtime initialization).
+    fn __runtime_bootstrap() -> !;
+    // Trap entry point called by the syscall entry stub.
+    pub fn trap_handler(regs: *mut TrapFrame);
+}
+
+mod x86_64 {
+    #[cfg(not(test))]
+    pub use crate::boot::{__bootstrap, _start};
+    #[cfg(not(test))]
+    pub use crate::msr::install_syscall_entry;
+    pub use crate::msr::{set_thread_anchor, thread_anchor};
+    pub use crate::ops::ARCH_OPS;
+    pub use crate::ret_from_fork::ret_from_fork;
+    #[cfg(not(test))]
+    pub use crate::trap::_syscall_entry;
+    pub use crate::trap::{TrapFrame, CAUSE_SYSCALL};
+    pub use foundation::kfn::thread::ThreadAnchor;
+}
+
+pub use x86_64::*;
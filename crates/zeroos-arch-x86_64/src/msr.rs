@@ -0,0 +1,67 @@
+//! Model-specific registers used by the syscall path.
+
+pub const IA32_EFER: u32 = 0xC000_0080;
+pub const IA32_STAR: u32 = 0xC000_0081;
+pub const IA32_LSTAR: u32 = 0xC000_0082;
+pub const IA32_FMASK: u32 = 0xC000_0084;
+pub const IA32_FS_BASE: u32 = 0xC000_0100;
+pub const IA32_GS_BASE: u32 = 0xC000_0101;
+
+pub const EFER_SCE: u64 = 1 << 0;
+/// RFLAGS bits cleared on `syscall`: TF, IF and DF.
+pub const SYSCALL_FMASK: u64 = (1 << 8) | (1 << 9) | (1 << 10);
+
+/// # Safety
+/// Ring 0 only; `msr` must exist on this CPU.
+#[inline(always)]
+pub unsafe fn rdmsr(msr: u32) -> u64 {
+    let (lo, hi): (u32, u32);
+    core::arch::asm!("rdmsr", in("ecx") msr, out("eax") lo, out("edx") hi, options(nomem, nostack));
+    ((hi as u64) << 32) | lo as u64
+}
+
+/// # Safety
+/// Ring 0 only; `msr` must exist on this CPU and accept `value`.
+#[inline(always)]
+pub unsafe fn wrmsr(msr: u32, value: u64) {
+    core::arch::asm!(
+        "wrmsr",
+        in("ecx") msr,
+        in("eax") value as u32,
+        in("edx") (value >> 32) as u32,
+        options(nostack)
+    );
+}
+
+/// Route the `syscall` instruction to the ZeroOS entry stub.
+///
+/// # Safety
+/// Ring 0 only. The GDT must hold the data selector right after the current code selector,
+/// and a valid anchor must be installed with [`set_thread_anchor`] before the first syscall.
+#[cfg(not(test))]
+pub unsafe fn install_syscall_entry() {
+    let cs: u16;
+    core::arch::asm!("mov {0:x}, cs", out(reg) cs, options(nomem, nostack, preserves_flags));
+    wrmsr(IA32_STAR, (cs as u64) << 32);
+    wrmsr(IA32_LSTAR, crate::trap::_syscall_entry as *const () as u64);
+    wrmsr(IA32_FMASK, SYSCALL_FMASK);
+    wrmsr(IA32_EFER, rdmsr(IA32_EFER) | EFER_SCE);
+}
+
+/// Make `anchor` the current thread's anchor (kept in the GS base).
+///
+/// # Safety
+/// Ring 0 only; `anchor` must point to a valid `ThreadAnchor`.
+#[inline(always)]
+pub unsafe fn set_thread_anchor(anchor: usize) {
+    wrmsr(IA32_GS_BASE, anchor as u64);
+}
+
+/// The current thread's anchor.
+///
+/// # Safety
+/// Ring 0 only.
+#[inline(always)]
+pub unsafe fn thread_anchor() -> usize {
+    rdmsr(IA32_GS_BASE) as usize
+}
@@ -0,0 +1,196 @@
+//! `foundation::ops::ArchOps` implementation for x86_64.
+
+use core::mem::size_of;
+
+use foundation::ops::ArchOps;
+
+use crate::ret_from_fork::ret_from_fork;
+use crate::switch_to::switch_to;
+use crate::trap::TrapFrame;
+use foundation::kfn::thread::ThreadAnchor;
+
+/// # Safety
+/// `dst` and `src` must point to valid `TrapFrame` memory regions.
+unsafe fn trap_frame_clone(dst: *mut u8, src: *const u8) {
+    core::ptr::copy_nonoverlapping(src, dst, size_of::<TrapFrame>());
+}
+
+/// # Safety
+/// `regs` must point to a valid, aligned region of at least `size_of::<TrapFrame>()` bytes.
+unsafe fn trap_frame_init(regs: *mut u8, user_sp: usize, user_tls: usize, pc: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    *r = TrapFrame::new();
+    r.rsp = user_sp;
+    r.fs_base = user_tls;
+    r.rip = pc;
+    // New thread returns 0 from clone in child context.
+    r.rax = 0;
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_set_retval(regs: *mut u8, val: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    r.rax = val;
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_set_sp(regs: *mut u8, sp: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    r.rsp = sp;
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_set_tp(regs: *mut u8, tp: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    r.fs_base = tp;
+}
+
+/// There is no link register: push `ra` on the saved stack, so the code resumed at `rip`
+/// returns to it.
+///
+/// # Safety
+/// `regs` must point to a valid `TrapFrame` whose `rsp` has 8 writable bytes below it.
+#[inline(always)]
+unsafe fn trap_frame_set_ra(regs: *mut u8, ra: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    r.rsp -= size_of::<usize>();
+    core::ptr::write(r.rsp as *mut usize, ra);
+}
+
+/// Arguments use the syscall register order (r10, not rcx, for index 3).
+///
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_set_arg(regs: *mut u8, idx: usize, val: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    if let Some(arg) = r.arg_mut(idx) {
+        *arg = val;
+    }
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_get_sp(regs: *const u8) -> usize {
+    let r = &*(regs as *const TrapFrame);
+    r.rsp
+}
+
+/// # Safety
+/// Must be called when the GS base holds a valid `ThreadAnchor` pointer.
+#[inline(always)]
+unsafe fn current_trap_frame() -> *mut u8 {
+    let anchor = crate::msr::thread_anchor() as *const ThreadAnchor;
+    let regs_addr = foundation::kfn::thread::ktrap_frame_addr(anchor);
+    regs_addr as *mut u8
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_get_pc(regs: *const u8) -> usize {
+    let r = &*(regs as *const TrapFrame);
+    r.rip
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_set_pc(regs: *mut u8, pc: usize) {
+    let r = &mut *(regs as *mut TrapFrame);
+    r.rip = pc;
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_get_nr(regs: *const u8) -> usize {
+    let r = &*(regs as *const TrapFrame);
+    r.rax
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_get_arg(regs: *const u8, idx: usize) -> usize {
+    let r = &*(regs as *const TrapFrame);
+    foundation::SyscallFrame::arg(r, idx)
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_get_cause(regs: *const u8) -> usize {
+    let r = &*(regs as *const TrapFrame);
+    r.cause
+}
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame`.
+#[inline(always)]
+unsafe fn trap_frame_get_fault_addr(regs: *const u8) -> usize {
+    let r = &*(regs as *const TrapFrame);
+    r.fault_addr
+}
+
+pub const ARCH_OPS: ArchOps = ArchOps {
+    thread_ctx_size: crate::thread_ctx::thread_ctx_size,
+    thread_ctx_align: crate::thread_ctx::thread_ctx_align,
+    trap_frame_size: || core::mem::size_of::<TrapFrame>(),
+    trap_frame_align: || core::mem::align_of::<TrapFrame>(),
+    thread_ctx_init: crate::thread_ctx::thread_ctx_init,
+    thread_ctx_set_sp: crate::thread_ctx::thread_ctx_set_sp,
+    thread_ctx_set_tp: crate::thread_ctx::thread_ctx_set_tp,
+    thread_ctx_set_ra: crate::thread_ctx::thread_ctx_set_ra,
+    thread_ctx_set_retval: crate::thread_ctx::thread_ctx_set_retval,
+    switch_to,
+    ret_from_fork: || ret_from_fork as *const () as usize,
+    trap_frame_clone,
+    trap_frame_init,
+    trap_frame_set_retval,
+    trap_frame_set_sp,
+    trap_frame_set_tp,
+    trap_frame_set_ra,
+    trap_frame_set_arg,
+    trap_frame_get_sp,
+    current_trap_frame,
+    trap_frame_get_pc,
+    trap_frame_set_pc,
+    trap_frame_get_nr,
+    trap_frame_get_arg,
+    trap_frame_get_cause,
+    trap_frame_get_fault_addr,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trap_frame_ops() {
+        let mut frame = TrapFrame::new();
+        let regs = core::ptr::addr_of_mut!(frame) as *mut u8;
+        let mut stack = [0usize; 4];
+        let top = stack.as_mut_ptr_range().end as usize;
+
+        // SAFETY: `regs` points at `frame`, and `top` has writable slots below it.
+        unsafe {
+            trap_frame_init(regs, top, 0x1000, 0x4000);
+            trap_frame_set_arg(regs, 3, 7);
+            trap_frame_set_ra(regs, 0xdead);
+            assert_eq!(trap_frame_get_pc(regs), 0x4000);
+            assert_eq!(trap_frame_get_arg(regs, 3), 7);
+            assert_eq!(trap_frame_get_sp(regs), top - 8);
+        }
+        assert_eq!(frame.r10, 7);
+        assert_eq!(frame.fs_base, 0x1000);
+        assert_eq!(stack[3], 0xdead);
+    }
+}
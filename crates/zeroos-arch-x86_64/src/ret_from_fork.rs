@@ -0,0 +1,83 @@
+//! Restore a saved `TrapFrame` and resume it with `iretq`.
+//!
+//! Used as the exit path of the syscall entry stub and as a trampoline by the cooperative
+//! scheduler when starting a new thread: it sets the first argument to the trap-frame pointer
+//! and jumps here.
+
+#[allow(unused_imports)]
+use crate::trap::TrapFrame;
+
+/// # Safety
+/// `regs` must point to a valid `TrapFrame` at the top of the current thread's kernel stack,
+/// and the GS base must point at that thread's `ThreadAnchor`.
+#[unsafe(naked)]
+#[no_mangle]
+pub unsafe extern "C" fn ret_from_fork(_regs: *mut TrapFrame) -> ! {
+    use foundation::kfn::thread::ThreadAnchor;
+    use memoffset::offset_of;
+
+    core::arch::naked_asm!(
+        "mov rsp, rdi",
+        // The next trap saves its frame in the same place.
+        "lea rax, [rsp + {size}]",
+        "mov qword ptr gs:[{a_kernel_sp}], rax",
+
+        "mov rax, [rsp + {fs_base}]",
+        "mov rdx, rax",
+        "shr rdx, 32",
+        "mov ecx, {fs_msr}",
+        "wrmsr",
+
+        // iretq frame below the trap frame: SS, RSP, RFLAGS, CS, RIP.
+        "mov r11, rsp",
+        "xor eax, eax",
+        "mov ax, ss",
+        "push rax",
+        "push qword ptr [r11 + {rsp_}]",
+        "push qword ptr [r11 + {rflags}]",
+        "mov ax, cs",
+        "push rax",
+        "push qword ptr [r11 + {rip}]",
+
+        "mov rax, [r11 + {rax}]",
+        "mov rbx, [r11 + {rbx}]",
+        "mov rcx, [r11 + {rcx}]",
+        "mov rdx, [r11 + {rdx}]",
+        "mov rsi, [r11 + {rsi}]",
+        "mov rdi, [r11 + {rdi}]",
+        "mov rbp, [r11 + {rbp}]",
+        "mov r8, [r11 + {r8}]",
+        "mov r9, [r11 + {r9}]",
+        "mov r10, [r11 + {r10}]",
+        "mov r12, [r11 + {r12}]",
+        "mov r13, [r11 + {r13}]",
+        "mov r14, [r11 + {r14}]",
+        "mov r15, [r11 + {r15}]",
+        // Restore r11 last (it was the frame base).
+        "mov r11, [r11 + {r11}]",
+        "iretq",
+
+        size = const core::mem::size_of::<TrapFrame>(),
+        a_kernel_sp = const offset_of!(ThreadAnchor, kernel_sp),
+        rax = const offset_of!(TrapFrame, rax),
+        rbx = const offset_of!(TrapFrame, rbx),
+        rcx = const offset_of!(TrapFrame, rcx),
+        rdx = const offset_of!(TrapFrame, rdx),
+        rsi = const offset_of!(TrapFrame, rsi),
+        rdi = const offset_of!(TrapFrame, rdi),
+        rbp = const offset_of!(TrapFrame, rbp),
+        r8 = const offset_of!(TrapFrame, r8),
+        r9 = const offset_of!(TrapFrame, r9),
+        r10 = const offset_of!(TrapFrame, r10),
+        r11 = const offset_of!(TrapFrame, r11),
+        r12 = const offset_of!(TrapFrame, r12),
+        r13 = const offset_of!(TrapFrame, r13),
+        r14 = const offset_of!(TrapFrame, r14),
+        r15 = const offset_of!(TrapFrame, r15),
+        rsp_ = const offset_of!(TrapFrame, rsp),
+        rip = const offset_of!(TrapFrame, rip),
+        rflags = const offset_of!(TrapFrame, rflags),
+        fs_base = const offset_of!(TrapFrame, fs_base),
+        fs_msr = const crate::msr::IA32_FS_BASE,
+    )
+}
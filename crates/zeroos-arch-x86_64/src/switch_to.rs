@@ -0,0 +1,65 @@
+//! Integer register context switch (callee-saved + core kernel regs).
+//!
+//! This is the x86_64 implementation of thread context switching.
+//! It is intentionally kept in the arch crate (not in schedulers).
+
+#[allow(unused_imports)]
+use crate::thread_ctx::ThreadContext;
+
+/// # Safety
+/// `old` and `new` must be valid pointers to `ThreadContext` structures.
+#[unsafe(naked)]
+#[no_mangle]
+pub unsafe extern "C" fn switch_to(_old: *mut u8, _new: *const u8) {
+    use memoffset::offset_of;
+
+    core::arch::naked_asm!(
+        // rdi = old, rsi = new
+
+        // Save current context; the return address becomes `ra` so new threads, which have
+        // nothing on their stack, resume the same way.
+        "pop rax",
+        "mov [rdi + {ra}], rax",
+        "mov [rdi + {sp}], rsp",
+        "mov [rdi + {rbx}], rbx",
+        "mov [rdi + {rbp}], rbp",
+        "mov [rdi + {r12}], r12",
+        "mov [rdi + {r13}], r13",
+        "mov [rdi + {r14}], r14",
+        "mov [rdi + {r15}], r15",
+        "mov ecx, {gs_msr}",
+        "rdmsr",
+        "shl rdx, 32",
+        "or rax, rdx",
+        "mov [rdi + {tp}], rax",
+        // Restore new context
+        "mov rax, [rsi + {tp}]",
+        "mov rdx, rax",
+        "shr rdx, 32",
+        "mov ecx, {gs_msr}",
+        "wrmsr",
+        "mov rsp, [rsi + {sp}]",
+        "mov rbx, [rsi + {rbx}]",
+        "mov rbp, [rsi + {rbp}]",
+        "mov r12, [rsi + {r12}]",
+        "mov r13, [rsi + {r13}]",
+        "mov r14, [rsi + {r14}]",
+        "mov r15, [rsi + {r15}]",
+        // Return value for the yielding thread; also the first argument of `ret_from_fork`.
+        "mov rdi, [rsi + {retval}]",
+        "mov rax, rdi",
+        "jmp qword ptr [rsi + {ra}]",
+
+        sp = const offset_of!(ThreadContext, sp),
+        tp = const offset_of!(ThreadContext, tp),
+        ra = const offset_of!(ThreadContext, ra),
+        rbx = const offset_of!(ThreadContext, rbx),
+        rbp = const offset_of!(ThreadContext, rbp),
+        r12 = const offset_of!(ThreadContext, r12),
+        r13 = const offset_of!(ThreadContext, r13),
+        r14 = const offset_of!(ThreadContext, r14),
+        r15 = const offset_of!(ThreadContext, r15),
+        retval = const offset_of!(ThreadContext, retval),
+        gs_msr = const crate::msr::IA32_GS_BASE,
+    )
+}
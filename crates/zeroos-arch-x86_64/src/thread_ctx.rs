@@ -0,0 +1,89 @@
+//! Arch-specific per-thread switch context.
+
+use core::mem::{align_of, size_of};
+
+/// Must match the save/restore order in `switch_to` asm.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadContext {
+    pub sp: usize,
+    /// GS base: the thread's `ThreadAnchor`.
+    pub tp: usize,
+    /// Resume address; `switch_to` jumps here instead of returning.
+    pub ra: usize,
+    pub rbx: usize,
+    pub rbp: usize,
+    pub r12: usize,
+    pub r13: usize,
+    pub r14: usize,
+    pub r15: usize,
+    pub retval: usize,
+}
+
+impl ThreadContext {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            sp: 0,
+            tp: 0,
+            ra: 0,
+            rbx: 0,
+            rbp: 0,
+            r12: 0,
+            r13: 0,
+            r14: 0,
+            r15: 0,
+            retval: 0,
+        }
+    }
+}
+
+#[inline]
+pub fn thread_ctx_size() -> usize {
+    size_of::<ThreadContext>()
+}
+
+#[inline]
+pub fn thread_ctx_align() -> usize {
+    align_of::<ThreadContext>()
+}
+
+#[inline]
+/// # Safety
+/// `p` must point to a valid `ThreadContext` structure.
+unsafe fn ctx_mut(p: *mut u8) -> &'static mut ThreadContext {
+    &mut *(p as *mut ThreadContext)
+}
+
+/// # Safety
+/// `ctx_ptr` must point to a valid, aligned region of at least `thread_ctx_size()` bytes.
+pub unsafe fn thread_ctx_init(ctx_ptr: *mut u8, anchor: usize, kstack_top: usize) {
+    core::ptr::write(ctx_ptr as *mut ThreadContext, ThreadContext::new());
+    let c = ctx_mut(ctx_ptr);
+    c.tp = anchor;
+    c.sp = kstack_top;
+}
+
+/// # Safety
+/// `ctx_ptr` must point to a valid `ThreadContext` structure.
+pub unsafe fn thread_ctx_set_sp(ctx_ptr: *mut u8, sp: usize) {
+    ctx_mut(ctx_ptr).sp = sp;
+}
+
+/// # Safety
+/// `ctx_ptr` must point to a valid `ThreadContext` structure.
+pub unsafe fn thread_ctx_set_tp(ctx_ptr: *mut u8, tp: usize) {
+    ctx_mut(ctx_ptr).tp = tp;
+}
+
+/// # Safety
+/// `ctx_ptr` must point to a valid `ThreadContext` structure.
+pub unsafe fn thread_ctx_set_ra(ctx_ptr: *mut u8, ra: usize) {
+    ctx_mut(ctx_ptr).ra = ra;
+}
+
+/// # Safety
+/// `ctx_ptr` must point to a valid `ThreadContext` structure.
+pub unsafe fn thread_ctx_set_retval(ctx_ptr: *mut u8, val: usize) {
+    ctx_mut(ctx_ptr).retval = val;
+}
@@ -0,0 +1,227 @@
+//! Syscall trap frame and the `syscall` entry stub.
+//!
+//! Platforms must provide `trap_handler(regs: *mut TrapFrame)`; this crate provides the
+//! entry/exit wrapper.
+
+/// `cause` recorded for frames saved by the `syscall` instruction (above every IDT vector).
+pub const CAUSE_SYSCALL: usize = 0x100;
+
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrapFrame {
+    pub rax: usize,
+    pub rbx: usize,
+    pub rcx: usize,
+    pub rdx: usize,
+    pub rsi: usize,
+    pub rdi: usize,
+    pub rbp: usize,
+    pub r8: usize,
+    pub r9: usize,
+    pub r10: usize,
+    pub r11: usize,
+    pub r12: usize,
+    pub r13: usize,
+    pub r14: usize,
+    pub r15: usize,
+    pub rsp: usize,
+
+    pub rip: usize,
+    pub rflags: usize,
+    /// Libc TLS pointer, restored into the FS base on return.
+    pub fs_base: usize,
+    /// Vector number, or [`CAUSE_SYSCALL`].
+    pub cause: usize,
+    /// CR2 for page faults, 0 otherwise.
+    pub fault_addr: usize,
+}
+
+impl TrapFrame {
+    /// RFLAGS with only the always-set reserved bit and IF.
+    pub const DEFAULT_RFLAGS: usize = (1 << 1) | (1 << 9);
+
+    pub const fn new() -> Self {
+        Self {
+            rax: 0,
+            rbx: 0,
+            rcx: 0,
+            rdx: 0,
+            rsi: 0,
+            rdi: 0,
+            rbp: 0,
+            r8: 0,
+            r9: 0,
+            r10: 0,
+            r11: 0,
+            r12: 0,
+            r13: 0,
+            r14: 0,
+            r15: 0,
+            rsp: 0,
+            rip: 0,
+            rflags: Self::DEFAULT_RFLAGS,
+            fs_base: 0,
+            cause: 0,
+            fault_addr: 0,
+        }
+    }
+
+    /// Syscall argument registers in Linux x86_64 ABI order.
+    #[inline(always)]
+    pub fn arg_mut(&mut self, idx: usize) -> Option<&mut usize> {
+        match idx {
+            0 => Some(&mut self.rdi),
+            1 => Some(&mut self.rsi),
+            2 => Some(&mut self.rdx),
+            3 => Some(&mut self.r10),
+            4 => Some(&mut self.r8),
+            5 => Some(&mut self.r9),
+            _ => None,
+        }
+    }
+}
+
+impl foundation::SyscallFrame for TrapFrame {
+    #[inline(always)]
+    fn pc(&self) -> usize {
+        self.rip
+    }
+
+    #[inline(always)]
+    fn syscall_number(&self) -> usize {
+        self.rax
+    }
+
+    #[inline(always)]
+    fn arg(&self, idx: usize) -> usize {
+        match idx {
+            0 => self.rdi,
+            1 => self.rsi,
+            2 => self.rdx,
+            3 => self.r10,
+            4 => self.r8,
+            5 => self.r9,
+            _ => 0,
+        }
+    }
+
+    #[inline(always)]
+    fn set_ret(&mut self, ret: isize) {
+        self.rax = ret as usize;
+    }
+}
+
+/// Target of `IA32_LSTAR`.
+///
+/// Saves the caller's registers in a `TrapFrame` at the top of the current thread's kernel
+/// stack, calls `trap_handler`, and returns through `ret_from_fork`. `syscall` leaves the
+/// return address in rcx and RFLAGS in r11; both are restored as saved, as on Linux.
+///
+/// # Safety
+/// Only reachable through `syscall`, with the GS base pointing at a valid `ThreadAnchor`.
+#[cfg(not(test))]
+#[unsafe(naked)]
+#[no_mangle]
+pub unsafe extern "C" fn _syscall_entry() -> ! {
+    use foundation::kfn::thread::ThreadAnchor;
+    use memoffset::offset_of;
+
+    core::arch::naked_asm!(
+        "mov qword ptr gs:[{a_user_sp}], rsp",
+        "mov rsp, qword ptr gs:[{a_kernel_sp}]",
+        "sub rsp, {size}",
+
+        "mov [rsp + {rax}], rax",
+        "mov [rsp + {rbx}], rbx",
+        "mov [rsp + {rcx}], rcx",
+        "mov [rsp + {rdx}], rdx",
+        "mov [rsp + {rsi}], rsi",
+        "mov [rsp + {rdi}], rdi",
+        "mov [rsp + {rbp}], rbp",
+        "mov [rsp + {r8}], r8",
+        "mov [rsp + {r9}], r9",
+        "mov [rsp + {r10}], r10",
+        "mov [rsp + {r11}], r11",
+        "mov [rsp + {r12}], r12",
+        "mov [rsp + {r13}], r13",
+        "mov [rsp + {r14}], r14",
+        "mov [rsp + {r15}], r15",
+        "mov [rsp + {rip}], rcx",
+        "mov [rsp + {rflags}], r11",
+        "mov rax, qword ptr gs:[{a_user_sp}]",
+        "mov [rsp + {rsp_}], rax",
+
+        "mov ecx, {fs_msr}",
+        "rdmsr",
+        "shl rdx, 32",
+        "or rax, rdx",
+        "mov [rsp + {fs_base}], rax",
+        "mov qword ptr [rsp + {cause}], {cause_syscall}",
+        "mov qword ptr [rsp + {fault_addr}], 0",
+
+        "cld",
+        "mov rdi, rsp",
+        "call {trap_handler}",
+        "mov rdi, rsp",
+        "jmp {ret_from_fork}",
+
+        a_user_sp = const offset_of!(ThreadAnchor, user_sp),
+        a_kernel_sp = const offset_of!(ThreadAnchor, kernel_sp),
+        size = const core::mem::size_of::<TrapFrame>(),
+        rax = const offset_of!(TrapFrame, rax),
+        rbx = const offset_of!(TrapFrame, rbx),
+        rcx = const offset_of!(TrapFrame, rcx),
+        rdx = const offset_of!(TrapFrame, rdx),
+        rsi = const offset_of!(TrapFrame, rsi),
+        rdi = const offset_of!(TrapFrame, rdi),
+        rbp = const offset_of!(TrapFrame, rbp),
+        r8 = const offset_of!(TrapFrame, r8),
+        r9 = const offset_of!(TrapFrame, r9),
+        r10 = const offset_of!(TrapFrame, r10),
+        r11 = const offset_of!(TrapFrame, r11),
+        r12 = const offset_of!(TrapFrame, r12),
+        r13 = const offset_of!(TrapFrame, r13),
+        r14 = const offset_of!(TrapFrame, r14),
+        r15 = const offset_of!(TrapFrame, r15),
+        rsp_ = const offset_of!(TrapFrame, rsp),
+        rip = const offset_of!(TrapFrame, rip),
+        rflags = const offset_of!(TrapFrame, rflags),
+        fs_base = const offset_of!(TrapFrame, fs_base),
+        cause = const offset_of!(TrapFrame, cause),
+        fault_addr = const offset_of!(TrapFrame, fault_addr),
+        fs_msr = const crate::msr::IA32_FS_BASE,
+        cause_syscall = const CAUSE_SYSCALL,
+        trap_handler = sym crate::trap_handler,
+        ret_from_fork = sym crate::ret_from_fork::ret_from_fork,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use foundation::SyscallFrame;
+
+    #[test]
+    fn test_linux_syscall_abi() {
+        let mut f = TrapFrame::new();
+        f.rax = 1;
+        for (i, v) in (0..6).zip(10..) {
+            *f.arg_mut(i).unwrap() = v;
+        }
+        assert_eq!(f.syscall_number(), 1);
+        assert_eq!(
+            (f.rdi, f.rsi, f.rdx, f.r10, f.r8, f.r9),
+            (10, 11, 12, 13, 14, 15)
+        );
+        assert_eq!((0..6).map(|i| f.arg(i)).sum::<usize>(), 75);
+        f.set_ret(-22);
+        assert_eq!(f.rax as isize, -22);
+        assert!(f.arg_mut(6).is_none());
+    }
+
+    #[test]
+    fn test_frame_keeps_stack_alignment() {
+        assert_eq!(core::mem::size_of::<TrapFrame>() % 16, 0);
+        assert_eq!(core::mem::align_of::<TrapFrame>(), 16);
+    }
+}
@@ -0,0 +1,259 @@
+//! Guest-side assertions with explicit cost levels.
+//!
+//! - [`debug_check!`]: compiled out unless `debug_assertions` is on.
+//! - [`check!`]: always on; a single branch on the hot path.
+//! - [`invariant!`]: marks a property the proof is expected to establish. On like [`check!`],
+//!   except that the `trust-invariants` feature compiles it out once the proof covers it.
+//!
+//! Failures print `file:line`, the level and a user tag through `__platform_stdout_write`, then
+//! terminate through `__platform_abort(SIGABRT)` like a panic, without pulling in `core::fmt`
+//! formatting of the failing expression.
+
+#![no_std]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Debug,
+    Check,
+    Invariant,
+}
+
+impl Level {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Check => "check",
+            Level::Invariant => "invariant",
+        }
+    }
+}
+
+/// Number of checks evaluated per level (always zero without the `stats` feature).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub debug: usize,
+    pub check: usize,
+    pub invariant: usize,
+}
+
+/// Whether [`invariant!`] is compiled out. Read through a constant so the feature of this crate
+/// decides, not one of the crate expanding the macro.
+#[doc(hidden)]
+pub const TRUST_INVARIANTS: bool = cfg!(feature = "trust-invariants");
+
+static DEBUG_COUNT: AtomicUsize = AtomicUsize::new(0);
+static CHECK_COUNT: AtomicUsize = AtomicUsize::new(0);
+static INVARIANT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn record(level: Level) {
+    if cfg!(feature = "stats") {
+        let counter = match level {
+            Level::Debug => &DEBUG_COUNT,
+            Level::Check => &CHECK_COUNT,
+            Level::Invariant => &INVARIANT_COUNT,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn stats() -> Stats {
+    Stats {
+        debug: DEBUG_COUNT.load(Ordering::Relaxed),
+        check: CHECK_COUNT.load(Ordering::Relaxed),
+        invariant: INVARIANT_COUNT.load(Ordering::Relaxed),
+    }
+}
+
+pub fn reset_stats() {
+    DEBUG_COUNT.store(0, Ordering::Relaxed);
+    CHECK_COUNT.store(0, Ordering::Relaxed);
+    INVARIANT_COUNT.store(0, Ordering::Relaxed);
+}
+
+/// Standard signal number for abort (SIGABRT)
+#[cfg(not(test))]
+const SIGABRT: i32 = 6;
+
+#[cfg(not(test))]
+extern "C" {
+    fn __platform_stdout_write(msg: *const u8, len: usize);
+    fn __platform_abort(sig: i32) -> !;
+}
+
+#[cfg(not(test))]
+fn write(s: &str) {
+    // SAFETY: `s` is a valid UTF-8 slice for the duration of the call.
+    unsafe { __platform_stdout_write(s.as_ptr(), s.len()) }
+}
+
+#[cfg(not(test))]
+fn write_u32(mut v: u32) {
+    let mut buf = [0u8; 10];
+    let mut i = buf.len();
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (v % 10) as u8;
+        v /= 10;
+        if v == 0 {
+            break;
+        }
+    }
+    // SAFETY: `buf[i..]` holds ASCII digits only.
+    write(unsafe { core::str::from_utf8_unchecked(&buf[i..]) });
+}
+
+/// Report a failed check and terminate. Kept out of line so passing checks stay one branch.
+#[cfg(not(test))]
+#[doc(hidden)]
+#[cold]
+#[inline(never)]
+pub fn fail(
+    level: Level,
+    tag: &'static str,
+    expr: &'static str,
+    file: &'static str,
+    line: u32,
+) -> ! {
+    write("ASSERT FAILED [");
+    write(level.as_str());
+    write("] ");
+    write(tag);
+    write(" at ");
+    write(file);
+    write(":");
+    write_u32(line);
+    write(": ");
+    write(expr);
+    write("\n");
+
+    // SAFETY: the platform ABI requires `__platform_abort` to terminate execution.
+    unsafe { __platform_abort(SIGABRT) }
+}
+
+// Host unit tests cannot link the platform ABI; surface failures as panics instead.
+#[cfg(test)]
+#[doc(hidden)]
+pub fn fail(
+    level: Level,
+    tag: &'static str,
+    expr: &'static str,
+    file: &'static str,
+    line: u32,
+) -> ! {
+    panic!(
+        "ASSERT FAILED [{}] {} at {}:{}: {}",
+        level.as_str(),
+        tag,
+        file,
+        line,
+        expr
+    )
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check_at_level {
+    ($level:expr, $cond:expr, $tag:expr) => {{
+        $crate::record($level);
+        if !($cond) {
+            $crate::fail($level, $tag, stringify!($cond), file!(), line!());
+        }
+    }};
+}
+
+/// Debug-only check; neither the condition nor the counter exist in release builds.
+#[macro_export]
+macro_rules! debug_check {
+    ($cond:expr $(,)?) => {
+        $crate::debug_check!($cond, "")
+    };
+    ($cond:expr, $tag:expr $(,)?) => {
+        if cfg!(debug_assertions) {
+            $crate::__check_at_level!($crate::Level::Debug, $cond, $tag)
+        }
+    };
+}
+
+/// Always-on cheap check.
+#[macro_export]
+macro_rules! check {
+    ($cond:expr $(,)?) => {
+        $crate::check!($cond, "")
+    };
+    ($cond:expr, $tag:expr $(,)?) => {
+        $crate::__check_at_level!($crate::Level::Check, $cond, $tag)
+    };
+}
+
+/// Invariant the proof is expected to establish. Checked like [`check!`] unless the
+/// `trust-invariants` feature is on, which drops both the condition and the counter.
+#[macro_export]
+macro_rules! invariant {
+    ($cond:expr $(,)?) => {
+        $crate::invariant!($cond, "")
+    };
+    ($cond:expr, $tag:expr $(,)?) => {
+        if !$crate::TRUST_INVARIANTS {
+            $crate::__check_at_level!($crate::Level::Invariant, $cond, $tag)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passing_checks() {
+        check!(1 + 1 == 2);
+        invariant!(true, "always");
+        debug_check!(2 > 1, "order");
+    }
+
+    #[test]
+    #[should_panic(expected = "ASSERT FAILED [check] len")]
+    fn test_failing_check_reports_tag() {
+        let v = [1u8, 2];
+        check!(v.len() == 3, "len");
+    }
+
+    #[test]
+    #[cfg(not(feature = "trust-invariants"))]
+    #[should_panic(expected = "[invariant]")]
+    fn test_failing_invariant() {
+        invariant!(false);
+    }
+
+    #[test]
+    #[cfg(feature = "trust-invariants")]
+    fn test_trusted_invariant_is_not_evaluated() {
+        let mut evaluated = false;
+        invariant!(
+            {
+                evaluated = true;
+                false
+            },
+            "trusted"
+        );
+        assert!(!evaluated);
+    }
+
+    #[test]
+    fn test_stats_counting() {
+        // Counters are global; only assert monotonic growth so tests can run in parallel.
+        let before = stats();
+        check!(true);
+        invariant!(true);
+        let after = stats();
+        if cfg!(feature = "stats") {
+            assert!(after.check > before.check);
+            assert_eq!(after.invariant > before.invariant, !TRUST_INVARIANTS);
+        } else {
+            assert_eq!(after, Stats::default());
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Adler-32 (RFC 1950), the zlib stream checksum.
+
+const MOD_ADLER: u32 = 65521;
+
+/// Most bytes that can be summed before `b` may overflow a `u32` (as in zlib).
+const NMAX: usize = 5552;
+
+/// Incremental Adler-32.
+#[derive(Clone, Copy, Debug)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    pub const fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let (mut a, mut b) = (self.a, self.b);
+        // Reduce once per NMAX bytes instead of once per byte.
+        for chunk in data.chunks(NMAX) {
+            for &byte in chunk {
+                a += byte as u32;
+                b += a;
+            }
+            a %= MOD_ADLER;
+            b %= MOD_ADLER;
+        }
+        self.a = a;
+        self.b = b;
+    }
+
+    /// The checksum of everything passed to [`update`](Self::update) so far.
+    pub const fn value(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let mut adler = Adler32::new();
+    adler.update(data);
+    adler.value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adler32_vectors() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+
+        // Long enough to need several reductions; all 0xff is the worst case for overflow.
+        let data = [0xffu8; 3 * NMAX + 7];
+        let mut adler = Adler32::new();
+        for piece in data.chunks(1000) {
+            adler.update(piece);
+        }
+        assert_eq!(adler.value(), adler32(&data));
+
+        let (mut a, mut b) = (1u64, 0u64);
+        for &byte in &data {
+            a = (a + byte as u64) % MOD_ADLER as u64;
+            b = (b + a) % MOD_ADLER as u64;
+        }
+        assert_eq!(adler32(&data), ((b << 16) | a) as u32);
+    }
+}
@@ -0,0 +1,12 @@
+#![no_std]
+
+//! Data integrity checksums for guests: CRC32 (IEEE, as in zlib, gzip and PNG) and Adler-32.
+//!
+//! Both come as a one-shot function and an incremental hasher, so data that arrives in pieces
+//! (blocks of a stream, reads from `/dev/stdin`) checks the same as the concatenation.
+
+pub mod adler32;
+pub mod crc32;
+
+pub use adler32::{adler32, Adler32};
+pub use crc32::{crc32, Crc32};
@@ -0,0 +1,289 @@
+//! Buffered stdout/stderr with pluggable sinks.
+//!
+//! Each stream collects writes in a [`CONSOLE_BUF_SIZE`] buffer and hands whole chunks to every
+//! attached [`Sink`], so a platform whose output costs a host round trip per call pays it per
+//! line instead of per byte. stdout is line buffered and stderr unbuffered by default; writing
+//! to stderr flushes stdout first so the two stay in order on a shared sink.
+//!
+//! Buffered bytes are only visible once flushed: platforms call [`flush_all`] on their exit path.
+
+use foundation::utils::GlobalCell;
+use vfs_core::{
+    iov_slices, noop_ioctl, noop_read, Fd, FdEntry, FileOps, IoVec, VfsResult, S_IFCHR,
+};
+
+/// Output backend, e.g. HTIF putchar, semihosting or [`crate::capture::sink`].
+pub type Sink = fn(&[u8]);
+
+pub const MAX_SINKS: usize = 4;
+
+pub const CONSOLE_BUF_SIZE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferMode {
+    /// Pass every write straight through.
+    Unbuffered,
+    /// Flush when a write contains a newline or the buffer fills.
+    Line,
+    /// Flush only when the buffer fills, on [`flush`], or on close.
+    Block,
+}
+
+const STDOUT: usize = 0;
+const STDERR: usize = 1;
+
+struct Stream {
+    mode: BufferMode,
+    buf: [u8; CONSOLE_BUF_SIZE],
+    len: usize,
+}
+
+impl Stream {
+    const fn new(mode: BufferMode) -> Self {
+        Self {
+            mode,
+            buf: [0; CONSOLE_BUF_SIZE],
+            len: 0,
+        }
+    }
+}
+
+struct Console {
+    sinks: [Option<Sink>; MAX_SINKS],
+    streams: [Stream; 2],
+}
+
+fn emit(sinks: &[Option<Sink>], bytes: &[u8]) {
+    if bytes.is_empty() {
+        return;
+    }
+    for sink in sinks.iter().flatten() {
+        sink(bytes);
+    }
+}
+
+fn drain(sinks: &[Option<Sink>], stream: &mut Stream) {
+    emit(sinks, &stream.buf[..stream.len]);
+    stream.len = 0;
+}
+
+impl Console {
+    const fn new() -> Self {
+        Self {
+            sinks: [None; MAX_SINKS],
+            streams: [
+                Stream::new(BufferMode::Line),
+                Stream::new(BufferMode::Unbuffered),
+            ],
+        }
+    }
+
+    fn attach(&mut self, sink: Sink) -> VfsResult<()> {
+        let slot = self
+            .sinks
+            .iter_mut()
+            .find(|s| s.is_none())
+            .ok_or(-(libc::ENOMEM as isize))?;
+        *slot = Some(sink);
+        Ok(())
+    }
+
+    fn flush(&mut self, stream: usize) {
+        drain(&self.sinks, &mut self.streams[stream]);
+    }
+
+    fn write(&mut self, stream: usize, data: &[u8]) {
+        self.write_parts(stream, [data]);
+    }
+
+    /// Write `parts` back to back as one write: the stream's buffering applies to the whole, so
+    /// an unbuffered or line-buffered stream still emits a few chunks rather than one per part.
+    fn write_parts<'a>(&mut self, stream: usize, parts: impl IntoIterator<Item = &'a [u8]>) {
+        if stream == STDERR {
+            self.flush(STDOUT);
+        }
+        let Self { sinks, streams } = self;
+        let stream = &mut streams[stream];
+
+        let mut newline = false;
+        for data in parts {
+            newline = newline || (stream.mode == BufferMode::Line && data.contains(&b'\n'));
+            let mut rest = data;
+            while !rest.is_empty() {
+                if stream.len == 0 && rest.len() >= CONSOLE_BUF_SIZE {
+                    // Nothing to coalesce with: skip the copy.
+                    emit(sinks, rest);
+                    break;
+                }
+                let n = rest.len().min(CONSOLE_BUF_SIZE - stream.len);
+                stream.buf[stream.len..stream.len + n].copy_from_slice(&rest[..n]);
+                stream.len += n;
+                rest = &rest[n..];
+                if stream.len == CONSOLE_BUF_SIZE {
+                    drain(sinks, stream);
+                }
+            }
+        }
+        if stream.mode == BufferMode::Unbuffered || newline {
+            drain(sinks, stream);
+        }
+    }
+}
+
+static CONSOLE: GlobalCell<Console> = GlobalCell::new(Console::new());
+
+fn stream_of(fd: Fd) -> VfsResult<usize> {
+    match fd {
+        1 => Ok(STDOUT),
+        2 => Ok(STDERR),
+        _ => Err(-(libc::EBADF as isize)),
+    }
+}
+
+/// Send flushed output to `sink` as well as the sinks already attached.
+pub fn attach_sink(sink: Sink) -> VfsResult<()> {
+    CONSOLE.with_mut(|console| console.attach(sink))
+}
+
+/// Flush both streams, then detach every sink.
+pub fn clear_sinks() {
+    flush_all();
+    CONSOLE.with_mut(|console| console.sinks = [None; MAX_SINKS]);
+}
+
+/// Change the buffering of fd 1 or 2, flushing what it holds first.
+pub fn set_mode(fd: Fd, mode: BufferMode) -> VfsResult<()> {
+    let stream = stream_of(fd)?;
+    CONSOLE.with_mut(|console| {
+        console.flush(stream);
+        console.streams[stream].mode = mode;
+    });
+    Ok(())
+}
+
+pub fn flush(fd: Fd) -> VfsResult<()> {
+    let stream = stream_of(fd)?;
+    CONSOLE.with_mut(|console| console.flush(stream));
+    Ok(())
+}
+
+pub fn flush_all() {
+    CONSOLE.with_mut(|console| {
+        console.flush(STDOUT);
+        console.flush(STDERR);
+    });
+}
+
+fn console_write(file: *mut u8, buf: *const u8, count: usize, _pos: &mut usize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    // SAFETY: the caller provides `count` readable bytes at `buf`.
+    let data = unsafe { core::slice::from_raw_parts(buf, count) };
+    CONSOLE.with_mut(|console| console.write(file as usize, data));
+    count as isize
+}
+
+fn console_write_iter(file: *mut u8, iov: &[IoVec], _pos: &mut usize) -> isize {
+    // SAFETY: the VFS hands over buffers readable for their lengths.
+    let parts = unsafe { iov_slices(iov) };
+    let total = iov.iter().map(|v| v.len).sum::<usize>();
+    CONSOLE.with_mut(|console| console.write_parts(file as usize, parts));
+    total as isize
+}
+
+fn console_release(file: *mut u8) -> isize {
+    CONSOLE.with_mut(|console| console.flush(file as usize));
+    0
+}
+
+/// Write-only console ops; `private_data` is the stream index.
+pub static CONSOLE_FOPS: FileOps = FileOps {
+    read: noop_read,
+    write: console_write,
+    release: console_release,
+    size: None,
+    mode: S_IFCHR | 0o620,
+    ioctl: noop_ioctl,
+    read_iter: None,
+    write_iter: Some(console_write_iter),
+};
+
+/// Buffered stdout, e.g. for `register_fd(1, ..)` or `register_device("/dev/stdout", ..)`.
+pub fn stdout_factory() -> FdEntry {
+    FdEntry {
+        ops: &CONSOLE_FOPS,
+        private_data: STDOUT as *mut u8,
+    }
+}
+
+pub fn stderr_factory() -> FdEntry {
+    FdEntry {
+        ops: &CONSOLE_FOPS,
+        private_data: STDERR as *mut u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+    fn counting_sink(bytes: &[u8]) {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(bytes.len(), Ordering::Relaxed);
+    }
+
+    fn take() -> (usize, usize) {
+        (
+            CALLS.swap(0, Ordering::Relaxed),
+            BYTES.swap(0, Ordering::Relaxed),
+        )
+    }
+
+    #[test]
+    fn test_buffer_modes() {
+        let mut console = Console::new();
+        console.attach(counting_sink).unwrap();
+
+        // Line: held until a newline, then flushed as one chunk.
+        console.write(STDOUT, b"hello ");
+        console.write(STDOUT, b"world");
+        assert_eq!(take(), (0, 0));
+        console.write(STDOUT, b"!\n");
+        assert_eq!(take(), (1, 13));
+
+        // stderr is unbuffered and pushes pending stdout out first.
+        console.write(STDOUT, b"partial");
+        console.write(STDERR, b"err");
+        assert_eq!(take(), (2, 10));
+
+        // Block: flushed whenever the buffer fills; large writes bypass it.
+        console.streams[STDOUT].mode = BufferMode::Block;
+        console.write(STDOUT, &[b'\n'; CONSOLE_BUF_SIZE - 1]);
+        assert_eq!(take(), (0, 0));
+        console.write(STDOUT, b"ab");
+        assert_eq!(take(), (1, CONSOLE_BUF_SIZE));
+        console.flush(STDOUT);
+        assert_eq!(take(), (1, 1));
+        console.write(STDOUT, &[0; 2 * CONSOLE_BUF_SIZE]);
+        assert_eq!(take(), (1, 2 * CONSOLE_BUF_SIZE));
+
+        // A vector is one write: a single chunk on an unbuffered stream.
+        console.write_parts(STDERR, [&b"ab"[..], b"", b"cd"]);
+        assert_eq!(take(), (1, 4));
+        console.streams[STDOUT].mode = BufferMode::Line;
+        console.write_parts(STDOUT, [&b"a\n"[..], b"b"]);
+        assert_eq!(take(), (1, 3));
+
+        for _ in 1..MAX_SINKS {
+            console.attach(counting_sink).unwrap();
+        }
+        assert_eq!(console.attach(counting_sink), Err(-(libc::ENOMEM as isize)));
+        console.write(STDERR, b"x");
+        assert_eq!(take(), (MAX_SINKS, MAX_SINKS));
+    }
+}
@@ -0,0 +1,70 @@
+//! In-memory console sink.
+//!
+//! Keeps the first [`CAPTURE_CAPACITY`] bytes of console output so tests, or a host reading
+//! guest memory, can inspect it. Attach with `attach_sink(capture::sink)`.
+
+use foundation::utils::GlobalCell;
+
+pub const CAPTURE_CAPACITY: usize = 4096;
+
+struct Capture {
+    buf: [u8; CAPTURE_CAPACITY],
+    len: usize,
+    /// Bytes that arrived after the buffer filled.
+    dropped: usize,
+}
+
+impl Capture {
+    const fn new() -> Self {
+        Self {
+            buf: [0; CAPTURE_CAPACITY],
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        let n = bytes.len().min(CAPTURE_CAPACITY - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+        self.len += n;
+        self.dropped += bytes.len() - n;
+    }
+}
+
+static CAPTURE: GlobalCell<Capture> = GlobalCell::new(Capture::new());
+
+pub fn sink(bytes: &[u8]) {
+    CAPTURE.with_mut(|capture| capture.push(bytes));
+}
+
+/// Run `f` on the bytes captured so far.
+pub fn with_captured<R>(f: impl FnOnce(&[u8]) -> R) -> R {
+    CAPTURE.with(|capture| f(&capture.buf[..capture.len]))
+}
+
+/// Bytes lost because the buffer was full.
+pub fn dropped() -> usize {
+    CAPTURE.with(|capture| capture.dropped)
+}
+
+pub fn clear() {
+    CAPTURE.with_mut(|capture| {
+        capture.len = 0;
+        capture.dropped = 0;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_keeps_prefix() {
+        let mut capture = Capture::new();
+        capture.push(b"abc");
+        capture.push(&[b'x'; CAPTURE_CAPACITY]);
+        assert_eq!(capture.len, CAPTURE_CAPACITY);
+        assert_eq!(&capture.buf[..4], b"abcx");
+        assert_eq!(capture.dropped, 3);
+    }
+}
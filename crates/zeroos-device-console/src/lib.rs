@@ -1,20 +1,38 @@
 #![no_std]
 
-use vfs_core::{noop_close, noop_ioctl, noop_seek, FileOps};
+mod buffered;
+pub mod capture;
 
-fn console_read_eof(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
+pub use buffered::{
+    attach_sink, clear_sinks, flush, flush_all, set_mode, stderr_factory, stdout_factory,
+    BufferMode, Sink, CONSOLE_BUF_SIZE, CONSOLE_FOPS, MAX_SINKS,
+};
+
+use vfs_core::{noop_close, noop_ioctl, FileOps, ReadOp, WriteOp, S_IFCHR};
+
+fn console_read_eof(_file: *mut u8, _buf: *mut u8, _count: usize, _pos: &mut usize) -> isize {
     0
 }
 
-fn console_read_unsupported(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
+fn console_read_unsupported(
+    _file: *mut u8,
+    _buf: *mut u8,
+    _count: usize,
+    _pos: &mut usize,
+) -> isize {
     -(libc::EBADF as isize)
 }
 
-fn console_write_unsupported(_file: *mut u8, _buf: *const u8, _count: usize) -> isize {
+fn console_write_unsupported(
+    _file: *mut u8,
+    _buf: *const u8,
+    _count: usize,
+    _pos: &mut usize,
+) -> isize {
     -(libc::EBADF as isize)
 }
 
-pub const fn read_only_fops(read_fn: Option<fn(*mut u8, *mut u8, usize) -> isize>) -> FileOps {
+pub const fn read_only_fops(read_fn: Option<ReadOp>) -> FileOps {
     FileOps {
         read: if let Some(f) = read_fn {
             f
@@ -23,18 +41,24 @@ pub const fn read_only_fops(read_fn: Option<fn(*mut u8, *mut u8, usize) -> isize
         },
         write: console_write_unsupported,
         release: noop_close,
-        llseek: noop_seek,
+        size: None,
+        mode: S_IFCHR | 0o620,
         ioctl: noop_ioctl,
+        read_iter: None,
+        write_iter: None,
     }
 }
 
-pub const fn write_only_fops(write_fn: fn(*mut u8, *const u8, usize) -> isize) -> FileOps {
+pub const fn write_only_fops(write_fn: WriteOp) -> FileOps {
     FileOps {
         read: console_read_unsupported,
         write: write_fn,
         release: noop_close,
-        llseek: noop_seek,
+        size: None,
+        mode: S_IFCHR | 0o620,
         ioctl: noop_ioctl,
+        read_iter: None,
+        write_iter: None,
     }
 }
 
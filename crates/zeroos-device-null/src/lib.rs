@@ -1,13 +1,13 @@
 #![no_std]
 
 use core::ptr::null_mut;
-use vfs_core::{noop_close, noop_ioctl, noop_seek, FdEntry, FileOps};
+use vfs_core::{noop_close, noop_ioctl, FdEntry, FileOps, S_IFCHR};
 
-fn null_read(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
+fn null_read(_file: *mut u8, _buf: *mut u8, _count: usize, _pos: &mut usize) -> isize {
     0
 }
 
-fn null_write(_file: *mut u8, _buf: *const u8, count: usize) -> isize {
+fn null_write(_file: *mut u8, _buf: *const u8, count: usize, _pos: &mut usize) -> isize {
     count as isize
 }
 
@@ -15,8 +15,11 @@ pub const NULL_FOPS: FileOps = FileOps {
     read: null_read,
     write: null_write,
     release: noop_close,
-    llseek: noop_seek,
+    size: None,
+    mode: S_IFCHR | 0o666,
     ioctl: noop_ioctl,
+    read_iter: None,
+    write_iter: None,
 };
 
 pub fn null_factory() -> FdEntry {
@@ -33,14 +36,14 @@ mod tests {
     #[test]
     fn test_null_read() {
         let mut buf = [0u8; 64];
-        let result = null_read(null_mut(), buf.as_mut_ptr(), buf.len());
+        let result = null_read(null_mut(), buf.as_mut_ptr(), buf.len(), &mut 0);
         assert_eq!(result, 0, "/dev/null read should return EOF");
     }
 
     #[test]
     fn test_null_write() {
         let buf = [0u8; 64];
-        let result = null_write(null_mut(), buf.as_ptr(), buf.len());
+        let result = null_write(null_mut(), buf.as_ptr(), buf.len(), &mut 0);
         assert_eq!(result, 64, "/dev/null write should succeed");
     }
 }
@@ -0,0 +1,126 @@
+#![no_std]
+
+//! `/dev/commit` (and fd [`COMMIT_FD`]): public output the guest commits to.
+//!
+//! Writes append to a buffer the platform exposes through `__platform_output`; nothing is read
+//! back and nothing is seekable. On exit the platform calls [`finalize`], which hands the
+//! committed bytes to `__platform_output_finalize` exactly once and rejects later writes with
+//! `EPIPE`. A write that does not fit is truncated; once the buffer is full writes fail with
+//! `ENOSPC`, so a guest never believes it committed bytes the host will not see.
+
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use vfs_core::{noop_close, noop_ioctl, Fd, FdEntry, FileOps, S_IFCHR};
+
+/// Descriptor the output device is registered on, next to stdin/stdout/stderr.
+pub const COMMIT_FD: Fd = 3;
+
+extern "C" {
+    /// Return the start of the output buffer and store its capacity in `*cap`.
+    fn __platform_output(cap: *mut usize) -> *mut u8;
+    /// Publish the `len` committed bytes at `buf` to the host.
+    fn __platform_output_finalize(buf: *const u8, len: usize);
+}
+
+static LEN: AtomicUsize = AtomicUsize::new(0);
+static FINALIZED: AtomicBool = AtomicBool::new(false);
+
+fn output() -> &'static mut [u8] {
+    let mut cap = 0;
+    // SAFETY: the platform returns a buffer of `cap` bytes that stays valid for the whole run
+    // and is only written through this device.
+    unsafe {
+        let ptr = __platform_output(&mut cap);
+        if ptr.is_null() {
+            return &mut [];
+        }
+        core::slice::from_raw_parts_mut(ptr, cap)
+    }
+}
+
+/// Append as much of `data` as fits after `len` bytes of `output`; returns the count written.
+fn append(output: &mut [u8], len: &AtomicUsize, data: &[u8]) -> isize {
+    let start = len.load(Ordering::Relaxed).min(output.len());
+    let n = data.len().min(output.len() - start);
+    if n == 0 && !data.is_empty() {
+        return -(libc::ENOSPC as isize);
+    }
+    output[start..start + n].copy_from_slice(&data[..n]);
+    len.store(start + n, Ordering::Relaxed);
+    n as isize
+}
+
+fn output_write(_file: *mut u8, buf: *const u8, count: usize, _pos: &mut usize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    if FINALIZED.load(Ordering::Acquire) {
+        return -(libc::EPIPE as isize);
+    }
+    // SAFETY: the caller provides `count` readable bytes at `buf`.
+    let data = unsafe { core::slice::from_raw_parts(buf, count) };
+    append(output(), &LEN, data)
+}
+
+fn output_read(_file: *mut u8, _buf: *mut u8, _count: usize, _pos: &mut usize) -> isize {
+    -(libc::EBADF as isize)
+}
+
+pub const OUTPUT_FOPS: FileOps = FileOps {
+    read: output_read,
+    write: output_write,
+    release: noop_close,
+    size: None,
+    mode: S_IFCHR | 0o222,
+    ioctl: noop_ioctl,
+    read_iter: None,
+    write_iter: None,
+};
+
+pub fn output_factory() -> FdEntry {
+    FdEntry {
+        ops: &OUTPUT_FOPS,
+        private_data: null_mut(),
+    }
+}
+
+/// Bytes committed so far.
+pub fn committed_len() -> usize {
+    LEN.load(Ordering::Relaxed)
+}
+
+/// Run `f` on the bytes committed so far.
+pub fn with_committed<R>(f: impl FnOnce(&[u8]) -> R) -> R {
+    let output = output();
+    let len = LEN.load(Ordering::Relaxed).min(output.len());
+    f(&output[..len])
+}
+
+/// Seal the output and publish it to the host; later calls do nothing.
+pub fn finalize() {
+    if FINALIZED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    with_committed(|bytes| {
+        // SAFETY: `bytes` is the committed prefix of the platform's own buffer.
+        unsafe { __platform_output_finalize(bytes.as_ptr(), bytes.len()) }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_truncates_then_fills() {
+        let len = AtomicUsize::new(0);
+        let mut output = [0u8; 6];
+        assert_eq!(append(&mut output, &len, b"abcd"), 4);
+        assert_eq!(append(&mut output, &len, b""), 0);
+        assert_eq!(append(&mut output, &len, b"efgh"), 2);
+        assert_eq!(&output, b"abcdef");
+        assert_eq!(append(&mut output, &len, b"i"), -(libc::ENOSPC as isize));
+        assert_eq!(len.load(Ordering::Relaxed), 6);
+    }
+}
@@ -0,0 +1,107 @@
+#![no_std]
+
+//! `/dev/stdin` backed by an input buffer the host commits before execution.
+//!
+//! The platform exposes the buffer through `__platform_input`. All descriptors share one read
+//! cursor, like a pipe: bytes consumed through fd 0 are gone for `/dev/stdin` too. [`tape`]
+//! serves the same buffer as length-prefixed records through `/dev/input`.
+
+pub mod tape;
+
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use vfs_core::{noop_close, noop_ioctl, FdEntry, FileOps, S_IFCHR};
+
+extern "C" {
+    /// Return the start of the input buffer and store its length in `*len`.
+    fn __platform_input(len: *mut usize) -> *const u8;
+}
+
+static CURSOR: AtomicUsize = AtomicUsize::new(0);
+
+fn input() -> &'static [u8] {
+    let mut len = 0;
+    // SAFETY: the platform returns a buffer of `len` bytes that stays valid and unchanged for
+    // the whole run.
+    unsafe {
+        let ptr = __platform_input(&mut len);
+        if ptr.is_null() {
+            return &[];
+        }
+        core::slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// Copy the next unread bytes of `input` into `buf`; returns the count (0 at EOF).
+fn read_from(input: &[u8], cursor: &AtomicUsize, buf: &mut [u8]) -> usize {
+    let start = cursor.load(Ordering::Relaxed).min(input.len());
+    let n = buf.len().min(input.len() - start);
+    buf[..n].copy_from_slice(&input[start..start + n]);
+    cursor.store(start + n, Ordering::Relaxed);
+    n
+}
+
+fn stdin_read(_file: *mut u8, buf: *mut u8, count: usize, _pos: &mut usize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    // SAFETY: the caller provides `count` writable bytes at `buf`.
+    let buf = unsafe { core::slice::from_raw_parts_mut(buf, count) };
+    #[cfg(feature = "journal")]
+    {
+        use foundation::journal::{journaled, EventKind};
+
+        journaled(EventKind::Input, buf, |buf| {
+            read_from(input(), &CURSOR, buf) as i64
+        }) as isize
+    }
+    #[cfg(not(feature = "journal"))]
+    {
+        read_from(input(), &CURSOR, buf) as isize
+    }
+}
+
+fn stdin_write(_file: *mut u8, _buf: *const u8, _count: usize, _pos: &mut usize) -> isize {
+    -(libc::EBADF as isize)
+}
+
+pub const STDIN_FOPS: FileOps = FileOps {
+    read: stdin_read,
+    write: stdin_write,
+    release: noop_close,
+    size: None,
+    mode: S_IFCHR | 0o444,
+    ioctl: noop_ioctl,
+    read_iter: None,
+    write_iter: None,
+};
+
+pub fn stdin_factory() -> FdEntry {
+    FdEntry {
+        ops: &STDIN_FOPS,
+        private_data: null_mut(),
+    }
+}
+
+/// Bytes of input not yet read.
+pub fn remaining() -> usize {
+    input().len().saturating_sub(CURSOR.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_advance_to_eof() {
+        let cursor = AtomicUsize::new(0);
+        let input = b"witness";
+        let mut buf = [0u8; 4];
+        assert_eq!(read_from(input, &cursor, &mut buf), 4);
+        assert_eq!(&buf, b"witn");
+        assert_eq!(read_from(input, &cursor, &mut buf), 3);
+        assert_eq!(&buf[..3], b"ess");
+        assert_eq!(read_from(input, &cursor, &mut buf), 0);
+    }
+}
@@ -0,0 +1,210 @@
+//! `/dev/input`: the host input buffer read as a tape of length-prefixed records.
+//!
+//! Each record is a little-endian `u32` length followed by that many bytes. A read returns bytes
+//! of the current record only; once the record is used up the next read returns 0, like a tape
+//! mark, and the read after that starts the next record. At the end of the tape every read
+//! returns 0, so [`INPUT_TAPE_REMAINING`] tells an empty record from the end. A header cut short
+//! by the end of the buffer ends the tape, and a record longer than what follows is truncated.
+//!
+//! The tape reads the same buffer as `/dev/stdin` with its own cursor; a guest uses one or the
+//! other.
+
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use vfs_core::{copy_to_iov, noop_close, FdEntry, FileOps, IoVec, S_IFCHR};
+
+/// `ioctl` request: store the unread bytes of the current record, or of the next one between
+/// records, in the `c_int` at `arg`.
+pub const FIONREAD: usize = libc::FIONREAD as usize;
+
+/// `ioctl` request: store the unread bytes of the whole tape, record headers included, in the
+/// `u64` at `arg`.
+pub const INPUT_TAPE_REMAINING: usize = 0x5a01;
+
+const HEADER: usize = 4;
+
+/// Marks `end` while the cursor sits between records.
+const BETWEEN: usize = usize::MAX;
+
+struct Tape {
+    /// Offset of the next unread byte, header or payload.
+    pos: AtomicUsize,
+    /// End of the current record's payload, or [`BETWEEN`].
+    end: AtomicUsize,
+}
+
+impl Tape {
+    const fn new() -> Self {
+        Self {
+            pos: AtomicUsize::new(0),
+            end: AtomicUsize::new(BETWEEN),
+        }
+    }
+
+    /// Payload bounds of the record whose header starts at `pos`, if a whole header is there.
+    fn record_at(input: &[u8], pos: usize) -> Option<(usize, usize)> {
+        let header = input.get(pos..pos.checked_add(HEADER)?)?;
+        let len = u32::from_le_bytes(header.try_into().ok()?) as usize;
+        let start = pos + HEADER;
+        Some((start, start.saturating_add(len).min(input.len())))
+    }
+
+    fn read(&self, input: &[u8], buf: &mut [u8]) -> usize {
+        let bytes = self.take(input, buf.len());
+        buf[..bytes.len()].copy_from_slice(bytes);
+        bytes.len()
+    }
+
+    /// Consume up to `want` bytes of the current record, as one read.
+    fn take<'a>(&self, input: &'a [u8], want: usize) -> &'a [u8] {
+        if want == 0 {
+            return &[];
+        }
+        let mut pos = self.pos.load(Ordering::Relaxed);
+        let mut end = self.end.load(Ordering::Relaxed);
+        if end == BETWEEN {
+            let Some((start, record_end)) = Self::record_at(input, pos) else {
+                return &[];
+            };
+            pos = start;
+            end = record_end;
+        }
+        let n = want.min(end - pos);
+        self.pos.store(pos + n, Ordering::Relaxed);
+        // An exhausted record reports its mark now and moves between records.
+        self.end
+            .store(if n == 0 { BETWEEN } else { end }, Ordering::Relaxed);
+        &input[pos..pos + n]
+    }
+
+    fn record_remaining(&self, input: &[u8]) -> usize {
+        let pos = self.pos.load(Ordering::Relaxed);
+        match self.end.load(Ordering::Relaxed) {
+            BETWEEN => Self::record_at(input, pos).map_or(0, |(start, end)| end - start),
+            end => end - pos,
+        }
+    }
+
+    fn tape_remaining(&self, input: &[u8]) -> usize {
+        input.len().saturating_sub(self.pos.load(Ordering::Relaxed))
+    }
+}
+
+static TAPE: Tape = Tape::new();
+
+fn input_read(_file: *mut u8, buf: *mut u8, count: usize, _pos: &mut usize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    // SAFETY: the caller provides `count` writable bytes at `buf`.
+    let buf = unsafe { core::slice::from_raw_parts_mut(buf, count) };
+    TAPE.read(crate::input(), buf) as isize
+}
+
+/// Scatter one read over the buffers, so a vector never runs into the record's mark the way a
+/// read per buffer would once the record is used up.
+fn input_read_iter(_file: *mut u8, iov: &[IoVec], _pos: &mut usize) -> isize {
+    let want = iov.iter().map(|v| v.len).sum();
+    let bytes = TAPE.take(crate::input(), want);
+    // SAFETY: the VFS hands over buffers writable for their lengths.
+    unsafe { copy_to_iov(bytes, iov) as isize }
+}
+
+fn input_write(_file: *mut u8, _buf: *const u8, _count: usize, _pos: &mut usize) -> isize {
+    -(libc::EBADF as isize)
+}
+
+fn input_ioctl(_file: *mut u8, request: usize, arg: usize) -> isize {
+    if arg == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    // SAFETY: for both requests the caller passes a pointer to the documented integer type.
+    unsafe {
+        match request {
+            FIONREAD => {
+                let n = TAPE.record_remaining(crate::input());
+                *(arg as *mut libc::c_int) = n.min(libc::c_int::MAX as usize) as libc::c_int;
+            }
+            INPUT_TAPE_REMAINING => {
+                *(arg as *mut u64) = TAPE.tape_remaining(crate::input()) as u64;
+            }
+            _ => return -(libc::ENOTTY as isize),
+        }
+    }
+    0
+}
+
+pub const INPUT_FOPS: FileOps = FileOps {
+    read: input_read,
+    write: input_write,
+    release: noop_close,
+    size: None,
+    mode: S_IFCHR | 0o444,
+    ioctl: input_ioctl,
+    read_iter: Some(input_read_iter),
+    write_iter: None,
+};
+
+pub fn input_factory() -> FdEntry {
+    FdEntry {
+        ops: &INPUT_FOPS,
+        private_data: null_mut(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_end_with_a_mark() {
+        let tape = Tape::new();
+        let input = b"\x03\0\0\0abc\0\0\0\0\x02\0\0\0de\x09\0";
+        let mut buf = [0u8; 2];
+
+        assert_eq!(tape.record_remaining(input), 3);
+        assert_eq!(tape.read(input, &mut buf), 2);
+        assert_eq!(&buf, b"ab");
+        assert_eq!(tape.record_remaining(input), 1);
+        assert_eq!(tape.read(input, &mut buf), 1);
+        assert_eq!(tape.read(input, &mut buf), 0);
+
+        // Empty record: just its mark.
+        assert_eq!(tape.record_remaining(input), 0);
+        assert_eq!(tape.read(input, &mut buf), 0);
+
+        assert_eq!(tape.tape_remaining(input), 8);
+        assert_eq!(tape.read(input, &mut buf), 2);
+        assert_eq!(&buf, b"de");
+        assert_eq!(tape.read(input, &mut buf), 0);
+
+        // A truncated header ends the tape.
+        assert_eq!(tape.tape_remaining(input), 2);
+        assert_eq!(tape.read(input, &mut buf), 0);
+        assert_eq!(tape.read(input, &mut buf), 0);
+    }
+
+    #[test]
+    fn test_vector_read_leaves_the_mark() {
+        let tape = Tape::new();
+        let input = b"\x03\0\0\0abc\x01\0\0\0d";
+        let (mut a, mut b) = ([0u8; 2], [0u8; 2]);
+        let iov = [
+            IoVec {
+                base: a.as_mut_ptr(),
+                len: a.len(),
+            },
+            IoVec {
+                base: b.as_mut_ptr(),
+                len: b.len(),
+            },
+        ];
+
+        let bytes = tape.take(input, 4);
+        assert_eq!(unsafe { copy_to_iov(bytes, &iov) }, 3);
+        assert_eq!((&a, &b[..1]), (b"ab", &b"c"[..]));
+        assert!(tape.take(input, 4).is_empty());
+        assert_eq!(tape.take(input, 4), b"d");
+    }
+}
@@ -2,16 +2,16 @@
 
 use core::ptr::null_mut;
 
-use vfs_core::FileOps;
+use vfs_core::{FileOps, S_IFCHR};
 
-fn urandom_read(_file: *mut u8, buf: *mut u8, count: usize) -> isize {
+fn urandom_read(_file: *mut u8, buf: *mut u8, count: usize, _pos: &mut usize) -> isize {
     if count != 0 && buf.is_null() {
         return -(libc::EFAULT as isize);
     }
     unsafe { foundation::kfn::random::krandom(buf, count) }
 }
 
-fn urandom_write(_file: *mut u8, _buf: *const u8, _count: usize) -> isize {
+fn urandom_write(_file: *mut u8, _buf: *const u8, _count: usize, _pos: &mut usize) -> isize {
     -(libc::EBADF as isize)
 }
 
@@ -19,10 +19,6 @@ fn urandom_close(_file: *mut u8) -> isize {
     0
 }
 
-fn urandom_seek(_file: *mut u8, _offset: isize, _whence: i32) -> isize {
-    -(libc::ESPIPE as isize)
-}
-
 fn urandom_ioctl(_file: *mut u8, _request: usize, _arg: usize) -> isize {
     -(libc::ENOTTY as isize)
 }
@@ -31,8 +27,11 @@ pub const URANDOM_FOPS: FileOps = FileOps {
     read: urandom_read,
     write: urandom_write,
     release: urandom_close,
-    llseek: urandom_seek,
+    size: None,
+    mode: S_IFCHR | 0o666,
     ioctl: urandom_ioctl,
+    read_iter: None,
+    write_iter: None,
 };
 
 pub fn urandom_factory() -> vfs_core::FdEntry {
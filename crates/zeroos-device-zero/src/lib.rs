@@ -1,9 +1,9 @@
 #![no_std]
 
 use core::ptr::null_mut;
-use vfs_core::{noop_close, noop_ioctl, noop_seek, FdEntry, FileOps};
+use vfs_core::{noop_close, noop_ioctl, FdEntry, FileOps, S_IFCHR};
 
-fn zero_read(_file: *mut u8, buf: *mut u8, count: usize) -> isize {
+fn zero_read(_file: *mut u8, buf: *mut u8, count: usize, _pos: &mut usize) -> isize {
     if count == 0 {
         return 0;
     }
@@ -18,7 +18,7 @@ fn zero_read(_file: *mut u8, buf: *mut u8, count: usize) -> isize {
     count as isize
 }
 
-fn zero_write(_file: *mut u8, _buf: *const u8, count: usize) -> isize {
+fn zero_write(_file: *mut u8, _buf: *const u8, count: usize, _pos: &mut usize) -> isize {
     count as isize
 }
 
@@ -26,8 +26,11 @@ pub const ZERO_FOPS: FileOps = FileOps {
     read: zero_read,
     write: zero_write,
     release: noop_close,
-    llseek: noop_seek,
+    size: None,
+    mode: S_IFCHR | 0o666,
     ioctl: noop_ioctl,
+    read_iter: None,
+    write_iter: None,
 };
 
 pub fn zero_factory() -> FdEntry {
@@ -44,7 +47,7 @@ mod tests {
     #[test]
     fn test_zero_read() {
         let mut buf = [0xFFu8; 64];
-        let result = zero_read(null_mut(), buf.as_mut_ptr(), buf.len());
+        let result = zero_read(null_mut(), buf.as_mut_ptr(), buf.len(), &mut 0);
         assert_eq!(result, 64, "/dev/zero read should succeed");
         assert!(buf.iter().all(|&b| b == 0), "Buffer should be all zeros");
     }
@@ -52,7 +55,7 @@ mod tests {
     #[test]
     fn test_zero_write() {
         let buf = [0u8; 64];
-        let result = zero_write(null_mut(), buf.as_ptr(), buf.len());
+        let result = zero_write(null_mut(), buf.as_ptr(), buf.len(), &mut 0);
         assert_eq!(result, 64, "/dev/zero write should succeed");
     }
 }
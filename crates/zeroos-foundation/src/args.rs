@@ -0,0 +1,39 @@
+//! Command-line arguments injected by the platform.
+//!
+//! The platform hands [`init`] the whole `argv`, program name first, as a blob of strings that
+//! each end in a NUL byte (the layout of `/proc/self/cmdline`), once at boot. As with
+//! [`env`](crate::env), the entries keep their NUL so the runtime can point `argv` at them, and
+//! no_std guests read the same list through [`args`].
+//!
+//! A last entry missing its NUL is dropped, and at most [`MAX_ARGS`] are kept.
+
+use crate::utils::{KOnce, NulStrings};
+
+/// Arguments kept from the blob; the rest are dropped.
+pub const MAX_ARGS: usize = 64;
+
+static ARGS: KOnce<NulStrings<MAX_ARGS>> = KOnce::new();
+
+/// Parse `blob` into the arguments and return how many there are. Only the first call has an
+/// effect.
+pub fn init(blob: &'static [u8]) -> usize {
+    ARGS.call_once(|| NulStrings::parse(blob, |_| true))
+        .as_slice()
+        .len()
+}
+
+/// The arguments, each ending in NUL; empty before [`init`] or if the host passed none.
+pub fn args() -> &'static [&'static [u8]] {
+    ARGS.get().map_or(&[], NulStrings::as_slice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_keep_empty_entries() {
+        assert_eq!(init(b"prog\0\0--n=3\0"), 3);
+        assert_eq!(args(), [b"prog\0" as &[u8], b"\0", b"--n=3\0"]);
+    }
+}
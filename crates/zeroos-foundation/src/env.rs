@@ -0,0 +1,59 @@
+//! Environment variables injected by the platform.
+//!
+//! The platform hands [`init`] a blob of `KEY=VALUE` entries, each ending in a NUL byte (the
+//! layout of `/proc/self/environ`), once at boot. The entries are referenced, not copied: each
+//! slice [`vars`] returns still ends in its NUL, so the runtime can point `envp` straight at
+//! them and libc's `getenv` (and with it `std::env::var`) sees the same variables as [`get`].
+//!
+//! Entries without `=` or with an empty key are skipped, as is a last entry missing its NUL.
+//! At most [`MAX_VARS`] are kept. When a key repeats, [`get`] returns the first, as musl does.
+
+use crate::utils::{KOnce, NulStrings};
+
+/// Variables kept from the blob; the rest are dropped.
+pub const MAX_VARS: usize = 64;
+
+static ENV: KOnce<NulStrings<MAX_VARS>> = KOnce::new();
+
+/// Parse `blob` into the environment and return how many variables it holds. Only the first
+/// call has an effect.
+pub fn init(blob: &'static [u8]) -> usize {
+    ENV.call_once(|| NulStrings::parse(blob, is_var))
+        .as_slice()
+        .len()
+}
+
+fn is_var(entry: &[u8]) -> bool {
+    matches!(entry.iter().position(|&b| b == b'='), Some(eq) if eq > 0)
+}
+
+/// The `KEY=VALUE` entries, each ending in NUL; empty before [`init`].
+pub fn vars() -> &'static [&'static [u8]] {
+    ENV.get().map_or(&[], NulStrings::as_slice)
+}
+
+/// Value of `key`, without its NUL.
+pub fn get(key: &str) -> Option<&'static [u8]> {
+    vars().iter().find_map(|entry| {
+        let entry = &entry[..entry.len() - 1];
+        entry
+            .strip_prefix(key.as_bytes())
+            .and_then(|rest| rest.strip_prefix(b"="))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_returns_the_first_match() {
+        assert_eq!(init(b"PATH=/bin\0=bad\0noeq\0PA=x\0PATH=/usr/bin\0"), 3);
+        assert_eq!(init(b"IGNORED=1\0"), 3);
+        assert_eq!(get("PATH"), Some(&b"/bin"[..]));
+        assert_eq!(get("PA"), Some(&b"x"[..]));
+        assert_eq!(get("P"), None);
+        assert_eq!(get("IGNORED"), None);
+        assert_eq!(vars()[1], b"PA=x\0");
+    }
+}
@@ -0,0 +1,218 @@
+//! Kernel error codes.
+//!
+//! Ops tables keep the raw ABI: 0 or a value on success, a negative errno on failure. The `kfn`
+//! wrappers decode that into [`KResult`] with [`KError::from_ret`], and syscall handlers encode it
+//! back with [`IntoRet::into_ret`], so the sign convention is handled here and nowhere else.
+
+/// An errno, with names for the codes ZeroOS itself returns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KError {
+    Perm,
+    NoEnt,
+    Srch,
+    Intr,
+    Io,
+    BadF,
+    Child,
+    Again,
+    NoMem,
+    Access,
+    Fault,
+    Busy,
+    Exist,
+    NotDir,
+    IsDir,
+    Inval,
+    MFile,
+    NoTty,
+    NoSpc,
+    SPipe,
+    Range,
+    NameTooLong,
+    NoSys,
+    TimedOut,
+    /// Any errno without a name above.
+    Other(i32),
+}
+
+/// Linux (asm-generic) errno values; `libc` has none for bare-metal targets.
+pub mod errno {
+    pub const EPERM: i32 = 1;
+    pub const ENOENT: i32 = 2;
+    pub const ESRCH: i32 = 3;
+    pub const EINTR: i32 = 4;
+    pub const EIO: i32 = 5;
+    pub const EBADF: i32 = 9;
+    pub const ECHILD: i32 = 10;
+    pub const EAGAIN: i32 = 11;
+    pub const ENOMEM: i32 = 12;
+    pub const EACCES: i32 = 13;
+    pub const EFAULT: i32 = 14;
+    pub const EBUSY: i32 = 16;
+    pub const EEXIST: i32 = 17;
+    pub const ENOTDIR: i32 = 20;
+    pub const EISDIR: i32 = 21;
+    pub const EINVAL: i32 = 22;
+    pub const EMFILE: i32 = 24;
+    pub const ENOTTY: i32 = 25;
+    pub const ENOSPC: i32 = 28;
+    pub const ESPIPE: i32 = 29;
+    pub const ERANGE: i32 = 34;
+    pub const EDEADLK: i32 = 35;
+    pub const ENAMETOOLONG: i32 = 36;
+    pub const ENOSYS: i32 = 38;
+    pub const ETIMEDOUT: i32 = 110;
+}
+
+pub type KResult<T = ()> = Result<T, KError>;
+
+/// Largest errno a negative return can carry; anything further below 0 is a value (Linux uses the
+/// same cut-off, so addresses in the upper half survive on 32-bit targets).
+pub const MAX_ERRNO: usize = 4095;
+
+impl KError {
+    /// The error for positive `errno`.
+    pub const fn from_errno(errno: i32) -> Self {
+        match errno {
+            errno::EPERM => Self::Perm,
+            errno::ENOENT => Self::NoEnt,
+            errno::ESRCH => Self::Srch,
+            errno::EINTR => Self::Intr,
+            errno::EIO => Self::Io,
+            errno::EBADF => Self::BadF,
+            errno::ECHILD => Self::Child,
+            errno::EAGAIN => Self::Again,
+            errno::ENOMEM => Self::NoMem,
+            errno::EACCES => Self::Access,
+            errno::EFAULT => Self::Fault,
+            errno::EBUSY => Self::Busy,
+            errno::EEXIST => Self::Exist,
+            errno::ENOTDIR => Self::NotDir,
+            errno::EISDIR => Self::IsDir,
+            errno::EINVAL => Self::Inval,
+            errno::EMFILE => Self::MFile,
+            errno::ENOTTY => Self::NoTty,
+            errno::ENOSPC => Self::NoSpc,
+            errno::ESPIPE => Self::SPipe,
+            errno::ERANGE => Self::Range,
+            errno::ENAMETOOLONG => Self::NameTooLong,
+            errno::ENOSYS => Self::NoSys,
+            errno::ETIMEDOUT => Self::TimedOut,
+            other => Self::Other(other),
+        }
+    }
+
+    /// Positive errno.
+    pub const fn errno(self) -> i32 {
+        match self {
+            Self::Perm => errno::EPERM,
+            Self::NoEnt => errno::ENOENT,
+            Self::Srch => errno::ESRCH,
+            Self::Intr => errno::EINTR,
+            Self::Io => errno::EIO,
+            Self::BadF => errno::EBADF,
+            Self::Child => errno::ECHILD,
+            Self::Again => errno::EAGAIN,
+            Self::NoMem => errno::ENOMEM,
+            Self::Access => errno::EACCES,
+            Self::Fault => errno::EFAULT,
+            Self::Busy => errno::EBUSY,
+            Self::Exist => errno::EEXIST,
+            Self::NotDir => errno::ENOTDIR,
+            Self::IsDir => errno::EISDIR,
+            Self::Inval => errno::EINVAL,
+            Self::MFile => errno::EMFILE,
+            Self::NoTty => errno::ENOTTY,
+            Self::NoSpc => errno::ENOSPC,
+            Self::SPipe => errno::ESPIPE,
+            Self::Range => errno::ERANGE,
+            Self::NameTooLong => errno::ENAMETOOLONG,
+            Self::NoSys => errno::ENOSYS,
+            Self::TimedOut => errno::ETIMEDOUT,
+            Self::Other(errno) => errno,
+        }
+    }
+
+    /// The negative-errno return value for this error.
+    #[inline]
+    pub const fn to_ret(self) -> isize {
+        -(self.errno() as isize)
+    }
+
+    /// Decode a raw ops return value.
+    #[inline]
+    pub const fn from_ret(ret: isize) -> KResult<usize> {
+        if ret < 0 && ret.unsigned_abs() <= MAX_ERRNO {
+            Err(Self::from_errno(ret.unsigned_abs() as i32))
+        } else {
+            Ok(ret as usize)
+        }
+    }
+}
+
+impl From<KError> for isize {
+    #[inline]
+    fn from(e: KError) -> isize {
+        e.to_ret()
+    }
+}
+
+/// Encoding of a kernel result as a syscall return value.
+pub trait IntoRet {
+    fn into_ret(self) -> isize;
+}
+
+impl IntoRet for KResult<usize> {
+    #[inline]
+    fn into_ret(self) -> isize {
+        match self {
+            Ok(v) => v as isize,
+            Err(e) => e.to_ret(),
+        }
+    }
+}
+
+impl IntoRet for KResult<()> {
+    #[inline]
+    fn into_ret(self) -> isize {
+        match self {
+            Ok(()) => 0,
+            Err(e) => e.to_ret(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errno_matches_libc() {
+        assert_eq!(errno::ENOENT, libc::ENOENT);
+        assert_eq!(errno::EAGAIN, libc::EAGAIN);
+        assert_eq!(errno::ENAMETOOLONG, libc::ENAMETOOLONG);
+        assert_eq!(errno::ENOSYS, libc::ENOSYS);
+        assert_eq!(errno::ETIMEDOUT, libc::ETIMEDOUT);
+    }
+
+    #[test]
+    fn test_errno_round_trip() {
+        for errno in 1..=200 {
+            assert_eq!(KError::from_errno(errno).errno(), errno);
+        }
+        assert_eq!(KError::from_errno(libc::ENOENT), KError::NoEnt);
+        assert_eq!(KError::from_errno(18), KError::Other(18));
+    }
+
+    #[test]
+    fn test_ret_conversions() {
+        assert_eq!(KError::from_ret(3), Ok(3));
+        assert_eq!(KError::from_ret(-(libc::EBADF as isize)), Err(KError::BadF));
+        // Below -MAX_ERRNO is a value, e.g. a high address on a 32-bit target.
+        assert_eq!(KError::from_ret(-4096), Ok(-4096isize as usize));
+
+        assert_eq!(Ok::<usize, KError>(7).into_ret(), 7);
+        assert_eq!(Err::<(), _>(KError::NoSys).into_ret(), -38);
+        assert_eq!(isize::from(KError::Inval), -(libc::EINVAL as isize));
+    }
+}
@@ -0,0 +1,238 @@
+//! Event journal: record nondeterministic inputs on one run, replay them on the next.
+//!
+//! When recording, every journaled read (random bytes, stdin, clock) appends its result to a
+//! buffer. When replaying, the same reads are answered from a previously recorded buffer instead
+//! of the source, so a run can be reproduced byte for byte, e.g. to compare a native execution
+//! with the one being proven.
+//!
+//! An entry is the event kind (one byte), the source's return value (`i64`) and the length of
+//! the data it produced (`u32`, both little-endian), followed by the data. A replayed read that
+//! does not match the next entry means the two runs diverged; that panics with the offset.
+
+use crate::utils::GlobalCell;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    Random = 1,
+    Input = 2,
+    Clock = 3,
+}
+
+impl EventKind {
+    fn from_u8(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(Self::Random),
+            2 => Some(Self::Input),
+            3 => Some(Self::Clock),
+            _ => None,
+        }
+    }
+}
+
+/// Kind, return value and data length that precede each entry's data.
+pub const ENTRY_HEADER_LEN: usize = 13;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Off,
+    Record,
+    Replay,
+}
+
+enum State {
+    Off,
+    Record {
+        buf: &'static mut [u8],
+        len: usize,
+        /// An entry did not fit; everything from it on is missing.
+        truncated: bool,
+    },
+    Replay {
+        buf: &'static [u8],
+        pos: usize,
+    },
+}
+
+static JOURNAL: GlobalCell<State> = GlobalCell::new(State::Off);
+
+/// Record journaled events into `buf` from now on.
+pub fn start_recording(buf: &'static mut [u8]) {
+    JOURNAL.with_mut(|state| {
+        *state = State::Record {
+            buf,
+            len: 0,
+            truncated: false,
+        }
+    });
+}
+
+/// Answer journaled events from `journal`, as produced by a recording run.
+pub fn start_replay(journal: &'static [u8]) {
+    JOURNAL.with_mut(|state| {
+        *state = State::Replay {
+            buf: journal,
+            pos: 0,
+        }
+    });
+}
+
+pub fn stop() {
+    JOURNAL.with_mut(|state| *state = State::Off);
+}
+
+pub fn mode() -> Mode {
+    JOURNAL.with(|state| match state {
+        State::Off => Mode::Off,
+        State::Record { .. } => Mode::Record,
+        State::Replay { .. } => Mode::Replay,
+    })
+}
+
+/// Run `f` on the journal recorded so far; empty unless recording. `truncated` is set if the
+/// buffer filled up and later events were dropped.
+pub fn with_recorded<R>(f: impl FnOnce(&[u8], bool) -> R) -> R {
+    JOURNAL.with(|state| match state {
+        State::Record {
+            buf,
+            len,
+            truncated,
+        } => f(&buf[..*len], *truncated),
+        _ => f(&[], false),
+    })
+}
+
+/// Read an event through the journal.
+///
+/// `source` fills `out` and returns its result: a byte count, a value (for clocks, with an empty
+/// `out`), or a negative errno. When replaying, `source` is not called and the recorded result
+/// and bytes are returned instead.
+pub fn journaled(kind: EventKind, out: &mut [u8], source: impl FnOnce(&mut [u8]) -> i64) -> i64 {
+    JOURNAL.with_mut(|state| match state {
+        State::Off => source(out),
+        State::Record {
+            buf,
+            len,
+            truncated,
+        } => {
+            let ret = source(out);
+            if !*truncated && !append(buf, len, kind, ret, out) {
+                *truncated = true;
+            }
+            ret
+        }
+        State::Replay { buf, pos } => replay(buf, pos, kind, out),
+    })
+}
+
+/// Bytes of `out` a source returning `ret` filled: clocks pass an empty `out`.
+fn data_len(ret: i64, out: &[u8]) -> usize {
+    if ret > 0 {
+        (ret as u64).min(out.len() as u64) as usize
+    } else {
+        0
+    }
+}
+
+fn append(buf: &mut [u8], len: &mut usize, kind: EventKind, ret: i64, out: &[u8]) -> bool {
+    let data = &out[..data_len(ret, out)];
+    let end = *len + ENTRY_HEADER_LEN + data.len();
+    if end > buf.len() {
+        return false;
+    }
+    let entry = &mut buf[*len..end];
+    entry[0] = kind as u8;
+    entry[1..9].copy_from_slice(&ret.to_le_bytes());
+    entry[9..ENTRY_HEADER_LEN].copy_from_slice(&(data.len() as u32).to_le_bytes());
+    entry[ENTRY_HEADER_LEN..].copy_from_slice(data);
+    *len = end;
+    true
+}
+
+fn replay(buf: &[u8], pos: &mut usize, kind: EventKind, out: &mut [u8]) -> i64 {
+    let at = *pos;
+    let Some(header) = buf.get(at..at + ENTRY_HEADER_LEN) else {
+        panic!(
+            "journal divergence at offset {}: replay ran past the end",
+            at
+        );
+    };
+    let found = EventKind::from_u8(header[0]);
+    if found != Some(kind) {
+        panic!(
+            "journal divergence at offset {}: expected {:?}, found {:?}",
+            at, kind, found
+        );
+    }
+    let mut ret = [0u8; 8];
+    ret.copy_from_slice(&header[1..9]);
+    let ret = i64::from_le_bytes(ret);
+    let mut n = [0u8; 4];
+    n.copy_from_slice(&header[9..]);
+    let n = u32::from_le_bytes(n) as usize;
+
+    if n > out.len() {
+        panic!(
+            "journal divergence at offset {}: {:?} of {} bytes into a {}-byte buffer",
+            at,
+            kind,
+            n,
+            out.len()
+        );
+    }
+    let start = at + ENTRY_HEADER_LEN;
+    let Some(data) = buf.get(start..start + n) else {
+        panic!("journal divergence at offset {}: entry is truncated", at);
+    };
+    out[..n].copy_from_slice(data);
+    *pos = start + n;
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_then_replay() {
+        let mut buf = [0u8; 64];
+        let mut len = 0;
+        assert!(append(
+            &mut buf,
+            &mut len,
+            EventKind::Random,
+            4,
+            &[1, 2, 3, 4, 5]
+        ));
+        assert!(append(&mut buf, &mut len, EventKind::Clock, 1_000, &[]));
+        assert!(append(&mut buf, &mut len, EventKind::Input, -11, &[9; 8]));
+        assert_eq!(len, 3 * ENTRY_HEADER_LEN + 4);
+        assert!(!append(&mut buf, &mut len, EventKind::Input, 60, &[0; 60]));
+
+        let mut pos = 0;
+        let mut out = [0u8; 8];
+        assert_eq!(
+            replay(&buf[..len], &mut pos, EventKind::Random, &mut out),
+            4
+        );
+        assert_eq!(out[..4], [1, 2, 3, 4]);
+        assert_eq!(
+            replay(&buf[..len], &mut pos, EventKind::Clock, &mut []),
+            1_000
+        );
+        assert_eq!(
+            replay(&buf[..len], &mut pos, EventKind::Input, &mut out),
+            -11
+        );
+        assert_eq!(pos, len);
+    }
+
+    #[test]
+    #[should_panic(expected = "journal divergence at offset 0: expected Clock, found Some(Random)")]
+    fn test_replay_divergence() {
+        let mut buf = [0u8; 16];
+        let mut len = 0;
+        assert!(append(&mut buf, &mut len, EventKind::Random, 1, &[7]));
+        replay(&buf[..len], &mut 0, EventKind::Clock, &mut []);
+    }
+}
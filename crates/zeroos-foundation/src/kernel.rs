@@ -8,7 +8,7 @@ pub struct Kernel {
     #[cfg(feature = "memory")]
     pub(crate) memory: ops::MemoryOps,
     #[cfg(feature = "scheduler")]
-    pub(crate) scheduler: ops::SchedulerOps,
+    pub(crate) scheduler: &'static dyn ops::SchedulerBackend,
     #[cfg(feature = "trap")]
     pub(crate) trap: ops::TrapOps,
     #[cfg(feature = "vfs")]
@@ -17,6 +17,8 @@ pub struct Kernel {
     pub(crate) random: ops::RandomOps,
     #[cfg(feature = "arch")]
     pub(crate) arch: ops::ArchOps,
+    #[cfg(feature = "time")]
+    pub(crate) time: ops::TimeOps,
 }
 
 pub struct GlobalKernel(MaybeUninit<Kernel>);
@@ -51,10 +53,22 @@ pub fn register_memory(ops: ops::MemoryOps) {
     }
 }
 
+/// Home of the table passed to `register_scheduler`, which the kernel borrows for good.
+#[cfg(feature = "scheduler")]
+static mut SCHEDULER_OPS: MaybeUninit<ops::SchedulerOps> = MaybeUninit::uninit();
+
 #[cfg(feature = "scheduler")]
 pub fn register_scheduler(ops: ops::SchedulerOps) {
     unsafe {
-        KERNEL.scheduler = ops;
+        let table = &mut *core::ptr::addr_of_mut!(SCHEDULER_OPS);
+        register_scheduler_backend(table.write(ops));
+    }
+}
+
+#[cfg(feature = "scheduler")]
+pub fn register_scheduler_backend(backend: &'static dyn ops::SchedulerBackend) {
+    unsafe {
+        KERNEL.scheduler = backend;
     }
 }
 
@@ -86,6 +100,13 @@ pub fn register_arch(ops: ops::ArchOps) {
     }
 }
 
+#[cfg(feature = "time")]
+pub fn register_time(ops: ops::TimeOps) {
+    unsafe {
+        KERNEL.time = ops;
+    }
+}
+
 /// Initialize the kernel subsystems.
 pub fn init(heap_start: usize, heap_size: usize) {
     crate::kfn::memory::kinit(heap_start, heap_size);
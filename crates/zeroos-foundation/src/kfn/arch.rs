@@ -126,6 +126,33 @@ cfg_if! {
             (crate::KERNEL.arch.trap_frame_set_tp)(regs, tp)
         }
 
+        /// Set the return address in a trap frame.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame.
+        #[inline]
+        pub unsafe fn ktrap_frame_set_ra(regs: *mut u8, ra: usize) {
+            (crate::KERNEL.arch.trap_frame_set_ra)(regs, ra)
+        }
+
+        /// Set an argument register (0-5) in a trap frame.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame.
+        #[inline]
+        pub unsafe fn ktrap_frame_set_arg(regs: *mut u8, idx: usize, val: usize) {
+            (crate::KERNEL.arch.trap_frame_set_arg)(regs, idx, val)
+        }
+
+        /// Get the stack pointer from a trap frame.
+        ///
+        /// # Safety
+        /// `regs` must point to a valid trap frame.
+        #[inline]
+        pub unsafe fn ktrap_frame_get_sp(regs: *const u8) -> usize {
+            (crate::KERNEL.arch.trap_frame_get_sp)(regs)
+        }
+
         #[inline]
         pub fn kcurrent_trap_frame() -> *mut u8 {
             unsafe { (crate::KERNEL.arch.current_trap_frame)() }
@@ -303,6 +330,32 @@ cfg_if! {
         #[allow(dead_code)]
         pub unsafe fn ktrap_frame_set_tp(_regs: *mut u8, _tp: usize) {}
 
+        /// Stub implementation of `ktrap_frame_set_ra`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn ktrap_frame_set_ra(_regs: *mut u8, _ra: usize) {}
+
+        /// Stub implementation of `ktrap_frame_set_arg`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn ktrap_frame_set_arg(_regs: *mut u8, _idx: usize, _val: usize) {}
+
+        /// Stub implementation of `ktrap_frame_get_sp`.
+        ///
+        /// # Safety
+        /// This is a stub and does nothing.
+        #[inline]
+        #[allow(dead_code)]
+        pub unsafe fn ktrap_frame_get_sp(_regs: *const u8) -> usize {
+            0
+        }
+
         #[inline]
         #[allow(dead_code)]
         pub fn kcurrent_trap_frame() -> *mut u8 {
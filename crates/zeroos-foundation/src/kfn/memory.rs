@@ -5,25 +5,702 @@ use cfg_if::cfg_if;
 
 cfg_if! {
     if #[cfg(feature = "memory")] {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::ops::HeapStats;
+
+        static HEAP_TOTAL: AtomicUsize = AtomicUsize::new(0);
+        /// Bytes callers asked for in blocks not yet freed, kept from the layouts alone so the
+        /// allocation paths never have to ask the allocator how full it is.
+        static HEAP_LIVE: AtomicUsize = AtomicUsize::new(0);
+        /// Highest `HEAP_LIVE` seen.
+        static HEAP_PEAK: AtomicUsize = AtomicUsize::new(0);
+
+        #[inline]
+        fn note_alloc(size: usize) {
+            let live = HEAP_LIVE.fetch_add(size, Ordering::Relaxed) + size;
+            HEAP_PEAK.fetch_max(live, Ordering::Relaxed);
+        }
+
+        #[inline]
+        fn note_free(size: usize) {
+            let _ = HEAP_LIVE.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |live| {
+                Some(live.saturating_sub(size))
+            });
+        }
+
+        /// Free a block; the caller holds the allocator lock.
+        fn dealloc(ptr: *mut u8, layout: Layout) {
+            canary::dealloc(ptr, layout);
+            note_free(layout.size());
+        }
+
         #[inline]
         pub fn kmalloc(layout: Layout) -> *mut u8 {
-            unsafe { (crate::KERNEL.memory.alloc)(layout) }
+            let Some(_guard) = lock::acquire() else {
+                return ptr::null_mut();
+            };
+            let mut ptr = canary::alloc(layout);
+            if ptr.is_null() && grow(layout.size().saturating_add(layout.align())) {
+                ptr = canary::alloc(layout);
+            }
+            if !ptr.is_null() {
+                note_alloc(layout.size());
+            }
+            ptr
         }
 
         #[inline]
         pub fn kfree(ptr: *mut u8, layout: Layout) {
-            unsafe { (crate::KERNEL.memory.dealloc)(ptr, layout) }
+            match lock::acquire() {
+                Some(_guard) => dealloc(ptr, layout),
+                None => lock::defer_free(ptr, layout),
+            }
         }
 
         #[inline]
         pub fn krealloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
-            unsafe { (crate::KERNEL.memory.realloc)(ptr, old_layout, new_size) }
+            let Some(_guard) = lock::acquire() else {
+                return ptr::null_mut();
+            };
+            let mut new_ptr = canary::realloc(ptr, old_layout, new_size);
+            if new_ptr.is_null()
+                && new_size != 0
+                && grow(new_size.saturating_add(old_layout.align()))
+            {
+                new_ptr = canary::realloc(ptr, old_layout, new_size);
+            }
+            if !new_ptr.is_null() {
+                note_free(old_layout.size());
+                note_alloc(new_size);
+            }
+            new_ptr
+        }
+
+        /// Ask the platform for room for `min_bytes` more and hand it to the allocator.
+        ///
+        /// Called with the allocator lock held, after an allocation failed.
+        fn grow(min_bytes: usize) -> bool {
+            let wanted = min_bytes.saturating_add(guard::SIZE);
+            let Some((start, len)) = expand::platform_expand_heap(wanted) else {
+                return false;
+            };
+            regions::claim(start, len);
+            let (start, len) = guard::regrow(start, len);
+            if !unsafe { (crate::KERNEL.memory.grow)(start, len) } {
+                return false;
+            }
+            HEAP_TOTAL.fetch_add(len, Ordering::Relaxed);
+            true
+        }
+
+        #[cfg(feature = "heap-expand")]
+        mod expand {
+            extern "C" {
+                /// Find at least `min_bytes` of unused memory for the heap and store its range
+                /// in `start`/`len`; false if there is none.
+                fn __platform_expand_heap(min_bytes: usize, start: *mut usize, len: *mut usize)
+                    -> bool;
+            }
+
+            pub fn platform_expand_heap(min_bytes: usize) -> Option<(usize, usize)> {
+                let (mut start, mut len) = (0, 0);
+                // SAFETY: the platform only writes the two out-parameters.
+                let granted = unsafe { __platform_expand_heap(min_bytes, &mut start, &mut len) };
+                (granted && len >= min_bytes).then_some((start, len))
+            }
+        }
+
+        #[cfg(not(feature = "heap-expand"))]
+        mod expand {
+            #[inline(always)]
+            pub fn platform_expand_heap(_min_bytes: usize) -> Option<(usize, usize)> {
+                None
+            }
+        }
+
+        /// Run `f` with allocations that never wait for the allocator lock.
+        ///
+        /// Allocator calls are serialized by a lock that parks contending threads on the
+        /// scheduler. Code that must not yield (the scheduler while it edits its own state, or
+        /// a trap taken while a preempted thread holds the lock) wraps its allocations in this
+        /// instead: if the lock is held, `kmalloc` and `krealloc` return null and `kfree` is
+        /// deferred until the holder releases it.
+        pub fn kalloc_in_critical_section<R>(f: impl FnOnce() -> R) -> R {
+            lock::CRITICAL.fetch_add(1, Ordering::Relaxed);
+            let result = f();
+            lock::CRITICAL.fetch_sub(1, Ordering::Relaxed);
+            result
+        }
+
+        /// Whether a thread holds the allocator lock, so that frees from a critical section
+        /// would be deferred rather than applied.
+        pub fn kalloc_lock_held() -> bool {
+            lock::held()
         }
 
         #[inline]
         pub fn kinit(heap_start: usize, heap_size: usize) {
+            regions::claim_initial(heap_start, heap_size);
+            let heap_size = guard::carve(heap_start, heap_size);
+            HEAP_TOTAL.store(heap_size, Ordering::Relaxed);
+            HEAP_LIVE.store(0, Ordering::Relaxed);
+            HEAP_PEAK.store(0, Ordering::Relaxed);
             unsafe { (crate::KERNEL.memory.init)(heap_start, heap_size) }
         }
+
+        /// Current heap usage, for detecting impending OOM before allocations start failing.
+        pub fn kheap_stats() -> HeapStats {
+            let total = HEAP_TOTAL.load(Ordering::Relaxed);
+            let used = unsafe { (crate::KERNEL.memory.used)() };
+            HeapStats {
+                total,
+                used,
+                remaining: total.saturating_sub(used),
+                peak: HEAP_PEAK.load(Ordering::Relaxed).max(used),
+            }
+        }
+
+        /// Whether the red zone above the heap still holds its canary pattern.
+        ///
+        /// A clobbered red zone means a write ran past the last heap block or the stack grew
+        /// down through its guard gap. Always true without the `heap-guard` feature.
+        pub fn kheap_guard_intact() -> bool {
+            guard::intact()
+        }
+
+        /// Whether every live block still has both canary words.
+        ///
+        /// Blocks are also checked one at a time when they are freed or reallocated, which
+        /// panics on a clobbered one. Always true without the `heap-canary` feature, and while
+        /// another thread holds the allocator lock.
+        pub fn kheap_canaries_intact() -> bool {
+            kalloc_in_critical_section(|| match lock::acquire() {
+                Some(_guard) => canary::all_intact(),
+                None => true,
+            })
+        }
+
+        mod lock {
+            use core::alloc::Layout;
+            use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
+            const UNLOCKED: i32 = 0;
+            const LOCKED: i32 = 1;
+            /// Locked, and a thread may be parked on the lock word.
+            const CONTENDED: i32 = 2;
+
+            static STATE: AtomicI32 = AtomicI32::new(UNLOCKED);
+
+            /// Nesting depth of `kalloc_in_critical_section`.
+            pub static CRITICAL: AtomicUsize = AtomicUsize::new(0);
+
+            /// The held allocator lock; applies deferred frees and unlocks on drop.
+            pub struct Guard(());
+
+            impl Drop for Guard {
+                fn drop(&mut self) {
+                    deferred::drain();
+                    if STATE.swap(UNLOCKED, Ordering::Release) == CONTENDED {
+                        wake();
+                    }
+                }
+            }
+
+            /// Take the lock, parking until it is free; `None` if that would mean waiting
+            /// inside a critical section.
+            pub fn acquire() -> Option<Guard> {
+                if STATE
+                    .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    return Some(Guard(()));
+                }
+                if CRITICAL.load(Ordering::Relaxed) > 0 {
+                    return None;
+                }
+                while STATE.swap(CONTENDED, Ordering::Acquire) != UNLOCKED {
+                    park();
+                }
+                Some(Guard(()))
+            }
+
+            pub fn held() -> bool {
+                STATE.load(Ordering::Relaxed) != UNLOCKED
+            }
+
+            pub fn defer_free(ptr: *mut u8, layout: Layout) {
+                deferred::push(ptr, layout);
+            }
+
+            fn park() {
+                // Only fails if the word already changed or no other thread could release
+                // the lock (another hart still can), so spin once and retry.
+                #[cfg(feature = "scheduler")]
+                if crate::kfn::scheduler::kwait_on_addr(STATE.as_ptr() as usize, CONTENDED)
+                    .is_ok()
+                {
+                    return;
+                }
+                core::hint::spin_loop();
+            }
+
+            fn wake() {
+                #[cfg(feature = "scheduler")]
+                crate::kfn::scheduler::kwake_on_addr(STATE.as_ptr() as usize, 1);
+            }
+
+            /// Frees that arrived from a critical section while the lock was held.
+            mod deferred {
+                use core::alloc::Layout;
+                use core::sync::atomic::{AtomicUsize, Ordering};
+
+                const SLOTS: usize = 16;
+                /// `Slot::ptr` while its layout is being written.
+                const CLAIMED: usize = 1;
+
+                struct Slot {
+                    ptr: AtomicUsize,
+                    size: AtomicUsize,
+                    align: AtomicUsize,
+                }
+
+                static SLOTS_USED: AtomicUsize = AtomicUsize::new(0);
+                static PENDING: [Slot; SLOTS] = [const {
+                    Slot {
+                        ptr: AtomicUsize::new(0),
+                        size: AtomicUsize::new(0),
+                        align: AtomicUsize::new(0),
+                    }
+                }; SLOTS];
+
+                pub fn push(ptr: *mut u8, layout: Layout) {
+                    for slot in &PENDING {
+                        if slot
+                            .ptr
+                            .compare_exchange(0, CLAIMED, Ordering::Acquire, Ordering::Relaxed)
+                            .is_ok()
+                        {
+                            slot.size.store(layout.size(), Ordering::Relaxed);
+                            slot.align.store(layout.align(), Ordering::Relaxed);
+                            slot.ptr.store(ptr as usize, Ordering::Release);
+                            SLOTS_USED.fetch_add(1, Ordering::Release);
+                            return;
+                        }
+                    }
+                    panic!("kfree: more than {SLOTS} frees deferred from critical sections");
+                }
+
+                /// Free every published slot; the caller holds the allocator lock.
+                pub fn drain() {
+                    if SLOTS_USED.load(Ordering::Acquire) == 0 {
+                        return;
+                    }
+                    for slot in &PENDING {
+                        let ptr = slot.ptr.load(Ordering::Acquire);
+                        if ptr <= CLAIMED {
+                            continue;
+                        }
+                        let size = slot.size.load(Ordering::Relaxed);
+                        let align = slot.align.load(Ordering::Relaxed);
+                        slot.ptr.store(0, Ordering::Release);
+                        SLOTS_USED.fetch_sub(1, Ordering::Release);
+                        // SAFETY: `push` stored the parts of a valid `Layout`; the block is
+                        // owned by the queue until now.
+                        unsafe {
+                            let layout = Layout::from_size_align_unchecked(size, align);
+                            super::super::dealloc(ptr as *mut u8, layout);
+                        }
+                    }
+                }
+            }
+        }
+
+        /// Canary words around every block, see `kheap_canaries_intact`.
+        ///
+        /// Each block carries a header in front (the live-block list, its size and the front
+        /// canary right before the data) and a canary word right after the data. Reallocation
+        /// always moves, so a block keeps one layout for its lifetime.
+        #[cfg(feature = "heap-canary")]
+        mod canary {
+            use core::alloc::Layout;
+            use core::mem::size_of;
+            use core::ptr;
+
+            use crate::utils::GlobalCell;
+
+            const CANARY: usize = 0x5afe_c0de_5afe_c0de_u64 as usize;
+            const WORD: usize = size_of::<usize>();
+            const HEADER: usize = size_of::<Header>();
+
+            #[repr(C)]
+            struct Header {
+                next: *mut Header,
+                prev: *mut Header,
+                size: usize,
+                canary: usize,
+            }
+
+            /// Live blocks, newest first; only touched with the allocator lock held.
+            static LIVE: GlobalCell<*mut Header> = GlobalCell::new(ptr::null_mut());
+
+            /// Distance from the start of the allocator's block to the data.
+            fn offset(align: usize) -> usize {
+                HEADER.next_multiple_of(align)
+            }
+
+            /// What the allocator is asked for to hold `layout` and its canaries.
+            fn outer(layout: Layout) -> Option<Layout> {
+                let size = offset(layout.align())
+                    .checked_add(layout.size())?
+                    .checked_add(WORD)?;
+                Layout::from_size_align(size, layout.align().max(WORD)).ok()
+            }
+
+            pub fn alloc(layout: Layout) -> *mut u8 {
+                let Some(outer) = outer(layout) else {
+                    return ptr::null_mut();
+                };
+                let block = unsafe { (crate::KERNEL.memory.alloc)(outer) };
+                if block.is_null() {
+                    return block;
+                }
+                // SAFETY: `outer` has room for the header in front of the data and the back
+                // canary after it, and the caller holds the allocator lock.
+                unsafe {
+                    let data = block.add(offset(layout.align()));
+                    let header = data.sub(HEADER) as *mut Header;
+                    LIVE.with_mut(|live| {
+                        header.write(Header {
+                            next: *live,
+                            prev: ptr::null_mut(),
+                            size: layout.size(),
+                            canary: CANARY,
+                        });
+                        if !live.is_null() {
+                            (**live).prev = header;
+                        }
+                        *live = header;
+                    });
+                    data.add(layout.size()).cast::<usize>().write_unaligned(CANARY);
+                    data
+                }
+            }
+
+            pub fn dealloc(data: *mut u8, layout: Layout) {
+                // SAFETY: `data` came from `alloc` with `layout`, and the caller holds the
+                // allocator lock.
+                unsafe {
+                    let header = checked(data, layout.size());
+                    LIVE.with_mut(|live| {
+                        let Header { next, prev, .. } = header.read();
+                        if prev.is_null() {
+                            *live = next;
+                        } else {
+                            (*prev).next = next;
+                        }
+                        if !next.is_null() {
+                            (*next).prev = prev;
+                        }
+                    });
+                    let block = data.sub(offset(layout.align()));
+                    (crate::KERNEL.memory.dealloc)(block, outer(layout).unwrap_unchecked());
+                }
+            }
+
+            pub fn realloc(data: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
+                // SAFETY: `data` came from `alloc` with `old_layout`.
+                unsafe { checked(data, old_layout.size()) };
+                let Ok(new_layout) = Layout::from_size_align(new_size, old_layout.align()) else {
+                    return ptr::null_mut();
+                };
+                let new_data = alloc(new_layout);
+                if !new_data.is_null() {
+                    // SAFETY: both blocks hold at least the bytes copied and do not overlap.
+                    unsafe {
+                        ptr::copy_nonoverlapping(data, new_data, old_layout.size().min(new_size))
+                    };
+                    dealloc(data, old_layout);
+                }
+                new_data
+            }
+
+            /// Whether the block at `data` has both canaries, reading the back one `size`
+            /// bytes on.
+            unsafe fn intact(data: *const u8, size: usize) -> bool {
+                let header = data.sub(HEADER) as *const Header;
+                (*header).canary == CANARY
+                    && data.add(size).cast::<usize>().read_unaligned() == CANARY
+            }
+
+            /// The header of the block at `data`; panics if the block was overrun.
+            unsafe fn checked(data: *mut u8, size: usize) -> *mut Header {
+                if !intact(data, size) || (*(data.sub(HEADER) as *const Header)).size != size {
+                    clobbered(data as usize, size);
+                }
+                data.sub(HEADER) as *mut Header
+            }
+
+            #[cold]
+            #[inline(never)]
+            fn clobbered(data: usize, size: usize) -> ! {
+                panic!("heap canary clobbered around block 0x{data:x} ({size} bytes)");
+            }
+
+            pub fn all_intact() -> bool {
+                LIVE.with(|&live| {
+                    let mut header = live;
+                    while !header.is_null() {
+                        // SAFETY: every header on the list belongs to a live block. The front
+                        // canary is checked first, so a clobbered header is never followed.
+                        unsafe {
+                            let data = (header as *const u8).add(HEADER);
+                            if (*header).canary != CANARY || !intact(data, (*header).size) {
+                                return false;
+                            }
+                            header = (*header).next;
+                        }
+                    }
+                    true
+                })
+            }
+        }
+
+        #[cfg(not(feature = "heap-canary"))]
+        mod canary {
+            use core::alloc::Layout;
+
+            #[inline(always)]
+            pub fn alloc(layout: Layout) -> *mut u8 {
+                unsafe { (crate::KERNEL.memory.alloc)(layout) }
+            }
+
+            #[inline(always)]
+            pub fn dealloc(ptr: *mut u8, layout: Layout) {
+                unsafe { (crate::KERNEL.memory.dealloc)(ptr, layout) }
+            }
+
+            #[inline(always)]
+            pub fn realloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
+                unsafe { (crate::KERNEL.memory.realloc)(ptr, old_layout, new_size) }
+            }
+
+            #[inline(always)]
+            pub fn all_intact() -> bool {
+                true
+            }
+        }
+
+        /// Ranges handed to the allocator, so a second `kinit` or a region that overlaps the
+        /// heap panics at boot instead of corrupting it later.
+        #[cfg(feature = "heap-init-check")]
+        mod regions {
+            use crate::utils::GlobalCell;
+
+            /// Regions tracked; later ones are only checked against these.
+            const MAX_REGIONS: usize = 16;
+
+            struct Regions {
+                ranges: [(usize, usize); MAX_REGIONS],
+                len: usize,
+            }
+
+            static REGIONS: GlobalCell<Regions> = GlobalCell::new(Regions {
+                ranges: [(0, 0); MAX_REGIONS],
+                len: 0,
+            });
+
+            pub fn claim_initial(start: usize, size: usize) {
+                if let Some((first, end)) = REGIONS.with(|r| r.ranges[..r.len].first().copied()) {
+                    panic!(
+                        "kinit: heap initialized twice (0x{first:x}..0x{end:x}, then 0x{start:x}..0x{:x})",
+                        start.saturating_add(size)
+                    );
+                }
+                claim(start, size);
+            }
+
+            pub fn claim(start: usize, len: usize) {
+                let end = start.saturating_add(len);
+                REGIONS.with_mut(|r| {
+                    if let Some(&(s, e)) = r.ranges[..r.len].iter().find(|&&(s, e)| start < e && s < end) {
+                        panic!("heap region 0x{start:x}..0x{end:x} overlaps the heap at 0x{s:x}..0x{e:x}");
+                    }
+                    if r.len < MAX_REGIONS {
+                        r.ranges[r.len] = (start, end);
+                        r.len += 1;
+                    }
+                });
+            }
+        }
+
+        #[cfg(not(feature = "heap-init-check"))]
+        mod regions {
+            #[inline(always)]
+            pub fn claim_initial(_start: usize, _size: usize) {}
+
+            #[inline(always)]
+            pub fn claim(_start: usize, _len: usize) {}
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            // Its fake allocator hands out addresses the canaries cannot be written to.
+            #[cfg(not(feature = "heap-canary"))]
+            #[test]
+            fn test_critical_section_never_waits() {
+                static FREED: AtomicUsize = AtomicUsize::new(0);
+
+                crate::register_memory(crate::ops::MemoryOps {
+                    init: |_, _| {},
+                    alloc: |_| 0x1000 as *mut u8,
+                    dealloc: |ptr, _| FREED.store(ptr as usize, Ordering::Relaxed),
+                    realloc: |_, _, _| ptr::null_mut(),
+                    used: || 0,
+                    grow: |_, _| false,
+                });
+                let layout = Layout::new::<u64>();
+
+                let held = lock::acquire().unwrap();
+                assert!(kalloc_lock_held());
+                kalloc_in_critical_section(|| {
+                    assert!(kmalloc(layout).is_null());
+                    kfree(0x2000 as *mut u8, layout);
+                });
+                assert_eq!(FREED.load(Ordering::Relaxed), 0);
+
+                // Releasing the lock applies the deferred free.
+                drop(held);
+                assert!(!kalloc_lock_held());
+                assert_eq!(FREED.load(Ordering::Relaxed), 0x2000);
+                assert_eq!(kmalloc(layout), 0x1000 as *mut u8);
+                // Only the allocation that went through counts towards the peak.
+                assert_eq!(kheap_stats().peak, layout.size());
+            }
+
+            #[cfg(feature = "heap-canary")]
+            #[test]
+            fn test_canaries_catch_overruns() {
+                extern crate std;
+                use std::alloc::{GlobalAlloc, System};
+
+                crate::register_memory(crate::ops::MemoryOps {
+                    init: |_, _| {},
+                    alloc: |layout| unsafe { System.alloc(layout) },
+                    dealloc: |ptr, layout| unsafe { System.dealloc(ptr, layout) },
+                    realloc: |_, _, _| ptr::null_mut(),
+                    used: || 0,
+                    grow: |_, _| false,
+                });
+                let layout = Layout::from_size_align(24, 16).unwrap();
+                let a = kmalloc(layout);
+                let b = kmalloc(Layout::new::<u8>());
+                assert_eq!(a as usize % 16, 0);
+                unsafe { ptr::write_bytes(a, 0xff, 24) };
+                let a = krealloc(a, layout, 40);
+                assert_eq!(unsafe { *a.add(23) }, 0xff);
+                assert!(kheap_canaries_intact());
+
+                // One byte past the end of `b`.
+                unsafe { *b.add(1) ^= 1 };
+                assert!(!kheap_canaries_intact());
+                let freed = std::panic::catch_unwind(|| kfree(b, Layout::new::<u8>()));
+                assert!(freed.is_err());
+                unsafe { *b.add(1) ^= 1 };
+                assert!(kheap_canaries_intact());
+
+                kfree(b, Layout::new::<u8>());
+                kfree(a, Layout::from_size_align(40, 16).unwrap());
+                assert!(kheap_canaries_intact());
+            }
+
+            #[cfg(feature = "heap-init-check")]
+            #[test]
+            fn test_overlapping_regions_panic() {
+                extern crate std;
+                use std::panic::catch_unwind;
+
+                regions::claim_initial(0x8000_0000, 0x1000);
+                regions::claim(0x8000_2000, 0x1000);
+                assert!(catch_unwind(|| regions::claim_initial(0x9000_0000, 0x1000)).is_err());
+                assert!(catch_unwind(|| regions::claim(0x8000_0800, 0x1000)).is_err());
+                assert!(catch_unwind(|| regions::claim(0x8000_1800, 0x1000)).is_err());
+                regions::claim(0x8000_1000, 0x1000);
+            }
+        }
+
+        #[cfg(feature = "heap-guard")]
+        mod guard {
+            use core::sync::atomic::{AtomicUsize, Ordering};
+
+            /// Bytes reserved at the top of the heap.
+            pub const SIZE: usize = 4096;
+            const PATTERN: u8 = 0xa5;
+
+            static START: AtomicUsize = AtomicUsize::new(0);
+
+            /// Reserve the red zone at the top of `[start, start + size)`; returns what is left
+            /// for the allocator.
+            pub fn carve(start: usize, size: usize) -> usize {
+                if size <= SIZE {
+                    return size;
+                }
+                let usable = size - SIZE;
+                // SAFETY: the red zone lies inside the heap region the platform handed us, which
+                // nothing else uses.
+                unsafe { core::ptr::write_bytes((start + usable) as *mut u8, PATTERN, SIZE) };
+                START.store(start + usable, Ordering::Relaxed);
+                usable
+            }
+
+            /// Move the red zone to the top of a region that continues the heap, so the
+            /// allocator sees one contiguous range; other regions stay unguarded.
+            pub fn regrow(start: usize, len: usize) -> (usize, usize) {
+                let zone = START.load(Ordering::Relaxed);
+                if zone == 0 || start != zone + SIZE || len < SIZE {
+                    return (start, len);
+                }
+                let new_zone = start + len - SIZE;
+                // SAFETY: the platform handed us `[start, start + len)` for the heap.
+                unsafe { core::ptr::write_bytes(new_zone as *mut u8, PATTERN, SIZE) };
+                START.store(new_zone, Ordering::Relaxed);
+                // The old zone joins the heap in front of the new region.
+                (zone, len)
+            }
+
+            pub fn intact() -> bool {
+                let start = START.load(Ordering::Relaxed);
+                if start == 0 {
+                    return true;
+                }
+                // SAFETY: `carve` reserved and filled this range; the allocator never hands it
+                // out.
+                let zone = unsafe { core::slice::from_raw_parts(start as *const u8, SIZE) };
+                zone.iter().all(|&b| b == PATTERN)
+            }
+        }
+
+        #[cfg(not(feature = "heap-guard"))]
+        mod guard {
+            pub const SIZE: usize = 0;
+
+            #[inline(always)]
+            pub fn regrow(start: usize, len: usize) -> (usize, usize) {
+                (start, len)
+            }
+
+            #[inline(always)]
+            pub fn carve(_start: usize, size: usize) -> usize {
+                size
+            }
+
+            #[inline(always)]
+            pub fn intact() -> bool {
+                true
+            }
+        }
     } else {
         #[inline]
         #[allow(dead_code)]
@@ -44,6 +721,30 @@ cfg_if! {
         #[inline]
         #[allow(dead_code)]
         pub fn kinit(_heap_start: usize, _heap_size: usize) {}
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kalloc_in_critical_section<R>(f: impl FnOnce() -> R) -> R {
+            f()
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kheap_stats() -> crate::ops::HeapStats {
+            crate::ops::HeapStats::default()
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kheap_guard_intact() -> bool {
+            true
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kheap_canaries_intact() -> bool {
+            true
+        }
     }
 }
 
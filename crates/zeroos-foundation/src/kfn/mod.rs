@@ -58,3 +58,11 @@ cfg_if! {
         pub(crate) mod trap;
     }
 }
+
+cfg_if! {
+    if #[cfg(feature = "time")] {
+        pub mod time;
+    } else {
+        pub(crate) mod time;
+    }
+}
@@ -11,7 +11,23 @@ cfg_if! {
         /// # Safety
         /// `buf` must be valid for writes of `len` bytes.
         pub unsafe fn krandom(buf: *mut u8, len: usize) -> isize {
-            (crate::KERNEL.random.fill_bytes)(buf, len)
+            #[cfg(feature = "journal")]
+            {
+                use crate::journal::{journaled, EventKind};
+
+                let out: &mut [u8] = if len == 0 {
+                    &mut []
+                } else {
+                    core::slice::from_raw_parts_mut(buf, len)
+                };
+                journaled(EventKind::Random, out, |out| {
+                    (crate::KERNEL.random.fill_bytes)(out.as_mut_ptr(), out.len()) as i64
+                }) as isize
+            }
+            #[cfg(not(feature = "journal"))]
+            {
+                (crate::KERNEL.random.fill_bytes)(buf, len)
+            }
         }
     } else {
         #[inline]
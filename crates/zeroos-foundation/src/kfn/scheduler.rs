@@ -1,5 +1,7 @@
 use cfg_if::cfg_if;
 
+use crate::error::{KError, KResult};
+
 #[cfg(feature = "scheduler")]
 #[allow(unused_imports)]
 pub use crate::kfn::thread::ktrap_frame_addr;
@@ -9,9 +11,15 @@ pub use crate::kfn::thread::{kalloc_kstack, ThreadAnchor};
 
 cfg_if! {
     if #[cfg(feature = "scheduler")] {
+        /// The registered scheduler.
+        #[inline(always)]
+        fn backend() -> &'static dyn crate::ops::SchedulerBackend {
+            unsafe { crate::KERNEL.scheduler }
+        }
+
         #[inline]
         pub fn kinit() -> usize {
-            unsafe { (crate::KERNEL.scheduler.init)() }
+            backend().init()
         }
 
         #[inline]
@@ -21,51 +29,90 @@ cfg_if! {
             parent_tid_ptr: usize,
             child_tid_ptr: usize,
             clear_child_tid_ptr: usize,
-        ) -> isize {
-            unsafe {
-                (crate::KERNEL.scheduler.spawn_thread)(
-                    stack,
-                    tls,
-                    parent_tid_ptr,
-                    child_tid_ptr,
-                    clear_child_tid_ptr,
-                )
-            }
+        ) -> KResult<usize> {
+            KError::from_ret(backend().spawn_thread(
+                stack,
+                tls,
+                parent_tid_ptr,
+                child_tid_ptr,
+                clear_child_tid_ptr,
+            ))
         }
 
         #[inline]
-        pub fn ksched_yield() -> isize {
-            unsafe { (crate::KERNEL.scheduler.yield_now)() }
+        pub fn ksched_yield() -> KResult {
+            KError::from_ret(backend().yield_now()).map(drop)
         }
 
         #[inline]
-        pub fn kexit_current(code: i32) -> isize {
-            unsafe { (crate::KERNEL.scheduler.exit_current)(code) }
+        pub fn kpreempt() {
+            backend().preempt()
+        }
+
+        #[inline]
+        pub fn kexit_current(code: i32) -> KResult<usize> {
+            KError::from_ret(backend().exit_current(code))
         }
 
         #[inline]
         pub fn kcurrent_tid() -> usize {
-            unsafe { (crate::KERNEL.scheduler.current_tid)() }
+            backend().current_tid()
         }
 
         #[inline]
         pub fn kthread_count() -> usize {
-            unsafe { (crate::KERNEL.scheduler.thread_count)() }
+            backend().thread_count()
         }
 
         #[inline]
-        pub fn kwait_on_addr(addr: usize, expected: i32) -> isize {
-            unsafe { (crate::KERNEL.scheduler.wait_on_addr)(addr, expected) }
+        pub fn kwait_on_addr(addr: usize, expected: i32) -> KResult {
+            KError::from_ret(backend().wait_on_addr(addr, expected)).map(drop)
         }
 
         #[inline]
         pub fn kwake_on_addr(addr: usize, count: usize) -> usize {
-            unsafe { (crate::KERNEL.scheduler.wake_on_addr)(addr, count) }
+            backend().wake_on_addr(addr, count)
+        }
+
+        #[inline]
+        pub fn kwait_on_addr_timeout(addr: usize, expected: i32, timeout_ns: u64) -> KResult {
+            KError::from_ret(backend().wait_on_addr_timeout(addr, expected, timeout_ns)).map(drop)
+        }
+
+        /// Nanoseconds on the scheduler's clock, which timed waits expire by.
+        #[inline]
+        pub fn kclock_ns() -> u64 {
+            backend().now_ns()
+        }
+
+        #[inline]
+        pub fn krequeue_on_addr(
+            addr: usize,
+            wake_count: usize,
+            addr2: usize,
+            requeue_count: usize,
+        ) -> usize {
+            backend().requeue_on_addr(addr, wake_count, addr2, requeue_count)
+        }
+
+        #[inline]
+        pub fn kjoin(tid: usize, status_ptr: usize, nohang: bool) -> KResult<usize> {
+            KError::from_ret(backend().join(tid, status_ptr, nohang))
+        }
+
+        #[inline]
+        pub fn kset_clear_on_exit_addr(addr: usize) -> KResult<usize> {
+            KError::from_ret(backend().set_clear_on_exit_addr(addr))
         }
 
         #[inline]
-        pub fn kset_clear_on_exit_addr(addr: usize) -> isize {
-            unsafe { (crate::KERNEL.scheduler.set_clear_on_exit_addr)(addr) }
+        pub fn kset_robust_list(head: usize) -> KResult {
+            KError::from_ret(backend().set_robust_list(head)).map(drop)
+        }
+
+        #[inline]
+        pub fn krobust_list(tid: usize) -> KResult<usize> {
+            KError::from_ret(backend().robust_list(tid))
         }
     } else {
         #[inline]
@@ -82,20 +129,24 @@ cfg_if! {
             _parent_tid_ptr: usize,
             _child_tid_ptr: usize,
             _clear_child_tid_ptr: usize,
-        ) -> isize {
-            -1
+        ) -> KResult<usize> {
+            Err(KError::NoSys)
         }
 
         #[inline]
         #[allow(dead_code)]
-        pub fn ksched_yield() -> isize {
-            0
+        pub fn ksched_yield() -> KResult {
+            Ok(())
         }
 
         #[inline]
         #[allow(dead_code)]
-        pub fn kexit_current(_code: i32) -> isize {
-            0
+        pub fn kpreempt() {}
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kexit_current(_code: i32) -> KResult<usize> {
+            Ok(0)
         }
 
         #[inline]
@@ -112,8 +163,8 @@ cfg_if! {
 
         #[inline]
         #[allow(dead_code)]
-        pub fn kwait_on_addr(_addr: usize, _expected: i32) -> isize {
-            0
+        pub fn kwait_on_addr(_addr: usize, _expected: i32) -> KResult {
+            Ok(())
         }
 
         #[inline]
@@ -124,8 +175,50 @@ cfg_if! {
 
         #[inline]
         #[allow(dead_code)]
-        pub fn kset_clear_on_exit_addr(_addr: usize) -> isize {
+        pub fn kwait_on_addr_timeout(_addr: usize, _expected: i32, _timeout_ns: u64) -> KResult {
+            Ok(())
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kclock_ns() -> u64 {
+            0
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn krequeue_on_addr(
+            _addr: usize,
+            _wake_count: usize,
+            _addr2: usize,
+            _requeue_count: usize,
+        ) -> usize {
             0
         }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kjoin(_tid: usize, _status_ptr: usize, _nohang: bool) -> KResult<usize> {
+            Err(KError::Srch)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kset_clear_on_exit_addr(_addr: usize) -> KResult<usize> {
+            Ok(0)
+        }
+
+        /// Nothing walks the list without threads, so it is not recorded.
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kset_robust_list(_head: usize) -> KResult {
+            Ok(())
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn krobust_list(_tid: usize) -> KResult<usize> {
+            Ok(0)
+        }
     }
 }
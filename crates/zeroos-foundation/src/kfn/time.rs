@@ -0,0 +1,66 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "time")] {
+        use crate::ops::TimeConfig;
+
+        extern "C" {
+            fn __platform_cycle_count() -> u64;
+        }
+
+        #[inline]
+        pub fn kinit(config: TimeConfig) {
+            unsafe { (crate::KERNEL.time.init)(config) }
+        }
+
+        /// Nanoseconds on Linux clock `clock_id`, or a negative errno.
+        #[inline]
+        pub fn know_ns(clock_id: usize) -> i64 {
+            #[cfg(feature = "journal")]
+            {
+                use crate::journal::{journaled, EventKind};
+
+                journaled(EventKind::Clock, &mut [], |_| unsafe {
+                    (crate::KERNEL.time.now_ns)(clock_id)
+                })
+            }
+            #[cfg(not(feature = "journal"))]
+            unsafe {
+                (crate::KERNEL.time.now_ns)(clock_id)
+            }
+        }
+
+        /// Move every clock forward by `ns` nanoseconds.
+        #[inline]
+        pub fn kadvance_ns(ns: u64) {
+            unsafe { (crate::KERNEL.time.advance)(ns) }
+        }
+
+        /// Raw platform cycle counter.
+        #[inline]
+        pub fn kcycles() -> u64 {
+            unsafe { __platform_cycle_count() }
+        }
+    } else {
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kinit(_config: crate::ops::TimeConfig) {}
+
+        /// No clock is configured.
+        #[inline]
+        #[allow(dead_code)]
+        pub fn know_ns(_clock_id: usize) -> i64 {
+            -(crate::error::errno::ENOSYS as i64)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kadvance_ns(_ns: u64) {}
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kcycles() -> u64 {
+            0
+        }
+    }
+}
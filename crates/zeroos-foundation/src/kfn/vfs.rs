@@ -1,5 +1,8 @@
 use cfg_if::cfg_if;
 
+use crate::error::{KError, KResult};
+use crate::ops::{FileStat, IoVec};
+
 cfg_if! {
     if #[cfg(feature = "vfs")] {
         #[inline]
@@ -8,40 +11,100 @@ cfg_if! {
         }
 
         #[inline]
-        pub fn kread(fd: i32, buf: *mut u8, count: usize) -> isize {
-            unsafe { (crate::KERNEL.vfs.read)(fd, buf, count) }
+        pub fn kread(fd: i32, buf: *mut u8, count: usize) -> KResult<usize> {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.read)(fd, buf, count) })
+        }
+
+        #[inline]
+        pub fn kwrite(fd: i32, buf: *const u8, count: usize) -> KResult<usize> {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.write)(fd, buf, count) })
+        }
+
+        /// Vectored read: one call into the file, however many buffers.
+        #[inline]
+        pub fn kreadv(fd: i32, iov: &[IoVec]) -> KResult<usize> {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.readv)(fd, iov) })
+        }
+
+        #[inline]
+        pub fn kwritev(fd: i32, iov: &[IoVec]) -> KResult<usize> {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.writev)(fd, iov) })
+        }
+
+        #[inline]
+        pub fn kpread(fd: i32, buf: *mut u8, count: usize, offset: usize) -> KResult<usize> {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.pread)(fd, buf, count, offset) })
+        }
+
+        #[inline]
+        pub fn kpwrite(fd: i32, buf: *const u8, count: usize, offset: usize) -> KResult<usize> {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.pwrite)(fd, buf, count, offset) })
+        }
+
+        #[inline]
+        /// # Safety
+        /// `path` must be a valid NUL-terminated string.
+        pub unsafe fn kopen(path: *const u8, flags: i32, mode: u32) -> KResult<usize> {
+            KError::from_ret((crate::KERNEL.vfs.open)(path, flags, mode))
+        }
+
+        #[inline]
+        pub fn kclose(fd: i32) -> KResult {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.close)(fd) }).map(drop)
+        }
+
+        #[inline]
+        pub fn klseek(fd: i32, offset: isize, whence: i32) -> KResult<usize> {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.lseek)(fd, offset, whence) })
         }
 
         #[inline]
-        pub fn kwrite(fd: i32, buf: *const u8, count: usize) -> isize {
-            unsafe { (crate::KERNEL.vfs.write)(fd, buf, count) }
+        pub fn kioctl(fd: i32, request: usize, arg: usize) -> KResult<usize> {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.ioctl)(fd, request, arg) })
+        }
+
+        #[inline]
+        pub fn kfstat(fd: i32) -> KResult<FileStat> {
+            let mut stat = FileStat::default();
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.fstat)(fd, &mut stat) })?;
+            Ok(stat)
         }
 
         #[inline]
         /// # Safety
         /// `path` must be a valid NUL-terminated string.
-        pub unsafe fn kopen(path: *const u8, flags: i32, mode: u32) -> isize {
-            (crate::KERNEL.vfs.open)(path, flags, mode)
+        pub unsafe fn kstat(path: *const u8) -> KResult<FileStat> {
+            let mut stat = FileStat::default();
+            KError::from_ret((crate::KERNEL.vfs.stat)(path, &mut stat))?;
+            Ok(stat)
         }
 
         #[inline]
-        pub fn kclose(fd: i32) -> isize {
-            unsafe { (crate::KERNEL.vfs.close)(fd) }
+        /// # Safety
+        /// `path` must be a valid NUL-terminated string.
+        pub unsafe fn kunlink(path: *const u8) -> KResult {
+            KError::from_ret((crate::KERNEL.vfs.unlink)(path)).map(drop)
         }
 
+        /// Open a pipe; returns its `[read, write]` descriptors.
         #[inline]
-        pub fn klseek(fd: i32, offset: isize, whence: i32) -> isize {
-            unsafe { (crate::KERNEL.vfs.lseek)(fd, offset, whence) }
+        pub fn kpipe(flags: i32) -> KResult<[i32; 2]> {
+            let mut fds = [-1; 2];
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.pipe)(&mut fds, flags) })?;
+            Ok(fds)
         }
 
         #[inline]
-        pub fn kioctl(fd: i32, request: usize, arg: usize) -> isize {
-            unsafe { (crate::KERNEL.vfs.ioctl)(fd, request, arg) }
+        pub fn kdup(fd: i32) -> KResult<usize> {
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.dup)(fd, -1, 0) })
         }
 
         #[inline]
-        pub fn kfstat(fd: i32, statbuf: *mut u8) -> isize {
-            unsafe { (crate::KERNEL.vfs.fstat)(fd, statbuf) }
+        pub fn kdup3(fd: i32, new_fd: i32, flags: i32) -> KResult<usize> {
+            if new_fd < 0 {
+                return Err(KError::BadF);
+            }
+            KError::from_ret(unsafe { (crate::KERNEL.vfs.dup)(fd, new_fd, flags) })
         }
     } else {
         #[inline]
@@ -50,46 +113,104 @@ cfg_if! {
 
         #[inline]
         #[allow(dead_code)]
-        pub fn kread(_fd: i32, _buf: *mut u8, _count: usize) -> isize {
-            -1
+        pub fn kread(_fd: i32, _buf: *mut u8, _count: usize) -> KResult<usize> {
+            Err(KError::NoSys)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kwrite(_fd: i32, _buf: *const u8, _count: usize) -> KResult<usize> {
+            Err(KError::NoSys)
         }
 
         #[inline]
         #[allow(dead_code)]
-        pub fn kwrite(_fd: i32, _buf: *const u8, _count: usize) -> isize {
-            -1
+        pub fn kreadv(_fd: i32, _iov: &[IoVec]) -> KResult<usize> {
+            Err(KError::NoSys)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kwritev(_fd: i32, _iov: &[IoVec]) -> KResult<usize> {
+            Err(KError::NoSys)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpread(_fd: i32, _buf: *mut u8, _count: usize, _offset: usize) -> KResult<usize> {
+            Err(KError::NoSys)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kpwrite(_fd: i32, _buf: *const u8, _count: usize, _offset: usize) -> KResult<usize> {
+            Err(KError::NoSys)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        /// # Safety
+        /// `path` is not used in the stub implementation.
+        pub unsafe fn kopen(_path: *const u8, _flags: i32, _mode: u32) -> KResult<usize> {
+            Err(KError::NoSys)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kclose(_fd: i32) -> KResult {
+            Err(KError::NoSys)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn klseek(_fd: i32, _offset: isize, _whence: i32) -> KResult<usize> {
+            Err(KError::NoSys)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kioctl(_fd: i32, _request: usize, _arg: usize) -> KResult<usize> {
+            Err(KError::NoSys)
+        }
+
+        #[inline]
+        #[allow(dead_code)]
+        pub fn kfstat(_fd: i32) -> KResult<FileStat> {
+            Err(KError::NoSys)
         }
 
         #[inline]
         #[allow(dead_code)]
         /// # Safety
         /// `path` is not used in the stub implementation.
-        pub unsafe fn kopen(_path: *const u8, _flags: i32, _mode: u32) -> isize {
-            -1
+        pub unsafe fn kstat(_path: *const u8) -> KResult<FileStat> {
+            Err(KError::NoSys)
         }
 
         #[inline]
         #[allow(dead_code)]
-        pub fn kclose(_fd: i32) -> isize {
-            -1
+        /// # Safety
+        /// `path` is not used in the stub implementation.
+        pub unsafe fn kunlink(_path: *const u8) -> KResult {
+            Err(KError::NoSys)
         }
 
         #[inline]
         #[allow(dead_code)]
-        pub fn klseek(_fd: i32, _offset: isize, _whence: i32) -> isize {
-            -1
+        pub fn kpipe(_flags: i32) -> KResult<[i32; 2]> {
+            Err(KError::NoSys)
         }
 
         #[inline]
         #[allow(dead_code)]
-        pub fn kioctl(_fd: i32, _request: usize, _arg: usize) -> isize {
-            -1
+        pub fn kdup(_fd: i32) -> KResult<usize> {
+            Err(KError::NoSys)
         }
 
         #[inline]
         #[allow(dead_code)]
-        pub fn kfstat(_fd: i32, _statbuf: *mut u8) -> isize {
-            -1
+        pub fn kdup3(_fd: i32, _new_fd: i32, _flags: i32) -> KResult<usize> {
+            Err(KError::NoSys)
         }
     }
 }
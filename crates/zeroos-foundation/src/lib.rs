@@ -3,14 +3,27 @@
 extern crate alloc;
 
 pub mod arch;
+#[cfg(feature = "args")]
+pub mod args;
 pub mod entry;
+#[cfg(feature = "env")]
+pub mod env;
+pub mod error;
+#[cfg(feature = "journal")]
+pub mod journal;
 pub mod kernel;
 pub mod kfn;
 pub mod ops;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+pub mod uaccess;
 pub mod utils;
+#[cfg(feature = "watchdog")]
+pub mod watchdog;
 
 pub use arch::SyscallFrame;
 pub use entry::__main_entry;
+pub use error::{IntoRet, KError, KResult};
 
 pub use kernel::{init, GlobalKernel, Kernel, KERNEL};
 
@@ -20,9 +33,11 @@ pub use kernel::register_arch;
 pub use kernel::register_memory;
 #[cfg(feature = "random")]
 pub use kernel::register_random;
-#[cfg(feature = "scheduler")]
-pub use kernel::register_scheduler;
+#[cfg(feature = "time")]
+pub use kernel::register_time;
 #[cfg(feature = "trap")]
 pub use kernel::register_trap;
 #[cfg(feature = "vfs")]
 pub use kernel::register_vfs;
+#[cfg(feature = "scheduler")]
+pub use kernel::{register_scheduler, register_scheduler_backend};
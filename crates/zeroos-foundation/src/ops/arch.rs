@@ -62,6 +62,17 @@ pub struct ArchOps {
     /// # Safety
     /// `regs` must be a valid, aligned, and mutable pointer.
     pub trap_frame_set_tp: unsafe fn(regs: *mut u8, tp: usize),
+    /// # Safety
+    /// `regs` must be a valid, aligned, and mutable pointer.
+    pub trap_frame_set_ra: unsafe fn(regs: *mut u8, ra: usize),
+    /// Set the argument register at the given index (0-5); index 0 aliases the return value.
+    /// # Safety
+    /// `regs` must be a valid, aligned, and mutable pointer.
+    pub trap_frame_set_arg: unsafe fn(regs: *mut u8, idx: usize, val: usize),
+    /// Return the saved stack pointer from a trap frame.
+    /// # Safety
+    /// `regs` must be a valid, aligned pointer.
+    pub trap_frame_get_sp: unsafe fn(regs: *const u8) -> usize,
 
     /// Return a pointer to the current CPU's trap frame.
     ///
@@ -6,4 +6,20 @@ pub struct MemoryOps {
     pub alloc: fn(layout: Layout) -> *mut u8,
     pub dealloc: fn(ptr: *mut u8, layout: Layout),
     pub realloc: fn(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8,
+    /// Bytes of the heap currently unavailable for allocation (including allocator overhead).
+    pub used: fn() -> usize,
+    /// Add `[start, start + len)` to the heap; false if the allocator cannot use that range.
+    pub grow: fn(start: usize, len: usize) -> bool,
+}
+
+/// Heap usage snapshot, see `kfn::memory::kheap_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Bytes handed to the allocator at init.
+    pub total: usize,
+    pub used: usize,
+    pub remaining: usize,
+    /// Most bytes ever requested by live allocations at once (without allocator overhead), or
+    /// `used` if that is higher.
+    pub peak: usize,
 }
@@ -7,7 +7,7 @@ cfg_if! {
         pub(crate) mod memory;
     }
 }
-pub use memory::MemoryOps;
+pub use memory::{HeapStats, MemoryOps};
 
 cfg_if! {
     if #[cfg(feature = "scheduler")] {
@@ -16,7 +16,7 @@ cfg_if! {
         pub(crate) mod scheduler;
     }
 }
-pub use scheduler::SchedulerOps;
+pub use scheduler::{SchedulerBackend, SchedulerOps};
 
 cfg_if! {
     if #[cfg(feature = "vfs")] {
@@ -25,7 +25,7 @@ cfg_if! {
         pub(crate) mod vfs;
     }
 }
-pub use vfs::VfsOps;
+pub use vfs::{FileStat, IoVec, VfsOps};
 
 cfg_if! {
     if #[cfg(feature = "random")] {
@@ -53,3 +53,12 @@ cfg_if! {
     }
 }
 pub use trap::TrapOps;
+
+cfg_if! {
+    if #[cfg(feature = "time")] {
+        pub mod time;
+    } else {
+        pub(crate) mod time;
+    }
+}
+pub use time::{TimeConfig, TimeOps};
@@ -1,6 +1,10 @@
 //! Scheduler operation table.
 //!
-//! Defines the interface for a thread scheduler.
+//! Defines the interface for a thread scheduler, both as the [`SchedulerOps`] table of free
+//! functions that schedulers export as a `const`, and as the [`SchedulerBackend`] trait the
+//! kernel dispatches through. The table implements the trait, so existing tables register
+//! unchanged; a type implementing the trait directly (a mock in a syscall handler test, or a
+//! scheduler that wraps another) registers with `register_scheduler_backend`.
 
 #[derive(Clone, Copy)]
 pub struct SchedulerOps {
@@ -9,6 +13,7 @@ pub struct SchedulerOps {
     pub init: fn() -> usize,
 
     /// Spawn a new thread with the given stack, TLS, and TIDs pointers.
+    /// A zero `tls` gives the child a fresh block initialized from the TLS template.
     pub spawn_thread: fn(
         stack: usize,
         tls: usize,
@@ -20,6 +25,9 @@ pub struct SchedulerOps {
     /// Voluntarily yield the CPU to another thread.
     pub yield_now: fn() -> isize,
 
+    /// Called from the platform's timer interrupt handler when the current quantum expires.
+    pub preempt: fn(),
+
     /// Terminate the current thread with the given exit code.
     pub exit_current: fn(code: i32) -> isize,
 
@@ -35,6 +43,146 @@ pub struct SchedulerOps {
     /// Wake up to `count` threads waiting on `addr`.
     pub wake_on_addr: fn(addr: usize, count: usize) -> usize,
 
+    /// Like `wait_on_addr`, but return `-ETIMEDOUT` if not woken within `timeout_ns`.
+    pub wait_on_addr_timeout: fn(addr: usize, expected: i32, timeout_ns: u64) -> isize,
+
+    /// Nanoseconds on the clock `wait_on_addr_timeout` timeouts are measured against.
+    pub now_ns: fn() -> u64,
+
+    /// Wake up to `wake_count` threads waiting on `addr` and move up to `requeue_count` of the
+    /// remaining waiters to `addr2`. Returns the number of threads woken or requeued.
+    pub requeue_on_addr:
+        fn(addr: usize, wake_count: usize, addr2: usize, requeue_count: usize) -> usize,
+
+    /// Wait for thread `tid` to exit, storing its `i32` exit code at `status_ptr` if non-zero.
+    /// Returns `tid`, `0` if `nohang` and the thread is still running, or a negative errno.
+    pub join: fn(tid: usize, status_ptr: usize, nohang: bool) -> isize,
+
     /// Set a memory address to be cleared when the current thread exits.
     pub set_clear_on_exit_addr: fn(addr: usize) -> isize,
+
+    /// Register the robust futex list head of the current thread.
+    pub set_robust_list: fn(head: usize) -> isize,
+
+    /// Return the robust futex list head of thread `tid`, or a negative errno.
+    pub robust_list: fn(tid: usize) -> isize,
+}
+
+/// A scheduler as a trait object. Each method has the contract of the [`SchedulerOps`] field of
+/// the same name.
+pub trait SchedulerBackend: Sync {
+    fn init(&self) -> usize;
+    fn spawn_thread(
+        &self,
+        stack: usize,
+        tls: usize,
+        parent_tid_ptr: usize,
+        child_tid_ptr: usize,
+        clear_child_tid_ptr: usize,
+    ) -> isize;
+    fn yield_now(&self) -> isize;
+    fn preempt(&self);
+    fn exit_current(&self, code: i32) -> isize;
+    fn current_tid(&self) -> usize;
+    fn thread_count(&self) -> usize;
+    fn wait_on_addr(&self, addr: usize, expected: i32) -> isize;
+    fn wake_on_addr(&self, addr: usize, count: usize) -> usize;
+    fn wait_on_addr_timeout(&self, addr: usize, expected: i32, timeout_ns: u64) -> isize;
+    fn now_ns(&self) -> u64;
+    fn requeue_on_addr(
+        &self,
+        addr: usize,
+        wake_count: usize,
+        addr2: usize,
+        requeue_count: usize,
+    ) -> usize;
+    fn join(&self, tid: usize, status_ptr: usize, nohang: bool) -> isize;
+    fn set_clear_on_exit_addr(&self, addr: usize) -> isize;
+    fn set_robust_list(&self, head: usize) -> isize;
+    fn robust_list(&self, tid: usize) -> isize;
+}
+
+impl SchedulerBackend for SchedulerOps {
+    fn init(&self) -> usize {
+        (self.init)()
+    }
+
+    fn spawn_thread(
+        &self,
+        stack: usize,
+        tls: usize,
+        parent_tid_ptr: usize,
+        child_tid_ptr: usize,
+        clear_child_tid_ptr: usize,
+    ) -> isize {
+        (self.spawn_thread)(
+            stack,
+            tls,
+            parent_tid_ptr,
+            child_tid_ptr,
+            clear_child_tid_ptr,
+        )
+    }
+
+    fn yield_now(&self) -> isize {
+        (self.yield_now)()
+    }
+
+    fn preempt(&self) {
+        (self.preempt)()
+    }
+
+    fn exit_current(&self, code: i32) -> isize {
+        (self.exit_current)(code)
+    }
+
+    fn current_tid(&self) -> usize {
+        (self.current_tid)()
+    }
+
+    fn thread_count(&self) -> usize {
+        (self.thread_count)()
+    }
+
+    fn wait_on_addr(&self, addr: usize, expected: i32) -> isize {
+        (self.wait_on_addr)(addr, expected)
+    }
+
+    fn wake_on_addr(&self, addr: usize, count: usize) -> usize {
+        (self.wake_on_addr)(addr, count)
+    }
+
+    fn wait_on_addr_timeout(&self, addr: usize, expected: i32, timeout_ns: u64) -> isize {
+        (self.wait_on_addr_timeout)(addr, expected, timeout_ns)
+    }
+
+    fn now_ns(&self) -> u64 {
+        (self.now_ns)()
+    }
+
+    fn requeue_on_addr(
+        &self,
+        addr: usize,
+        wake_count: usize,
+        addr2: usize,
+        requeue_count: usize,
+    ) -> usize {
+        (self.requeue_on_addr)(addr, wake_count, addr2, requeue_count)
+    }
+
+    fn join(&self, tid: usize, status_ptr: usize, nohang: bool) -> isize {
+        (self.join)(tid, status_ptr, nohang)
+    }
+
+    fn set_clear_on_exit_addr(&self, addr: usize) -> isize {
+        (self.set_clear_on_exit_addr)(addr)
+    }
+
+    fn set_robust_list(&self, head: usize) -> isize {
+        (self.set_robust_list)(head)
+    }
+
+    fn robust_list(&self, tid: usize) -> isize {
+        (self.robust_list)(tid)
+    }
 }
@@ -0,0 +1,45 @@
+//! Time operation table.
+//!
+//! Clocks are derived from the platform cycle counter rather than host time, so timed code paths
+//! replay identically across runs and hosts.
+
+/// Boot-time clock configuration.
+///
+/// `cycles` executed cycles correspond to `nanos` nanoseconds of virtual time.
+#[derive(Clone, Copy)]
+pub struct TimeConfig {
+    pub cycles: u64,
+    pub nanos: u64,
+    /// `CLOCK_REALTIME` at boot, in nanoseconds since the Unix epoch.
+    pub realtime_base_ns: u64,
+}
+
+impl TimeConfig {
+    /// One cycle per nanosecond (a nominal 1 GHz core), starting at the Unix epoch.
+    pub const fn new() -> Self {
+        Self {
+            cycles: 1,
+            nanos: 1,
+            realtime_base_ns: 0,
+        }
+    }
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TimeOps {
+    /// Latch the boot cycle count and the cycles-per-nanosecond ratio.
+    pub init: fn(config: TimeConfig),
+
+    /// Return the current time of Linux clock `clock_id` in nanoseconds, or a negative errno.
+    pub now_ns: fn(clock_id: usize) -> i64,
+
+    /// Move every clock forward by `ns` nanoseconds, for time that passed without executing
+    /// (a sleep that ended before its duration was up).
+    pub advance: fn(ns: u64),
+}
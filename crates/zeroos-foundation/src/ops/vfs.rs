@@ -1,11 +1,41 @@
+/// One buffer of a vectored transfer, laid out like Linux `struct iovec`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct IoVec {
+    pub base: *mut u8,
+    pub len: usize,
+}
+
+/// What the VFS knows about a file; the syscall layer lays it out as `struct stat` or `statx`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileStat {
+    /// File type and permission bits, as in `st_mode`.
+    pub mode: u32,
+    pub size: u64,
+}
+
 #[derive(Clone, Copy)]
 pub struct VfsOps {
     pub init: fn(),
     pub read: fn(fd: i32, buf: *mut u8, count: usize) -> isize,
     pub write: fn(fd: i32, buf: *const u8, count: usize) -> isize,
+    /// Fill the buffers in order, as one read.
+    pub readv: fn(fd: i32, iov: &[IoVec]) -> isize,
+    /// Write the buffers in order, as one write.
+    pub writev: fn(fd: i32, iov: &[IoVec]) -> isize,
+    /// Read at `offset` without moving the file offset.
+    pub pread: fn(fd: i32, buf: *mut u8, count: usize, offset: usize) -> isize,
+    /// Write at `offset` without moving the file offset.
+    pub pwrite: fn(fd: i32, buf: *const u8, count: usize, offset: usize) -> isize,
     pub open: unsafe fn(path: *const u8, flags: i32, mode: u32) -> isize,
     pub close: fn(fd: i32) -> isize,
     pub lseek: fn(fd: i32, offset: isize, whence: i32) -> isize,
     pub ioctl: fn(fd: i32, request: usize, arg: usize) -> isize,
-    pub fstat: fn(fd: i32, statbuf: *mut u8) -> isize,
+    pub fstat: fn(fd: i32, stat: &mut FileStat) -> isize,
+    /// Like `fstat`, for the file at `path`.
+    pub stat: unsafe fn(path: *const u8, stat: &mut FileStat) -> isize,
+    pub unlink: unsafe fn(path: *const u8) -> isize,
+    pub pipe: fn(fds: &mut [i32; 2], flags: i32) -> isize,
+    /// Duplicate `oldfd` onto `newfd`, or onto the lowest free descriptor if `newfd` is negative.
+    pub dup: fn(oldfd: i32, newfd: i32, flags: i32) -> isize,
 }
@@ -0,0 +1,409 @@
+//! Guest snapshots: checkpoint a run at the end of one proof segment, resume it in the next.
+//!
+//! A snapshot is a copy of the memory regions the platform names, typically `.data`, `.bss`,
+//! the heap and the boot stack. Between them they hold everything the kernel knows: the
+//! scheduler's thread table and every TCB, kernel stack and trap frame, the allocator and its
+//! heap bounds, and the fd table. [`checkpoint`] saves the regions together with the calling
+//! context. The next segment boots the same image and calls [`resume`] before anything else
+//! writes to the regions; it copies them back and switches to the saved context, where
+//! `checkpoint` returns [`Checkpoint::Resumed`]. CPU state outside the kernel's contexts
+//! (trap vector, interrupt enables, timers) is the platform's to re-establish.
+//!
+//! The image is little-endian: a header (magic, version, region count, reserved; `u32` each),
+//! then per region its start, length and number of saved pages (`u64` each), followed by the
+//! pages, each an offset into the region (`u64`) and up to [`PAGE_SIZE`] bytes. All-zero pages
+//! are left out and come back as zeros, so untouched heap costs only the scan. Pages past the
+//! last saved one of a region are not written on restore: they are assumed to still be zero.
+
+/// First word of every snapshot image.
+pub const SNAPSHOT_MAGIC: u32 = u32::from_le_bytes(*b"ZSNP");
+/// Bumped when the image layout changes.
+pub const SNAPSHOT_VERSION: u32 = 1;
+/// Granularity at which zero memory is left out.
+pub const PAGE_SIZE: usize = 4096;
+
+const HEADER_LEN: usize = 16;
+const REGION_HEADER_LEN: usize = 24;
+const PAGE_HEADER_LEN: usize = 8;
+
+/// Memory saved in a snapshot, `[start, end)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Region {
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The buffer is too small for the image.
+    NoSpace,
+    /// Not a snapshot image.
+    BadMagic,
+    /// An image of another layout version.
+    BadVersion,
+    /// The image is truncated or inconsistent.
+    Malformed,
+    /// The image's regions differ from this boot's: another binary or heap layout.
+    LayoutMismatch,
+}
+
+/// Whether `image` starts like a snapshot.
+pub fn is_snapshot(image: &[u8]) -> bool {
+    image.len() >= HEADER_LEN && read_u32(image, 0) == SNAPSHOT_MAGIC
+}
+
+/// Write the contents of `regions` to `buf`; returns the image length.
+///
+/// # Safety
+/// Every region must be readable.
+pub unsafe fn save(regions: &[Region], buf: &mut [u8]) -> Result<usize, SnapshotError> {
+    let mut out = Writer { buf, len: 0 };
+    out.put(&SNAPSHOT_MAGIC.to_le_bytes())?;
+    out.put(&SNAPSHOT_VERSION.to_le_bytes())?;
+    out.put(&(regions.len() as u32).to_le_bytes())?;
+    out.put(&0u32.to_le_bytes())?;
+
+    for region in regions {
+        let mem = unsafe { core::slice::from_raw_parts(region.start as *const u8, region.len()) };
+        let count_at = out.len + 16;
+        out.put(&(region.start as u64).to_le_bytes())?;
+        out.put(&(region.len() as u64).to_le_bytes())?;
+        out.put(&0u64.to_le_bytes())?;
+
+        let mut pages = 0u64;
+        for (index, page) in mem.chunks(PAGE_SIZE).enumerate() {
+            if page.iter().all(|&b| b == 0) {
+                continue;
+            }
+            out.put(&((index * PAGE_SIZE) as u64).to_le_bytes())?;
+            out.put(page)?;
+            pages += 1;
+        }
+        out.buf[count_at..count_at + 8].copy_from_slice(&pages.to_le_bytes());
+    }
+    Ok(out.len)
+}
+
+/// Copy the contents saved in `image` back into `regions`, which must be the regions it was
+/// saved from. The image is checked in full before any memory is written.
+///
+/// # Safety
+/// Every region must be writable, and nothing may rely on their current contents.
+pub unsafe fn restore(regions: &[Region], image: &[u8]) -> Result<(), SnapshotError> {
+    check(regions, image)?;
+
+    let mut pos = HEADER_LEN;
+    for region in regions {
+        let pages = read_u64(image, pos + 16) as usize;
+        pos += REGION_HEADER_LEN;
+        let mut zero_from = 0;
+        for _ in 0..pages {
+            let offset = read_u64(image, pos) as usize;
+            let len = PAGE_SIZE.min(region.len() - offset);
+            let data = &image[pos + PAGE_HEADER_LEN..pos + PAGE_HEADER_LEN + len];
+            unsafe {
+                let dst = (region.start + zero_from) as *mut u8;
+                core::ptr::write_bytes(dst, 0, offset - zero_from);
+                core::ptr::copy_nonoverlapping(
+                    data.as_ptr(),
+                    (region.start + offset) as *mut u8,
+                    len,
+                );
+            }
+            zero_from = offset + len;
+            pos += PAGE_HEADER_LEN + len;
+        }
+    }
+    Ok(())
+}
+
+/// Validate the whole of `image` against `regions` without touching memory.
+fn check(regions: &[Region], image: &[u8]) -> Result<(), SnapshotError> {
+    if !is_snapshot(image) {
+        return Err(SnapshotError::BadMagic);
+    }
+    if read_u32(image, 4) != SNAPSHOT_VERSION {
+        return Err(SnapshotError::BadVersion);
+    }
+    if read_u32(image, 8) as usize != regions.len() {
+        return Err(SnapshotError::LayoutMismatch);
+    }
+
+    let mut pos = HEADER_LEN;
+    for region in regions {
+        let header = image
+            .get(pos..pos + REGION_HEADER_LEN)
+            .ok_or(SnapshotError::Malformed)?;
+        if read_u64(header, 0) != region.start as u64 || read_u64(header, 8) != region.len() as u64
+        {
+            return Err(SnapshotError::LayoutMismatch);
+        }
+        let pages = read_u64(header, 16);
+        pos += REGION_HEADER_LEN;
+
+        let mut next = 0u64;
+        for _ in 0..pages {
+            let offset = image
+                .get(pos..pos + PAGE_HEADER_LEN)
+                .map(|bytes| read_u64(bytes, 0))
+                .ok_or(SnapshotError::Malformed)?;
+            // Offsets are page-aligned, ascending and inside the region.
+            if offset < next || offset % PAGE_SIZE as u64 != 0 || offset >= region.len() as u64 {
+                return Err(SnapshotError::Malformed);
+            }
+            let len = PAGE_SIZE.min(region.len() - offset as usize);
+            pos += PAGE_HEADER_LEN + len;
+            if pos > image.len() {
+                return Err(SnapshotError::Malformed);
+            }
+            next = offset + PAGE_SIZE as u64;
+        }
+    }
+    Ok(())
+}
+
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn put(&mut self, bytes: &[u8]) -> Result<(), SnapshotError> {
+        let end = self.len + bytes.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(SnapshotError::NoSpace)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+#[cfg(feature = "arch")]
+pub use resume::{checkpoint, resume, Checkpoint};
+
+/// Saving and resuming the running context.
+///
+/// Both copies run on a worker context with a stack of its own, in the `.zeroos_snapshot_stack`
+/// section that platforms keep out of every region: `checkpoint` must not save a stack that is
+/// still changing under it, and `resume` must not overwrite the stack it is running on.
+#[cfg(feature = "arch")]
+mod resume {
+    use core::ptr::{addr_of, addr_of_mut};
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use super::{Region, SnapshotError};
+    use crate::kfn::arch as karch;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Checkpoint {
+        /// The snapshot was written; its length.
+        Saved(usize),
+        /// A later boot restored the snapshot and resumed here.
+        Resumed,
+    }
+
+    /// Room for any architecture's thread context.
+    #[repr(C, align(16))]
+    struct Context([usize; 32]);
+
+    const WORKER_STACK_SIZE: usize = 8 * 1024;
+
+    #[repr(C, align(16))]
+    struct Stack([u8; WORKER_STACK_SIZE]);
+
+    #[link_section = ".zeroos_snapshot_stack"]
+    static mut WORKER_STACK: Stack = Stack([0; WORKER_STACK_SIZE]);
+    static mut WORKER: Context = Context([0; 32]);
+
+    /// The context `checkpoint` was called from; saved with `.bss`.
+    static mut CALLER: Context = Context([0; 32]);
+    /// Set by `resume` after the copy, so the restored caller can tell it was resumed.
+    static RESUMED: AtomicBool = AtomicBool::new(false);
+
+    /// What the worker is asked to do.
+    enum Job {
+        Save {
+            regions: *const [Region],
+            buf: *mut [u8],
+        },
+        Restore {
+            regions: *const [Region],
+            image: *const [u8],
+        },
+    }
+
+    static mut JOB: Option<Job> = None;
+    static mut RESULT: Result<usize, SnapshotError> = Ok(0);
+
+    /// Save `regions` and the calling context into `buf`.
+    ///
+    /// Returns [`Checkpoint::Saved`] right away, and [`Checkpoint::Resumed`] once a later boot
+    /// passes the image to [`resume`]. Call with interrupts disabled and no other hart running.
+    ///
+    /// # Safety
+    /// `regions` must be readable and must not contain `buf`.
+    pub unsafe fn checkpoint(
+        regions: &[Region],
+        buf: &mut [u8],
+    ) -> Result<Checkpoint, SnapshotError> {
+        RESUMED.store(false, Ordering::Relaxed);
+        unsafe {
+            *addr_of_mut!(JOB) = Some(Job::Save { regions, buf });
+            run_worker();
+        }
+        if RESUMED.load(Ordering::Relaxed) {
+            return Ok(Checkpoint::Resumed);
+        }
+        unsafe { *addr_of!(RESULT) }.map(Checkpoint::Saved)
+    }
+
+    /// Restore `image` into `regions` and continue where it was checkpointed. Returns only if the
+    /// image does not fit this boot's regions, in which case no memory was written.
+    ///
+    /// # Safety
+    /// Call during bootstrap, before anything has written to `regions`; `image` must lie outside
+    /// them.
+    pub unsafe fn resume(regions: &[Region], image: &[u8]) -> SnapshotError {
+        if let Err(e) = super::check(regions, image) {
+            return e;
+        }
+        unsafe {
+            *addr_of_mut!(JOB) = Some(Job::Restore { regions, image });
+            run_worker();
+        }
+        unreachable!("resumed context returned to the boot path")
+    }
+
+    /// Switch to the worker, saving the current context in `CALLER`.
+    unsafe fn run_worker() {
+        unsafe {
+            let worker = addr_of_mut!(WORKER) as *mut u8;
+            let stack_top = addr_of_mut!(WORKER_STACK) as usize + WORKER_STACK_SIZE;
+            karch::kthread_ctx_init(worker, 0, stack_top);
+            karch::kthread_ctx_set_ra(worker, worker_main as *const () as usize);
+            karch::kswitch_to(addr_of_mut!(CALLER) as *mut u8, worker);
+        }
+    }
+
+    extern "C" fn worker_main() -> ! {
+        unsafe {
+            match (*addr_of_mut!(JOB)).take() {
+                Some(Job::Save { regions, buf }) => {
+                    *addr_of_mut!(RESULT) = super::save(&*regions, &mut *buf);
+                }
+                Some(Job::Restore { regions, image }) => {
+                    // Checked by `resume` already; overwrites `CALLER` with the saved context.
+                    let _ = super::restore(&*regions, &*image);
+                    RESUMED.store(true, Ordering::Relaxed);
+                }
+                None => {}
+            }
+            karch::kswitch_to(
+                addr_of_mut!(WORKER) as *mut u8,
+                addr_of!(CALLER) as *const u8,
+            );
+        }
+        unreachable!("worker switched back to")
+    }
+
+    const _: () = assert!(core::mem::size_of::<Context>() >= 17 * core::mem::size_of::<usize>());
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use super::*;
+    use alloc::vec;
+
+    fn region_of(mem: &[u8]) -> Region {
+        Region::new(mem.as_ptr() as usize, mem.as_ptr() as usize + mem.len())
+    }
+
+    #[test]
+    fn test_round_trip_skips_zero_pages() {
+        let mut a = vec![0u8; 3 * PAGE_SIZE + 100];
+        a[5] = 1;
+        a[3 * PAGE_SIZE + 99] = 2;
+        let mut b = vec![7u8; 10];
+        let regions = [region_of(&a), region_of(&b)];
+
+        let mut image = vec![0u8; 4 * PAGE_SIZE];
+        let len = unsafe { save(&regions, &mut image) }.unwrap();
+        // Two of the four pages of `a` and the whole of `b`.
+        let expected = HEADER_LEN
+            + 2 * REGION_HEADER_LEN
+            + 2 * PAGE_HEADER_LEN
+            + PAGE_SIZE
+            + 100
+            + PAGE_HEADER_LEN
+            + 10;
+        assert_eq!(len, expected);
+        assert!(is_snapshot(&image));
+
+        let a_saved = a.clone();
+        a[..2 * PAGE_SIZE].fill(9);
+        b.fill(0);
+        unsafe { restore(&regions, &image[..len]) }.unwrap();
+        assert_eq!(a, a_saved);
+        assert_eq!(b, [7; 10]);
+
+        let mut small = vec![0u8; len - 1];
+        assert_eq!(
+            unsafe { save(&regions, &mut small) },
+            Err(SnapshotError::NoSpace)
+        );
+    }
+
+    #[test]
+    fn test_restore_rejects_mismatched_images() {
+        let mut a = vec![1u8; PAGE_SIZE];
+        let regions = [region_of(&a)];
+        let mut image = vec![0u8; 2 * PAGE_SIZE];
+        let len = unsafe { save(&regions, &mut image) }.unwrap();
+        a.fill(3);
+
+        let other = [Region::new(regions[0].start, regions[0].end - 8)];
+        assert_eq!(
+            unsafe { restore(&other, &image[..len]) },
+            Err(SnapshotError::LayoutMismatch)
+        );
+        assert_eq!(
+            unsafe { restore(&regions, &image[..len - 1]) },
+            Err(SnapshotError::Malformed)
+        );
+        image[4] = 9;
+        assert_eq!(
+            unsafe { restore(&regions, &image[..len]) },
+            Err(SnapshotError::BadVersion)
+        );
+        assert_eq!(
+            unsafe { restore(&regions, &[0; 16]) },
+            Err(SnapshotError::BadMagic)
+        );
+        // Nothing was written by the failed restores.
+        assert!(a.iter().all(|&b| b == 3));
+    }
+}
@@ -0,0 +1,217 @@
+//! Checked access to guest memory for syscall handlers.
+//!
+//! Handlers receive raw guest addresses: buffers, `iovec` arrays, path strings, result structs.
+//! The platform registers the memory a guest may hand over (image, heap, stacks) at boot with
+//! [`register_region`]; handlers check a range with [`access_ok`] before passing it on, or go
+//! through [`copy_from_user`], [`copy_to_user`], [`get_user`], [`put_user`] and
+//! [`strncpy_from_user`], which check first. A null or wrapping range, or one that is not inside
+//! a single region, fails with [`KError::Fault`] rather than reading or overwriting whatever
+//! lies there.
+//!
+//! Until a region is registered only null and wrapping ranges are rejected, so platforms without
+//! a memory map (and host tests) work as before.
+
+use core::mem::size_of;
+
+use crate::utils::GlobalCell;
+use crate::{KError, KResult};
+
+/// Regions that can be registered; adjacent ones merge and count once.
+pub const MAX_REGIONS: usize = 8;
+
+/// Longest path, NUL included, a handler copies in; longer ones fail with `ENAMETOOLONG`.
+pub const PATH_MAX: usize = 1024;
+
+#[derive(Clone, Copy)]
+struct Regions {
+    /// `[start, end)` ranges; the first `len` are in use.
+    ranges: [(usize, usize); MAX_REGIONS],
+    len: usize,
+}
+
+impl Regions {
+    const fn new() -> Self {
+        Self {
+            ranges: [(0, 0); MAX_REGIONS],
+            len: 0,
+        }
+    }
+
+    fn add(&mut self, start: usize, end: usize) -> bool {
+        if start >= end {
+            return false;
+        }
+        let ranges = &mut self.ranges[..self.len];
+        if let Some(r) = ranges.iter_mut().find(|r| r.1 == start || r.0 == end) {
+            *r = (r.0.min(start), r.1.max(end));
+            return true;
+        }
+        if self.len == MAX_REGIONS {
+            return false;
+        }
+        self.ranges[self.len] = (start, end);
+        self.len += 1;
+        true
+    }
+
+    /// End of the region holding `addr`; `usize::MAX` while none is registered.
+    fn limit(&self, addr: usize) -> Option<usize> {
+        if self.len == 0 {
+            return Some(usize::MAX);
+        }
+        self.ranges[..self.len]
+            .iter()
+            .find(|&&(start, end)| start <= addr && addr < end)
+            .map(|&(_, end)| end)
+    }
+}
+
+static REGIONS: GlobalCell<Regions> = GlobalCell::new(Regions::new());
+
+/// Let handlers accept guest ranges inside `[start, end)`. Returns false for an empty range or
+/// when [`MAX_REGIONS`] are taken.
+pub fn register_region(start: usize, end: usize) -> bool {
+    REGIONS.with_mut(|regions| regions.add(start, end))
+}
+
+/// Forget every region, which turns the checks back off.
+pub fn clear_regions() {
+    REGIONS.with_mut(|regions| *regions = Regions::new());
+}
+
+/// Whether the guest may hand over `[addr, addr + len)`. An empty range always may.
+pub fn access_ok(addr: usize, len: usize) -> bool {
+    if len == 0 {
+        return true;
+    }
+    let Some(end) = addr.checked_add(len) else {
+        return false;
+    };
+    addr != 0 && REGIONS.with(|regions| regions.limit(addr).is_some_and(|limit| end <= limit))
+}
+
+fn check(addr: usize, len: usize) -> KResult {
+    if access_ok(addr, len) {
+        Ok(())
+    } else {
+        Err(KError::Fault)
+    }
+}
+
+/// Copy `dst.len()` bytes from guest address `src`.
+///
+/// # Safety
+/// While no region is registered, `src` must be readable for `dst.len()` bytes.
+pub unsafe fn copy_from_user(dst: &mut [u8], src: usize) -> KResult {
+    check(src, dst.len())?;
+    core::ptr::copy_nonoverlapping(src as *const u8, dst.as_mut_ptr(), dst.len());
+    Ok(())
+}
+
+/// Copy `src` to guest address `dst`.
+///
+/// # Safety
+/// While no region is registered, `dst` must be writable for `src.len()` bytes.
+pub unsafe fn copy_to_user(dst: usize, src: &[u8]) -> KResult {
+    check(dst, src.len())?;
+    core::ptr::copy_nonoverlapping(src.as_ptr(), dst as *mut u8, src.len());
+    Ok(())
+}
+
+/// Read a `T` from guest address `src`, which need not be aligned.
+///
+/// # Safety
+/// Any bit pattern must be a valid `T`. While no region is registered, `src` must be readable
+/// for `size_of::<T>()` bytes.
+pub unsafe fn get_user<T: Copy>(src: usize) -> KResult<T> {
+    check(src, size_of::<T>())?;
+    Ok(core::ptr::read_unaligned(src as *const T))
+}
+
+/// Store `value` at guest address `dst`, which need not be aligned.
+///
+/// # Safety
+/// While no region is registered, `dst` must be writable for `size_of::<T>()` bytes.
+pub unsafe fn put_user<T>(dst: usize, value: T) -> KResult {
+    check(dst, size_of::<T>())?;
+    core::ptr::write_unaligned(dst as *mut T, value);
+    Ok(())
+}
+
+/// Copy the NUL-terminated string at guest address `src` into `dst`, NUL included, and return
+/// its length without the NUL. Fails with [`KError::NameTooLong`] if it does not fit and with
+/// [`KError::Fault`] if it runs out of its region first.
+///
+/// # Safety
+/// While no region is registered, `src` must point to a NUL-terminated string.
+pub unsafe fn strncpy_from_user(dst: &mut [u8], src: usize) -> KResult<usize> {
+    if src == 0 {
+        return Err(KError::Fault);
+    }
+    let limit = REGIONS
+        .with(|regions| regions.limit(src))
+        .ok_or(KError::Fault)?;
+    let readable = limit - src;
+    for (i, slot) in dst.iter_mut().enumerate() {
+        if i == readable {
+            return Err(KError::Fault);
+        }
+        let byte = *((src + i) as *const u8);
+        *slot = byte;
+        if byte == 0 {
+            return Ok(i);
+        }
+    }
+    Err(KError::NameTooLong)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_follow_registered_regions() {
+        let mut buf = *b"path\0\0\0\0abcdefg";
+        let base = buf.as_mut_ptr() as usize;
+        let mut name = [0u8; 8];
+
+        assert!(!access_ok(0, 1));
+        assert!(!access_ok(usize::MAX, 2));
+        assert_eq!(unsafe { strncpy_from_user(&mut name, base) }, Ok(4));
+
+        // Two adjacent registrations make one region; the rest of the buffer stays outside.
+        assert!(register_region(base, base + 4));
+        assert!(register_region(base + 4, base + 12));
+        assert!(access_ok(base, 12));
+        assert!(!access_ok(base + 8, 5));
+        assert!(!access_ok(base - 1, 1));
+        assert!(access_ok(0, 0));
+
+        assert_eq!(
+            unsafe { get_user::<u32>(base + 1) },
+            Ok(u32::from_le_bytes(*b"ath\0"))
+        );
+        assert_eq!(unsafe { strncpy_from_user(&mut name, base) }, Ok(4));
+        assert_eq!(&name[..5], b"path\0");
+        assert_eq!(
+            unsafe { strncpy_from_user(&mut name[..3], base) },
+            Err(KError::NameTooLong)
+        );
+        assert_eq!(unsafe { put_user(base + 4, 0x2121u16) }, Ok(()));
+        assert_eq!(unsafe { put_user(base + 11, 0u16) }, Err(KError::Fault));
+        assert_eq!(unsafe { copy_to_user(base + 12, b"x") }, Err(KError::Fault));
+        // "!!" then "\0\0": fine, but "abc.." runs out of the region before its NUL.
+        assert_eq!(unsafe { strncpy_from_user(&mut name, base + 4) }, Ok(2));
+        assert_eq!(
+            unsafe { strncpy_from_user(&mut name, base + 8) },
+            Err(KError::Fault)
+        );
+        assert_eq!(
+            unsafe { strncpy_from_user(&mut name, base + 12) },
+            Err(KError::Fault)
+        );
+
+        clear_regions();
+        assert!(access_ok(base + 12, 3));
+    }
+}
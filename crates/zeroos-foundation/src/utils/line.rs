@@ -0,0 +1,192 @@
+//! Console lines of strings and integers formatted without `core::fmt`.
+//!
+//! `core::fmt` builds `Arguments`, calls every piece through a vtable and runs the `Formatter`
+//! padding logic even for a bare `{}`. [`Line`] covers the common "label + integers" line with
+//! direct calls instead: strings go to the sink as they are and integers through a digit loop
+//! on the stack. There is no width, fill, precision or alignment.
+
+/// A line written piece by piece to `sink`; [`end`](Line::end) terminates it.
+pub struct Line {
+    sink: fn(&[u8]),
+}
+
+impl Line {
+    pub const fn new(sink: fn(&[u8])) -> Self {
+        Self { sink }
+    }
+
+    #[inline(always)]
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        (self.sink)(bytes);
+    }
+
+    #[inline(always)]
+    pub fn push_str(&mut self, s: &str) {
+        self.push_bytes(s.as_bytes());
+    }
+
+    /// Append `v` in decimal.
+    pub fn push_u64(&mut self, mut v: u64) {
+        let mut digits = [0u8; 20];
+        let mut i = digits.len();
+        loop {
+            i -= 1;
+            digits[i] = b'0' + (v % 10) as u8;
+            v /= 10;
+            if v == 0 {
+                break;
+            }
+        }
+        self.push_bytes(&digits[i..]);
+    }
+
+    /// Append `v` in decimal, with a leading `-` if negative.
+    pub fn push_i64(&mut self, v: i64) {
+        if v < 0 {
+            self.push_bytes(b"-");
+        }
+        self.push_u64(v.unsigned_abs());
+    }
+
+    /// Append `v` in lowercase hex with a `0x` prefix, as `{:#x}` does.
+    pub fn push_hex(&mut self, mut v: u64) {
+        let mut digits = [0u8; 18];
+        let mut i = digits.len();
+        loop {
+            i -= 1;
+            digits[i] = b"0123456789abcdef"[(v & 0xf) as usize];
+            v >>= 4;
+            if v == 0 {
+                break;
+            }
+        }
+        i -= 2;
+        digits[i..i + 2].copy_from_slice(b"0x");
+        self.push_bytes(&digits[i..]);
+    }
+
+    /// Append the newline.
+    #[inline(always)]
+    pub fn end(mut self) {
+        self.push_bytes(b"\n");
+    }
+}
+
+/// A value [`Line`] can append without `core::fmt`.
+pub trait Put {
+    fn put(&self, line: &mut Line);
+}
+
+impl<T: Put + ?Sized> Put for &T {
+    #[inline(always)]
+    fn put(&self, line: &mut Line) {
+        (**self).put(line);
+    }
+}
+
+impl Put for str {
+    #[inline(always)]
+    fn put(&self, line: &mut Line) {
+        line.push_str(self);
+    }
+}
+
+impl Put for bool {
+    #[inline(always)]
+    fn put(&self, line: &mut Line) {
+        line.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+impl Put for char {
+    #[inline(always)]
+    fn put(&self, line: &mut Line) {
+        line.push_str(self.encode_utf8(&mut [0; 4]));
+    }
+}
+
+macro_rules! impl_put_int {
+    ($push:ident as $wide:ty: $($ty:ty),*) => {$(
+        impl Put for $ty {
+            #[inline(always)]
+            fn put(&self, line: &mut Line) {
+                line.$push(*self as $wide);
+            }
+        }
+    )*};
+}
+
+impl_put_int!(push_u64 as u64: u8, u16, u32, u64, usize);
+impl_put_int!(push_i64 as i64: i8, i16, i32, i64, isize);
+
+/// Puts an unsigned integer in hex, e.g. `Hex(255u32)` as `0xff`.
+#[derive(Clone, Copy, Debug)]
+pub struct Hex<T>(pub T);
+
+macro_rules! impl_put_hex {
+    ($($ty:ty),*) => {$(
+        impl Put for Hex<$ty> {
+            #[inline(always)]
+            fn put(&self, line: &mut Line) {
+                line.push_hex(self.0 as u64);
+            }
+        }
+    )*};
+}
+
+impl_put_hex!(u8, u16, u32, u64, usize);
+
+/// Formats a byte slice as lowercase hex digits with no prefix, for dumps over `core::fmt`.
+impl core::fmt::Display for Hex<&[u8]> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{cell::RefCell, vec::Vec};
+
+    std::thread_local!(static OUT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) });
+
+    fn capture(bytes: &[u8]) {
+        OUT.with_borrow_mut(|out| out.extend_from_slice(bytes));
+    }
+
+    #[test]
+    fn test_put_formats_like_display() {
+        let mut line = Line::new(capture);
+        for piece in [
+            &"n=" as &dyn Put,
+            &0u8,
+            &' ',
+            &u64::MAX,
+            &' ',
+            &i64::MIN,
+            &' ',
+            &-7i32,
+            &' ',
+            &Hex(0u32),
+            &' ',
+            &Hex(0xdead_beefusize),
+            &' ',
+            &true,
+        ] {
+            piece.put(&mut line);
+        }
+        line.end();
+        assert_eq!(
+            OUT.take(),
+            b"n=0 18446744073709551615 -9223372036854775808 -7 0x0 0xdeadbeef true\n"
+        );
+    }
+
+    #[test]
+    fn test_hex_bytes_display() {
+        let bytes: &[u8] = &[0x00, 0x0f, 0xab];
+        assert_eq!(std::format!("{}", Hex(bytes)), "000fab");
+    }
+}
@@ -1,7 +1,15 @@
 pub mod global;
+pub mod line;
+pub mod once;
 pub mod random;
 pub mod stack;
+pub mod strings;
+pub mod tls;
 
 pub use global::{GlobalCell, GlobalOption};
+pub use line::{Hex, Line, Put};
+pub use once::{KLazy, KOnce};
 pub use random::generate_random_bytes;
 pub use stack::DownwardStack;
+pub use strings::NulStrings;
+pub use tls::TlsImage;
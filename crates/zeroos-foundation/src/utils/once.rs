@@ -0,0 +1,203 @@
+//! One-time initialization for globals that outlive the cooperative single-hart model.
+//!
+//! Unlike [`GlobalOption`](super::GlobalOption), initialization is an atomic state transition,
+//! so a value published by one hart (or a thread preempted mid-init) is only ever observed
+//! complete. A contender spins until the initializer finishes, which is why initializers must
+//! not block on another thread: under the cooperative scheduler nothing else would run.
+//!
+//! An initializer that panics poisons the cell, and every later access panics too, rather than
+//! retrying against half-built state.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+const POISONED: u8 = 3;
+
+pub struct KOnce<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// SAFETY: the value is written once, before `COMPLETE` is published with release ordering, and
+// only shared afterwards; sending it to the hart that drops it requires `T: Send`.
+unsafe impl<T: Send + Sync> Sync for KOnce<T> {}
+
+/// Poisons the cell if the initializer unwinds.
+struct Poison<'a>(&'a AtomicU8);
+
+impl Drop for Poison<'_> {
+    fn drop(&mut self) {
+        self.0.store(POISONED, Ordering::Release);
+    }
+}
+
+impl<T> KOnce<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(INCOMPLETE),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Initialize with `f` unless already initialized, then return the value.
+    ///
+    /// # Panics
+    /// If this or an earlier initializer panicked.
+    pub fn call_once(&self, f: impl FnOnce() -> T) -> &T {
+        let mut f = Some(f);
+        loop {
+            match self.state.compare_exchange_weak(
+                INCOMPLETE,
+                RUNNING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    let poison = Poison(&self.state);
+                    let value = (f.take().unwrap())();
+                    core::mem::forget(poison);
+                    // SAFETY: `RUNNING` gives this caller exclusive access until `COMPLETE`.
+                    unsafe { (*self.value.get()).write(value) };
+                    self.state.store(COMPLETE, Ordering::Release);
+                }
+                Err(COMPLETE) => {}
+                Err(POISONED) => panic!("KOnce initializer panicked"),
+                // RUNNING on another hart or thread, or a spurious failure: retry.
+                Err(_) => {
+                    core::hint::spin_loop();
+                    continue;
+                }
+            }
+            // SAFETY: the state is `COMPLETE`, so the value is initialized and never written again.
+            return unsafe { (*self.value.get()).assume_init_ref() };
+        }
+    }
+
+    /// Initialize with `value`; gives it back if the cell was already initialized.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        self.call_once(|| value.take().unwrap());
+        match value {
+            None => Ok(()),
+            Some(value) => Err(value),
+        }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.is_completed()
+            // SAFETY: `COMPLETE` was observed with acquire ordering.
+            .then(|| unsafe { (*self.value.get()).assume_init_ref() })
+    }
+
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        (*self.state.get_mut() == COMPLETE)
+            // SAFETY: `&mut self` rules out any other access; the value is initialized.
+            .then(|| unsafe { self.value.get_mut().assume_init_mut() })
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.state.load(Ordering::Acquire) == POISONED
+    }
+}
+
+impl<T> Default for KOnce<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for KOnce<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == COMPLETE {
+            // SAFETY: initialized, and never touched again.
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+/// A value computed by `F` on first dereference, for `static`s that need non-const init.
+pub struct KLazy<T, F = fn() -> T> {
+    once: KOnce<T>,
+    init: UnsafeCell<Option<F>>,
+}
+
+// SAFETY: `init` is only taken by the caller that won the `KOnce` race.
+unsafe impl<T: Send + Sync, F: Send> Sync for KLazy<T, F> {}
+
+impl<T, F: FnOnce() -> T> KLazy<T, F> {
+    pub const fn new(init: F) -> Self {
+        Self {
+            once: KOnce::new(),
+            init: UnsafeCell::new(Some(init)),
+        }
+    }
+
+    pub fn force(this: &Self) -> &T {
+        this.once.call_once(|| {
+            // SAFETY: only the initializing caller runs this closure, exactly once.
+            let init = unsafe { (*this.init.get()).take() };
+            init.expect("KLazy initializer taken twice")()
+        })
+    }
+
+    pub fn get(this: &Self) -> Option<&T> {
+        this.once.get()
+    }
+}
+
+impl<T, F: FnOnce() -> T> Deref for KLazy<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        Self::force(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_once_initializes_once() {
+        let once = KOnce::new();
+        assert!(once.get().is_none());
+        assert_eq!(*once.call_once(|| 1), 1);
+        assert_eq!(*once.call_once(|| 2), 1);
+        assert_eq!(once.set(3), Err(3));
+        assert_eq!(once.get(), Some(&1));
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        static LAZY: KLazy<usize> = KLazy::new(|| CALLS.fetch_add(1, Ordering::Relaxed) + 41);
+        assert_eq!(KLazy::get(&LAZY), None);
+        assert_eq!(*LAZY, 41);
+        assert_eq!(*LAZY, 41);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_panicking_initializer_poisons() {
+        let once = KOnce::<u32>::new();
+        let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            once.call_once(|| panic!("init failed"));
+        }));
+        assert!(result.is_err());
+        assert!(once.is_poisoned());
+        let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            once.call_once(|| 1);
+        }));
+        assert!(result.is_err());
+        assert!(once.get().is_none());
+    }
+}
@@ -0,0 +1,61 @@
+//! Fixed-capacity lists of NUL-terminated strings borrowed from a `'static` blob.
+
+/// Up to `N` entries of a blob of strings that each end in a NUL byte. The entries are not
+/// copied, and each keeps its NUL, so they can go straight into a C `argv` or `envp`.
+pub struct NulStrings<const N: usize> {
+    items: [&'static [u8]; N],
+    len: usize,
+}
+
+impl<const N: usize> NulStrings<N> {
+    pub const fn new() -> Self {
+        Self {
+            items: [&[]; N],
+            len: 0,
+        }
+    }
+
+    /// Split `blob` into the entries `keep` accepts (it sees them without the NUL). A last entry
+    /// missing its NUL is dropped, as is every accepted entry past the first `N`.
+    pub fn parse(blob: &'static [u8], keep: impl Fn(&[u8]) -> bool) -> Self {
+        let mut list = Self::new();
+        let mut rest = blob;
+        while let Some(end) = rest.iter().position(|&b| b == 0) {
+            let (entry, tail) = rest.split_at(end + 1);
+            rest = tail;
+            if !keep(&entry[..end]) {
+                continue;
+            }
+            if list.len == N {
+                break;
+            }
+            list.items[list.len] = entry;
+            list.len += 1;
+        }
+        list
+    }
+
+    /// The entries, each ending in NUL.
+    pub fn as_slice(&self) -> &[&'static [u8]] {
+        &self.items[..self.len]
+    }
+}
+
+impl<const N: usize> Default for NulStrings<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keeps_terminated_entries() {
+        let list = NulStrings::<2>::parse(b"a\0\0bc\0d\0e", |s| !s.is_empty());
+        assert_eq!(list.as_slice(), [b"a\0" as &[u8], b"bc\0"]);
+        let list = NulStrings::<4>::parse(b"a\0\0bc\0d\0e", |_| true);
+        assert_eq!(list.as_slice(), [b"a\0" as &[u8], b"\0", b"bc\0", b"d\0"]);
+    }
+}
@@ -0,0 +1,97 @@
+//! ELF TLS template discovery and per-thread block setup.
+//!
+//! The linker script places `.tdata` and `.tbss` back to back and exports their bounds, so the
+//! template can be found without program headers. RISC-V uses TLS variant I with no gap above
+//! `tp`: a thread's `tp` is simply the start of its block.
+
+use core::alloc::Layout;
+
+/// Alignment the linker script gives `.tdata` and `.tbss`.
+pub const TLS_ALIGN: usize = 16;
+
+/// The initialization image for thread-local storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TlsImage {
+    /// Address of the `.tdata` initializer bytes.
+    pub init: usize,
+    /// Bytes copied from `init` into every block.
+    pub init_len: usize,
+    /// Full block size; bytes past `init_len` are zeroed (`.tbss`).
+    pub mem_len: usize,
+    pub align: usize,
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+extern "C" {
+    static __tdata_start: u8;
+    static __tdata_end: u8;
+    static __tbss_end: u8;
+}
+
+impl TlsImage {
+    /// Read the template bounds exported by the linker script.
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    pub fn discover() -> Self {
+        let start = core::ptr::addr_of!(__tdata_start) as usize;
+        let data_end = core::ptr::addr_of!(__tdata_end) as usize;
+        let end = core::ptr::addr_of!(__tbss_end) as usize;
+        Self {
+            init: start,
+            init_len: data_end - start,
+            mem_len: end - start,
+            align: TLS_ALIGN,
+        }
+    }
+
+    /// Layout of one thread's block, or `None` for an empty template.
+    pub fn layout(&self) -> Option<Layout> {
+        if self.mem_len == 0 {
+            return None;
+        }
+        Layout::from_size_align(self.mem_len, self.align.max(TLS_ALIGN)).ok()
+    }
+
+    /// Fill `block` from the template and return the `tp` value for it.
+    ///
+    /// # Safety
+    /// `block` must be valid for writes of `layout()` and `init` readable for `init_len` bytes.
+    pub unsafe fn init_block(&self, block: *mut u8) -> usize {
+        core::ptr::copy_nonoverlapping(self.init as *const u8, block, self.init_len);
+        core::ptr::write_bytes(block.add(self.init_len), 0, self.mem_len - self.init_len);
+        block as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_block_copies_and_zeroes() {
+        let template = [1u8, 2, 3];
+        let image = TlsImage {
+            init: template.as_ptr() as usize,
+            init_len: template.len(),
+            mem_len: 8,
+            align: 8,
+        };
+        let layout = image.layout().unwrap();
+        assert_eq!(layout.align(), TLS_ALIGN);
+
+        let mut block = [0xffu8; 8];
+        let tp = unsafe { image.init_block(block.as_mut_ptr()) };
+        assert_eq!(tp, block.as_ptr() as usize);
+        assert_eq!(block, [1, 2, 3, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_empty_template_has_no_layout() {
+        let image = TlsImage {
+            init: 0,
+            init_len: 0,
+            mem_len: 0,
+            align: TLS_ALIGN,
+        };
+        assert!(image.layout().is_none());
+    }
+}
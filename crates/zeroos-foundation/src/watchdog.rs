@@ -0,0 +1,86 @@
+//! Cycle-budget watchdog.
+//!
+//! A zkVM proves at most a fixed number of cycles, so a guest that runs past them wastes prover
+//! time on a run that cannot be proven. The platform [`arm`]s the watchdog with the budget, counted
+//! on `__platform_cycle_count` from reset, so boot is included. The trap and scheduler paths call
+//! [`check`]. The first check past the budget prints the budget, the cycle count, the running
+//! thread and the interrupted pc, then exits with [`EXIT_CODE`], which also runs the platform's
+//! exit dumps.
+//!
+//! Checks only run on traps and context switches. A loop that does neither is caught at its next
+//! one.
+
+use crate::kfn::{kexit, scheduler::kcurrent_tid, time::kcycles};
+use crate::utils::{GlobalCell, Hex, Line, Put};
+
+/// Exit status of a guest stopped by the watchdog, as `timeout(1)` uses.
+pub const EXIT_CODE: i32 = 124;
+
+/// Cycle count past which the guest is stopped; `u64::MAX` while disarmed.
+static LIMIT: GlobalCell<u64> = GlobalCell::new(u64::MAX);
+
+extern "C" {
+    fn __platform_stdout_write(msg: *const u8, len: usize);
+}
+
+/// Stop the guest once the cycle counter passes `budget`.
+pub fn arm(budget: u64) {
+    LIMIT.with_mut(|limit| *limit = budget);
+}
+
+pub fn disarm() {
+    arm(u64::MAX);
+}
+
+/// The armed budget.
+pub fn budget() -> Option<u64> {
+    LIMIT.with(|&limit| (limit != u64::MAX).then_some(limit))
+}
+
+/// Stop the guest if it is over budget. `pc` is the interrupted instruction, or 0 outside a
+/// trap.
+#[inline(always)]
+pub fn check(pc: usize) {
+    let now = kcycles();
+    if now > LIMIT.with(|&limit| limit) {
+        expired(now, pc);
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn expired(now: u64, pc: usize) -> ! {
+    fn sink(bytes: &[u8]) {
+        // SAFETY: `bytes` is a valid slice for the duration of the call.
+        unsafe { __platform_stdout_write(bytes.as_ptr(), bytes.len()) }
+    }
+
+    let mut line = Line::new(sink);
+    "watchdog: cycle budget ".put(&mut line);
+    LIMIT.with(|limit| limit.put(&mut line));
+    " exceeded at cycle ".put(&mut line);
+    now.put(&mut line);
+    " (tid ".put(&mut line);
+    kcurrent_tid().put(&mut line);
+    if pc != 0 {
+        ", pc ".put(&mut line);
+        Hex(pc).put(&mut line);
+    }
+    ")".put(&mut line);
+    line.end();
+    kexit(EXIT_CODE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arm_and_disarm() {
+        assert_eq!(budget(), None);
+        arm(1_000_000);
+        assert_eq!(budget(), Some(1_000_000));
+        disarm();
+        assert_eq!(budget(), None);
+    }
+}
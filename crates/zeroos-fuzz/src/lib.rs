@@ -0,0 +1,280 @@
+#![no_std]
+
+//! Run a fuzz target inside the guest on inputs replayed from the input tape.
+//!
+//! A fuzzer on the host finds crashing inputs; to check that one crashes the guest the same way
+//! under the zkVM runtime, the host packs the inputs into a tape ([`tape::push_record`]) and
+//! commits it as the guest's input buffer. [`fuzz_target!`] defines the guest's `main`, which
+//! runs the target on each record of `/dev/input` in order and exits 0 once the tape is used up.
+//!
+//! The first crash ends the run with [`CRASH_EXIT_CODE`] and a report naming the input by its
+//! index on the tape, length and CRC32, followed by the panic message or, with the `trap`
+//! feature, the trap frame of the CPU fault. Guests are deterministic, so the same tape gives
+//! the same report on every run.
+//!
+//! Needs a std guest built with `vfs-device-stdin`, since the tape is only served through the
+//! VFS.
+
+extern crate alloc;
+
+pub mod tape;
+
+use core::fmt::{self, Display, Write};
+
+use foundation::utils::GlobalCell;
+
+/// Exit status of a run that crashed, libFuzzer's default `-error_exitcode`.
+pub const CRASH_EXIT_CODE: i32 = 77;
+
+/// Exit status when the guest has no `/dev/input` to read.
+pub const NO_INPUT_EXIT_CODE: i32 = 2;
+
+extern "C" {
+    fn __platform_stdout_write(msg: *const u8, len: usize);
+    fn __platform_exit(code: i32) -> !;
+}
+
+struct Console;
+
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // SAFETY: `s` is `s.len()` readable bytes.
+        unsafe { __platform_stdout_write(s.as_ptr(), s.len()) };
+        Ok(())
+    }
+}
+
+/// The input a crash happened on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputId {
+    /// Position on the tape, from 0.
+    pub index: usize,
+    pub len: usize,
+    pub crc32: u32,
+}
+
+impl InputId {
+    pub fn of(index: usize, input: &[u8]) -> Self {
+        Self {
+            index,
+            len: input.len(),
+            crc32: checksum::crc32(input),
+        }
+    }
+}
+
+/// The input the target is running on, if any.
+static CURRENT: GlobalCell<Option<InputId>> = GlobalCell::new(None);
+
+/// Integer registers x1..x31, in the ABI names of [`FaultFrame::regs`].
+pub const REG_NAMES: [&str; 31] = [
+    "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5", "a6",
+    "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+/// The CPU state at a fault in the target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FaultFrame {
+    pub mcause: usize,
+    pub mepc: usize,
+    pub mtval: usize,
+    /// x1..x31.
+    pub regs: [usize; 31],
+}
+
+/// What ended the run.
+#[derive(Clone, Copy)]
+pub enum Crash<'a> {
+    Panic(&'a dyn Display),
+    Fault(&'a FaultFrame),
+}
+
+/// Write the crash report: the input, then the panic message or the fault and its registers.
+pub fn write_report(w: &mut impl Write, input: Option<&InputId>, crash: Crash<'_>) -> fmt::Result {
+    match input {
+        Some(input) => writeln!(
+            w,
+            "fuzz: crash on input {} (len {}, crc32 0x{:08x})",
+            input.index, input.len, input.crc32
+        )?,
+        None => writeln!(w, "fuzz: crash outside the target")?,
+    }
+    match crash {
+        Crash::Panic(message) => writeln!(w, "fuzz: {}", message),
+        Crash::Fault(frame) => {
+            writeln!(
+                w,
+                "fuzz: trap mcause=0x{:x} mepc=0x{:x} mtval=0x{:x}",
+                frame.mcause, frame.mepc, frame.mtval
+            )?;
+            for (names, values) in REG_NAMES.chunks(4).zip(frame.regs.chunks(4)) {
+                for (name, value) in names.iter().zip(values) {
+                    write!(w, "  {:<3} 0x{:016x}", name, value)?;
+                }
+                writeln!(w)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Report `crash` against the current input and exit with [`CRASH_EXIT_CODE`].
+pub fn crashed(crash: Crash<'_>) -> ! {
+    let input = CURRENT.with(|current| *current);
+    let _ = write_report(&mut Console, input.as_ref(), crash);
+    // SAFETY: the platform provides `__platform_exit`; it does not return.
+    unsafe { __platform_exit(CRASH_EXIT_CODE) }
+}
+
+/// Run `target` on every input of the tape, then exit 0.
+///
+/// Install the panic hook first ([`fuzz_target!`] does), so panics are reported by
+/// [`crashed`].
+pub fn run(mut target: impl FnMut(&[u8])) -> ! {
+    #[cfg(feature = "trap")]
+    trap::install();
+
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "linux")] {
+            let Some(mut inputs) = tape::Inputs::open() else {
+                let _ = Console.write_str("fuzz: no /dev/input; build with vfs-device-stdin\n");
+                // SAFETY: the platform provides `__platform_exit`; it does not return.
+                unsafe { __platform_exit(NO_INPUT_EXIT_CODE) }
+            };
+            let mut input = alloc::vec::Vec::new();
+            let mut index = 0;
+            while inputs.next_into(&mut input) {
+                CURRENT.with_mut(|current| *current = Some(InputId::of(index, &input)));
+                target(&input);
+                index += 1;
+            }
+            CURRENT.with_mut(|current| *current = None);
+            let _ = writeln!(Console, "fuzz: {} inputs ran without a crash", index);
+            drop(inputs);
+            // SAFETY: plain process exit; runs the guest's `atexit` handlers first.
+            unsafe { libc::exit(0) }
+        } else {
+            let _ = &mut target;
+            let _ = Console.write_str("fuzz: no /dev/input; build with vfs-device-stdin\n");
+            // SAFETY: the platform provides `__platform_exit`; it does not return.
+            unsafe { __platform_exit(NO_INPUT_EXIT_CODE) }
+        }
+    }
+}
+
+#[cfg(feature = "trap")]
+mod trap {
+    cfg_if::cfg_if! {
+        if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
+            use arch_riscv::{register_trap_hook, Exception, Trap, TrapAction, TrapFilter, TrapFrame};
+
+            use crate::{crashed, Crash, FaultFrame};
+
+            /// Runs after every other hook, so only faults nothing else handles are reported.
+            pub(crate) fn install() {
+                register_trap_hook(TrapFilter::AnyException, i32::MIN, on_exception);
+            }
+
+            fn on_exception(regs: &mut TrapFrame, trap: Trap) -> TrapAction {
+                if matches!(
+                    trap,
+                    Trap::Exception(
+                        Exception::UserEnvCall
+                            | Exception::SupervisorEnvCall
+                            | Exception::MachineEnvCall
+                            | Exception::Breakpoint
+                    )
+                ) {
+                    return TrapAction::Pass;
+                }
+                let frame = FaultFrame {
+                    mcause: regs.mcause,
+                    mepc: regs.mepc,
+                    mtval: regs.mtval,
+                    regs: [
+                        regs.ra, regs.sp, regs.gp, regs.tp, regs.t0, regs.t1, regs.t2, regs.s0,
+                        regs.s1, regs.a0, regs.a1, regs.a2, regs.a3, regs.a4, regs.a5, regs.a6,
+                        regs.a7, regs.s2, regs.s3, regs.s4, regs.s5, regs.s6, regs.s7, regs.s8,
+                        regs.s9, regs.s10, regs.s11, regs.t3, regs.t4, regs.t5, regs.t6,
+                    ],
+                };
+                crashed(Crash::Fault(&frame))
+            }
+        } else {
+            pub(crate) fn install() {}
+        }
+    }
+}
+
+/// Define the guest's `main` to run `$body` on each input of the tape, like `cargo fuzz`'s
+/// macro of the same name:
+///
+/// ```ignore
+/// #![no_main]
+///
+/// zeroos_fuzz::fuzz_target!(|data: &[u8]| {
+///     let _ = my_crate::parse(data);
+/// });
+/// ```
+///
+/// Panics in the target are reported through a std panic hook.
+#[macro_export]
+macro_rules! fuzz_target {
+    (|$data:ident: &[u8]| $body:expr) => {
+        #[no_mangle]
+        fn main() -> ! {
+            ::std::panic::set_hook(::std::boxed::Box::new(|info| {
+                $crate::crashed($crate::Crash::Panic(info))
+            }));
+            $crate::run(|$data: &[u8]| {
+                $body;
+            })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_report_names_the_input() {
+        let input = InputId::of(3, b"123456789");
+        assert_eq!(input.crc32, 0xcbf4_3926);
+
+        let mut out = String::new();
+        write_report(&mut out, Some(&input), Crash::Panic(&"index out of bounds")).unwrap();
+        assert_eq!(
+            out,
+            "fuzz: crash on input 3 (len 9, crc32 0xcbf43926)\nfuzz: index out of bounds\n"
+        );
+    }
+
+    #[test]
+    fn test_fault_report_lists_registers() {
+        let mut regs = [0; 31];
+        regs[1] = 0x8000_0000; // sp
+        regs[30] = 0xff; // t6
+        let frame = FaultFrame {
+            mcause: 0xd,
+            mepc: 0x1234,
+            mtval: 0x10,
+            regs,
+        };
+        let mut out = String::new();
+        write_report(&mut out, None, Crash::Fault(&frame)).unwrap();
+
+        let lines: std::vec::Vec<_> = out.lines().collect();
+        assert_eq!(lines[0], "fuzz: crash outside the target");
+        assert_eq!(lines[1], "fuzz: trap mcause=0xd mepc=0x1234 mtval=0x10");
+        assert_eq!(lines.len(), 2 + 8);
+        assert!(lines[2].starts_with("  ra  0x0000000000000000  sp  0x0000000080000000"));
+        assert_eq!(
+            lines[9],
+            "  t4  0x0000000000000000  t5  0x0000000000000000  t6  0x00000000000000ff"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Fuzz inputs as records of the `/dev/input` tape.
+//!
+//! A replay tape holds one input per record: a little-endian `u32` length followed by the input
+//! bytes. [`push_record`] builds it on the host from the crash files a fuzzer found; the host
+//! commits it as the guest's input buffer and [`Inputs`] reads it back, one input per record,
+//! empty inputs included.
+
+use alloc::vec::Vec;
+
+/// Append `input` to `tape` as one record.
+///
+/// # Panics
+/// If `input` is longer than `u32::MAX` bytes.
+pub fn push_record(tape: &mut Vec<u8>, input: &[u8]) {
+    let len = u32::try_from(input.len()).expect("fuzz input longer than a tape record");
+    tape.extend_from_slice(&len.to_le_bytes());
+    tape.extend_from_slice(input);
+}
+
+#[cfg(target_os = "linux")]
+pub use reader::Inputs;
+
+#[cfg(target_os = "linux")]
+mod reader {
+    use alloc::vec::Vec;
+
+    /// `ioctl` request for the unread bytes of the whole tape, as
+    /// `zeroos_device_stdin::tape::INPUT_TAPE_REMAINING`.
+    const INPUT_TAPE_REMAINING: u64 = 0x5a01;
+
+    /// The tape at `/dev/input`, read one record at a time.
+    pub struct Inputs {
+        fd: libc::c_int,
+    }
+
+    impl Inputs {
+        /// Open `/dev/input`; `None` if the guest was built without `vfs-device-stdin`.
+        pub fn open() -> Option<Self> {
+            // SAFETY: the path is a NUL-terminated string.
+            let fd = unsafe { libc::open(c"/dev/input".as_ptr(), libc::O_RDONLY) };
+            (fd >= 0).then_some(Self { fd })
+        }
+
+        fn tape_remaining(&self) -> u64 {
+            let mut left = 0u64;
+            // SAFETY: the request stores a `u64` at the pointer.
+            let ret = unsafe { libc::ioctl(self.fd, INPUT_TAPE_REMAINING as _, &mut left) };
+            if ret < 0 {
+                0
+            } else {
+                left
+            }
+        }
+
+        /// Replace the contents of `buf` with the next record; false at the end of the tape.
+        pub fn next_into(&mut self, buf: &mut Vec<u8>) -> bool {
+            let before = self.tape_remaining();
+            if before == 0 {
+                return false;
+            }
+            buf.clear();
+            let mut pending: libc::c_int = 0;
+            // SAFETY: `FIONREAD` stores a `c_int` at the pointer.
+            if unsafe { libc::ioctl(self.fd, libc::FIONREAD as _, &mut pending) } == 0 {
+                buf.reserve(pending.max(0) as usize);
+            }
+            // Reads stop at the record's end, so 0 marks it even if `FIONREAD` undercounted.
+            loop {
+                if buf.len() == buf.capacity() {
+                    buf.reserve(256);
+                }
+                let spare = buf.spare_capacity_mut();
+                // SAFETY: `spare` is writable for its length.
+                let n = unsafe { libc::read(self.fd, spare.as_mut_ptr().cast(), spare.len()) };
+                if n <= 0 {
+                    break;
+                }
+                // SAFETY: `read` initialized the first `n` spare bytes.
+                unsafe { buf.set_len(buf.len() + n as usize) };
+            }
+            // A header cut short by the end of the buffer reads as nothing, forever.
+            self.tape_remaining() != before
+        }
+    }
+
+    impl Drop for Inputs {
+        fn drop(&mut self) {
+            // SAFETY: `fd` was opened by `open` and is closed only here.
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_are_length_prefixed() {
+        let mut tape = Vec::new();
+        push_record(&mut tape, b"abc");
+        push_record(&mut tape, b"");
+        push_record(&mut tape, b"de");
+        assert_eq!(tape, b"\x03\0\0\0abc\0\0\0\0\x02\0\0\0de");
+    }
+}
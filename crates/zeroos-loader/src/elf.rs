@@ -0,0 +1,270 @@
+//! The parts of an ELF file the loader needs: the file header and the program headers.
+//!
+//! Both classes are read, but only the one matching the target's pointer width is accepted, for
+//! the target's machine, little-endian.
+
+use crate::{LoadError, Source};
+
+pub const ET_EXEC: u16 = 2;
+pub const ET_DYN: u16 = 3;
+
+pub const PT_LOAD: u32 = 1;
+pub const PT_DYNAMIC: u32 = 2;
+pub const PT_INTERP: u32 = 3;
+pub const PT_PHDR: u32 = 6;
+pub const PT_TLS: u32 = 7;
+
+pub const PF_X: u32 = 1;
+pub const PF_W: u32 = 2;
+pub const PF_R: u32 = 4;
+
+pub const EM_X86_64: u16 = 62;
+pub const EM_RISCV: u16 = 243;
+
+/// Program headers read; files with more are refused.
+pub const MAX_PHDRS: usize = 16;
+
+const ELFCLASS32: u8 = 1;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+
+const ELFCLASS_NATIVE: u8 = if cfg!(target_pointer_width = "64") {
+    ELFCLASS64
+} else {
+    ELFCLASS32
+};
+
+cfg_if::cfg_if! {
+    if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
+        /// `e_machine` of images this target runs.
+        pub const EM_NATIVE: u16 = EM_RISCV;
+    } else if #[cfg(target_arch = "x86_64")] {
+        /// `e_machine` of images this target runs.
+        pub const EM_NATIVE: u16 = EM_X86_64;
+    } else {
+        /// `e_machine` of images this target runs; none here.
+        pub const EM_NATIVE: u16 = 0;
+    }
+}
+
+/// One program header, widened to the 64-bit layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProgramHeader {
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+impl ProgramHeader {
+    /// `PROT_*` bits for the segment's `PF_*` flags.
+    pub fn prot(&self) -> usize {
+        let mut prot = 0;
+        if self.p_flags & PF_R != 0 {
+            prot |= libc::PROT_READ;
+        }
+        if self.p_flags & PF_W != 0 {
+            prot |= libc::PROT_WRITE;
+        }
+        if self.p_flags & PF_X != 0 {
+            prot |= libc::PROT_EXEC;
+        }
+        prot as usize
+    }
+}
+
+/// A parsed and checked ELF header with its program headers.
+#[derive(Clone, Debug)]
+pub struct Elf {
+    pub e_type: u16,
+    pub e_entry: u64,
+    pub e_phoff: u64,
+    pub e_phentsize: u16,
+    phdrs: [ProgramHeader; MAX_PHDRS],
+    phnum: usize,
+}
+
+impl Elf {
+    /// Read the headers from `src` and check that the loader can place the image: a static
+    /// executable for this target whose loadable segments are consistent with the file.
+    pub fn parse<S: Source + ?Sized>(src: &S) -> Result<Self, LoadError> {
+        let mut ident = [0u8; 16];
+        src.read_at(&mut ident, 0)?;
+        if ident[..4] != *b"\x7fELF" {
+            return Err(LoadError::NotElf);
+        }
+        if ident[4] != ELFCLASS_NATIVE || ident[5] != ELFDATA2LSB || ident[6] != EV_CURRENT {
+            return Err(LoadError::Unsupported);
+        }
+        let wide = ident[4] == ELFCLASS64;
+
+        let mut raw = [0u8; 64];
+        let ehsize = if wide { 64 } else { 52 };
+        src.read_at(&mut raw[..ehsize], 0)?;
+        let e_type = u16_at(&raw, 16);
+        if u16_at(&raw, 18) != EM_NATIVE {
+            return Err(LoadError::Unsupported);
+        }
+        let (e_entry, e_phoff, e_phentsize, phnum) = if wide {
+            (
+                u64_at(&raw, 24),
+                u64_at(&raw, 32),
+                u16_at(&raw, 54),
+                u16_at(&raw, 56),
+            )
+        } else {
+            (
+                u32_at(&raw, 24) as u64,
+                u32_at(&raw, 28) as u64,
+                u16_at(&raw, 42),
+                u16_at(&raw, 44),
+            )
+        };
+        match e_type {
+            ET_DYN => {}
+            ET_EXEC => return Err(LoadError::NotRelocatable),
+            _ => return Err(LoadError::NotExecutable),
+        }
+
+        let phentsize = if wide { 56 } else { 32 };
+        if e_phentsize as usize != phentsize || phnum as usize > MAX_PHDRS {
+            return Err(LoadError::Malformed);
+        }
+        let mut elf = Self {
+            e_type,
+            e_entry,
+            e_phoff,
+            e_phentsize,
+            phdrs: [ProgramHeader::default(); MAX_PHDRS],
+            phnum: phnum as usize,
+        };
+        for (i, ph) in elf.phdrs[..elf.phnum].iter_mut().enumerate() {
+            let offset = (i * phentsize) as u64;
+            let offset = e_phoff.checked_add(offset).ok_or(LoadError::Malformed)?;
+            src.read_at(&mut raw[..phentsize], to_usize(offset)?)?;
+            *ph = if wide {
+                ProgramHeader {
+                    p_type: u32_at(&raw, 0),
+                    p_flags: u32_at(&raw, 4),
+                    p_offset: u64_at(&raw, 8),
+                    p_vaddr: u64_at(&raw, 16),
+                    p_filesz: u64_at(&raw, 32),
+                    p_memsz: u64_at(&raw, 40),
+                    p_align: u64_at(&raw, 48),
+                }
+            } else {
+                ProgramHeader {
+                    p_type: u32_at(&raw, 0),
+                    p_offset: u32_at(&raw, 4) as u64,
+                    p_vaddr: u32_at(&raw, 8) as u64,
+                    p_filesz: u32_at(&raw, 16) as u64,
+                    p_memsz: u32_at(&raw, 20) as u64,
+                    p_flags: u32_at(&raw, 24),
+                    p_align: u32_at(&raw, 28) as u64,
+                }
+            };
+        }
+        elf.check()?;
+        Ok(elf)
+    }
+
+    fn check(&self) -> Result<(), LoadError> {
+        if self
+            .program_headers()
+            .iter()
+            .any(|ph| ph.p_type == PT_INTERP)
+        {
+            return Err(LoadError::NotStatic);
+        }
+        let mut loads = self.segments().peekable();
+        if loads.peek().is_none() {
+            return Err(LoadError::Malformed);
+        }
+        let mut prev_end = 0;
+        for ph in loads {
+            let fits = ph.p_filesz <= ph.p_memsz
+                && ph.p_offset.checked_add(ph.p_filesz).is_some()
+                && ph
+                    .p_vaddr
+                    .checked_add(ph.p_memsz)
+                    .is_some_and(|end| end <= isize::MAX as u64);
+            // Linkers emit segments in address order; overlap would mean one overwrites another.
+            if !fits || ph.p_vaddr < prev_end {
+                return Err(LoadError::Malformed);
+            }
+            prev_end = ph.p_vaddr + ph.p_memsz;
+        }
+        let entry_ok = self.segments().any(|ph| {
+            ph.p_flags & PF_X != 0
+                && ph.p_vaddr <= self.e_entry
+                && self.e_entry < ph.p_vaddr + ph.p_memsz
+        });
+        if !entry_ok || self.phdr_vaddr().is_none() {
+            return Err(LoadError::Malformed);
+        }
+        Ok(())
+    }
+
+    pub fn program_headers(&self) -> &[ProgramHeader] {
+        &self.phdrs[..self.phnum]
+    }
+
+    /// The `PT_LOAD` headers, in address order.
+    pub fn segments(&self) -> impl Iterator<Item = &ProgramHeader> {
+        self.program_headers()
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+    }
+
+    /// `[start, end)` of the addresses the segments occupy, widened to whole pages.
+    pub fn span(&self, page_size: usize) -> (usize, usize) {
+        let page = page_size as u64;
+        let start = self.segments().map(|ph| ph.p_vaddr).min().unwrap_or(0);
+        let end = self
+            .segments()
+            .map(|ph| ph.p_vaddr + ph.p_memsz)
+            .max()
+            .unwrap_or(0);
+        (
+            (start / page * page) as usize,
+            end.div_ceil(page).saturating_mul(page) as usize,
+        )
+    }
+
+    /// Link address of the program headers, if a segment loads the part of the file they are in.
+    pub fn phdr_vaddr(&self) -> Option<u64> {
+        if let Some(ph) = self
+            .program_headers()
+            .iter()
+            .find(|ph| ph.p_type == PT_PHDR)
+        {
+            return Some(ph.p_vaddr);
+        }
+        let len = (self.phnum * self.e_phentsize as usize) as u64;
+        self.segments()
+            .find(|ph| {
+                ph.p_offset <= self.e_phoff && self.e_phoff + len <= ph.p_offset + ph.p_filesz
+            })
+            .map(|ph| ph.p_vaddr + (self.e_phoff - ph.p_offset))
+    }
+}
+
+fn to_usize(value: u64) -> Result<usize, LoadError> {
+    usize::try_from(value).map_err(|_| LoadError::Malformed)
+}
+
+fn u16_at(raw: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([raw[at], raw[at + 1]])
+}
+
+fn u32_at(raw: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(raw[at..at + 4].try_into().unwrap())
+}
+
+fn u64_at(raw: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(raw[at..at + 8].try_into().unwrap())
+}
@@ -0,0 +1,382 @@
+#![no_std]
+
+//! Load a second static program into a running guest and hand the CPU over to it.
+//!
+//! A guest that runs other guests (a test harness proving several programs in one run, a
+//! sandbox for semi-trusted code) parses the program with [`Elf::parse`] from an embedded blob
+//! (`&[u8]`) or, with the `vfs` feature, a [`File`]. [`load`] maps its segments into the mmap
+//! arena with their `PF_*` protections and zeroes the `.bss` tails. [`exec`] then builds a
+//! fresh musl initial stack (argv, envp, auxv with the program's `AT_PHDR`) and jumps to the
+//! entry point. Like `execve`, it does not return. The running program's memory is left as it
+//! is; the new one allocates its own through the same kernel.
+//!
+//! Images must be static PIE (`-static-pie`): `ET_DYN` without `PT_INTERP`, relocating
+//! themselves from `_start` as musl's `rcrt1.o` does, so they run wherever the arena puts them.
+//! Position-dependent images are refused. Platforms link guests at the start of RAM, which the
+//! running guest occupies.
+
+pub mod elf;
+
+use core::convert::Infallible;
+
+use foundation::KError;
+use os_linux::handlers::memory::{sys_mmap, sys_mprotect, sys_munmap};
+use runtime_musl::{build_musl_stack, StartupInfo};
+
+pub use elf::Elf;
+
+/// Granularity of segment placement and protection.
+pub const PAGE_SIZE: usize = 4096;
+
+/// Stack [`exec`] gives the program when the caller passes 0.
+pub const DEFAULT_STACK_SIZE: usize = 256 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// Reading the source or mapping memory failed.
+    Io(KError),
+    /// The source ends before a header or segment does.
+    Truncated,
+    /// No ELF magic.
+    NotElf,
+    /// Another class, byte order, version or machine than this target's, or an architecture
+    /// [`exec`] cannot jump to a program on.
+    Unsupported,
+    /// Neither an executable nor a position-independent one.
+    NotExecutable,
+    /// A position-dependent (`ET_EXEC`) image; it would have to run at its link address.
+    NotRelocatable,
+    /// The image asks for a dynamic linker (`PT_INTERP`).
+    NotStatic,
+    /// Inconsistent headers: no or overlapping segments, sizes past the file or the address
+    /// space, an entry point outside the code, or program headers no segment loads.
+    Malformed,
+}
+
+impl From<KError> for LoadError {
+    fn from(e: KError) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Random access to the bytes of an ELF file.
+pub trait Source {
+    /// Fill `buf` from `offset`, failing with [`LoadError::Truncated`] if the file ends first.
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<(), LoadError>;
+}
+
+impl Source for [u8] {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<(), LoadError> {
+        let bytes = offset
+            .checked_add(buf.len())
+            .and_then(|end| self.get(offset..end))
+            .ok_or(LoadError::Truncated)?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "vfs")]
+pub use file::File;
+
+#[cfg(feature = "vfs")]
+mod file {
+    use core::ffi::CStr;
+
+    use foundation::kfn::vfs;
+
+    use crate::{LoadError, Source};
+
+    /// A VFS file opened read-only; closed on drop.
+    pub struct File {
+        fd: i32,
+    }
+
+    impl File {
+        pub fn open(path: &CStr) -> Result<Self, LoadError> {
+            // SAFETY: `path` is NUL-terminated.
+            let fd = unsafe { vfs::kopen(path.as_ptr().cast(), libc::O_RDONLY, 0) }?;
+            Ok(Self { fd: fd as i32 })
+        }
+    }
+
+    impl Source for File {
+        fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<(), LoadError> {
+            let mut filled = 0;
+            while filled < buf.len() {
+                let rest = &mut buf[filled..];
+                let n = vfs::kpread(self.fd, rest.as_mut_ptr(), rest.len(), offset + filled)?;
+                if n == 0 {
+                    return Err(LoadError::Truncated);
+                }
+                filled += n;
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for File {
+        fn drop(&mut self) {
+            let _ = vfs::kclose(self.fd);
+        }
+    }
+}
+
+/// A program in memory, ready for [`exec`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Image {
+    /// Start of the mapping holding every segment.
+    pub base: usize,
+    pub len: usize,
+    /// Added to link addresses to get load addresses.
+    pub bias: usize,
+    pub entry: usize,
+    /// `(AT_PHDR, AT_PHENT, AT_PHNUM)`.
+    pub phdr: (usize, usize, usize),
+}
+
+impl Image {
+    /// Give the memory back to the arena, for an image that is not going to run.
+    pub fn unmap(self) {
+        sys_munmap(self.base, self.len);
+    }
+}
+
+/// Map the segments of `elf`, read from `src`, into the mmap arena.
+pub fn load<S: Source + ?Sized>(elf: &Elf, src: &S) -> Result<Image, LoadError> {
+    let phdr = elf.phdr_vaddr().ok_or(LoadError::Malformed)?;
+    let (start, end) = elf.span(PAGE_SIZE);
+    let len = end - start;
+    let rw = (libc::PROT_READ | libc::PROT_WRITE) as usize;
+    let flags = (libc::MAP_PRIVATE | libc::MAP_ANONYMOUS) as usize;
+    let base = KError::from_ret(sys_mmap(0, len, rw, flags, usize::MAX, 0))?;
+    let bias = base.wrapping_sub(start);
+    let image = Image {
+        base,
+        len,
+        bias,
+        entry: bias.wrapping_add(elf.e_entry as usize),
+        phdr: (
+            bias.wrapping_add(phdr as usize),
+            elf.e_phentsize as usize,
+            elf.program_headers().len(),
+        ),
+    };
+    if let Err(e) = fill(elf, src, &image) {
+        image.unmap();
+        return Err(e);
+    }
+    Ok(image)
+}
+
+/// Copy each segment's file bytes in (the rest of the fresh mapping is already zero), then
+/// apply its protection.
+fn fill<S: Source + ?Sized>(elf: &Elf, src: &S, image: &Image) -> Result<(), LoadError> {
+    for ph in elf.segments() {
+        let addr = image.bias.wrapping_add(ph.p_vaddr as usize);
+        // SAFETY: `span` covers every segment, and the mapping is `span` long.
+        let dst = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, ph.p_filesz as usize) };
+        src.read_at(dst, ph.p_offset as usize)?;
+    }
+    for ph in elf.segments() {
+        let addr = image.bias.wrapping_add(ph.p_vaddr as usize);
+        let start = addr & !(PAGE_SIZE - 1);
+        let end = (addr + ph.p_memsz as usize).next_multiple_of(PAGE_SIZE);
+        KError::from_ret(sys_mprotect(start, end - start, ph.prot()))?;
+    }
+    Ok(())
+}
+
+/// Run `image` on a fresh stack of `stack_size` bytes ([`DEFAULT_STACK_SIZE`] for 0) with the
+/// argv, envp and `AT_RANDOM` of `info`. Returns only if the stack cannot be mapped, or with
+/// [`LoadError::Unsupported`] on an architecture without a way to enter the program.
+///
+/// # Safety
+/// Nothing of the calling program runs again: its stack, locks and open borrows are abandoned
+/// as they are. `image` must come from [`load`] and not have been unmapped.
+pub unsafe fn exec(
+    image: &Image,
+    info: StartupInfo,
+    stack_size: usize,
+) -> Result<Infallible, LoadError> {
+    let size = match stack_size {
+        0 => DEFAULT_STACK_SIZE,
+        size => size.next_multiple_of(PAGE_SIZE),
+    };
+    let rw = (libc::PROT_READ | libc::PROT_WRITE) as usize;
+    let flags = (libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_STACK) as usize;
+    let bottom = KError::from_ret(sys_mmap(0, size, rw, flags, usize::MAX, 0))?;
+    let info = StartupInfo {
+        phdr: Some(image.phdr),
+        page_size: PAGE_SIZE,
+        ..info
+    };
+    let top = bottom + size;
+    let sp = top - build_musl_stack(top, bottom, &info);
+    let unsupported = enter(image.entry, sp);
+    sys_munmap(bottom, size);
+    unsupported
+}
+
+/// Jump to `entry` with `sp` at `argc`, as the kernel starts a process. Returns only where that
+/// is not implemented.
+unsafe fn enter(entry: usize, sp: usize) -> Result<Infallible, LoadError> {
+    cfg_if::cfg_if! {
+        if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
+            // The segments were written as data; make them visible to instruction fetch.
+            core::arch::asm!(
+                "fence.i",
+                "mv sp, {sp}",
+                "jr {entry}",
+                sp = in(reg) sp,
+                entry = in(reg) entry,
+                in("ra") 0usize,
+                options(noreturn)
+            );
+        } else if #[cfg(target_arch = "x86_64")] {
+            // `rdx` is the `atexit` hook the SysV ABI lets the kernel pass; there is none.
+            core::arch::asm!(
+                "mov rsp, {sp}",
+                "jmp {entry}",
+                sp = in(reg) sp,
+                entry = in(reg) entry,
+                in("rdx") 0usize,
+                options(noreturn)
+            );
+        } else {
+            let _ = (entry, sp);
+            Err(LoadError::Unsupported)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use foundation::ops::MemoryOps;
+    use os_linux::handlers::memory::page_protection;
+
+    use super::elf::*;
+    use super::*;
+
+    const HOST_MEMORY: MemoryOps = MemoryOps {
+        init: |_, _| {},
+        alloc: |layout| unsafe { std::alloc::alloc(layout) },
+        dealloc: |ptr, layout| unsafe { std::alloc::dealloc(ptr, layout) },
+        realloc: |ptr, old, new_size| unsafe { std::alloc::realloc(ptr, old, new_size) },
+        used: || 0,
+        grow: |_, _| false,
+    };
+
+    const CODE: [u8; 16] = *b"\x90\x90\x90\x90\x90\x90\x90\x90\xf4\xf4\xf4\xf4\xf4\xf4\xf4\xf4";
+    const DATA: [u8; 8] = *b"initdata";
+
+    fn phdr(
+        p_type: u32,
+        p_flags: u32,
+        offset: u64,
+        vaddr: u64,
+        filesz: u64,
+        memsz: u64,
+    ) -> [u8; 56] {
+        let mut raw = [0u8; 56];
+        raw[0..4].copy_from_slice(&p_type.to_le_bytes());
+        raw[4..8].copy_from_slice(&p_flags.to_le_bytes());
+        raw[8..16].copy_from_slice(&offset.to_le_bytes());
+        raw[16..24].copy_from_slice(&vaddr.to_le_bytes());
+        raw[32..40].copy_from_slice(&filesz.to_le_bytes());
+        raw[40..48].copy_from_slice(&memsz.to_le_bytes());
+        raw[48..56].copy_from_slice(&0x1000u64.to_le_bytes());
+        raw
+    }
+
+    /// A static PIE for this host: headers and code in an R+X page at 0, eight bytes of data
+    /// and a page and a half of `.bss` at 0x2000.
+    fn image(e_type: u16, extra: Option<[u8; 56]>) -> Vec<u8> {
+        let mut phdrs = std::vec![
+            phdr(PT_PHDR, PF_R, 64, 64, 5 * 56, 5 * 56),
+            phdr(PT_LOAD, PF_R | PF_X, 0, 0, 0x200, 0x200),
+            phdr(PT_LOAD, PF_R | PF_W, 0x1000, 0x2000, 8, 0x1800),
+            phdr(PT_DYNAMIC, PF_R | PF_W, 0x1000, 0x2000, 8, 8),
+        ];
+        phdrs.extend(extra);
+
+        let mut file = std::vec![0u8; 0x1008];
+        file[..4].copy_from_slice(b"\x7fELF");
+        file[4..7].copy_from_slice(&[2, 1, 1]);
+        file[16..18].copy_from_slice(&e_type.to_le_bytes());
+        file[18..20].copy_from_slice(&EM_NATIVE.to_le_bytes());
+        file[24..32].copy_from_slice(&0x1f0u64.to_le_bytes());
+        file[32..40].copy_from_slice(&64u64.to_le_bytes());
+        file[54..56].copy_from_slice(&56u16.to_le_bytes());
+        file[56..58].copy_from_slice(&(phdrs.len() as u16).to_le_bytes());
+        for (i, ph) in phdrs.iter().enumerate() {
+            file[64 + i * 56..][..56].copy_from_slice(ph);
+        }
+        file[0x1f0..0x200].copy_from_slice(&CODE);
+        file[0x1000..].copy_from_slice(&DATA);
+        file
+    }
+
+    #[test]
+    fn test_parse_refuses_what_it_cannot_place() {
+        let file = image(ET_DYN, None);
+        let elf = Elf::parse(&file[..]).unwrap();
+        assert_eq!(elf.segments().count(), 2);
+        assert_eq!(elf.span(PAGE_SIZE), (0, 0x4000));
+        assert_eq!(elf.phdr_vaddr(), Some(64));
+
+        assert_eq!(Elf::parse(&file[..40]).err(), Some(LoadError::Truncated));
+        assert_eq!(Elf::parse(&file[1..]).err(), Some(LoadError::NotElf));
+        assert_eq!(
+            Elf::parse(&image(ET_EXEC, None)[..]).err(),
+            Some(LoadError::NotRelocatable)
+        );
+        let interp = phdr(PT_INTERP, PF_R, 0x100, 0x100, 16, 16);
+        assert_eq!(
+            Elf::parse(&image(ET_DYN, Some(interp))[..]).err(),
+            Some(LoadError::NotStatic)
+        );
+        // Overlaps the data segment.
+        let overlap = phdr(PT_LOAD, PF_R, 0, 0x2800, 0, 0x100);
+        assert_eq!(
+            Elf::parse(&image(ET_DYN, Some(overlap))[..]).err(),
+            Some(LoadError::Malformed)
+        );
+
+        let mut other = file.clone();
+        other[18] ^= 1;
+        assert_eq!(Elf::parse(&other[..]).err(), Some(LoadError::Unsupported));
+        let mut outside = file;
+        outside[24..32].copy_from_slice(&0x2000u64.to_le_bytes());
+        assert_eq!(Elf::parse(&outside[..]).err(), Some(LoadError::Malformed));
+    }
+
+    #[test]
+    fn test_load_places_segments() {
+        foundation::register_memory(HOST_MEMORY);
+        let file = image(ET_DYN, None);
+        let elf = Elf::parse(&file[..]).unwrap();
+        let image = load(&elf, &file[..]).unwrap();
+
+        assert_eq!((image.len, image.bias), (0x4000, image.base));
+        assert_eq!(image.entry, image.base + 0x1f0);
+        assert_eq!(image.phdr, (image.base + 64, 56, 4));
+        let mem = unsafe { core::slice::from_raw_parts(image.base as *const u8, image.len) };
+        assert_eq!(mem[..0x200], file[..0x200]);
+        assert_eq!(mem[0x2000..0x2008], DATA);
+        assert!(mem[0x2008..].iter().all(|&b| b == 0));
+
+        let (r, w, x) = (libc::PROT_READ, libc::PROT_WRITE, libc::PROT_EXEC);
+        assert_eq!(page_protection(image.base), Some((r | x) as usize));
+        assert_eq!(page_protection(image.base + 0x3000), Some((r | w) as usize));
+        image.unmap();
+        assert_eq!(page_protection(image.base), None);
+
+        // The data segment is past the end: nothing stays mapped.
+        assert_eq!(load(&elf, &file[..0x800]).err(), Some(LoadError::Truncated));
+    }
+}
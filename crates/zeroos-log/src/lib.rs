@@ -0,0 +1,225 @@
+//! Backend for the `log` facade that writes to the debug console.
+//!
+//! Guest and library crates log with the standard `log::error!` .. `log::trace!` macros; once
+//! [`init`] has installed the logger, each enabled record is written as
+//! `[LEVEL target] message` through `__debug_write`.
+//!
+//! Which records are enabled is fixed when the guest is built. `ZEROOS_LOG` in the build
+//! environment holds an `env_logger`-style spec, e.g. `warn,my_guest=debug,my_guest::net=trace`:
+//! a bare level sets the default, `module=level` sets the level of that module and the modules
+//! below it, and the longest matching module wins. The spec is parsed at compile time into
+//! [`FILTER`], so a malformed one fails the build; without it, the default is `info`. The
+//! `max-level-*` features additionally compile every call above that level out of the binary.
+
+#![no_std]
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Modules a spec can name; more fail the build.
+pub const MAX_DIRECTIVES: usize = 16;
+
+/// Level of one module and the modules below it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Directive {
+    pub module: &'static str,
+    pub level: LevelFilter,
+}
+
+/// Per-module maximum levels parsed from a spec.
+#[derive(Clone, Copy, Debug)]
+pub struct Filter {
+    default: LevelFilter,
+    directives: [Directive; MAX_DIRECTIVES],
+    len: usize,
+}
+
+impl Filter {
+    /// Parse comma-separated `level` and `module=level` entries; empty entries are skipped and
+    /// levels are case-insensitive.
+    ///
+    /// # Panics
+    /// On an unknown level, an empty module name or more than [`MAX_DIRECTIVES`] modules.
+    pub const fn parse(spec: &'static str) -> Self {
+        let mut filter = Self {
+            default: LevelFilter::Info,
+            directives: [Directive {
+                module: "",
+                level: LevelFilter::Off,
+            }; MAX_DIRECTIVES],
+            len: 0,
+        };
+        let mut rest = spec.as_bytes();
+        while !rest.is_empty() {
+            let (entry, tail) = split_once(rest, b',');
+            rest = tail;
+            let entry = entry.trim_ascii();
+            if entry.is_empty() {
+                continue;
+            }
+            let (module, level) = split_once(entry, b'=');
+            if level.is_empty() && !contains(entry, b'=') {
+                filter.default = parse_level(module);
+                continue;
+            }
+            let module = module.trim_ascii();
+            assert!(!module.is_empty(), "ZEROOS_LOG: empty module name");
+            assert!(filter.len < MAX_DIRECTIVES, "ZEROOS_LOG: too many modules");
+            let Ok(module) = core::str::from_utf8(module) else {
+                panic!("ZEROOS_LOG: module name is not UTF-8");
+            };
+            filter.directives[filter.len] = Directive {
+                module,
+                level: parse_level(level.trim_ascii()),
+            };
+            filter.len += 1;
+        }
+        filter
+    }
+
+    /// Level of the default entry.
+    pub const fn default_level(&self) -> LevelFilter {
+        self.default
+    }
+
+    pub fn directives(&self) -> &[Directive] {
+        &self.directives[..self.len]
+    }
+
+    /// Most verbose level any module logs at.
+    pub const fn max_level(&self) -> LevelFilter {
+        let mut max = self.default;
+        let mut i = 0;
+        while i < self.len {
+            if self.directives[i].level as usize > max as usize {
+                max = self.directives[i].level;
+            }
+            i += 1;
+        }
+        max
+    }
+
+    /// Maximum level of `target`, a module path such as `my_guest::net::tcp`.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives()
+            .iter()
+            .filter(|d| {
+                target
+                    .strip_prefix(d.module)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|d| d.module.len())
+            .map_or(self.default, |d| d.level)
+    }
+}
+
+/// Split at the first `sep`; the tail is empty if there is none.
+const fn split_once(bytes: &[u8], sep: u8) -> (&[u8], &[u8]) {
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == sep {
+            let (head, tail) = bytes.split_at(i);
+            return (head, tail.split_at(1).1);
+        }
+        i += 1;
+    }
+    (bytes, &[])
+}
+
+const fn contains(bytes: &[u8], byte: u8) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == byte {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn parse_level(name: &[u8]) -> LevelFilter {
+    const LEVELS: [(&[u8], LevelFilter); 6] = [
+        (b"off", LevelFilter::Off),
+        (b"error", LevelFilter::Error),
+        (b"warn", LevelFilter::Warn),
+        (b"info", LevelFilter::Info),
+        (b"debug", LevelFilter::Debug),
+        (b"trace", LevelFilter::Trace),
+    ];
+    let mut i = 0;
+    while i < LEVELS.len() {
+        if name.eq_ignore_ascii_case(LEVELS[i].0) {
+            return LEVELS[i].1;
+        }
+        i += 1;
+    }
+    panic!("ZEROOS_LOG: unknown level");
+}
+
+/// The filter built into this binary from `ZEROOS_LOG`.
+pub const FILTER: Filter = Filter::parse(match option_env!("ZEROOS_LOG") {
+    Some(spec) => spec,
+    None => "info",
+});
+
+pub struct Logger {
+    filter: Filter,
+}
+
+impl Logger {
+    pub const fn new(filter: Filter) -> Self {
+        Self { filter }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            debug::writeln!(
+                "[{:<5} {}] {}",
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger::new(FILTER);
+
+/// Install the logger; later calls, or calls after another logger was installed, do nothing.
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(FILTER.max_level());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_module_wins() {
+        const F: Filter = Filter::parse(" warn, app=debug ,app::net=trace,,other=OFF");
+        assert_eq!(F.default_level(), LevelFilter::Warn);
+        assert_eq!(F.max_level(), LevelFilter::Trace);
+        assert_eq!(F.level_for("app"), LevelFilter::Debug);
+        assert_eq!(F.level_for("app::disk"), LevelFilter::Debug);
+        assert_eq!(F.level_for("app::net::tcp"), LevelFilter::Trace);
+        assert_eq!(F.level_for("apple"), LevelFilter::Warn);
+        assert_eq!(F.level_for("other::x"), LevelFilter::Off);
+    }
+
+    #[test]
+    fn test_default_spec() {
+        const F: Filter = Filter::parse("");
+        assert_eq!(F.default_level(), LevelFilter::Info);
+        assert!(F.directives().is_empty());
+        assert_eq!(F.max_level(), LevelFilter::Info);
+    }
+}
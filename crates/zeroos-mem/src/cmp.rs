@@ -0,0 +1,70 @@
+//! `memcmp` and `bcmp`.
+
+use crate::{misalignment, THRESHOLD, WORD};
+
+/// # Safety
+///
+/// `a` and `b` must be valid for reads of `n` bytes.
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn memcmp(a: *const u8, b: *const u8, n: usize) -> i32 {
+    let mut i = 0;
+    // Words can only be compared in place when both sides reach alignment together; the
+    // differing word, if any, is then resolved byte by byte below.
+    if n >= THRESHOLD && misalignment(a as usize) == misalignment(b as usize) {
+        let head = misalignment(a as usize).wrapping_neg() & (WORD - 1);
+        if let Some(diff) = compare_bytes(a, b, 0, head) {
+            return diff;
+        }
+        i = head;
+        while i + WORD <= n && *(a.add(i) as *const usize) == *(b.add(i) as *const usize) {
+            i += WORD;
+        }
+    }
+    compare_bytes(a, b, i, n).unwrap_or(0)
+}
+
+/// Emitted by LLVM for equality-only comparisons; any non-zero result means "different".
+///
+/// # Safety
+///
+/// `a` and `b` must be valid for reads of `n` bytes.
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn bcmp(a: *const u8, b: *const u8, n: usize) -> i32 {
+    memcmp(a, b, n)
+}
+
+unsafe fn compare_bytes(a: *const u8, b: *const u8, from: usize, to: usize) -> Option<i32> {
+    let mut i = from;
+    while i < to {
+        let (x, y) = (*a.add(i), *b.add(i));
+        if x != y {
+            return Some(x as i32 - y as i32);
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memcmp_first_difference() {
+        let base: [u8; 80] = core::array::from_fn(|i| i as u8);
+        for off in 0..WORD {
+            for n in 0..64 {
+                let a = &base[off..off + n];
+                assert_eq!(unsafe { memcmp(a.as_ptr(), a.as_ptr(), n) }, 0);
+                for at in 0..n {
+                    let mut other = base;
+                    other[off + at] = other[off + at].wrapping_add(1);
+                    let b = &other[off..off + n];
+                    assert_eq!(unsafe { memcmp(a.as_ptr(), b.as_ptr(), n) }, -1);
+                    assert_eq!(unsafe { memcmp(b.as_ptr(), a.as_ptr(), n) }, 1);
+                    assert_ne!(unsafe { bcmp(a.as_ptr(), b.as_ptr(), n) }, 0);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! `memcpy` and `memmove`.
+
+use core::ptr;
+
+use crate::{misalignment, THRESHOLD, WORD};
+
+/// # Safety
+///
+/// `src` must be valid for reads and `dest` for writes of `n` bytes, and the two ranges must not
+/// overlap.
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn memcpy(dest: *mut u8, src: *const u8, n: usize) -> *mut u8 {
+    copy_forward(dest, src, n);
+    dest
+}
+
+/// # Safety
+///
+/// `src` must be valid for reads and `dest` for writes of `n` bytes; the ranges may overlap.
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn memmove(dest: *mut u8, src: *const u8, n: usize) -> *mut u8 {
+    // `dest` below `src` or past its end: a forward copy never reads a byte it already wrote.
+    if (dest as usize).wrapping_sub(src as usize) >= n {
+        copy_forward(dest, src, n);
+    } else {
+        copy_backward(dest, src, n);
+    }
+    dest
+}
+
+unsafe fn copy_forward(mut dest: *mut u8, mut src: *const u8, mut n: usize) {
+    if n >= THRESHOLD {
+        let head = misalignment(dest as usize).wrapping_neg() & (WORD - 1);
+        copy_bytes_forward(dest, src, head);
+        dest = dest.add(head);
+        src = src.add(head);
+        n -= head;
+
+        let words = n / WORD;
+        if misalignment(src as usize) == 0 {
+            copy_words_forward(dest as *mut usize, src as *const usize, words);
+        } else {
+            copy_words_forward_unaligned(dest as *mut usize, src, words);
+        }
+        dest = dest.add(words * WORD);
+        src = src.add(words * WORD);
+        n -= words * WORD;
+    }
+    copy_bytes_forward(dest, src, n);
+}
+
+/// Copies the `n` bytes that end at `dest + n` and `src + n`, last byte first.
+unsafe fn copy_backward(dest: *mut u8, src: *const u8, mut n: usize) {
+    let mut dest = dest.add(n);
+    let mut src = src.add(n);
+    if n >= THRESHOLD {
+        let tail = misalignment(dest as usize);
+        dest = dest.sub(tail);
+        src = src.sub(tail);
+        copy_bytes_backward(dest, src, tail);
+        n -= tail;
+
+        let words = n / WORD;
+        dest = dest.sub(words * WORD);
+        src = src.sub(words * WORD);
+        if misalignment(src as usize) == 0 {
+            copy_words_backward(dest as *mut usize, src as *const usize, words);
+        } else {
+            copy_words_backward_unaligned(dest as *mut usize, src, words);
+        }
+        n -= words * WORD;
+    }
+    copy_bytes_backward(dest.sub(n), src.sub(n), n);
+}
+
+unsafe fn copy_bytes_forward(dest: *mut u8, src: *const u8, n: usize) {
+    let mut i = 0;
+    while i < n {
+        *dest.add(i) = *src.add(i);
+        i += 1;
+    }
+}
+
+unsafe fn copy_bytes_backward(dest: *mut u8, src: *const u8, n: usize) {
+    let mut i = n;
+    while i > 0 {
+        i -= 1;
+        *dest.add(i) = *src.add(i);
+    }
+}
+
+// The word loops read a whole group before writing it, so a forward `memmove` onto a lower
+// address (and a backward one onto a higher address) stays correct at word granularity.
+
+unsafe fn copy_words_forward(dest: *mut usize, src: *const usize, words: usize) {
+    let mut i = 0;
+    while i + 4 <= words {
+        let (a, b, c, d) = (
+            *src.add(i),
+            *src.add(i + 1),
+            *src.add(i + 2),
+            *src.add(i + 3),
+        );
+        *dest.add(i) = a;
+        *dest.add(i + 1) = b;
+        *dest.add(i + 2) = c;
+        *dest.add(i + 3) = d;
+        i += 4;
+    }
+    while i < words {
+        *dest.add(i) = *src.add(i);
+        i += 1;
+    }
+}
+
+unsafe fn copy_words_backward(dest: *mut usize, src: *const usize, words: usize) {
+    let mut i = words;
+    while i >= 4 {
+        let (a, b, c, d) = (
+            *src.add(i - 1),
+            *src.add(i - 2),
+            *src.add(i - 3),
+            *src.add(i - 4),
+        );
+        *dest.add(i - 1) = a;
+        *dest.add(i - 2) = b;
+        *dest.add(i - 3) = c;
+        *dest.add(i - 4) = d;
+        i -= 4;
+    }
+    while i > 0 {
+        i -= 1;
+        *dest.add(i) = *src.add(i);
+    }
+}
+
+unsafe fn copy_words_forward_unaligned(dest: *mut usize, src: *const u8, words: usize) {
+    let src = src as *const usize;
+    let mut i = 0;
+    while i < words {
+        *dest.add(i) = ptr::read_unaligned(src.add(i));
+        i += 1;
+    }
+}
+
+unsafe fn copy_words_backward_unaligned(dest: *mut usize, src: *const u8, words: usize) {
+    let src = src as *const usize;
+    let mut i = words;
+    while i > 0 {
+        i -= 1;
+        *dest.add(i) = ptr::read_unaligned(src.add(i));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern() -> [u8; 96] {
+        core::array::from_fn(|i| (i as u8).wrapping_mul(37).wrapping_add(11))
+    }
+
+    #[test]
+    fn test_memcpy_alignments() {
+        let src = pattern();
+        for s in 0..WORD {
+            for d in 0..WORD {
+                for n in 0..64 {
+                    let mut dst = [0u8; 96];
+                    unsafe { memcpy(dst.as_mut_ptr().add(d), src.as_ptr().add(s), n) };
+                    assert_eq!(dst[d..d + n], src[s..s + n], "s={s} d={d} n={n}");
+                    assert!(dst[..d].iter().chain(&dst[d + n..]).all(|&b| b == 0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_memmove_overlap() {
+        for from in 0..24 {
+            for to in 0..24 {
+                for n in 0..64 {
+                    let mut buf = pattern();
+                    let mut expected = buf;
+                    expected.copy_within(from..from + n, to);
+                    unsafe { memmove(buf.as_mut_ptr().add(to), buf.as_ptr().add(from), n) };
+                    assert_eq!(buf, expected, "from={from} to={to} n={n}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,32 @@
+#![no_std]
+// Keep LLVM from turning the loops below back into calls to the functions they implement.
+#![no_builtins]
+
+//! Word-at-a-time replacements for the memory intrinsics.
+//!
+//! The versions in `compiler-builtins` move one byte per iteration on targets without fast
+//! misaligned access, which covers every RISC-V guest target. These align the destination first
+//! and then move a `usize` at a time, four words per iteration, falling back to bytes only for
+//! the head and tail.
+//!
+//! Linking this crate overrides `memcpy`, `memmove`, `memset`, `memcmp` and `bcmp` for the whole
+//! image: the symbols here are strong, while the `compiler-builtins` ones are weak and musl's are
+//! only pulled from `libc.a` when nothing else defines them.
+
+mod cmp;
+mod copy;
+mod set;
+
+pub use cmp::{bcmp, memcmp};
+pub use copy::{memcpy, memmove};
+pub use set::memset;
+
+const WORD: usize = core::mem::size_of::<usize>();
+
+/// Below this many bytes, aligning and word-looping costs more than it saves.
+const THRESHOLD: usize = 4 * WORD;
+
+#[inline(always)]
+fn misalignment(ptr: usize) -> usize {
+    ptr & (WORD - 1)
+}
@@ -0,0 +1,64 @@
+//! `memset`.
+
+use crate::{misalignment, THRESHOLD, WORD};
+
+/// # Safety
+///
+/// `dest` must be valid for writes of `n` bytes.
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn memset(dest: *mut u8, c: i32, n: usize) -> *mut u8 {
+    let byte = c as u8;
+    let mut p = dest;
+    let mut n = n;
+    if n >= THRESHOLD {
+        let head = misalignment(p as usize).wrapping_neg() & (WORD - 1);
+        set_bytes(p, byte, head);
+        p = p.add(head);
+        n -= head;
+
+        let words = n / WORD;
+        let splat = byte as usize * (usize::MAX / 0xff);
+        let w = p as *mut usize;
+        let mut i = 0;
+        while i + 4 <= words {
+            *w.add(i) = splat;
+            *w.add(i + 1) = splat;
+            *w.add(i + 2) = splat;
+            *w.add(i + 3) = splat;
+            i += 4;
+        }
+        while i < words {
+            *w.add(i) = splat;
+            i += 1;
+        }
+        p = p.add(words * WORD);
+        n -= words * WORD;
+    }
+    set_bytes(p, byte, n);
+    dest
+}
+
+unsafe fn set_bytes(dest: *mut u8, byte: u8, n: usize) {
+    let mut i = 0;
+    while i < n {
+        *dest.add(i) = byte;
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memset_alignments() {
+        for d in 0..WORD {
+            for n in 0..64 {
+                let mut buf = [0u8; 80];
+                unsafe { memset(buf.as_mut_ptr().add(d), 0x1A5, n) };
+                assert!(buf[d..d + n].iter().all(|&b| b == 0xA5), "d={d} n={n}");
+                assert!(buf[..d].iter().chain(&buf[d + n..]).all(|&b| b == 0));
+            }
+        }
+    }
+}
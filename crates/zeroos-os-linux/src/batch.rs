@@ -0,0 +1,141 @@
+//! Syscall batching: run many syscalls for the cost of one trap.
+//!
+//! The guest fills an array of [`BatchEntry`] and issues `ecall` with `a7 = SYS_BATCH`,
+//! `a0 = entries`, `a1 = count`, `a2 = flags`. Each entry is dispatched in order exactly as if
+//! it had been trapped on its own (hostcalls, tracing and perf included) and its result is
+//! stored in `ret`.
+
+use foundation::uaccess::access_ok;
+
+use crate::syscall::{linux_handle, NR_SYSCALLS};
+
+/// Syscall number of the batch call: the first number past the Linux table.
+pub const SYS_BATCH: usize = NR_SYSCALLS;
+
+/// Stop at the first entry that returns a negative errno.
+pub const BATCH_STOP_ON_ERROR: usize = 1;
+
+const ALLOWED_FLAGS: usize = BATCH_STOP_ON_ERROR;
+
+/// One syscall in a batch. Layout is part of the guest ABI.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchEntry {
+    pub nr: usize,
+    pub args: [usize; 6],
+    /// Written by the kernel; entries that were not run are left untouched.
+    pub ret: isize,
+}
+
+impl BatchEntry {
+    pub const fn new(nr: usize, args: [usize; 6]) -> Self {
+        Self { nr, args, ret: 0 }
+    }
+}
+
+type Dispatch =
+    fn(a0: usize, a1: usize, a2: usize, a3: usize, a4: usize, a5: usize, nr: usize) -> isize;
+
+/// Run `entries` in order; returns how many were run, so with [`BATCH_STOP_ON_ERROR`] the
+/// failing entry is `entries[n - 1]`.
+pub fn run_batch(entries: &mut [BatchEntry], flags: usize) -> isize {
+    run_with(entries, flags, linux_handle)
+}
+
+fn run_with(entries: &mut [BatchEntry], flags: usize, dispatch: Dispatch) -> isize {
+    if (flags & !ALLOWED_FLAGS) != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    let mut run = 0;
+    for e in entries.iter_mut() {
+        let [a0, a1, a2, a3, a4, a5] = e.args;
+        e.ret = if e.nr == SYS_BATCH {
+            // No nesting: bounds the work one trap can do to what the guest can see.
+            -(libc::EINVAL as isize)
+        } else {
+            dispatch(a0, a1, a2, a3, a4, a5, e.nr)
+        };
+        run += 1;
+        if (flags & BATCH_STOP_ON_ERROR) != 0 && e.ret < 0 {
+            break;
+        }
+    }
+    run
+}
+
+/// The guest array at `addr`, after checking it is non-null, aligned and in guest memory.
+fn entries_at<'a>(addr: usize, count: usize) -> Result<&'a mut [BatchEntry], isize> {
+    if count == 0 {
+        return Ok(&mut []);
+    }
+    if addr == 0 || !addr.is_multiple_of(core::mem::align_of::<BatchEntry>()) {
+        return Err(-(libc::EFAULT as isize));
+    }
+    let fits = count
+        .checked_mul(core::mem::size_of::<BatchEntry>())
+        .is_some_and(|len| len <= isize::MAX as usize && addr.checked_add(len).is_some());
+    if !fits {
+        return Err(-(libc::EINVAL as isize));
+    }
+    if !access_ok(addr, count * core::mem::size_of::<BatchEntry>()) {
+        return Err(-(libc::EFAULT as isize));
+    }
+    // SAFETY: non-null, aligned and in guest memory; the guest owns the array for the duration of the
+    // call, as with any syscall buffer.
+    Ok(unsafe { core::slice::from_raw_parts_mut(addr as *mut BatchEntry, count) })
+}
+
+pub fn sys_batch(entries: usize, count: usize, flags: usize) -> isize {
+    match entries_at(entries, count) {
+        Ok(entries) => run_batch(entries, flags),
+        Err(e) => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake(a0: usize, a1: usize, _: usize, _: usize, _: usize, _: usize, nr: usize) -> isize {
+        if nr == 0 {
+            (a0 + a1) as isize
+        } else {
+            -(libc::ENOSYS as isize)
+        }
+    }
+
+    #[test]
+    fn test_runs_entries_in_order() {
+        let mut entries = [
+            BatchEntry::new(0, [1, 2, 0, 0, 0, 0]),
+            BatchEntry::new(7, [0; 6]),
+            BatchEntry::new(SYS_BATCH, [0; 6]),
+        ];
+        assert_eq!(run_with(&mut entries, 0, fake), 3);
+        assert_eq!(entries[0].ret, 3);
+        assert_eq!(entries[1].ret, -(libc::ENOSYS as isize));
+        assert_eq!(entries[2].ret, -(libc::EINVAL as isize));
+        assert_eq!(run_with(&mut entries, 2, fake), -(libc::EINVAL as isize));
+    }
+
+    #[test]
+    fn test_stop_on_error() {
+        let mut entries = [
+            BatchEntry::new(7, [0; 6]),
+            BatchEntry::new(0, [1, 1, 0, 0, 0, 0]),
+        ];
+        assert_eq!(run_with(&mut entries, BATCH_STOP_ON_ERROR, fake), 1);
+        assert_eq!(entries[1].ret, 0);
+    }
+
+    #[test]
+    fn test_entries_at_validates_array() {
+        assert!(entries_at(0, 0).is_ok_and(|e| e.is_empty()));
+        assert_eq!(entries_at(0, 1).err(), Some(-(libc::EFAULT as isize)));
+        assert_eq!(entries_at(1, 1).err(), Some(-(libc::EFAULT as isize)));
+        assert_eq!(
+            entries_at(8, usize::MAX).err(),
+            Some(-(libc::EINVAL as isize))
+        );
+    }
+}
@@ -1,14 +1,128 @@
 use core::alloc::Layout;
 
 use foundation::kfn;
+use foundation::utils::{GlobalCell, GlobalOption};
+use foundation::{KError, KResult};
 use libc;
 
+mod mappings;
+
+use mappings::MappingTable;
+
 const PAGE_SIZE: usize = 4096;
 
+/// Live mappings (fragments count separately once split by `munmap`) tracked in static memory;
+/// beyond that the table moves to the allocator.
+const STATIC_MAPPINGS: usize = 64;
+
+static MAPPINGS: GlobalCell<MappingTable<STATIC_MAPPINGS>> = GlobalCell::new(MappingTable::new());
+
+const ALLOWED_PROT: usize = (libc::PROT_READ | libc::PROT_WRITE | libc::PROT_EXEC) as usize;
+
+/// Hardware page protection supplied by a platform (e.g. RISC-V PMP).
+///
+/// Without one, `PROT_*` bits are only recorded: every mapped page stays readable, writable and
+/// executable.
+#[derive(Clone, Copy)]
+pub struct PageProtection {
+    /// Apply `prot` to `[addr, addr + len)`; returns 0 or a negative errno.
+    pub apply: fn(addr: usize, len: usize, prot: usize) -> isize,
+    /// Reject mappings that are both writable and executable with `EACCES`.
+    pub enforce_wx: bool,
+}
+
+static PROTECTION: GlobalOption<PageProtection> = GlobalOption::none();
+
+/// Route `mmap`/`mprotect` protections to the platform. Call during bootstrap.
+pub fn register_page_protection(protection: PageProtection) {
+    PROTECTION.set(protection);
+}
+
+/// Validate `prot` and forward it to the platform hook, if any.
+fn apply_protection(addr: usize, len: usize, prot: usize) -> isize {
+    if (prot & !ALLOWED_PROT) != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    let wx = (libc::PROT_WRITE | libc::PROT_EXEC) as usize;
+    PROTECTION
+        .with_some(|p| {
+            if p.enforce_wx && (prot & wx) == wx {
+                -(libc::EACCES as isize)
+            } else {
+                (p.apply)(addr, len, prot)
+            }
+        })
+        .unwrap_or(0)
+}
+
+/// Recorded `PROT_*` bits of the mmap page containing `addr`, if it is mapped.
+pub fn page_protection(addr: usize) -> Option<usize> {
+    MAPPINGS.with(|m| m.prot_at(addr))
+}
+
+/// `mmap` flag asking to skip zeroing of anonymous pages (Linux `MAP_UNINITIALIZED`).
+///
+/// Zeroing a large mapping costs a store per word, which dominates `mmap` in a zkVM. The flag is
+/// accepted but ignored unless the platform opts in with [`allow_uninitialized_mmap`].
+pub const MAP_UNINITIALIZED: usize = 0x0400_0000;
+
+static ALLOW_UNINITIALIZED: GlobalCell<bool> = GlobalCell::new(false);
+
+/// Honour `MAP_UNINITIALIZED`. Only for trusted guests: the pages may hold whatever a freed
+/// allocation left behind.
+pub fn allow_uninitialized_mmap(allow: bool) {
+    ALLOW_UNINITIALIZED.with_mut(|a| *a = allow);
+}
+
+#[inline]
+fn release(base: usize, size: usize) {
+    // `mmap` allocated every tracked block with exactly this layout.
+    if let Ok(layout) = Layout::from_size_align(size, PAGE_SIZE) {
+        kfn::memory::kfree(base as *mut u8, layout);
+    }
+}
+
 pub fn sys_brk(_brk: usize) -> isize {
     -(libc::ENOMEM as isize)
 }
 
+/// Copy up to `len` bytes of `fd` from `offset` into `dst` and return how many there were; the
+/// file offset is left alone.
+#[cfg(feature = "vfs")]
+fn read_file_at(fd: i32, dst: *mut u8, len: usize, offset: usize) -> KResult<usize> {
+    let mut filled = 0;
+    while filled < len {
+        // SAFETY: `dst` holds `len` bytes.
+        let n = kfn::vfs::kpread(
+            fd,
+            unsafe { dst.add(filled) },
+            len - filled,
+            offset + filled,
+        )
+        .map_err(|e| match e {
+            // Pipes and the console have no offsets to map from.
+            KError::SPipe => KError::Other(libc::ENODEV),
+            e => e,
+        })?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+/// Without a VFS no descriptor refers to a file.
+#[cfg(not(feature = "vfs"))]
+fn read_file_at(_fd: i32, _dst: *mut u8, _len: usize, _offset: usize) -> KResult<usize> {
+    Err(KError::BadF)
+}
+
+/// `mmap` for anonymous and file-backed `MAP_PRIVATE` mappings.
+///
+/// A file mapping is a copy of the file taken when it is mapped: `len` bytes from `offset`, which
+/// must be page-aligned, with the rest of the last page and anything past end of file zeroed.
+/// Later writes to either side are not seen by the other.
 pub fn sys_mmap(
     addr: usize,
     len: usize,
@@ -20,22 +134,36 @@ pub fn sys_mmap(
     if len == 0 {
         return -(libc::EINVAL as isize);
     }
-    let allowed_prot = (libc::PROT_NONE | libc::PROT_READ | libc::PROT_WRITE) as usize;
-    if (prot & !allowed_prot) != 0 {
+    if (prot & !ALLOWED_PROT) != 0 {
         return -(libc::EINVAL as isize);
     }
 
-    let allowed_flags = (libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_STACK) as usize;
+    let allowed_flags =
+        (libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_STACK) as usize | MAP_UNINITIALIZED;
     if (flags & !allowed_flags) != 0 {
         return -(libc::EINVAL as isize);
     }
-    if (flags & libc::MAP_PRIVATE as usize) == 0 || (flags & libc::MAP_ANONYMOUS as usize) == 0 {
+    if (flags & libc::MAP_PRIVATE as usize) == 0 {
         return -(libc::EINVAL as isize);
     }
-    if addr != 0 || offset != 0 {
+    if addr != 0 {
         return -(libc::EINVAL as isize);
     }
-    if fd != usize::MAX && fd != 0 {
+    let file = (flags & libc::MAP_ANONYMOUS as usize) == 0;
+    if file {
+        if !offset.is_multiple_of(PAGE_SIZE) {
+            return -(libc::EINVAL as isize);
+        }
+        if offset
+            .checked_add(len)
+            .is_none_or(|end| end > isize::MAX as usize)
+        {
+            return -(libc::EOVERFLOW as isize);
+        }
+        if (fd as i32) < 0 {
+            return -(libc::EBADF as isize);
+        }
+    } else if offset != 0 || (fd != usize::MAX && fd != 0) {
         return -(libc::EINVAL as isize);
     }
 
@@ -52,8 +180,31 @@ pub fn sys_mmap(
     if ptr.is_null() {
         return -(libc::ENOMEM as isize);
     }
+    if !MAPPINGS.with_mut(|m| m.insert(ptr as usize, size, prot)) {
+        kfn::memory::kfree(ptr, layout);
+        return -(libc::ENOMEM as isize);
+    }
+    let zero_from = if file {
+        match read_file_at(fd as i32, ptr, len, offset) {
+            Ok(n) => n,
+            Err(e) => {
+                MAPPINGS.with_mut(|m| m.unmap(ptr as usize, ptr as usize + size, release));
+                return e.to_ret();
+            }
+        }
+    } else if (flags & MAP_UNINITIALIZED) != 0 && ALLOW_UNINITIALIZED.with(|a| *a) {
+        size
+    } else {
+        0
+    };
+    // SAFETY: `ptr` is a fresh allocation of `size` bytes.
     unsafe {
-        core::ptr::write_bytes(ptr, 0, size);
+        core::ptr::write_bytes(ptr.add(zero_from), 0, size - zero_from);
+    }
+    let ret = apply_protection(ptr as usize, size, prot);
+    if ret < 0 {
+        MAPPINGS.with_mut(|m| m.unmap(ptr as usize, ptr as usize + size, release));
+        return ret;
     }
     ptr as isize
 }
@@ -70,11 +221,14 @@ pub fn sys_munmap(addr: usize, len: usize) -> isize {
         Some(s) => s,
         None => return -(libc::EINVAL as isize),
     };
-    let layout = match Layout::from_size_align(size, PAGE_SIZE) {
-        Ok(l) => l,
-        Err(_) => return -(libc::EINVAL as isize),
+    let Some(end) = addr.checked_add(size) else {
+        return -(libc::EINVAL as isize);
     };
-    kfn::memory::kfree(addr as *mut u8, layout);
+    // Ranges that were never mapped (or are already unmapped) are silently skipped, as on
+    // Linux; partially unmapped blocks are freed once their last page goes.
+    if !MAPPINGS.with_mut(|m| m.unmap(addr, end, release)) {
+        return -(libc::ENOMEM as isize);
+    }
     0
 }
 
@@ -85,9 +239,181 @@ pub fn sys_mprotect(addr: usize, len: usize, prot: usize) -> isize {
     if !addr.is_multiple_of(PAGE_SIZE) {
         return -(libc::EINVAL as isize);
     }
-    let allowed_prot = (libc::PROT_NONE | libc::PROT_READ | libc::PROT_WRITE) as usize;
-    if (prot & !allowed_prot) != 0 {
+    if (prot & !ALLOWED_PROT) != 0 {
         return -(libc::EINVAL as isize);
     }
+    let Some(end) = len
+        .div_ceil(PAGE_SIZE)
+        .checked_mul(PAGE_SIZE)
+        .and_then(|size| addr.checked_add(size))
+    else {
+        return -(libc::ENOMEM as isize);
+    };
+    // Only mmap regions are tracked; the image and heap keep their boot-time permissions.
+    if !MAPPINGS.with_mut(|m| m.covers(addr, end) && m.make_room_to_protect(addr, end, prot)) {
+        return -(libc::ENOMEM as isize);
+    }
+    let ret = apply_protection(addr, end - addr, prot);
+    if ret < 0 {
+        return ret;
+    }
+    MAPPINGS.with_mut(|m| m.protect(addr, end, prot));
     0
 }
+
+/// Bytes to reserve when `mremap` has to move a mapping of `size` bytes: round up to a power of
+/// two so a run of growing reallocs moves O(log n) times instead of every call.
+fn remap_reserve(size: usize) -> usize {
+    size.checked_next_power_of_two().unwrap_or(size)
+}
+
+/// `mremap` for anonymous mappings.
+///
+/// `[old_addr, old_addr + old_len)` must be exactly one mapped range as returned by `mmap` (or
+/// left by `munmap`/`mprotect`). Shrinking and growing into unmapped room of the same block happen
+/// in place; otherwise, with `MREMAP_MAYMOVE`, the contents move to a larger block.
+pub fn sys_mremap(
+    old_addr: usize,
+    old_len: usize,
+    new_len: usize,
+    flags: usize,
+    _new_addr: usize,
+) -> isize {
+    if old_addr == 0 || !old_addr.is_multiple_of(PAGE_SIZE) || old_len == 0 || new_len == 0 {
+        return -(libc::EINVAL as isize);
+    }
+    // MREMAP_FIXED and MREMAP_DONTUNMAP would need address-space control we do not have.
+    if (flags & !(libc::MREMAP_MAYMOVE as usize)) != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    let page_round = |len: usize| len.div_ceil(PAGE_SIZE).checked_mul(PAGE_SIZE);
+    let (Some(old_size), Some(new_size)) = (page_round(old_len), page_round(new_len)) else {
+        return -(libc::EINVAL as isize);
+    };
+    let (Some(old_end), Some(new_end)) = (
+        old_addr.checked_add(old_size),
+        old_addr.checked_add(new_size),
+    ) else {
+        return -(libc::EINVAL as isize);
+    };
+    let Some(prot) = MAPPINGS.with(|m| m.fragment_prot(old_addr, old_end)) else {
+        return -(libc::EFAULT as isize);
+    };
+
+    if new_size <= old_size {
+        // Trimming the end of a fragment never needs a new slot.
+        MAPPINGS.with_mut(|m| m.unmap(new_end, old_end, release));
+        return old_addr as isize;
+    }
+
+    if MAPPINGS.with_mut(|m| m.extend(old_addr, old_end, new_end)) {
+        unsafe {
+            core::ptr::write_bytes(old_end as *mut u8, 0, new_size - old_size);
+        }
+        return old_addr as isize;
+    }
+    if (flags & libc::MREMAP_MAYMOVE as usize) == 0 {
+        return -(libc::ENOMEM as isize);
+    }
+
+    // Prefer a block with room to grow; fall back to the exact size under memory pressure.
+    let mut reserve = remap_reserve(new_size);
+    let mut ptr = match Layout::from_size_align(reserve, PAGE_SIZE) {
+        Ok(layout) => kfn::memory::kmalloc(layout),
+        Err(_) => core::ptr::null_mut(),
+    };
+    if ptr.is_null() && reserve != new_size {
+        reserve = new_size;
+        ptr = match Layout::from_size_align(reserve, PAGE_SIZE) {
+            Ok(layout) => kfn::memory::kmalloc(layout),
+            Err(_) => core::ptr::null_mut(),
+        };
+    }
+    if ptr.is_null() {
+        return -(libc::ENOMEM as isize);
+    }
+    let new_addr = ptr as usize;
+    if !MAPPINGS.with_mut(|m| m.reserve(new_addr, reserve, new_size, prot)) {
+        release(new_addr, reserve);
+        return -(libc::ENOMEM as isize);
+    }
+    unsafe {
+        core::ptr::copy_nonoverlapping(old_addr as *const u8, ptr, old_size);
+        core::ptr::write_bytes(ptr.add(old_size), 0, new_size - old_size);
+    }
+    let ret = apply_protection(new_addr, new_size, prot);
+    if ret < 0 {
+        MAPPINGS.with_mut(|m| m.unmap(new_addr, new_addr + new_size, release));
+        return ret;
+    }
+    MAPPINGS.with_mut(|m| m.unmap(old_addr, old_end, release));
+    new_addr as isize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_mmap_through_dispatch() {
+        let _kernel = testing::kernel();
+
+        let flags = (libc::MAP_PRIVATE | libc::MAP_ANONYMOUS) as usize;
+        let prot = (libc::PROT_READ | libc::PROT_WRITE) as usize;
+        let addr = testing::syscall(libc::SYS_mmap, [0, 3 * PAGE_SIZE, prot, flags, 0, 0]);
+        assert!(addr > 0 && (addr as usize).is_multiple_of(PAGE_SIZE));
+        let addr = addr as usize;
+        let pages = unsafe { core::slice::from_raw_parts(addr as *const u8, 3 * PAGE_SIZE) };
+        assert!(pages.iter().all(|&b| b == 0));
+        assert_eq!(page_protection(addr + PAGE_SIZE), Some(prot));
+
+        // Shared mappings are refused.
+        let shared = (libc::MAP_SHARED | libc::MAP_ANONYMOUS) as usize;
+        assert_eq!(
+            testing::syscall(libc::SYS_mmap, [0, PAGE_SIZE, prot, shared, 0, 0]),
+            -(libc::EINVAL as isize)
+        );
+
+        assert_eq!(
+            testing::syscall(libc::SYS_munmap, [addr, 3 * PAGE_SIZE, 0, 0, 0, 0]),
+            0
+        );
+        assert_eq!(page_protection(addr), None);
+    }
+
+    #[cfg(feature = "vfs")]
+    #[test]
+    fn test_mmap_file_copies_from_offset() {
+        let _kernel = testing::kernel();
+
+        let contents: [u8; PAGE_SIZE + 100] = core::array::from_fn(|i| i as u8);
+        testing::set_file(&contents);
+        let fd = testing::FILE_FD as usize;
+        assert_eq!(testing::syscall(libc::SYS_lseek, [fd, 7, 0, 0, 0, 0]), 7);
+
+        let flags = libc::MAP_PRIVATE as usize;
+        let prot = libc::PROT_READ as usize;
+        let addr = testing::syscall(
+            libc::SYS_mmap,
+            [0, 2 * PAGE_SIZE, prot, flags, fd, PAGE_SIZE],
+        );
+        assert!(addr > 0);
+        let addr = addr as usize;
+        let pages = unsafe { core::slice::from_raw_parts(addr as *const u8, 2 * PAGE_SIZE) };
+        assert_eq!(&pages[..100], &contents[PAGE_SIZE..]);
+        assert!(pages[100..].iter().all(|&b| b == 0));
+        assert_eq!(testing::file_offset(), 7);
+        assert_eq!(
+            testing::syscall(libc::SYS_munmap, [addr, 2 * PAGE_SIZE, 0, 0, 0, 0]),
+            0
+        );
+
+        let mmap = |fd: usize, offset: usize| {
+            testing::syscall(libc::SYS_mmap, [0, PAGE_SIZE, prot, flags, fd, offset])
+        };
+        assert_eq!(mmap(fd, 1), -(libc::EINVAL as isize));
+        assert_eq!(mmap(usize::MAX, 0), -(libc::EBADF as isize));
+        assert_eq!(mmap(1, 0), -(libc::ENODEV as isize));
+    }
+}
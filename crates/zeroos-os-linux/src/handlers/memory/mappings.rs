@@ -0,0 +1,445 @@
+//! Bookkeeping for anonymous mappings, so `munmap` can release exactly what `mmap` handed out.
+//!
+//! Each `mmap` is one allocator block. `munmap` may cover any page range: fragments of a block
+//! are tracked separately, and the block goes back to the allocator once its last page is gone.
+//! `mprotect` splits fragments the same way so every page range carries its own `PROT_*` bits.
+//!
+//! The table starts out in static memory. When an operation needs more slots than are free, it
+//! moves to an allocator block twice the size, so the number of live mappings is bounded by
+//! memory rather than a fixed limit.
+
+use core::alloc::Layout;
+
+use foundation::kfn;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Mapping {
+    /// Allocator block backing this mapping.
+    base: usize,
+    size: usize,
+    /// Still-mapped range `[start, end)` within the block.
+    start: usize,
+    end: usize,
+    /// `PROT_*` bits of `[start, end)`.
+    prot: usize,
+}
+
+pub(super) struct MappingTable<const N: usize> {
+    inline: [Option<Mapping>; N],
+    /// Slots the table moved to once `inline` ran out; null until then.
+    heap: *mut Option<Mapping>,
+    heap_len: usize,
+}
+
+impl<const N: usize> MappingTable<N> {
+    pub(super) const fn new() -> Self {
+        Self {
+            inline: [None; N],
+            heap: core::ptr::null_mut(),
+            heap_len: 0,
+        }
+    }
+
+    fn slots(&self) -> &[Option<Mapping>] {
+        if self.heap.is_null() {
+            &self.inline
+        } else {
+            // SAFETY: `heap` holds `heap_len` initialized slots, owned by the table.
+            unsafe { core::slice::from_raw_parts(self.heap, self.heap_len) }
+        }
+    }
+
+    fn slots_mut(&mut self) -> &mut [Option<Mapping>] {
+        if self.heap.is_null() {
+            &mut self.inline
+        } else {
+            // SAFETY: as in `slots`, and `&mut self` makes the borrow unique.
+            unsafe { core::slice::from_raw_parts_mut(self.heap, self.heap_len) }
+        }
+    }
+
+    fn free(&self) -> usize {
+        self.slots().iter().filter(|s| s.is_none()).count()
+    }
+
+    /// Make sure `n` slots are free, moving the table to a larger allocator block if they are
+    /// not. Returns false if that allocation fails.
+    fn make_room(&mut self, n: usize) -> bool {
+        let free = self.free();
+        if free >= n {
+            return true;
+        }
+        let len = self.slots().len();
+        let new_len = (len * 2).max(len + n - free);
+        let Ok(layout) = Layout::array::<Option<Mapping>>(new_len) else {
+            return false;
+        };
+        let heap = kfn::memory::kmalloc(layout) as *mut Option<Mapping>;
+        if heap.is_null() {
+            return false;
+        }
+        // SAFETY: `heap` is a fresh block of `new_len` slots.
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.slots().as_ptr(), heap, len);
+            for i in len..new_len {
+                heap.add(i).write(None);
+            }
+        }
+        if !self.heap.is_null() {
+            if let Ok(old) = Layout::array::<Option<Mapping>>(self.heap_len) {
+                kfn::memory::kfree(self.heap as *mut u8, old);
+            }
+        }
+        self.heap = heap;
+        self.heap_len = new_len;
+        true
+    }
+
+    /// Track a freshly allocated block; returns false if the table cannot grow to hold it.
+    pub(super) fn insert(&mut self, base: usize, size: usize, prot: usize) -> bool {
+        self.reserve(base, size, size, prot)
+    }
+
+    /// Track a block of `size` bytes of which only the first `len` are mapped; the rest is
+    /// room for [`Self::extend`].
+    pub(super) fn reserve(&mut self, base: usize, size: usize, len: usize, prot: usize) -> bool {
+        if !self.make_room(1) {
+            return false;
+        }
+        let Some(slot) = self.slots_mut().iter_mut().find(|s| s.is_none()) else {
+            return false;
+        };
+        *slot = Some(Mapping {
+            base,
+            size,
+            start: base,
+            end: base + len,
+            prot,
+        });
+        true
+    }
+
+    /// Protection of the fragment that is exactly `[addr, end)`.
+    pub(super) fn fragment_prot(&self, addr: usize, end: usize) -> Option<usize> {
+        self.slots()
+            .iter()
+            .flatten()
+            .find(|m| m.start == addr && m.end == end)
+            .map(|m| m.prot)
+    }
+
+    /// Grow the fragment `[addr, end)` to `new_end` in place, if its block has unmapped room
+    /// there. Returns false (and changes nothing) otherwise.
+    pub(super) fn extend(&mut self, addr: usize, end: usize, new_end: usize) -> bool {
+        let Some(i) = self
+            .slots()
+            .iter()
+            .position(|s| matches!(s, Some(m) if m.start == addr && m.end == end))
+        else {
+            return false;
+        };
+        let Some(m) = self.slots()[i] else {
+            return false;
+        };
+        if new_end > m.base + m.size {
+            return false;
+        }
+        let taken = self
+            .slots()
+            .iter()
+            .flatten()
+            .any(|o| o.base == m.base && o.start < new_end && end < o.end);
+        if taken {
+            return false;
+        }
+        self.slots_mut()[i] = Some(Mapping { end: new_end, ..m });
+        self.coalesce(i);
+        true
+    }
+
+    /// Protection of the page containing `addr`, if it is mapped.
+    pub(super) fn prot_at(&self, addr: usize) -> Option<usize> {
+        self.slots()
+            .iter()
+            .flatten()
+            .find(|m| m.start <= addr && addr < m.end)
+            .map(|m| m.prot)
+    }
+
+    /// Whether every byte of `[addr, end)` is mapped.
+    pub(super) fn covers(&self, addr: usize, end: usize) -> bool {
+        let mapped: usize = self
+            .slots()
+            .iter()
+            .flatten()
+            .map(|m| m.end.min(end).saturating_sub(m.start.max(addr)))
+            .sum();
+        mapped == end - addr
+    }
+
+    /// Make sure the table has the free slots [`Self::protect`] needs to split fragments.
+    /// Returns false if it cannot grow to that.
+    pub(super) fn make_room_to_protect(&mut self, addr: usize, end: usize, prot: usize) -> bool {
+        let splits: usize = self
+            .slots()
+            .iter()
+            .flatten()
+            .filter(|m| Self::changes(m, addr, end, prot))
+            .map(|m| usize::from(m.start < addr) + usize::from(end < m.end))
+            .sum();
+        self.make_room(splits)
+    }
+
+    fn changes(m: &Mapping, addr: usize, end: usize, prot: usize) -> bool {
+        m.start < end && addr < m.end && m.prot != prot
+    }
+
+    /// Set the protection of `[addr, end)`, which must be fully mapped (see [`Self::covers`]).
+    ///
+    /// Returns false without changing anything if [`Self::make_room_to_protect`] fails.
+    pub(super) fn protect(&mut self, addr: usize, end: usize, prot: usize) -> bool {
+        if !self.make_room_to_protect(addr, end, prot) {
+            return false;
+        }
+
+        for i in 0..self.slots().len() {
+            let Some(m) = self.slots()[i] else {
+                continue;
+            };
+            if !Self::changes(&m, addr, end, prot) {
+                continue;
+            }
+            let mid = Mapping {
+                start: m.start.max(addr),
+                end: m.end.min(end),
+                prot,
+                ..m
+            };
+            self.slots_mut()[i] = Some(mid);
+            if m.start < addr {
+                self.put(Mapping { end: addr, ..m });
+            }
+            if end < m.end {
+                self.put(Mapping { start: end, ..m });
+            }
+            self.coalesce(i);
+        }
+        true
+    }
+
+    fn put(&mut self, m: Mapping) {
+        // Callers check that a free slot exists.
+        if let Some(slot) = self.slots_mut().iter_mut().find(|s| s.is_none()) {
+            *slot = Some(m);
+        }
+    }
+
+    /// Merge slot `i` with fragments of the same block and protection that touch it, so
+    /// toggling protections back and forth does not use up slots.
+    fn coalesce(&mut self, i: usize) {
+        let Some(mut m) = self.slots()[i] else {
+            return;
+        };
+        for j in 0..self.slots().len() {
+            let Some(o) = self.slots()[j] else {
+                continue;
+            };
+            if j == i || o.base != m.base || o.prot != m.prot {
+                continue;
+            }
+            if o.end == m.start || m.end == o.start {
+                m.start = m.start.min(o.start);
+                m.end = m.end.max(o.end);
+                self.slots_mut()[j] = None;
+            }
+        }
+        self.slots_mut()[i] = Some(m);
+    }
+
+    /// Unmap `[addr, end)`, calling `release(base, size)` for every block left with no mapped
+    /// pages. Unmapped holes in the range are ignored, as on Linux.
+    ///
+    /// Returns false without changing anything if punching a hole would need more slots than
+    /// the table can grow to.
+    pub(super) fn unmap(
+        &mut self,
+        addr: usize,
+        end: usize,
+        mut release: impl FnMut(usize, usize),
+    ) -> bool {
+        let splits = self
+            .slots()
+            .iter()
+            .flatten()
+            .filter(|m| m.start < addr && end < m.end)
+            .count();
+        if !self.make_room(splits) {
+            return false;
+        }
+
+        for i in 0..self.slots().len() {
+            let Some(m) = self.slots()[i] else {
+                continue;
+            };
+            if end <= m.start || m.end <= addr {
+                continue;
+            }
+            match (addr <= m.start, m.end <= end) {
+                (true, true) => {
+                    self.slots_mut()[i] = None;
+                    if !self.slots().iter().flatten().any(|o| o.base == m.base) {
+                        release(m.base, m.size);
+                    }
+                }
+                (true, false) => self.slots_mut()[i] = Some(Mapping { start: end, ..m }),
+                (false, true) => self.slots_mut()[i] = Some(Mapping { end: addr, ..m }),
+                (false, false) => {
+                    self.slots_mut()[i] = Some(Mapping { end: addr, ..m });
+                    // Checked above that a free slot exists.
+                    let tail = self.slots_mut().iter_mut().find(|s| s.is_none());
+                    if let Some(tail) = tail {
+                        *tail = Some(Mapping { start: end, ..m });
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: usize = 4096;
+    const RW: usize = (libc::PROT_READ | libc::PROT_WRITE) as usize;
+    const RO: usize = libc::PROT_READ as usize;
+
+    fn released(table: &mut MappingTable<4>, addr: usize, end: usize) -> Option<(usize, usize)> {
+        let mut out = None;
+        assert!(table.unmap(addr, end, |b, s| out = Some((b, s))));
+        out
+    }
+
+    #[test]
+    fn test_whole_unmap_releases_block() {
+        let mut t = MappingTable::<4>::new();
+        assert!(t.insert(0x10000, 2 * PAGE, RW));
+        assert_eq!(
+            released(&mut t, 0x10000, 0x10000 + 2 * PAGE),
+            Some((0x10000, 2 * PAGE))
+        );
+        // Unmapping it again is a no-op.
+        assert_eq!(released(&mut t, 0x10000, 0x10000 + 2 * PAGE), None);
+    }
+
+    #[test]
+    fn test_partial_unmaps_release_on_last_page() {
+        let mut t = MappingTable::<4>::new();
+        let base = 0x20000;
+        assert!(t.insert(base, 4 * PAGE, RW));
+        // Punch a hole in the middle, then remove the head and tail.
+        assert_eq!(released(&mut t, base + PAGE, base + 3 * PAGE), None);
+        assert_eq!(released(&mut t, base, base + PAGE), None);
+        assert_eq!(
+            released(&mut t, base + 3 * PAGE, base + 4 * PAGE),
+            Some((base, 4 * PAGE))
+        );
+    }
+
+    #[test]
+    fn test_range_spanning_two_blocks() {
+        let mut t = MappingTable::<4>::new();
+        assert!(t.insert(0x30000, PAGE, RW));
+        assert!(t.insert(0x31000, PAGE, RW));
+        let mut freed = 0;
+        assert!(t.unmap(0x30000, 0x32000, |_, _| freed += 1));
+        assert_eq!(freed, 2);
+    }
+
+    #[test]
+    fn test_full_table_moves_to_heap() {
+        let _kernel = crate::testing::kernel();
+
+        let mut t = MappingTable::<1>::new();
+        assert!(t.insert(0x40000, 3 * PAGE, RW));
+        assert!(t.insert(0x50000, PAGE, RW));
+        assert!(!t.heap.is_null());
+        // Punching a hole takes another slot.
+        assert!(t.unmap(0x41000, 0x42000, |_, _| unreachable!()));
+        assert_eq!(t.slots().iter().flatten().count(), 3);
+        assert_eq!(t.prot_at(0x40000), Some(RW));
+        assert_eq!(t.prot_at(0x41000), None);
+        assert_eq!(t.prot_at(0x42000), Some(RW));
+        assert_eq!(t.prot_at(0x50000), Some(RW));
+
+        let mut freed = 0;
+        assert!(t.unmap(0x40000, 0x51000, |_, _| freed += 1));
+        assert_eq!(freed, 2);
+    }
+    #[test]
+    fn test_protect_splits_and_coalesces() {
+        let mut t = MappingTable::<4>::new();
+        let base = 0x60000;
+        assert!(t.insert(base, 4 * PAGE, RW));
+        assert!(t.covers(base, base + 4 * PAGE));
+        assert!(!t.covers(base, base + 5 * PAGE));
+
+        assert!(t.protect(base + PAGE, base + 2 * PAGE, RO));
+        assert_eq!(t.prot_at(base), Some(RW));
+        assert_eq!(t.prot_at(base + PAGE), Some(RO));
+        assert_eq!(t.prot_at(base + 2 * PAGE), Some(RW));
+        assert_eq!(t.slots().iter().flatten().count(), 3);
+
+        // Restoring the protection merges the fragments back into one.
+        assert!(t.protect(base + PAGE, base + 2 * PAGE, RW));
+        assert_eq!(t.slots().iter().flatten().count(), 1);
+
+        // Fragments still release the block once all are unmapped.
+        assert!(t.protect(base, base + PAGE, RO));
+        assert_eq!(released(&mut t, base + PAGE, base + 4 * PAGE), None);
+        assert_eq!(released(&mut t, base, base + PAGE), Some((base, 4 * PAGE)));
+        assert_eq!(t.prot_at(base), None);
+    }
+
+    #[test]
+    fn test_protect_grows_table() {
+        let _kernel = crate::testing::kernel();
+
+        let mut t = MappingTable::<1>::new();
+        assert!(t.insert(0x70000, 3 * PAGE, RW));
+        assert!(t.protect(0x71000, 0x72000, RO));
+        assert_eq!(t.prot_at(0x70000), Some(RW));
+        assert_eq!(t.prot_at(0x71000), Some(RO));
+        assert_eq!(t.prot_at(0x72000), Some(RW));
+    }
+
+    #[test]
+    fn test_extend_within_reserved_block() {
+        let mut t = MappingTable::<4>::new();
+        let base = 0x80000;
+        assert!(t.reserve(base, 4 * PAGE, PAGE, RW));
+        assert_eq!(t.prot_at(base + PAGE), None);
+        assert_eq!(t.fragment_prot(base, base + PAGE), Some(RW));
+
+        assert!(t.extend(base, base + PAGE, base + 3 * PAGE));
+        assert_eq!(t.prot_at(base + 2 * PAGE), Some(RW));
+        assert!(!t.extend(base, base + 3 * PAGE, base + 5 * PAGE));
+
+        // A shrunk fragment can grow back into its own tail.
+        assert_eq!(released(&mut t, base + 2 * PAGE, base + 3 * PAGE), None);
+        assert!(t.extend(base, base + 2 * PAGE, base + 4 * PAGE));
+        assert_eq!(t.fragment_prot(base, base + 4 * PAGE), Some(RW));
+    }
+
+    #[test]
+    fn test_extend_blocked_by_sibling_fragment() {
+        let mut t = MappingTable::<4>::new();
+        let base = 0x90000;
+        assert!(t.insert(base, 3 * PAGE, RW));
+        assert_eq!(released(&mut t, base + PAGE, base + 2 * PAGE), None);
+        assert!(!t.extend(base, base + PAGE, base + 3 * PAGE));
+        assert!(t.extend(base, base + PAGE, base + 2 * PAGE));
+        assert_eq!(t.slots().iter().flatten().count(), 1);
+    }
+}
@@ -7,11 +7,15 @@ use libc;
 
 #[cfg(feature = "memory")]
 pub mod memory;
+pub mod process;
 #[cfg(feature = "random")]
 pub mod random;
+pub mod sched;
 pub mod signal;
 #[cfg(feature = "scheduler")]
 pub mod thread;
+#[cfg(feature = "time")]
+pub mod time;
 #[cfg(feature = "vfs")]
 pub mod vfs;
 
@@ -48,3 +52,28 @@ cfg_if! {
         }
     }
 }
+
+/// Nanoseconds left until `deadline` on `clock_id`, measured on that clock, since the guest took
+/// the deadline from it. Without clocks, deadlines count from boot on the scheduler's clock.
+#[cfg(feature = "scheduler")]
+fn until_deadline(clock_id: usize, deadline: u64) -> u64 {
+    cfg_if! {
+        if #[cfg(feature = "time")] {
+            let now = kfn::time::know_ns(clock_id).max(0) as u64;
+        } else {
+            let _ = clock_id;
+            let now = kfn::scheduler::kclock_ns();
+        }
+    }
+    deadline.saturating_sub(now)
+}
+
+/// Skip the clocks forward to `deadline` on `clock_id` after a wait for it timed out, since the
+/// scheduler's clock it timed out on need not keep pace with them.
+#[cfg(feature = "scheduler")]
+fn pass_deadline(clock_id: usize, deadline: u64) {
+    #[cfg(feature = "time")]
+    kfn::time::kadvance_ns(until_deadline(clock_id, deadline));
+    #[cfg(not(feature = "time"))]
+    let _ = (clock_id, deadline);
+}
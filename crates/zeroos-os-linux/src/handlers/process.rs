@@ -0,0 +1,115 @@
+//! Process attribute syscalls libc issues while setting up threads.
+//!
+//! musl and glibc call `set_robust_list`, `prctl(PR_SET_NAME)` and `rseq` from their thread
+//! start paths. Robust list heads are kept per thread by the scheduler, the name is recorded
+//! process-wide, and `rseq` reports itself unavailable, which libc handles by not using it.
+//! Without a scheduler there is one thread and nothing ever walks its robust list, so the list
+//! is accepted but not kept.
+
+use cfg_if::cfg_if;
+use foundation::utils::GlobalCell;
+use libc;
+
+/// Kernel `struct robust_list_head`: list head, futex offset and pending entry.
+pub const ROBUST_LIST_HEAD_SIZE: usize = 3 * core::mem::size_of::<usize>();
+
+/// `TASK_COMM_LEN`: thread names are truncated to 15 bytes plus the NUL.
+const COMM_LEN: usize = 16;
+
+/// Name set with `PR_SET_NAME`, shared by every thread.
+static COMM: GlobalCell<[u8; COMM_LEN]> = GlobalCell::new(*b"main\0\0\0\0\0\0\0\0\0\0\0\0");
+
+pub fn sys_set_robust_list(head: usize, len: usize) -> isize {
+    if len != ROBUST_LIST_HEAD_SIZE {
+        return -(libc::EINVAL as isize);
+    }
+    robust::set(head)
+}
+
+/// `pid` 0 names the calling thread; any other value must be a thread id.
+pub fn sys_get_robust_list(pid: usize, head_ptr: usize, len_ptr: usize) -> isize {
+    if head_ptr == 0 || len_ptr == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let head = match robust::get(pid) {
+        Ok(head) => head,
+        Err(e) => return e,
+    };
+    unsafe {
+        (head_ptr as *mut usize).write_unaligned(head);
+        (len_ptr as *mut usize).write_unaligned(ROBUST_LIST_HEAD_SIZE);
+    }
+    0
+}
+
+cfg_if! {
+    if #[cfg(feature = "scheduler")] {
+        mod robust {
+            use foundation::kfn;
+
+            pub fn set(head: usize) -> isize {
+                match kfn::scheduler::kset_robust_list(head) {
+                    Ok(()) => 0,
+                    Err(e) => e.into(),
+                }
+            }
+
+            pub fn get(pid: usize) -> Result<usize, isize> {
+                let tid = if pid == 0 { kfn::scheduler::kcurrent_tid() } else { pid };
+                kfn::scheduler::krobust_list(tid).map_err(Into::into)
+            }
+        }
+    } else {
+        mod robust {
+            pub fn set(_head: usize) -> isize {
+                0
+            }
+
+            pub fn get(pid: usize) -> Result<usize, isize> {
+                if pid == 0 || pid == 1 {
+                    Ok(0)
+                } else {
+                    Err(-(libc::ESRCH as isize))
+                }
+            }
+        }
+    }
+}
+
+/// `prctl` for `PR_SET_NAME` and `PR_GET_NAME`; other options fail with `EINVAL`.
+pub fn sys_prctl(option: usize, arg2: usize, _arg3: usize, _arg4: usize, _arg5: usize) -> isize {
+    match option as i32 {
+        libc::PR_SET_NAME => {
+            if arg2 == 0 {
+                return -(libc::EFAULT as isize);
+            }
+            let mut name = [0u8; COMM_LEN];
+            for (i, slot) in name[..COMM_LEN - 1].iter_mut().enumerate() {
+                let byte = unsafe { ((arg2 + i) as *const u8).read_volatile() };
+                if byte == 0 {
+                    break;
+                }
+                *slot = byte;
+            }
+            COMM.with_mut(|comm| *comm = name);
+            0
+        }
+        libc::PR_GET_NAME => {
+            if arg2 == 0 {
+                return -(libc::EFAULT as isize);
+            }
+            COMM.with(|comm| unsafe {
+                core::ptr::copy_nonoverlapping(comm.as_ptr(), arg2 as *mut u8, COMM_LEN);
+            });
+            0
+        }
+        _ => -(libc::EINVAL as isize),
+    }
+}
+
+/// Restartable sequences need the kernel to keep `cpu_id` current and abort critical sections
+/// on preemption. Neither is done here, so registration reports `ENOSYS` like a kernel built
+/// without rseq, and libc carries on without it.
+pub fn sys_rseq(_rseq: usize, _len: usize, _flags: usize, _sig: usize) -> isize {
+    -(libc::ENOSYS as isize)
+}
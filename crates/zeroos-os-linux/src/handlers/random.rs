@@ -1,7 +1,17 @@
 use foundation::kfn;
 use libc;
 
-pub fn sys_getrandom(buf: usize, buflen: usize, _flags: usize) -> isize {
+pub fn sys_getrandom(buf: usize, buflen: usize, flags: usize) -> isize {
+    let flags = flags as u32;
+    if (flags & !(libc::GRND_NONBLOCK | libc::GRND_RANDOM | libc::GRND_INSECURE)) != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    // Linux rejects asking for both the blocking pool and explicitly insecure bytes.
+    if (flags & libc::GRND_RANDOM) != 0 && (flags & libc::GRND_INSECURE) != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    // The RNG is seeded during bootstrap, before the guest runs, so reads never block and
+    // GRND_NONBLOCK never has to report EAGAIN.
     if buflen == 0 {
         return 0;
     }
@@ -0,0 +1,114 @@
+//! CPU affinity emulation.
+//!
+//! Guests see a fixed set of logical CPUs, `0..online_cpus()`, which runtimes such as Rayon and
+//! `sysconf(_SC_NPROCESSORS_ONLN)` size their thread pools from. The count is a platform knob
+//! set with [`set_online_cpus`] during bootstrap and defaults to 1, so a guest's thread count
+//! never depends on the host. Every thread may run on every CPU; setting an affinity is
+//! accepted as long as it names one of them.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use libc;
+
+/// Largest CPU count reported, the size of a glibc/musl `cpu_set_t`.
+pub const MAX_CPUS: usize = 1024;
+
+const WORD_BITS: usize = usize::BITS as usize;
+
+static ONLINE_CPUS: AtomicUsize = AtomicUsize::new(1);
+
+/// Report `count` logical CPUs (clamped to `1..=MAX_CPUS`). Call during bootstrap.
+pub fn set_online_cpus(count: usize) {
+    ONLINE_CPUS.store(count.clamp(1, MAX_CPUS), Ordering::Relaxed);
+}
+
+pub fn online_cpus() -> usize {
+    ONLINE_CPUS.load(Ordering::Relaxed)
+}
+
+/// Bytes of mask the kernel copies: whole words covering every online CPU.
+fn mask_bytes(cpus: usize) -> usize {
+    cpus.div_ceil(WORD_BITS) * core::mem::size_of::<usize>()
+}
+
+/// Fill `mask` with the first `cpus` bits set; returns the bytes written or a negative errno.
+fn fill_mask(mask: &mut [u8], cpus: usize) -> isize {
+    let bytes = mask_bytes(cpus);
+    if mask.len() < bytes || !mask.len().is_multiple_of(core::mem::size_of::<usize>()) {
+        return -(libc::EINVAL as isize);
+    }
+    let mask = &mut mask[..bytes];
+    mask.fill(0);
+    mask[..cpus / 8].fill(0xff);
+    if !cpus.is_multiple_of(8) {
+        mask[cpus / 8] = (1u8 << (cpus % 8)) - 1;
+    }
+    bytes as isize
+}
+
+/// Whether `mask` selects at least one of the first `cpus` CPUs.
+fn names_online_cpu(mask: &[u8], cpus: usize) -> bool {
+    mask.iter()
+        .enumerate()
+        .take(cpus.div_ceil(8))
+        .any(|(i, &byte)| {
+            let valid = cpus.saturating_sub(i * 8).min(8);
+            byte & (((1u16 << valid) - 1) as u8) != 0
+        })
+}
+
+pub fn sys_sched_getaffinity(_pid: usize, len: usize, mask: usize) -> isize {
+    if mask == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let mask = unsafe { core::slice::from_raw_parts_mut(mask as *mut u8, len) };
+    fill_mask(mask, online_cpus())
+}
+
+pub fn sys_sched_setaffinity(_pid: usize, len: usize, mask: usize) -> isize {
+    if mask == 0 {
+        return -(libc::EFAULT as isize);
+    }
+    let mask = unsafe { core::slice::from_raw_parts(mask as *const u8, len) };
+    if !names_online_cpu(mask, online_cpus()) {
+        return -(libc::EINVAL as isize);
+    }
+    0
+}
+
+/// Every thread reports CPU 0 on NUMA node 0.
+pub fn sys_getcpu(cpu: usize, node: usize, _cache: usize) -> isize {
+    unsafe {
+        if cpu != 0 {
+            (cpu as *mut u32).write_unaligned(0);
+        }
+        if node != 0 {
+            (node as *mut u32).write_unaligned(0);
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affinity_masks() {
+        let word = core::mem::size_of::<usize>();
+        let mut mask = [0xaau8; 128];
+        assert_eq!(fill_mask(&mut mask, 12), word as isize);
+        assert_eq!(&mask[..3], &[0xff, 0x0f, 0]);
+        assert!(mask[2..word].iter().all(|&b| b == 0));
+        assert_eq!(mask[word], 0xaa);
+
+        assert_eq!(fill_mask(&mut mask, 1024), 128);
+        assert_eq!(fill_mask(&mut mask[..word], 1024), -(libc::EINVAL as isize));
+        assert_eq!(fill_mask(&mut mask[..3], 1), -(libc::EINVAL as isize));
+
+        assert!(names_online_cpu(&[0b0100], 3));
+        assert!(!names_online_cpu(&[0b1000], 3));
+        assert!(!names_online_cpu(&[0, 1], 8));
+        assert!(names_online_cpu(&[0, 1], 9));
+    }
+}
@@ -1,8 +1,11 @@
 //! Signal handling for ZeroOS
 //!
-//! Implements lightweight signal support for zkVM environment.
-//! Only handles SIGABRT for panic detection, other signals return ENOSYS.
+//! Implements lightweight signal support for zkVM environment: `rt_sigaction` and
+//! `rt_sigprocmask` keep a real (process-wide) handler table and mask, and with the `signal`
+//! feature synchronous faults (SIGSEGV, SIGILL, SIGBUS, ...) are delivered to the registered
+//! handler by rewriting the trap frame. `tkill` still only handles SIGABRT for panic detection.
 
+use foundation::utils::GlobalCell;
 use libc;
 
 extern "C" {
@@ -14,20 +17,154 @@ extern "C" {
     fn __platform_abort(sig: i32) -> !;
 }
 
+/// Highest signal number (Linux `_NSIG - 1`).
+const NSIG: usize = 64;
+
+/// Kernel `sigset_t` size; the only one `rt_sig*` accept.
+const SIGSET_SIZE: usize = 8;
+
+/// Signals whose disposition and mask bit can never change.
+const UNBLOCKABLE: u64 = sig_bit(libc::SIGKILL as usize) | sig_bit(libc::SIGSTOP as usize);
+
+/// Kernel `struct sigaction` as passed to `rt_sigaction` (no `sa_restorer` on RISC-V).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KSigaction {
+    pub handler: usize,
+    pub flags: usize,
+    pub mask: u64,
+}
+
+impl KSigaction {
+    const DEFAULT: Self = Self {
+        handler: libc::SIG_DFL,
+        flags: 0,
+        mask: 0,
+    };
+}
+
+#[inline]
+const fn sig_bit(sig: usize) -> u64 {
+    1 << (sig - 1)
+}
+
+/// Handler table and blocked mask.
+pub struct SignalTable {
+    actions: [KSigaction; NSIG],
+    mask: u64,
+}
+
+impl SignalTable {
+    pub const fn new() -> Self {
+        Self {
+            actions: [KSigaction::DEFAULT; NSIG],
+            mask: 0,
+        }
+    }
+
+    /// Swap the action for `sig`, returning the previous one, or `None` for an invalid signal.
+    pub fn set_action(&mut self, sig: usize, act: Option<KSigaction>) -> Option<KSigaction> {
+        if !(1..=NSIG).contains(&sig) {
+            return None;
+        }
+        if act.is_some() && (sig_bit(sig) & UNBLOCKABLE) != 0 {
+            return None;
+        }
+        let old = self.actions[sig - 1];
+        if let Some(act) = act {
+            self.actions[sig - 1] = act;
+        }
+        Some(old)
+    }
+
+    /// Apply `rt_sigprocmask(how, set)`, returning the previous mask or `None` for a bad `how`.
+    pub fn update_mask(&mut self, how: i32, set: Option<u64>) -> Option<u64> {
+        let old = self.mask;
+        if let Some(set) = set {
+            self.mask = match how {
+                libc::SIG_BLOCK => old | set,
+                libc::SIG_UNBLOCK => old & !set,
+                libc::SIG_SETMASK => set,
+                _ => return None,
+            } & !UNBLOCKABLE;
+        }
+        Some(old)
+    }
+
+    /// Begin delivering `sig`: the handler to run and the mask to restore afterwards.
+    ///
+    /// Returns `None` when the signal is blocked or has no handler; a synchronous fault then
+    /// terminates the guest, as Linux does for ignored or blocked faults.
+    pub fn begin_delivery(&mut self, sig: usize) -> Option<(usize, u64)> {
+        if !(1..=NSIG).contains(&sig) || (self.mask & sig_bit(sig)) != 0 {
+            return None;
+        }
+        let act = self.actions[sig - 1];
+        if act.handler == libc::SIG_DFL || act.handler == libc::SIG_IGN {
+            return None;
+        }
+        let saved = self.mask;
+        self.mask |= act.mask;
+        if (act.flags & libc::SA_NODEFER as usize) == 0 {
+            self.mask |= sig_bit(sig);
+        }
+        self.mask &= !UNBLOCKABLE;
+        if (act.flags & libc::SA_RESETHAND as usize) != 0 {
+            self.actions[sig - 1] = KSigaction::DEFAULT;
+        }
+        Some((act.handler, saved))
+    }
+
+    pub fn restore_mask(&mut self, mask: u64) {
+        self.mask = mask & !UNBLOCKABLE;
+    }
+}
+
+impl Default for SignalTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static SIGNALS: GlobalCell<SignalTable> = GlobalCell::new(SignalTable::new());
+
 /// Handle rt_sigaction syscall
-///
-/// For zkVM, we don't need full signal handler tables.
-/// We accept all sigaction calls but ignore them (return success).
-pub fn sys_rt_sigaction(_signum: usize, _act: usize, _oldact: usize, _sigsetsize: usize) -> isize {
-    // Accept but ignore - we'll handle signals directly
+pub fn sys_rt_sigaction(signum: usize, act: usize, oldact: usize, sigsetsize: usize) -> isize {
+    if sigsetsize != SIGSET_SIZE {
+        return -(libc::EINVAL as isize);
+    }
+    let align = core::mem::align_of::<KSigaction>();
+    if !act.is_multiple_of(align) || !oldact.is_multiple_of(align) {
+        return -(libc::EFAULT as isize);
+    }
+    let new = (act != 0).then(|| unsafe { core::ptr::read(act as *const KSigaction) });
+    let Some(old) = SIGNALS.with_mut(|t| t.set_action(signum, new)) else {
+        return -(libc::EINVAL as isize);
+    };
+    if oldact != 0 {
+        unsafe { core::ptr::write(oldact as *mut KSigaction, old) };
+    }
     0
 }
 
 /// Handle rt_sigprocmask syscall
 ///
-/// Signal masks aren't needed in zkVM - accept but ignore.
-pub fn sys_rt_sigprocmask(_how: usize, _set: usize, _oldset: usize, _sigsetsize: usize) -> isize {
-    // Accept but ignore
+/// There is one mask for the whole guest rather than one per thread.
+pub fn sys_rt_sigprocmask(how: usize, set: usize, oldset: usize, sigsetsize: usize) -> isize {
+    if sigsetsize != SIGSET_SIZE {
+        return -(libc::EINVAL as isize);
+    }
+    let align = core::mem::align_of::<u64>();
+    if !set.is_multiple_of(align) || !oldset.is_multiple_of(align) {
+        return -(libc::EFAULT as isize);
+    }
+    let new = (set != 0).then(|| unsafe { core::ptr::read(set as *const u64) });
+    let Some(old) = SIGNALS.with_mut(|t| t.update_mask(how as i32, new)) else {
+        return -(libc::EINVAL as isize);
+    };
+    if oldset != 0 {
+        unsafe { core::ptr::write(oldset as *mut u64, old) };
+    }
     0
 }
 
@@ -57,3 +194,184 @@ pub fn sys_tgkill(_tgid: usize, tid: usize, sig: usize) -> isize {
     // For single-threaded zkVM, tgkill behaves like tkill
     sys_tkill(tid, sig)
 }
+
+#[cfg(feature = "signal")]
+mod deliver {
+    use super::SIGNALS;
+    use foundation::kfn::arch as karch;
+
+    /// Linux `siginfo_t` with the fault fields filled in (always 128 bytes).
+    #[repr(C)]
+    struct SigInfo {
+        signo: i32,
+        errno: i32,
+        code: i32,
+        addr: usize,
+        _pad: [u8; 128 - 8 - 2 * core::mem::size_of::<usize>()],
+    }
+
+    const _: () = assert!(core::mem::size_of::<SigInfo>() == 128);
+
+    /// Marks a signal frame so a stray `rt_sigreturn` is not mistaken for one.
+    const FRAME_MAGIC: usize = 0x5349_4746; // "SIGF"
+
+    /// Pushed below the interrupted `sp`; the saved trap frame follows at `trap_frame_offset()`.
+    #[repr(C)]
+    struct SignalFrame {
+        magic: usize,
+        saved_mask: u64,
+        info: SigInfo,
+    }
+
+    fn trap_frame_offset() -> usize {
+        core::mem::size_of::<SignalFrame>().next_multiple_of(karch::ktrap_frame_align())
+    }
+
+    // The handler returns here with `sp` back at the signal frame.
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    core::arch::global_asm!(
+        ".pushsection .text.__zeroos_sigreturn,\"ax\",@progbits",
+        ".globl __zeroos_sigreturn",
+        ".p2align 2",
+        "__zeroos_sigreturn:",
+        "   li      a7, {nr}",
+        "   ecall",
+        "   unimp",
+        ".popsection",
+        nr = const libc::SYS_rt_sigreturn,
+    );
+
+    extern "C" {
+        fn __zeroos_sigreturn();
+    }
+
+    /// Redirect the trapped context in `regs` into the handler for synchronous signal `sig`.
+    ///
+    /// The handler runs as `handler(sig, &siginfo, ctx)` on the interrupted stack, where `ctx`
+    /// points at the saved ZeroOS trap frame rather than a Linux `ucontext_t`. Returning from it
+    /// issues `rt_sigreturn`, which the platform forwards to [`sigreturn`].
+    ///
+    /// Returns `false` when the guest has no handler for `sig` (or has it blocked); the caller
+    /// should then terminate as before.
+    ///
+    /// # Safety
+    /// `regs` must point to the valid trap frame of the faulting context.
+    pub unsafe fn deliver_fault(regs: *mut u8, sig: i32, code: i32, addr: usize) -> bool {
+        let Some((handler, saved_mask)) = SIGNALS.with_mut(|t| t.begin_delivery(sig as usize))
+        else {
+            return false;
+        };
+
+        let tf_size = karch::ktrap_frame_size();
+        let sp = karch::ktrap_frame_get_sp(regs);
+        let frame_addr = (sp - trap_frame_offset() - tf_size) & !0xf;
+        let frame = frame_addr as *mut SignalFrame;
+        // SAFETY: the frame is carved out of the interrupted stack below its live data; a fault
+        // while writing it re-enters with `sig` blocked and terminates the guest.
+        unsafe {
+            core::ptr::addr_of_mut!((*frame).info).write_bytes(0, 1);
+            (*frame).magic = FRAME_MAGIC;
+            (*frame).saved_mask = saved_mask;
+            (*frame).info.signo = sig;
+            (*frame).info.code = code;
+            (*frame).info.addr = addr;
+            let saved = (frame_addr + trap_frame_offset()) as *mut u8;
+            karch::ktrap_frame_clone(saved, regs);
+
+            karch::ktrap_frame_set_sp(regs, frame_addr);
+            karch::ktrap_frame_set_pc(regs, handler);
+            karch::ktrap_frame_set_ra(regs, __zeroos_sigreturn as *const () as usize);
+            karch::ktrap_frame_set_retval(regs, sig as usize);
+            karch::ktrap_frame_set_arg(regs, 1, core::ptr::addr_of!((*frame).info) as usize);
+            karch::ktrap_frame_set_arg(regs, 2, saved as usize);
+        }
+        true
+    }
+
+    /// Resume the context saved by [`deliver_fault`]; `regs` is the `rt_sigreturn` trap frame.
+    ///
+    /// Returns `false` (leaving `regs` untouched) if `sp` does not point at a signal frame.
+    ///
+    /// # Safety
+    /// `regs` must point to the valid trap frame of the `rt_sigreturn` call.
+    pub unsafe fn sigreturn(regs: *mut u8) -> bool {
+        let frame_addr = karch::ktrap_frame_get_sp(regs);
+        let frame = frame_addr as *const SignalFrame;
+        // SAFETY: `sp` is back where `deliver_fault` built the frame; the magic check rejects
+        // calls from anywhere else.
+        unsafe {
+            if frame_addr == 0 || (*frame).magic != FRAME_MAGIC {
+                return false;
+            }
+            let saved_mask = (*frame).saved_mask;
+            SIGNALS.with_mut(|t| t.restore_mask(saved_mask));
+            karch::ktrap_frame_clone(regs, (frame_addr + trap_frame_offset()) as *const u8);
+        }
+        true
+    }
+}
+
+#[cfg(feature = "signal")]
+pub use deliver::{deliver_fault, sigreturn};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handler(addr: usize, flags: usize) -> KSigaction {
+        KSigaction {
+            handler: addr,
+            flags,
+            mask: 0,
+        }
+    }
+
+    #[test]
+    fn test_sigaction_roundtrip() {
+        let mut t = SignalTable::new();
+        let segv = libc::SIGSEGV as usize;
+        assert_eq!(
+            t.set_action(segv, Some(handler(0x1000, 0))),
+            Some(KSigaction::DEFAULT)
+        );
+        assert_eq!(t.set_action(segv, None), Some(handler(0x1000, 0)));
+        assert_eq!(t.set_action(0, None), None);
+        assert_eq!(
+            t.set_action(libc::SIGKILL as usize, Some(handler(0x1000, 0))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_procmask_never_blocks_sigkill() {
+        let mut t = SignalTable::new();
+        assert_eq!(t.update_mask(libc::SIG_SETMASK, Some(u64::MAX)), Some(0));
+        assert_eq!(t.update_mask(libc::SIG_BLOCK, None), Some(!UNBLOCKABLE));
+        assert_eq!(t.update_mask(99, Some(0)), None);
+    }
+
+    #[test]
+    fn test_delivery_blocks_signal_until_restored() {
+        let mut t = SignalTable::new();
+        let segv = libc::SIGSEGV as usize;
+        assert_eq!(t.begin_delivery(segv), None);
+
+        t.set_action(segv, Some(handler(0x1000, 0)));
+        let (h, saved) = t.begin_delivery(segv).unwrap();
+        assert_eq!((h, saved), (0x1000, 0));
+        // A nested fault while the handler runs is not delivered again.
+        assert_eq!(t.begin_delivery(segv), None);
+        t.restore_mask(saved);
+        assert!(t.begin_delivery(segv).is_some());
+    }
+
+    #[test]
+    fn test_resethand_restores_default() {
+        let mut t = SignalTable::new();
+        let ill = libc::SIGILL as usize;
+        t.set_action(ill, Some(handler(0x2000, libc::SA_RESETHAND as usize)));
+        assert!(t.begin_delivery(ill).is_some());
+        t.restore_mask(0);
+        assert_eq!(t.begin_delivery(ill), None);
+    }
+}
@@ -1,52 +1,80 @@
 use libc;
 
-use foundation::kfn;
+use foundation::{kfn, IntoRet, KError};
 
-pub fn sys_clone(
+/// Flags `clone` accepts. musl's and glibc's `pthread_create` use all of them but
+/// `CLONE_CHILD_SETTID`.
+const CLONE_SUPPORTED: usize = (libc::CLONE_VM
+    | libc::CLONE_FS
+    | libc::CLONE_FILES
+    | libc::CLONE_SIGHAND
+    | libc::CLONE_SYSVSEM
+    | libc::CLONE_THREAD
+    | libc::CLONE_SETTLS
+    | libc::CLONE_PARENT_SETTID
+    | libc::CLONE_CHILD_CLEARTID
+    | libc::CLONE_CHILD_SETTID
+    | libc::CLONE_DETACHED) as usize;
+
+/// Check a `clone` request against what the scheduler can do: start a thread in the one shared
+/// address space.
+///
+/// Unknown flags and combinations Linux itself rejects fail with `EINVAL`. A child with its own
+/// address space (fork-style, no `CLONE_VM`) or one that suspends the parent (`CLONE_VFORK`) is
+/// a valid request there is no process model for, so it fails with `ENOSYS` and libc's `fork`
+/// and `posix_spawn` report that instead of running the child on the parent's memory.
+fn check_clone_args(
     flags: usize,
-    stack: usize,
     parent_tid: usize,
     tls: usize,
     child_tid: usize,
-) -> isize {
-    if stack == 0 {
-        return -(libc::EINVAL as isize);
+) -> Result<(), isize> {
+    let einval = -(libc::EINVAL as isize);
+    let has = |flag: libc::c_int| flags & flag as usize != 0;
+
+    // Linux `clone(2)` packs the exit signal in the low 8 bits; any value is accepted.
+    if flags & !0xff & !(CLONE_SUPPORTED | libc::CLONE_VFORK as usize) != 0 {
+        return Err(einval);
     }
-    if parent_tid != 0 && !parent_tid.is_multiple_of(core::mem::align_of::<i32>()) {
-        return -(libc::EINVAL as isize);
+    // A thread shares its signal handlers, and shared handlers need shared memory.
+    if has(libc::CLONE_THREAD) && !has(libc::CLONE_SIGHAND) {
+        return Err(einval);
     }
-    if child_tid != 0 && !child_tid.is_multiple_of(core::mem::align_of::<i32>()) {
-        return -(libc::EINVAL as isize);
+    if has(libc::CLONE_SIGHAND) && !has(libc::CLONE_VM) {
+        return Err(einval);
     }
-
-    // Linux `clone(2)` packs a signal number in the low 8 bits.
-    // We allow any signal value, but reject unsupported flag bits.
-    let allowed_flags = (libc::CLONE_VM
-        | libc::CLONE_FS
-        | libc::CLONE_FILES
-        | libc::CLONE_SIGHAND
-        | libc::CLONE_SYSVSEM
-        | libc::CLONE_THREAD
-        | libc::CLONE_SETTLS
-        | libc::CLONE_PARENT_SETTID
-        | libc::CLONE_CHILD_CLEARTID
-        | libc::CLONE_CHILD_SETTID
-        | libc::CLONE_DETACHED) as usize;
-    let flags_no_sig = flags & !0xff;
-    if (flags_no_sig & !allowed_flags) != 0 {
-        return -(libc::EINVAL as isize);
+    if !has(libc::CLONE_VM) || has(libc::CLONE_VFORK) {
+        return Err(-(libc::ENOSYS as isize));
     }
-    if (flags & libc::CLONE_PARENT_SETTID as usize) != 0 && parent_tid == 0 {
-        return -(libc::EINVAL as isize);
-    }
-    if (flags & libc::CLONE_CHILD_CLEARTID as usize) != 0 && child_tid == 0 {
-        return -(libc::EINVAL as isize);
+
+    for (addr, needed) in [
+        (parent_tid, has(libc::CLONE_PARENT_SETTID)),
+        (
+            child_tid,
+            has(libc::CLONE_CHILD_SETTID) || has(libc::CLONE_CHILD_CLEARTID),
+        ),
+    ] {
+        if needed && (addr == 0 || !addr.is_multiple_of(core::mem::align_of::<i32>())) {
+            return Err(einval);
+        }
     }
-    if (flags & libc::CLONE_CHILD_SETTID as usize) != 0 && child_tid == 0 {
-        return -(libc::EINVAL as isize);
+    if has(libc::CLONE_SETTLS) && tls == 0 {
+        return Err(einval);
     }
-    if (flags & libc::CLONE_SETTLS as usize) != 0 && tls == 0 {
-        return -(libc::EINVAL as isize);
+    Ok(())
+}
+
+pub fn sys_clone(
+    flags: usize,
+    stack: usize,
+    parent_tid: usize,
+    tls: usize,
+    child_tid: usize,
+) -> isize {
+    // A NULL `stack` asks the scheduler for a guarded stack of its own (see
+    // `Scheduler::set_stack_size`) instead of sharing the parent's.
+    if let Err(e) = check_clone_args(flags, parent_tid, tls, child_tid) {
+        return e;
     }
 
     let tls_val = if (flags & libc::CLONE_SETTLS as usize) != 0 {
@@ -79,17 +107,63 @@ pub fn sys_clone(
         child_tid_ptr,
         clear_child_tid_ptr,
     )
+    .into_ret()
 }
 
 pub fn sys_exit(status: usize) -> isize {
-    kfn::scheduler::kexit_current(status as i32)
+    kfn::scheduler::kexit_current(status as i32).into_ret()
 }
 
 pub fn sys_exit_group(status: usize) -> isize {
-    kfn::scheduler::kexit_current(status as i32)
+    kfn::scheduler::kexit_current(status as i32).into_ret()
+}
+
+/// `wait4` on a thread id: joins a thread spawned by `clone` and reports its exit status.
+///
+/// There are no child processes, so only `pid > 0` naming a live or exited thread is accepted.
+pub fn sys_wait4(pid: usize, wstatus: usize, options: usize, rusage: usize) -> isize {
+    let allowed_options =
+        (libc::WNOHANG | libc::__WCLONE | libc::__WALL | libc::__WNOTHREAD) as u32;
+    if (options as u32 & !allowed_options) != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    if wstatus != 0 && !wstatus.is_multiple_of(core::mem::align_of::<i32>()) {
+        return -(libc::EINVAL as isize);
+    }
+    let pid = pid as i32;
+    if pid <= 0 {
+        return -(libc::ECHILD as isize);
+    }
+
+    let nohang = (options as i32 & libc::WNOHANG) != 0;
+    let mut code: i32 = 0;
+    let tid = match kfn::scheduler::kjoin(pid as usize, &mut code as *mut i32 as usize, nohang) {
+        Ok(0) => return 0,
+        Ok(tid) => tid,
+        Err(KError::Srch) => return KError::Child.into(),
+        Err(e) => return e.into(),
+    };
+
+    unsafe {
+        if wstatus != 0 {
+            // Encode as a normal exit, as `WEXITSTATUS` expects.
+            (wstatus as *mut i32).write_volatile((code & 0xff) << 8);
+        }
+        if rusage != 0 {
+            core::ptr::write_bytes(rusage as *mut libc::rusage, 0, 1);
+        }
+    }
+    tid as isize
 }
 
-pub fn sys_futex(addr: usize, op: usize, val: usize) -> isize {
+pub fn sys_futex(
+    addr: usize,
+    op: usize,
+    val: usize,
+    timeout: usize,
+    addr2: usize,
+    val3: usize,
+) -> isize {
     if addr == 0 || !addr.is_multiple_of(core::mem::align_of::<i32>()) {
         return -(libc::EINVAL as isize);
     }
@@ -98,18 +172,76 @@ pub fn sys_futex(addr: usize, op: usize, val: usize) -> isize {
 
     match cmd {
         libc::FUTEX_WAIT | libc::FUTEX_WAIT_BITSET => {
-            kfn::scheduler::kwait_on_addr(addr, val as i32)
+            if cmd == libc::FUTEX_WAIT_BITSET && val3 as u32 == 0 {
+                return -(libc::EINVAL as isize);
+            }
+            if timeout == 0 {
+                return kfn::scheduler::kwait_on_addr(addr, val as i32).into_ret();
+            }
+            let Some(timeout_ns) = read_timespec_ns(timeout) else {
+                return -(libc::EINVAL as isize);
+            };
+            // FUTEX_WAIT takes a relative timeout; FUTEX_WAIT_BITSET an absolute one.
+            if cmd != libc::FUTEX_WAIT_BITSET {
+                return kfn::scheduler::kwait_on_addr_timeout(addr, val as i32, timeout_ns)
+                    .into_ret();
+            }
+            let clock = if (op_i32 & libc::FUTEX_CLOCK_REALTIME) != 0 {
+                libc::CLOCK_REALTIME
+            } else {
+                libc::CLOCK_MONOTONIC
+            } as usize;
+            let ret = kfn::scheduler::kwait_on_addr_timeout(
+                addr,
+                val as i32,
+                super::until_deadline(clock, timeout_ns),
+            )
+            .into_ret();
+            if ret == -(libc::ETIMEDOUT as isize) {
+                super::pass_deadline(clock, timeout_ns);
+            }
+            ret
         }
 
         libc::FUTEX_WAKE | libc::FUTEX_WAKE_BITSET => {
             kfn::scheduler::kwake_on_addr(addr, val) as isize
         }
+
+        libc::FUTEX_REQUEUE | libc::FUTEX_CMP_REQUEUE => {
+            if addr2 == 0 || !addr2.is_multiple_of(core::mem::align_of::<i32>()) {
+                return -(libc::EINVAL as isize);
+            }
+            if cmd == libc::FUTEX_CMP_REQUEUE {
+                let current = unsafe { core::ptr::read_volatile(addr as *const i32) };
+                if current != val3 as i32 {
+                    return -(libc::EAGAIN as isize);
+                }
+            }
+            // For requeue ops the timeout slot carries `val2`, the requeue limit.
+            kfn::scheduler::krequeue_on_addr(addr, val, addr2, timeout as u32 as usize) as isize
+        }
         _ => -(libc::EINVAL as isize),
     }
 }
 
+/// Read a user `timespec` as nanoseconds; `None` if it is malformed.
+fn read_timespec_ns(ptr: usize) -> Option<u64> {
+    if !ptr.is_multiple_of(core::mem::align_of::<libc::timespec>()) {
+        return None;
+    }
+    let ts = unsafe { core::ptr::read(ptr as *const libc::timespec) };
+    if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+        return None;
+    }
+    Some(
+        (ts.tv_sec as u64)
+            .saturating_mul(1_000_000_000)
+            .saturating_add(ts.tv_nsec as u64),
+    )
+}
+
 pub fn sys_sched_yield() -> isize {
-    kfn::scheduler::ksched_yield()
+    kfn::scheduler::ksched_yield().into_ret()
 }
 
 pub fn sys_getpid() -> isize {
@@ -124,5 +256,140 @@ pub fn sys_set_tid_address(tidptr: usize) -> isize {
     if tidptr != 0 && !tidptr.is_multiple_of(core::mem::align_of::<i32>()) {
         return -(libc::EINVAL as isize);
     }
-    kfn::scheduler::kset_clear_on_exit_addr(tidptr)
+    kfn::scheduler::kset_clear_on_exit_addr(tidptr).into_ret()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    /// The flags musl's `pthread_create` passes.
+    const MUSL_PTHREAD_FLAGS: libc::c_int = libc::CLONE_VM
+        | libc::CLONE_FS
+        | libc::CLONE_FILES
+        | libc::CLONE_SIGHAND
+        | libc::CLONE_THREAD
+        | libc::CLONE_SYSVSEM
+        | libc::CLONE_SETTLS
+        | libc::CLONE_PARENT_SETTID
+        | libc::CLONE_CHILD_CLEARTID
+        | libc::CLONE_DETACHED;
+
+    const EINVAL: Result<(), isize> = Err(-(libc::EINVAL as isize));
+
+    #[test]
+    fn test_musl_pthread_create_flags() {
+        let flags = MUSL_PTHREAD_FLAGS as usize;
+        assert_eq!(check_clone_args(flags, 0x1000, 0x2000, 0x1004), Ok(()));
+
+        // Each pointer the flags promise must be there and aligned.
+        assert_eq!(check_clone_args(flags, 0, 0x2000, 0x1004), EINVAL);
+        assert_eq!(check_clone_args(flags, 0x1002, 0x2000, 0x1004), EINVAL);
+        assert_eq!(check_clone_args(flags, 0x1000, 0, 0x1004), EINVAL);
+        assert_eq!(check_clone_args(flags, 0x1000, 0x2000, 0), EINVAL);
+
+        let without = |flag: libc::c_int| (MUSL_PTHREAD_FLAGS & !flag) as usize;
+        assert_eq!(
+            check_clone_args(without(libc::CLONE_SIGHAND), 0x1000, 0x2000, 0x1004),
+            EINVAL
+        );
+        assert_eq!(
+            check_clone_args(without(libc::CLONE_VM), 0x1000, 0x2000, 0x1004),
+            EINVAL
+        );
+    }
+
+    #[test]
+    fn test_unsupported_clone_modes() {
+        let enosys = Err(-(libc::ENOSYS as isize));
+        // musl's `fork`: `clone(SIGCHLD)`.
+        assert_eq!(check_clone_args(libc::SIGCHLD as usize, 0, 0, 0), enosys);
+        // musl's `posix_spawn`.
+        let vfork = (libc::CLONE_VM | libc::CLONE_VFORK | libc::SIGCHLD) as usize;
+        assert_eq!(check_clone_args(vfork, 0, 0, 0), enosys);
+        assert_eq!(
+            check_clone_args(libc::CLONE_NEWNS as usize, 0, 0, 0),
+            EINVAL
+        );
+        // A CLONE_VM child that is not a thread still shares everything and is joined with wait4.
+        let shared = (libc::CLONE_VM | libc::SIGCHLD) as usize;
+        assert_eq!(check_clone_args(shared, 0, 0, 0), Ok(()));
+    }
+
+    #[test]
+    fn test_handlers_against_mock_scheduler() {
+        let _kernel = testing::kernel();
+
+        let flags = MUSL_PTHREAD_FLAGS as usize;
+        let args = [flags, 0x8000, 0x1000, 0x2000, 0x1004, 0];
+        assert_eq!(testing::syscall(libc::SYS_clone, args), 2);
+        // CLONE_CHILD_CLEARTID without CLONE_CHILD_SETTID still passes the address for both.
+        assert_eq!(
+            testing::last_spawn(),
+            [0x8000, 0x2000, 0x1000, 0x1004, 0x1004]
+        );
+
+        assert_eq!(
+            testing::syscall(libc::SYS_gettid, [0; 6]),
+            testing::TID as isize
+        );
+        assert_eq!(
+            testing::syscall(libc::SYS_wait4, [5, 0, 0, 0, 0, 0]),
+            -(libc::ECHILD as isize)
+        );
+
+        // FUTEX_WAIT's timeout is relative and passes through unchanged.
+        let word = 0i32;
+        let ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 2_000_000,
+        };
+        let args = [
+            &word as *const i32 as usize,
+            libc::FUTEX_WAIT as usize,
+            0,
+            &ts as *const libc::timespec as usize,
+            0,
+            0,
+        ];
+        assert_eq!(
+            testing::syscall(libc::SYS_futex, args),
+            -(libc::ETIMEDOUT as isize)
+        );
+        assert_eq!(testing::last_timeout_ns(), 2_000_000);
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn test_absolute_futex_deadline_counts_from_its_clock() {
+        let _kernel = testing::kernel();
+        // Long after boot on the cycle clock; the scheduler's clock is still at 0.
+        testing::set_clock_ns(100_000_000_000);
+        let deadline = testing::REALTIME_BASE_NS + 100_001_000_000;
+        let ts = libc::timespec {
+            tv_sec: (deadline / 1_000_000_000) as _,
+            tv_nsec: (deadline % 1_000_000_000) as _,
+        };
+        let word = 0i32;
+        let op = libc::FUTEX_WAIT_BITSET | libc::FUTEX_CLOCK_REALTIME;
+        let args = [
+            &word as *const i32 as usize,
+            op as usize,
+            0,
+            &ts as *const libc::timespec as usize,
+            0,
+            u32::MAX as usize,
+        ];
+        assert_eq!(
+            testing::syscall(libc::SYS_futex, args),
+            -(libc::ETIMEDOUT as isize)
+        );
+        assert_eq!(testing::last_timeout_ns(), 1_000_000);
+        // Timed out, so the clock has reached the deadline.
+        assert_eq!(
+            kfn::time::know_ns(libc::CLOCK_REALTIME as usize),
+            deadline as i64
+        );
+    }
 }
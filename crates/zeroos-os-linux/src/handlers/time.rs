@@ -0,0 +1,244 @@
+//! Clock syscalls backed by the foundation virtual clock.
+//!
+//! Every reading is derived from the platform cycle counter, so a guest sees the same timestamps
+//! on every replay. Sleeping never consults host time.
+
+use cfg_if::cfg_if;
+use foundation::kfn;
+use libc;
+
+const NSEC_PER_SEC: u64 = 1_000_000_000;
+
+fn clock_ns(clock_id: usize) -> Result<u64, isize> {
+    let ns = kfn::time::know_ns(clock_id);
+    if ns < 0 {
+        Err(ns as isize)
+    } else {
+        Ok(ns as u64)
+    }
+}
+
+fn read_timespec_ns(ptr: usize) -> Result<u64, isize> {
+    if ptr == 0 || !ptr.is_multiple_of(core::mem::align_of::<libc::timespec>()) {
+        return Err(-(libc::EFAULT as isize));
+    }
+    let ts = unsafe { core::ptr::read(ptr as *const libc::timespec) };
+    if ts.tv_sec < 0 || !(0..NSEC_PER_SEC as i64).contains(&(ts.tv_nsec as i64)) {
+        return Err(-(libc::EINVAL as isize));
+    }
+    Ok((ts.tv_sec as u64)
+        .saturating_mul(NSEC_PER_SEC)
+        .saturating_add(ts.tv_nsec as u64))
+}
+
+fn write_timespec(ptr: usize, ns: u64) -> isize {
+    if ptr == 0 || !ptr.is_multiple_of(core::mem::align_of::<libc::timespec>()) {
+        return -(libc::EFAULT as isize);
+    }
+    let ts = libc::timespec {
+        tv_sec: (ns / NSEC_PER_SEC) as _,
+        tv_nsec: (ns % NSEC_PER_SEC) as _,
+    };
+    unsafe { core::ptr::write(ptr as *mut libc::timespec, ts) };
+    0
+}
+
+pub fn sys_clock_gettime(clock_id: usize, tp: usize) -> isize {
+    match clock_ns(clock_id) {
+        Ok(ns) => write_timespec(tp, ns),
+        Err(e) => e,
+    }
+}
+
+pub fn sys_clock_getres(clock_id: usize, res: usize) -> isize {
+    if let Err(e) = clock_ns(clock_id) {
+        return e;
+    }
+    // A NULL `res` is allowed and only validates the clock.
+    if res == 0 {
+        return 0;
+    }
+    write_timespec(res, 1)
+}
+
+pub fn sys_gettimeofday(tv: usize, tz: usize) -> isize {
+    if tv != 0 {
+        if !tv.is_multiple_of(core::mem::align_of::<libc::timeval>()) {
+            return -(libc::EFAULT as isize);
+        }
+        let ns = match clock_ns(libc::CLOCK_REALTIME as usize) {
+            Ok(ns) => ns,
+            Err(e) => return e,
+        };
+        let val = libc::timeval {
+            tv_sec: (ns / NSEC_PER_SEC) as _,
+            tv_usec: ((ns % NSEC_PER_SEC) / 1_000) as _,
+        };
+        unsafe { core::ptr::write(tv as *mut libc::timeval, val) };
+    }
+    if tz != 0 {
+        // The guest always runs in UTC.
+        if !tz.is_multiple_of(core::mem::align_of::<libc::timezone>()) {
+            return -(libc::EFAULT as isize);
+        }
+        unsafe { core::ptr::write_bytes(tz as *mut libc::timezone, 0, 1) };
+    }
+    0
+}
+
+pub fn sys_nanosleep(req: usize, rem: usize) -> isize {
+    let ns = match read_timespec_ns(req) {
+        Ok(ns) => ns,
+        Err(e) => return e,
+    };
+    sleep_ns(ns);
+    // Sleeps are never interrupted, so nothing remains.
+    if rem != 0 {
+        return write_timespec(rem, 0);
+    }
+    0
+}
+
+pub fn sys_clock_nanosleep(clock_id: usize, flags: usize, req: usize, rem: usize) -> isize {
+    if clock_id == libc::CLOCK_THREAD_CPUTIME_ID as usize {
+        return -(libc::EINVAL as isize);
+    }
+    if let Err(e) = clock_ns(clock_id) {
+        return e;
+    }
+    let ns = match read_timespec_ns(req) {
+        Ok(ns) => ns,
+        Err(e) => return e,
+    };
+    let absolute = (flags as i32 & libc::TIMER_ABSTIME) != 0;
+    if absolute {
+        sleep_until(clock_id, ns);
+    } else {
+        sleep_ns(ns);
+    }
+    if !absolute && rem != 0 {
+        return write_timespec(rem, 0);
+    }
+    0
+}
+
+/// Sleep for `ns`, then move the clocks past whatever part of it did not elapse while parked, so
+/// every clock reads at least `ns` later afterwards, as on Linux.
+fn sleep_ns(ns: u64) {
+    if ns == 0 {
+        return;
+    }
+    let monotonic = libc::CLOCK_MONOTONIC as usize;
+    let start = clock_ns(monotonic).unwrap_or(0);
+    park(ns);
+    let slept = clock_ns(monotonic).unwrap_or(0).saturating_sub(start);
+    kfn::time::kadvance_ns(ns.saturating_sub(slept));
+}
+
+/// Sleep until `clock_id` reads `deadline`, measured on that clock.
+fn sleep_until(clock_id: usize, deadline: u64) {
+    if let Ok(now) = clock_ns(clock_id) {
+        sleep_ns(deadline.saturating_sub(now));
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "scheduler")] {
+        /// Park the caller for `ns` of scheduler time so other threads can run meanwhile.
+        fn park(ns: u64) {
+            // Nobody else knows this address, so only the timeout can end the wait.
+            let key: i32 = 0;
+            let addr = &key as *const i32 as usize;
+            let _ = kfn::scheduler::kwait_on_addr_timeout(addr, key, ns);
+        }
+    } else {
+        /// A single thread has nothing to wait for; the clocks skip ahead instead of burning
+        /// cycles.
+        fn park(_ns: u64) {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_timespec_roundtrip() {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        let ptr = &mut ts as *mut libc::timespec as usize;
+        assert_eq!(write_timespec(ptr, 3 * NSEC_PER_SEC + 7), 0);
+        assert_eq!(ts.tv_sec, 3);
+        assert_eq!(ts.tv_nsec, 7);
+        assert_eq!(read_timespec_ns(ptr), Ok(3 * NSEC_PER_SEC + 7));
+    }
+
+    #[test]
+    fn test_timespec_rejects_bad_nsec() {
+        let ts = libc::timespec {
+            tv_sec: 1,
+            tv_nsec: NSEC_PER_SEC as _,
+        };
+        let ptr = &ts as *const libc::timespec as usize;
+        assert_eq!(read_timespec_ns(ptr), Err(-(libc::EINVAL as isize)));
+        assert_eq!(read_timespec_ns(0), Err(-(libc::EFAULT as isize)));
+    }
+
+    fn now(clock_id: libc::clockid_t) -> u64 {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        let ptr = &mut ts as *mut libc::timespec as usize;
+        assert_eq!(
+            testing::syscall(
+                libc::SYS_clock_gettime,
+                [clock_id as usize, ptr, 0, 0, 0, 0]
+            ),
+            0
+        );
+        read_timespec_ns(ptr).unwrap()
+    }
+
+    #[test]
+    fn test_nanosleep_moves_clocks_by_at_least_its_duration() {
+        let _kernel = testing::kernel();
+        testing::set_clock_ns(5 * NSEC_PER_SEC);
+        let before = now(libc::CLOCK_MONOTONIC);
+        let req = libc::timespec {
+            tv_sec: 1,
+            tv_nsec: 500,
+        };
+        let req = &req as *const libc::timespec as usize;
+        assert_eq!(
+            testing::syscall(libc::SYS_nanosleep, [req, 0, 0, 0, 0, 0]),
+            0
+        );
+        assert!(now(libc::CLOCK_MONOTONIC) >= before + NSEC_PER_SEC + 500);
+    }
+
+    #[test]
+    fn test_absolute_clock_nanosleep_reaches_deadline() {
+        let _kernel = testing::kernel();
+        testing::set_clock_ns(NSEC_PER_SEC);
+        let deadline = testing::REALTIME_BASE_NS + 3 * NSEC_PER_SEC;
+        let req = libc::timespec {
+            tv_sec: (deadline / NSEC_PER_SEC) as _,
+            tv_nsec: 0,
+        };
+        let req = &req as *const libc::timespec as usize;
+        let args = [
+            libc::CLOCK_REALTIME as usize,
+            libc::TIMER_ABSTIME as usize,
+            req,
+            0,
+            0,
+            0,
+        ];
+        assert_eq!(testing::syscall(libc::SYS_clock_nanosleep, args), 0);
+        assert!(now(libc::CLOCK_REALTIME) >= deadline);
+    }
+}
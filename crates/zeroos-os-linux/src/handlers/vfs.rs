@@ -1,130 +1,450 @@
 use foundation::kfn;
+use foundation::ops::{FileStat, IoVec};
+use foundation::uaccess::{self, access_ok, PATH_MAX};
+use foundation::{IntoRet, KResult};
 use libc;
 
+/// The guest's NUL-terminated path at `path`, copied into `buf`; returns its length.
+fn path_from_user(buf: &mut [u8; PATH_MAX], path: usize) -> KResult<usize> {
+    // SAFETY: with regions registered the range is checked; without, the guest passes a string.
+    unsafe { uaccess::strncpy_from_user(buf, path) }
+}
+
 pub fn sys_openat(_dirfd: usize, path: usize, flags: usize, mode: usize) -> isize {
-    if path == 0 {
-        return -(libc::EFAULT as isize);
+    let mut buf = [0u8; PATH_MAX];
+    if let Err(e) = path_from_user(&mut buf, path) {
+        return e.into();
+    }
+    unsafe { kfn::vfs::kopen(buf.as_ptr(), flags as i32, mode as u32) }.into_ret()
+}
+
+pub fn sys_unlinkat(_dirfd: usize, path: usize, flags: usize) -> isize {
+    let mut buf = [0u8; PATH_MAX];
+    if let Err(e) = path_from_user(&mut buf, path) {
+        return e.into();
     }
-    unsafe { kfn::vfs::kopen(path as *const u8, flags as i32, mode as u32) }
+    // No directories exist, so there is nothing `rmdir` could remove.
+    if flags as i32 == libc::AT_REMOVEDIR {
+        return -(libc::ENOTDIR as isize);
+    }
+    if flags != 0 {
+        return -(libc::EINVAL as isize);
+    }
+    unsafe { kfn::vfs::kunlink(buf.as_ptr()) }.into_ret()
 }
 
 pub fn sys_close(fd: usize) -> isize {
-    kfn::vfs::kclose(fd as i32)
+    kfn::vfs::kclose(fd as i32).into_ret()
 }
 
 pub fn sys_read(fd: usize, buf: usize, count: usize) -> isize {
     if count == 0 {
         return 0;
     }
-    if buf == 0 {
+    if !access_ok(buf, count) {
         return -(libc::EFAULT as isize);
     }
-    kfn::vfs::kread(fd as i32, buf as *mut u8, count)
+    kfn::vfs::kread(fd as i32, buf as *mut u8, count).into_ret()
 }
 
 pub fn sys_write(fd: usize, buf: usize, count: usize) -> isize {
     if count == 0 {
         return 0;
     }
-    if buf == 0 {
+    if !access_ok(buf, count) {
         return -(libc::EFAULT as isize);
     }
-    kfn::vfs::kwrite(fd as i32, buf as *const u8, count)
-}
-
-#[repr(C)]
-struct IoVec {
-    iov_base: *mut u8,
-    iov_len: usize,
+    kfn::vfs::kwrite(fd as i32, buf as *const u8, count).into_ret()
 }
 
-pub fn sys_readv(fd: usize, iov: usize, iovcnt: usize) -> isize {
-    if iovcnt == 0 {
+pub fn sys_pread64(fd: usize, buf: usize, count: usize, offset: usize) -> isize {
+    if (offset as isize) < 0 {
         return -(libc::EINVAL as isize);
     }
-    if iov == 0 {
+    if !access_ok(buf, count) {
         return -(libc::EFAULT as isize);
     }
-    if iovcnt > (libc::UIO_MAXIOV as usize) {
-        return -(libc::EINVAL as isize);
-    }
-    if !iov.is_multiple_of(core::mem::align_of::<IoVec>()) {
+    kfn::vfs::kpread(fd as i32, buf as *mut u8, count, offset).into_ret()
+}
+
+pub fn sys_pwrite64(fd: usize, buf: usize, count: usize, offset: usize) -> isize {
+    if (offset as isize) < 0 {
         return -(libc::EINVAL as isize);
     }
-    let iovecs = unsafe { core::slice::from_raw_parts(iov as *const IoVec, iovcnt) };
-    let mut total = 0isize;
-    for v in iovecs {
-        if v.iov_len == 0 {
-            continue;
-        }
-        if v.iov_base.is_null() {
-            return if total > 0 {
-                total
-            } else {
-                -(libc::EFAULT as isize)
-            };
-        }
-        let r = kfn::vfs::kread(fd as i32, v.iov_base, v.iov_len);
-        if r < 0 {
-            return if total > 0 { total } else { r };
-        }
-        total += r;
-        if (r as usize) < v.iov_len {
-            break;
-        }
+    if !access_ok(buf, count) {
+        return -(libc::EFAULT as isize);
     }
-    total
+    kfn::vfs::kpwrite(fd as i32, buf as *const u8, count, offset).into_ret()
 }
 
-pub fn sys_writev(fd: usize, iov: usize, iovcnt: usize) -> isize {
+/// The `iovcnt` buffers at `iov`, or an errno if the vector or one of its buffers lies outside
+/// guest memory, the vector is malformed, or the total overflows the return value.
+fn iovecs<'a>(iov: usize, iovcnt: usize) -> Result<&'a [IoVec], isize> {
     if iovcnt == 0 {
-        return -(libc::EINVAL as isize);
-    }
-    if iov == 0 {
-        return -(libc::EFAULT as isize);
+        return Err(-(libc::EINVAL as isize));
     }
     if iovcnt > (libc::UIO_MAXIOV as usize) {
-        return -(libc::EINVAL as isize);
+        return Err(-(libc::EINVAL as isize));
+    }
+    if !access_ok(iov, iovcnt * core::mem::size_of::<IoVec>()) {
+        return Err(-(libc::EFAULT as isize));
     }
     if !iov.is_multiple_of(core::mem::align_of::<IoVec>()) {
-        return -(libc::EINVAL as isize);
+        return Err(-(libc::EINVAL as isize));
     }
+    // SAFETY: checked to be aligned guest memory above.
     let iovecs = unsafe { core::slice::from_raw_parts(iov as *const IoVec, iovcnt) };
-    let mut total = 0isize;
-    for v in iovecs {
-        if v.iov_len == 0 {
-            continue;
-        }
-        if v.iov_base.is_null() {
-            return if total > 0 {
-                total
-            } else {
-                -(libc::EFAULT as isize)
-            };
-        }
-        let r = kfn::vfs::kwrite(fd as i32, v.iov_base as *const u8, v.iov_len);
-        if r < 0 {
-            return if total > 0 { total } else { r };
-        }
-        total += r;
-        if (r as usize) < v.iov_len {
-            break;
-        }
+    if !iovecs.iter().all(|v| access_ok(v.base as usize, v.len)) {
+        return Err(-(libc::EFAULT as isize));
+    }
+    iovecs
+        .iter()
+        .try_fold(0usize, |total, v| total.checked_add(v.len))
+        .filter(|&total| total <= isize::MAX as usize)
+        .ok_or(-(libc::EINVAL as isize))?;
+    Ok(iovecs)
+}
+
+pub fn sys_readv(fd: usize, iov: usize, iovcnt: usize) -> isize {
+    match iovecs(iov, iovcnt) {
+        Ok(iovecs) => kfn::vfs::kreadv(fd as i32, iovecs).into_ret(),
+        Err(e) => e,
+    }
+}
+
+pub fn sys_writev(fd: usize, iov: usize, iovcnt: usize) -> isize {
+    match iovecs(iov, iovcnt) {
+        Ok(iovecs) => kfn::vfs::kwritev(fd as i32, iovecs).into_ret(),
+        Err(e) => e,
     }
-    total
 }
 
 pub fn sys_lseek(fd: usize, offset: usize, whence: usize) -> isize {
-    kfn::vfs::klseek(fd as i32, offset as isize, whence as i32)
+    kfn::vfs::klseek(fd as i32, offset as isize, whence as i32).into_ret()
 }
 
 pub fn sys_ioctl(fd: usize, request: usize, arg: usize) -> isize {
-    kfn::vfs::kioctl(fd as i32, request, arg)
+    kfn::vfs::kioctl(fd as i32, request, arg).into_ret()
+}
+
+/// Linux `struct statx_timestamp`.
+#[repr(C)]
+#[derive(Default)]
+struct StatxTimestamp {
+    tv_sec: i64,
+    tv_nsec: u32,
+    _pad: i32,
+}
+
+/// Linux `struct statx`; `libc` only has it for glibc and newer musl.
+#[repr(C)]
+#[derive(Default)]
+struct Statx {
+    mask: u32,
+    blksize: u32,
+    attributes: u64,
+    nlink: u32,
+    uid: u32,
+    gid: u32,
+    mode: u16,
+    _pad1: u16,
+    ino: u64,
+    size: u64,
+    blocks: u64,
+    attributes_mask: u64,
+    atime: StatxTimestamp,
+    btime: StatxTimestamp,
+    ctime: StatxTimestamp,
+    mtime: StatxTimestamp,
+    rdev_major: u32,
+    rdev_minor: u32,
+    dev_major: u32,
+    dev_minor: u32,
+    _spare: [u64; 14],
+}
+
+const _: () = assert!(core::mem::size_of::<Statx>() == 256);
+
+const STATX_BASIC_STATS: u32 = 0x07ff;
+const STATX_RESERVED: u32 = 0x8000_0000;
+const AT_STATX_SYNC_TYPE: i32 = 0x6000;
+
+/// Block size reported for every file.
+const BLKSIZE: u32 = 4096;
+
+/// Metadata of the file at `path`, or of `dirfd` itself for an empty path with `AT_EMPTY_PATH`.
+/// There are no directories to resolve against, so `dirfd` is otherwise ignored, as in
+/// `openat`.
+fn stat_at(dirfd: usize, path: usize, flags: usize, allowed: i32) -> Result<FileStat, isize> {
+    if flags & !(allowed as usize) != 0 {
+        return Err(-(libc::EINVAL as isize));
+    }
+    let mut buf = [0u8; PATH_MAX];
+    let result = if path_from_user(&mut buf, path)? == 0 {
+        if flags as i32 & libc::AT_EMPTY_PATH == 0 {
+            return Err(-(libc::ENOENT as isize));
+        }
+        kfn::vfs::kfstat(dirfd as i32)
+    } else {
+        unsafe { kfn::vfs::kstat(buf.as_ptr()) }
+    };
+    result.map_err(Into::into)
+}
+
+/// Store `st` at `statbuf` as a `struct stat`.
+fn put_stat(statbuf: usize, st: &FileStat) -> isize {
+    // SAFETY: all-zero is a valid `stat`.
+    let mut out: libc::stat = unsafe { core::mem::zeroed() };
+    out.st_mode = st.mode;
+    out.st_nlink = 1;
+    out.st_size = st.size as libc::off_t;
+    out.st_blksize = BLKSIZE as libc::blksize_t;
+    out.st_blocks = st.size.div_ceil(512) as libc::blkcnt_t;
+    // SAFETY: with regions registered the range is checked; without, the guest passes a
+    // `struct stat`.
+    unsafe { uaccess::put_user(statbuf, out) }.into_ret()
 }
 
 pub fn sys_fstat(fd: usize, statbuf: usize) -> isize {
-    if statbuf == 0 {
+    match kfn::vfs::kfstat(fd as i32) {
+        Ok(st) => put_stat(statbuf, &st),
+        Err(e) => e.into(),
+    }
+}
+
+pub fn sys_newfstatat(dirfd: usize, path: usize, statbuf: usize, flags: usize) -> isize {
+    let allowed = libc::AT_EMPTY_PATH | libc::AT_SYMLINK_NOFOLLOW | libc::AT_NO_AUTOMOUNT;
+    match stat_at(dirfd, path, flags, allowed) {
+        Ok(st) => put_stat(statbuf, &st),
+        Err(e) => e,
+    }
+}
+
+/// `statx`, filling every basic field whatever `mask` asks for, as Linux may. Owners are root,
+/// timestamps are zero, and every file has one link.
+pub fn sys_statx(dirfd: usize, path: usize, flags: usize, mask: usize, statxbuf: usize) -> isize {
+    if flags as i32 & AT_STATX_SYNC_TYPE == AT_STATX_SYNC_TYPE || mask as u32 & STATX_RESERVED != 0
+    {
+        return -(libc::EINVAL as isize);
+    }
+    let allowed = libc::AT_EMPTY_PATH
+        | libc::AT_SYMLINK_NOFOLLOW
+        | libc::AT_NO_AUTOMOUNT
+        | AT_STATX_SYNC_TYPE;
+    let st = match stat_at(dirfd, path, flags, allowed) {
+        Ok(st) => st,
+        Err(e) => return e,
+    };
+    let out = Statx {
+        mask: STATX_BASIC_STATS,
+        blksize: BLKSIZE,
+        nlink: 1,
+        mode: st.mode as u16,
+        size: st.size,
+        blocks: st.size.div_ceil(512),
+        ..Statx::default()
+    };
+    // SAFETY: with regions registered the range is checked; without, the guest passes a
+    // `struct statx`.
+    unsafe { uaccess::put_user(statxbuf, out) }.into_ret()
+}
+
+pub fn sys_pipe2(fds: usize, flags: usize) -> isize {
+    // Checked before the pipe exists, so a bad pointer leaks no descriptors.
+    if !access_ok(fds, core::mem::size_of::<[i32; 2]>())
+        || !fds.is_multiple_of(core::mem::align_of::<i32>())
+    {
         return -(libc::EFAULT as isize);
     }
-    kfn::vfs::kfstat(fd as i32, statbuf as *mut u8)
+    match kfn::vfs::kpipe(flags as i32) {
+        // SAFETY: checked above.
+        Ok(pair) => unsafe { uaccess::put_user(fds, pair) }.into_ret(),
+        Err(e) => e.into(),
+    }
+}
+
+pub fn sys_dup(fd: usize) -> isize {
+    kfn::vfs::kdup(fd as i32).into_ret()
+}
+
+pub fn sys_dup3(fd: usize, new_fd: usize, flags: usize) -> isize {
+    kfn::vfs::kdup3(fd as i32, new_fd as i32, flags as i32).into_ret()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_console_through_dispatch() {
+        let _kernel = testing::kernel();
+        testing::take_stdout();
+
+        let (hello, world) = (b"hello, ", b"world\n");
+        let iov = [
+            IoVec {
+                base: hello.as_ptr() as *mut u8,
+                len: hello.len(),
+            },
+            IoVec {
+                base: core::ptr::null_mut(),
+                len: 0,
+            },
+            IoVec {
+                base: world.as_ptr() as *mut u8,
+                len: world.len(),
+            },
+        ];
+        let args = [1, iov.as_ptr() as usize, iov.len(), 0, 0, 0];
+        assert_eq!(testing::syscall(libc::SYS_writev, args), 13);
+        assert_eq!(testing::take_stdout(), b"hello, world\n");
+
+        testing::set_stdin(b"abc");
+        let mut buf = [0u8; 8];
+        let args = [0, buf.as_mut_ptr() as usize, buf.len(), 0, 0, 0];
+        assert_eq!(testing::syscall(libc::SYS_read, args), 3);
+        assert_eq!(&buf[..3], b"abc");
+
+        let args = [1, 0, 1, 0, 0, 0];
+        assert_eq!(
+            testing::syscall(libc::SYS_write, args),
+            -(libc::EFAULT as isize)
+        );
+        let args = [7, buf.as_ptr() as usize, 1, 0, 0, 0];
+        assert_eq!(
+            testing::syscall(libc::SYS_write, args),
+            -(libc::EBADF as isize)
+        );
+    }
+
+    #[test]
+    fn test_stat_through_dispatch() {
+        let _kernel = testing::kernel();
+        testing::set_file(b"0123456789");
+        let empty = c"";
+        let mut stx = Statx::default();
+        let stx_ptr = &mut stx as *mut Statx as usize;
+
+        let args = [
+            0,
+            testing::FILE_PATH.as_ptr() as usize,
+            0,
+            0x7ff,
+            stx_ptr,
+            0,
+        ];
+        assert_eq!(testing::syscall(libc::SYS_statx, args), 0);
+        assert_eq!((stx.mode as u32, stx.size), (libc::S_IFREG | 0o444, 10));
+        assert_eq!((stx.mask, stx.nlink, stx.blocks), (STATX_BASIC_STATS, 1, 1));
+
+        // `File::metadata` in std: the descriptor itself through an empty path.
+        let flags = libc::AT_EMPTY_PATH as usize;
+        let args = [1, empty.as_ptr() as usize, flags, 0x7ff, stx_ptr, 0];
+        assert_eq!(testing::syscall(libc::SYS_statx, args), 0);
+        assert_eq!(stx.mode as u32 & libc::S_IFMT, libc::S_IFCHR);
+
+        let mut st: libc::stat = unsafe { core::mem::zeroed() };
+        let st_ptr = &mut st as *mut libc::stat as usize;
+        let args = [
+            testing::FILE_FD as usize,
+            empty.as_ptr() as usize,
+            st_ptr,
+            flags,
+            0,
+            0,
+        ];
+        assert_eq!(testing::syscall(libc::SYS_newfstatat, args), 0);
+        assert_eq!((st.st_mode, st.st_size), (libc::S_IFREG | 0o444, 10));
+
+        let args = [3, empty.as_ptr() as usize, st_ptr, 0, 0, 0];
+        assert_eq!(
+            testing::syscall(libc::SYS_newfstatat, args),
+            -(libc::ENOENT as isize)
+        );
+        // std probes for statx with null pointers and expects EFAULT.
+        assert_eq!(
+            testing::syscall(libc::SYS_statx, [0, 0, 0, 0xfff, 0, 0]),
+            -(libc::EFAULT as isize)
+        );
+        let args = [0, c"/nope".as_ptr() as usize, 0, 0x7ff, stx_ptr, 0];
+        assert_eq!(
+            testing::syscall(libc::SYS_statx, args),
+            -(libc::ENOENT as isize)
+        );
+        let args = [0, empty.as_ptr() as usize, 0x6000, 0x7ff, stx_ptr, 0];
+        assert_eq!(
+            testing::syscall(libc::SYS_statx, args),
+            -(libc::EINVAL as isize)
+        );
+    }
+
+    #[test]
+    fn test_guest_pointers_checked_against_regions() {
+        let _kernel = testing::kernel();
+        testing::take_stdout();
+        let guest = *b"/dev/stdout\0ok";
+        let outside = *b"xx";
+        let base = guest.as_ptr() as usize;
+        assert!(foundation::uaccess::register_region(
+            base,
+            base + guest.len()
+        ));
+
+        let write = |buf: usize, len| testing::syscall(libc::SYS_write, [1, buf, len, 0, 0, 0]);
+        assert_eq!(write(base + 12, 2), 2);
+        assert_eq!(write(base + 12, 3), -(libc::EFAULT as isize));
+        assert_eq!(
+            write(outside.as_ptr() as usize, 2),
+            -(libc::EFAULT as isize)
+        );
+        let iov = [IoVec {
+            base: outside.as_ptr() as *mut u8,
+            len: 2,
+        }];
+        let args = [1, iov.as_ptr() as usize, 1, 0, 0, 0];
+        assert_eq!(
+            testing::syscall(libc::SYS_writev, args),
+            -(libc::EFAULT as isize)
+        );
+        assert_eq!(testing::take_stdout(), b"ok");
+
+        // The path is copied in before the lookup, so one past the region never gets read.
+        let args = [0, base + 5, 0, 0, 0, 0];
+        assert_eq!(
+            testing::syscall(libc::SYS_openat, args),
+            -(libc::ENOENT as isize)
+        );
+        let args = [0, base + 12, 0, 0, 0, 0];
+        assert_eq!(
+            testing::syscall(libc::SYS_openat, args),
+            -(libc::EFAULT as isize)
+        );
+
+        foundation::uaccess::clear_regions();
+    }
+
+    #[test]
+    fn test_pread_through_dispatch() {
+        let _kernel = testing::kernel();
+        testing::set_file(b"0123456789");
+        let fd = testing::FILE_FD as usize;
+        let mut buf = [0u8; 4];
+        let ptr = buf.as_mut_ptr() as usize;
+
+        assert_eq!(
+            testing::syscall(libc::SYS_pread64, [fd, ptr, 4, 6, 0, 0]),
+            4
+        );
+        assert_eq!(&buf, b"6789");
+        assert_eq!(testing::file_offset(), 0);
+        assert_eq!(
+            testing::syscall(libc::SYS_pread64, [fd, ptr, 4, usize::MAX, 0, 0]),
+            -(libc::EINVAL as isize)
+        );
+        assert_eq!(
+            testing::syscall(libc::SYS_pread64, [0, ptr, 4, 0, 0, 0]),
+            -(libc::ESPIPE as isize)
+        );
+    }
 }
@@ -0,0 +1,93 @@
+//! Hostcalls: platform-defined ecalls outside the Linux syscall range.
+//!
+//! zkVMs expose precompiles and host services through ecall numbers Linux never uses. A platform
+//! registers a [`HostcallTable`] at bootstrap; syscall number `base + id` then runs `calls[id]`
+//! with the usual six arguments instead of reaching the Linux dispatcher.
+
+use foundation::utils::GlobalOption;
+
+use crate::batch::SYS_BATCH;
+
+/// Namespace start used when a platform has no reason to pick another.
+pub const DEFAULT_HOSTCALL_BASE: usize = 0x0100_0000;
+
+/// Same shape as a syscall handler; returns a value or a negative errno.
+pub type Hostcall = fn(a0: usize, a1: usize, a2: usize, a3: usize, a4: usize, a5: usize) -> isize;
+
+#[derive(Clone, Copy)]
+pub struct HostcallTable {
+    /// Syscall number of hostcall 0. Must lie above the Linux table and `SYS_BATCH`.
+    pub base: usize,
+    /// Indexed by hostcall id.
+    pub calls: &'static [Hostcall],
+}
+
+impl HostcallTable {
+    /// Run the hostcall for syscall `nr`, or `None` if `nr` is below the namespace.
+    ///
+    /// Ids past the end of `calls` return `ENOSYS`.
+    pub fn dispatch(&self, nr: usize, args: [usize; 6]) -> Option<isize> {
+        let id = nr.checked_sub(self.base)?;
+        let [a0, a1, a2, a3, a4, a5] = args;
+        Some(match self.calls.get(id) {
+            Some(call) => call(a0, a1, a2, a3, a4, a5),
+            None => -(libc::ENOSYS as isize),
+        })
+    }
+}
+
+static HOSTCALLS: GlobalOption<HostcallTable> = GlobalOption::none();
+
+/// Route syscall numbers from `table.base` up to `table.calls`. Call during bootstrap.
+pub fn register_hostcalls(table: HostcallTable) {
+    assert!(
+        table.base > SYS_BATCH,
+        "hostcall base overlaps the Linux syscall table"
+    );
+    HOSTCALLS.set(table);
+}
+
+/// Syscall number of hostcall 0, if a platform registered a table.
+pub fn hostcall_base() -> Option<usize> {
+    HOSTCALLS.with_some(|t| t.base)
+}
+
+#[inline]
+pub(crate) fn dispatch(nr: usize, args: [usize; 6]) -> Option<isize> {
+    // Copy the table out so a hostcall may itself issue syscalls.
+    HOSTCALLS.with_some(|t| *t)?.dispatch(nr, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum(a0: usize, a1: usize, _: usize, _: usize, _: usize, a5: usize) -> isize {
+        (a0 + a1 + a5) as isize
+    }
+
+    fn fail(_: usize, _: usize, _: usize, _: usize, _: usize, _: usize) -> isize {
+        -(libc::EINVAL as isize)
+    }
+
+    #[test]
+    fn test_dispatch_by_id() {
+        static CALLS: [Hostcall; 2] = [sum, fail];
+        let table = HostcallTable {
+            base: DEFAULT_HOSTCALL_BASE,
+            calls: &CALLS,
+        };
+        let args = [1, 2, 0, 0, 0, 4];
+        assert_eq!(table.dispatch(DEFAULT_HOSTCALL_BASE, args), Some(7));
+        assert_eq!(
+            table.dispatch(DEFAULT_HOSTCALL_BASE + 1, args),
+            Some(-(libc::EINVAL as isize))
+        );
+        assert_eq!(
+            table.dispatch(DEFAULT_HOSTCALL_BASE + 2, args),
+            Some(-(libc::ENOSYS as isize))
+        );
+        assert_eq!(table.dispatch(DEFAULT_HOSTCALL_BASE - 1, args), None);
+        assert_eq!(table.dispatch(64, args), None);
+    }
+}
@@ -1,5 +1,14 @@
 #![no_std]
+pub mod batch;
 pub mod handlers;
+pub mod hostcall;
+#[cfg(feature = "seccomp")]
+pub mod seccomp;
+#[cfg(feature = "strace")]
+pub mod strace;
 pub mod syscall;
+#[cfg(test)]
+pub(crate) mod testing;
 
+pub use handlers::sched::{online_cpus, set_online_cpus};
 pub use syscall::*;
@@ -0,0 +1,243 @@
+//! Syscall filtering for guests that run semi-trusted code.
+//!
+//! A [`Filter`] is an allowlist ([`Filter::allow_only`]) or a denylist ([`Filter::deny`]) of
+//! syscall numbers, plus the [`Action`] taken on a call it blocks: fail it with an errno
+//! (`ENOSYS` by default, so libc takes its unsupported-syscall path) or end the guest with
+//! [`KILL_STATUS`], as `SECCOMP_RET_KILL_PROCESS` would.
+//!
+//! Filters stack like seccomp's: a call runs only if every installed filter allows it, and
+//! [`install`] can only take syscalls away. A platform can fix the syscall surface before the
+//! guest starts, and nothing the guest installs later gives a blocked call back. Entries of a
+//! `SYS_BATCH` are checked one by one; numbers past the Linux table (hostcalls) all count as
+//! [`HOSTCALLS`].
+
+use foundation::utils::GlobalCell;
+
+use crate::batch::SYS_BATCH;
+use crate::syscall::NR_SYSCALLS;
+
+/// Filters that can be installed.
+pub const MAX_FILTERS: usize = 8;
+
+/// The number that stands for every syscall past the Linux table, hostcalls included.
+pub const HOSTCALLS: usize = NR_SYSCALLS - 1;
+
+/// Exit status of a guest killed by [`Action::Kill`]: 128 + `SIGSYS`, as a shell reports it.
+pub const KILL_STATUS: i32 = 128 + libc::SIGSYS;
+
+const FILTER_WORDS: usize = NR_SYSCALLS.div_ceil(64);
+
+/// What happens to a call a filter blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Return `-errno` without running the handler.
+    Errno(i32),
+    /// End the guest with [`KILL_STATUS`]; the call never returns.
+    Kill,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Filter {
+    /// One bit per syscall number; set bits are allowed.
+    allowed: [u64; FILTER_WORDS],
+    action: Action,
+}
+
+impl Filter {
+    /// Allow the syscalls in `nrs` and block the rest.
+    pub const fn allow_only(nrs: &[usize]) -> Self {
+        Self::new(nrs, false)
+    }
+
+    /// Block the syscalls in `nrs` and allow the rest.
+    pub const fn deny(nrs: &[usize]) -> Self {
+        Self::new(nrs, true)
+    }
+
+    const fn new(nrs: &[usize], allow_rest: bool) -> Self {
+        let mut allowed = [if allow_rest { u64::MAX } else { 0 }; FILTER_WORDS];
+        let mut i = 0;
+        while i < nrs.len() {
+            let nr = slot(nrs[i]);
+            if allow_rest {
+                allowed[nr / 64] &= !(1 << (nr % 64));
+            } else {
+                allowed[nr / 64] |= 1 << (nr % 64);
+            }
+            i += 1;
+        }
+        Self {
+            allowed,
+            action: Action::Errno(libc::ENOSYS),
+        }
+    }
+
+    /// Take `action` on blocked calls instead of failing them with `ENOSYS`.
+    pub const fn on_blocked(mut self, action: Action) -> Self {
+        self.action = action;
+        self
+    }
+
+    pub fn allows(&self, nr: usize) -> bool {
+        let nr = slot(nr);
+        self.allowed[nr / 64] & (1 << (nr % 64)) != 0
+    }
+}
+
+const fn slot(nr: usize) -> usize {
+    if nr < HOSTCALLS {
+        nr
+    } else {
+        HOSTCALLS
+    }
+}
+
+pub struct Filters {
+    list: [Option<Filter>; MAX_FILTERS],
+}
+
+impl Filters {
+    pub const fn new() -> Self {
+        Self {
+            list: [None; MAX_FILTERS],
+        }
+    }
+
+    /// Add `filter` on top of the others; false if [`MAX_FILTERS`] are installed.
+    pub fn install(&mut self, filter: Filter) -> bool {
+        match self.list.iter_mut().find(|f| f.is_none()) {
+            Some(free) => {
+                *free = Some(filter);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// What to do with syscall `nr`: `None` runs it. [`Action::Kill`] from any filter wins,
+    /// then the errno of the first filter that blocks it.
+    pub fn verdict(&self, nr: usize) -> Option<Action> {
+        if nr == SYS_BATCH {
+            return None;
+        }
+        let mut verdict = None;
+        for filter in self.list.iter().flatten() {
+            if filter.allows(nr) {
+                continue;
+            }
+            if filter.action == Action::Kill {
+                return Some(Action::Kill);
+            }
+            verdict = verdict.or(Some(filter.action));
+        }
+        verdict
+    }
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static FILTERS: GlobalCell<Filters> = GlobalCell::new(Filters::new());
+
+/// Add `filter` to the ones every syscall is checked against. Returns false, leaving the
+/// syscall surface as it was, when [`MAX_FILTERS`] are already installed.
+#[must_use]
+pub fn install(filter: Filter) -> bool {
+    FILTERS.with_mut(|filters| filters.install(filter))
+}
+
+/// Whether syscall `nr` would run.
+pub fn allowed(nr: usize) -> bool {
+    FILTERS.with(|filters| filters.verdict(nr).is_none())
+}
+
+/// The result of a call the filters block, or `None` to run it.
+#[inline]
+pub(crate) fn check(nr: usize, args: [usize; 6]) -> Option<isize> {
+    match FILTERS.with(|filters| filters.verdict(nr))? {
+        Action::Errno(errno) => Some(-(errno as isize)),
+        Action::Kill => {
+            #[cfg(feature = "strace")]
+            {
+                crate::strace::record(nr, args, -(libc::ENOSYS as isize));
+                crate::strace::flush();
+            }
+            let _ = args;
+            foundation::kfn::kexit(KILL_STATUS)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn reset() {
+    FILTERS.with_mut(|filters| *filters = Filters::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    const READ: usize = libc::SYS_read as usize;
+    const WRITE: usize = libc::SYS_write as usize;
+    const OPENAT: usize = libc::SYS_openat as usize;
+
+    #[test]
+    fn test_filters_only_narrow() {
+        let mut filters = Filters::new();
+        assert_eq!(filters.verdict(OPENAT), None);
+
+        assert!(filters.install(Filter::allow_only(&[READ, WRITE, OPENAT, READ])));
+        assert!(filters.install(Filter::deny(&[OPENAT]).on_blocked(Action::Errno(libc::EPERM))));
+        // Allowing everything again gives nothing back.
+        assert!(filters.install(Filter::deny(&[])));
+        assert_eq!(filters.verdict(WRITE), None);
+        assert_eq!(filters.verdict(OPENAT), Some(Action::Errno(libc::EPERM)));
+        assert_eq!(
+            filters.verdict(libc::SYS_close as usize),
+            Some(Action::Errno(libc::ENOSYS))
+        );
+
+        // Hostcalls share one number; batches are checked entry by entry instead.
+        assert_eq!(
+            filters.verdict(0x0100_0000),
+            Some(Action::Errno(libc::ENOSYS))
+        );
+        assert_eq!(filters.verdict(SYS_BATCH), None);
+
+        assert!(filters.install(Filter::deny(&[HOSTCALLS, WRITE]).on_blocked(Action::Kill)));
+        assert_eq!(filters.verdict(WRITE), Some(Action::Kill));
+        assert_eq!(filters.verdict(0x0100_0005), Some(Action::Kill));
+        assert_eq!(filters.verdict(READ), None);
+
+        for _ in 4..MAX_FILTERS {
+            assert!(filters.install(Filter::deny(&[])));
+        }
+        assert!(!filters.install(Filter::deny(&[READ])));
+        assert_eq!(filters.verdict(READ), None);
+    }
+
+    #[test]
+    fn test_blocked_calls_skip_the_handler() {
+        let _kernel = testing::kernel();
+        assert!(install(Filter::deny(&[
+            libc::SYS_sched_getaffinity as usize
+        ])));
+        assert!(!allowed(libc::SYS_sched_getaffinity as usize));
+
+        let mut mask = [0u8; 8];
+        let args = [0, mask.len(), mask.as_mut_ptr() as usize, 0, 0, 0];
+        assert_eq!(
+            testing::syscall(libc::SYS_sched_getaffinity, args),
+            -(libc::ENOSYS as isize)
+        );
+        assert_eq!(mask, [0; 8]);
+        assert_eq!(testing::syscall(libc::SYS_getcpu, [0; 6]), 0);
+
+        reset();
+        assert!(testing::syscall(libc::SYS_sched_getaffinity, args) > 0);
+    }
+}
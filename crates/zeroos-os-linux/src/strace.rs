@@ -0,0 +1,206 @@
+//! Syscall tracing into a fixed-size ring buffer.
+//!
+//! Every dispatched syscall that passes the filter is recorded with its arguments and return
+//! value. The oldest entries are overwritten once the ring is full. [`flush`] dumps the ring to
+//! the debug console; it runs automatically on `exit_group` and can be called on demand.
+
+use foundation::utils::GlobalCell;
+
+use crate::syscall::{syscall_name, NR_SYSCALLS};
+
+/// Number of syscalls kept in the ring.
+pub const TRACE_CAPACITY: usize = 64;
+
+const FILTER_WORDS: usize = NR_SYSCALLS.div_ceil(64);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub nr: usize,
+    pub args: [usize; 6],
+    pub ret: isize,
+}
+
+pub struct TraceRing {
+    entries: [TraceEntry; TRACE_CAPACITY],
+    /// Slot the next entry is written to.
+    head: usize,
+    len: usize,
+    /// Entries overwritten since the last flush.
+    dropped: usize,
+    /// One bit per syscall number; set bits are recorded.
+    filter: [u64; FILTER_WORDS],
+}
+
+impl TraceRing {
+    pub const fn new() -> Self {
+        Self {
+            entries: [TraceEntry {
+                nr: 0,
+                args: [0; 6],
+                ret: 0,
+            }; TRACE_CAPACITY],
+            head: 0,
+            len: 0,
+            dropped: 0,
+            filter: [u64::MAX; FILTER_WORDS],
+        }
+    }
+
+    pub fn is_traced(&self, nr: usize) -> bool {
+        // Out-of-table numbers share the last bit so unknown syscalls can still be traced.
+        let nr = nr.min(NR_SYSCALLS - 1);
+        self.filter[nr / 64] & (1 << (nr % 64)) != 0
+    }
+
+    pub fn set_traced(&mut self, nr: usize, traced: bool) {
+        let nr = nr.min(NR_SYSCALLS - 1);
+        if traced {
+            self.filter[nr / 64] |= 1 << (nr % 64);
+        } else {
+            self.filter[nr / 64] &= !(1 << (nr % 64));
+        }
+    }
+
+    /// Trace every syscall (`true`) or none (`false`).
+    pub fn set_all(&mut self, traced: bool) {
+        self.filter = [if traced { u64::MAX } else { 0 }; FILTER_WORDS];
+    }
+
+    pub fn record(&mut self, entry: TraceEntry) {
+        if !self.is_traced(entry.nr) {
+            return;
+        }
+        self.entries[self.head] = entry;
+        self.head = (self.head + 1) % TRACE_CAPACITY;
+        if self.len < TRACE_CAPACITY {
+            self.len += 1;
+        } else {
+            self.dropped += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Recorded entries, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &TraceEntry> {
+        let start = (self.head + TRACE_CAPACITY - self.len) % TRACE_CAPACITY;
+        (0..self.len).map(move |i| &self.entries[(start + i) % TRACE_CAPACITY])
+    }
+
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+        self.dropped = 0;
+    }
+}
+
+impl Default for TraceRing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static TRACE: GlobalCell<TraceRing> = GlobalCell::new(TraceRing::new());
+
+#[inline]
+pub(crate) fn record(nr: usize, args: [usize; 6], ret: isize) {
+    TRACE.with_mut(|t| t.record(TraceEntry { nr, args, ret }));
+}
+
+/// Enable or disable tracing of syscall `nr`.
+pub fn set_traced(nr: usize, traced: bool) {
+    TRACE.with_mut(|t| t.set_traced(nr, traced));
+}
+
+/// Trace only the syscalls in `nrs`.
+pub fn trace_only(nrs: &[usize]) {
+    TRACE.with_mut(|t| {
+        t.set_all(false);
+        for &nr in nrs {
+            t.set_traced(nr, true);
+        }
+    });
+}
+
+/// Trace every syscall (the default).
+pub fn trace_all() {
+    TRACE.with_mut(|t| t.set_all(true));
+}
+
+/// Write the recorded syscalls to the debug console, oldest first, and empty the ring.
+pub fn flush() {
+    TRACE.with_mut(|t| {
+        if t.dropped() != 0 {
+            debug::writeln!("[strace] ... {} earlier syscalls dropped", t.dropped());
+        }
+        for e in t.iter() {
+            debug::writeln!(
+                "[strace] {}({:#x}, {:#x}, {:#x}, {:#x}, {:#x}, {:#x}) = {}",
+                syscall_name(e.nr),
+                e.args[0],
+                e.args[1],
+                e.args[2],
+                e.args[3],
+                e.args[4],
+                e.args[5],
+                e.ret
+            );
+        }
+        t.clear();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(nr: usize, ret: isize) -> TraceEntry {
+        TraceEntry {
+            nr,
+            args: [nr; 6],
+            ret,
+        }
+    }
+
+    #[test]
+    fn test_ring_wraps_oldest_first() {
+        let mut ring = TraceRing::new();
+        for i in 0..TRACE_CAPACITY + 3 {
+            ring.record(entry(i, i as isize));
+        }
+        assert_eq!(ring.len(), TRACE_CAPACITY);
+        assert_eq!(ring.dropped(), 3);
+        assert_eq!(ring.iter().next().map(|e| e.nr), Some(3));
+        assert_eq!(ring.iter().last().map(|e| e.nr), Some(TRACE_CAPACITY + 2));
+
+        ring.clear();
+        assert!(ring.is_empty());
+        assert_eq!(ring.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut ring = TraceRing::new();
+        ring.set_all(false);
+        ring.set_traced(64, true);
+        ring.record(entry(63, 0));
+        ring.record(entry(64, -38));
+        ring.record(entry(NR_SYSCALLS + 5, 0));
+        assert_eq!(ring.len(), 1);
+        assert_eq!(ring.iter().next(), Some(&entry(64, -38)));
+
+        ring.set_traced(NR_SYSCALLS + 5, true);
+        ring.record(entry(NR_SYSCALLS + 5, 0));
+        assert_eq!(ring.len(), 2);
+    }
+}
@@ -9,7 +9,7 @@ use crate::handlers;
 ///
 /// Linux uses `__NR_syscalls` as the syscall-space size (arch-dependent, typically a few hundred).
 /// We pick a conservative bound to keep the table simple while staying small (~8 KiB on riscv64).
-const NR_SYSCALLS: usize = 1024;
+pub(crate) const NR_SYSCALLS: usize = 1024;
 
 type SysHandler = fn(a0: usize, a1: usize, a2: usize, a3: usize, a4: usize, a5: usize) -> isize;
 
@@ -143,12 +143,20 @@ sys_registry! {
     (SYS_rt_sigprocmask, handlers::signal::sys_rt_sigprocmask, 4),
     (SYS_tkill, handlers::signal::sys_tkill, 2),
     (SYS_tgkill, handlers::signal::sys_tgkill, 3),
+    (SYS_set_robust_list, handlers::process::sys_set_robust_list, 2),
+    (SYS_get_robust_list, handlers::process::sys_get_robust_list, 3),
+    (SYS_prctl, handlers::process::sys_prctl, 5),
+    (SYS_rseq, handlers::process::sys_rseq, 4),
+    (SYS_sched_getaffinity, handlers::sched::sys_sched_getaffinity, 3),
+    (SYS_sched_setaffinity, handlers::sched::sys_sched_setaffinity, 3),
+    (SYS_getcpu, handlers::sched::sys_getcpu, 3),
 
     // Scheduler/sys-thread syscalls.
     #[cfg(feature = "scheduler")]
     {
         (SYS_clone, handlers::thread::sys_clone, 5),
-        (SYS_futex, handlers::thread::sys_futex, 3),
+        (SYS_futex, handlers::thread::sys_futex, 6),
+        (SYS_wait4, handlers::thread::sys_wait4, 4),
         (SYS_sched_yield, handlers::thread::sys_sched_yield, 0),
         (SYS_getpid, handlers::thread::sys_getpid, 0),
         (SYS_gettid, handlers::thread::sys_gettid, 0),
@@ -162,20 +170,31 @@ sys_registry! {
         (SYS_mmap, handlers::memory::sys_mmap, 6),
         (SYS_munmap, handlers::memory::sys_munmap, 2),
         (SYS_mprotect, handlers::memory::sys_mprotect, 3),
+        (SYS_mremap, handlers::memory::sys_mremap, 5),
     }
 
     // VFS syscalls.
     #[cfg(feature = "vfs")]
     {
         (SYS_openat, handlers::vfs::sys_openat, 4),
+        (SYS_unlinkat, handlers::vfs::sys_unlinkat, 3),
         (SYS_close, handlers::vfs::sys_close, 1),
         (SYS_read, handlers::vfs::sys_read, 3),
         (SYS_write, handlers::vfs::sys_write, 3),
         (SYS_readv, handlers::vfs::sys_readv, 3),
         (SYS_writev, handlers::vfs::sys_writev, 3),
+        (SYS_pread64, handlers::vfs::sys_pread64, 4),
+        (SYS_pwrite64, handlers::vfs::sys_pwrite64, 4),
         (SYS_lseek, handlers::vfs::sys_lseek, 3),
         (SYS_ioctl, handlers::vfs::sys_ioctl, 3),
+        #[cfg(target_pointer_width = "64")]
         (SYS_fstat, handlers::vfs::sys_fstat, 2),
+        #[cfg(target_pointer_width = "64")]
+        (SYS_newfstatat, handlers::vfs::sys_newfstatat, 4),
+        (SYS_statx, handlers::vfs::sys_statx, 5),
+        (SYS_pipe2, handlers::vfs::sys_pipe2, 2),
+        (SYS_dup, handlers::vfs::sys_dup, 1),
+        (SYS_dup3, handlers::vfs::sys_dup3, 3),
     }
 
     // Random syscalls.
@@ -183,6 +202,16 @@ sys_registry! {
     {
         (SYS_getrandom, handlers::random::sys_getrandom, 3),
     }
+
+    // Time syscalls.
+    #[cfg(feature = "time")]
+    {
+        (SYS_clock_gettime, handlers::time::sys_clock_gettime, 2),
+        (SYS_clock_getres, handlers::time::sys_clock_getres, 2),
+        (SYS_clock_nanosleep, handlers::time::sys_clock_nanosleep, 4),
+        (SYS_gettimeofday, handlers::time::sys_gettimeofday, 2),
+        (SYS_nanosleep, handlers::time::sys_nanosleep, 2),
+    }
 }
 
 /// Returns the name of a syscall given its number.
@@ -561,6 +590,7 @@ pub fn syscall_name(nr: usize) -> &'static str {
         SYS_pidfd_send_signal => "SYS_pidfd_send_signal",
         SYS_pidfd_getfd => "SYS_pidfd_getfd",
 
+        n if n == crate::batch::SYS_BATCH as i64 => "SYS_batch",
         _ => "SYS_unknown",
     }
 }
@@ -584,11 +614,7 @@ pub unsafe fn dispatch_syscall<Frame: SyscallFrame>(regs: *mut Frame) {
     let a4 = regs_ref.arg(4);
     let a5 = regs_ref.arg(5);
 
-    let ret = if nr < NR_SYSCALLS {
-        (HANDLERS[nr])(a0, a1, a2, a3, a4, a5)
-    } else {
-        sys_unsupported_handler(a0, a1, a2, a3, a4, a5)
-    };
+    let ret = linux_handle(a0, a1, a2, a3, a4, a5, nr);
     unsafe { (*regs).set_ret(ret) }
 }
 
@@ -601,11 +627,40 @@ pub fn linux_handle(
     a5: usize,
     nr: usize,
 ) -> isize {
-    if nr < NR_SYSCALLS {
+    #[cfg(feature = "strace")]
+    if nr == SYS_exit_group as usize {
+        // exit_group does not return: log it and dump the ring now.
+        crate::strace::record(nr, [a0, a1, a2, a3, a4, a5], 0);
+        crate::strace::flush();
+    }
+
+    #[cfg(feature = "perf")]
+    perf::begin(syscall_name(nr));
+
+    #[cfg(feature = "seccomp")]
+    let blocked = crate::seccomp::check(nr, [a0, a1, a2, a3, a4, a5]);
+    #[cfg(not(feature = "seccomp"))]
+    let blocked = None;
+
+    let ret = if let Some(ret) = blocked {
+        ret
+    } else if nr < NR_SYSCALLS {
         (HANDLERS[nr])(a0, a1, a2, a3, a4, a5)
+    } else if nr == crate::batch::SYS_BATCH {
+        crate::batch::sys_batch(a0, a1, a2)
+    } else if let Some(ret) = crate::hostcall::dispatch(nr, [a0, a1, a2, a3, a4, a5]) {
+        ret
     } else {
         sys_unsupported_handler(a0, a1, a2, a3, a4, a5)
-    }
+    };
+
+    #[cfg(feature = "perf")]
+    perf::end(syscall_name(nr));
+
+    #[cfg(feature = "strace")]
+    crate::strace::record(nr, [a0, a1, a2, a3, a4, a5], ret);
+
+    ret
 }
 
 pub const TRAP_OPS: foundation::ops::TrapOps = foundation::ops::TrapOps {
@@ -0,0 +1,525 @@
+//! Host test harness for the syscall handlers.
+//!
+//! [`FakeFrame`] stands in for an architecture trap frame, so tests enter the handlers through
+//! [`dispatch_syscall`] exactly as a trap would. [`kernel`] registers mock backends for every
+//! enabled subsystem and holds a lock for the duration of the test, since the kernel they are
+//! registered in is global:
+//!
+//! - memory: the host allocator;
+//! - vfs: a console whose fd 0 reads [`set_stdin`] and fds 1 and 2 append to [`take_stdout`],
+//!   and a read-only regular file [`FILE_FD`] holding [`set_file`];
+//! - scheduler: a single thread [`TID`] that never has anything to join, whose clock stays at 0.
+//!   It records the arguments of the last spawn for [`last_spawn`] and the last timed wait for
+//!   [`last_timeout_ns`];
+//! - time: clocks that only move when [`set_clock_ns`] or a sleep moves them, with
+//!   `CLOCK_REALTIME` [`REALTIME_BASE_NS`] ahead of `CLOCK_MONOTONIC`.
+
+extern crate std;
+
+use std::sync::{Mutex, MutexGuard, Once};
+
+use foundation::SyscallFrame;
+
+use crate::syscall::dispatch_syscall;
+
+/// Register state of a syscall trap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FakeFrame {
+    pub pc: usize,
+    pub nr: usize,
+    pub args: [usize; 6],
+    /// Set by the dispatcher.
+    pub ret: Option<isize>,
+}
+
+impl FakeFrame {
+    pub fn new(nr: libc::c_long, args: [usize; 6]) -> Self {
+        Self {
+            nr: nr as usize,
+            args,
+            ..Self::default()
+        }
+    }
+}
+
+impl SyscallFrame for FakeFrame {
+    fn pc(&self) -> usize {
+        self.pc
+    }
+
+    fn syscall_number(&self) -> usize {
+        self.nr
+    }
+
+    fn arg(&self, idx: usize) -> usize {
+        self.args[idx]
+    }
+
+    fn set_ret(&mut self, ret: isize) {
+        assert_eq!(self.ret, None, "syscall returned twice");
+        self.ret = Some(ret);
+    }
+}
+
+/// Dispatch syscall `nr` and return what it stored in the frame.
+pub fn syscall(nr: libc::c_long, args: [usize; 6]) -> isize {
+    let mut frame = FakeFrame::new(nr, args);
+    unsafe { dispatch_syscall(&mut frame) };
+    frame.ret.expect("syscall did not return")
+}
+
+// Platform hooks the handlers link against. A test that exits or aborts the guest fails.
+
+#[no_mangle]
+extern "C" fn __platform_exit(code: i32) -> ! {
+    panic!("guest exited with code {code}");
+}
+
+#[no_mangle]
+extern "C" fn __platform_abort(sig: i32) -> ! {
+    panic!("guest aborted with signal {sig}");
+}
+
+#[cfg(feature = "perf")]
+#[no_mangle]
+extern "C" fn __platform_cycle_count() -> u64 {
+    0
+}
+
+#[cfg(feature = "strace")]
+#[no_mangle]
+extern "C" fn __debug_write(msg: *const u8, len: usize) {
+    use std::io::Write;
+    let msg = unsafe { core::slice::from_raw_parts(msg, len) };
+    let _ = std::io::stderr().write_all(msg);
+}
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Register the mock backends (once) and serialize the caller with every other user of the
+/// kernel until the guard drops.
+pub fn kernel() -> MutexGuard<'static, ()> {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        #[cfg(feature = "memory")]
+        foundation::register_memory(memory::OPS);
+        #[cfg(feature = "vfs")]
+        foundation::register_vfs(vfs::OPS);
+        #[cfg(feature = "scheduler")]
+        foundation::register_scheduler_backend(&scheduler::MOCK);
+        #[cfg(feature = "time")]
+        foundation::register_time(time::OPS);
+    });
+    LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(feature = "memory")]
+mod memory {
+    extern crate std;
+
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use foundation::ops::MemoryOps;
+
+    static USED: AtomicUsize = AtomicUsize::new(0);
+
+    pub const OPS: MemoryOps = MemoryOps {
+        init: |_, _| {},
+        alloc: |layout| {
+            USED.fetch_add(layout.size(), Ordering::Relaxed);
+            unsafe { std::alloc::alloc(layout) }
+        },
+        dealloc: |ptr, layout| {
+            USED.fetch_sub(layout.size(), Ordering::Relaxed);
+            unsafe { std::alloc::dealloc(ptr, layout) }
+        },
+        realloc: |ptr, old: Layout, new_size| {
+            USED.fetch_add(new_size, Ordering::Relaxed);
+            USED.fetch_sub(old.size(), Ordering::Relaxed);
+            unsafe { std::alloc::realloc(ptr, old, new_size) }
+        },
+        used: || USED.load(Ordering::Relaxed),
+        grow: |_, _| false,
+    };
+}
+
+#[cfg(feature = "vfs")]
+pub use vfs::{file_offset, set_file, set_stdin, take_stdout, FILE_FD, FILE_PATH};
+
+#[cfg(feature = "vfs")]
+mod vfs {
+    extern crate std;
+
+    use core::ffi::{c_char, CStr};
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    use foundation::ops::{FileStat, VfsOps};
+
+    static STDIN: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+    static STDOUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+    /// Contents and offset of [`FILE_FD`].
+    static FILE: Mutex<(Vec<u8>, usize)> = Mutex::new((Vec::new(), 0));
+
+    /// Descriptor of the regular file.
+    pub const FILE_FD: i32 = 3;
+    /// Path of the regular file.
+    pub const FILE_PATH: &CStr = c"/file";
+
+    /// Bytes the next reads of fd 0 return.
+    pub fn set_stdin(bytes: &[u8]) {
+        *STDIN.lock().unwrap() = bytes.to_vec();
+    }
+
+    /// Everything written to fds 1 and 2 since the last call.
+    pub fn take_stdout() -> Vec<u8> {
+        core::mem::take(&mut *STDOUT.lock().unwrap())
+    }
+
+    /// Replace the contents of [`FILE_FD`] and rewind it.
+    pub fn set_file(bytes: &[u8]) {
+        *FILE.lock().unwrap() = (bytes.to_vec(), 0);
+    }
+
+    /// Current offset of [`FILE_FD`].
+    pub fn file_offset() -> usize {
+        FILE.lock().unwrap().1
+    }
+
+    const EBADF: isize = -(libc::EBADF as isize);
+
+    fn file_stat() -> FileStat {
+        FileStat {
+            mode: libc::S_IFREG | 0o444,
+            size: FILE.lock().unwrap().0.len() as u64,
+        }
+    }
+
+    fn is_console(fd: i32) -> bool {
+        (0..=2).contains(&fd)
+    }
+
+    fn is_open(fd: i32) -> bool {
+        is_console(fd) || fd == FILE_FD
+    }
+
+    pub const OPS: VfsOps = VfsOps {
+        init: || {},
+        read: |fd, buf, count| {
+            if fd == FILE_FD {
+                let mut file = FILE.lock().unwrap();
+                let (contents, offset) = &mut *file;
+                let rest = contents.get(*offset..).unwrap_or_default();
+                let n = count.min(rest.len());
+                unsafe { core::ptr::copy_nonoverlapping(rest.as_ptr(), buf, n) };
+                *offset += n;
+                return n as isize;
+            }
+            if fd != 0 {
+                return EBADF;
+            }
+            let mut stdin = STDIN.lock().unwrap();
+            let n = count.min(stdin.len());
+            unsafe { core::ptr::copy_nonoverlapping(stdin.as_ptr(), buf, n) };
+            stdin.drain(..n);
+            n as isize
+        },
+        write: |fd, buf, count| {
+            if fd != 1 && fd != 2 {
+                return EBADF;
+            }
+            let bytes = unsafe { core::slice::from_raw_parts(buf, count) };
+            STDOUT.lock().unwrap().extend_from_slice(bytes);
+            count as isize
+        },
+        // One call per buffer, enough for files that would have no `read_iter`.
+        readv: |fd, iov| {
+            let mut total = 0;
+            for v in iov.iter().filter(|v| v.len != 0) {
+                let n = (OPS.read)(fd, v.base, v.len);
+                if n < 0 {
+                    return n;
+                }
+                total += n;
+                if (n as usize) < v.len {
+                    break;
+                }
+            }
+            total
+        },
+        writev: |fd, iov| {
+            let mut total = 0;
+            for v in iov.iter().filter(|v| v.len != 0) {
+                let n = (OPS.write)(fd, v.base, v.len);
+                if n < 0 {
+                    return n;
+                }
+                total += n;
+            }
+            total
+        },
+        pread: |fd, buf, count, offset| {
+            if fd != FILE_FD {
+                return if is_console(fd) {
+                    -(libc::ESPIPE as isize)
+                } else {
+                    EBADF
+                };
+            }
+            let file = FILE.lock().unwrap();
+            let rest = file.0.get(offset..).unwrap_or_default();
+            let n = count.min(rest.len());
+            unsafe { core::ptr::copy_nonoverlapping(rest.as_ptr(), buf, n) };
+            n as isize
+        },
+        pwrite: |fd, _, _, _| {
+            if is_console(fd) {
+                -(libc::ESPIPE as isize)
+            } else {
+                EBADF
+            }
+        },
+        open: |_, _, _| -(libc::ENOENT as isize),
+        close: |fd| if is_open(fd) { 0 } else { EBADF },
+        lseek: |fd, offset, whence| {
+            if fd == FILE_FD {
+                let mut file = FILE.lock().unwrap();
+                let base = match whence {
+                    libc::SEEK_SET => 0,
+                    libc::SEEK_CUR => file.1 as isize,
+                    libc::SEEK_END => file.0.len() as isize,
+                    _ => return -(libc::EINVAL as isize),
+                };
+                return match base.checked_add(offset) {
+                    Some(pos) if pos >= 0 => {
+                        file.1 = pos as usize;
+                        pos
+                    }
+                    _ => -(libc::EINVAL as isize),
+                };
+            }
+            if is_console(fd) {
+                -(libc::ESPIPE as isize)
+            } else {
+                EBADF
+            }
+        },
+        ioctl: |fd, _, _| {
+            if is_open(fd) {
+                -(libc::ENOTTY as isize)
+            } else {
+                EBADF
+            }
+        },
+        fstat: |fd, stat| {
+            if !is_open(fd) {
+                return EBADF;
+            }
+            *stat = if fd == FILE_FD {
+                file_stat()
+            } else {
+                FileStat {
+                    mode: libc::S_IFCHR | 0o620,
+                    size: 0,
+                }
+            };
+            0
+        },
+        // The regular file is also reachable as [`FILE_PATH`].
+        stat: |path, stat| {
+            if unsafe { CStr::from_ptr(path as *const c_char) } != FILE_PATH {
+                return -(libc::ENOENT as isize);
+            }
+            *stat = file_stat();
+            0
+        },
+        unlink: |_| -(libc::ENOENT as isize),
+        pipe: |_, _| -(libc::ENFILE as isize),
+        dup: |_, _, _| -(libc::EMFILE as isize),
+    };
+}
+
+#[cfg(feature = "scheduler")]
+pub use scheduler::{last_spawn, last_timeout_ns, TID};
+
+#[cfg(feature = "scheduler")]
+mod scheduler {
+    use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    use foundation::ops::SchedulerBackend;
+
+    /// The one thread; spawns return `TID + 1`.
+    pub const TID: usize = 1;
+
+    pub struct MockScheduler {
+        spawned: [AtomicUsize; 5],
+        timeout_ns: AtomicU64,
+    }
+
+    pub static MOCK: MockScheduler = MockScheduler {
+        spawned: [const { AtomicUsize::new(0) }; 5],
+        timeout_ns: AtomicU64::new(0),
+    };
+
+    /// `[stack, tls, parent_tid_ptr, child_tid_ptr, clear_child_tid_ptr]` of the last spawn.
+    pub fn last_spawn() -> [usize; 5] {
+        MOCK.spawned.each_ref().map(|a| a.load(Ordering::Relaxed))
+    }
+
+    /// Timeout of the last `wait_on_addr_timeout`.
+    pub fn last_timeout_ns() -> u64 {
+        MOCK.timeout_ns.load(Ordering::Relaxed)
+    }
+
+    impl SchedulerBackend for MockScheduler {
+        fn init(&self) -> usize {
+            0
+        }
+
+        fn spawn_thread(
+            &self,
+            stack: usize,
+            tls: usize,
+            parent_tid_ptr: usize,
+            child_tid_ptr: usize,
+            clear_child_tid_ptr: usize,
+        ) -> isize {
+            let args = [
+                stack,
+                tls,
+                parent_tid_ptr,
+                child_tid_ptr,
+                clear_child_tid_ptr,
+            ];
+            for (slot, arg) in self.spawned.iter().zip(args) {
+                slot.store(arg, Ordering::Relaxed);
+            }
+            (TID + 1) as isize
+        }
+
+        fn yield_now(&self) -> isize {
+            0
+        }
+
+        fn preempt(&self) {}
+
+        fn exit_current(&self, _code: i32) -> isize {
+            unimplemented!("exit in a host test")
+        }
+
+        fn current_tid(&self) -> usize {
+            TID
+        }
+
+        fn thread_count(&self) -> usize {
+            1
+        }
+
+        fn wait_on_addr(&self, addr: usize, expected: i32) -> isize {
+            // Nobody else could wake the only thread.
+            let current = unsafe { core::ptr::read_volatile(addr as *const i32) };
+            if current == expected {
+                -(libc::EDEADLK as isize)
+            } else {
+                -(libc::EAGAIN as isize)
+            }
+        }
+
+        fn wake_on_addr(&self, _addr: usize, _count: usize) -> usize {
+            0
+        }
+
+        fn wait_on_addr_timeout(&self, addr: usize, expected: i32, timeout_ns: u64) -> isize {
+            self.timeout_ns.store(timeout_ns, Ordering::Relaxed);
+            let current = unsafe { core::ptr::read_volatile(addr as *const i32) };
+            if current == expected {
+                -(libc::ETIMEDOUT as isize)
+            } else {
+                -(libc::EAGAIN as isize)
+            }
+        }
+
+        fn now_ns(&self) -> u64 {
+            0
+        }
+
+        fn requeue_on_addr(
+            &self,
+            _addr: usize,
+            _wake_count: usize,
+            _addr2: usize,
+            _requeue_count: usize,
+        ) -> usize {
+            0
+        }
+
+        fn join(&self, _tid: usize, _status_ptr: usize, _nohang: bool) -> isize {
+            -(libc::ESRCH as isize)
+        }
+
+        fn set_clear_on_exit_addr(&self, _addr: usize) -> isize {
+            TID as isize
+        }
+
+        fn set_robust_list(&self, _head: usize) -> isize {
+            0
+        }
+
+        fn robust_list(&self, _tid: usize) -> isize {
+            0
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+pub use time::{set_clock_ns, REALTIME_BASE_NS};
+
+#[cfg(feature = "time")]
+mod time {
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    use foundation::ops::{TimeConfig, TimeOps};
+
+    /// `CLOCK_REALTIME` minus `CLOCK_MONOTONIC`.
+    pub const REALTIME_BASE_NS: u64 = 1_000_000_000_000;
+
+    /// `CLOCK_MONOTONIC`.
+    static NOW: AtomicU64 = AtomicU64::new(0);
+
+    /// Set `CLOCK_MONOTONIC` to `ns`.
+    pub fn set_clock_ns(ns: u64) {
+        NOW.store(ns, Ordering::Relaxed);
+    }
+
+    fn now_ns(clock_id: usize) -> i64 {
+        let now = NOW.load(Ordering::Relaxed);
+        match clock_id as libc::clockid_t {
+            libc::CLOCK_REALTIME => (now + REALTIME_BASE_NS) as i64,
+            libc::CLOCK_MONOTONIC | libc::CLOCK_BOOTTIME => now as i64,
+            _ => -(libc::EINVAL as i64),
+        }
+    }
+
+    pub const OPS: TimeOps = TimeOps {
+        init: |_: TimeConfig| {},
+        now_ns,
+        advance: |ns| {
+            NOW.fetch_add(ns, Ordering::Relaxed);
+        },
+    };
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_syscall_is_enosys() {
+        let _kernel = kernel();
+        assert_eq!(syscall(-1, [0; 6]), -(libc::ENOSYS as isize));
+        assert_eq!(
+            syscall(crate::syscall::NR_SYSCALLS as libc::c_long - 1, [0; 6]),
+            -(libc::ENOSYS as isize)
+        );
+    }
+}
@@ -0,0 +1,190 @@
+#![no_std]
+
+//! Panic handler that reports where and in what context a guest panicked.
+//!
+//! The report goes to `__platform_stdout_write` and covers the message, its location, the
+//! current tid (`scheduler` feature), the innermost trap being handled (`trap` feature) and a
+//! backtrace when the build enables one (`--backtrace=frame-pointers` or `dwarf`). The guest
+//! then exits through `__platform_exit` with [`PANIC_EXIT_CODE`], like a Rust process that
+//! panicked on the host.
+//!
+//! This replaces the platform's own handler and `runtime-nostd`'s `panic` feature; only link one.
+
+use core::fmt::{self, Display, Write};
+use core::panic::Location;
+
+/// Exit code of a panicked guest, the same as `std` uses on the host.
+pub const PANIC_EXIT_CODE: i32 = 101;
+
+#[cfg(target_os = "none")]
+extern "C" {
+    fn __platform_stdout_write(msg: *const u8, len: usize);
+    fn __platform_exit(code: i32) -> !;
+}
+
+#[cfg(target_os = "none")]
+struct Console;
+
+#[cfg(target_os = "none")]
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // SAFETY: `s` is `s.len()` readable bytes.
+        unsafe { __platform_stdout_write(s.as_ptr(), s.len()) };
+        Ok(())
+    }
+}
+
+/// The trap a panic was raised under, from the arch trap stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrapSnapshot {
+    pub mcause: usize,
+    pub mepc: usize,
+    pub mtval: usize,
+    pub from_kernel: bool,
+    /// Nesting depth, 1 for a trap taken outside any other.
+    pub depth: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PanicContext {
+    pub tid: Option<usize>,
+    pub trap: Option<TrapSnapshot>,
+}
+
+impl PanicContext {
+    /// What is known about the code running right now.
+    pub fn current() -> Self {
+        Self {
+            tid: current_tid(),
+            trap: current_trap(),
+        }
+    }
+}
+
+fn current_tid() -> Option<usize> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "scheduler")] {
+            Some(foundation::kfn::scheduler::kcurrent_tid())
+        } else {
+            None
+        }
+    }
+}
+
+fn current_trap() -> Option<TrapSnapshot> {
+    cfg_if::cfg_if! {
+        if #[cfg(all(feature = "trap", any(target_arch = "riscv32", target_arch = "riscv64")))] {
+            let depth = arch_riscv::trap_depth();
+            let record = arch_riscv::trap_record(depth.checked_sub(1)?)?;
+            Some(TrapSnapshot {
+                mcause: record.mcause,
+                mepc: record.mepc,
+                mtval: record.mtval,
+                from_kernel: record.from_kernel,
+                depth,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Write the report header: message, location and context, one item per line.
+pub fn write_report(
+    w: &mut impl Write,
+    message: &dyn Display,
+    location: Option<&Location<'_>>,
+    context: &PanicContext,
+) -> fmt::Result {
+    writeln!(w, "PANIC: {}", message)?;
+    if let Some(location) = location {
+        writeln!(
+            w,
+            "  at {}:{}:{}",
+            location.file(),
+            location.line(),
+            location.column()
+        )?;
+    }
+    if let Some(tid) = context.tid {
+        writeln!(w, "  tid: {}", tid)?;
+    }
+    if let Some(trap) = context.trap {
+        writeln!(
+            w,
+            "  trap: mcause=0x{:x} mepc=0x{:x} mtval=0x{:x} from {} (depth {})",
+            trap.mcause,
+            trap.mepc,
+            trap.mtval,
+            if trap.from_kernel { "kernel" } else { "user" },
+            trap.depth
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "none")]
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    static PANICKING: AtomicBool = AtomicBool::new(false);
+
+    // A panic while reporting (say, in the backtrace walk) skips straight to the exit.
+    if !PANICKING.swap(true, Ordering::Relaxed) {
+        let _ = write_report(
+            &mut Console,
+            &info.message(),
+            info.location(),
+            &PanicContext::current(),
+        );
+        // SAFETY: called from the panic handler; the stack is intact up to this frame.
+        unsafe {
+            use zeroos_backtrace::BacktraceCapture;
+            zeroos_backtrace::Backtrace::print_backtrace();
+        }
+    } else {
+        let _ = Console.write_str("PANIC: panicked while reporting a panic\n");
+    }
+
+    // SAFETY: the platform provides `__platform_exit`; it does not return.
+    unsafe { __platform_exit(PANIC_EXIT_CODE) }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_report_lists_context() {
+        let location = Location::caller();
+        let context = PanicContext {
+            tid: Some(3),
+            trap: Some(TrapSnapshot {
+                mcause: 0xd,
+                mepc: 0x8000_1234,
+                mtval: 0x10,
+                from_kernel: false,
+                depth: 1,
+            }),
+        };
+        let mut out = String::new();
+        write_report(&mut out, &"index out of bounds", Some(location), &context).unwrap();
+
+        let expected = std::format!(
+            "PANIC: index out of bounds\n  at {}:{}:{}\n  tid: 3\n  \
+             trap: mcause=0xd mepc=0x80001234 mtval=0x10 from user (depth 1)\n",
+            location.file(),
+            location.line(),
+            location.column()
+        );
+        assert_eq!(out, expected);
+
+        let mut out = String::new();
+        write_report(&mut out, &"oops", None, &PanicContext::default()).unwrap();
+        assert_eq!(out, "PANIC: oops\n");
+    }
+}
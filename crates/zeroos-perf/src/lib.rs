@@ -0,0 +1,211 @@
+//! Cycle-counter instrumentation.
+//!
+//! Bracket a region with [`begin`] and [`end`] (or wrap it in [`measure`]) to charge the cycles
+//! it takes, as read from `__platform_cycle_count`, to a label. Counters aggregate calls, total,
+//! minimum and maximum per label; [`dump`] writes them to the debug console.
+//!
+//! A label that is begun again before it ends nests: only the outermost pair is counted.
+//! Counters are global, so concurrent threads using the same label share one.
+
+#![no_std]
+
+use foundation::kfn::time as ktime;
+use foundation::utils::GlobalCell;
+
+/// Distinct labels that can be tracked; later ones are counted as dropped.
+pub const MAX_LABELS: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Counter {
+    pub label: &'static str,
+    /// Completed outermost `begin`/`end` pairs.
+    pub calls: u64,
+    pub total: u64,
+    pub min: u64,
+    pub max: u64,
+    start: u64,
+    depth: u32,
+}
+
+impl Counter {
+    const fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            calls: 0,
+            total: 0,
+            min: u64::MAX,
+            max: 0,
+            start: 0,
+            depth: 0,
+        }
+    }
+}
+
+pub struct Perf {
+    counters: [Option<Counter>; MAX_LABELS],
+    /// `begin` calls for labels that did not fit.
+    dropped: u64,
+}
+
+impl Perf {
+    pub const fn new() -> Self {
+        Self {
+            counters: [None; MAX_LABELS],
+            dropped: 0,
+        }
+    }
+
+    fn slot(&mut self, label: &'static str) -> Option<&mut Counter> {
+        // Labels are usually string literals: compare pointers before contents.
+        let idx = self.counters.iter().position(|c| match c {
+            Some(c) => core::ptr::eq(c.label, label) || c.label == label,
+            None => true,
+        })?;
+        Some(self.counters[idx].get_or_insert(Counter::new(label)))
+    }
+
+    pub fn begin(&mut self, label: &'static str, now: u64) {
+        match self.slot(label) {
+            Some(c) => {
+                if c.depth == 0 {
+                    c.start = now;
+                }
+                c.depth += 1;
+            }
+            None => self.dropped += 1,
+        }
+    }
+
+    /// Unmatched `end`s are ignored.
+    pub fn end(&mut self, label: &'static str, now: u64) {
+        let Some(c) = self.slot(label) else {
+            return;
+        };
+        if c.depth == 0 {
+            return;
+        }
+        c.depth -= 1;
+        if c.depth == 0 {
+            let elapsed = now.wrapping_sub(c.start);
+            c.calls += 1;
+            c.total += elapsed;
+            c.min = c.min.min(elapsed);
+            c.max = c.max.max(elapsed);
+        }
+    }
+
+    pub fn get(&self, label: &str) -> Option<&Counter> {
+        self.iter().find(|c| c.label == label)
+    }
+
+    /// Counters in the order their labels were first seen.
+    pub fn iter(&self) -> impl Iterator<Item = &Counter> {
+        self.counters.iter().flatten()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl Default for Perf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static PERF: GlobalCell<Perf> = GlobalCell::new(Perf::new());
+
+/// Start charging cycles to `label`.
+#[inline]
+pub fn begin(label: &'static str) {
+    let now = ktime::kcycles();
+    PERF.with_mut(|p| p.begin(label, now));
+}
+
+/// Stop charging cycles to `label`.
+#[inline]
+pub fn end(label: &'static str) {
+    let now = ktime::kcycles();
+    PERF.with_mut(|p| p.end(label, now));
+}
+
+/// Run `f` between `begin(label)` and `end(label)`.
+#[inline]
+pub fn measure<R>(label: &'static str, f: impl FnOnce() -> R) -> R {
+    begin(label);
+    let r = f();
+    end(label);
+    r
+}
+
+/// Copy of the counter for `label`, if it was ever begun.
+pub fn counter(label: &str) -> Option<Counter> {
+    PERF.with(|p| p.get(label).copied())
+}
+
+/// Forget every counter.
+pub fn reset() {
+    PERF.with_mut(|p| p.reset());
+}
+
+/// Write a report of every counter with completed calls to the debug console.
+pub fn dump() {
+    PERF.with(|p| {
+        debug::writeln!("[perf] label calls total min avg max (cycles)");
+        for c in p.iter().filter(|c| c.calls != 0) {
+            debug::writeln!(
+                "[perf] {} {} {} {} {} {}",
+                c.label,
+                c.calls,
+                c.total,
+                c.min,
+                c.total / c.calls,
+                c.max
+            );
+        }
+        if p.dropped() != 0 {
+            debug::writeln!("[perf] {} begins on untracked labels", p.dropped());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregates_outermost_pairs() {
+        let mut p = Perf::new();
+        p.begin("a", 10);
+        p.begin("a", 12);
+        p.end("a", 15);
+        p.end("a", 30);
+        p.begin("a", 100);
+        p.end("a", 105);
+        p.end("a", 200);
+
+        let a = p.get("a").unwrap();
+        assert_eq!((a.calls, a.total, a.min, a.max), (2, 25, 5, 20));
+        assert!(p.get("b").is_none());
+    }
+
+    #[test]
+    fn test_labels_beyond_capacity_are_dropped() {
+        const NAMES: &str = "abcdefghijklmnopqrstuvwxyzABCDEFG";
+        let mut p = Perf::new();
+        for i in 0..=MAX_LABELS {
+            let label = &NAMES[i..=i];
+            p.begin(label, 0);
+            p.end(label, 1);
+        }
+        assert_eq!(p.iter().count(), MAX_LABELS);
+        assert_eq!(p.dropped(), 1);
+        p.reset();
+        assert_eq!(p.iter().count(), 0);
+    }
+}
@@ -2,8 +2,10 @@
 
 mod lock_override;
 mod stack;
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+mod tls;
 
-pub use stack::build_musl_stack;
+pub use stack::{build_musl_stack, set_startup_info, startup_info, StartupInfo};
 
 // Re-export unified backtrace interface for public API
 pub use zeroos_backtrace::{Backtrace, BacktraceCapture};
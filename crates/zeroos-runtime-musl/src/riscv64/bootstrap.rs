@@ -1,4 +1,4 @@
-use crate::build_musl_stack;
+use crate::{build_musl_stack, startup_info};
 use core::arch::naked_asm;
 
 use foundation::__main_entry;
@@ -21,8 +21,6 @@ pub extern "C" fn _init() {}
 #[no_mangle]
 pub extern "C" fn _fini() {}
 
-static PROGRAM_NAME: &[u8] = b"zerokernel\0";
-
 #[no_mangle]
 extern "C" fn __boot_trace_runtime() {
     debug::writeln!("[BOOT] __runtime_bootstrap");
@@ -40,7 +38,7 @@ unsafe fn build_musl_in_buffer() -> usize {
     let buffer_bottom = buffer_ptr as usize;
     let buffer_top = buffer_ptr.add(MUSL_BUFFER_SIZE) as usize;
 
-    let size = build_musl_stack(buffer_top, buffer_bottom, PROGRAM_NAME);
+    let size = build_musl_stack(buffer_top, buffer_bottom, &startup_info());
 
     if size > MUSL_BUFFER_BYTES {
         panic!(
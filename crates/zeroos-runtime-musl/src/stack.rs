@@ -1,3 +1,5 @@
+use foundation::utils::GlobalCell;
+
 const AT_NULL: usize = 0;
 const AT_PHDR: usize = 3;
 const AT_PHENT: usize = 4;
@@ -13,6 +15,62 @@ const AT_SECURE: usize = 23;
 const AT_RANDOM: usize = 25;
 const AT_HWCAP: usize = 16;
 
+/// Process startup values the platform hands to the guest on its initial stack.
+///
+/// Strings are referenced, not copied, so they must be NUL-terminated and live for the whole run.
+#[derive(Clone, Copy, Debug)]
+pub struct StartupInfo {
+    /// `argv` strings; `argv[0]` is the program name.
+    pub argv: &'static [&'static [u8]],
+    /// `KEY=VALUE` strings for `envp`.
+    pub envp: &'static [&'static [u8]],
+    /// The 16 bytes behind `AT_RANDOM` (musl seeds the stack protector from them). When `None`
+    /// they are derived from the stack address, which is deterministic.
+    pub random: Option<[u8; 16]>,
+    /// `(AT_PHDR, AT_PHENT, AT_PHNUM)`. When `None` on RISC-V, a synthesized `PT_TLS` header is
+    /// used so musl still finds the TLS template.
+    pub phdr: Option<(usize, usize, usize)>,
+    pub page_size: usize,
+}
+
+impl StartupInfo {
+    pub const fn new() -> Self {
+        Self {
+            argv: &[b"zerokernel\0"],
+            envp: &[],
+            random: None,
+            phdr: None,
+            page_size: 4096,
+        }
+    }
+}
+
+impl Default for StartupInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static STARTUP: GlobalCell<StartupInfo> = GlobalCell::new(StartupInfo::new());
+
+/// Set the values `__runtime_bootstrap` places on the initial stack.
+///
+/// Call from `__platform_bootstrap`; later calls have no effect on the running program.
+pub fn set_startup_info(info: StartupInfo) {
+    for s in info.argv.iter().chain(info.envp) {
+        assert!(
+            s.last() == Some(&0),
+            "startup strings must be NUL-terminated"
+        );
+    }
+    STARTUP.with_mut(|slot| *slot = info);
+}
+
+/// The values the initial stack is (or will be) built from.
+pub fn startup_info() -> StartupInfo {
+    STARTUP.with(|slot| *slot)
+}
+
 struct DownwardStack<T> {
     sp: usize,
     #[cfg(feature = "bounds-checks")]
@@ -129,15 +187,13 @@ fn generate_random_bytes(entropy: &[u64]) -> (u64, u64) {
     (random_low, random_high)
 }
 
+/// Build `argc`, `argv`, `envp` and `auxv` below `stack_top`, returning the bytes used.
+///
 /// The stack layout follows the System V ABI and Linux kernel conventions.
 /// # Safety
-/// Caller must ensure:
+/// Caller must ensure `[stack_bottom, stack_top)` is writable and `stack_top` is 16-byte aligned.
 #[inline]
-pub unsafe fn build_musl_stack(
-    stack_top: usize,
-    stack_bottom: usize,
-    program_name: &'static [u8],
-) -> usize {
+pub unsafe fn build_musl_stack(stack_top: usize, stack_bottom: usize, info: &StartupInfo) -> usize {
     let mut ds = DownwardStack::<usize>::new(stack_top, stack_bottom);
 
     // Pre-populate RUST_BACKTRACE environment variable for DWARF mode.
@@ -160,9 +216,14 @@ pub unsafe fn build_musl_stack(
     let rust_backtrace_ptr =
         ds.push_bytes_aligned(b"RUST_BACKTRACE=full\0", core::mem::align_of::<usize>());
 
-    // In ZeroOS we run as a single static image with no dynamic loader; musl startup does not
-    // require AT_PHDR/AT_PHNUM/AT_PHENT/AT_ENTRY for correctness, so we set them to 0.
-    let (at_phdr, at_phent, at_phnum, at_entry) = (0usize, 0usize, 0usize, 0usize);
+    // In ZeroOS we run as a single static image with no dynamic loader. musl only scans the
+    // program headers for PT_TLS, so AT_PHDR points at a synthesized TLS entry unless the
+    // platform supplies real headers; AT_ENTRY stays 0.
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    let (at_phdr, at_phent, at_phnum) = info.phdr.unwrap_or_else(crate::tls::auxv_phdr);
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    let (at_phdr, at_phent, at_phnum) = info.phdr.unwrap_or((0, 0, 0));
+    let at_entry = 0usize;
 
     // Prepare auxiliary vector entries
     let auxv_entries = [
@@ -170,7 +231,7 @@ pub unsafe fn build_musl_stack(
         (AT_PHENT, at_phent),
         (AT_PHNUM, at_phnum),
         (AT_ENTRY, at_entry),
-        (AT_PAGESZ, 4096),
+        (AT_PAGESZ, info.page_size),
         (AT_CLKTCK, 100),
         (AT_HWCAP, 0),
         (AT_UID, 0),
@@ -182,11 +243,18 @@ pub unsafe fn build_musl_stack(
         (AT_NULL, 0),
     ];
 
-    // Generate 16 bytes for AT_RANDOM (Linux kernel standard)
+    // 16 bytes for AT_RANDOM (Linux kernel standard)
     // Musl's __init_ssp uses first sizeof(uintptr_t) bytes for stack canary
-
-    let entropy = [stack_top as u64, 0xdeadbeef_cafebabe_u64];
-    let (random_low, random_high) = generate_random_bytes(&entropy);
+    let (random_low, random_high) = match info.random {
+        Some(bytes) => {
+            let (lo, hi) = bytes.split_at(8);
+            (
+                u64::from_le_bytes(lo.try_into().unwrap()),
+                u64::from_le_bytes(hi.try_into().unwrap()),
+            )
+        }
+        None => generate_random_bytes(&[stack_top as u64, 0xdeadbeef_cafebabe_u64]),
+    };
 
     // Memory layout after pushes (stack grows downward, lower addresses at bottom):
 
@@ -205,6 +273,20 @@ pub unsafe fn build_musl_stack(
 
     let at_random_ptr = ds.sp();
 
+    // Pad so the total size stays a multiple of 16: the bootstrap copies it below a 16-byte
+    // aligned sp. Remaining words: argc, argv + NULL, envp + NULL, auxv pairs.
+    let word = core::mem::size_of::<usize>();
+    let words = 1
+        + info.argv.len()
+        + 1
+        + info.envp.len()
+        + usize::from(cfg!(zeroos_backtrace = "dwarf"))
+        + 1
+        + 2 * auxv_entries.len();
+    while !((stack_top - ds.sp()) / word + words).is_multiple_of(16 / word) {
+        ds.push(0);
+    }
+
     for &(key, val) in auxv_entries.iter().rev() {
         let eff_val = if key == AT_RANDOM { at_random_ptr } else { val };
         ds.push(eff_val);
@@ -213,15 +295,20 @@ pub unsafe fn build_musl_stack(
 
     // envp terminator (always present)
     ds.push(0);
-    // envp[0] (optional, only in DWARF mode)
+    // envp[n] (only in DWARF mode)
     #[cfg(zeroos_backtrace = "dwarf")]
     ds.push(rust_backtrace_ptr);
+    for env in info.envp.iter().rev() {
+        ds.push(env.as_ptr() as usize);
+    }
 
     // argv terminator
     ds.push(0);
-    ds.push(program_name.as_ptr() as usize);
+    for arg in info.argv.iter().rev() {
+        ds.push(arg.as_ptr() as usize);
+    }
 
-    ds.push(1);
+    ds.push(info.argv.len());
 
     stack_top - ds.sp()
 }
@@ -230,48 +317,93 @@ pub unsafe fn build_musl_stack(
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_build_musl_stack_alignment() {
-        let stack_buffer = vec![0u8; 4096];
-        let stack_top = (stack_buffer.as_ptr() as usize) + stack_buffer.len();
-
-        let program_name = b"test\0";
+    const WORD: usize = core::mem::size_of::<usize>();
 
-        unsafe {
-            let new_sp = build_musl_stack(stack_top, stack_top - 4096, program_name);
+    /// Build into `buf` and return `(sp, used bytes)`.
+    fn build(buf: &mut [usize; 256], info: &StartupInfo) -> (usize, usize) {
+        let bottom = buf.as_mut_ptr() as usize;
+        let top = (bottom + buf.len() * WORD) & !0xf;
+        let used = unsafe { build_musl_stack(top, bottom, info) };
+        (top - used, used)
+    }
 
-            assert_eq!(new_sp % 16, 0, "Stack pointer must be 16-byte aligned");
+    fn word_at(sp: usize, index: usize) -> usize {
+        unsafe { *((sp + index * WORD) as *const usize) }
+    }
 
-            assert!(new_sp < stack_top, "Stack pointer must move downward");
+    #[test]
+    fn test_build_musl_stack_alignment() {
+        let mut buf = [0usize; 256];
+        for argv in [
+            &[b"a\0" as &[u8]][..],
+            &[b"a\0", b"b\0"],
+            &[b"a\0", b"b\0", b"c\0"],
+        ] {
+            let info = StartupInfo {
+                argv,
+                ..StartupInfo::new()
+            };
+            let (sp, used) = build(&mut buf, &info);
+            assert_eq!(used % 16, 0, "stack size must keep sp 16-byte aligned");
+            assert_eq!(sp % 16, 0);
         }
     }
 
     #[test]
     fn test_build_musl_stack_argc_argv() {
-        let stack_buffer = vec![0u8; 4096];
-        let stack_top = (stack_buffer.as_ptr() as usize) + stack_buffer.len();
-
-        let program_name = b"myprogram\0";
+        let mut buf = [0usize; 256];
+        let (sp, _) = build(&mut buf, &StartupInfo::new());
+
+        assert_eq!(word_at(sp, 0), 1, "argc must be 1");
+        assert_eq!(
+            word_at(sp, 1),
+            StartupInfo::new().argv[0].as_ptr() as usize,
+            "argv[0] must point to program name"
+        );
+        assert_eq!(word_at(sp, 2), 0, "argv[1] must be NULL");
+    }
 
-        unsafe {
-            let new_sp = build_musl_stack(stack_top, stack_top - 4096, program_name);
-
-            let argc_ptr = new_sp as *const usize;
-            let argc = *argc_ptr;
-            assert_eq!(argc, 1, "argc must be 1");
-
-            let argv_ptr = (new_sp + core::mem::size_of::<usize>()) as *const *const u8;
-            let argv0 = *argv_ptr;
-            assert_eq!(
-                argv0,
-                program_name.as_ptr(),
-                "argv[0] must point to program name"
-            );
-
-            let argv1_ptr = (new_sp + 2 * core::mem::size_of::<usize>()) as *const usize;
-            let argv1 = *argv1_ptr;
-            assert_eq!(argv1, 0, "argv[1] must be NULL");
+    #[test]
+    fn test_build_musl_stack_envp_auxv() {
+        static ARGV: [&[u8]; 2] = [b"prog\0", b"--flag\0"];
+        static ENVP: [&[u8]; 1] = [b"KEY=value\0"];
+        let info = StartupInfo {
+            argv: &ARGV,
+            envp: &ENVP,
+            random: Some(*b"0123456789abcdef"),
+            phdr: Some((0x1000, 56, 3)),
+            page_size: 8192,
+        };
+        let mut buf = [0usize; 256];
+        let (sp, _) = build(&mut buf, &info);
+
+        assert_eq!(word_at(sp, 0), 2);
+        assert_eq!(word_at(sp, 2), ARGV[1].as_ptr() as usize);
+        assert_eq!(word_at(sp, 3), 0);
+        assert_eq!(word_at(sp, 4), ENVP[0].as_ptr() as usize);
+        let mut i = 5;
+        if cfg!(zeroos_backtrace = "dwarf") {
+            i += 1;
+        }
+        assert_eq!(word_at(sp, i), 0, "envp must be NULL-terminated");
+
+        let mut auxv = [0usize; 32];
+        i += 1;
+        loop {
+            let (key, val) = (word_at(sp, i), word_at(sp, i + 1));
+            if key == AT_NULL {
+                break;
+            }
+            auxv[key] = val;
+            i += 2;
         }
+        assert_eq!(auxv[AT_PAGESZ], 8192);
+        assert_eq!(
+            (auxv[AT_PHDR], auxv[AT_PHENT], auxv[AT_PHNUM]),
+            (0x1000, 56, 3)
+        );
+        let random = unsafe { core::slice::from_raw_parts(auxv[AT_RANDOM] as *const u8, 16) };
+        assert_eq!(random, b"0123456789abcdef");
     }
 
     #[test]
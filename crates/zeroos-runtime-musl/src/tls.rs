@@ -0,0 +1,60 @@
+//! A one-entry program header table describing the TLS template.
+//!
+//! The image is loaded without its ELF headers, so musl's static TLS setup would otherwise see no
+//! `PT_TLS` and size every thread's block for zero thread-locals. Pointing `AT_PHDR` here lets
+//! `__init_tls` and `pthread_create` copy `.tdata`/`.tbss` like they do on Linux.
+
+const PT_TLS: u32 = 7;
+const PF_R: u32 = 4;
+
+#[cfg(target_pointer_width = "64")]
+#[repr(C)]
+struct Phdr {
+    p_type: u32,
+    p_flags: u32,
+    p_offset: u64,
+    p_vaddr: u64,
+    p_paddr: u64,
+    p_filesz: u64,
+    p_memsz: u64,
+    p_align: u64,
+}
+
+#[cfg(target_pointer_width = "32")]
+#[repr(C)]
+struct Phdr {
+    p_type: u32,
+    p_offset: u32,
+    p_vaddr: u32,
+    p_paddr: u32,
+    p_filesz: u32,
+    p_memsz: u32,
+    p_flags: u32,
+    p_align: u32,
+}
+
+static mut TLS_PHDR: Phdr = Phdr {
+    p_type: PT_TLS,
+    p_flags: PF_R,
+    p_offset: 0,
+    p_vaddr: 0,
+    p_paddr: 0,
+    p_filesz: 0,
+    p_memsz: 0,
+    p_align: 0,
+};
+
+/// `(AT_PHDR, AT_PHENT, AT_PHNUM)` for the initial auxiliary vector.
+pub(crate) fn auxv_phdr() -> (usize, usize, usize) {
+    let image = foundation::utils::TlsImage::discover();
+    let phdr = core::ptr::addr_of_mut!(TLS_PHDR);
+    // SAFETY: boot is single-threaded and musl only reads the table after this returns.
+    unsafe {
+        (*phdr).p_vaddr = image.init as _;
+        (*phdr).p_paddr = image.init as _;
+        (*phdr).p_filesz = image.init_len as _;
+        (*phdr).p_memsz = image.mem_len as _;
+        (*phdr).p_align = image.align as _;
+    }
+    (phdr as usize, core::mem::size_of::<Phdr>(), 1)
+}
@@ -12,9 +12,9 @@ cfg_if! {
 }
 
 cfg_if! {
-    if #[cfg(target_arch = "riscv64")] {
-        pub mod riscv64;
-        pub use riscv64::__runtime_bootstrap;
+    if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
+        pub mod riscv;
+        pub use riscv::__runtime_bootstrap;
     }
 }
 
@@ -3,9 +3,28 @@
 extern crate alloc;
 
 pub mod ops;
+pub mod policy;
+#[cfg(feature = "preempt")]
+pub mod preempt;
+pub mod robust;
 pub mod scheduler;
+#[cfg(target_os = "none")]
+pub mod spawn;
+pub mod stack;
+pub mod stats;
+pub mod table;
 pub mod thread;
+pub mod timer;
 
-pub use ops::SCHEDULER_OPS;
-pub use scheduler::{Scheduler, MAX_THREADS};
+pub use ops::{set_max_threads, set_priority, set_stack_size, set_tick_source, SCHEDULER_OPS};
+pub use policy::{Priority, DEFAULT_PRIORITY};
+pub use scheduler::{Scheduler, MAX_THREADS, TICK_NS};
+pub use stack::{DEFAULT_THREAD_STACK_SIZE, STACK_GUARD_SIZE};
+#[cfg(feature = "stats")]
+pub use stats::dump as dump_stats;
+pub use stats::{stats, ThreadStats};
 pub use thread::{ThreadControlBlock, ThreadState, Tid};
+pub use timer::TickSource;
+
+#[cfg(target_os = "none")]
+pub use spawn::{spawn, try_spawn, yield_now, JoinHandle, DEFAULT_STACK_SIZE};
@@ -1,7 +1,7 @@
 use crate::scheduler::Scheduler;
 
 // Standard EPERM (Operation not permitted) value for ABI compatibility.
-use libc::EPERM;
+use foundation::error::errno::{EPERM, ESRCH};
 
 pub fn init() -> usize {
     Scheduler::init()
@@ -14,29 +14,34 @@ pub fn spawn_thread(
     child_tid_ptr: usize,
     clear_child_tid_ptr: usize,
 ) -> isize {
-    Scheduler::with_mut(|scheduler| {
-        // Recover the current trap frame from the current thread's kernel stack.
-        // This avoids threading trap-dispatch details through the scheduler API.
-        let parent_frame_ptr = foundation::kfn::arch::kcurrent_trap_frame() as usize;
-        let mepc =
-            unsafe { foundation::kfn::arch::ktrap_frame_get_pc(parent_frame_ptr as *const u8) };
+    // The TCB, stack and TLS block are allocated while the scheduler is borrowed, where
+    // parking on the allocator lock would re-enter it.
+    foundation::kfn::memory::kalloc_in_critical_section(|| {
+        Scheduler::with_mut(|scheduler| {
+            // Recover the current trap frame from the current thread's kernel stack.
+            // This avoids threading trap-dispatch details through the scheduler API.
+            let parent_frame_ptr = foundation::kfn::arch::kcurrent_trap_frame() as usize;
+            let mepc =
+                unsafe { foundation::kfn::arch::ktrap_frame_get_pc(parent_frame_ptr as *const u8) };
 
-        let tid = scheduler.spawn_thread(parent_frame_ptr, stack, tls, clear_child_tid_ptr, mepc);
+            let tid =
+                scheduler.spawn_thread(parent_frame_ptr, stack, tls, clear_child_tid_ptr, mepc);
 
-        if tid > 0 {
-            let tid = tid as i32;
-            unsafe {
-                if parent_tid_ptr != 0 {
-                    (parent_tid_ptr as *mut i32).write_volatile(tid);
-                }
-                if child_tid_ptr != 0 {
-                    (child_tid_ptr as *mut i32).write_volatile(tid);
+            if tid > 0 {
+                let tid = tid as i32;
+                unsafe {
+                    if parent_tid_ptr != 0 {
+                        (parent_tid_ptr as *mut i32).write_volatile(tid);
+                    }
+                    if child_tid_ptr != 0 {
+                        (child_tid_ptr as *mut i32).write_volatile(tid);
+                    }
                 }
+                // Parent return value is handled by syscall dispatch (`set_ret`), so no direct patching needed.
             }
-            // Parent return value is handled by syscall dispatch (`set_ret`), so no direct patching needed.
-        }
 
-        tid
+            tid
+        })
     })
     .unwrap_or(-EPERM as isize)
 }
@@ -46,6 +51,11 @@ pub fn yield_now() -> isize {
     0
 }
 
+/// Timer interrupt: the current thread's quantum is used up.
+pub fn preempt() {
+    Scheduler::with_mut(|scheduler| scheduler.yield_now());
+}
+
 pub fn exit_current(code: i32) -> isize {
     Scheduler::with_mut(|scheduler| scheduler.exit_current_and_yield(code))
         .unwrap_or_else(|| foundation::kfn::kexit(code))
@@ -70,6 +80,38 @@ pub fn wake_on_addr(addr: usize, count: usize) -> usize {
     Scheduler::with_mut(|scheduler| scheduler.wake_on_addr(addr, count)).unwrap_or(0)
 }
 
+#[inline(always)]
+pub fn wait_on_addr_timeout(addr: usize, val: i32, timeout_ns: u64) -> isize {
+    Scheduler::with_mut(|scheduler| scheduler.wait_on_addr_timeout(addr, val, timeout_ns))
+        .unwrap_or(0)
+}
+
+/// The scheduler clock in nanoseconds: its tick times [`crate::TICK_NS`].
+pub fn now_ns() -> u64 {
+    Scheduler::with_mut(|scheduler| scheduler.tick())
+        .unwrap_or(0)
+        .saturating_mul(crate::TICK_NS)
+}
+
+#[inline(always)]
+pub fn requeue_on_addr(
+    addr: usize,
+    wake_count: usize,
+    addr2: usize,
+    requeue_count: usize,
+) -> usize {
+    Scheduler::with_mut(|scheduler| {
+        scheduler.requeue_on_addr(addr, wake_count, addr2, requeue_count)
+    })
+    .unwrap_or(0)
+}
+
+/// Wait for thread `tid` to exit (see [`Scheduler::join_thread`]).
+pub fn join(tid: usize, status_ptr: usize, nohang: bool) -> isize {
+    Scheduler::with_mut(|scheduler| scheduler.join_thread(tid, status_ptr, nohang))
+        .unwrap_or(-(ESRCH as isize))
+}
+
 pub fn set_tid_address(tidptr: usize) -> isize {
     Scheduler::with_mut(|scheduler| {
         if let Some(tcb) = scheduler.current_thread() {
@@ -84,14 +126,73 @@ pub fn set_tid_address(tidptr: usize) -> isize {
     .unwrap_or(0)
 }
 
+pub fn set_robust_list(head: usize) -> isize {
+    Scheduler::with_mut(|scheduler| {
+        if let Some(tcb) = scheduler.current_thread() {
+            unsafe { (*tcb.as_ptr()).robust_list = head };
+        }
+        0
+    })
+    .unwrap_or(0)
+}
+
+/// Robust futex list head of thread `tid`.
+pub fn robust_list(tid: usize) -> isize {
+    Scheduler::with_mut(|scheduler| scheduler.robust_list(tid))
+        .flatten()
+        .map_or(-(ESRCH as isize), |head| head as isize)
+}
+
+/// Set the scheduling priority of thread `tid` (see [`crate::policy`]).
+pub fn set_priority(tid: usize, priority: crate::policy::Priority) -> isize {
+    Scheduler::with_mut(|scheduler| {
+        if scheduler.set_priority(tid, priority) {
+            0
+        } else {
+            -(ESRCH as isize)
+        }
+    })
+    .unwrap_or(-EPERM as isize)
+}
+
+/// Set the stack size for threads spawned without a caller-provided stack.
+pub fn set_stack_size(size: usize) -> isize {
+    Scheduler::with_mut(|scheduler| scheduler.set_stack_size(size))
+        .map(|()| 0)
+        .unwrap_or(-EPERM as isize)
+}
+
+/// Limit the number of threads, exited ones not yet reaped included (default
+/// [`crate::MAX_THREADS`]).
+pub fn set_max_threads(max: usize) -> isize {
+    Scheduler::with_mut(|scheduler| scheduler.set_max_threads(max))
+        .map(|()| 0)
+        .unwrap_or(-EPERM as isize)
+}
+
+/// Advance the clock futex timeouts and sleeps are measured against by `source`, in ticks of
+/// [`crate::TICK_NS`], instead of one tick per scheduling decision (see [`crate::timer`]).
+pub fn set_tick_source(source: crate::timer::TickSource) -> isize {
+    Scheduler::with_mut(|scheduler| scheduler.set_tick_source(Some(source)))
+        .map(|()| 0)
+        .unwrap_or(-EPERM as isize)
+}
+
 pub const SCHEDULER_OPS: foundation::ops::SchedulerOps = foundation::ops::SchedulerOps {
     init,
     spawn_thread,
     yield_now,
+    preempt,
     exit_current,
     current_tid,
     thread_count,
     wait_on_addr,
     wake_on_addr,
+    wait_on_addr_timeout,
+    now_ns,
+    requeue_on_addr,
+    join,
     set_clear_on_exit_addr: set_tid_address,
+    set_robust_list,
+    robust_list,
 };
@@ -0,0 +1,159 @@
+//! Next-thread selection policy, chosen at build time.
+//!
+//! - default: round-robin over ready threads.
+//! - `policy-priority`: strict priority; the highest `priority` ready thread runs, round-robin
+//!   among equals.
+//! - `policy-weighted`: weighted round-robin; each ready thread gets `priority + 1` turns per
+//!   round.
+
+use core::ptr::NonNull;
+
+use crate::thread::{ThreadControlBlock, ThreadState};
+
+zeroos_macros::require_at_most_one_feature!("policy-priority", "policy-weighted");
+
+/// Scheduling priority; larger runs first (or more often).
+pub type Priority = u8;
+
+pub const DEFAULT_PRIORITY: Priority = 0;
+
+/// Indices `start, start+1, .., count-1, 0, .., start-1`.
+#[inline]
+fn rotation(start: usize, count: usize) -> impl Iterator<Item = usize> {
+    (start..count).chain(0..start.min(count))
+}
+
+#[inline]
+fn ready<'a>(slot: Option<NonNull<ThreadControlBlock>>) -> Option<&'a mut ThreadControlBlock> {
+    // SAFETY: scheduler slots hold live TCB pointers owned by the scheduler, which is the only
+    // code touching them while a policy runs.
+    let tcb = unsafe { &mut *slot?.as_ptr() };
+    (tcb.state == ThreadState::Ready).then_some(tcb)
+}
+
+#[allow(dead_code)]
+fn round_robin(threads: &[Option<NonNull<ThreadControlBlock>>], start: usize) -> Option<usize> {
+    rotation(start, threads.len()).find(|&i| ready(threads[i]).is_some())
+}
+
+#[allow(dead_code)]
+fn strict_priority(threads: &[Option<NonNull<ThreadControlBlock>>], start: usize) -> Option<usize> {
+    let mut best: Option<(usize, Priority)> = None;
+    for i in rotation(start, threads.len()) {
+        if let Some(tcb) = ready(threads[i]) {
+            if best.is_none_or(|(_, p)| tcb.priority > p) {
+                best = Some((i, tcb.priority));
+            }
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+#[allow(dead_code)]
+fn weighted(threads: &[Option<NonNull<ThreadControlBlock>>], start: usize) -> Option<usize> {
+    let pick = |i: usize| {
+        let tcb = ready(threads[i])?;
+        if tcb.credits == 0 {
+            return None;
+        }
+        tcb.credits -= 1;
+        Some(i)
+    };
+    if let Some(i) = rotation(start, threads.len()).find_map(pick) {
+        return Some(i);
+    }
+
+    // Round over: refill every ready thread and start the next one.
+    let mut any = false;
+    for &slot in threads {
+        if let Some(tcb) = ready(slot) {
+            tcb.credits = tcb.priority as u32 + 1;
+            any = true;
+        }
+    }
+    if !any {
+        return None;
+    }
+    rotation(start, threads.len()).find_map(pick)
+}
+
+/// Pick the next thread to run among `threads`, scanning from `start`.
+#[inline]
+pub(crate) fn select(
+    threads: &[Option<NonNull<ThreadControlBlock>>],
+    start: usize,
+) -> Option<usize> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "policy-priority")] {
+            strict_priority(threads, start)
+        } else if #[cfg(feature = "policy-weighted")] {
+            weighted(threads, start)
+        } else {
+            round_robin(threads, start)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    struct Table(Vec<ThreadControlBlock>);
+
+    impl Table {
+        fn new(spec: &[(ThreadState, Priority)]) -> Self {
+            Self(
+                spec.iter()
+                    .enumerate()
+                    .map(|(i, &(s, p))| ThreadControlBlock::stub(i + 1, s, p))
+                    .collect(),
+            )
+        }
+
+        fn slots(&mut self) -> Vec<Option<NonNull<ThreadControlBlock>>> {
+            self.0.iter_mut().map(|t| Some(NonNull::from(t))).collect()
+        }
+    }
+
+    use ThreadState::{Blocked, Ready};
+
+    #[test]
+    fn test_round_robin_skips_blocked() {
+        let mut t = Table::new(&[(Ready, 0), (Blocked, 0), (Ready, 0)]);
+        let slots = t.slots();
+        assert_eq!(round_robin(&slots, 1), Some(2));
+        assert_eq!(round_robin(&slots, 3), Some(0));
+    }
+
+    #[test]
+    fn test_strict_priority_prefers_highest_then_rotation() {
+        let mut t = Table::new(&[(Ready, 1), (Ready, 5), (Ready, 5), (Blocked, 9)]);
+        let slots = t.slots();
+        assert_eq!(strict_priority(&slots, 0), Some(1));
+        assert_eq!(strict_priority(&slots, 2), Some(2));
+    }
+
+    #[test]
+    fn test_weighted_shares_turns_by_priority() {
+        let mut t = Table::new(&[(Ready, 2), (Ready, 0)]);
+        let slots = t.slots();
+        let mut turns = [0usize; 2];
+        let mut start = 0;
+        for _ in 0..8 {
+            let i = weighted(&slots, start).unwrap();
+            turns[i] += 1;
+            start = (i + 1) % slots.len();
+        }
+        assert_eq!(turns, [6, 2]);
+    }
+
+    #[test]
+    fn test_no_ready_thread() {
+        let mut t = Table::new(&[(Blocked, 0), (Blocked, 3)]);
+        let slots = t.slots();
+        assert_eq!(round_robin(&slots, 0), None);
+        assert_eq!(strict_priority(&slots, 0), None);
+        assert_eq!(weighted(&slots, 0), None);
+    }
+}
@@ -0,0 +1,21 @@
+//! Timer-driven preemption (feature `preempt`).
+//!
+//! The platform programs its machine timer through `__platform_timer_arm`; when the timer fires,
+//! its trap handler calls `foundation::kfn::scheduler::kpreempt()`, which forces a yield. Every
+//! scheduling decision re-arms the timer, so each thread starts with a full quantum.
+
+extern "C" {
+    /// Raise a machine timer interrupt `ticks` platform timer ticks from now (e.g. by writing
+    /// `mtimecmp = mtime + ticks`).
+    fn __platform_timer_arm(ticks: u64);
+}
+
+/// Platform timer ticks a thread may run before it is preempted.
+pub const QUANTUM_TICKS: u64 = 10_000;
+
+#[inline]
+pub(crate) fn arm() {
+    // SAFETY: the platform provides this symbol whenever preemption is enabled; it only touches
+    // timer registers.
+    unsafe { __platform_timer_arm(QUANTUM_TICKS) }
+}
@@ -0,0 +1,127 @@
+//! Robust futex list release on thread exit.
+//!
+//! A thread registers a `struct robust_list_head` with `set_robust_list`; libc links every robust
+//! mutex the thread holds into it. When the thread exits, each lock word still holding its tid
+//! is marked `FUTEX_OWNER_DIED` and one waiter is woken, so the next locker gets `EOWNERDEAD`
+//! instead of sleeping forever. Values follow the Linux uapi (`linux/futex.h`).
+
+/// Some thread is blocked on the lock.
+pub const FUTEX_WAITERS: u32 = 0x8000_0000;
+/// The owner exited while holding the lock.
+pub const FUTEX_OWNER_DIED: u32 = 0x4000_0000;
+pub const FUTEX_TID_MASK: u32 = 0x3fff_ffff;
+
+/// Most list entries walked, which also bounds a corrupted, cyclic list.
+pub const ROBUST_LIST_LIMIT: usize = 2048;
+
+/// Kernel `struct robust_list_head`.
+#[repr(C)]
+struct RobustListHead {
+    /// First entry; the list is circular through the head itself.
+    next: usize,
+    /// Offset from an entry to its lock word.
+    futex_offset: isize,
+    /// Entry being locked or unlocked when the thread died, or 0.
+    list_op_pending: usize,
+}
+
+/// Mark every lock in the list at `head` still owned by `tid`, calling `wake` for each lock
+/// word that had waiters.
+///
+/// # Safety
+/// `head` must be 0 or the robust list head registered by thread `tid`, with its entries and
+/// lock words mapped.
+pub unsafe fn release(head: usize, tid: usize, mut wake: impl FnMut(usize)) {
+    let align = core::mem::align_of::<usize>();
+    if head == 0 || !head.is_multiple_of(align) {
+        return;
+    }
+    let list = unsafe { (head as *const RobustListHead).read_volatile() };
+    // Bit 0 of an entry pointer flags a PI futex; it is not part of the address.
+    let pending = list.list_op_pending & !1;
+
+    let mut entry = list.next & !1;
+    let mut walked = 0;
+    while entry != head && entry != 0 && entry.is_multiple_of(align) {
+        if walked == ROBUST_LIST_LIMIT {
+            return;
+        }
+        // Read the link first: once the lock is released the entry may be reused.
+        let next = unsafe { (entry as *const usize).read_volatile() } & !1;
+        if entry != pending {
+            unsafe { owner_died(entry, list.futex_offset, tid, &mut wake) };
+        }
+        entry = next;
+        walked += 1;
+    }
+    if pending != 0 {
+        unsafe { owner_died(pending, list.futex_offset, tid, &mut wake) };
+    }
+}
+
+unsafe fn owner_died(entry: usize, futex_offset: isize, tid: usize, wake: &mut impl FnMut(usize)) {
+    let addr = entry.wrapping_add_signed(futex_offset);
+    if addr == 0 || !addr.is_multiple_of(core::mem::align_of::<u32>()) {
+        return;
+    }
+    let word = addr as *mut u32;
+    let value = unsafe { word.read_volatile() };
+    if (value & FUTEX_TID_MASK) as usize != tid {
+        return;
+    }
+    unsafe { word.write_volatile((value & FUTEX_WAITERS) | FUTEX_OWNER_DIED) };
+    if value & FUTEX_WAITERS != 0 {
+        wake(addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// A robust mutex as libc lays it out: list link, then the lock word.
+    #[repr(C)]
+    struct Mutex {
+        next: usize,
+        lock: u32,
+    }
+
+    #[test]
+    fn test_release_marks_owned_locks() {
+        let mut head = RobustListHead {
+            next: 0,
+            futex_offset: core::mem::offset_of!(Mutex, lock) as isize,
+            list_op_pending: 0,
+        };
+        let mut mutexes = [
+            Mutex {
+                next: 0,
+                lock: 7 | FUTEX_WAITERS,
+            },
+            Mutex { next: 0, lock: 7 },
+            Mutex {
+                next: 0,
+                lock: 9 | FUTEX_WAITERS,
+            },
+        ];
+        // Link head -> 0 -> 1 -> 2 -> head through raw pointers, as guest memory would be.
+        let head_ptr = &mut head as *mut RobustListHead;
+        let m = mutexes.as_mut_ptr();
+        let mut woken = Vec::new();
+        unsafe {
+            (*head_ptr).next = m as usize;
+            (*m).next = m.add(1) as usize;
+            (*m.add(1)).next = m.add(2) as usize;
+            (*m.add(2)).next = head_ptr as usize;
+
+            release(head_ptr as usize, 7, |addr| woken.push(addr));
+
+            assert_eq!((*m).lock, FUTEX_WAITERS | FUTEX_OWNER_DIED);
+            assert_eq!((*m.add(1)).lock, FUTEX_OWNER_DIED);
+            // Held by another thread: untouched.
+            assert_eq!((*m.add(2)).lock, 9 | FUTEX_WAITERS);
+            assert_eq!(woken, [core::ptr::addr_of!((*m).lock) as usize]);
+        }
+    }
+}
@@ -1,24 +1,50 @@
+use crate::policy::Priority;
+use crate::table::ThreadTable;
 use crate::thread::{ThreadControlBlock, ThreadState, Tid};
+use crate::timer::{Clock, TickSource, TimerWheel};
 use alloc::boxed::Box;
 use core::ptr::NonNull;
-use foundation::utils::GlobalOption;
+use foundation::utils::{GlobalCell, KOnce};
 
-use libc::{EAGAIN, EDEADLK, EPERM};
+use foundation::error::errno::{EAGAIN, EDEADLK, EPERM, ESRCH, ETIMEDOUT};
 
 use alloc::alloc::Layout;
 use foundation::kfn::arch as karch;
 
+/// Default limit on threads in the table, exited ones not yet reaped included.
 pub const MAX_THREADS: usize = 64;
 
-static SCHEDULER: GlobalOption<Scheduler> = GlobalOption::none();
+/// Virtual nanoseconds per scheduler tick.
+///
+/// The cooperative scheduler has no timer interrupt, so unless a [`TickSource`] is set, time as
+/// seen by futex timeouts advances one tick per scheduling decision (and jumps straight to the
+/// earliest deadline when every thread is blocked). See [`crate::timer`].
+pub const TICK_NS: u64 = 1_000_000;
+
+/// Exited threads whose memory one pass of `release_exited` frees. Each takes up to four frees,
+/// which stay within the allocator's deferred-free queue should another hart take the lock.
+const RELEASES_PER_TICK: usize = 2;
+
+/// Published once by `init`; the cell inside is still only touched with traps disabled.
+static SCHEDULER: KOnce<GlobalCell<Scheduler>> = KOnce::new();
 
 pub struct Scheduler {
-    pub(crate) threads: [Option<NonNull<ThreadControlBlock>>; MAX_THREADS],
-    pub(crate) thread_count: usize,
+    pub(crate) threads: ThreadTable,
+    /// Most threads the table may hold, see [`Scheduler::set_max_threads`].
+    pub(crate) max_threads: usize,
     pub(crate) current_index: usize,
     pub(crate) next_tid: Tid,
+    pub(crate) clock: Clock,
+    /// Blocked threads with a futex deadline.
+    pub(crate) timers: TimerWheel,
+    /// Size of stacks allocated for threads spawned without one.
+    pub(crate) stack_size: usize,
+    pub(crate) stack_pool: crate::stack::StackPool,
 }
 
+// SAFETY: the TCBs behind `threads` are owned by the scheduler and only reached through it.
+unsafe impl Send for Scheduler {}
+
 impl Default for Scheduler {
     fn default() -> Self {
         Self::new()
@@ -28,10 +54,14 @@ impl Default for Scheduler {
 impl Scheduler {
     pub const fn new() -> Self {
         Self {
-            threads: [None; MAX_THREADS],
-            thread_count: 0,
+            threads: ThreadTable::new(),
+            max_threads: MAX_THREADS,
             current_index: 0,
             next_tid: 1,
+            clock: Clock::new(),
+            timers: TimerWheel::new(),
+            stack_size: crate::stack::DEFAULT_THREAD_STACK_SIZE,
+            stack_pool: crate::stack::StackPool::new(),
         }
     }
 
@@ -45,7 +75,9 @@ impl Scheduler {
             panic!("kalloc_kstack(KSTACK_SIZE) failed for boot thread");
         }
 
-        SCHEDULER.set(Scheduler::new());
+        if SCHEDULER.set(GlobalCell::new(Scheduler::new())).is_err() {
+            panic!("scheduler initialized twice");
+        }
 
         Scheduler::with_mut(|scheduler| {
             // Create the boot TCB (tid=1) eagerly.
@@ -66,9 +98,21 @@ impl Scheduler {
                         state: ThreadState::Running,
                         saved_pc: 0,
                         futex_wait_addr: 0,
+                        futex_deadline: None,
+                        futex_timed_out: false,
+                        timer_next: None,
                         clear_child_tid: 0,
+                        robust_list: 0,
+                        tls_block: 0,
+                        stack_base: 0,
+                        stack_size: 0,
+                        exit_code: 0,
+                        join_waiters: 0,
                         kstack_base: anchor_ptr as usize,
                         kstack_size: crate::thread::KSTACK_SIZE,
+                        priority: crate::policy::DEFAULT_PRIORITY,
+                        credits: 0,
+                        stats: crate::stats::RunStats::new(),
                     },
                 );
             }
@@ -101,9 +145,10 @@ impl Scheduler {
             }
 
             let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(boot)) };
-            scheduler.threads[0] = Some(ptr);
-            scheduler.thread_count = 1;
-            scheduler.current_index = 0;
+            if !scheduler.threads.reserve(scheduler.max_threads) {
+                panic!("kmalloc(thread table) failed for boot thread");
+            }
+            scheduler.current_index = scheduler.threads.insert(ptr);
             scheduler.next_tid = 2;
 
             unsafe {
@@ -116,19 +161,26 @@ impl Scheduler {
 
     #[inline(always)]
     pub fn with_mut<R>(f: impl FnOnce(&mut Scheduler) -> R) -> Option<R> {
-        SCHEDULER.with_some_mut(f)
+        SCHEDULER.get().map(|scheduler| scheduler.with_mut(f))
     }
 
     pub fn current_thread(&self) -> Option<NonNull<ThreadControlBlock>> {
-        if self.current_index < self.thread_count {
-            self.threads[self.current_index]
-        } else {
-            None
-        }
+        self.threads.get(self.current_index).copied().flatten()
     }
 
+    /// Threads in the table, exited ones not yet reaped included.
     pub fn thread_count(&self) -> usize {
-        self.thread_count
+        self.threads.occupied()
+    }
+
+    /// The tick futex deadlines are measured against.
+    pub fn tick(&self) -> u64 {
+        self.clock.now()
+    }
+
+    /// Advance the clock by `source` instead of one tick per scheduling decision.
+    pub fn set_tick_source(&mut self, source: Option<TickSource>) {
+        self.clock.set_source(source);
     }
 
     pub fn current_tid_or_1(&self) -> usize {
@@ -145,10 +197,23 @@ impl Scheduler {
                 karch::kthread_ctx_set_retval((*tcb.as_ptr()).thread_ctx_ptr_mut(), 0);
             }
         }
-        if self.thread_count == 0 {
+        if self.threads.is_empty() {
             return;
         }
 
+        #[cfg(feature = "watchdog")]
+        foundation::watchdog::check(0);
+
+        if let Some(tcb) = self.current_thread() {
+            check_stack_guard(unsafe { tcb.as_ref() });
+        }
+
+        self.clock.tick();
+        self.expire_timeouts();
+        self.release_exited();
+        #[cfg(feature = "preempt")]
+        crate::preempt::arm();
+
         let current_idx = self.current_index;
 
         if let Some(current_tcb) = self.threads[current_idx] {
@@ -159,7 +224,12 @@ impl Scheduler {
             }
         }
 
-        let Some(next_idx) = self.find_next_ready((current_idx + 1) % self.thread_count) else {
+        let start = (current_idx + 1) % self.threads.len();
+        let next = match self.find_next_ready(start) {
+            None if self.skip_to_next_deadline() => self.find_next_ready(start),
+            next => next,
+        };
+        let Some(next_idx) = next else {
             if let Some(current_tcb) = self.threads[current_idx] {
                 unsafe {
                     if (*current_tcb.as_ptr()).state == ThreadState::Ready {
@@ -188,65 +258,135 @@ impl Scheduler {
 
         // Perform context switch if needed
         unsafe {
-            if let (Some(mut old_ptr), Some(new_ptr)) =
+            if let (Some(mut old_ptr), Some(mut new_ptr)) =
                 (self.threads[current_idx], self.threads[self.current_index])
             {
                 let old_tcb = old_ptr.as_mut();
-                let new_tcb = new_ptr.as_ref();
+                let new_tcb = new_ptr.as_mut();
+                let now = crate::stats::now();
+                old_tcb.stats.switch_out(now);
+                new_tcb.stats.switch_in(now);
+                #[cfg(feature = "vdso")]
+                vdso::set_tid(new_tcb.tid);
                 karch::kswitch_to(old_tcb.thread_ctx_ptr_mut(), new_tcb.thread_ctx_ptr());
             }
         }
     }
 
     pub fn wait_on_addr(&mut self, addr: usize, expected: i32) -> isize {
+        self.futex_wait(addr, expected, None)
+    }
+
+    /// Like [`Scheduler::wait_on_addr`], but give up with `-ETIMEDOUT` after `timeout_ns`.
+    pub fn wait_on_addr_timeout(&mut self, addr: usize, expected: i32, timeout_ns: u64) -> isize {
+        let deadline = self.tick().saturating_add(timeout_ns.div_ceil(TICK_NS));
+        self.futex_wait(addr, expected, Some(deadline))
+    }
+
+    fn futex_wait(&mut self, addr: usize, expected: i32, deadline: Option<u64>) -> isize {
         let actual = unsafe { core::ptr::read_volatile(addr as *const i32) };
         if actual != expected {
-            if let Some(tcb) = self.current_thread() {
-                unsafe {
-                    karch::kthread_ctx_set_retval(
-                        (*tcb.as_ptr()).thread_ctx_ptr_mut(),
-                        (-EAGAIN as isize) as usize,
-                    );
-                }
+            return self.set_current_retval(-EAGAIN as isize);
+        }
+
+        if let Some(deadline) = deadline {
+            // Nobody else can run (or the deadline already passed): time out right away.
+            if self.thread_count() <= 1 || deadline <= self.tick() {
+                self.clock.advance_to(deadline);
+                return self.set_current_retval(-ETIMEDOUT as isize);
             }
-            return -EAGAIN as isize;
+        } else if self.thread_count() <= 1 {
+            return self.set_current_retval(-EDEADLK as isize);
         }
 
-        if self.thread_count() <= 1 {
-            if let Some(tcb) = self.current_thread() {
-                unsafe {
-                    karch::kthread_ctx_set_retval(
-                        (*tcb.as_ptr()).thread_ctx_ptr_mut(),
-                        (-EDEADLK as isize) as usize,
-                    );
-                }
+        self.set_current_retval(0);
+
+        let Some(current_tcb) = self.current_thread() else {
+            return 0;
+        };
+        unsafe {
+            let tcb = &mut *current_tcb.as_ptr();
+            tcb.state = ThreadState::Blocked;
+            tcb.futex_wait_addr = addr;
+            tcb.futex_deadline = deadline;
+            tcb.futex_timed_out = false;
+            tcb.stats.futex_waits += 1;
+            if deadline.is_some() {
+                self.timers.insert(current_tcb);
             }
-            return -EDEADLK as isize;
         }
+        self.yield_now();
 
+        // Resumed: by a wake, a requeue + wake, or the deadline passing.
+        if unsafe { core::mem::take(&mut (*current_tcb.as_ptr()).futex_timed_out) } {
+            return self.set_current_retval(-ETIMEDOUT as isize);
+        }
+        0
+    }
+
+    fn set_current_retval(&mut self, ret: isize) -> isize {
         if let Some(tcb) = self.current_thread() {
             unsafe {
-                karch::kthread_ctx_set_retval((*tcb.as_ptr()).thread_ctx_ptr_mut(), 0);
+                karch::kthread_ctx_set_retval((*tcb.as_ptr()).thread_ctx_ptr_mut(), ret as usize);
             }
         }
+        ret
+    }
 
-        if let Some(current_tcb) = self.current_thread() {
-            unsafe {
-                (*current_tcb.as_ptr()).state = ThreadState::Blocked;
-                (*current_tcb.as_ptr()).futex_wait_addr = addr;
-            }
-            self.yield_now();
+    /// Wake threads whose futex deadline has passed.
+    fn expire_timeouts(&mut self) {
+        // SAFETY: armed TCBs are blocked, so none has been released.
+        unsafe {
+            self.timers.expire(self.clock.now(), |tcb| {
+                tcb.state = ThreadState::Ready;
+                tcb.futex_wait_addr = 0;
+                tcb.futex_deadline = None;
+                tcb.futex_timed_out = true;
+            });
         }
-        0
+    }
+
+    /// With nothing runnable, fast-forward to the earliest futex deadline and expire it.
+    ///
+    /// Returns false if no blocked thread has a deadline.
+    fn skip_to_next_deadline(&mut self) -> bool {
+        let Some(deadline) = self.timers.next_deadline() else {
+            return false;
+        };
+        self.clock.advance_to(deadline);
+        self.expire_timeouts();
+        true
     }
 
     pub fn wake_on_addr(&mut self, addr: usize, count: usize) -> usize {
         let ret = self.wake_futex(addr, count);
-        if let Some(tcb) = self.current_thread() {
-            unsafe {
-                karch::kthread_ctx_set_retval((*tcb.as_ptr()).thread_ctx_ptr_mut(), ret);
+        self.set_current_retval(ret as isize);
+        ret
+    }
+
+    /// Wake up to `wake_count` waiters on `addr` and move up to `requeue_count` of the rest to
+    /// wait on `addr2` instead. Returns the number of threads woken or requeued.
+    pub fn requeue_on_addr(
+        &mut self,
+        addr: usize,
+        wake_count: usize,
+        addr2: usize,
+        requeue_count: usize,
+    ) -> usize {
+        let woken = self.wake_futex(addr, wake_count);
+        let mut requeued = 0;
+        for tcb in self.threads.iter().flatten() {
+            if requeued >= requeue_count {
+                break;
+            }
+            let tcb = unsafe { &mut *tcb.as_ptr() };
+            if tcb.state == ThreadState::Blocked && tcb.futex_wait_addr == addr {
+                tcb.futex_wait_addr = addr2;
+                requeued += 1;
             }
         }
+        let ret = woken + requeued;
+        self.set_current_retval(ret as isize);
         ret
     }
 
@@ -258,10 +398,34 @@ impl Scheduler {
         clear_child_tid_ptr: usize,
         mepc: usize,
     ) -> isize {
-        if self.thread_count == 0 {
+        if self.threads.is_empty() {
             // Scheduler must be initialized (boot TCB installed) before spawning threads.
             return -EPERM as isize;
         }
+        if !self.make_room() {
+            return -EAGAIN as isize;
+        }
+
+        // Without an explicit `tp`, give the child its own copy of the TLS template rather than
+        // aliasing the parent's thread-locals.
+        let (tls, tls_block) = if tls == 0 {
+            match alloc_tls_block() {
+                Ok(block) => (block, block),
+                Err(e) => return e,
+            }
+        } else {
+            (tls, 0)
+        };
+
+        // Without a caller stack, allocate one with a poisoned guard at its low end.
+        let (stack, owned_stack) = if stack == 0 {
+            match self.alloc_stack(self.stack_size) {
+                Some((base, size)) => (base + size, (base, size)),
+                None => return -EAGAIN as isize,
+            }
+        } else {
+            (stack, (0, 0))
+        };
 
         let new_tid = self.next_tid;
         self.next_tid += 1;
@@ -305,13 +469,11 @@ impl Scheduler {
         }
 
         child_tcb.clear_child_tid = clear_child_tid_ptr;
+        child_tcb.tls_block = tls_block;
+        (child_tcb.stack_base, child_tcb.stack_size) = owned_stack;
 
         let child_ptr = unsafe { NonNull::new_unchecked(Box::into_raw(child_tcb)) };
-        if self.thread_count >= MAX_THREADS {
-            return -EPERM as isize;
-        }
-        self.threads[self.thread_count] = Some(child_ptr);
-        self.thread_count += 1;
+        self.threads.insert(child_ptr);
 
         if let Some(parent_tcb) = self.current_thread() {
             unsafe {
@@ -323,27 +485,13 @@ impl Scheduler {
     }
 
     fn find_next_ready(&self, start_from: usize) -> Option<usize> {
-        for i in start_from..self.thread_count {
-            if let Some(tcb) = self.threads[i] {
-                if unsafe { (*tcb.as_ptr()).state == ThreadState::Ready } {
-                    return Some(i);
-                }
-            }
-        }
-        for i in 0..start_from {
-            if let Some(tcb) = self.threads[i] {
-                if unsafe { (*tcb.as_ptr()).state == ThreadState::Ready } {
-                    return Some(i);
-                }
-            }
-        }
-        None
+        crate::policy::select(&self.threads, start_from)
     }
 
     pub fn wake_futex(&mut self, futex_addr: usize, max_count: usize) -> usize {
         let mut woken = 0;
 
-        for i in 0..self.thread_count {
+        for i in 0..self.threads.len() {
             if woken >= max_count {
                 break;
             }
@@ -354,6 +502,8 @@ impl Scheduler {
                     {
                         (*tcb.as_ptr()).state = ThreadState::Ready;
                         (*tcb.as_ptr()).futex_wait_addr = 0;
+                        self.timers.remove(tcb);
+                        (*tcb.as_ptr()).futex_deadline = None;
                         woken += 1;
                     }
                 }
@@ -363,12 +513,264 @@ impl Scheduler {
         woken
     }
 
+    /// Create a kernel-mode thread that starts at `entry(arg)` on a scheduler-owned stack of
+    /// `stack_size` bytes, released with the thread's other resources once it exits.
+    ///
+    /// Unlike `spawn_thread`, no parent trap frame is cloned: the first switch to the thread
+    /// "returns" into `entry` with `arg` in the first argument register.
+    pub fn spawn_kernel_thread(&mut self, entry: usize, arg: usize, stack_size: usize) -> isize {
+        if self.threads.is_empty() {
+            return -EPERM as isize;
+        }
+        if !self.make_room() {
+            return -EAGAIN as isize;
+        }
+        let Some((stack_base, stack_size)) = self.alloc_stack(stack_size) else {
+            return -EAGAIN as isize;
+        };
+
+        let new_tid = self.next_tid;
+        self.next_tid += 1;
+
+        let mut tcb = Box::new(ThreadControlBlock::new(new_tid, 0, 0, entry));
+        (tcb.stack_base, tcb.stack_size) = (stack_base, stack_size);
+        unsafe {
+            let anchor_ptr = tcb.kstack_base as *mut foundation::kfn::scheduler::ThreadAnchor;
+            (*anchor_ptr).task_ptr = Box::as_ref(&tcb) as *const _ as usize;
+
+            karch::kthread_ctx_set_sp(tcb.thread_ctx_ptr_mut(), stack_base + stack_size);
+            karch::kthread_ctx_set_ra(tcb.thread_ctx_ptr_mut(), entry);
+            karch::kthread_ctx_set_retval(tcb.thread_ctx_ptr_mut(), arg);
+        }
+
+        let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(tcb)) };
+        self.threads.insert(ptr);
+
+        new_tid as isize
+    }
+
+    pub fn thread_state(&self, tid: Tid) -> Option<ThreadState> {
+        self.find_tcb(tid).map(|tcb| unsafe { tcb.as_ref() }.state)
+    }
+
+    /// Set the scheduling priority of `tid`; returns false if no such thread exists.
+    pub fn set_priority(&mut self, tid: Tid, priority: Priority) -> bool {
+        for tcb in self.threads.iter().flatten() {
+            let tcb = unsafe { &mut *tcb.as_ptr() };
+            if tcb.tid == tid {
+                tcb.priority = priority;
+                tcb.credits = tcb.credits.min(priority as u32 + 1);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// A stack of at least `size` bytes with a poisoned guard, from the pool if one fits, as
+    /// `(base, size)`.
+    fn alloc_stack(&mut self, size: usize) -> Option<(usize, usize)> {
+        let size = crate::stack::normalize_size(size);
+        self.stack_pool
+            .take(size)
+            .or_else(|| crate::stack::alloc(size))
+            .map(|base| (base, size))
+    }
+
+    /// Set the size of stacks allocated for threads spawned without one.
+    pub fn set_stack_size(&mut self, size: usize) {
+        self.stack_size = size;
+    }
+
+    /// Limit the table to `max` threads (at least 1). Threads already in it stay; lowering the
+    /// limit below their number only fails further spawns.
+    pub fn set_max_threads(&mut self, max: usize) {
+        self.max_threads = max.max(1);
+    }
+
+    /// Make sure a spawn can take a slot: a free one, a new one below the limit, or, at the
+    /// limit, one of the exited threads nobody is waiting to join.
+    fn make_room(&mut self) -> bool {
+        if self.threads.reserve(self.max_threads) {
+            return true;
+        }
+        // Reaping now would defer every free to the allocator lock's holder.
+        if foundation::kfn::memory::kalloc_lock_held() {
+            return false;
+        }
+        for index in 0..self.threads.len() {
+            if self.reapable(index) {
+                self.reap(index);
+            }
+        }
+        self.threads.reserve(self.max_threads)
+    }
+
+    /// Whether slot `index` holds an exited thread that is off the CPU and has no joiners.
+    fn reapable(&self, index: usize) -> bool {
+        let Some(tcb) = self.threads[index] else {
+            return false;
+        };
+        let tcb = unsafe { tcb.as_ref() };
+        index != self.current_index && tcb.state == ThreadState::Exited && tcb.join_waiters == 0
+    }
+
+    /// Drop the thread in slot `index` from the table and free its TCB. Its exit code is gone:
+    /// joining it now fails with `ESRCH`.
+    fn reap(&mut self, index: usize) {
+        let Some(tcb) = self.threads.remove(index) else {
+            return;
+        };
+        self.release_resources(unsafe { &mut *tcb.as_ptr() });
+        // The boot TCB is never reaped: its exit ends the process.
+        foundation::kfn::memory::kalloc_in_critical_section(|| {
+            drop(unsafe { Box::from_raw(tcb.as_ptr()) });
+        });
+    }
+
+    /// Release the memory of exited threads that are off the CPU, keeping only their TCBs.
+    ///
+    /// Frees at most [`RELEASES_PER_TICK`] threads per call, and none while a thread holds the
+    /// allocator lock (its frees would pile up in the deferred queue); the rest wait for a later
+    /// tick.
+    fn release_exited(&mut self) {
+        if foundation::kfn::memory::kalloc_lock_held() {
+            return;
+        }
+        let mut released = 0;
+        for index in 0..self.threads.len() {
+            if released == RELEASES_PER_TICK {
+                break;
+            }
+            let Some(tcb) = self.threads[index] else {
+                continue;
+            };
+            let tcb = unsafe { &mut *tcb.as_ptr() };
+            if index != self.current_index
+                && tcb.state == ThreadState::Exited
+                && tcb.kstack_base != 0
+            {
+                self.release_resources(tcb);
+                released += 1;
+            }
+        }
+    }
+
+    /// Free what an exited thread no longer needs once it has switched away for good: its
+    /// scheduler-allocated stack (into the pool), TLS block, switch context and kernel stack.
+    /// The TCB stays, so the thread can still be joined. Safe to call again.
+    fn release_resources(&mut self, tcb: &mut ThreadControlBlock) {
+        // Frees under the scheduler borrow must not park on the allocator lock.
+        foundation::kfn::memory::kalloc_in_critical_section(|| {
+            if tcb.stack_base != 0 {
+                self.stack_pool.put(tcb.stack_base, tcb.stack_size);
+                (tcb.stack_base, tcb.stack_size) = (0, 0);
+            }
+            if tcb.tls_block != 0 {
+                free_tls_block(core::mem::take(&mut tcb.tls_block));
+            }
+            let ctx = core::mem::replace(
+                &mut tcb.thread_ctx,
+                crate::thread::ThreadContext(core::ptr::null_mut()),
+            );
+            if !ctx.0.is_null() {
+                let layout =
+                    Layout::from_size_align(karch::kthread_ctx_size(), karch::kthread_ctx_align())
+                        .expect("invalid thread ctx layout");
+                foundation::kfn::memory::kfree(ctx.0, layout);
+            }
+            if tcb.kstack_base != 0 {
+                let layout = Layout::from_size_align(tcb.kstack_size, tcb.kstack_size)
+                    .expect("invalid kernel stack layout");
+                foundation::kfn::memory::kfree(
+                    core::mem::take(&mut tcb.kstack_base) as *mut u8,
+                    layout,
+                );
+            }
+        });
+    }
+
+    /// Robust futex list head of thread `tid`, or `None` if there is no such thread.
+    pub fn robust_list(&self, tid: Tid) -> Option<usize> {
+        self.find_tcb(tid)
+            .map(|tcb| unsafe { tcb.as_ref() }.robust_list)
+    }
+
+    fn find_tcb(&self, tid: Tid) -> Option<NonNull<ThreadControlBlock>> {
+        self.threads
+            .iter()
+            .flatten()
+            .copied()
+            .find(|tcb| unsafe { tcb.as_ref() }.tid == tid)
+    }
+
+    /// Wait for thread `tid` to exit and store its exit code at `status_ptr` (if non-zero).
+    ///
+    /// Returns `tid` once it has exited, `0` if `nohang` and it is still running, `-ESRCH` for
+    /// an unknown thread, or `-EDEADLK` when joining itself or when no thread is left to run.
+    pub fn join_thread(&mut self, tid: Tid, status_ptr: usize, nohang: bool) -> isize {
+        let Some(target) = self.find_tcb(tid) else {
+            return self.set_current_retval(-ESRCH as isize);
+        };
+        let Some(current) = self.current_thread() else {
+            return self.set_current_retval(-EPERM as isize);
+        };
+        if target == current {
+            return self.set_current_retval(-EDEADLK as isize);
+        }
+
+        // Joiners are ordinary futex waiters keyed on the target's exit code word, which stays
+        // put for the lifetime of the TCB.
+        let key = unsafe { core::ptr::addr_of!((*target.as_ptr()).exit_code) } as usize;
+        while unsafe { target.as_ref() }.state != ThreadState::Exited {
+            if nohang {
+                return self.set_current_retval(0);
+            }
+            unsafe {
+                (*target.as_ptr()).join_waiters += 1;
+                let tcb = &mut *current.as_ptr();
+                tcb.state = ThreadState::Blocked;
+                tcb.futex_wait_addr = key;
+                tcb.futex_deadline = None;
+            }
+            self.yield_now();
+            unsafe { (*target.as_ptr()).join_waiters -= 1 };
+
+            let tcb = unsafe { &mut *current.as_ptr() };
+            if tcb.state == ThreadState::Blocked {
+                // Nothing else was runnable: the target can never exit.
+                tcb.state = ThreadState::Running;
+                tcb.futex_wait_addr = 0;
+                return self.set_current_retval(-EDEADLK as isize);
+            }
+        }
+
+        if status_ptr != 0 {
+            unsafe { (status_ptr as *mut i32).write_volatile(target.as_ref().exit_code) };
+        }
+        // The last joiner collects the thread, freeing its slot.
+        if let Some(index) = self.threads.iter().position(|&slot| slot == Some(target)) {
+            if self.reapable(index) {
+                self.reap(index);
+            }
+        }
+        self.set_current_retval(tid as isize)
+    }
+
     pub fn exit_current_and_yield(&mut self, exit_code: i32) -> isize {
         if let Some(current_tcb) = self.current_thread() {
             let is_main_thread = unsafe { (*current_tcb.as_ptr()).tid == 1 };
 
             unsafe {
                 (*current_tcb.as_ptr()).state = ThreadState::Exited;
+                (*current_tcb.as_ptr()).exit_code = exit_code;
+                let key = core::ptr::addr_of!((*current_tcb.as_ptr()).exit_code) as usize;
+                self.wake_futex(key, usize::MAX);
+
+                // Hand robust mutexes the thread still holds to a waiter as owner-died.
+                let tid = (*current_tcb.as_ptr()).tid;
+                crate::robust::release((*current_tcb.as_ptr()).robust_list, tid, |addr| {
+                    self.wake_futex(addr, 1);
+                });
 
                 let clear = (*current_tcb.as_ptr()).clear_child_tid;
                 if clear != 0 {
@@ -388,3 +790,170 @@ impl Scheduler {
         }
     }
 }
+
+/// Panic if `tcb` ran past the end of its scheduler-allocated stack.
+fn check_stack_guard(tcb: &ThreadControlBlock) {
+    if tcb.stack_base != 0 && !crate::stack::guard_intact(tcb.stack_base) {
+        panic!(
+            "stack overflow: thread {} overran its {}-byte stack at 0x{:x}",
+            tcb.tid, tcb.stack_size, tcb.stack_base
+        );
+    }
+}
+
+/// Free a block returned by [`alloc_tls_block`].
+fn free_tls_block(block: usize) {
+    cfg_if::cfg_if! {
+        if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
+            if let Some(layout) = foundation::utils::TlsImage::discover().layout() {
+                foundation::kfn::memory::kfree(block as *mut u8, layout);
+            }
+        } else {
+            let _ = block;
+        }
+    }
+}
+
+/// Allocate and initialize a TLS block from the linked template; `Ok(0)` when there is none.
+fn alloc_tls_block() -> Result<usize, isize> {
+    cfg_if::cfg_if! {
+        if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
+            let image = foundation::utils::TlsImage::discover();
+            let Some(layout) = image.layout() else {
+                return Ok(0);
+            };
+            let block = foundation::kfn::memory::kmalloc(layout);
+            if block.is_null() {
+                return Err(-EAGAIN as isize);
+            }
+            // SAFETY: `block` was just allocated with the template's layout.
+            Ok(unsafe { image.init_block(block) })
+        } else {
+            Ok(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn with_threads(tcbs: &mut [ThreadControlBlock]) -> Scheduler {
+        let mut s = Scheduler::new();
+        for tcb in tcbs {
+            let armed = tcb.futex_deadline.is_some();
+            let tcb = NonNull::from(tcb);
+            assert!(s.threads.reserve(usize::MAX));
+            s.threads.insert(tcb);
+            if armed {
+                unsafe { s.timers.insert(tcb) };
+            }
+        }
+        s
+    }
+
+    fn waiting(tid: Tid, addr: usize, deadline: Option<u64>) -> ThreadControlBlock {
+        let mut tcb = ThreadControlBlock::stub(tid, ThreadState::Blocked, 0);
+        tcb.futex_wait_addr = addr;
+        tcb.futex_deadline = deadline;
+        tcb
+    }
+
+    #[test]
+    fn test_skip_to_next_deadline_expires_earliest() {
+        let mut tcbs = Vec::from([
+            waiting(1, 0x100, Some(30)),
+            waiting(2, 0x100, Some(10)),
+            waiting(3, 0x200, None),
+        ]);
+        let mut s = with_threads(&mut tcbs);
+        assert!(s.skip_to_next_deadline());
+        assert_eq!(s.tick(), 10);
+
+        assert_eq!(tcbs[0].state, ThreadState::Blocked);
+        assert_eq!(tcbs[1].state, ThreadState::Ready);
+        assert!(tcbs[1].futex_timed_out);
+        assert_eq!(tcbs[1].futex_deadline, None);
+        assert_eq!(tcbs[2].state, ThreadState::Blocked);
+    }
+
+    #[test]
+    fn test_wake_disarms_deadline() {
+        let mut tcbs = Vec::from([waiting(1, 0x100, Some(5)), waiting(2, 0x200, Some(8))]);
+        let mut s = with_threads(&mut tcbs);
+        assert_eq!(s.wake_futex(0x100, 1), 1);
+        assert_eq!(s.timers.len(), 1);
+
+        assert!(s.skip_to_next_deadline());
+        assert_eq!(s.tick(), 8);
+        assert!(!tcbs[0].futex_timed_out);
+        assert!(tcbs[1].futex_timed_out);
+        assert!(!s.skip_to_next_deadline());
+    }
+
+    #[test]
+    fn test_skip_without_deadlines() {
+        let mut tcbs = Vec::from([waiting(1, 0x100, None)]);
+        let mut s = with_threads(&mut tcbs);
+        assert!(!s.skip_to_next_deadline());
+        assert_eq!(s.tick(), 0);
+    }
+
+    #[test]
+    fn test_requeue_moves_remaining_waiters() {
+        let mut tcbs = Vec::from([
+            waiting(1, 0x100, None),
+            waiting(2, 0x100, None),
+            waiting(3, 0x100, None),
+            waiting(4, 0x100, None),
+        ]);
+        let mut s = with_threads(&mut tcbs);
+        // No current thread in this table, so no retval is patched.
+        s.current_index = MAX_THREADS;
+        assert_eq!(s.requeue_on_addr(0x100, 1, 0x200, 2), 3);
+
+        let addrs: Vec<_> = tcbs.iter().map(|t| (t.state, t.futex_wait_addr)).collect();
+        assert_eq!(
+            addrs,
+            [
+                (ThreadState::Ready, 0),
+                (ThreadState::Blocked, 0x200),
+                (ThreadState::Blocked, 0x200),
+                (ThreadState::Blocked, 0x100),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_join_unknown_thread() {
+        let mut tcbs = Vec::from([ThreadControlBlock::stub(2, ThreadState::Exited, 0)]);
+        let mut s = with_threads(&mut tcbs);
+        // No current thread in this table, so no retval is patched.
+        s.current_index = MAX_THREADS;
+        assert_eq!(s.join_thread(9, 0, false), -(ESRCH as isize));
+        assert_eq!(s.join_thread(2, 0, false), -(EPERM as isize));
+    }
+
+    #[test]
+    fn test_full_table_reaps_exited_threads() {
+        let mut running = ThreadControlBlock::stub(1, ThreadState::Running, 0);
+        let mut joined = ThreadControlBlock::stub(3, ThreadState::Exited, 0);
+        joined.join_waiters = 1;
+        let mut s = with_threads(core::slice::from_mut(&mut running));
+        // Reaping frees the TCB, so this one must be boxed like a spawned thread's.
+        let exited = Box::new(ThreadControlBlock::stub(2, ThreadState::Exited, 0));
+        assert!(s.threads.reserve(usize::MAX));
+        s.threads.insert(NonNull::from(Box::leak(exited)));
+        assert!(s.threads.reserve(usize::MAX));
+        s.threads.insert(NonNull::from(&mut joined));
+        s.set_max_threads(3);
+
+        assert!(s.make_room());
+        assert_eq!(s.thread_count(), 2);
+        assert_eq!(s.thread_state(2), None);
+        // Still awaited by a joiner: kept.
+        assert_eq!(s.thread_state(3), Some(ThreadState::Exited));
+        assert!(s.threads[1].is_none());
+    }
+}
@@ -0,0 +1,145 @@
+//! Safe `std::thread`-style API for no_std guests.
+//!
+//! Threads are kernel-mode threads on the cooperative scheduler: they run until they call
+//! [`yield_now`], block in [`JoinHandle::join`], or return.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicI32, Ordering};
+
+use crate::scheduler::Scheduler;
+use crate::thread::Tid;
+use foundation::error::errno::EAGAIN;
+
+/// Default stack size for [`spawn`].
+pub const DEFAULT_STACK_SIZE: usize = 64 * 1024;
+
+/// Result slot shared between a thread and its `JoinHandle`.
+struct Packet<T> {
+    /// Futex word: 0 while running, 1 once `result` is written.
+    done: AtomicI32,
+    result: UnsafeCell<Option<T>>,
+}
+
+// SAFETY: `result` is written once by the thread before `done` is published and read only by the
+// joiner after observing `done`.
+unsafe impl<T: Send> Sync for Packet<T> {}
+
+struct Start<F, T> {
+    f: F,
+    packet: Arc<Packet<T>>,
+}
+
+pub struct JoinHandle<T> {
+    tid: Tid,
+    packet: Arc<Packet<T>>,
+}
+
+impl<T> JoinHandle<T> {
+    pub fn tid(&self) -> Tid {
+        self.tid
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.packet.done.load(Ordering::Acquire) != 0
+    }
+
+    /// Block until the thread returns and take its result. The scheduler frees the thread's stack
+    /// once it exits, so dropping the handle instead leaks nothing.
+    pub fn join(self) -> T {
+        let addr = self.packet.done.as_ptr() as usize;
+        while self.packet.done.load(Ordering::Acquire) == 0 {
+            let ret = Scheduler::with_mut(|s| s.wait_on_addr(addr, 0)).unwrap_or(0);
+            if ret < 0 && ret != -(EAGAIN as isize) {
+                panic!(
+                    "join: thread {} can never finish (errno {})",
+                    self.tid, -ret
+                );
+            }
+        }
+
+        // SAFETY: `done` was published after the single write of `result`.
+        unsafe { (*self.packet.result.get()).take() }.expect("join: missing thread result")
+    }
+}
+
+/// Spawn a thread running `f` on a stack of at least `stack_size` bytes.
+///
+/// # Panics
+/// If the scheduler is not initialized, the thread table is full, or allocation fails.
+pub fn spawn<F, T>(f: F, stack_size: usize) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    match try_spawn(f, stack_size) {
+        Ok(h) => h,
+        Err(e) => panic!("spawn failed (errno {})", -e),
+    }
+}
+
+/// Like [`spawn`], returning a negative errno instead of panicking.
+pub fn try_spawn<F, T>(f: F, stack_size: usize) -> Result<JoinHandle<T>, isize>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let packet = Arc::new(Packet {
+        done: AtomicI32::new(0),
+        result: UnsafeCell::new(None),
+    });
+    let start = Box::into_raw(Box::new(Start {
+        f,
+        packet: packet.clone(),
+    }));
+
+    let entry = thread_start::<F, T> as *const () as usize;
+    let stack_size = stack_size.max(4096);
+    let tid = foundation::kfn::memory::kalloc_in_critical_section(|| {
+        Scheduler::with_mut(|s| s.spawn_kernel_thread(entry, start as usize, stack_size))
+    })
+    .unwrap_or(-(foundation::error::errno::EPERM as isize));
+
+    if tid < 0 {
+        // SAFETY: the thread was never created, so `start` is still exclusively ours.
+        unsafe { drop(Box::from_raw(start)) };
+        return Err(tid);
+    }
+
+    Ok(JoinHandle {
+        tid: tid as Tid,
+        packet,
+    })
+}
+
+/// Voluntarily give the CPU to the next ready thread.
+pub fn yield_now() {
+    Scheduler::with_mut(|s| s.yield_now());
+}
+
+/// Thread entry: the first context switch "returns" here with `a0 = start`.
+extern "C" fn thread_start<F, T>(start: usize) -> !
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    // SAFETY: `start` is the `Box<Start>` leaked by `try_spawn` and is consumed exactly once.
+    let Start { f, packet } = *unsafe { Box::from_raw(start as *mut Start<F, T>) };
+
+    let result = f();
+    // SAFETY: only this thread writes `result`, before publishing `done`.
+    unsafe { *packet.result.get() = Some(result) };
+    packet.done.store(1, Ordering::Release);
+    let addr = packet.done.as_ptr() as usize;
+    drop(packet);
+
+    Scheduler::with_mut(|s| {
+        s.wake_futex(addr, usize::MAX);
+        s.exit_current_and_yield(0)
+    });
+
+    // Only reached when no other thread is runnable, yet the main thread has not exited: it is
+    // blocked on something nobody is left to release.
+    panic!("deadlock: no runnable threads after this thread exited")
+}
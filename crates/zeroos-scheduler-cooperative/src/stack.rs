@@ -0,0 +1,131 @@
+//! Scheduler-owned thread stacks.
+//!
+//! The lowest [`STACK_GUARD_SIZE`] bytes of every stack are filled with [`STACK_POISON`]. There
+//! is no MMU or PMP region to fault on them, so overflow is caught by re-checking the poison when a
+//! thread is switched out.
+//!
+//! Stacks of exited threads go to a small [`StackPool`] and are handed to the next spawn that
+//! asks for the same size, so a spawn/join loop does not churn the heap.
+
+use alloc::alloc::Layout;
+
+/// Stack size used when the caller of `spawn_thread` does not provide a stack.
+pub const DEFAULT_THREAD_STACK_SIZE: usize = 64 * 1024;
+
+/// Bytes at the low end of each stack reserved as a poisoned guard.
+pub const STACK_GUARD_SIZE: usize = 1024;
+
+pub const STACK_POISON: u8 = 0xcc;
+
+/// Freed stacks kept for reuse.
+pub const STACK_POOL_SIZE: usize = 4;
+
+const STACK_ALIGN: usize = 16;
+
+/// Round a requested size up so it is aligned and leaves room above the guard.
+pub(crate) fn normalize_size(size: usize) -> usize {
+    size.max(2 * STACK_GUARD_SIZE).next_multiple_of(STACK_ALIGN)
+}
+
+/// Allocate a stack of `size` bytes (see [`normalize_size`]) and poison its guard.
+///
+/// Returns the lowest address, or `None` if the heap is exhausted.
+pub(crate) fn alloc(size: usize) -> Option<usize> {
+    let layout = Layout::from_size_align(size, STACK_ALIGN).ok()?;
+    let base = foundation::kfn::memory::kmalloc(layout);
+    if base.is_null() {
+        return None;
+    }
+    // SAFETY: `base` was just allocated with at least `STACK_GUARD_SIZE` bytes.
+    unsafe { core::ptr::write_bytes(base, STACK_POISON, STACK_GUARD_SIZE) };
+    Some(base as usize)
+}
+
+/// Free a stack returned by [`alloc`].
+pub(crate) fn free(base: usize, size: usize) {
+    if let Ok(layout) = Layout::from_size_align(size, STACK_ALIGN) {
+        foundation::kfn::memory::kfree(base as *mut u8, layout);
+    }
+}
+
+/// Stacks of exited threads, as `(base, size)`.
+pub(crate) struct StackPool {
+    stacks: [(usize, usize); STACK_POOL_SIZE],
+    len: usize,
+}
+
+impl StackPool {
+    pub const fn new() -> Self {
+        Self {
+            stacks: [(0, 0); STACK_POOL_SIZE],
+            len: 0,
+        }
+    }
+
+    /// A pooled stack of exactly `size` bytes with its guard poisoned again, if there is one.
+    pub fn take(&mut self, size: usize) -> Option<usize> {
+        let i = self.stacks[..self.len]
+            .iter()
+            .position(|&(_, s)| s == size)?;
+        let (base, _) = self.stacks[i];
+        self.len -= 1;
+        self.stacks[i] = self.stacks[self.len];
+        // SAFETY: pooled stacks are live allocations of `size >= STACK_GUARD_SIZE` bytes.
+        unsafe { core::ptr::write_bytes(base as *mut u8, STACK_POISON, STACK_GUARD_SIZE) };
+        Some(base)
+    }
+
+    /// Keep the stack at `base` for reuse, or free it if the pool is full.
+    pub fn put(&mut self, base: usize, size: usize) {
+        if self.len == STACK_POOL_SIZE {
+            free(base, size);
+            return;
+        }
+        self.stacks[self.len] = (base, size);
+        self.len += 1;
+    }
+}
+
+/// Whether the guard of the stack starting at `base` still holds its poison.
+pub(crate) fn guard_intact(base: usize) -> bool {
+    // SAFETY: `base` is the start of a live stack allocated by `alloc`.
+    let guard = unsafe { core::slice::from_raw_parts(base as *const u8, STACK_GUARD_SIZE) };
+    guard.iter().all(|&b| b == STACK_POISON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_detects_clobber() {
+        let mut stack = alloc::vec![STACK_POISON; 2 * STACK_GUARD_SIZE];
+        let base = stack.as_ptr() as usize;
+        assert!(guard_intact(base));
+
+        stack[STACK_GUARD_SIZE - 8] = 0;
+        assert!(!guard_intact(base));
+    }
+
+    #[test]
+    fn test_pool_reuses_matching_stacks() {
+        let mut a = alloc::vec![0u8; 2 * STACK_GUARD_SIZE];
+        let mut b = alloc::vec![0u8; 4 * STACK_GUARD_SIZE];
+        let (a, b) = (a.as_mut_ptr() as usize, b.as_mut_ptr() as usize);
+
+        let mut pool = StackPool::new();
+        pool.put(a, 2 * STACK_GUARD_SIZE);
+        pool.put(b, 4 * STACK_GUARD_SIZE);
+        assert_eq!(pool.take(3 * STACK_GUARD_SIZE), None);
+        assert_eq!(pool.take(4 * STACK_GUARD_SIZE), Some(b));
+        assert!(guard_intact(b));
+        assert_eq!(pool.take(4 * STACK_GUARD_SIZE), None);
+        assert_eq!(pool.take(2 * STACK_GUARD_SIZE), Some(a));
+    }
+
+    #[test]
+    fn test_normalize_size() {
+        assert_eq!(normalize_size(0), 2 * STACK_GUARD_SIZE);
+        assert_eq!(normalize_size(4097), 4112);
+    }
+}
@@ -0,0 +1,158 @@
+//! Per-thread run statistics.
+//!
+//! Every thread counts how often it was switched in and how many times it blocked on a futex.
+//! With the `stats` feature it also accumulates the cycles it ran, read from
+//! `__platform_cycle_count` at each context switch, and [`dump`] prints a summary to the debug
+//! console, so uneven work across worker threads shows up as uneven cycle counts.
+
+use alloc::vec::Vec;
+
+use crate::scheduler::Scheduler;
+use crate::thread::{ThreadState, Tid};
+
+/// Counters kept in each TCB.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RunStats {
+    /// Times the thread was switched to.
+    pub switches: u64,
+    /// Cycles run up to the last switch away (0 without the `stats` feature).
+    pub cycles: u64,
+    /// Futex waits that blocked.
+    pub futex_waits: u64,
+    /// Cycle count when the thread was last switched to.
+    since: u64,
+}
+
+impl RunStats {
+    pub const fn new() -> Self {
+        Self {
+            switches: 0,
+            cycles: 0,
+            futex_waits: 0,
+            since: 0,
+        }
+    }
+
+    /// The thread stops running at cycle `now`.
+    pub(crate) fn switch_out(&mut self, now: u64) {
+        self.cycles += now.saturating_sub(self.since);
+    }
+
+    /// The thread starts running at cycle `now`.
+    pub(crate) fn switch_in(&mut self, now: u64) {
+        self.switches += 1;
+        self.since = now;
+    }
+}
+
+/// Snapshot of one thread's statistics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThreadStats {
+    pub tid: Tid,
+    pub state: ThreadState,
+    pub switches: u64,
+    /// Cycles run so far, including the current slice of the running thread.
+    pub cycles: u64,
+    pub futex_waits: u64,
+}
+
+#[inline]
+pub(crate) fn now() -> u64 {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "stats")] {
+            foundation::kfn::time::kcycles()
+        } else {
+            0
+        }
+    }
+}
+
+impl Scheduler {
+    /// Append a snapshot of every thread to `out`, without growing it past its capacity.
+    pub fn collect_stats(&self, out: &mut Vec<ThreadStats>) {
+        let now = now();
+        let current = self.current_thread();
+        for tcb in self.threads.iter().flatten() {
+            if out.len() == out.capacity() {
+                break;
+            }
+            let running = Some(*tcb) == current;
+            let tcb = unsafe { tcb.as_ref() };
+            let mut run = tcb.stats;
+            if running {
+                run.switch_out(now);
+            }
+            out.push(ThreadStats {
+                tid: tcb.tid,
+                state: tcb.state,
+                switches: run.switches,
+                cycles: run.cycles,
+                futex_waits: run.futex_waits,
+            });
+        }
+    }
+}
+
+/// Statistics of every thread, exited ones not yet reaped included, in table order.
+pub fn stats() -> Vec<ThreadStats> {
+    // Allocate before borrowing the scheduler: the allocator may park on its lock.
+    let count = Scheduler::with_mut(|scheduler| scheduler.thread_count()).unwrap_or(0);
+    let mut out = Vec::with_capacity(count);
+    Scheduler::with_mut(|scheduler| scheduler.collect_stats(&mut out));
+    out
+}
+
+/// Write per-thread statistics and each thread's share of the cycles to the debug console.
+#[cfg(feature = "stats")]
+pub fn dump() {
+    let stats = stats();
+    let total: u64 = stats.iter().map(|s| s.cycles).sum();
+    debug::writeln!("[sched] tid state switches futex_waits cycles share%");
+    for s in &stats {
+        let share = (s.cycles * 100).checked_div(total).unwrap_or(0);
+        debug::writeln!(
+            "[sched] {} {:?} {} {} {} {}",
+            s.tid,
+            s.state,
+            s.switches,
+            s.futex_waits,
+            s.cycles,
+            share
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::ThreadControlBlock;
+    use core::ptr::NonNull;
+
+    #[test]
+    fn test_collect_stats_snapshot() {
+        let mut tcbs = [
+            ThreadControlBlock::stub(1, ThreadState::Running, 0),
+            ThreadControlBlock::stub(2, ThreadState::Ready, 0),
+        ];
+        tcbs[0].stats.switch_in(0);
+        tcbs[1].stats.switch_in(5);
+        tcbs[1].stats.switch_out(25);
+        tcbs[1].stats.futex_waits = 3;
+
+        let mut s = Scheduler::new();
+        for tcb in &mut tcbs {
+            assert!(s.threads.reserve(2));
+            s.threads.insert(NonNull::from(tcb));
+        }
+
+        let mut out = Vec::with_capacity(1);
+        s.collect_stats(&mut out);
+        assert_eq!(out.len(), 1);
+
+        let mut out = Vec::with_capacity(2);
+        s.collect_stats(&mut out);
+        assert_eq!((out[1].tid, out[1].switches), (2, 1));
+        assert_eq!((out[1].cycles, out[1].futex_waits), (20, 3));
+        assert_eq!((out[0].tid, out[0].switches), (1, 1));
+    }
+}
@@ -0,0 +1,239 @@
+//! Growable thread table.
+//!
+//! Slots hold TCB pointers in a kernel-heap array that doubles as threads are added, up to the
+//! scheduler's thread limit. When a thread is reaped its slot is emptied and the index pushed on
+//! a free list, which later spawns take from before the array grows, so a guest that keeps
+//! spawning and joining threads cycles through the same few slots.
+//!
+//! The arrays come from `kmalloc`, not the global allocator: under a libc runtime that is the
+//! libc `malloc`, which cannot run before the runtime is up and the boot thread needs a slot.
+
+use core::alloc::Layout;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use crate::thread::ThreadControlBlock;
+
+pub type Slot = Option<NonNull<ThreadControlBlock>>;
+
+/// Slots allocated on first use.
+const INITIAL_CAPACITY: usize = 8;
+
+/// Array of `Copy` items on the kernel heap; growing moves it to a larger block.
+struct Array<T: Copy> {
+    ptr: NonNull<T>,
+    len: usize,
+    cap: usize,
+}
+
+impl<T: Copy> Array<T> {
+    const fn new() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            len: 0,
+            cap: 0,
+        }
+    }
+
+    /// Make room for `want` items; false if out of memory.
+    fn reserve(&mut self, want: usize) -> bool {
+        if want <= self.cap {
+            return true;
+        }
+        let cap = want.max(self.cap * 2).max(INITIAL_CAPACITY);
+        let Ok(layout) = Layout::array::<T>(cap) else {
+            return false;
+        };
+        let ptr = heap::alloc(layout) as *mut T;
+        let Some(ptr) = NonNull::new(ptr) else {
+            return false;
+        };
+        if self.cap != 0 {
+            // SAFETY: both blocks hold at least `len` items and do not overlap.
+            unsafe { core::ptr::copy_nonoverlapping(self.ptr.as_ptr(), ptr.as_ptr(), self.len) };
+            heap::free(
+                self.ptr.as_ptr() as *mut u8,
+                Layout::array::<T>(self.cap).unwrap(),
+            );
+        }
+        self.ptr = ptr;
+        self.cap = cap;
+        true
+    }
+
+    /// Append `item`; the caller has reserved room for it.
+    fn push(&mut self, item: T) {
+        assert!(self.len < self.cap, "thread table push without reserve");
+        // SAFETY: `len < cap`, so the slot is inside the allocation.
+        unsafe { self.ptr.as_ptr().add(self.len).write(item) };
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        // SAFETY: the item at the old `len - 1` was initialized by `push`.
+        Some(unsafe { self.ptr.as_ptr().add(self.len).read() })
+    }
+
+    fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` items are initialized; `ptr` is dangling only when `len` is 0.
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: as in `as_slice`, and `&mut self` makes the borrow unique.
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy> Drop for Array<T> {
+    fn drop(&mut self) {
+        if self.cap != 0 {
+            heap::free(
+                self.ptr.as_ptr() as *mut u8,
+                Layout::array::<T>(self.cap).unwrap(),
+            );
+        }
+    }
+}
+
+/// Thread slots plus the indices of the empty ones.
+///
+/// Indexing yields every slot, empty ones as `None`, so a thread keeps its index for as long as
+/// it is in the table.
+pub struct ThreadTable {
+    slots: Array<Slot>,
+    /// Indices of empty slots; its capacity always covers every slot.
+    free: Array<usize>,
+}
+
+impl ThreadTable {
+    pub const fn new() -> Self {
+        Self {
+            slots: Array::new(),
+            free: Array::new(),
+        }
+    }
+
+    /// Threads in the table.
+    pub fn occupied(&self) -> usize {
+        self.slots.len - self.free.len
+    }
+
+    /// Whether [`ThreadTable::insert`] can take another thread without holding more than `limit`;
+    /// grows the arrays if needed and returns false if that fails.
+    pub fn reserve(&mut self, limit: usize) -> bool {
+        if self.free.len > 0 {
+            return true;
+        }
+        let want = self.slots.len + 1;
+        want <= limit && self.slots.reserve(want) && self.free.reserve(want)
+    }
+
+    /// Put `tcb` in an empty slot, reusing a freed one if there is any, and return its index.
+    ///
+    /// # Panics
+    /// If no room was made with [`ThreadTable::reserve`].
+    pub fn insert(&mut self, tcb: NonNull<ThreadControlBlock>) -> usize {
+        if let Some(index) = self.free.pop() {
+            self.slots.as_mut_slice()[index] = Some(tcb);
+            return index;
+        }
+        self.slots.push(Some(tcb));
+        self.slots.len - 1
+    }
+
+    /// Empty slot `index` for reuse and return what it held.
+    pub fn remove(&mut self, index: usize) -> Slot {
+        let tcb = self.slots.as_mut_slice()[index].take();
+        if tcb.is_some() {
+            self.free.push(index);
+        }
+        tcb
+    }
+}
+
+impl Default for ThreadTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for ThreadTable {
+    type Target = [Slot];
+
+    fn deref(&self) -> &[Slot] {
+        self.slots.as_slice()
+    }
+}
+
+impl DerefMut for ThreadTable {
+    fn deref_mut(&mut self) -> &mut [Slot] {
+        self.slots.as_mut_slice()
+    }
+}
+
+// Host tests have no kernel heap registered; they use the test harness's allocator.
+#[cfg(not(test))]
+mod heap {
+    use core::alloc::Layout;
+
+    pub fn alloc(layout: Layout) -> *mut u8 {
+        foundation::kfn::memory::kmalloc(layout)
+    }
+
+    pub fn free(ptr: *mut u8, layout: Layout) {
+        foundation::kfn::memory::kfree(ptr, layout)
+    }
+}
+
+#[cfg(test)]
+mod heap {
+    use core::alloc::Layout;
+
+    pub fn alloc(layout: Layout) -> *mut u8 {
+        unsafe { alloc::alloc::alloc(layout) }
+    }
+
+    pub fn free(ptr: *mut u8, layout: Layout) {
+        unsafe { alloc::alloc::dealloc(ptr, layout) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::ThreadState;
+
+    #[test]
+    fn test_reuses_freed_slots() {
+        let mut tcbs = [
+            ThreadControlBlock::stub(1, ThreadState::Running, 0),
+            ThreadControlBlock::stub(2, ThreadState::Ready, 0),
+            ThreadControlBlock::stub(3, ThreadState::Ready, 0),
+        ];
+        let [a, b, c] = tcbs.each_mut().map(NonNull::from);
+
+        let mut table = ThreadTable::new();
+        assert!(table.reserve(2));
+        assert_eq!(table.insert(a), 0);
+        assert!(table.reserve(2));
+        assert_eq!(table.insert(b), 1);
+        assert!(!table.reserve(2));
+
+        assert_eq!(table.remove(0), Some(a));
+        assert_eq!(table.remove(0), None);
+        assert_eq!((table.len(), table.occupied()), (2, 1));
+        assert!(table.reserve(2));
+        assert_eq!(table.insert(c), 0);
+        assert_eq!(&table[..], &[Some(c), Some(b)]);
+
+        // Growing past the first allocation keeps every slot.
+        for _ in 0..2 * INITIAL_CAPACITY {
+            assert!(table.reserve(usize::MAX));
+            table.insert(a);
+        }
+        assert_eq!(table.len(), 2 + 2 * INITIAL_CAPACITY);
+        assert_eq!(&table[..2], &[Some(c), Some(b)]);
+    }
+}
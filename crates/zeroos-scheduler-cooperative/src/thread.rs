@@ -1,6 +1,10 @@
 use alloc::alloc::Layout;
+use core::ptr::NonNull;
 use foundation::kfn::arch as karch;
 
+use crate::policy::{Priority, DEFAULT_PRIORITY};
+use crate::stats::RunStats;
+
 /// Thread ID type (arch-independent).
 pub type Tid = usize;
 
@@ -35,12 +39,35 @@ pub struct ThreadControlBlock {
     pub state: ThreadState,
     pub saved_pc: usize,
     pub futex_wait_addr: usize,
+    /// Scheduler tick at which a timed futex wait gives up.
+    pub futex_deadline: Option<u64>,
+    /// Set when the last futex wait ended by timeout rather than a wake.
+    pub futex_timed_out: bool,
+    /// Next thread in the same timer wheel bucket while `futex_deadline` is armed.
+    pub timer_next: Option<NonNull<ThreadControlBlock>>,
     pub clear_child_tid: usize,
+    /// Head of the robust futex list registered with `set_robust_list` (0 when none).
+    pub robust_list: usize,
+    /// TLS block the scheduler allocated for this thread (0 when the caller supplied `tp`).
+    pub tls_block: usize,
+    /// Lowest address of a scheduler-allocated stack (0 when the caller supplied one).
+    pub stack_base: usize,
+    pub stack_size: usize,
+
+    /// Code passed to `exit`; meaningful once `state` is `Exited`.
+    pub exit_code: i32,
+    /// Threads currently blocked joining this one.
+    pub join_waiters: usize,
 
     // Kernel stack base/size (low-level thread anchor lives at base).
     // This is conceptually independent of the scheduler; the scheduler just tracks it.
     pub kstack_base: usize,
     pub kstack_size: usize,
+
+    pub priority: Priority,
+    /// Turns left in the current round (weighted policy only).
+    pub credits: u32,
+    pub stats: RunStats,
 }
 
 pub const KSTACK_SIZE: usize = 16 * 1024; // 16KB kernel stack
@@ -83,9 +110,21 @@ impl ThreadControlBlock {
             state: ThreadState::Ready,
             saved_pc: initial_pc,
             futex_wait_addr: 0,
+            futex_deadline: None,
+            futex_timed_out: false,
+            timer_next: None,
             clear_child_tid: 0,
+            robust_list: 0,
+            tls_block: 0,
+            stack_base: 0,
+            stack_size: 0,
+            exit_code: 0,
+            join_waiters: 0,
             kstack_base: anchor_addr,
             kstack_size: KSTACK_SIZE,
+            priority: DEFAULT_PRIORITY,
+            credits: 0,
+            stats: RunStats::new(),
         }
     }
 
@@ -101,3 +140,32 @@ impl ThreadControlBlock {
         self.thread_ctx.as_mut_ptr()
     }
 }
+
+#[cfg(test)]
+impl ThreadControlBlock {
+    /// A TCB without kernel stack or arch context, for exercising scheduler bookkeeping.
+    pub(crate) fn stub(tid: Tid, state: ThreadState, priority: Priority) -> Self {
+        Self {
+            thread_ctx: ThreadContext(core::ptr::null_mut()),
+            tid,
+            state,
+            saved_pc: 0,
+            futex_wait_addr: 0,
+            futex_deadline: None,
+            futex_timed_out: false,
+            timer_next: None,
+            clear_child_tid: 0,
+            robust_list: 0,
+            tls_block: 0,
+            stack_base: 0,
+            stack_size: 0,
+            exit_code: 0,
+            join_waiters: 0,
+            kstack_base: 0,
+            kstack_size: 0,
+            priority,
+            credits: 0,
+            stats: RunStats::new(),
+        }
+    }
+}
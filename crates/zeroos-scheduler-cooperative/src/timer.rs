@@ -0,0 +1,256 @@
+//! The clock timed waits are measured against, and the threads waiting on it.
+//!
+//! [`Clock`] counts ticks of [`TICK_NS`](crate::TICK_NS). Without a [`TickSource`] it advances
+//! one tick per scheduling decision, so timeouts fire at the same point of every run whatever the
+//! platform; with one (see [`crate::set_tick_source`]) it follows that counter instead, e.g.
+//! executed cycles scaled to ticks. Either way the scheduler moves it straight to the earliest
+//! deadline when every thread is waiting, rather than spinning until the deadline comes.
+//!
+//! [`TimerWheel`] keeps the threads that have a deadline in [`WHEEL_SLOTS`] buckets, keyed by
+//! deadline modulo the slot count and linked through [`ThreadControlBlock::timer_next`]. Arming,
+//! disarming and expiring cost the length of a bucket, not a scan of the thread table.
+
+use core::ptr::NonNull;
+
+use crate::thread::ThreadControlBlock;
+
+/// Monotonic tick counter the [`Clock`] follows instead of scheduling decisions.
+pub type TickSource = fn() -> u64;
+
+/// Buckets of the [`TimerWheel`].
+pub const WHEEL_SLOTS: usize = 64;
+
+pub struct Clock {
+    source: Option<TickSource>,
+    now: u64,
+    /// Added to the source's readings: ticks jumped over by [`Clock::advance_to`], and the lead
+    /// the clock had when the source was set.
+    offset: u64,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock {
+    pub const fn new() -> Self {
+        Self {
+            source: None,
+            now: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Follow `source` from now on, or scheduling decisions if `None`. The clock never runs
+    /// backwards: a source behind it is offset to continue from the current tick.
+    pub fn set_source(&mut self, source: Option<TickSource>) {
+        self.source = source;
+        if let Some(source) = source {
+            self.offset = self.now.saturating_sub(source());
+        }
+    }
+
+    /// Account for one scheduling decision.
+    pub fn tick(&mut self) {
+        self.now = match self.source {
+            None => self.now + 1,
+            Some(source) => self.now.max(source().saturating_add(self.offset)),
+        };
+    }
+
+    /// Jump forward to `tick`; a tick already passed leaves the clock as it is.
+    pub fn advance_to(&mut self, tick: u64) {
+        if tick > self.now {
+            self.offset += tick - self.now;
+            self.now = tick;
+        }
+    }
+}
+
+pub struct TimerWheel {
+    slots: [Option<NonNull<ThreadControlBlock>>; WHEEL_SLOTS],
+    len: usize,
+    /// Every deadline up to this tick has been expired.
+    expired_to: u64,
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimerWheel {
+    pub const fn new() -> Self {
+        Self {
+            slots: [None; WHEEL_SLOTS],
+            len: 0,
+            expired_to: 0,
+        }
+    }
+
+    /// Threads with a deadline.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Arm `tcb` to expire at its `futex_deadline`, which must be past the last [`expire`].
+    ///
+    /// # Safety
+    /// `tcb` must be valid, not already armed, and stay valid until it is removed or expired.
+    ///
+    /// [`expire`]: TimerWheel::expire
+    pub unsafe fn insert(&mut self, mut tcb: NonNull<ThreadControlBlock>) {
+        let deadline = tcb
+            .as_ref()
+            .futex_deadline
+            .expect("armed without a deadline");
+        debug_assert!(deadline > self.expired_to);
+        let slot = &mut self.slots[slot(deadline)];
+        tcb.as_mut().timer_next = slot.replace(tcb);
+        self.len += 1;
+    }
+
+    /// Disarm `tcb`, before its deadline is cleared. Returns false if it was not armed.
+    ///
+    /// # Safety
+    /// Every armed TCB must be valid.
+    pub unsafe fn remove(&mut self, tcb: NonNull<ThreadControlBlock>) -> bool {
+        let Some(deadline) = tcb.as_ref().futex_deadline else {
+            return false;
+        };
+        let mut link = &mut self.slots[slot(deadline)];
+        while let Some(mut entry) = *link {
+            if entry == tcb {
+                *link = entry.as_mut().timer_next.take();
+                self.len -= 1;
+                return true;
+            }
+            link = &mut (*entry.as_ptr()).timer_next;
+        }
+        false
+    }
+
+    /// Disarm every thread whose deadline is at or before `now` and pass it to `expired`.
+    ///
+    /// # Safety
+    /// Every armed TCB must be valid.
+    pub unsafe fn expire(&mut self, now: u64, mut expired: impl FnMut(&mut ThreadControlBlock)) {
+        let from = self.expired_to;
+        if now <= from {
+            return;
+        }
+        self.expired_to = now;
+        if self.len == 0 {
+            return;
+        }
+        // Deadlines in (from, now] sit in the slots of those ticks; past a full turn, in all.
+        let turns = (now - from).min(WHEEL_SLOTS as u64);
+        for tick in from + 1..=from + turns {
+            let mut link = &mut self.slots[slot(tick)];
+            while let Some(mut entry) = *link {
+                let tcb = entry.as_mut();
+                if tcb.futex_deadline.is_some_and(|d| d <= now) {
+                    *link = tcb.timer_next.take();
+                    self.len -= 1;
+                    expired(tcb);
+                } else {
+                    link = &mut (*entry.as_ptr()).timer_next;
+                }
+            }
+        }
+    }
+
+    /// The earliest deadline armed.
+    pub fn next_deadline(&self) -> Option<u64> {
+        let mut earliest = None;
+        for head in &self.slots {
+            let mut next = *head;
+            while let Some(entry) = next {
+                // SAFETY: armed TCBs stay valid until removed or expired.
+                let tcb = unsafe { entry.as_ref() };
+                if let Some(deadline) = tcb.futex_deadline {
+                    earliest = Some(earliest.map_or(deadline, |e: u64| e.min(deadline)));
+                }
+                next = tcb.timer_next;
+            }
+        }
+        earliest
+    }
+}
+
+fn slot(tick: u64) -> usize {
+    (tick % WHEEL_SLOTS as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::{ThreadState, Tid};
+    use alloc::vec::Vec;
+
+    fn armed(wheel: &mut TimerWheel, tcbs: &mut [ThreadControlBlock]) {
+        for tcb in tcbs {
+            unsafe { wheel.insert(NonNull::from(tcb)) };
+        }
+    }
+
+    fn timed(tid: Tid, deadline: u64) -> ThreadControlBlock {
+        let mut tcb = ThreadControlBlock::stub(tid, ThreadState::Blocked, 0);
+        tcb.futex_deadline = Some(deadline);
+        tcb
+    }
+
+    #[test]
+    fn test_wheel_expires_in_deadline_order() {
+        // 3 and 67 share a slot; 200 is several turns out.
+        let mut tcbs = Vec::from([timed(1, 67), timed(2, 3), timed(3, 200), timed(4, 5)]);
+        let mut wheel = TimerWheel::new();
+        armed(&mut wheel, &mut tcbs);
+        assert_eq!(wheel.next_deadline(), Some(3));
+
+        let mut fired = Vec::new();
+        unsafe { wheel.expire(4, |tcb| fired.push(tcb.tid)) };
+        assert_eq!(fired, [2]);
+        assert!(unsafe { wheel.remove(NonNull::from(&mut tcbs[3])) });
+        assert!(!unsafe { wheel.remove(NonNull::from(&mut tcbs[3])) });
+        assert_eq!(wheel.next_deadline(), Some(67));
+
+        unsafe { wheel.expire(150, |tcb| fired.push(tcb.tid)) };
+        assert_eq!(fired, [2, 1]);
+        assert_eq!(wheel.len(), 1);
+        unsafe { wheel.expire(200, |tcb| fired.push(tcb.tid)) };
+        assert_eq!(fired, [2, 1, 3]);
+        assert!(wheel.is_empty());
+        assert_eq!(wheel.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_clock_follows_source_without_going_back() {
+        let mut clock = Clock::new();
+        clock.tick();
+        clock.advance_to(10);
+        clock.advance_to(4);
+        assert_eq!(clock.now(), 10);
+
+        clock.set_source(Some(|| 3));
+        clock.tick();
+        assert_eq!(clock.now(), 10);
+        clock.advance_to(12);
+        clock.tick();
+        assert_eq!(clock.now(), 12);
+        clock.set_source(Some(|| 20));
+        clock.tick();
+        assert_eq!(clock.now(), 20);
+    }
+}
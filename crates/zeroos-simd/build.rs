@@ -0,0 +1,13 @@
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(zeroos_rvv)");
+
+    // Vector kernels need a RISC-V target and either `+v` in the target features or the
+    // `rvv` feature; everything else gets the scalar fallbacks.
+    let arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    let features = std::env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default();
+    let riscv = arch == "riscv32" || arch == "riscv64";
+    let has_v = features.split(',').any(|f| f == "v");
+    if riscv && (has_v || std::env::var_os("CARGO_FEATURE_RVV").is_some()) {
+        println!("cargo::rustc-cfg=zeroos_rvv");
+    }
+}
@@ -0,0 +1,104 @@
+//! The theta and chi steps of Keccak-f[1600].
+//!
+//! The state is 25 lanes with lane `(x, y)` at index `x + 5 * y`, as in FIPS 202. Both steps
+//! work on whole rows of five lanes, which is what the vector kernels load at once; rho, pi and
+//! iota are per-lane rotations and moves and stay with the caller.
+
+/// Theta: xors every lane with the parities of two neighbouring columns.
+pub fn theta(a: &mut [u64; 25]) {
+    cfg_if::cfg_if! {
+        if #[cfg(zeroos_rvv)] {
+            // SAFETY: `a` is valid for reads and writes of 25 lanes.
+            unsafe { crate::rvv::keccak_theta(a.as_mut_ptr()) }
+        } else {
+            let mut c = [0u64; 5];
+            for (x, cx) in c.iter_mut().enumerate() {
+                *cx = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+            }
+            for x in 0..5 {
+                let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+                for y in 0..5 {
+                    a[x + 5 * y] ^= d;
+                }
+            }
+        }
+    }
+}
+
+/// Chi: the only non-linear step, `a[x] ^= !a[x + 1] & a[x + 2]` within each row.
+pub fn chi(a: &mut [u64; 25]) {
+    cfg_if::cfg_if! {
+        if #[cfg(zeroos_rvv)] {
+            // SAFETY: `a` is valid for reads and writes of 25 lanes.
+            unsafe { crate::rvv::keccak_chi(a.as_mut_ptr()) }
+        } else {
+            for row in a.chunks_exact_mut(5) {
+                let r = [row[0], row[1], row[2], row[3], row[4]];
+                for x in 0..5 {
+                    row[x] = r[x] ^ (!r[(x + 1) % 5] & r[(x + 2) % 5]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUND_CONSTANTS: [u64; 24] = [
+        0x0000_0000_0000_0001,
+        0x0000_0000_0000_8082,
+        0x8000_0000_0000_808A,
+        0x8000_0000_8000_8000,
+        0x0000_0000_0000_808B,
+        0x0000_0000_8000_0001,
+        0x8000_0000_8000_8081,
+        0x8000_0000_0000_8009,
+        0x0000_0000_0000_008A,
+        0x0000_0000_0000_0088,
+        0x0000_0000_8000_8009,
+        0x0000_0000_8000_000A,
+        0x0000_0000_8000_808B,
+        0x8000_0000_0000_008B,
+        0x8000_0000_0000_8089,
+        0x8000_0000_0000_8003,
+        0x8000_0000_0000_8002,
+        0x8000_0000_0000_0080,
+        0x0000_0000_0000_800A,
+        0x8000_0000_8000_000A,
+        0x8000_0000_8000_8081,
+        0x8000_0000_0000_8080,
+        0x0000_0000_8000_0001,
+        0x8000_0000_8000_8008,
+    ];
+
+    const RHO: [u32; 25] = [
+        0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56,
+        14,
+    ];
+
+    fn keccak_f(a: &mut [u64; 25]) {
+        for rc in ROUND_CONSTANTS {
+            theta(a);
+            // Rho and pi: lane (x, y) moves to (y, 2x + 3y).
+            let mut b = [0u64; 25];
+            for x in 0..5 {
+                for y in 0..5 {
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotate_left(RHO[x + 5 * y]);
+                }
+            }
+            *a = b;
+            chi(a);
+            a[0] ^= rc;
+        }
+    }
+
+    #[test]
+    fn test_keccak_f_zero_state() {
+        let mut a = [0u64; 25];
+        keccak_f(&mut a);
+        assert_eq!(a[0], 0xF125_8F79_40E1_DDE7);
+        assert_eq!(a[1], 0x84D5_CCF9_33C0_478A);
+    }
+}
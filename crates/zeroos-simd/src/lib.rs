@@ -0,0 +1,17 @@
+#![no_std]
+
+//! Data-parallel kernels for guests, vectorized with the RISC-V V extension when available.
+//!
+//! The vector path is picked at build time: it is used on RISC-V targets whose target features
+//! include `v`, or when the `rvv` feature is enabled. Otherwise each kernel compiles to plain
+//! scalar code with the same results, so callers never need their own `cfg`.
+
+pub mod keccak;
+pub mod matmul;
+pub mod mem;
+
+#[cfg(zeroos_rvv)]
+mod rvv;
+
+/// Whether the kernels in this build use the V extension.
+pub const VECTOR: bool = cfg!(zeroos_rvv);
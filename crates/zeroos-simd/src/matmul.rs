@@ -0,0 +1,65 @@
+//! Inner loops of integer matrix multiplication, in wrapping `u32` arithmetic.
+//!
+//! A row-major `C = A * B` is `axpy(&mut c[i], a[i][k], &b[k])` over `k`, which streams
+//! contiguous rows of `B`; `dot` serves the column-major (pre-transposed `B`) layout.
+
+/// Sum of `a[i] * b[i]`.
+///
+/// # Panics
+///
+/// Panics if the slices differ in length.
+pub fn dot(a: &[u32], b: &[u32]) -> u32 {
+    assert_eq!(a.len(), b.len(), "dot: length mismatch");
+    cfg_if::cfg_if! {
+        if #[cfg(zeroos_rvv)] {
+            // SAFETY: both slices are valid for `len` elements.
+            unsafe { crate::rvv::dot_u32(a.as_ptr(), b.as_ptr(), a.len()) }
+        } else {
+            a.iter()
+                .zip(b)
+                .fold(0u32, |acc, (&x, &y)| acc.wrapping_add(x.wrapping_mul(y)))
+        }
+    }
+}
+
+/// `out[i] += k * row[i]`.
+///
+/// # Panics
+///
+/// Panics if the slices differ in length.
+pub fn axpy(out: &mut [u32], k: u32, row: &[u32]) {
+    assert_eq!(out.len(), row.len(), "axpy: length mismatch");
+    cfg_if::cfg_if! {
+        if #[cfg(zeroos_rvv)] {
+            // SAFETY: both slices are valid for `len` elements and cannot overlap.
+            unsafe { crate::rvv::axpy_u32(out.as_mut_ptr(), k, row.as_ptr(), out.len()) }
+        } else {
+            for (o, &r) in out.iter_mut().zip(row) {
+                *o = o.wrapping_add(k.wrapping_mul(r));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_and_axpy() {
+        assert_eq!(dot(&[], &[]), 0);
+        assert_eq!(dot(&[1, 2, 3], &[4, 5, 6]), 32);
+        assert_eq!(dot(&[u32::MAX, 2], &[2, 1]), 0);
+
+        // 2x3 * 3x2 through row-streamed axpy.
+        let a = [[1, 2, 3], [4, 5, 6]];
+        let b = [[7, 8], [9, 10], [11, 12]];
+        let mut c = [[0u32; 2]; 2];
+        for (ci, ai) in c.iter_mut().zip(&a) {
+            for (&aik, bk) in ai.iter().zip(&b) {
+                axpy(ci, aik, bk);
+            }
+        }
+        assert_eq!(c, [[58, 64], [139, 154]]);
+    }
+}
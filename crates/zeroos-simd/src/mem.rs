@@ -0,0 +1,51 @@
+//! Bulk byte copy and fill.
+
+/// Copies `src` into `dst`.
+///
+/// # Panics
+///
+/// Panics if the slices differ in length, like [`slice::copy_from_slice`].
+pub fn copy(dst: &mut [u8], src: &[u8]) {
+    assert_eq!(dst.len(), src.len(), "copy: length mismatch");
+    cfg_if::cfg_if! {
+        if #[cfg(zeroos_rvv)] {
+            // SAFETY: both slices are valid for `len` bytes and cannot overlap (one is `&mut`).
+            unsafe { crate::rvv::copy(dst.as_mut_ptr(), src.as_ptr(), dst.len()) }
+        } else {
+            dst.copy_from_slice(src);
+        }
+    }
+}
+
+/// Sets every byte of `dst` to `byte`.
+pub fn fill(dst: &mut [u8], byte: u8) {
+    cfg_if::cfg_if! {
+        if #[cfg(zeroos_rvv)] {
+            // SAFETY: `dst` is valid for writes of `len` bytes.
+            unsafe { crate::rvv::fill(dst.as_mut_ptr(), byte, dst.len()) }
+        } else {
+            dst.fill(byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_and_fill() {
+        let src: [u8; 37] = core::array::from_fn(|i| i as u8);
+        let mut dst = [0u8; 37];
+        copy(&mut dst, &src);
+        assert_eq!(dst, src);
+
+        fill(&mut dst[3..20], 0xAB);
+        assert!(dst[..3].iter().zip(&src).all(|(a, b)| a == b));
+        assert!(dst[3..20].iter().all(|&b| b == 0xAB));
+        assert_eq!(dst[20..], src[20..]);
+
+        copy(&mut [], &[]);
+        fill(&mut [], 0);
+    }
+}
@@ -0,0 +1,219 @@
+//! Kernels written against the V extension.
+//!
+//! The assembler is told about `v` inside each block, so this builds for `imac` targets when the
+//! `rvv` feature asserts that the hart has it. Loops strip-mine with `vsetvli`; the Keccak
+//! kernels instead rely on the V minimum `VLEN` of 128 bits, which gives eight 64-bit lanes at
+//! `m4`, enough for a five-lane row plus the two lanes that wrap around it.
+
+use core::arch::asm;
+
+pub(crate) unsafe fn copy(dst: *mut u8, src: *const u8, len: usize) {
+    asm!(
+        ".option push",
+        ".option arch, +v",
+        "2:",
+        "vsetvli {vl}, {len}, e8, m8, ta, ma",
+        "vle8.v v0, ({src})",
+        "vse8.v v0, ({dst})",
+        "sub {len}, {len}, {vl}",
+        "add {src}, {src}, {vl}",
+        "add {dst}, {dst}, {vl}",
+        "bnez {len}, 2b",
+        ".option pop",
+        dst = inout(reg) dst => _,
+        src = inout(reg) src => _,
+        len = inout(reg) len => _,
+        vl = out(reg) _,
+        out("v0") _, out("v1") _, out("v2") _, out("v3") _,
+        out("v4") _, out("v5") _, out("v6") _, out("v7") _,
+        options(nostack),
+    );
+}
+
+pub(crate) unsafe fn fill(dst: *mut u8, byte: u8, len: usize) {
+    asm!(
+        ".option push",
+        ".option arch, +v",
+        "vsetvli zero, {vlmax}, e8, m8, ta, ma",
+        "vmv.v.x v0, {byte}",
+        "2:",
+        "vsetvli {vl}, {len}, e8, m8, ta, ma",
+        "vse8.v v0, ({dst})",
+        "sub {len}, {len}, {vl}",
+        "add {dst}, {dst}, {vl}",
+        "bnez {len}, 2b",
+        ".option pop",
+        dst = inout(reg) dst => _,
+        len = inout(reg) len => _,
+        byte = in(reg) byte as usize,
+        vlmax = in(reg) usize::MAX,
+        vl = out(reg) _,
+        out("v0") _, out("v1") _, out("v2") _, out("v3") _,
+        out("v4") _, out("v5") _, out("v6") _, out("v7") _,
+        options(nostack),
+    );
+}
+
+pub(crate) unsafe fn dot_u32(a: *const u32, b: *const u32, len: usize) -> u32 {
+    let sum: usize;
+    asm!(
+        ".option push",
+        ".option arch, +v",
+        // Zero the accumulator across all of VLMAX; the loop then keeps lanes past a short
+        // final `vl` intact (`tu`), so the reduction sees every partial sum.
+        "vsetvli zero, {vlmax}, e32, m4, ta, ma",
+        "vmv.v.i v8, 0",
+        "2:",
+        "vsetvli {vl}, {len}, e32, m4, tu, ma",
+        "vle32.v v0, ({a})",
+        "vle32.v v4, ({b})",
+        "vmacc.vv v8, v0, v4",
+        "sub {len}, {len}, {vl}",
+        "slli {vl}, {vl}, 2",
+        "add {a}, {a}, {vl}",
+        "add {b}, {b}, {vl}",
+        "bnez {len}, 2b",
+        "vsetvli zero, {vlmax}, e32, m4, ta, ma",
+        "vmv.s.x v12, zero",
+        "vredsum.vs v12, v8, v12",
+        "vmv.x.s {sum}, v12",
+        ".option pop",
+        a = inout(reg) a => _,
+        b = inout(reg) b => _,
+        len = inout(reg) len => _,
+        vlmax = in(reg) usize::MAX,
+        vl = out(reg) _,
+        sum = out(reg) sum,
+        out("v0") _, out("v1") _, out("v2") _, out("v3") _,
+        out("v4") _, out("v5") _, out("v6") _, out("v7") _,
+        out("v8") _, out("v9") _, out("v10") _, out("v11") _,
+        out("v12") _, out("v13") _, out("v14") _, out("v15") _,
+        options(nostack, readonly),
+    );
+    sum as u32
+}
+
+pub(crate) unsafe fn axpy_u32(out: *mut u32, k: u32, row: *const u32, len: usize) {
+    asm!(
+        ".option push",
+        ".option arch, +v",
+        "2:",
+        "vsetvli {vl}, {len}, e32, m4, ta, ma",
+        "vle32.v v0, ({row})",
+        "vle32.v v4, ({out})",
+        "vmacc.vx v4, {k}, v0",
+        "vse32.v v4, ({out})",
+        "sub {len}, {len}, {vl}",
+        "slli {vl}, {vl}, 2",
+        "add {row}, {row}, {vl}",
+        "add {out}, {out}, {vl}",
+        "bnez {len}, 2b",
+        ".option pop",
+        out = inout(reg) out => _,
+        row = inout(reg) row => _,
+        len = inout(reg) len => _,
+        k = in(reg) k as usize,
+        vl = out(reg) _,
+        out("v0") _, out("v1") _, out("v2") _, out("v3") _,
+        out("v4") _, out("v5") _, out("v6") _, out("v7") _,
+        options(nostack),
+    );
+}
+
+pub(crate) unsafe fn keccak_theta(a: *mut u64) {
+    asm!(
+        ".option push",
+        ".option arch, +v",
+        // Column parities C[x] into v0.
+        "vsetivli zero, 5, e64, m4, tu, ma",
+        "vle64.v v0, ({a})",
+        "addi {t}, {a}, 40",
+        "vle64.v v4, ({t})",
+        "vxor.vv v0, v0, v4",
+        "addi {t}, {a}, 80",
+        "vle64.v v4, ({t})",
+        "vxor.vv v0, v0, v4",
+        "addi {t}, {a}, 120",
+        "vle64.v v4, ({t})",
+        "vxor.vv v0, v0, v4",
+        "addi {t}, {a}, 160",
+        "vle64.v v4, ({t})",
+        "vxor.vv v0, v0, v4",
+        // v8 = [C4, C0, C1, C2, C3, C4, C0]: lanes 0..5 are C[x - 1], lanes 2..7 are C[x + 1].
+        "vslidedown.vi v12, v0, 4",
+        "vsetivli zero, 6, e64, m4, tu, ma",
+        "vslideup.vi v8, v0, 1",
+        "vsetivli zero, 1, e64, m4, tu, ma",
+        "vmv.v.v v8, v12",
+        "vsetivli zero, 7, e64, m4, tu, ma",
+        "vslideup.vi v8, v0, 6",
+        // D[x] = C[x - 1] ^ rotl(C[x + 1], 1) into v20.
+        "vsetivli zero, 5, e64, m4, tu, ma",
+        "vslidedown.vi v16, v8, 2",
+        "vsll.vi v20, v16, 1",
+        "vsrl.vx v24, v16, {shift}",
+        "vxor.vv v20, v20, v24",
+        "vxor.vv v20, v20, v8",
+        "vle64.v v4, ({a})",
+        "vxor.vv v4, v4, v20",
+        "vse64.v v4, ({a})",
+        "addi {t}, {a}, 40",
+        "vle64.v v4, ({t})",
+        "vxor.vv v4, v4, v20",
+        "vse64.v v4, ({t})",
+        "addi {t}, {a}, 80",
+        "vle64.v v4, ({t})",
+        "vxor.vv v4, v4, v20",
+        "vse64.v v4, ({t})",
+        "addi {t}, {a}, 120",
+        "vle64.v v4, ({t})",
+        "vxor.vv v4, v4, v20",
+        "vse64.v v4, ({t})",
+        "addi {t}, {a}, 160",
+        "vle64.v v4, ({t})",
+        "vxor.vv v4, v4, v20",
+        "vse64.v v4, ({t})",
+        ".option pop",
+        a = in(reg) a,
+        shift = in(reg) 63usize,
+        t = out(reg) _,
+        out("v0") _, out("v1") _, out("v2") _, out("v3") _,
+        out("v4") _, out("v5") _, out("v6") _, out("v7") _,
+        out("v8") _, out("v9") _, out("v10") _, out("v11") _,
+        out("v12") _, out("v13") _, out("v14") _, out("v15") _,
+        out("v16") _, out("v17") _, out("v18") _, out("v19") _,
+        out("v20") _, out("v21") _, out("v22") _, out("v23") _,
+        out("v24") _, out("v25") _, out("v26") _, out("v27") _,
+        options(nostack),
+    );
+}
+
+pub(crate) unsafe fn keccak_chi(a: *mut u64) {
+    for y in 0..5 {
+        asm!(
+            ".option push",
+            ".option arch, +v",
+            // v8 = [r0, r1, r2, r3, r4, r0, r1], so sliding it down by 1 and 2 gives
+            // r[x + 1] and r[x + 2] with the wrap-around built in.
+            "vsetivli zero, 5, e64, m4, tu, ma",
+            "vle64.v v0, ({row})",
+            "vmv.v.v v8, v0",
+            "vsetivli zero, 7, e64, m4, tu, ma",
+            "vslideup.vi v8, v0, 5",
+            "vsetivli zero, 5, e64, m4, tu, ma",
+            "vslidedown.vi v12, v8, 1",
+            "vslidedown.vi v16, v8, 2",
+            "vxor.vi v12, v12, -1",
+            "vand.vv v12, v12, v16",
+            "vxor.vv v0, v0, v12",
+            "vse64.v v0, ({row})",
+            ".option pop",
+            row = in(reg) a.add(5 * y),
+            out("v0") _, out("v1") _, out("v2") _, out("v3") _,
+            out("v8") _, out("v9") _, out("v10") _, out("v11") _,
+            out("v12") _, out("v13") _, out("v14") _, out("v15") _,
+            out("v16") _, out("v17") _, out("v18") _, out("v19") _,
+            options(nostack),
+        );
+    }
+}
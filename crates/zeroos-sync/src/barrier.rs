@@ -0,0 +1,98 @@
+//! Reusable barrier for phased work.
+//!
+//! A [`Barrier`] for `n` threads blocks each caller of [`Barrier::wait`] until the `n`th arrives,
+//! then releases them all and resets for the next phase. Waiters park on a generation counter
+//! that the last arrival bumps, and go back to sleep if woken while it has not moved, so a
+//! spurious wakeup never lets a thread through early.
+
+use core::sync::atomic::{AtomicI32, Ordering};
+
+use foundation::kfn::scheduler as ksched;
+use spin::Mutex;
+
+pub struct Barrier {
+    n: usize,
+    /// Threads that arrived in the current generation.
+    arrived: Mutex<usize>,
+    /// Futex word, bumped each time the barrier opens.
+    generation: AtomicI32,
+}
+
+/// Returned by [`Barrier::wait`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// True for exactly one thread per phase: the one whose arrival opened the barrier.
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    /// A barrier that opens once `n` threads wait on it. `0` behaves like `1`.
+    pub const fn new(n: usize) -> Self {
+        Self {
+            n,
+            arrived: Mutex::new(0),
+            generation: AtomicI32::new(0),
+        }
+    }
+
+    /// Block until `n` threads, this one included, have called `wait` in this phase.
+    pub fn wait(&self) -> BarrierWaitResult {
+        let mut arrived = self.arrived.lock();
+        let generation = self.generation.load(Ordering::Relaxed);
+        *arrived += 1;
+        if *arrived < self.n {
+            drop(arrived);
+            while self.generation.load(Ordering::Acquire) == generation {
+                let _ = ksched::kwait_on_addr(self.generation.as_ptr() as usize, generation);
+            }
+            return BarrierWaitResult(false);
+        }
+        *arrived = 0;
+        self.generation
+            .store(generation.wrapping_add(1), Ordering::Release);
+        drop(arrived);
+        ksched::kwake_on_addr(self.generation.as_ptr() as usize, usize::MAX);
+        BarrierWaitResult(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_single_thread_barrier_opens_at_once() {
+        testing::install();
+        let barrier = Barrier::new(0);
+        assert!(barrier.wait().is_leader());
+        assert!(barrier.wait().is_leader());
+    }
+
+    #[test]
+    fn test_phases_stay_in_step() {
+        use core::sync::atomic::AtomicUsize;
+
+        const THREADS: usize = 4;
+        const PHASES: usize = 50;
+        static BARRIER: Barrier = Barrier::new(THREADS);
+        static DONE: [AtomicUsize; PHASES] = [const { AtomicUsize::new(0) }; PHASES];
+        static LEADERS: AtomicUsize = AtomicUsize::new(0);
+
+        testing::run_threads(THREADS, |_| {
+            for done in &DONE {
+                done.fetch_add(1, Ordering::Relaxed);
+                if BARRIER.wait().is_leader() {
+                    LEADERS.fetch_add(1, Ordering::Relaxed);
+                }
+                // Nobody gets past the barrier before everyone finished the phase.
+                assert_eq!(done.load(Ordering::Relaxed), THREADS);
+            }
+        });
+        assert_eq!(LEADERS.load(Ordering::Relaxed), PHASES);
+    }
+}
@@ -0,0 +1,308 @@
+//! Fixed-capacity MPSC channel.
+//!
+//! A [`Channel`] owns a ring of `N` slots. [`Channel::split`] hands out one [`Receiver`] and a
+//! [`Sender`] that can be cloned for more producers. `send` blocks while the ring is full and
+//! `recv` blocks while it is empty; dropping every sender (or the receiver) disconnects the
+//! channel and wakes the other side.
+
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+
+use foundation::kfn::scheduler as ksched;
+use spin::Mutex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecvError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+struct Ring<T, const N: usize> {
+    slots: [MaybeUninit<T>; N],
+    head: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> Ring<T, N> {
+    fn push(&mut self, value: T) -> Result<(), T> {
+        if self.len == N {
+            return Err(value);
+        }
+        self.slots[(self.head + self.len) % N].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        // SAFETY: the `len` slots starting at `head` are initialized.
+        let value = unsafe { self.slots[self.head].assume_init_read() };
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(value)
+    }
+}
+
+/// Futex word bumped on every state change, plus the number of threads parked on it.
+struct Event {
+    seq: AtomicI32,
+    waiters: AtomicUsize,
+}
+
+impl Event {
+    const fn new() -> Self {
+        Self {
+            seq: AtomicI32::new(0),
+            waiters: AtomicUsize::new(0),
+        }
+    }
+
+    fn notify(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        if self.waiters.load(Ordering::Acquire) != 0 {
+            ksched::kwake_on_addr(self.seq.as_ptr() as usize, usize::MAX);
+        }
+    }
+
+    /// Block until `notify` runs after `seq` was sampled.
+    fn wait(&self, seq: i32) {
+        self.waiters.fetch_add(1, Ordering::AcqRel);
+        let _ = ksched::kwait_on_addr(self.seq.as_ptr() as usize, seq);
+        self.waiters.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+pub struct Channel<T, const N: usize> {
+    ring: Mutex<Ring<T, N>>,
+    /// Signalled when an item is queued or the last sender goes away.
+    not_empty: Event,
+    /// Signalled when an item is taken or the receiver goes away.
+    not_full: Event,
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+    split: AtomicBool,
+}
+
+// SAFETY: items only move between threads through the mutex-guarded ring.
+unsafe impl<T: Send, const N: usize> Sync for Channel<T, N> {}
+unsafe impl<T: Send, const N: usize> Send for Channel<T, N> {}
+
+impl<T, const N: usize> Channel<T, N> {
+    pub const fn new() -> Self {
+        assert!(N > 0, "channel capacity must be non-zero");
+        Self {
+            ring: Mutex::new(Ring {
+                slots: [const { MaybeUninit::uninit() }; N],
+                head: 0,
+                len: 0,
+            }),
+            not_empty: Event::new(),
+            not_full: Event::new(),
+            senders: AtomicUsize::new(0),
+            receiver_alive: AtomicBool::new(false),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Take the two ends of the channel. Returns `None` if it was already split.
+    pub fn split(&self) -> Option<(Sender<'_, T, N>, Receiver<'_, T, N>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        self.senders.store(1, Ordering::Release);
+        self.receiver_alive.store(true, Ordering::Release);
+        Some((Sender { chan: self }, Receiver { chan: self }))
+    }
+
+    fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut ring = self.ring.lock();
+        if !self.receiver_alive.load(Ordering::Acquire) {
+            return Err(TrySendError::Disconnected(value));
+        }
+        ring.push(value).map_err(TrySendError::Full)?;
+        drop(ring);
+        self.not_empty.notify();
+        Ok(())
+    }
+
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut ring = self.ring.lock();
+        match ring.pop() {
+            Some(value) => {
+                drop(ring);
+                self.not_full.notify();
+                Ok(value)
+            }
+            None if self.senders.load(Ordering::Acquire) == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for Channel<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for Channel<T, N> {
+    fn drop(&mut self) {
+        while self.ring.get_mut().pop().is_some() {}
+    }
+}
+
+pub struct Sender<'a, T, const N: usize> {
+    chan: &'a Channel<T, N>,
+}
+
+impl<T, const N: usize> Sender<'_, T, N> {
+    /// Queue `value`, blocking while the channel is full.
+    pub fn send(&self, mut value: T) -> Result<(), SendError<T>> {
+        loop {
+            let seq = self.chan.not_full.seq.load(Ordering::Acquire);
+            match self.chan.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(v)) => return Err(SendError(v)),
+                Err(TrySendError::Full(v)) => {
+                    value = v;
+                    self.chan.not_full.wait(seq);
+                }
+            }
+        }
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.chan.try_send(value)
+    }
+}
+
+impl<T, const N: usize> Clone for Sender<'_, T, N> {
+    fn clone(&self) -> Self {
+        self.chan.senders.fetch_add(1, Ordering::AcqRel);
+        Self { chan: self.chan }
+    }
+}
+
+impl<T, const N: usize> Drop for Sender<'_, T, N> {
+    fn drop(&mut self) {
+        if self.chan.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.chan.not_empty.notify();
+        }
+    }
+}
+
+pub struct Receiver<'a, T, const N: usize> {
+    chan: &'a Channel<T, N>,
+}
+
+impl<T, const N: usize> Receiver<'_, T, N> {
+    /// Take the oldest item, blocking while the channel is empty. Fails once it is empty and
+    /// every sender has been dropped.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        loop {
+            let seq = self.chan.not_empty.seq.load(Ordering::Acquire);
+            match self.chan.try_recv() {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => self.chan.not_empty.wait(seq),
+            }
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.chan.try_recv()
+    }
+
+    /// Items until the channel is empty and disconnected.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(move || self.recv().ok())
+    }
+}
+
+impl<T, const N: usize> Drop for Receiver<'_, T, N> {
+    fn drop(&mut self) {
+        self.chan.receiver_alive.store(false, Ordering::Release);
+        self.chan.not_full.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fifo_and_capacity() {
+        let chan = Channel::<u32, 3>::new();
+        let (tx, rx) = chan.split().unwrap();
+        assert!(chan.split().is_none());
+
+        for i in 0..3 {
+            tx.try_send(i).unwrap();
+        }
+        assert_eq!(tx.try_send(9), Err(TrySendError::Full(9)));
+        assert_eq!(rx.try_recv(), Ok(0));
+        tx.send(3).unwrap();
+        assert_eq!([rx.recv(), rx.recv(), rx.recv()], [Ok(1), Ok(2), Ok(3)]);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let chan = Channel::<u32, 2>::new();
+        let (tx, rx) = chan.split().unwrap();
+        let tx2 = tx.clone();
+        tx.send(1).unwrap();
+        drop(tx);
+        tx2.send(2).unwrap();
+        drop(tx2);
+        // Queued items drain before the receiver sees the disconnect.
+        assert_eq!(rx.iter().sum::<u32>(), 3);
+        assert_eq!(rx.recv(), Err(RecvError));
+    }
+
+    #[test]
+    fn test_send_after_receiver_dropped() {
+        let chan = Channel::<u32, 2>::new();
+        let (tx, rx) = chan.split().unwrap();
+        drop(rx);
+        assert_eq!(tx.send(7), Err(SendError(7)));
+    }
+
+    #[test]
+    fn test_drop_releases_queued_items() {
+        use core::sync::atomic::AtomicUsize;
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        struct Counted;
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        {
+            let chan = Channel::<Counted, 4>::new();
+            let (tx, _rx) = chan.split().unwrap();
+            assert!(tx.send(Counted).is_ok());
+            assert!(tx.send(Counted).is_ok());
+        }
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+    }
+}
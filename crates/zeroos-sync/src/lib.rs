@@ -0,0 +1,19 @@
+//! Blocking primitives for threads on the ZeroOS scheduler.
+//!
+//! Waiting goes through `kfn::scheduler::kwait_on_addr`, so a blocked thread yields the hart
+//! instead of spinning. The primitives call the kernel directly: use them from no_std guests and
+//! kernel code, not from behind libc.
+
+#![no_std]
+
+pub mod barrier;
+pub mod channel;
+pub mod rwlock;
+pub mod semaphore;
+#[cfg(test)]
+mod testing;
+
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use channel::{Channel, Receiver, RecvError, SendError, Sender, TryRecvError, TrySendError};
+pub use rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+pub use semaphore::Semaphore;
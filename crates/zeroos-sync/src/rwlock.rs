@@ -0,0 +1,376 @@
+//! Reader-writer lock that prefers writers and never spins.
+//!
+//! Lock and unlock are one atomic operation each while uncontended, so readers only reach the
+//! kernel when a writer holds or waits for the lock. Contended callers park straight away rather
+//! than spinning first: under the cooperative scheduler the holder cannot make progress until
+//! the waiter yields, so spinning only burns cycles.
+//!
+//! Once a writer waits, new readers queue behind it, so a steady stream of readers cannot starve
+//! writers. Readers park on the state word, writers on a separate notification counter; both
+//! re-check the state after every wakeup.
+
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use foundation::kfn::scheduler as ksched;
+
+const READ_LOCKED: u32 = 1;
+const MASK: u32 = (1 << 30) - 1;
+/// All reader bits set: held by a writer.
+const WRITE_LOCKED: u32 = MASK;
+const MAX_READERS: u32 = MASK - 1;
+const READERS_WAITING: u32 = 1 << 30;
+const WRITERS_WAITING: u32 = 1 << 31;
+
+fn is_unlocked(state: u32) -> bool {
+    state & MASK == 0
+}
+
+fn is_read_lockable(state: u32) -> bool {
+    state & MASK < MAX_READERS && state & (READERS_WAITING | WRITERS_WAITING) == 0
+}
+
+pub struct RwLock<T: ?Sized> {
+    /// Futex word for readers: the reader count, or [`WRITE_LOCKED`], plus the waiting bits.
+    state: AtomicU32,
+    /// Futex word for writers, bumped to wake one.
+    writer_notify: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+// SAFETY: the lock hands out `&T` to many threads at once and `&mut T` to one at a time.
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: AtomicU32::new(0),
+            writer_notify: AtomicU32::new(0),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Share the lock, blocking while a writer holds it or waits for it.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        if !self.try_read_lock() {
+            self.read_contended();
+        }
+        RwLockReadGuard { lock: self }
+    }
+
+    /// Share the lock if that needs no waiting.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.try_read_lock().then(|| RwLockReadGuard { lock: self })
+    }
+
+    /// Take the lock exclusively, blocking while anyone else holds it.
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        if !self.try_write_lock() {
+            self.write_contended();
+        }
+        RwLockWriteGuard { lock: self }
+    }
+
+    /// Take the lock exclusively if nobody holds it.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        self.try_write_lock()
+            .then(|| RwLockWriteGuard { lock: self })
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    fn try_read_lock(&self) -> bool {
+        let state = self.state.load(Ordering::Relaxed);
+        is_read_lockable(state)
+            && self
+                .state
+                .compare_exchange(
+                    state,
+                    state + READ_LOCKED,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    fn try_write_lock(&self) -> bool {
+        self.state
+            .compare_exchange(0, WRITE_LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    fn read_contended(&self) {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if is_read_lockable(state) {
+                match self.state.compare_exchange(
+                    state,
+                    state + READ_LOCKED,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return,
+                    Err(s) => {
+                        state = s;
+                        continue;
+                    }
+                }
+            }
+            assert!(state & MASK != MAX_READERS, "too many readers on RwLock");
+
+            // Tell the unlocking side that readers need a wake.
+            if state & READERS_WAITING == 0 {
+                if let Err(s) = self.state.compare_exchange(
+                    state,
+                    state | READERS_WAITING,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    state = s;
+                    continue;
+                }
+            }
+            let expected = (state | READERS_WAITING) as i32;
+            let _ = ksched::kwait_on_addr(self.state.as_ptr() as usize, expected);
+            state = self.state.load(Ordering::Relaxed);
+        }
+    }
+
+    fn write_contended(&self) {
+        let mut state = self.state.load(Ordering::Relaxed);
+        // Once this writer has waited, others may still be waiting: keep the bit when locking.
+        let mut other_writers_waiting = 0;
+        loop {
+            if is_unlocked(state) {
+                match self.state.compare_exchange(
+                    state,
+                    state | WRITE_LOCKED | other_writers_waiting,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return,
+                    Err(s) => {
+                        state = s;
+                        continue;
+                    }
+                }
+            }
+
+            if state & WRITERS_WAITING == 0 {
+                if let Err(s) = self.state.compare_exchange(
+                    state,
+                    state | WRITERS_WAITING,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    state = s;
+                    continue;
+                }
+            }
+            other_writers_waiting = WRITERS_WAITING;
+
+            // Sample the counter before re-checking, so an unlock in between changes it and the
+            // wait returns at once.
+            let seq = self.writer_notify.load(Ordering::Acquire);
+            state = self.state.load(Ordering::Relaxed);
+            if is_unlocked(state) || state & WRITERS_WAITING == 0 {
+                continue;
+            }
+            let _ = ksched::kwait_on_addr(self.writer_notify.as_ptr() as usize, seq as i32);
+            state = self.state.load(Ordering::Relaxed);
+        }
+    }
+
+    /// # Safety
+    /// The caller must hold a read lock.
+    unsafe fn read_unlock(&self) {
+        let state = self.state.fetch_sub(READ_LOCKED, Ordering::Release) - READ_LOCKED;
+        // Readers only wait while a writer does, so the last reader out only has writers to wake.
+        if is_unlocked(state) && state & WRITERS_WAITING != 0 {
+            self.wake_writer_or_readers(state);
+        }
+    }
+
+    /// # Safety
+    /// The caller must hold the write lock.
+    unsafe fn write_unlock(&self) {
+        let state = self.state.fetch_sub(WRITE_LOCKED, Ordering::Release) - WRITE_LOCKED;
+        if state & (READERS_WAITING | WRITERS_WAITING) != 0 {
+            self.wake_writer_or_readers(state);
+        }
+    }
+
+    /// Hand an unlocked lock on: to one writer if any waits, else to every waiting reader.
+    fn wake_writer_or_readers(&self, mut state: u32) {
+        if state == WRITERS_WAITING {
+            match self
+                .state
+                .compare_exchange(state, 0, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => {
+                    self.wake_writer();
+                    return;
+                }
+                Err(s) => state = s,
+            }
+        }
+
+        if state == READERS_WAITING | WRITERS_WAITING {
+            if self
+                .state
+                .compare_exchange(state, READERS_WAITING, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+            {
+                // Locked again in the meantime; its unlock will wake the waiters.
+                return;
+            }
+            if self.wake_writer() {
+                return;
+            }
+            // No writer was parked after all: let the readers in.
+            state = READERS_WAITING;
+        }
+
+        if state == READERS_WAITING
+            && self
+                .state
+                .compare_exchange(state, 0, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            ksched::kwake_on_addr(self.state.as_ptr() as usize, usize::MAX);
+        }
+    }
+
+    /// Wake one writer; false if none was parked.
+    fn wake_writer(&self) -> bool {
+        self.writer_notify.fetch_add(1, Ordering::Release);
+        ksched::kwake_on_addr(self.writer_notify.as_ptr() as usize, 1) != 0
+    }
+}
+
+pub struct RwLockReadGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: readers only share the data.
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard holds a read lock.
+        unsafe { self.lock.read_unlock() }
+    }
+}
+
+pub struct RwLockWriteGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard holds the write lock.
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard holds the write lock.
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard holds the write lock.
+        unsafe { self.lock.write_unlock() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_readers_share_writers_exclude() {
+        let lock = RwLock::new(5);
+        {
+            let a = lock.read();
+            let b = lock.read();
+            assert_eq!(*a + *b, 10);
+            assert!(lock.try_write().is_none());
+        }
+        {
+            let mut w = lock.write();
+            *w += 1;
+            assert!(lock.try_read().is_none());
+            assert!(lock.try_write().is_none());
+        }
+        assert_eq!(*lock.try_read().unwrap(), 6);
+        assert_eq!(lock.into_inner(), 6);
+    }
+
+    #[test]
+    fn test_waiting_writer_holds_off_new_readers() {
+        static LOCK: RwLock<u32> = RwLock::new(0);
+
+        testing::run_threads(2, |i| {
+            if i == 0 {
+                let held = LOCK.read();
+                // Readers keep getting in until the writer queues up.
+                while LOCK.try_read().is_some() {
+                    testing::yield_now();
+                }
+                assert_eq!(*held, 0);
+            } else {
+                *LOCK.write() += 1;
+            }
+        });
+        assert_eq!(*LOCK.read(), 1);
+    }
+
+    #[test]
+    fn test_writes_are_never_seen_half_done() {
+        static LOCK: RwLock<(u64, u64)> = RwLock::new((0, 0));
+
+        testing::run_threads(6, |i| {
+            for _ in 0..200 {
+                if i % 3 == 0 {
+                    let mut pair = LOCK.write();
+                    pair.0 += 1;
+                    testing::yield_now();
+                    pair.1 += 1;
+                } else {
+                    let pair = LOCK.read();
+                    assert_eq!(pair.0, pair.1);
+                }
+            }
+        });
+        assert_eq!(*LOCK.read(), (400, 400));
+    }
+}
@@ -0,0 +1,102 @@
+//! Counting semaphore.
+//!
+//! A [`Semaphore`] holds a number of permits. [`Semaphore::acquire`] takes one, blocking while
+//! none are left, and [`Semaphore::release`] puts one back and wakes a waiter. The permit count
+//! is the futex word itself: a woken thread tries to take a permit again and parks once more if
+//! another thread got there first, so spurious wakeups cost a retry and nothing else.
+
+use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
+use foundation::kfn::scheduler as ksched;
+
+pub struct Semaphore {
+    /// Futex word: permits left.
+    permits: AtomicI32,
+    /// Threads parked in `acquire`; `release` skips the wake syscall while there are none.
+    waiters: AtomicUsize,
+}
+
+impl Semaphore {
+    /// A semaphore with `permits` permits, at most `i32::MAX`.
+    pub const fn new(permits: usize) -> Self {
+        assert!(permits <= i32::MAX as usize, "too many permits");
+        Self {
+            permits: AtomicI32::new(permits as i32),
+            waiters: AtomicUsize::new(0),
+        }
+    }
+
+    /// Permits left right now.
+    pub fn available(&self) -> usize {
+        self.permits.load(Ordering::Acquire) as usize
+    }
+
+    /// Take a permit if one is left.
+    pub fn try_acquire(&self) -> bool {
+        self.permits
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |n| {
+                (n > 0).then(|| n - 1)
+            })
+            .is_ok()
+    }
+
+    /// Take a permit, blocking until one is released.
+    pub fn acquire(&self) {
+        while !self.try_acquire() {
+            self.waiters.fetch_add(1, Ordering::SeqCst);
+            // Returns at once if a permit showed up since `try_acquire` looked.
+            let _ = ksched::kwait_on_addr(self.permits.as_ptr() as usize, 0);
+            self.waiters.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Put a permit back, waking one blocked [`Semaphore::acquire`].
+    pub fn release(&self) {
+        let prev = self.permits.fetch_add(1, Ordering::SeqCst);
+        assert!(prev < i32::MAX, "semaphore permit count overflowed");
+        if self.waiters.load(Ordering::SeqCst) != 0 {
+            ksched::kwake_on_addr(self.permits.as_ptr() as usize, 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_permits_count_down_and_up() {
+        let sem = Semaphore::new(2);
+        assert!(sem.try_acquire());
+        sem.acquire();
+        assert!(!sem.try_acquire());
+        assert_eq!(sem.available(), 0);
+        sem.release();
+        assert_eq!(sem.available(), 1);
+        sem.acquire();
+        assert!(!sem.try_acquire());
+    }
+
+    #[test]
+    fn test_never_more_holders_than_permits() {
+        const THREADS: usize = 6;
+        const PERMITS: usize = 2;
+        static SEM: Semaphore = Semaphore::new(PERMITS);
+        static HOLDERS: AtomicUsize = AtomicUsize::new(0);
+        static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+        testing::run_threads(THREADS, |_| {
+            for _ in 0..100 {
+                SEM.acquire();
+                let holders = HOLDERS.fetch_add(1, Ordering::SeqCst) + 1;
+                PEAK.fetch_max(holders, Ordering::SeqCst);
+                testing::yield_now();
+                HOLDERS.fetch_sub(1, Ordering::SeqCst);
+                SEM.release();
+            }
+        });
+        assert!(PEAK.load(Ordering::SeqCst) <= PERMITS);
+        assert_eq!(SEM.available(), PERMITS);
+    }
+}
@@ -0,0 +1,131 @@
+//! Host stand-in for the scheduler's wait/wake calls, so tests can block on real threads.
+//!
+//! Every wake releases every waiter, whatever address it waits on, and every other wait returns
+//! without being woken at all, so the primitives see far more spurious wakeups here than on the
+//! cooperative scheduler.
+
+extern crate std;
+
+use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, Once};
+
+use foundation::ops::SchedulerBackend;
+
+const EAGAIN: isize = 11;
+
+struct HostFutex {
+    /// Bumped by every wake.
+    wakes: Mutex<u64>,
+    woken: Condvar,
+    waits: AtomicUsize,
+}
+
+static HOST: HostFutex = HostFutex {
+    wakes: Mutex::new(0),
+    woken: Condvar::new(),
+    waits: AtomicUsize::new(0),
+};
+
+static INSTALL: Once = Once::new();
+
+/// Register the host futex as the scheduler.
+pub fn install() {
+    INSTALL.call_once(|| foundation::register_scheduler_backend(&HOST));
+}
+
+/// Run `f(0)` .. `f(n - 1)` on `n` host threads and wait for all of them.
+pub fn run_threads(n: usize, f: impl Fn(usize) + Sync) {
+    install();
+    std::thread::scope(|s| {
+        for i in 0..n {
+            let f = &f;
+            s.spawn(move || f(i));
+        }
+    });
+}
+
+pub fn yield_now() {
+    std::thread::yield_now();
+}
+
+impl SchedulerBackend for HostFutex {
+    fn init(&self) -> usize {
+        0
+    }
+
+    fn spawn_thread(&self, _: usize, _: usize, _: usize, _: usize, _: usize) -> isize {
+        unimplemented!("spawn in a host test")
+    }
+
+    fn yield_now(&self) -> isize {
+        std::thread::yield_now();
+        0
+    }
+
+    fn preempt(&self) {}
+
+    fn exit_current(&self, _code: i32) -> isize {
+        unimplemented!("exit in a host test")
+    }
+
+    fn current_tid(&self) -> usize {
+        1
+    }
+
+    fn thread_count(&self) -> usize {
+        1
+    }
+
+    fn wait_on_addr(&self, addr: usize, expected: i32) -> isize {
+        let mut wakes = self.wakes.lock().unwrap();
+        // Checked under the lock a waker must take, so a wake after the check is not missed.
+        let current = unsafe { (*(addr as *const AtomicI32)).load(Ordering::SeqCst) };
+        if current != expected {
+            return -EAGAIN;
+        }
+        if self.waits.fetch_add(1, Ordering::Relaxed).is_multiple_of(2) {
+            drop(wakes);
+            std::thread::yield_now();
+            return 0;
+        }
+        let seen = *wakes;
+        while *wakes == seen {
+            wakes = self.woken.wait(wakes).unwrap();
+        }
+        0
+    }
+
+    fn wake_on_addr(&self, _addr: usize, count: usize) -> usize {
+        *self.wakes.lock().unwrap() += 1;
+        self.woken.notify_all();
+        count
+    }
+
+    fn wait_on_addr_timeout(&self, addr: usize, expected: i32, _timeout_ns: u64) -> isize {
+        self.wait_on_addr(addr, expected)
+    }
+
+    fn now_ns(&self) -> u64 {
+        0
+    }
+
+    fn requeue_on_addr(&self, addr: usize, _: usize, _: usize, _: usize) -> usize {
+        self.wake_on_addr(addr, usize::MAX)
+    }
+
+    fn join(&self, _tid: usize, _status_ptr: usize, _nohang: bool) -> isize {
+        unimplemented!("join in a host test")
+    }
+
+    fn set_clear_on_exit_addr(&self, _addr: usize) -> isize {
+        1
+    }
+
+    fn set_robust_list(&self, _head: usize) -> isize {
+        0
+    }
+
+    fn robust_list(&self, _tid: usize) -> isize {
+        0
+    }
+}
@@ -0,0 +1,81 @@
+//! Per-worker task deques.
+
+// Only `scope` uses these, and it needs the no_std spawn API.
+#![cfg_attr(not(target_os = "none"), allow(dead_code))]
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use spin::Mutex;
+
+pub(crate) type Task<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// Per-worker task deques with stealing.
+pub(crate) struct Deques<'a> {
+    queues: Vec<Mutex<VecDeque<Task<'a>>>>,
+    next: AtomicUsize,
+}
+
+impl<'a> Deques<'a> {
+    pub(crate) fn new(workers: usize) -> Self {
+        Self {
+            queues: (0..workers.max(1))
+                .map(|_| Mutex::new(VecDeque::new()))
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn push(&self, task: Task<'a>) {
+        let i = self.next.fetch_add(1, Ordering::Relaxed) % self.queues.len();
+        self.queues[i].lock().push_back(task);
+    }
+
+    /// Oldest task of `worker`'s own deque, else the newest task of another worker's.
+    pub(crate) fn pop(&self, worker: usize) -> Option<Task<'a>> {
+        let n = self.queues.len();
+        let own = worker % n;
+        if let Some(task) = self.queues[own].lock().pop_front() {
+            return Some(task);
+        }
+        (1..n).find_map(|k| self.queues[(own + k) % n].lock().pop_back())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task<'a>(log: &'a Mutex<Vec<u32>>, id: u32) -> Task<'a> {
+        Box::new(move || log.lock().push(id))
+    }
+
+    #[test]
+    fn test_deques_round_robin_and_steal() {
+        let log = Mutex::new(Vec::new());
+        let d = Deques::new(2);
+        for id in 0..4 {
+            d.push(task(&log, id));
+        }
+        // Worker 0 drains its own deque in order: 0, 2.
+        d.pop(0).unwrap()();
+        d.pop(0).unwrap()();
+        // Then steals worker 1's newest first: 3, 1.
+        d.pop(0).unwrap()();
+        d.pop(0).unwrap()();
+        assert!(d.pop(0).is_none());
+        assert!(d.pop(1).is_none());
+        assert_eq!(*log.lock(), [0, 2, 3, 1]);
+    }
+
+    #[test]
+    fn test_zero_workers_still_has_a_deque() {
+        let log = Mutex::new(Vec::new());
+        let d = Deques::new(0);
+        d.push(task(&log, 7));
+        d.pop(5).unwrap()();
+        assert_eq!(*log.lock(), [7]);
+    }
+}
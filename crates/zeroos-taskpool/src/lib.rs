@@ -0,0 +1,28 @@
+//! Scoped task pool for no_std guests on the cooperative scheduler.
+//!
+//! [`scope`] starts a fixed set of worker threads, runs the closure, and returns only after every
+//! task spawned through the [`Scope`] has finished, so tasks may borrow from the caller's stack:
+//!
+//! ```ignore
+//! let mut sums = [0u64; 4];
+//! zeroos_taskpool::scope(|s| {
+//!     for (i, sum) in sums.iter_mut().enumerate() {
+//!         s.spawn(move || *sum = work(i));
+//!     }
+//! });
+//! ```
+//!
+//! Each worker owns a deque. Spawned tasks are dealt round-robin; an idle worker takes from the
+//! front of its own deque and steals from the back of the others'. The calling thread helps run
+//! tasks while it waits.
+
+#![no_std]
+
+extern crate alloc;
+
+mod deque;
+#[cfg(target_os = "none")]
+mod scope;
+
+#[cfg(target_os = "none")]
+pub use scope::{scope, scope_with, Scope, DEFAULT_WORKERS};
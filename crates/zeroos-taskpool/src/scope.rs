@@ -0,0 +1,146 @@
+//! [`scope`] and the worker threads behind it.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+
+use foundation::kfn::scheduler as ksched;
+use scheduler_cooperative::{spawn, JoinHandle, DEFAULT_STACK_SIZE};
+
+use crate::deque::Deques;
+
+/// Workers started by [`scope`].
+pub const DEFAULT_WORKERS: usize = 4;
+
+/// Tasks are stored as `'static`; [`scope_with`] does not return before they have all run.
+struct Pool {
+    deques: Deques<'static>,
+    /// Tasks spawned but not yet finished.
+    pending: AtomicUsize,
+    /// Futex word bumped whenever a task is queued or `pending` drops to zero.
+    seq: AtomicI32,
+    closed: AtomicBool,
+}
+
+impl Pool {
+    fn notify(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        ksched::kwake_on_addr(self.seq.as_ptr() as usize, usize::MAX);
+    }
+
+    fn wait(&self, seq: i32) {
+        let _ = ksched::kwait_on_addr(self.seq.as_ptr() as usize, seq);
+    }
+
+    /// Run one task if any is queued.
+    fn run_one(&self, worker: usize) -> bool {
+        let Some(task) = self.deques.pop(worker) else {
+            return false;
+        };
+        task();
+        if self.pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.notify();
+        }
+        true
+    }
+
+    fn worker_loop(&self, worker: usize) {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if self.run_one(worker) {
+                continue;
+            }
+            if self.closed.load(Ordering::Acquire) {
+                return;
+            }
+            self.wait(seq);
+        }
+    }
+}
+
+/// Handle for spawning tasks that may borrow anything outliving the [`scope`] call.
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: Pool,
+    _scope: PhantomData<&'scope mut &'scope ()>,
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Queue `f` to run on a worker before the scope returns.
+    pub fn spawn<F>(&'scope self, f: F)
+    where
+        F: FnOnce() + Send + 'scope,
+    {
+        let task: Box<dyn FnOnce() + Send + 'scope> = Box::new(f);
+        // SAFETY: only the lifetime changes; `scope_with` waits for every task before `'scope`
+        // ends.
+        let task: Box<dyn FnOnce() + Send + 'static> = unsafe { core::mem::transmute(task) };
+        self.pool.pending.fetch_add(1, Ordering::AcqRel);
+        self.pool.deques.push(task);
+        self.pool.notify();
+    }
+}
+
+/// Run `f` with [`DEFAULT_WORKERS`] workers; see [`scope_with`].
+pub fn scope<'env, F, T>(f: F) -> T
+where
+    F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+{
+    scope_with(DEFAULT_WORKERS, f)
+}
+
+/// Start `workers` threads, run `f`, then block until every spawned task has finished.
+///
+/// # Panics
+/// If the worker threads cannot be spawned (scheduler not initialized or thread table full).
+pub fn scope_with<'env, F, T>(workers: usize, f: F) -> T
+where
+    F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+{
+    let workers = workers.max(1);
+    let scope = Scope {
+        pool: Pool {
+            deques: Deques::new(workers),
+            pending: AtomicUsize::new(0),
+            seq: AtomicI32::new(0),
+            closed: AtomicBool::new(false),
+        },
+        _scope: PhantomData,
+        _env: PhantomData,
+    };
+
+    // Workers need `'static` closures, so they get the pool by address.
+    let pool_addr = &scope.pool as *const Pool as usize;
+    let handles: Vec<JoinHandle<()>> = (0..workers)
+        .map(|w| {
+            spawn(
+                move || {
+                    // SAFETY: the pool outlives every worker; they are all joined below.
+                    unsafe { (*(pool_addr as *const Pool)).worker_loop(w) }
+                },
+                DEFAULT_STACK_SIZE,
+            )
+        })
+        .collect();
+
+    let result = f(&scope);
+
+    // Help out, then wait for tasks still running on workers.
+    let pool = &scope.pool;
+    loop {
+        let seq = pool.seq.load(Ordering::Acquire);
+        if pool.run_one(workers) {
+            continue;
+        }
+        if pool.pending.load(Ordering::Acquire) == 0 {
+            break;
+        }
+        pool.wait(seq);
+    }
+
+    pool.closed.store(true, Ordering::Release);
+    pool.notify();
+    handles.into_iter().for_each(JoinHandle::join);
+    result
+}
@@ -0,0 +1,15 @@
+#![no_std]
+
+#[cfg(feature = "virtual")]
+use foundation::ops::TimeOps;
+
+pub mod virtual_time;
+
+pub use virtual_time::VirtualClock;
+
+#[cfg(feature = "virtual")]
+pub const TIME_OPS: TimeOps = TimeOps {
+    init: virtual_time::init,
+    now_ns: virtual_time::now_ns,
+    advance: virtual_time::advance,
+};
@@ -0,0 +1,164 @@
+//! Virtual time: every clock is a pure function of executed cycles.
+//!
+//! Elapsed cycles since boot are scaled by the boot-time `cycles : nanos` ratio, so a given guest
+//! observes identical timestamps on every run regardless of host speed. Sleeps add the time they
+//! did not spend executing with [`advance`], which moves every clock forward together.
+
+use foundation::ops::TimeConfig;
+use foundation::utils::GlobalCell;
+
+// Linux clock IDs (identical on rv32/rv64).
+pub const CLOCK_REALTIME: usize = 0;
+pub const CLOCK_MONOTONIC: usize = 1;
+pub const CLOCK_PROCESS_CPUTIME_ID: usize = 2;
+pub const CLOCK_THREAD_CPUTIME_ID: usize = 3;
+pub const CLOCK_MONOTONIC_RAW: usize = 4;
+pub const CLOCK_REALTIME_COARSE: usize = 5;
+pub const CLOCK_MONOTONIC_COARSE: usize = 6;
+pub const CLOCK_BOOTTIME: usize = 7;
+
+const EINVAL: i64 = 22;
+
+#[derive(Clone, Copy)]
+pub struct VirtualClock {
+    boot_cycles: u64,
+    cycles: u64,
+    nanos: u64,
+    realtime_base_ns: u64,
+}
+
+impl VirtualClock {
+    pub const fn new(boot_cycles: u64, config: TimeConfig) -> Self {
+        // A zero ratio would either divide by zero or freeze time; fall back to 1:1.
+        let (cycles, nanos) = if config.cycles == 0 || config.nanos == 0 {
+            (1, 1)
+        } else {
+            (config.cycles, config.nanos)
+        };
+        Self {
+            boot_cycles,
+            cycles,
+            nanos,
+            realtime_base_ns: config.realtime_base_ns,
+        }
+    }
+
+    /// Nanoseconds elapsed since boot at cycle count `now`.
+    #[inline]
+    pub fn elapsed_ns(&self, now: u64) -> u64 {
+        let elapsed = now.wrapping_sub(self.boot_cycles) as u128;
+        let ns = elapsed * self.nanos as u128 / self.cycles as u128;
+        ns.min(u64::MAX as u128) as u64
+    }
+
+    /// Nanoseconds on `clock_id` at cycle count `now`, or a negative errno.
+    pub fn clock_ns(&self, clock_id: usize, now: u64) -> i64 {
+        self.clock_ns_skipped(clock_id, now, 0)
+    }
+
+    /// Like [`VirtualClock::clock_ns`], with every clock moved forward by `skipped_ns`.
+    pub fn clock_ns_skipped(&self, clock_id: usize, now: u64, skipped_ns: u64) -> i64 {
+        let elapsed = self.elapsed_ns(now).saturating_add(skipped_ns);
+        let ns = match clock_id {
+            CLOCK_REALTIME | CLOCK_REALTIME_COARSE => self.realtime_base_ns.saturating_add(elapsed),
+            // Single process, never suspended: every other clock is time since boot.
+            CLOCK_MONOTONIC
+            | CLOCK_MONOTONIC_RAW
+            | CLOCK_MONOTONIC_COARSE
+            | CLOCK_BOOTTIME
+            | CLOCK_PROCESS_CPUTIME_ID
+            | CLOCK_THREAD_CPUTIME_ID => elapsed,
+            _ => return -EINVAL,
+        };
+        ns.min(i64::MAX as u64) as i64
+    }
+}
+
+static CLOCK: GlobalCell<VirtualClock> = GlobalCell::new(VirtualClock::new(0, TimeConfig::new()));
+
+/// Nanoseconds added by [`advance`] since boot.
+static SKIPPED_NS: GlobalCell<u64> = GlobalCell::new(0);
+
+#[allow(dead_code)]
+pub fn init(config: TimeConfig) {
+    let boot = foundation::kfn::time::kcycles();
+    CLOCK.with_mut(|c| *c = VirtualClock::new(boot, config));
+    SKIPPED_NS.with_mut(|s| *s = 0);
+}
+
+#[allow(dead_code)]
+pub fn now_ns(clock_id: usize) -> i64 {
+    let now = foundation::kfn::time::kcycles();
+    CLOCK.with(|c| c.clock_ns_skipped(clock_id, now, skipped_ns()))
+}
+
+/// Move every clock forward by `ns` without executing cycles.
+#[allow(dead_code)]
+pub fn advance(ns: u64) {
+    SKIPPED_NS.with_mut(|s| *s = s.saturating_add(ns));
+}
+
+/// Nanoseconds every clock has been moved forward by [`advance`].
+pub fn skipped_ns() -> u64 {
+    SKIPPED_NS.with(|s| *s)
+}
+
+/// The clock latched by `init`, for readers that scale cycles themselves.
+#[allow(dead_code)]
+pub fn clock() -> VirtualClock {
+    CLOCK.with(|c| *c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(cycles: u64, nanos: u64, realtime_base_ns: u64) -> TimeConfig {
+        TimeConfig {
+            cycles,
+            nanos,
+            realtime_base_ns,
+        }
+    }
+
+    #[test]
+    fn test_monotonic_scales_by_ratio() {
+        // 3 cycles per 2 ns.
+        let c = VirtualClock::new(1000, config(3, 2, 0));
+        assert_eq!(c.clock_ns(CLOCK_MONOTONIC, 1000), 0);
+        assert_eq!(c.clock_ns(CLOCK_MONOTONIC, 1300), 200);
+        assert_eq!(c.clock_ns(CLOCK_BOOTTIME, 1300), 200);
+    }
+
+    #[test]
+    fn test_realtime_offsets_from_base() {
+        let c = VirtualClock::new(0, config(1, 1, 1_700_000_000_000_000_000));
+        assert_eq!(c.clock_ns(CLOCK_REALTIME, 5), 1_700_000_000_000_000_005);
+    }
+
+    #[test]
+    fn test_zero_ratio_falls_back() {
+        let c = VirtualClock::new(0, config(0, 7, 0));
+        assert_eq!(c.clock_ns(CLOCK_MONOTONIC, 42), 42);
+    }
+
+    #[test]
+    fn test_skip_moves_every_clock() {
+        let c = VirtualClock::new(0, config(1, 1, 1_000));
+        assert_eq!(c.clock_ns_skipped(CLOCK_MONOTONIC, 5, 100), 105);
+        assert_eq!(c.clock_ns_skipped(CLOCK_REALTIME, 5, 100), 1_105);
+        assert_eq!(c.clock_ns_skipped(99, 5, 100), -EINVAL);
+    }
+
+    #[test]
+    fn test_unknown_clock_is_einval() {
+        let c = VirtualClock::new(0, TimeConfig::new());
+        assert_eq!(c.clock_ns(99, 0), -EINVAL);
+    }
+
+    #[test]
+    fn test_no_overflow_on_large_ratio() {
+        let c = VirtualClock::new(0, config(1, 1_000_000, 0));
+        assert_eq!(c.clock_ns(CLOCK_MONOTONIC, u64::MAX), i64::MAX);
+    }
+}
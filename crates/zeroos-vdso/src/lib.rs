@@ -0,0 +1,185 @@
+//! vDSO-style data page: tid, pid, CPU count and clocks without a trap.
+//!
+//! The platform fills a page-aligned [`VdsoData`] in the `.zeroos_vdso` section once at boot
+//! with [`publish`], and the scheduler stores the running thread's id in it at every switch.
+//! Guests call [`gettid`], [`getpid`], [`online_cpus`] and [`clock_ns`], which read the page
+//! directly and scale the platform cycle counter themselves, the same way the virtual clock does
+//! in the kernel. Each returns `None` when the page was never published (or, for clocks, when
+//! the platform has no clock), and the caller falls back to the syscall.
+//!
+//! Only the kernel writes the page; guests treat it as read-only.
+
+#![no_std]
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use time::VirtualClock;
+
+/// `magic` once [`publish`] has filled the page; bump the low byte when the layout changes.
+pub const VDSO_MAGIC: u32 = 0x5a56_4401;
+
+#[repr(C, align(4096))]
+pub struct VdsoData {
+    /// [`VDSO_MAGIC`] once published, else 0.
+    magic: AtomicU32,
+    pid: AtomicU32,
+    online_cpus: AtomicU32,
+    /// Thread running on the boot hart.
+    tid: AtomicUsize,
+    /// Written once before `magic` is published.
+    clock: UnsafeCell<Option<VirtualClock>>,
+}
+
+// SAFETY: `clock` is written only before `magic` is stored with release ordering and read only
+// after it is loaded with acquire ordering; every other field is atomic.
+unsafe impl Sync for VdsoData {}
+
+impl VdsoData {
+    pub const fn new() -> Self {
+        Self {
+            magic: AtomicU32::new(0),
+            pid: AtomicU32::new(0),
+            online_cpus: AtomicU32::new(0),
+            tid: AtomicUsize::new(0),
+            clock: UnsafeCell::new(None),
+        }
+    }
+
+    /// Fill the page and make it visible to readers. Later calls are ignored.
+    pub fn publish(&self, pid: u32, online_cpus: u32, clock: Option<VirtualClock>) {
+        if self.is_published() {
+            return;
+        }
+        self.pid.store(pid, Ordering::Relaxed);
+        self.online_cpus.store(online_cpus, Ordering::Relaxed);
+        self.tid.store(pid as usize, Ordering::Relaxed);
+        // SAFETY: not published yet, so no reader looks at `clock`.
+        unsafe { *self.clock.get() = clock };
+        self.magic.store(VDSO_MAGIC, Ordering::Release);
+    }
+
+    pub fn set_tid(&self, tid: usize) {
+        self.tid.store(tid, Ordering::Relaxed);
+    }
+
+    pub fn is_published(&self) -> bool {
+        self.magic.load(Ordering::Acquire) == VDSO_MAGIC
+    }
+
+    pub fn tid(&self) -> Option<usize> {
+        self.is_published()
+            .then(|| self.tid.load(Ordering::Relaxed))
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.is_published()
+            .then(|| self.pid.load(Ordering::Relaxed))
+    }
+
+    pub fn online_cpus(&self) -> Option<u32> {
+        self.is_published()
+            .then(|| self.online_cpus.load(Ordering::Relaxed))
+    }
+
+    /// Nanoseconds on Linux clock `clock_id` at cycle count `now`, plus the time sleeps skipped
+    /// the kernel clocks forward by; `None` without a published clock or for a clock the kernel
+    /// would reject.
+    pub fn clock_ns_at(&self, clock_id: usize, now: u64) -> Option<i64> {
+        if !self.is_published() {
+            return None;
+        }
+        // SAFETY: published, so `clock` is no longer written.
+        let clock = unsafe { (*self.clock.get()).as_ref() }?;
+        let ns = clock.clock_ns_skipped(clock_id, now, time::virtual_time::skipped_ns());
+        (ns >= 0).then_some(ns)
+    }
+}
+
+impl Default for VdsoData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(
+    any(target_arch = "riscv32", target_arch = "riscv64"),
+    link_section = ".zeroos_vdso"
+)]
+static VDSO: VdsoData = VdsoData::new();
+
+/// The data page.
+pub fn data() -> &'static VdsoData {
+    &VDSO
+}
+
+/// Platform side: fill the page once the clock and CPU count are set up.
+pub fn publish(pid: u32, online_cpus: u32, clock: Option<VirtualClock>) {
+    VDSO.publish(pid, online_cpus, clock);
+}
+
+/// Scheduler side: `tid` is now running.
+#[inline]
+pub fn set_tid(tid: usize) {
+    VDSO.set_tid(tid);
+}
+
+#[inline]
+pub fn gettid() -> Option<usize> {
+    VDSO.tid()
+}
+
+#[inline]
+pub fn getpid() -> Option<u32> {
+    VDSO.pid()
+}
+
+#[inline]
+pub fn online_cpus() -> Option<u32> {
+    VDSO.online_cpus()
+}
+
+/// Nanoseconds on Linux clock `clock_id`, read from the cycle counter without trapping.
+#[inline]
+pub fn clock_ns(clock_id: usize) -> Option<i64> {
+    VDSO.clock_ns_at(clock_id, foundation::kfn::time::kcycles())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use foundation::ops::TimeConfig;
+    use time::virtual_time::{CLOCK_MONOTONIC, CLOCK_REALTIME};
+
+    #[test]
+    fn test_unpublished_page_reads_nothing() {
+        let page = VdsoData::new();
+        page.set_tid(5);
+        assert_eq!(page.tid(), None);
+        assert_eq!(page.pid(), None);
+        assert_eq!(page.clock_ns_at(CLOCK_MONOTONIC, 0), None);
+    }
+
+    #[test]
+    fn test_published_page() {
+        let page = VdsoData::new();
+        let config = TimeConfig {
+            cycles: 2,
+            nanos: 1,
+            realtime_base_ns: 1_000,
+        };
+        page.publish(1, 4, Some(VirtualClock::new(100, config)));
+        page.publish(9, 9, None);
+
+        assert_eq!(
+            (page.pid(), page.online_cpus(), page.tid()),
+            (Some(1), Some(4), Some(1))
+        );
+        page.set_tid(3);
+        assert_eq!(page.tid(), Some(3));
+
+        assert_eq!(page.clock_ns_at(CLOCK_MONOTONIC, 300), Some(100));
+        assert_eq!(page.clock_ns_at(CLOCK_REALTIME, 300), Some(1_100));
+        assert_eq!(page.clock_ns_at(99, 300), None);
+    }
+}
@@ -1,12 +1,13 @@
 #![no_std]
 
-pub use foundation::ops::VfsOps;
+pub use foundation::ops::{FileStat, IoVec, VfsOps};
 
 pub use libc::{
     S_IFBLK, S_IFCHR, S_IFDIR, S_IFIFO, S_IFLNK, S_IFMT, S_IFREG, S_IFSOCK, S_IRGRP, S_IROTH,
     S_IRUSR, S_IRWXG, S_IRWXO, S_IRWXU, S_IWGRP, S_IWOTH, S_IWUSR, S_IXGRP, S_IXOTH, S_IXUSR,
 };
 
+pub mod pipe;
 mod vfs;
 
 pub use vfs::*;
@@ -15,14 +16,32 @@ pub type Fd = i32;
 
 pub type VfsResult<T> = Result<T, isize>;
 
+pub type ReadOp = fn(file: *mut u8, buf: *mut u8, count: usize, pos: &mut usize) -> isize;
+pub type WriteOp = fn(file: *mut u8, buf: *const u8, count: usize, pos: &mut usize) -> isize;
+/// Vectored transfer of a whole `iov` at `pos`.
+pub type IterOp = fn(file: *mut u8, iov: &[IoVec], pos: &mut usize) -> isize;
+
+/// Operations of an open file.
+///
+/// The VFS keeps the file offset: `read` and `write` get it as `pos`, and advance it past the
+/// bytes they move. Streams (pipes, consoles, the input buffer) ignore it and have no `size`,
+/// which makes `lseek`, `pread` and `pwrite` fail with `ESPIPE`.
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct FileOps {
-    pub read: fn(file: *mut u8, buf: *mut u8, count: usize) -> isize,
-    pub write: fn(file: *mut u8, buf: *const u8, count: usize) -> isize,
+    pub read: ReadOp,
+    pub write: WriteOp,
     pub release: fn(file: *mut u8) -> isize,
-    pub llseek: fn(file: *mut u8, offset: isize, whence: i32) -> isize,
+    /// Current length, for `SEEK_END`; `None` for a stream.
+    pub size: Option<fn(file: *mut u8) -> usize>,
+    /// File type and permission bits `fstat` reports, e.g. `S_IFCHR | 0o666`.
+    pub mode: u32,
     pub ioctl: fn(file: *mut u8, request: usize, arg: usize) -> isize,
+    /// Fill the buffers in order in one pass, as a single read; without it, `readv` calls
+    /// `read` once per buffer.
+    pub read_iter: Option<IterOp>,
+    /// Write the buffers in order in one pass; without it, `writev` calls `write` per buffer.
+    pub write_iter: Option<IterOp>,
 }
 
 #[repr(C)]
@@ -34,22 +53,53 @@ pub struct FdEntry {
 
 pub type DeviceFactory = fn() -> FdEntry;
 
-pub fn noop_close(_file: *mut u8) -> isize {
-    0
+/// A filesystem mounted at a path prefix. Paths are passed relative to the mount point (always
+/// starting with `/`), or unchanged if relative and the filesystem is mounted at `/`.
+#[derive(Clone, Copy)]
+pub struct FsOps {
+    pub open: fn(path: &str, flags: i32, mode: u32) -> VfsResult<FdEntry>,
+    pub unlink: fn(path: &str) -> VfsResult<()>,
+}
+
+/// Copy `src` into the buffers in order; returns the bytes copied.
+///
+/// # Safety
+/// Every buffer must be writable for its length.
+pub unsafe fn copy_to_iov(src: &[u8], iov: &[IoVec]) -> usize {
+    let mut done = 0;
+    for v in iov {
+        if done == src.len() {
+            break;
+        }
+        let n = v.len.min(src.len() - done);
+        core::ptr::copy_nonoverlapping(src.as_ptr().add(done), v.base, n);
+        done += n;
+    }
+    done
+}
+
+/// The non-empty buffers as byte slices.
+///
+/// # Safety
+/// Every non-empty buffer must be readable for its length while the slices live.
+pub unsafe fn iov_slices<'a>(iov: &'a [IoVec]) -> impl Iterator<Item = &'a [u8]> + 'a {
+    iov.iter()
+        .filter(|v| v.len != 0)
+        .map(|v| core::slice::from_raw_parts(v.base as *const u8, v.len))
 }
 
-pub fn noop_seek(_file: *mut u8, _offset: isize, _whence: i32) -> isize {
-    -(libc::ESPIPE as isize)
+pub fn noop_close(_file: *mut u8) -> isize {
+    0
 }
 
 pub fn noop_ioctl(_file: *mut u8, _request: usize, _arg: usize) -> isize {
     -(libc::ENOTTY as isize)
 }
 
-pub fn noop_read(_file: *mut u8, _buf: *mut u8, _count: usize) -> isize {
+pub fn noop_read(_file: *mut u8, _buf: *mut u8, _count: usize, _pos: &mut usize) -> isize {
     -(libc::EBADF as isize)
 }
 
-pub fn noop_write(_file: *mut u8, _buf: *const u8, _count: usize) -> isize {
+pub fn noop_write(_file: *mut u8, _buf: *const u8, _count: usize, _pos: &mut usize) -> isize {
     -(libc::EBADF as isize)
 }
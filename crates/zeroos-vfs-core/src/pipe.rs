@@ -0,0 +1,317 @@
+//! Anonymous pipes.
+//!
+//! A fixed pool of [`MAX_PIPES`] ring buffers of [`PIPE_CAPACITY`] bytes, so pipes work without
+//! a heap. Reads block while the pipe is empty and the write end is open; writes block while it
+//! is full and the read end is open. Blocked threads park on the pipe's futex word, which every
+//! read, write and close bumps.
+//!
+//! Blocking needs the `scheduler` feature and another thread that could make progress; otherwise
+//! the call fails with `EAGAIN` instead of hanging the guest.
+
+#[cfg(feature = "scheduler")]
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::{AtomicI32, Ordering};
+
+use foundation::utils::GlobalCell;
+
+use crate::{noop_ioctl, noop_read, noop_write, FdEntry, FileOps, VfsResult, S_IFIFO};
+
+pub const MAX_PIPES: usize = 8;
+
+/// Bytes a pipe holds before writers block; also `PIPE_BUF`, the limit for atomic writes.
+pub const PIPE_CAPACITY: usize = 4096;
+
+/// Tag in the low bit of `private_data` for ends opened with `O_NONBLOCK`.
+const NONBLOCK: usize = 1;
+
+struct Pipe {
+    buf: [u8; PIPE_CAPACITY],
+    /// Index of the oldest unread byte.
+    head: usize,
+    len: usize,
+    reader_open: bool,
+    writer_open: bool,
+    /// Futex word, bumped on every state change.
+    seq: AtomicI32,
+    /// Threads parked on `seq`.
+    #[cfg(feature = "scheduler")]
+    waiters: AtomicUsize,
+}
+
+impl Pipe {
+    const fn new() -> Self {
+        Self {
+            buf: [0; PIPE_CAPACITY],
+            head: 0,
+            len: 0,
+            reader_open: false,
+            writer_open: false,
+            seq: AtomicI32::new(0),
+            #[cfg(feature = "scheduler")]
+            waiters: AtomicUsize::new(0),
+        }
+    }
+
+    fn is_free(&self) -> bool {
+        !self.reader_open && !self.writer_open
+    }
+
+    /// Move up to `out.len()` buffered bytes into `out`.
+    fn pop(&mut self, out: &mut [u8]) -> usize {
+        let n = out.len().min(self.len);
+        let first = n.min(PIPE_CAPACITY - self.head);
+        out[..first].copy_from_slice(&self.buf[self.head..self.head + first]);
+        out[first..n].copy_from_slice(&self.buf[..n - first]);
+        self.head = (self.head + n) % PIPE_CAPACITY;
+        self.len -= n;
+        n
+    }
+
+    /// Buffer as much of `data` as fits.
+    fn push(&mut self, data: &[u8]) -> usize {
+        let n = data.len().min(PIPE_CAPACITY - self.len);
+        let tail = (self.head + self.len) % PIPE_CAPACITY;
+        let first = n.min(PIPE_CAPACITY - tail);
+        self.buf[tail..tail + first].copy_from_slice(&data[..first]);
+        self.buf[..n - first].copy_from_slice(&data[first..n]);
+        self.len += n;
+        n
+    }
+
+    fn notify(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        #[cfg(feature = "scheduler")]
+        if self.waiters.load(Ordering::Acquire) != 0 {
+            foundation::kfn::scheduler::kwake_on_addr(self.seq.as_ptr() as usize, usize::MAX);
+        }
+    }
+
+    /// Park until `notify` runs after `seq` was sampled; `false` if nothing could ever wake us.
+    fn wait(&self, _seq: i32) -> bool {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "scheduler")] {
+                use foundation::kfn::scheduler as ksched;
+
+                if ksched::kthread_count() <= 1 {
+                    return false;
+                }
+                self.waiters.fetch_add(1, Ordering::AcqRel);
+                let _ = ksched::kwait_on_addr(self.seq.as_ptr() as usize, _seq);
+                self.waiters.fetch_sub(1, Ordering::AcqRel);
+                true
+            } else {
+                false
+            }
+        }
+    }
+}
+
+static PIPES: GlobalCell<[Pipe; MAX_PIPES]> = GlobalCell::new([const { Pipe::new() }; MAX_PIPES]);
+
+/// # Safety
+/// `file` must be the `private_data` of an open pipe end.
+unsafe fn pipe_of<'a>(file: *mut u8) -> &'a mut Pipe {
+    &mut *((file as usize & !NONBLOCK) as *mut Pipe)
+}
+
+fn nonblocking(file: *mut u8) -> bool {
+    file as usize & NONBLOCK != 0
+}
+
+fn pipe_read(file: *mut u8, buf: *mut u8, count: usize, _pos: &mut usize) -> isize {
+    if count == 0 {
+        return 0;
+    }
+    // SAFETY: the VFS validated `buf` for `count` bytes.
+    let out = unsafe { core::slice::from_raw_parts_mut(buf, count) };
+    loop {
+        // SAFETY: `file` is this end's `private_data`; the pipe stays allocated while it is open.
+        let pipe = unsafe { pipe_of(file) };
+        let seq = pipe.seq.load(Ordering::Acquire);
+        if pipe.len != 0 {
+            let n = pipe.pop(out);
+            pipe.notify();
+            return n as isize;
+        }
+        if !pipe.writer_open {
+            return 0;
+        }
+        if nonblocking(file) || !pipe.wait(seq) {
+            return -(libc::EAGAIN as isize);
+        }
+    }
+}
+
+fn pipe_write(file: *mut u8, buf: *const u8, count: usize, _pos: &mut usize) -> isize {
+    // SAFETY: the VFS validated `buf` for `count` bytes.
+    let data = unsafe { core::slice::from_raw_parts(buf, count) };
+    let mut written = 0;
+    loop {
+        // SAFETY: `file` is this end's `private_data`; the pipe stays allocated while it is open.
+        let pipe = unsafe { pipe_of(file) };
+        let seq = pipe.seq.load(Ordering::Acquire);
+        if !pipe.reader_open {
+            // No SIGPIPE: signals are not delivered, so EPIPE is all the writer sees.
+            return if written > 0 {
+                written as isize
+            } else {
+                -(libc::EPIPE as isize)
+            };
+        }
+        // Writes of at most PIPE_CAPACITY bytes are atomic: wait until all of it fits.
+        let room = PIPE_CAPACITY - pipe.len;
+        if room >= count - written || (count > PIPE_CAPACITY && room != 0) {
+            written += pipe.push(&data[written..]);
+            pipe.notify();
+        }
+        if written == count {
+            return written as isize;
+        }
+        if nonblocking(file) || !pipe.wait(seq) {
+            return if written > 0 {
+                written as isize
+            } else {
+                -(libc::EAGAIN as isize)
+            };
+        }
+    }
+}
+
+fn pipe_release_read(file: *mut u8) -> isize {
+    // SAFETY: called once, when the last descriptor for this end closes.
+    let pipe = unsafe { pipe_of(file) };
+    pipe.reader_open = false;
+    pipe.len = 0;
+    pipe.notify();
+    0
+}
+
+fn pipe_release_write(file: *mut u8) -> isize {
+    // SAFETY: called once, when the last descriptor for this end closes.
+    let pipe = unsafe { pipe_of(file) };
+    pipe.writer_open = false;
+    pipe.notify();
+    0
+}
+
+static PIPE_READ_FOPS: FileOps = FileOps {
+    read: pipe_read,
+    write: noop_write,
+    release: pipe_release_read,
+    size: None,
+    mode: S_IFIFO | 0o600,
+    ioctl: noop_ioctl,
+    read_iter: None,
+    write_iter: None,
+};
+
+static PIPE_WRITE_FOPS: FileOps = FileOps {
+    read: noop_read,
+    write: pipe_write,
+    release: pipe_release_write,
+    size: None,
+    mode: S_IFIFO | 0o600,
+    ioctl: noop_ioctl,
+    read_iter: None,
+    write_iter: None,
+};
+
+/// Allocate a pipe and return its `[read, write]` ends. `flags` may contain `O_NONBLOCK` and
+/// `O_CLOEXEC` (a no-op: there is no `exec`).
+pub fn pipe(flags: i32) -> VfsResult<[FdEntry; 2]> {
+    if flags & !(libc::O_NONBLOCK | libc::O_CLOEXEC) != 0 {
+        return Err(-(libc::EINVAL as isize));
+    }
+    let tag = if flags & libc::O_NONBLOCK != 0 {
+        NONBLOCK
+    } else {
+        0
+    };
+
+    PIPES.with_mut(|pipes| {
+        let pipe = pipes
+            .iter_mut()
+            .find(|p| p.is_free())
+            .ok_or(-(libc::ENFILE as isize))?;
+        pipe.head = 0;
+        pipe.len = 0;
+        pipe.reader_open = true;
+        pipe.writer_open = true;
+
+        let private_data = (pipe as *mut Pipe as usize | tag) as *mut u8;
+        Ok([
+            FdEntry {
+                ops: &PIPE_READ_FOPS,
+                private_data,
+            },
+            FdEntry {
+                ops: &PIPE_WRITE_FOPS,
+                private_data,
+            },
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(end: &FdEntry, buf: &mut [u8]) -> isize {
+        (end.ops.read)(end.private_data, buf.as_mut_ptr(), buf.len(), &mut 0)
+    }
+
+    fn write(end: &FdEntry, data: &[u8]) -> isize {
+        (end.ops.write)(end.private_data, data.as_ptr(), data.len(), &mut 0)
+    }
+
+    #[test]
+    fn test_ring_wraps() {
+        let mut pipe = Pipe::new();
+        let mut out = [0u8; PIPE_CAPACITY];
+        assert_eq!(pipe.push(&[1; PIPE_CAPACITY - 2]), PIPE_CAPACITY - 2);
+        assert_eq!(pipe.pop(&mut out[..PIPE_CAPACITY - 4]), PIPE_CAPACITY - 4);
+        assert_eq!(pipe.push(&[2, 3, 4, 5, 6]), 5);
+        assert_eq!(pipe.pop(&mut out), 7);
+        assert_eq!(out[..7], [1, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(pipe.push(&[0; PIPE_CAPACITY + 1]), PIPE_CAPACITY);
+    }
+
+    #[test]
+    fn test_pipe_ends() {
+        assert_eq!(pipe(libc::O_APPEND).err(), Some(-(libc::EINVAL as isize)));
+
+        // Non-blocking, so the tests never park on a scheduler that is not there.
+        let [r, w] = pipe(libc::O_CLOEXEC | libc::O_NONBLOCK).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(write(&w, b"hello"), 5);
+        assert_eq!(read(&r, &mut buf[..3]), 3);
+        assert_eq!(&buf[..3], b"hel");
+        assert_eq!(read(&r, &mut buf), 2);
+        // Empty with the writer open.
+        assert_eq!(read(&r, &mut buf), -(libc::EAGAIN as isize));
+        assert_eq!(
+            (w.ops.read)(w.private_data, buf.as_mut_ptr(), 1, &mut 0),
+            -(libc::EBADF as isize)
+        );
+
+        // A full pipe refuses a write that cannot go in whole.
+        assert_eq!(
+            write(&w, &[0; PIPE_CAPACITY - 1]),
+            (PIPE_CAPACITY - 1) as isize
+        );
+        assert_eq!(write(&w, b"ab"), -(libc::EAGAIN as isize));
+
+        // Buffered data survives the writer closing, then reads see EOF.
+        (w.ops.release)(w.private_data);
+        assert_eq!(read(&r, &mut buf), 8);
+        let mut rest = [0u8; PIPE_CAPACITY];
+        assert_eq!(read(&r, &mut rest), (PIPE_CAPACITY - 9) as isize);
+        assert_eq!(read(&r, &mut buf), 0);
+        (r.ops.release)(r.private_data);
+
+        let [r, w] = pipe(0).unwrap();
+        (r.ops.release)(r.private_data);
+        assert_eq!(write(&w, b"x"), -(libc::EPIPE as isize));
+        (w.ops.release)(w.private_data);
+    }
+}
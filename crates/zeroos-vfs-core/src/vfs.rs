@@ -1,12 +1,26 @@
-use crate::{DeviceFactory, Fd, FdEntry, VfsResult};
+use crate::{DeviceFactory, Fd, FdEntry, FileStat, FsOps, IoVec, VfsResult};
 use foundation::utils::GlobalCell;
 
 const MAX_FDS: usize = 256;
+const MAX_MOUNTS: usize = 8;
+
+/// An open file description: what `open` returned plus the file offset, shared by every
+/// descriptor `dup`ed from it.
+#[derive(Clone, Copy)]
+struct OpenFile {
+    entry: FdEntry,
+    pos: usize,
+    /// Descriptors pointing here plus transfers in flight; released when it drops to zero.
+    refs: usize,
+}
 
 pub struct Vfs {
-    fd_table: [Option<FdEntry>; MAX_FDS],
+    /// Index into `files` of the open file behind each descriptor.
+    fd_table: [Option<usize>; MAX_FDS],
+    files: [Option<OpenFile>; MAX_FDS],
     next_fd: Fd,
     devices: [(Option<&'static str>, Option<DeviceFactory>); 32],
+    mounts: [Option<(&'static str, &'static FsOps)>; MAX_MOUNTS],
 }
 
 impl Default for Vfs {
@@ -21,8 +35,10 @@ impl Vfs {
         const NONE: (Option<&'static str>, Option<DeviceFactory>) = (None, None);
         Self {
             fd_table: [None; MAX_FDS],
+            files: [None; MAX_FDS],
             next_fd: 3,
             devices: [NONE; 32],
+            mounts: [None; MAX_MOUNTS],
         }
     }
 
@@ -30,8 +46,10 @@ impl Vfs {
         if fd < 0 || fd as usize >= MAX_FDS {
             return Err(-(libc::EINVAL as isize));
         }
-        self.fd_table[fd as usize] = Some(entry);
-        Ok(())
+        if self.fd_table[fd as usize].is_some() {
+            let _ = self.close(fd);
+        }
+        self.install(fd, entry)
     }
 
     pub fn register_device(&mut self, path: &'static str, factory: DeviceFactory) -> VfsResult<()> {
@@ -44,14 +62,46 @@ impl Vfs {
         Err(-(libc::ENOMEM as isize))
     }
 
-    pub fn open(&mut self, path: &str, _flags: i32, _mode: u32) -> VfsResult<Fd> {
-        let factory = self
-            .devices
-            .iter()
-            .find(|(p, _)| p.is_some_and(|device_path| device_path == path))
-            .and_then(|(_, f)| *f)
-            .ok_or(-(libc::ENOENT as isize))?;
+    /// Mount `fs` at `mount_point` (e.g. `/` or `/tmp`); the longest matching mount wins.
+    pub fn register_filesystem(
+        &mut self,
+        mount_point: &'static str,
+        fs: &'static FsOps,
+    ) -> VfsResult<()> {
+        for entry in &mut self.mounts {
+            if entry.is_none() {
+                *entry = Some((mount_point, fs));
+                return Ok(());
+            }
+        }
+        Err(-(libc::ENOMEM as isize))
+    }
 
+    /// Find the filesystem serving `path` and the path relative to its mount point.
+    fn lookup_mount<'p>(&self, path: &'p str) -> Option<(&'static FsOps, &'p str)> {
+        let mut best: Option<(&'static FsOps, &'p str, usize)> = None;
+        for &(mount, fs) in self.mounts.iter().flatten() {
+            let rel = if mount == "/" {
+                Some(path)
+            } else {
+                let mount = mount.trim_end_matches('/');
+                match path.strip_prefix(mount) {
+                    Some("") => Some("/"),
+                    Some(rest) if rest.starts_with('/') => Some(rest),
+                    _ => None,
+                }
+            };
+            if let Some(rel) = rel {
+                if best.is_none_or(|(_, _, len)| mount.len() > len) {
+                    best = Some((fs, rel, mount.len()));
+                }
+            }
+        }
+        best.map(|(fs, rel, _)| (fs, rel))
+    }
+
+    /// Reserve the next free descriptor, searching round-robin from the last one handed out.
+    fn alloc_fd(&mut self) -> VfsResult<Fd> {
         let mut found: Option<Fd> = None;
         let start = self.next_fd.max(3) as usize;
         for idx in start..MAX_FDS {
@@ -78,83 +128,323 @@ impl Vfs {
             3
         };
 
-        let entry = factory();
-        self.fd_table[fd as usize] = Some(entry);
+        Ok(fd)
+    }
+
+    /// Point the free descriptor `fd` at a new open file for `entry`, at offset 0.
+    fn install(&mut self, fd: Fd, entry: FdEntry) -> VfsResult<()> {
+        let idx = self
+            .files
+            .iter()
+            .position(Option::is_none)
+            .ok_or(-(libc::ENFILE as isize))?;
+        self.files[idx] = Some(OpenFile {
+            entry,
+            pos: 0,
+            refs: 1,
+        });
+        self.fd_table[fd as usize] = Some(idx);
+        Ok(())
+    }
 
+    /// Claim the next free descriptor for `entry`.
+    fn install_next(&mut self, entry: FdEntry) -> VfsResult<Fd> {
+        let fd = self.alloc_fd()?;
+        self.install(fd, entry)?;
         Ok(fd)
     }
 
-    pub fn read(&self, fd: Fd, buf: *mut u8, count: usize) -> isize {
-        if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
-        }
-        if count != 0 && buf.is_null() {
-            return -(libc::EFAULT as isize);
+    fn device(&self, path: &str) -> Option<DeviceFactory> {
+        self.devices
+            .iter()
+            .find(|(p, _)| p.is_some_and(|device_path| device_path == path))
+            .and_then(|(_, f)| *f)
+    }
+
+    pub fn open(&mut self, path: &str, flags: i32, mode: u32) -> VfsResult<Fd> {
+        let factory = self.device(path);
+        let mount = match factory {
+            Some(_) => None,
+            None => Some(self.lookup_mount(path).ok_or(-(libc::ENOENT as isize))?),
+        };
+
+        let fd = self.alloc_fd()?;
+
+        let entry = match (factory, mount) {
+            (Some(factory), _) => factory(),
+            (None, Some((fs, rel))) => (fs.open)(rel, flags, mode)?,
+            (None, None) => return Err(-(libc::ENOENT as isize)),
+        };
+        if let Err(e) = self.install(fd, entry) {
+            (entry.ops.release)(entry.private_data);
+            return Err(e);
         }
+        Ok(fd)
+    }
 
-        match self.fd_table[fd as usize] {
-            Some(entry) => (entry.ops.read)(entry.private_data, buf, count),
-            None => -(libc::EBADF as isize),
+    /// Open a pipe, returning `[read, write]` descriptors.
+    pub fn pipe(&mut self, flags: i32) -> VfsResult<[Fd; 2]> {
+        let [read_end, write_end] = crate::pipe::pipe(flags)?;
+        let read_fd = match self.install_next(read_end) {
+            Ok(fd) => fd,
+            Err(e) => {
+                (read_end.ops.release)(read_end.private_data);
+                (write_end.ops.release)(write_end.private_data);
+                return Err(e);
+            }
+        };
+        match self.install_next(write_end) {
+            Ok(write_fd) => Ok([read_fd, write_fd]),
+            Err(e) => {
+                self.close(read_fd);
+                (write_end.ops.release)(write_end.private_data);
+                Err(e)
+            }
         }
     }
 
-    pub fn write(&self, fd: Fd, buf: *const u8, count: usize) -> isize {
-        if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
+    /// Duplicate `fd` onto the lowest free descriptor. Both share the open file (and its
+    /// offset); it is released when the last of them closes.
+    pub fn dup(&mut self, fd: Fd) -> VfsResult<Fd> {
+        let idx = self.slot(fd)?;
+        let new_fd = self
+            .fd_table
+            .iter()
+            .position(Option::is_none)
+            .ok_or(-(libc::EMFILE as isize))?;
+        self.hold(idx);
+        self.fd_table[new_fd] = Some(idx);
+        Ok(new_fd as Fd)
+    }
+
+    /// Duplicate `fd` onto `new_fd`, closing whatever `new_fd` referred to.
+    pub fn dup3(&mut self, fd: Fd, new_fd: Fd, flags: i32) -> VfsResult<Fd> {
+        if flags & !libc::O_CLOEXEC != 0 || fd == new_fd {
+            return Err(-(libc::EINVAL as isize));
+        }
+        if new_fd < 0 || new_fd as usize >= MAX_FDS {
+            return Err(-(libc::EBADF as isize));
         }
-        if count != 0 && buf.is_null() {
-            return -(libc::EFAULT as isize);
+        let idx = self.slot(fd)?;
+        if self.fd_table[new_fd as usize].is_some() {
+            // Linux ignores errors from the implicit close.
+            let _ = self.close(new_fd);
         }
+        self.hold(idx);
+        self.fd_table[new_fd as usize] = Some(idx);
+        Ok(new_fd)
+    }
 
-        match self.fd_table[fd as usize] {
-            Some(entry) => (entry.ops.write)(entry.private_data, buf, count),
-            None => -(libc::EBADF as isize),
+    pub fn unlink(&mut self, path: &str) -> VfsResult<()> {
+        if self.devices.iter().any(|(p, _)| *p == Some(path)) {
+            return Err(-(libc::EPERM as isize));
         }
+        let (fs, rel) = self.lookup_mount(path).ok_or(-(libc::ENOENT as isize))?;
+        (fs.unlink)(rel)
     }
 
-    pub fn lseek(&self, fd: Fd, offset: isize, whence: i32) -> isize {
+    /// Index of the open file behind `fd`.
+    fn slot(&self, fd: Fd) -> VfsResult<usize> {
         if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
+            return Err(-(libc::EBADF as isize));
         }
+        self.fd_table[fd as usize].ok_or(-(libc::EBADF as isize))
+    }
+
+    fn file(&self, fd: Fd) -> VfsResult<(usize, OpenFile)> {
+        let idx = self.slot(fd)?;
+        let file = self.files[idx].ok_or(-(libc::EBADF as isize))?;
+        Ok((idx, file))
+    }
 
-        match self.fd_table[fd as usize] {
-            Some(entry) => (entry.ops.llseek)(entry.private_data, offset, whence),
-            None => -(libc::EBADF as isize),
+    /// Take a reference on open file `idx`.
+    fn hold(&mut self, idx: usize) {
+        if let Some(file) = self.files[idx].as_mut() {
+            file.refs += 1;
         }
     }
 
-    pub fn ioctl(&self, fd: Fd, request: usize, arg: usize) -> isize {
-        if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
+    /// Drop a reference on open file `idx`, releasing it with the last one.
+    fn put(&mut self, idx: usize) -> isize {
+        let Some(file) = self.files[idx].as_mut() else {
+            return 0;
+        };
+        file.refs -= 1;
+        if file.refs != 0 {
+            return 0;
         }
+        let entry = file.entry;
+        self.files[idx] = None;
+        (entry.ops.release)(entry.private_data)
+    }
 
-        match self.fd_table[fd as usize] {
-            Some(entry) => (entry.ops.ioctl)(entry.private_data, request, arg),
-            None => -(libc::EBADF as isize),
+    /// The open file behind `fd`, held until the matching [`Vfs::put`] so that closing `fd`
+    /// meanwhile cannot release it under a transfer.
+    fn file_held(&mut self, fd: Fd) -> VfsResult<(usize, OpenFile)> {
+        let (idx, file) = self.file(fd)?;
+        self.hold(idx);
+        Ok((idx, file))
+    }
+
+    fn set_pos(&mut self, idx: usize, pos: usize) {
+        if let Some(file) = self.files[idx].as_mut() {
+            file.pos = pos;
         }
     }
 
-    pub fn close(&mut self, fd: Fd) -> isize {
-        if fd < 0 || fd as usize >= MAX_FDS {
-            return -(libc::EBADF as isize);
+    /// The open file behind `fd`.
+    pub fn get(&self, fd: Fd) -> VfsResult<FdEntry> {
+        Ok(self.file(fd)?.1.entry)
+    }
+
+    /// Offset of the open file behind `fd`.
+    pub fn offset(&self, fd: Fd) -> VfsResult<usize> {
+        Ok(self.file(fd)?.1.pos)
+    }
+
+    /// Move the offset of a seekable file; streams fail with `ESPIPE`.
+    pub fn lseek(&mut self, fd: Fd, offset: isize, whence: i32) -> isize {
+        let (idx, file) = match self.file(fd) {
+            Ok(file) => file,
+            Err(e) => return e,
+        };
+        let Some(size) = file.entry.ops.size else {
+            return -(libc::ESPIPE as isize);
+        };
+        let base = match whence {
+            libc::SEEK_SET => 0,
+            libc::SEEK_CUR => file.pos as isize,
+            libc::SEEK_END => size(file.entry.private_data) as isize,
+            _ => return -(libc::EINVAL as isize),
+        };
+        match base.checked_add(offset) {
+            Some(pos) if pos >= 0 => {
+                self.set_pos(idx, pos as usize);
+                pos
+            }
+            _ => -(libc::EINVAL as isize),
         }
+    }
 
-        match self.fd_table[fd as usize].take() {
-            Some(entry) => (entry.ops.release)(entry.private_data),
-            None => -(libc::EBADF as isize),
+    pub fn ioctl(&self, fd: Fd, request: usize, arg: usize) -> isize {
+        match self.get(fd) {
+            Ok(entry) => (entry.ops.ioctl)(entry.private_data, request, arg),
+            Err(e) => e,
         }
     }
 
-    pub fn fstat(&self, fd: Fd, statbuf: *mut libc::stat) -> isize {
+    pub fn close(&mut self, fd: Fd) -> isize {
         if fd < 0 || fd as usize >= MAX_FDS {
             return -(libc::EBADF as isize);
         }
 
-        if statbuf.is_null() {
-            return -(libc::EFAULT as isize);
+        let Some(idx) = self.fd_table[fd as usize].take() else {
+            return -(libc::EBADF as isize);
+        };
+        // Descriptors from `dup` and transfers in flight share the open file; only the last of
+        // them releases it.
+        self.put(idx)
+    }
+
+    pub fn fstat(&self, fd: Fd) -> VfsResult<FileStat> {
+        Ok(stat_of(&self.get(fd)?))
+    }
+
+    /// Stat the file at `path`. Files keep no metadata apart from their ops, so this opens the
+    /// file read-only and releases it again; a filesystem root, which cannot be opened, is
+    /// reported as a directory.
+    pub fn stat(&self, path: &str) -> VfsResult<FileStat> {
+        let entry = match self.device(path) {
+            Some(factory) => factory(),
+            None => {
+                let (fs, rel) = self.lookup_mount(path).ok_or(-(libc::ENOENT as isize))?;
+                match (fs.open)(rel, libc::O_RDONLY, 0) {
+                    Err(e) if e == -(libc::EISDIR as isize) => {
+                        return Ok(FileStat {
+                            mode: crate::S_IFDIR | 0o755,
+                            size: 0,
+                        })
+                    }
+                    entry => entry?,
+                }
+            }
+        };
+        let stat = stat_of(&entry);
+        (entry.ops.release)(entry.private_data);
+        Ok(stat)
+    }
+}
+
+fn stat_of(entry: &FdEntry) -> FileStat {
+    FileStat {
+        mode: entry.ops.mode,
+        size: entry
+            .ops
+            .size
+            .map_or(0, |size| size(entry.private_data) as u64),
+    }
+}
+
+fn read_at(entry: &FdEntry, buf: *mut u8, count: usize, pos: &mut usize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    (entry.ops.read)(entry.private_data, buf, count, pos)
+}
+
+fn write_at(entry: &FdEntry, buf: *const u8, count: usize, pos: &mut usize) -> isize {
+    if count != 0 && buf.is_null() {
+        return -(libc::EFAULT as isize);
+    }
+    (entry.ops.write)(entry.private_data, buf, count, pos)
+}
+
+/// A buffer with bytes to move must have an address.
+fn check_iov(iov: &[IoVec]) -> VfsResult<()> {
+    if iov.iter().any(|v| v.len != 0 && v.base.is_null()) {
+        return Err(-(libc::EFAULT as isize));
+    }
+    Ok(())
+}
+
+/// Move the buffers one at a time with `op`, stopping at the first short transfer. An error
+/// after some bytes moved returns the count so far, as Linux does.
+fn each_iov(iov: &[IoVec], mut op: impl FnMut(&IoVec) -> isize) -> isize {
+    let mut total = 0isize;
+    for v in iov.iter().filter(|v| v.len != 0) {
+        let n = op(v);
+        if n < 0 {
+            return if total > 0 { total } else { n };
+        }
+        total += n;
+        if (n as usize) < v.len {
+            break;
         }
+    }
+    total
+}
 
-        -(libc::ENOSYS as isize)
+fn readv_at(entry: &FdEntry, iov: &[IoVec], pos: &mut usize) -> isize {
+    if let Err(e) = check_iov(iov) {
+        return e;
+    }
+    match entry.ops.read_iter {
+        Some(read_iter) => read_iter(entry.private_data, iov, pos),
+        None => each_iov(iov, |v| {
+            (entry.ops.read)(entry.private_data, v.base, v.len, pos)
+        }),
+    }
+}
+
+fn writev_at(entry: &FdEntry, iov: &[IoVec], pos: &mut usize) -> isize {
+    if let Err(e) = check_iov(iov) {
+        return e;
+    }
+    match entry.ops.write_iter {
+        Some(write_iter) => write_iter(entry.private_data, iov, pos),
+        None => each_iov(iov, |v| {
+            (entry.ops.write)(entry.private_data, v.base, v.len, pos)
+        }),
     }
 }
 
@@ -168,16 +458,86 @@ pub fn register_device(path: &'static str, factory: DeviceFactory) -> VfsResult<
     VFS.with_mut(|vfs| vfs.register_device(path, factory))
 }
 
+pub fn register_filesystem(mount_point: &'static str, fs: &'static FsOps) -> VfsResult<()> {
+    VFS.with_mut(|vfs| vfs.register_filesystem(mount_point, fs))
+}
+
+// Transfers call the file op outside the table borrow: pipe ends block, and other threads must
+// be able to open and close descriptors meanwhile. The transfer holds a reference on the open
+// file, so a concurrent close never releases it (and frees a pipe for reuse) under the op.
+
+/// Run `op` at the offset of the open file behind `fd` and store where it leaves it.
+fn at_file_offset(
+    table: &GlobalCell<Vfs>,
+    fd: Fd,
+    op: impl FnOnce(&FdEntry, &mut usize) -> isize,
+) -> isize {
+    let (idx, file) = match table.with_mut(|vfs| vfs.file_held(fd)) {
+        Ok(file) => file,
+        Err(e) => return e,
+    };
+    let mut pos = file.pos;
+    let ret = op(&file.entry, &mut pos);
+    table.with_mut(|vfs| {
+        if pos != file.pos {
+            vfs.set_pos(idx, pos);
+        }
+        vfs.put(idx);
+    });
+    ret
+}
+
+/// Run `op` at `offset` of the seekable file behind `fd`, leaving the file's own offset alone.
+fn at_offset(
+    table: &GlobalCell<Vfs>,
+    fd: Fd,
+    offset: usize,
+    op: impl FnOnce(&FdEntry, &mut usize) -> isize,
+) -> isize {
+    let (idx, file) = match table.with_mut(|vfs| vfs.file_held(fd)) {
+        Ok(file) => file,
+        Err(e) => return e,
+    };
+    let mut pos = offset;
+    let ret = if file.entry.ops.size.is_none() {
+        -(libc::ESPIPE as isize)
+    } else {
+        op(&file.entry, &mut pos)
+    };
+    table.with_mut(|vfs| vfs.put(idx));
+    ret
+}
+
 pub fn read(fd: Fd, buf: *mut u8, count: usize) -> isize {
-    VFS.with(|vfs| vfs.read(fd, buf, count))
+    at_file_offset(&VFS, fd, |entry, pos| read_at(entry, buf, count, pos))
 }
 
 pub fn write(fd: Fd, buf: *const u8, count: usize) -> isize {
-    VFS.with(|vfs| vfs.write(fd, buf, count))
+    at_file_offset(&VFS, fd, |entry, pos| write_at(entry, buf, count, pos))
+}
+
+pub fn readv(fd: Fd, iov: &[IoVec]) -> isize {
+    at_file_offset(&VFS, fd, |entry, pos| readv_at(entry, iov, pos))
+}
+
+pub fn writev(fd: Fd, iov: &[IoVec]) -> isize {
+    at_file_offset(&VFS, fd, |entry, pos| writev_at(entry, iov, pos))
+}
+
+pub fn pread(fd: Fd, buf: *mut u8, count: usize, offset: usize) -> isize {
+    at_offset(&VFS, fd, offset, |entry, pos| {
+        read_at(entry, buf, count, pos)
+    })
+}
+
+pub fn pwrite(fd: Fd, buf: *const u8, count: usize, offset: usize) -> isize {
+    at_offset(&VFS, fd, offset, |entry, pos| {
+        write_at(entry, buf, count, pos)
+    })
 }
 
 pub fn lseek(fd: Fd, offset: isize, whence: i32) -> isize {
-    VFS.with(|vfs| vfs.lseek(fd, offset, whence))
+    VFS.with_mut(|vfs| vfs.lseek(fd, offset, whence))
 }
 
 pub fn ioctl(fd: Fd, request: usize, arg: usize) -> isize {
@@ -188,45 +548,282 @@ pub fn close(fd: Fd) -> isize {
     VFS.with_mut(|vfs| vfs.close(fd))
 }
 
-pub fn fstat(fd: Fd, statbuf: *mut libc::stat) -> isize {
-    VFS.with(|vfs| vfs.fstat(fd, statbuf))
+pub fn fstat(fd: Fd, stat: &mut FileStat) -> isize {
+    match VFS.with(|vfs| vfs.fstat(fd)) {
+        Ok(st) => {
+            *stat = st;
+            0
+        }
+        Err(e) => e,
+    }
 }
 
-pub(crate) fn fstat_raw(fd: Fd, statbuf: *mut u8) -> isize {
-    fstat(fd, statbuf as *mut libc::stat)
+pub fn pipe(fds: &mut [Fd; 2], flags: i32) -> isize {
+    match VFS.with_mut(|vfs| vfs.pipe(flags)) {
+        Ok(pair) => {
+            *fds = pair;
+            0
+        }
+        Err(e) => e,
+    }
+}
+
+/// `dup3`, or `dup` onto the lowest free descriptor when `new_fd` is negative.
+pub fn dup(fd: Fd, new_fd: Fd, flags: i32) -> isize {
+    let result = VFS.with_mut(|vfs| {
+        if new_fd < 0 {
+            vfs.dup(fd)
+        } else {
+            vfs.dup3(fd, new_fd, flags)
+        }
+    });
+    match result {
+        Ok(fd) => fd as isize,
+        Err(e) => e,
+    }
 }
 
 pub const VFS_OPS: crate::VfsOps = crate::VfsOps {
     init: || {},
     read,
     write,
+    readv,
+    writev,
+    pread,
+    pwrite,
     open: open_cstr,
     close,
     lseek,
     ioctl,
-    fstat: fstat_raw,
+    fstat,
+    stat: stat_cstr,
+    unlink: unlink_cstr,
+    pipe,
+    dup,
 };
 
 /// # Safety
-/// `path` must be a valid NUL-terminated string.
-pub unsafe fn open_cstr(path: *const u8, flags: i32, mode: u32) -> isize {
+/// `path` must be null or a valid NUL-terminated string.
+unsafe fn path_str<'a>(path: *const u8) -> VfsResult<&'a str> {
     if path.is_null() {
-        return -(libc::EFAULT as isize);
+        return Err(-(libc::EFAULT as isize));
     }
 
     let mut len = 0;
     while *path.add(len) != 0 {
         len += 1;
         if len > 4096 {
-            return -(libc::ENAMETOOLONG as isize);
+            return Err(-(libc::ENAMETOOLONG as isize));
         }
     }
     let slice = core::slice::from_raw_parts(path, len);
-    match core::str::from_utf8(slice) {
+    core::str::from_utf8(slice).map_err(|_| -(libc::EINVAL as isize))
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+pub unsafe fn open_cstr(path: *const u8, flags: i32, mode: u32) -> isize {
+    match path_str(path) {
         Ok(s) => VFS.with_mut(|vfs| match vfs.open(s, flags, mode) {
             Ok(fd) => fd as isize,
             Err(e) => e,
         }),
-        Err(_) => -(libc::EINVAL as isize),
+        Err(e) => e,
+    }
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+pub unsafe fn stat_cstr(path: *const u8, stat: &mut FileStat) -> isize {
+    match path_str(path).and_then(|s| VFS.with(|vfs| vfs.stat(s))) {
+        Ok(st) => {
+            *stat = st;
+            0
+        }
+        Err(e) => e,
+    }
+}
+
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+pub unsafe fn unlink_cstr(path: *const u8) -> isize {
+    match path_str(path) {
+        Ok(s) => VFS.with_mut(|vfs| match vfs.unlink(s) {
+            Ok(()) => 0,
+            Err(e) => e,
+        }),
+        Err(e) => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = b"0123456789";
+
+    fn data_read(_file: *mut u8, buf: *mut u8, count: usize, pos: &mut usize) -> isize {
+        let rest = DATA.get(*pos..).unwrap_or_default();
+        let n = count.min(rest.len());
+        unsafe { core::ptr::copy_nonoverlapping(rest.as_ptr(), buf, n) };
+        *pos += n;
+        n as isize
+    }
+
+    static DATA_FOPS: crate::FileOps = crate::FileOps {
+        read: data_read,
+        write: crate::noop_write,
+        release: crate::noop_close,
+        size: Some(|_| DATA.len()),
+        mode: crate::S_IFREG | 0o444,
+        ioctl: crate::noop_ioctl,
+        read_iter: None,
+        write_iter: None,
+    };
+
+    fn data_file() -> FdEntry {
+        FdEntry {
+            ops: &DATA_FOPS,
+            private_data: core::ptr::null_mut(),
+        }
+    }
+
+    fn read_from(table: &GlobalCell<Vfs>, fd: Fd, buf: &mut [u8]) -> isize {
+        at_file_offset(table, fd, |entry, pos| {
+            read_at(entry, buf.as_mut_ptr(), buf.len(), pos)
+        })
+    }
+
+    fn write_to(table: &GlobalCell<Vfs>, fd: Fd, data: &[u8]) -> isize {
+        at_file_offset(table, fd, |entry, pos| {
+            write_at(entry, data.as_ptr(), data.len(), pos)
+        })
+    }
+
+    fn close_in(table: &GlobalCell<Vfs>, fd: Fd) -> isize {
+        table.with_mut(|vfs| vfs.close(fd))
+    }
+
+    #[test]
+    fn test_offsets_are_per_open_file() {
+        static TABLE: GlobalCell<Vfs> = GlobalCell::new(Vfs::new());
+        TABLE.with_mut(|vfs| {
+            vfs.register_fd(3, data_file()).unwrap();
+            vfs.register_fd(4, data_file()).unwrap();
+        });
+        let mut buf = [0u8; 4];
+
+        assert_eq!(read_from(&TABLE, 3, &mut buf), 4);
+        assert_eq!(&buf, b"0123");
+        // A dup shares the offset; a separate open does not.
+        let dup = TABLE.with_mut(|vfs| vfs.dup(3)).unwrap();
+        assert_eq!(read_from(&TABLE, dup, &mut buf), 4);
+        assert_eq!(&buf, b"4567");
+        TABLE.with_mut(|vfs| {
+            assert_eq!(vfs.offset(3), Ok(8));
+            assert_eq!(vfs.offset(4), Ok(0));
+
+            assert_eq!(vfs.lseek(4, -3, libc::SEEK_END), 7);
+            assert_eq!(vfs.lseek(4, 1, libc::SEEK_CUR), 8);
+            assert_eq!(vfs.lseek(4, -9, libc::SEEK_CUR), -(libc::EINVAL as isize));
+            assert_eq!(vfs.offset(4), Ok(8));
+
+            let [r, _w] = vfs.pipe(libc::O_NONBLOCK).unwrap();
+            assert_eq!(vfs.lseek(r, 0, libc::SEEK_SET), -(libc::ESPIPE as isize));
+        });
+    }
+
+    #[test]
+    fn test_dup_shares_pipe_end() {
+        static TABLE: GlobalCell<Vfs> = GlobalCell::new(Vfs::new());
+        let (r, w, w2) = TABLE.with_mut(|vfs| {
+            let [r, w] = vfs.pipe(0).unwrap();
+            let w2 = vfs.dup(w).unwrap();
+            assert_eq!(w2, 0);
+            assert_eq!(vfs.dup3(r, r, 0), Err(-(libc::EINVAL as isize)));
+            (r, w, w2)
+        });
+
+        // The write end stays open until its last descriptor closes.
+        assert_eq!(close_in(&TABLE, w), 0);
+        assert_eq!(write_to(&TABLE, w2, b"a"), 1);
+        assert_eq!(close_in(&TABLE, w2), 0);
+        let mut buf = [0u8; 2];
+        assert_eq!(read_from(&TABLE, r, &mut buf), 1);
+        assert_eq!(read_from(&TABLE, r, &mut buf), 0);
+
+        TABLE.with_mut(|vfs| {
+            assert_eq!(vfs.dup3(r, 7, 0), Ok(7));
+            assert_eq!(vfs.close(r), 0);
+            assert_eq!(vfs.close(7), 0);
+            assert_eq!(vfs.close(7), -(libc::EBADF as isize));
+        });
+    }
+
+    #[test]
+    fn test_close_waits_for_transfers_in_flight() {
+        static TABLE: GlobalCell<Vfs> = GlobalCell::new(Vfs::new());
+        let [r, w] = TABLE.with_mut(|vfs| vfs.pipe(libc::O_NONBLOCK)).unwrap();
+
+        // Closing the last descriptor mid-read leaves the pipe to the read until it returns.
+        let ret = at_file_offset(&TABLE, r, |entry, _| {
+            assert_eq!(close_in(&TABLE, r), 0);
+            assert_eq!(write_to(&TABLE, w, b"x"), 1);
+            let mut buf = [0u8; 1];
+            (entry.ops.read)(entry.private_data, buf.as_mut_ptr(), 1, &mut 0)
+        });
+        assert_eq!(ret, 1);
+        assert_eq!(read_from(&TABLE, r, &mut [0u8; 1]), -(libc::EBADF as isize));
+        // Released now, so the writer sees the read end gone.
+        assert_eq!(write_to(&TABLE, w, b"x"), -(libc::EPIPE as isize));
+        assert_eq!(close_in(&TABLE, w), 0);
+    }
+
+    static DATA_FS: crate::FsOps = crate::FsOps {
+        open: |path, _, _| match path {
+            "/" => Err(-(libc::EISDIR as isize)),
+            "/data" => Ok(data_file()),
+            _ => Err(-(libc::ENOENT as isize)),
+        },
+        unlink: |_| Err(-(libc::EPERM as isize)),
+    };
+
+    #[test]
+    fn test_stat_reports_type_and_size() {
+        let mut vfs = Vfs::new();
+        vfs.register_fd(3, data_file()).unwrap();
+        let file = FileStat {
+            mode: libc::S_IFREG | 0o444,
+            size: DATA.len() as u64,
+        };
+        assert_eq!(vfs.fstat(3), Ok(file));
+        assert_eq!(vfs.fstat(4), Err(-(libc::EBADF as isize)));
+        let [r, _w] = vfs.pipe(libc::O_NONBLOCK).unwrap();
+        assert_eq!(
+            vfs.fstat(r).map(|st| st.mode & libc::S_IFMT),
+            Ok(libc::S_IFIFO)
+        );
+
+        vfs.register_filesystem("/mnt", &DATA_FS).unwrap();
+        assert_eq!(vfs.stat("/mnt/data"), Ok(file));
+        assert_eq!(
+            vfs.stat("/mnt").map(|st| st.mode & libc::S_IFMT),
+            Ok(libc::S_IFDIR)
+        );
+        assert_eq!(vfs.stat("/mnt/nope"), Err(-(libc::ENOENT as isize)));
+        assert_eq!(vfs.stat("/elsewhere"), Err(-(libc::ENOENT as isize)));
+    }
+
+    #[test]
+    fn test_pread_leaves_the_offset() {
+        register_fd(100, data_file()).unwrap();
+        let mut buf = [0u8; 3];
+        assert_eq!(lseek(100, 2, libc::SEEK_SET), 2);
+        assert_eq!(pread(100, buf.as_mut_ptr(), 3, 6), 3);
+        assert_eq!(&buf, b"678");
+        assert_eq!(pread(100, buf.as_mut_ptr(), 3, 20), 0);
+        assert_eq!(read(100, buf.as_mut_ptr(), 3), 3);
+        assert_eq!(&buf, b"234");
+        assert_eq!(close(100), 0);
     }
 }
@@ -0,0 +1,263 @@
+//! Synthesized `/proc` files.
+//!
+//! Runtimes probe `/proc/cpuinfo` to size thread pools and read `/proc/self/maps` for
+//! diagnostics. There is no process to inspect, so the contents are generated from what the
+//! platform configures at boot: a core count, an ISA string and a list of memory regions. The
+//! same configuration always produces the same bytes.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use foundation::utils::GlobalCell;
+use vfs_core::{FdEntry, FileOps, FsOps, VfsResult, S_IFREG};
+
+/// Regions that can be listed in `/proc/self/maps`.
+pub const MAX_REGIONS: usize = 16;
+
+/// One line of `/proc/self/maps`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub start: usize,
+    pub end: usize,
+    /// Linux-style permissions, e.g. `"rw-p"`.
+    pub perms: &'static str,
+    /// Pathname column, e.g. `"[heap]"`; may be empty.
+    pub name: &'static str,
+}
+
+pub struct Procfs {
+    cpus: usize,
+    isa: &'static str,
+    regions: [Option<Region>; MAX_REGIONS],
+}
+
+impl Procfs {
+    pub const fn new() -> Self {
+        Self {
+            cpus: 1,
+            isa: if cfg!(target_pointer_width = "32") {
+                "rv32ima"
+            } else {
+                "rv64imac"
+            },
+            regions: [None; MAX_REGIONS],
+        }
+    }
+
+    pub fn add_region(&mut self, region: Region) -> VfsResult<()> {
+        if region.start > region.end {
+            return Err(-(libc::EINVAL as isize));
+        }
+        let slot = self
+            .regions
+            .iter_mut()
+            .find(|r| r.is_none())
+            .ok_or(-(libc::ENOMEM as isize))?;
+        *slot = Some(region);
+        Ok(())
+    }
+
+    pub fn cpuinfo(&self) -> String {
+        let mut out = String::new();
+        for cpu in 0..self.cpus {
+            let _ = write!(
+                out,
+                "processor\t: {cpu}\nhart\t\t: {cpu}\nisa\t\t: {}\nmmu\t\t: none\n\n",
+                self.isa
+            );
+        }
+        out
+    }
+
+    /// Regions sorted by start address, in the `/proc/self/maps` line format.
+    pub fn maps(&self) -> String {
+        let mut regions: Vec<Region> = self.regions.iter().flatten().copied().collect();
+        regions.sort_by_key(|r| r.start);
+        let mut out = String::new();
+        for r in regions {
+            let _ = write!(
+                out,
+                "{:08x}-{:08x} {} 00000000 00:00 0",
+                r.start, r.end, r.perms
+            );
+            if !r.name.is_empty() {
+                let _ = write!(out, "          {}", r.name);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn render(&self, path: &str) -> VfsResult<String> {
+        match path {
+            "/cpuinfo" => Ok(self.cpuinfo()),
+            "/self/maps" => Ok(self.maps()),
+            "/" | "/self" => Err(-(libc::EISDIR as isize)),
+            _ => Err(-(libc::ENOENT as isize)),
+        }
+    }
+}
+
+impl Default for Procfs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static PROCFS: GlobalCell<Procfs> = GlobalCell::new(Procfs::new());
+
+/// Number of `processor` entries in `/proc/cpuinfo` (default 1).
+pub fn set_cpu_count(cpus: usize) {
+    PROCFS.with_mut(|p| p.cpus = cpus.max(1));
+}
+
+/// ISA string reported in `/proc/cpuinfo`.
+pub fn set_isa(isa: &'static str) {
+    PROCFS.with_mut(|p| p.isa = isa);
+}
+
+/// List `region` in `/proc/self/maps`. Fails with `ENOMEM` once [`MAX_REGIONS`] are listed.
+pub fn add_region(region: Region) -> VfsResult<()> {
+    PROCFS.with_mut(|p| p.add_region(region))
+}
+
+/// Contents are rendered once at open, so a descriptor sees a stable snapshot.
+struct OpenFile {
+    data: Vec<u8>,
+}
+
+fn open(path: &str, flags: i32, _mode: u32) -> VfsResult<FdEntry> {
+    if (flags & libc::O_ACCMODE) != libc::O_RDONLY || (flags & libc::O_CREAT) != 0 {
+        return Err(-(libc::EACCES as isize));
+    }
+    let data = PROCFS.with(|p| p.render(path))?.into_bytes();
+    let file = Box::new(OpenFile { data });
+    Ok(FdEntry {
+        ops: &PROCFS_FOPS,
+        private_data: Box::into_raw(file) as *mut u8,
+    })
+}
+
+fn unlink(_path: &str) -> VfsResult<()> {
+    Err(-(libc::EPERM as isize))
+}
+
+#[inline]
+fn file<'a>(private: *mut u8) -> &'a mut OpenFile {
+    // SAFETY: `private_data` of a procfs `FdEntry` is always the `OpenFile` boxed in `open`,
+    // alive until `release`.
+    unsafe { &mut *(private as *mut OpenFile) }
+}
+
+fn procfs_read(private: *mut u8, buf: *mut u8, count: usize, pos: &mut usize) -> isize {
+    let f = file(private);
+    let start = (*pos).min(f.data.len());
+    let n = count.min(f.data.len() - start);
+    // SAFETY: the VFS checked `buf` is non-null and the caller provides `count` writable bytes.
+    unsafe { core::ptr::copy_nonoverlapping(f.data.as_ptr().add(start), buf, n) };
+    *pos = start + n;
+    n as isize
+}
+
+fn procfs_write(_private: *mut u8, _buf: *const u8, _count: usize, _pos: &mut usize) -> isize {
+    -(libc::EBADF as isize)
+}
+
+fn procfs_size(private: *mut u8) -> usize {
+    file(private).data.len()
+}
+
+fn procfs_release(private: *mut u8) -> isize {
+    // SAFETY: see `file`; the VFS releases each entry exactly once.
+    drop(unsafe { Box::from_raw(private as *mut OpenFile) });
+    0
+}
+
+pub static PROCFS_FOPS: FileOps = FileOps {
+    read: procfs_read,
+    write: procfs_write,
+    release: procfs_release,
+    size: Some(procfs_size),
+    mode: S_IFREG | 0o444,
+    ioctl: vfs_core::noop_ioctl,
+    read_iter: None,
+    write_iter: None,
+};
+
+pub static PROCFS_OPS: FsOps = FsOps { open, unlink };
+
+/// Mount the procfs at `mount_point`, normally `/proc`.
+pub fn mount(mount_point: &'static str) -> VfsResult<()> {
+    vfs_core::register_filesystem(mount_point, &PROCFS_OPS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpuinfo_lists_each_cpu() {
+        let mut p = Procfs::new();
+        p.cpus = 3;
+        p.isa = "rv64gc";
+        let info = p.cpuinfo();
+        assert_eq!(info.matches("processor\t: ").count(), 3);
+        assert!(info.contains("processor\t: 2\n"));
+        assert!(info.contains("isa\t\t: rv64gc\n"));
+    }
+
+    #[test]
+    fn test_maps_sorted_by_address() {
+        let mut p = Procfs::new();
+        p.add_region(Region {
+            start: 0x8010_0000,
+            end: 0x8020_0000,
+            perms: "rw-p",
+            name: "[stack]",
+        })
+        .unwrap();
+        p.add_region(Region {
+            start: 0x8000_0000,
+            end: 0x8010_0000,
+            perms: "rw-p",
+            name: "[heap]",
+        })
+        .unwrap();
+        assert_eq!(
+            p.maps(),
+            "80000000-80100000 rw-p 00000000 00:00 0          [heap]\n\
+             80100000-80200000 rw-p 00000000 00:00 0          [stack]\n"
+        );
+        assert_eq!(p.render("/self"), Err(-(libc::EISDIR as isize)));
+        assert_eq!(p.render("/meminfo"), Err(-(libc::ENOENT as isize)));
+    }
+
+    #[test]
+    fn test_open_is_read_only_snapshot() {
+        assert_eq!(
+            open("/cpuinfo", libc::O_WRONLY, 0).err(),
+            Some(-(libc::EACCES as isize))
+        );
+        let f = open("/cpuinfo", libc::O_RDONLY, 0).unwrap();
+        let mut buf = [0u8; 256];
+        let mut pos = 0;
+        let n = (f.ops.read)(f.private_data, buf.as_mut_ptr(), buf.len(), &mut pos);
+        assert!(n > 0);
+        assert!(buf[..n as usize].starts_with(b"processor\t: 0\n"));
+        assert_eq!(
+            (f.ops.read)(f.private_data, buf.as_mut_ptr(), buf.len(), &mut pos),
+            0
+        );
+        assert_eq!(
+            (f.ops.write)(f.private_data, buf.as_ptr(), 1, &mut 0),
+            -(libc::EBADF as isize)
+        );
+        (f.ops.release)(f.private_data);
+    }
+}
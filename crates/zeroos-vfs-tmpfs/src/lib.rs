@@ -0,0 +1,261 @@
+//! In-memory filesystem (tmpfs).
+//!
+//! A flat namespace of regular files kept on the heap: paths are normalized (`.`/`..`, repeated
+//! slashes) but there are no directories. Files live until unlinked and their last descriptor
+//! is closed.
+//!
+//! The name table and each file's data sit behind their own lock, so a thread preempted in the
+//! middle of an operation never leaves them half-updated for the next one.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use spin::Mutex;
+use vfs_core::{FdEntry, FileOps, FsOps, VfsResult, S_IFREG};
+
+type Node = Arc<Mutex<Vec<u8>>>;
+
+struct Tmpfs {
+    files: Vec<(String, Node)>,
+}
+
+static TMPFS: Mutex<Tmpfs> = Mutex::new(Tmpfs { files: Vec::new() });
+
+/// Per-open state behind `FdEntry::private_data`; the VFS keeps the offset.
+struct OpenFile {
+    node: Node,
+    flags: i32,
+}
+
+/// Canonical form of `path`: `/`-separated components with `.` and `..` resolved.
+fn normalize(path: &str) -> VfsResult<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            p => parts.push(p),
+        }
+    }
+    if parts.is_empty() {
+        // The root: a directory, not a file.
+        return Err(-(libc::EISDIR as isize));
+    }
+    let mut out = String::new();
+    for p in parts {
+        out.push('/');
+        out.push_str(p);
+    }
+    Ok(out)
+}
+
+fn open(path: &str, flags: i32, _mode: u32) -> VfsResult<FdEntry> {
+    let name = normalize(path)?;
+    if (flags & libc::O_DIRECTORY) != 0 {
+        return Err(-(libc::ENOTDIR as isize));
+    }
+    let writable = (flags & libc::O_ACCMODE) != libc::O_RDONLY;
+
+    let mut fs = TMPFS.lock();
+    let node = match fs.files.iter().find(|(n, _)| *n == name) {
+        Some(_) if (flags & libc::O_CREAT) != 0 && (flags & libc::O_EXCL) != 0 => {
+            Err(-(libc::EEXIST as isize))
+        }
+        Some((_, node)) => Ok(node.clone()),
+        None if (flags & libc::O_CREAT) != 0 => {
+            let node = Node::default();
+            fs.files.push((name, node.clone()));
+            Ok(node)
+        }
+        None => Err(-(libc::ENOENT as isize)),
+    }?;
+    drop(fs);
+    if writable && (flags & libc::O_TRUNC) != 0 {
+        node.lock().clear();
+    }
+
+    let file = Box::new(OpenFile { node, flags });
+    Ok(FdEntry {
+        ops: &TMPFS_FOPS,
+        private_data: Box::into_raw(file) as *mut u8,
+    })
+}
+
+fn unlink(path: &str) -> VfsResult<()> {
+    let name = normalize(path)?;
+    let mut fs = TMPFS.lock();
+    let idx = fs
+        .files
+        .iter()
+        .position(|(n, _)| *n == name)
+        .ok_or(-(libc::ENOENT as isize))?;
+    // Open descriptors keep their own reference to the data.
+    fs.files.swap_remove(idx);
+    Ok(())
+}
+
+#[inline]
+fn file<'a>(private: *mut u8) -> &'a mut OpenFile {
+    // SAFETY: `private_data` of a tmpfs `FdEntry` is always the `OpenFile` boxed in `open`,
+    // alive until `release`.
+    unsafe { &mut *(private as *mut OpenFile) }
+}
+
+fn tmpfs_read(private: *mut u8, buf: *mut u8, count: usize, pos: &mut usize) -> isize {
+    let f = file(private);
+    if (f.flags & libc::O_ACCMODE) == libc::O_WRONLY {
+        return -(libc::EBADF as isize);
+    }
+    let data = f.node.lock();
+    let start = (*pos).min(data.len());
+    let n = count.min(data.len() - start);
+    // SAFETY: the VFS checked `buf` is non-null and the caller provides `count` writable bytes.
+    unsafe { core::ptr::copy_nonoverlapping(data.as_ptr().add(start), buf, n) };
+    *pos = start + n;
+    n as isize
+}
+
+fn tmpfs_write(private: *mut u8, buf: *const u8, count: usize, pos: &mut usize) -> isize {
+    let f = file(private);
+    if (f.flags & libc::O_ACCMODE) == libc::O_RDONLY {
+        return -(libc::EBADF as isize);
+    }
+    let mut data = f.node.lock();
+    if (f.flags & libc::O_APPEND) != 0 {
+        *pos = data.len();
+    }
+    let Some(end) = pos.checked_add(count) else {
+        return -(libc::EFBIG as isize);
+    };
+    let len = data.len();
+    if end > len {
+        // Writing past EOF leaves a zero-filled gap, as on Linux.
+        if data.try_reserve(end - len).is_err() {
+            return -(libc::ENOSPC as isize);
+        }
+        data.resize(end, 0);
+    }
+    // SAFETY: the VFS checked `buf` is non-null and the caller provides `count` readable bytes.
+    let src = unsafe { core::slice::from_raw_parts(buf, count) };
+    data[*pos..end].copy_from_slice(src);
+    *pos = end;
+    count as isize
+}
+
+fn tmpfs_size(private: *mut u8) -> usize {
+    file(private).node.lock().len()
+}
+
+fn tmpfs_release(private: *mut u8) -> isize {
+    // SAFETY: see `file`; the VFS releases each entry exactly once.
+    drop(unsafe { Box::from_raw(private as *mut OpenFile) });
+    0
+}
+
+pub static TMPFS_FOPS: FileOps = FileOps {
+    read: tmpfs_read,
+    write: tmpfs_write,
+    release: tmpfs_release,
+    size: Some(tmpfs_size),
+    mode: S_IFREG | 0o644,
+    ioctl: vfs_core::noop_ioctl,
+    read_iter: None,
+    write_iter: None,
+};
+
+pub static TMPFS_OPS: FsOps = FsOps { open, unlink };
+
+/// Mount the tmpfs at `mount_point` (e.g. `/` to back every path without a device).
+pub fn mount(mount_point: &'static str) -> VfsResult<()> {
+    vfs_core::register_filesystem(mount_point, &TMPFS_OPS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(entry: &FdEntry, pos: &mut usize) -> Vec<u8> {
+        let mut buf = [0u8; 64];
+        let n = (entry.ops.read)(entry.private_data, buf.as_mut_ptr(), buf.len(), pos);
+        assert!(n >= 0);
+        buf[..n as usize].to_vec()
+    }
+
+    fn write(entry: &FdEntry, data: &[u8], pos: &mut usize) -> isize {
+        (entry.ops.write)(entry.private_data, data.as_ptr(), data.len(), pos)
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("a//b/./c/../d").unwrap(), "/a/b/d");
+        assert_eq!(normalize("/x").unwrap(), "/x");
+        assert!(normalize("/./").is_err());
+    }
+
+    #[test]
+    fn test_create_write_reopen_read() {
+        let path = "/test_create_write_reopen_read";
+        assert_eq!(
+            open(path, libc::O_RDONLY, 0).err(),
+            Some(-(libc::ENOENT as isize))
+        );
+
+        let w = open(path, libc::O_WRONLY | libc::O_CREAT, 0o644).unwrap();
+        let mut pos = 0;
+        assert_eq!(write(&w, b"hello", &mut pos), 5);
+        assert_eq!(pos, 5);
+        pos = 7;
+        assert_eq!(write(&w, b"!", &mut pos), 1);
+        assert_eq!(w.ops.size.map(|size| size(w.private_data)), Some(8));
+        (w.ops.release)(w.private_data);
+
+        let r = open(path, libc::O_RDONLY, 0).unwrap();
+        assert_eq!(read_all(&r, &mut 0), b"hello\0\0!");
+        assert_eq!(read_all(&r, &mut 6), b"\0!");
+        assert_eq!(write(&r, b"x", &mut 0), -(libc::EBADF as isize));
+        (r.ops.release)(r.private_data);
+    }
+
+    #[test]
+    fn test_append_trunc_excl() {
+        let path = "/test_append_trunc_excl";
+        let f = open(path, libc::O_RDWR | libc::O_CREAT | libc::O_EXCL, 0).unwrap();
+        write(&f, b"abc", &mut 0);
+        (f.ops.release)(f.private_data);
+        assert!(open(path, libc::O_RDWR | libc::O_CREAT | libc::O_EXCL, 0).is_err());
+
+        let a = open(path, libc::O_WRONLY | libc::O_APPEND, 0).unwrap();
+        let mut pos = 0;
+        write(&a, b"de", &mut pos);
+        assert_eq!(pos, 5);
+        (a.ops.release)(a.private_data);
+        let r = open(path, libc::O_RDONLY, 0).unwrap();
+        assert_eq!(read_all(&r, &mut 0), b"abcde");
+        (r.ops.release)(r.private_data);
+
+        let t = open(path, libc::O_RDWR | libc::O_TRUNC, 0).unwrap();
+        assert_eq!(read_all(&t, &mut 0), b"");
+        (t.ops.release)(t.private_data);
+    }
+
+    #[test]
+    fn test_unlink_keeps_open_file() {
+        let path = "/test_unlink_keeps_open_file";
+        let f = open(path, libc::O_RDWR | libc::O_CREAT, 0).unwrap();
+        write(&f, b"data", &mut 0);
+        assert_eq!(unlink(path), Ok(()));
+        assert_eq!(unlink(path), Err(-(libc::ENOENT as isize)));
+        assert!(open(path, libc::O_RDONLY, 0).is_err());
+
+        assert_eq!(read_all(&f, &mut 0), b"data");
+        (f.ops.release)(f.private_data);
+    }
+}
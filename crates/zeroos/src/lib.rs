@@ -1,7 +1,13 @@
 #![no_std]
 
-zeroos_macros::require_at_most_one_feature!("alloc-linked-list", "alloc-buddy", "alloc-bump");
+zeroos_macros::require_at_most_one_feature!(
+    "alloc-linked-list",
+    "alloc-buddy",
+    "alloc-bump",
+    "alloc-tlsf"
+);
 zeroos_macros::require_at_most_one_feature!("scheduler-cooperative");
+zeroos_macros::require_at_most_one_feature!("arch-riscv", "arch-x86_64");
 
 pub use foundation;
 
@@ -16,9 +22,18 @@ pub extern crate arch_riscv;
 #[cfg(feature = "arch-riscv")]
 pub use arch_riscv::TrapFrame;
 
+#[cfg(feature = "arch-x86_64")]
+pub extern crate arch_x86_64;
+
+#[cfg(feature = "arch-x86_64")]
+pub use arch_x86_64::TrapFrame;
+
 #[cfg(feature = "os-linux")]
 extern crate os_linux;
 
+#[cfg(feature = "mem-intrinsics")]
+extern crate mem;
+
 #[cfg(target_os = "none")]
 pub extern crate runtime_nostd;
 
@@ -35,11 +50,14 @@ pub use foundation::register_memory;
 pub use foundation::register_vfs;
 
 #[cfg(feature = "scheduler")]
-pub use foundation::register_scheduler;
+pub use foundation::{register_scheduler, register_scheduler_backend};
 
 #[cfg(feature = "random")]
 pub use foundation::register_random;
 
+#[cfg(feature = "time")]
+pub use foundation::register_time;
+
 pub mod arch {
     #[cfg(all(
         feature = "arch-riscv",
@@ -51,6 +69,21 @@ pub mod arch {
         pub use arch_riscv::{Exception, Trap, __bootstrap, _default_trap_handler, _start};
 
         pub use arch_riscv::TrapFrame;
+        pub use arch_riscv::{register_trap_hook, Interrupt, TrapAction, TrapFilter, TrapHook};
+        pub use arch_riscv::{trap_depth, trap_record, TrapRecord, MAX_TRAP_DEPTH};
+
+        #[cfg(feature = "smp")]
+        pub use arch_riscv::smp;
+    }
+
+    #[cfg(all(feature = "arch-x86_64", target_arch = "x86_64"))]
+    pub mod x86_64 {
+        pub use arch_x86_64::{boot, msr, trap};
+
+        pub use arch_x86_64::{__bootstrap, _start, _syscall_entry};
+
+        pub use arch_x86_64::{install_syscall_entry, set_thread_anchor, thread_anchor};
+        pub use arch_x86_64::{TrapFrame, CAUSE_SYSCALL};
     }
 }
 
@@ -74,6 +107,12 @@ pub mod runtime {
     }
 }
 
+#[cfg(feature = "panic-report")]
+pub use zeroos_panic as panic;
+
+#[cfg(feature = "loader")]
+pub use loader;
+
 #[cfg(target_os = "none")]
 pub use runtime_nostd::alloc;
 
@@ -88,12 +127,26 @@ pub mod vfs {
         #[cfg(feature = "vfs-device-null")]
         pub use device_null as null;
 
+        #[cfg(feature = "vfs-device-output")]
+        pub use device_output as output;
+
+        #[cfg(feature = "vfs-device-stdin")]
+        pub use device_stdin as stdin;
+
         #[cfg(feature = "vfs-device-urandom")]
         pub use device_urandom as urandom;
 
         #[cfg(feature = "vfs-device-zero")]
         pub use device_zero as zero;
     }
+
+    pub mod fs {
+        #[cfg(feature = "vfs-tmpfs")]
+        pub use vfs_tmpfs as tmpfs;
+
+        #[cfg(feature = "vfs-procfs")]
+        pub use vfs_procfs as procfs;
+    }
 }
 
 #[cfg(feature = "scheduler")]
@@ -102,15 +155,53 @@ pub mod scheduler {
     pub use scheduler_cooperative::*;
 }
 
+#[cfg(feature = "sync")]
+pub mod sync {
+    pub use sync::*;
+}
+
+#[cfg(feature = "taskpool")]
+pub mod taskpool {
+    pub use taskpool::*;
+}
+
+#[cfg(feature = "alloc-stats")]
+pub mod alloc_stats {
+    pub use allocator_bump::stats::*;
+}
+
+#[cfg(feature = "perf")]
+pub mod perf {
+    pub use perf::*;
+}
+
 #[cfg(any(feature = "rng-lcg", feature = "rng-chacha"))]
 pub mod rng {
     pub use rng::*;
 }
 
+#[cfg(feature = "time-virtual")]
+pub mod time {
+    pub use time::*;
+}
+
+#[cfg(feature = "vdso")]
+pub mod vdso {
+    pub use vdso::*;
+}
+
+#[cfg(feature = "log")]
+pub mod log {
+    pub use zeroos_log::*;
+}
+
 pub fn initialize() {
     #[cfg(feature = "arch-riscv")]
     foundation::register_arch(arch_riscv::ARCH_OPS);
 
+    #[cfg(feature = "arch-x86_64")]
+    foundation::register_arch(arch_x86_64::ARCH_OPS);
+
     #[cfg(feature = "os-linux")]
     foundation::register_trap(os_linux::TRAP_OPS);
 
@@ -123,6 +214,9 @@ pub fn initialize() {
     #[cfg(feature = "alloc-bump")]
     foundation::register_memory(allocator_bump::BUMP_ALLOCATOR_OPS);
 
+    #[cfg(feature = "alloc-tlsf")]
+    foundation::register_memory(allocator_tlsf::TLSF_ALLOCATOR_OPS);
+
     #[cfg(feature = "vfs")]
     foundation::register_vfs(vfs_core::VFS_OPS);
 
@@ -131,4 +225,10 @@ pub fn initialize() {
 
     #[cfg(feature = "random")]
     foundation::register_random(rng::RNG_OPS);
+
+    #[cfg(feature = "time-virtual")]
+    foundation::register_time(time::TIME_OPS);
+
+    #[cfg(feature = "log")]
+    zeroos_log::init();
 }
@@ -0,0 +1,222 @@
+//! G1 (`y^2 = x^3 + 4` over `F_p`) and G2 (the twist `y^2 = x^3 + 4ξ` over `F_p2`), sharing
+//! one set of Jacobian formulas over any [`Field`].
+
+use core::ops::{Add, Neg};
+
+use crate::fp::Fp;
+use crate::tower::Fp2;
+use crate::Field;
+
+/// A finite point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Affine<F> {
+    pub x: F,
+    pub y: F,
+}
+
+pub type G1Affine = Affine<Fp>;
+pub type G2Affine = Affine<Fp2>;
+
+impl<F: Field> Neg for Affine<F> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            x: self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl G1Affine {
+    pub fn generator() -> Self {
+        Self {
+            x: Fp::from_raw([
+                0xFB3A_F00A_DB22_C6BB,
+                0x6C55_E83F_F97A_1AEF,
+                0xA14E_3A3F_171B_AC58,
+                0xC368_8C4F_9774_B905,
+                0x2695_638C_4FA9_AC0F,
+                0x17F1_D3A7_3197_D794,
+            ]),
+            y: Fp::from_raw([
+                0x0CAA_2329_46C5_E7E1,
+                0xD03C_C744_A288_8AE4,
+                0x00DB_18CB_2C04_B3ED,
+                0xFCF5_E095_D5D0_0AF6,
+                0xA09E_30ED_741D_8AE4,
+                0x08B3_F481_E3AA_A0F1,
+            ]),
+        }
+    }
+
+    pub fn is_on_curve(&self) -> bool {
+        self.y.square() == self.x.square() * self.x + Fp::from_u64(4)
+    }
+}
+
+impl G2Affine {
+    pub fn generator() -> Self {
+        Self {
+            x: Fp2::new(
+                Fp::from_raw([
+                    0xD480_56C8_C121_BDB8,
+                    0x0BAC_0326_A805_BBEF,
+                    0xB451_0B64_7AE3_D177,
+                    0xC6E4_7AD4_FA40_3B02,
+                    0x2608_0527_2DC5_1051,
+                    0x024A_A2B2_F08F_0A91,
+                ]),
+                Fp::from_raw([
+                    0xE5AC_7D05_5D04_2B7E,
+                    0x334C_F112_1394_5D57,
+                    0xB5DA_61BB_DC7F_5049,
+                    0x596B_D0D0_9920_B61A,
+                    0x7DAC_D3A0_8827_4F65,
+                    0x13E0_2B60_5271_9F60,
+                ]),
+            ),
+            y: Fp2::new(
+                Fp::from_raw([
+                    0xE193_5486_08B8_2801,
+                    0x923A_C9CC_3BAC_A289,
+                    0x6D42_9A69_5160_D12C,
+                    0xADFD_9BAA_8CBD_D3A7,
+                    0x8CC9_CDC6_DA2E_351A,
+                    0x0CE5_D527_727D_6E11,
+                ]),
+                Fp::from_raw([
+                    0xAAA9_075F_F05F_79BE,
+                    0x3F37_0D27_5CEC_1DA1,
+                    0x2674_92AB_572E_99AB,
+                    0xCB3E_287E_85A7_63AF,
+                    0x32AC_D2B0_2BC2_8B99,
+                    0x0606_C4A0_2EA7_34CC,
+                ]),
+            ),
+        }
+    }
+
+    pub fn is_on_curve(&self) -> bool {
+        let four = Fp2::new(Fp::from_u64(4), Fp::zero());
+        self.y.square() == self.x.square() * self.x + four.mul_by_xi()
+    }
+}
+
+/// `(X, Y, Z)` for the affine point `(X / Z^2, Y / Z^3)`; `Z = 0` is the point at infinity.
+#[derive(Clone, Copy, Debug)]
+pub struct Jacobian<F> {
+    x: F,
+    y: F,
+    z: F,
+}
+
+pub type G1 = Jacobian<Fp>;
+pub type G2 = Jacobian<Fp2>;
+
+impl<F: Field> From<Affine<F>> for Jacobian<F> {
+    fn from(p: Affine<F>) -> Self {
+        Self {
+            x: p.x,
+            y: p.y,
+            z: F::one(),
+        }
+    }
+}
+
+impl<F: Field> Jacobian<F> {
+    pub fn infinity() -> Self {
+        Self {
+            x: F::one(),
+            y: F::one(),
+            z: F::zero(),
+        }
+    }
+
+    pub fn is_infinity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    pub fn to_affine(self) -> Option<Affine<F>> {
+        if self.is_infinity() {
+            return None;
+        }
+        let zinv = self.z.invert();
+        let zinv2 = zinv.square();
+        Some(Affine {
+            x: self.x * zinv2,
+            y: self.y * zinv2 * zinv,
+        })
+    }
+
+    /// `2P` (dbl-2009-l, for `a = 0`).
+    pub fn double(self) -> Self {
+        if self.is_infinity() || self.y.is_zero() {
+            return Self::infinity();
+        }
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = b.square();
+        let d = (self.x + b).square() - a - c;
+        let d = d + d;
+        let e = a + a + a;
+        let x = e.square() - d - d;
+        let c2 = c + c;
+        let c4 = c2 + c2;
+        let y = e * (d - x) - (c4 + c4);
+        let yz = self.y * self.z;
+        Self { x, y, z: yz + yz }
+    }
+
+    /// `k * P` for a little-endian multi-limb `k`, by double and add from the top bit.
+    ///
+    /// Variable time; fine for the public scalars of this example, not for secret keys.
+    pub fn mul_scalar(self, k: &[u64]) -> Self {
+        let mut acc = Self::infinity();
+        for limb in k.iter().rev() {
+            for bit in (0..64).rev() {
+                acc = acc.double();
+                if (limb >> bit) & 1 != 0 {
+                    acc = acc + self;
+                }
+            }
+        }
+        acc
+    }
+}
+
+impl<F: Field> Add for Jacobian<F> {
+    type Output = Self;
+
+    /// `P + Q` (add-2007-bl), falling back to doubling for `P = Q`.
+    fn add(self, other: Self) -> Self {
+        if self.is_infinity() {
+            return other;
+        }
+        if other.is_infinity() {
+            return self;
+        }
+        let z1z1 = self.z.square();
+        let z2z2 = other.z.square();
+        let u1 = self.x * z2z2;
+        let u2 = other.x * z1z1;
+        let s1 = self.y * other.z * z2z2;
+        let s2 = other.y * self.z * z1z1;
+        let h = u2 - u1;
+        let r = s2 - s1;
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                Self::infinity()
+            };
+        }
+        let hh = h.square();
+        let hhh = h * hh;
+        let v = u1 * hh;
+        let x = r.square() - hhh - v - v;
+        let y = r * (v - x) - s1 * hhh;
+        let z = self.z * other.z * h;
+        Self { x, y, z }
+    }
+}
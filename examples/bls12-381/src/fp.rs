@@ -0,0 +1,190 @@
+//! The BLS12-381 base field `F_p`, a 381-bit prime, in six-limb Montgomery form.
+
+use core::ops::{Add, Mul, Neg, Sub};
+
+use crate::Field;
+
+const LIMBS: usize = 6;
+
+/// `p`, little-endian limbs.
+const P: [u64; LIMBS] = [
+    0xB9FE_FFFF_FFFF_AAAB,
+    0x1EAB_FFFE_B153_FFFF,
+    0x6730_D2A0_F6B0_F624,
+    0x6477_4B84_F385_12BF,
+    0x4B1B_A7B6_434B_ACD7,
+    0x1A01_11EA_397F_E69A,
+];
+
+/// `-p^-1 mod 2^64`.
+const INV: u64 = {
+    // Newton iteration doubles the correct low bits each step: 1 (p is odd) -> 64 in 6 steps.
+    let mut inv = 1u64;
+    let mut i = 0;
+    while i < 6 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(P[0].wrapping_mul(inv)));
+        i += 1;
+    }
+    inv.wrapping_neg()
+};
+
+/// `R mod p` and `R^2 mod p` for `R = 2^384`, by doubling 1 mod p.
+const R: [u64; LIMBS] = double_n(ONE_RAW, 384);
+const R2: [u64; LIMBS] = double_n(R, 384);
+
+const ONE_RAW: [u64; LIMBS] = [1, 0, 0, 0, 0, 0];
+
+const fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+const fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (t as u64, (t >> 127) as u64)
+}
+
+const fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 * c as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+const fn add_raw(a: [u64; LIMBS], b: [u64; LIMBS]) -> [u64; LIMBS] {
+    let mut out = [0u64; LIMBS];
+    let mut carry = 0;
+    let mut i = 0;
+    while i < LIMBS {
+        (out[i], carry) = adc(a[i], b[i], carry);
+        i += 1;
+    }
+    out
+}
+
+/// `a - b` and whether it borrowed.
+const fn sub_raw(a: [u64; LIMBS], b: [u64; LIMBS]) -> ([u64; LIMBS], bool) {
+    let mut out = [0u64; LIMBS];
+    let mut borrow = 0;
+    let mut i = 0;
+    while i < LIMBS {
+        (out[i], borrow) = sbb(a[i], b[i], borrow);
+        i += 1;
+    }
+    (out, borrow != 0)
+}
+
+/// `a mod p` for `a < 2p`.
+const fn reduce_once(a: [u64; LIMBS]) -> [u64; LIMBS] {
+    let (diff, borrow) = sub_raw(a, P);
+    if borrow {
+        a
+    } else {
+        diff
+    }
+}
+
+const fn double_n(mut x: [u64; LIMBS], n: usize) -> [u64; LIMBS] {
+    // p < 2^381, so doubling a reduced value never carries out of the top limb.
+    let mut i = 0;
+    while i < n {
+        x = reduce_once(add_raw(x, x));
+        i += 1;
+    }
+    x
+}
+
+fn mont_mul(a: &[u64; LIMBS], b: &[u64; LIMBS]) -> [u64; LIMBS] {
+    let mut t = [0u64; LIMBS + 2];
+    for &bi in b {
+        let mut carry = 0;
+        for j in 0..LIMBS {
+            (t[j], carry) = mac(t[j], a[j], bi, carry);
+        }
+        (t[LIMBS], carry) = adc(t[LIMBS], carry, 0);
+        t[LIMBS + 1] = carry;
+
+        let k = t[0].wrapping_mul(INV);
+        let (_, mut carry) = mac(t[0], k, P[0], 0);
+        for j in 1..LIMBS {
+            (t[j - 1], carry) = mac(t[j], k, P[j], carry);
+        }
+        (t[LIMBS - 1], carry) = adc(t[LIMBS], carry, 0);
+        t[LIMBS] = t[LIMBS + 1] + carry;
+    }
+    // The result is below 2p < 2^384, so t[LIMBS] is always 0 here.
+    let mut out = [0u64; LIMBS];
+    out.copy_from_slice(&t[..LIMBS]);
+    reduce_once(out)
+}
+
+/// An element of `F_p`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fp([u64; LIMBS]);
+
+impl Fp {
+    /// From the canonical integer, little-endian limbs below `p`.
+    pub fn from_raw(limbs: [u64; LIMBS]) -> Self {
+        Self(mont_mul(&limbs, &R2))
+    }
+
+    pub fn from_u64(x: u64) -> Self {
+        Self::from_raw([x, 0, 0, 0, 0, 0])
+    }
+
+    /// The canonical integer in `[0, p)`.
+    pub fn to_raw(self) -> [u64; LIMBS] {
+        mont_mul(&self.0, &ONE_RAW)
+    }
+}
+
+impl Field for Fp {
+    fn zero() -> Self {
+        Self([0; LIMBS])
+    }
+
+    fn one() -> Self {
+        Self(R)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == [0; LIMBS]
+    }
+
+    /// Fermat: `self^(p - 2)`; zero maps to zero.
+    fn invert(self) -> Self {
+        let (exp, _) = sub_raw(P, [2, 0, 0, 0, 0, 0]);
+        self.pow(&exp)
+    }
+}
+
+impl Add for Fp {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(reduce_once(add_raw(self.0, other.0)))
+    }
+}
+
+impl Sub for Fp {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        let (diff, borrow) = sub_raw(self.0, other.0);
+        Self(if borrow { add_raw(diff, P) } else { diff })
+    }
+}
+
+impl Neg for Fp {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::zero() - self
+    }
+}
+
+impl Mul for Fp {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self(mont_mul(&self.0, &other.0))
+    }
+}
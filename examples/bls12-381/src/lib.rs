@@ -0,0 +1,91 @@
+#![no_std]
+
+//! BLS12-381 field, curve and pairing arithmetic for guests, sized for benchmarking
+//! aggregate-signature verification.
+//!
+//! Fixed-size limb arithmetic only: no allocation and no dependencies, so the cycle count is
+//! the arithmetic itself. Checking an aggregate signature is a product of pairings, one Miller
+//! loop per signer plus one for the signature, and the loops are independent:
+//! [`miller_loop_batch`] takes slices that can be handed to separate threads, and
+//! [`pairing_product_is_one`] finishes with the single shared final exponentiation.
+//!
+//! Correct but unoptimized (see [`pairing`] for what production code does differently), and
+//! nothing here is constant time. Verification only handles public data. Points are checked to
+//! be on their curve but not to be in the prime-order subgroup.
+
+pub mod curve;
+pub mod fp;
+pub mod pairing;
+pub mod tower;
+
+use core::ops::{Add, Mul, Neg, Sub};
+
+use curve::{G1Affine, G2Affine};
+use tower::Fp12;
+
+/// The operations shared by `F_p` and each extension above it.
+pub trait Field:
+    Copy + PartialEq + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn is_zero(&self) -> bool;
+    /// `self^-1`; zero maps to zero.
+    fn invert(self) -> Self;
+
+    fn square(self) -> Self {
+        self * self
+    }
+
+    /// `self^exp` for a little-endian multi-limb `exp`, by square and multiply.
+    fn pow(self, exp: &[u64]) -> Self {
+        let mut acc = Self::one();
+        for limb in exp.iter().rev() {
+            for bit in (0..64).rev() {
+                acc = acc.square();
+                if (limb >> bit) & 1 != 0 {
+                    acc = acc * self;
+                }
+            }
+        }
+        acc
+    }
+}
+
+/// The Miller loop of each pair, into the matching slot of `out`.
+///
+/// # Panics
+/// If `out` is not as long as `pairs`.
+pub fn miller_loop_batch(pairs: &[(G1Affine, G2Affine)], out: &mut [Fp12]) {
+    assert_eq!(pairs.len(), out.len(), "miller_loop_batch: length mismatch");
+    for ((p, q), slot) in pairs.iter().zip(out) {
+        *slot = pairing::miller_loop(p, q);
+    }
+}
+
+/// Whether the pairings whose Miller loops are `loops` multiply to 1.
+pub fn pairing_product_is_one(loops: &[Fp12]) -> bool {
+    let product = loops.iter().fold(Fp12::one(), |acc, &f| acc * f);
+    pairing::final_exponentiation(product) == Fp12::one()
+}
+
+/// Check an aggregate signature: `e(G1, sig) = prod e(pk_i, H(m_i))`, with `msgs` the messages
+/// already hashed to G2. Runs every Miller loop on the calling thread.
+///
+/// # Panics
+/// If `pks` and `msgs` differ in length.
+pub fn verify_aggregate(pks: &[G1Affine], msgs: &[G2Affine], sig: &G2Affine) -> bool {
+    assert_eq!(pks.len(), msgs.len(), "verify_aggregate: length mismatch");
+    let valid = pks.iter().all(G1Affine::is_on_curve)
+        && msgs.iter().all(G2Affine::is_on_curve)
+        && sig.is_on_curve();
+    if !valid {
+        return false;
+    }
+    // Move e(G1, sig) to the other side as e(-G1, sig) and fold it into the same product.
+    let product = pks.iter().zip(msgs).fold(
+        pairing::miller_loop(&-G1Affine::generator(), sig),
+        |acc, (p, q)| acc * pairing::miller_loop(p, q),
+    );
+    pairing::final_exponentiation(product) == Fp12::one()
+}
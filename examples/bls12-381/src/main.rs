@@ -0,0 +1,85 @@
+#![no_std]
+#![no_main]
+
+use bls12_381::curve::{G1Affine, G2Affine, G1, G2};
+use bls12_381::tower::Fp12;
+use bls12_381::{miller_loop_batch, pairing_product_is_one, verify_aggregate, Field};
+use platform::println;
+
+/// Signers in the aggregate.
+const SIGNERS: usize = 4;
+
+/// Secret keys, one per signer.
+const SECRET_KEYS: [u64; SIGNERS] = [
+    0x2F6B_1C3D_9A48_E705,
+    0x0B7D_55E2_C413_9A6F,
+    0x61A9_0E34_7DC2_58B1,
+    0x1C4E_93B7_206F_DA83,
+];
+
+const MESSAGES: [&[u8]; SIGNERS] = [b"block 1", b"block 2", b"block 3", b"block 4"];
+
+/// Toy message-to-G2 map: an FNV-1a hash of the message times the G2 generator.
+///
+/// Not a hash to curve: anyone can sign for any message once they know the discrete log of its
+/// point. It only stands in so the pairing side of verification has realistic inputs.
+fn hash_to_g2(msg: &[u8]) -> G2Affine {
+    let mut hash = 0xCBF2_9CE4_8422_2325u64;
+    for &b in msg {
+        hash = (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01B3);
+    }
+    G2::from(G2Affine::generator())
+        .mul_scalar(&[hash])
+        .to_affine()
+        .expect("hash is below the group order")
+}
+
+#[no_mangle]
+fn main() -> ! {
+    debug::writeln!("[BOOT] bls12-381");
+
+    let mut pks = [G1Affine::generator(); SIGNERS];
+    let mut msgs = [G2Affine::generator(); SIGNERS];
+    let mut sig = G2::infinity();
+    for i in 0..SIGNERS {
+        pks[i] = G1::from(G1Affine::generator())
+            .mul_scalar(&[SECRET_KEYS[i]])
+            .to_affine()
+            .expect("nonzero key");
+        msgs[i] = hash_to_g2(MESSAGES[i]);
+        sig = sig + G2::from(msgs[i]).mul_scalar(&[SECRET_KEYS[i]]);
+    }
+    let sig = sig.to_affine().expect("nonzero signature");
+
+    // e(-G1, sig) * prod e(pk_i, H(m_i)) = 1, one Miller loop per task. Only the final
+    // exponentiation of the product is serial.
+    let mut pairs = [(-G1Affine::generator(), sig); SIGNERS + 1];
+    for (slot, (pk, msg)) in pairs[1..].iter_mut().zip(pks.iter().zip(&msgs)) {
+        *slot = (*pk, *msg);
+    }
+    let mut loops = [Fp12::one(); SIGNERS + 1];
+    zeroos::taskpool::scope(|s| {
+        for (pairs, out) in pairs.chunks(1).zip(loops.chunks_mut(1)) {
+            s.spawn(move || miller_loop_batch(pairs, out));
+        }
+    });
+
+    let mut failed = false;
+    let parallel = pairing_product_is_one(&loops);
+    println!("aggregate of {} signatures: verified={}", SIGNERS, parallel);
+    failed |= !parallel;
+
+    // Swapping two messages must break the aggregate.
+    msgs.swap(0, 1);
+    if verify_aggregate(&pks, &msgs, &sig) {
+        println!("aggregate over swapped messages accepted");
+        failed = true;
+    }
+
+    if failed {
+        println!("Test FAILED!");
+        platform::exit(1)
+    }
+    println!("Test PASSED!");
+    platform::exit(0)
+}
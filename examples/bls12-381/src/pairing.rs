@@ -0,0 +1,112 @@
+//! Optimal ate pairing `e: G1 x G2 -> F_p12`.
+//!
+//! [`miller_loop`] is independent per `(P, Q)` pair, so a multi-pairing can run one loop per
+//! thread and combine them with a single [`final_exponentiation`]. Both are written for
+//! clarity over speed: the loop works on the untwisted point in affine `F_p12` coordinates
+//! (one inversion per step), and the hard part of the final exponentiation is a plain
+//! square-and-multiply. Sparse line multiplication and a cyclotomic addition chain are what a
+//! production implementation substitutes.
+
+use crate::curve::{G1Affine, G2Affine};
+use crate::tower::Fp12;
+use crate::Field;
+
+/// `|x|` for the curve parameter `x = -0xd201000000010000`.
+const X: u64 = 0xD201_0000_0001_0000;
+
+/// `(p^6 + 1) / r`, little-endian: the part of `(p^12 - 1) / r` left after the easy part.
+const HARD_EXPONENT: [u64; 32] = [
+    0x8739_E1CD_C070_5D6A,
+    0x09A5_256D_E038_1A16,
+    0x9CF0_F70A_61C7_91E2,
+    0x3A09_C449_7903_F76E,
+    0x2D72_7156_3890_F133,
+    0x2247_41B3_6FEC_7760,
+    0x3382_59C2_2A12_BD40,
+    0x38EE_1CD4_778E_0DE7,
+    0xC3B5_EF4B_188A_20B0,
+    0x1D61_5D49_E276_4D7B,
+    0x8161_01DD_D076_117D,
+    0xF007_C01E_7EBE_3AFC,
+    0x27D7_BD90_9350_21C3,
+    0xC3B5_E2F5_57C0_B15F,
+    0x5E88_6C94_C4F8_2384,
+    0xEE6A_95DB_11E6_3F56,
+    0x2B82_2F51_4A9C_4F6F,
+    0x12D6_A874_D21B_73DA,
+    0x1304_275E_F499_DFFB,
+    0x9678_78FE_BCB9_5D1F,
+    0x4744_497F_8B2F_2922,
+    0x85A2_E707_F084_1855,
+    0x9F0C_5012_6C80_2EEC,
+    0xFB46_E197_BD2F_A489,
+    0x548C_E080_9BC5_F61A,
+    0xCF56_FB15_73BE_AA8C,
+    0xAD73_75A3_763B_DF7C,
+    0xE0EC_9031_179B_DECC,
+    0x6579_AEA8_3C48_C1DA,
+    0xDBF8_5AE6_64CF_5BB3,
+    0x7B6F_235C_55CA_7566,
+    0x0000_28B3_1487_7503,
+];
+
+/// An `E(F_p12)` point in affine coordinates.
+#[derive(Clone, Copy)]
+struct Point {
+    x: Fp12,
+    y: Fp12,
+}
+
+/// Step `t` to `t + q` (or `2t` when `q` is `None`) and return the line through them at `p`.
+fn line_step(t: &mut Point, q: Option<Point>, p: &Point) -> Fp12 {
+    let lambda = match q {
+        None => {
+            let x2 = t.x.square();
+            (x2 + x2 + x2) * (t.y + t.y).invert()
+        }
+        Some(q) => (q.y - t.y) * (q.x - t.x).invert(),
+    };
+    let line = p.y - t.y - lambda * (p.x - t.x);
+    let qx = q.map_or(t.x, |q| q.x);
+    let x = lambda.square() - t.x - qx;
+    t.y = lambda * (t.x - x) - t.y;
+    t.x = x;
+    line
+}
+
+/// `f_{|x|, Q}(P)`, conjugated for the negative `x`. Vertical lines are dropped: they lie in
+/// `F_p6`, which the final exponentiation sends to 1.
+pub fn miller_loop(p: &G1Affine, q: &G2Affine) -> Fp12 {
+    // Untwist: (x', y') on E' maps to (x' / w^2, y' / w^3) on E.
+    let winv = Fp12::w().invert();
+    let winv2 = winv.square();
+    let q = Point {
+        x: Fp12::from(q.x) * winv2,
+        y: Fp12::from(q.y) * winv2 * winv,
+    };
+    let p = Point {
+        x: Fp12::from(p.x),
+        y: Fp12::from(p.y),
+    };
+
+    let mut t = q;
+    let mut f = Fp12::one();
+    for bit in (0..63).rev() {
+        f = f.square() * line_step(&mut t, None, &p);
+        if (X >> bit) & 1 != 0 {
+            f = f * line_step(&mut t, Some(q), &p);
+        }
+    }
+    f.conjugate()
+}
+
+/// Raise a Miller loop output (or a product of them) to `(p^12 - 1) / r`.
+pub fn final_exponentiation(f: Fp12) -> Fp12 {
+    // Easy part: f^(p^6 - 1); the conjugate is the p^6-power Frobenius.
+    let f = f.conjugate() * f.invert();
+    f.pow(&HARD_EXPONENT)
+}
+
+pub fn pairing(p: &G1Affine, q: &G2Affine) -> Fp12 {
+    final_exponentiation(miller_loop(p, q))
+}
@@ -0,0 +1,250 @@
+//! The extension tower the pairing lands in:
+//!
+//! - `F_p2 = F_p[u] / (u^2 + 1)`
+//! - `F_p6 = F_p2[v] / (v^3 - ξ)` with `ξ = u + 1`
+//! - `F_p12 = F_p6[w] / (w^2 - v)`
+//!
+//! Multiplication is schoolbook throughout; Karatsuba and cyclotomic squaring are the obvious
+//! next steps when optimizing for cycles.
+
+use core::ops::{Add, Mul, Neg, Sub};
+
+use crate::fp::Fp;
+use crate::Field;
+
+macro_rules! field_ops {
+    ($ty:ident { $($c:ident),+ }) => {
+        impl Add for $ty {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Self { $($c: self.$c + other.$c),+ }
+            }
+        }
+
+        impl Sub for $ty {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Self { $($c: self.$c - other.$c),+ }
+            }
+        }
+
+        impl Neg for $ty {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($c: -self.$c),+ }
+            }
+        }
+    };
+}
+
+/// `c0 + c1 u`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fp2 {
+    pub c0: Fp,
+    pub c1: Fp,
+}
+
+field_ops!(Fp2 { c0, c1 });
+
+impl Fp2 {
+    pub fn new(c0: Fp, c1: Fp) -> Self {
+        Self { c0, c1 }
+    }
+
+    /// `self * ξ = self * (u + 1)`.
+    pub fn mul_by_xi(self) -> Self {
+        Self {
+            c0: self.c0 - self.c1,
+            c1: self.c0 + self.c1,
+        }
+    }
+}
+
+impl Mul for Fp2 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            c0: self.c0 * other.c0 - self.c1 * other.c1,
+            c1: self.c0 * other.c1 + self.c1 * other.c0,
+        }
+    }
+}
+
+impl Field for Fp2 {
+    fn zero() -> Self {
+        Self::new(Fp::zero(), Fp::zero())
+    }
+
+    fn one() -> Self {
+        Self::new(Fp::one(), Fp::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.c0.is_zero() && self.c1.is_zero()
+    }
+
+    fn invert(self) -> Self {
+        // (c0 + c1 u)^-1 = (c0 - c1 u) / (c0^2 + c1^2)
+        let t = (self.c0.square() + self.c1.square()).invert();
+        Self::new(self.c0 * t, -(self.c1 * t))
+    }
+}
+
+/// `c0 + c1 v + c2 v^2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fp6 {
+    pub c0: Fp2,
+    pub c1: Fp2,
+    pub c2: Fp2,
+}
+
+field_ops!(Fp6 { c0, c1, c2 });
+
+impl Fp6 {
+    /// `self * v`.
+    pub fn mul_by_v(self) -> Self {
+        Self {
+            c0: self.c2.mul_by_xi(),
+            c1: self.c0,
+            c2: self.c1,
+        }
+    }
+}
+
+impl Mul for Fp6 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let (a, b) = (self, other);
+        Self {
+            c0: a.c0 * b.c0 + (a.c1 * b.c2 + a.c2 * b.c1).mul_by_xi(),
+            c1: a.c0 * b.c1 + a.c1 * b.c0 + (a.c2 * b.c2).mul_by_xi(),
+            c2: a.c0 * b.c2 + a.c1 * b.c1 + a.c2 * b.c0,
+        }
+    }
+}
+
+impl Field for Fp6 {
+    fn zero() -> Self {
+        Self {
+            c0: Fp2::zero(),
+            c1: Fp2::zero(),
+            c2: Fp2::zero(),
+        }
+    }
+
+    fn one() -> Self {
+        Self {
+            c0: Fp2::one(),
+            ..Self::zero()
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.c0.is_zero() && self.c1.is_zero() && self.c2.is_zero()
+    }
+
+    fn invert(self) -> Self {
+        let a = self;
+        let t0 = a.c0.square() - (a.c1 * a.c2).mul_by_xi();
+        let t1 = a.c2.square().mul_by_xi() - a.c0 * a.c1;
+        let t2 = a.c1.square() - a.c0 * a.c2;
+        let norm = a.c0 * t0 + (a.c2 * t1 + a.c1 * t2).mul_by_xi();
+        let inv = norm.invert();
+        Self {
+            c0: t0 * inv,
+            c1: t1 * inv,
+            c2: t2 * inv,
+        }
+    }
+}
+
+/// `c0 + c1 w`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fp12 {
+    pub c0: Fp6,
+    pub c1: Fp6,
+}
+
+field_ops!(Fp12 { c0, c1 });
+
+impl Fp12 {
+    /// `w` itself, the generator of `F_p12` over `F_p6`.
+    pub fn w() -> Self {
+        Self {
+            c0: Fp6::zero(),
+            c1: Fp6::one(),
+        }
+    }
+
+    /// `c0 - c1 w`, the `p^6`-power Frobenius.
+    pub fn conjugate(self) -> Self {
+        Self {
+            c0: self.c0,
+            c1: -self.c1,
+        }
+    }
+}
+
+impl From<Fp> for Fp12 {
+    fn from(x: Fp) -> Self {
+        Fp12::from(Fp2::new(x, Fp::zero()))
+    }
+}
+
+impl From<Fp2> for Fp12 {
+    fn from(x: Fp2) -> Self {
+        Self {
+            c0: Fp6 {
+                c0: x,
+                ..Fp6::zero()
+            },
+            c1: Fp6::zero(),
+        }
+    }
+}
+
+impl Mul for Fp12 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let (a, b) = (self, other);
+        Self {
+            c0: a.c0 * b.c0 + (a.c1 * b.c1).mul_by_v(),
+            c1: a.c0 * b.c1 + a.c1 * b.c0,
+        }
+    }
+}
+
+impl Field for Fp12 {
+    fn zero() -> Self {
+        Self {
+            c0: Fp6::zero(),
+            c1: Fp6::zero(),
+        }
+    }
+
+    fn one() -> Self {
+        Self {
+            c0: Fp6::one(),
+            c1: Fp6::zero(),
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.c0.is_zero() && self.c1.is_zero()
+    }
+
+    fn invert(self) -> Self {
+        // (c0 + c1 w)^-1 = (c0 - c1 w) / (c0^2 - c1^2 v)
+        let inv = (self.c0.square() - self.c1.square().mul_by_v()).invert();
+        Self {
+            c0: self.c0 * inv,
+            c1: -(self.c1 * inv),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+use core::hint::black_box;
+
+use platform::{println, putln, Hex, Put};
+use riscv::register::mcycle;
+use zeroos::foundation::utils::Line;
+
+const ROUNDS: u64 = 100;
+
+// The `putln` side spells out what `platform::putln!` expands to, with a discarding sink.
+
+/// Swallows output, so only the formatting is measured, not the HTIF console.
+struct Discard;
+
+impl Write for Discard {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        black_box(s);
+        Ok(())
+    }
+}
+
+fn discard(bytes: &[u8]) {
+    black_box(bytes);
+}
+
+/// Average cycles of `f` over [`ROUNDS`] calls.
+fn cycles(mut f: impl FnMut(u64)) -> u64 {
+    let start = mcycle::read64();
+    for i in 0..ROUNDS {
+        f(black_box(i));
+    }
+    (mcycle::read64() - start) / ROUNDS
+}
+
+fn report(case: &str, fmt: u64, fast: u64) {
+    putln!(
+        "print-cycles: ",
+        case,
+        ": fmt=",
+        fmt,
+        " putln=",
+        fast,
+        " saved=",
+        fmt.saturating_sub(fast)
+    );
+}
+
+#[no_mangle]
+fn main() -> ! {
+    debug::writeln!("[BOOT] print-cycles");
+
+    // One label, two integers: the shape of most example output.
+    let fmt = cycles(|n| {
+        let _ = writeln!(Discard, "threads: sum(1..={}) = {}", n, n * (n + 1) / 2);
+    });
+    let fast = cycles(|n| {
+        let mut line = Line::new(discard);
+        "threads: sum(1..=".put(&mut line);
+        n.put(&mut line);
+        ") = ".put(&mut line);
+        (n * (n + 1) / 2).put(&mut line);
+        line.end();
+    });
+    report("label+2 ints", fmt, fast);
+
+    let fmt = cycles(|n| {
+        let _ = writeln!(
+            Discard,
+            "addr={:#x} len={} delta={}",
+            n << 12,
+            n,
+            -(n as i64)
+        );
+    });
+    let fast = cycles(|n| {
+        let mut line = Line::new(discard);
+        "addr=".put(&mut line);
+        Hex(n << 12).put(&mut line);
+        " len=".put(&mut line);
+        n.put(&mut line);
+        " delta=".put(&mut line);
+        (-(n as i64)).put(&mut line);
+        line.end();
+    });
+    report("hex+signed", fmt, fast);
+
+    let fmt = cycles(|n| {
+        let _ = writeln!(Discard, "label only {}", black_box("x"));
+        black_box(n);
+    });
+    let fast = cycles(|n| {
+        let mut line = Line::new(discard);
+        "label only ".put(&mut line);
+        black_box("x").put(&mut line);
+        line.end();
+        black_box(n);
+    });
+    report("label only", fmt, fast);
+
+    println!("Test PASSED!");
+    platform::exit(0)
+}
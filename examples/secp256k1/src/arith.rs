@@ -0,0 +1,272 @@
+//! 256-bit integers and Montgomery arithmetic modulo a 256-bit odd modulus.
+//!
+//! Everything is fixed-size `u64` limbs, no allocation. The moduli used here (the secp256k1
+//! field prime and group order) are both above `2^255`, which [`Modulus`] relies on.
+
+use core::marker::PhantomData;
+use core::ops::{Add, Mul, Neg, Sub};
+
+/// Little-endian `u64` limbs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct U256(pub [u64; 4]);
+
+#[inline(always)]
+const fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline(always)]
+const fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (t as u64, (t >> 127) as u64)
+}
+
+/// `a + b * c + carry`, split into low and high words.
+#[inline(always)]
+const fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 * c as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+impl U256 {
+    pub const ZERO: Self = Self([0; 4]);
+    pub const ONE: Self = Self([1, 0, 0, 0]);
+
+    pub const fn from_be_bytes(bytes: &[u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        let mut i = 0;
+        while i < 4 {
+            let mut limb = 0u64;
+            let mut j = 0;
+            while j < 8 {
+                limb = (limb << 8) | bytes[(3 - i) * 8 + j] as u64;
+                j += 1;
+            }
+            limbs[i] = limb;
+            i += 1;
+        }
+        Self(limbs)
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, limb) in self.0.iter().enumerate() {
+            bytes[(3 - i) * 8..(4 - i) * 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// `self + other` and the carry out.
+    pub const fn overflowing_add(self, other: Self) -> (Self, bool) {
+        let (l0, c) = adc(self.0[0], other.0[0], 0);
+        let (l1, c) = adc(self.0[1], other.0[1], c);
+        let (l2, c) = adc(self.0[2], other.0[2], c);
+        let (l3, c) = adc(self.0[3], other.0[3], c);
+        (Self([l0, l1, l2, l3]), c != 0)
+    }
+
+    /// `self - other` and the borrow out.
+    pub const fn overflowing_sub(self, other: Self) -> (Self, bool) {
+        let (l0, b) = sbb(self.0[0], other.0[0], 0);
+        let (l1, b) = sbb(self.0[1], other.0[1], b);
+        let (l2, b) = sbb(self.0[2], other.0[2], b);
+        let (l3, b) = sbb(self.0[3], other.0[3], b);
+        (Self([l0, l1, l2, l3]), b != 0)
+    }
+
+    pub const fn lt(self, other: Self) -> bool {
+        self.overflowing_sub(other).1
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0[0] | self.0[1] | self.0[2] | self.0[3] == 0
+    }
+
+    pub const fn bit(self, i: usize) -> bool {
+        (self.0[i / 64] >> (i % 64)) & 1 != 0
+    }
+}
+
+/// An odd modulus above `2^255`, with the constants Montgomery multiplication needs.
+pub trait Modulus: Copy {
+    const M: U256;
+    /// `-M^-1 mod 2^64`.
+    const INV: u64 = neg_inv(Self::M.0[0]);
+    /// `R^2 mod M` with `R = 2^256`, to convert into Montgomery form.
+    const R2: U256 = r_squared(Self::M);
+}
+
+const fn neg_inv(m0: u64) -> u64 {
+    // Newton iteration doubles the correct low bits each step: 1 (m0 is odd) -> 64 in 6 steps.
+    let mut inv = 1u64;
+    let mut i = 0;
+    while i < 6 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(m0.wrapping_mul(inv)));
+        i += 1;
+    }
+    inv.wrapping_neg()
+}
+
+const fn r_squared(m: U256) -> U256 {
+    // R mod M is 2^256 - M since M > 2^255; doubling it 256 more times gives R^2 mod M.
+    let mut x = U256::ZERO.overflowing_sub(m).0;
+    let mut i = 0;
+    while i < 256 {
+        let (y, carry) = x.overflowing_add(x);
+        x = if carry || !y.lt(m) {
+            y.overflowing_sub(m).0
+        } else {
+            y
+        };
+        i += 1;
+    }
+    x
+}
+
+/// An integer modulo `P::M`, held in Montgomery form.
+pub struct Residue<P: Modulus>(U256, PhantomData<P>);
+
+impl<P: Modulus> Clone for Residue<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Modulus> Copy for Residue<P> {}
+
+impl<P: Modulus> PartialEq for Residue<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<P: Modulus> Eq for Residue<P> {}
+
+impl<P: Modulus> core::fmt::Debug for Residue<P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self.value())
+    }
+}
+
+impl<P: Modulus> Residue<P> {
+    pub const ZERO: Self = Self(U256::ZERO, PhantomData);
+
+    /// `x mod M`; `x` may be up to `2^256 - 1`.
+    pub fn new(x: U256) -> Self {
+        let x = if x.lt(P::M) {
+            x
+        } else {
+            x.overflowing_sub(P::M).0
+        };
+        Self(mont_mul::<P>(x, P::R2), PhantomData)
+    }
+
+    /// `x` if it is already reduced, as required for signature components.
+    pub fn new_canonical(x: U256) -> Option<Self> {
+        x.lt(P::M).then(|| Self::new(x))
+    }
+
+    pub fn one() -> Self {
+        Self::new(U256::ONE)
+    }
+
+    /// The canonical integer in `[0, M)`.
+    pub fn value(self) -> U256 {
+        mont_mul::<P>(self.0, U256::ONE)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn square(self) -> Self {
+        self * self
+    }
+
+    /// `self^exp`, by square and multiply. Not constant time; only public values go through it.
+    pub fn pow(self, exp: U256) -> Self {
+        let mut acc = Self::one();
+        for i in (0..256).rev() {
+            acc = acc.square();
+            if exp.bit(i) {
+                acc = acc * self;
+            }
+        }
+        acc
+    }
+
+    /// `self^-1` by Fermat (`M` is prime); zero maps to zero.
+    pub fn invert(self) -> Self {
+        self.pow(P::M.overflowing_sub(U256([2, 0, 0, 0])).0)
+    }
+}
+
+fn mont_mul<P: Modulus>(a: U256, b: U256) -> U256 {
+    let (a, b, m) = (a.0, b.0, P::M.0);
+    let mut t = [0u64; 6];
+    for &bi in &b {
+        let mut carry = 0;
+        for j in 0..4 {
+            (t[j], carry) = mac(t[j], a[j], bi, carry);
+        }
+        (t[4], carry) = adc(t[4], carry, 0);
+        t[5] = carry;
+
+        let k = t[0].wrapping_mul(P::INV);
+        let (_, mut carry) = mac(t[0], k, m[0], 0);
+        for j in 1..4 {
+            (t[j - 1], carry) = mac(t[j], k, m[j], carry);
+        }
+        (t[3], carry) = adc(t[4], carry, 0);
+        t[4] = t[5] + carry;
+    }
+    let r = U256([t[0], t[1], t[2], t[3]]);
+    if t[4] != 0 || !r.lt(P::M) {
+        r.overflowing_sub(P::M).0
+    } else {
+        r
+    }
+}
+
+impl<P: Modulus> Add for Residue<P> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (sum, carry) = self.0.overflowing_add(other.0);
+        if carry || !sum.lt(P::M) {
+            Self(sum.overflowing_sub(P::M).0, PhantomData)
+        } else {
+            Self(sum, PhantomData)
+        }
+    }
+}
+
+impl<P: Modulus> Sub for Residue<P> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        let (diff, borrow) = self.0.overflowing_sub(other.0);
+        if borrow {
+            Self(diff.overflowing_add(P::M).0, PhantomData)
+        } else {
+            Self(diff, PhantomData)
+        }
+    }
+}
+
+impl<P: Modulus> Neg for Residue<P> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::ZERO - self
+    }
+}
+
+impl<P: Modulus> Mul for Residue<P> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self(mont_mul::<P>(self.0, other.0), PhantomData)
+    }
+}
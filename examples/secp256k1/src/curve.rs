@@ -0,0 +1,214 @@
+//! The secp256k1 curve `y^2 = x^3 + 7` over `F_p`, in Jacobian coordinates.
+
+use core::ops::Add;
+
+use crate::arith::{Modulus, Residue, U256};
+
+#[derive(Clone, Copy, Debug)]
+pub struct FieldModulus;
+
+impl Modulus for FieldModulus {
+    /// `p = 2^256 - 2^32 - 977`.
+    const M: U256 = U256([
+        0xFFFF_FFFE_FFFF_FC2F,
+        0xFFFF_FFFF_FFFF_FFFF,
+        0xFFFF_FFFF_FFFF_FFFF,
+        0xFFFF_FFFF_FFFF_FFFF,
+    ]);
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OrderModulus;
+
+impl Modulus for OrderModulus {
+    /// `n`, the order of the generator.
+    const M: U256 = U256([
+        0xBFD2_5E8C_D036_4141,
+        0xBAAE_DCE6_AF48_A03B,
+        0xFFFF_FFFF_FFFF_FFFE,
+        0xFFFF_FFFF_FFFF_FFFF,
+    ]);
+}
+
+/// Field element.
+pub type Fe = Residue<FieldModulus>;
+/// Scalar modulo the group order.
+pub type Scalar = Residue<OrderModulus>;
+
+const GX: U256 = U256([
+    0x59F2_815B_16F8_1798,
+    0x029B_FCDB_2DCE_28D9,
+    0x55A0_6295_CE87_0B07,
+    0x79BE_667E_F9DC_BBAC,
+]);
+const GY: U256 = U256([
+    0x9C47_D08F_FB10_D4B8,
+    0xFD17_B448_A685_5419,
+    0x5DA4_FBFC_0E11_08A8,
+    0x483A_DA77_26A3_C465,
+]);
+
+fn b() -> Fe {
+    Fe::new(U256([7, 0, 0, 0]))
+}
+
+/// A finite curve point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Affine {
+    pub x: Fe,
+    pub y: Fe,
+}
+
+impl Affine {
+    pub fn generator() -> Self {
+        Self {
+            x: Fe::new(GX),
+            y: Fe::new(GY),
+        }
+    }
+
+    pub fn is_on_curve(&self) -> bool {
+        self.y.square() == self.x.square() * self.x + b()
+    }
+
+    /// The point with x-coordinate `x` and a y of the given parity, if there is one.
+    pub fn lift_x(x: Fe, odd: bool) -> Option<Self> {
+        let y2 = x.square() * x + b();
+        // p = 3 mod 4, so a square root is y2^((p + 1) / 4).
+        let exp = U256([
+            0xFFFF_FFFF_BFFF_FF0C,
+            0xFFFF_FFFF_FFFF_FFFF,
+            0xFFFF_FFFF_FFFF_FFFF,
+            0x3FFF_FFFF_FFFF_FFFF,
+        ]);
+        let y = y2.pow(exp);
+        if y.square() != y2 {
+            return None;
+        }
+        let y = if y.value().bit(0) == odd { y } else { -y };
+        Some(Self { x, y })
+    }
+}
+
+/// `(X, Y, Z)` for the affine point `(X / Z^2, Y / Z^3)`; `Z = 0` is the point at infinity.
+#[derive(Clone, Copy, Debug)]
+pub struct Jacobian {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+}
+
+impl From<Affine> for Jacobian {
+    fn from(p: Affine) -> Self {
+        Self {
+            x: p.x,
+            y: p.y,
+            z: Fe::one(),
+        }
+    }
+}
+
+impl Jacobian {
+    pub fn infinity() -> Self {
+        Self {
+            x: Fe::one(),
+            y: Fe::one(),
+            z: Fe::ZERO,
+        }
+    }
+
+    pub fn is_infinity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    pub fn to_affine(self) -> Option<Affine> {
+        if self.is_infinity() {
+            return None;
+        }
+        let zinv = self.z.invert();
+        let zinv2 = zinv.square();
+        Some(Affine {
+            x: self.x * zinv2,
+            y: self.y * zinv2 * zinv,
+        })
+    }
+
+    /// `2P` (dbl-2009-l, for `a = 0`).
+    pub fn double(self) -> Self {
+        if self.is_infinity() || self.y.is_zero() {
+            return Self::infinity();
+        }
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = b.square();
+        let d = (self.x + b).square() - a - c;
+        let d = d + d;
+        let e = a + a + a;
+        let f = e.square();
+        let x = f - d - d;
+        let c8 = {
+            let c2 = c + c;
+            let c4 = c2 + c2;
+            c4 + c4
+        };
+        let y = e * (d - x) - c8;
+        let yz = self.y * self.z;
+        Self { x, y, z: yz + yz }
+    }
+
+    /// `a * A + b * B` with one shared doubling chain (Shamir's trick).
+    ///
+    /// Variable time: fine for verification and recovery, where every input is public.
+    pub fn mul_add(a: Scalar, pa: Affine, b: Scalar, pb: Affine) -> Self {
+        let (a, b) = (a.value(), b.value());
+        let (pa, pb) = (Self::from(pa), Self::from(pb));
+        let both = pa + pb;
+        let mut acc = Self::infinity();
+        for i in (0..256).rev() {
+            acc = acc.double();
+            acc = match (a.bit(i), b.bit(i)) {
+                (true, true) => acc + both,
+                (true, false) => acc + pa,
+                (false, true) => acc + pb,
+                (false, false) => acc,
+            };
+        }
+        acc
+    }
+}
+
+impl Add for Jacobian {
+    type Output = Self;
+
+    /// `P + Q` (add-2007-bl), falling back to doubling for `P = Q`.
+    fn add(self, other: Self) -> Self {
+        if self.is_infinity() {
+            return other;
+        }
+        if other.is_infinity() {
+            return self;
+        }
+        let z1z1 = self.z.square();
+        let z2z2 = other.z.square();
+        let u1 = self.x * z2z2;
+        let u2 = other.x * z1z1;
+        let s1 = self.y * other.z * z2z2;
+        let s2 = other.y * self.z * z1z1;
+        let h = u2 - u1;
+        let r = s2 - s1;
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                Self::infinity()
+            };
+        }
+        let hh = h.square();
+        let hhh = h * hh;
+        let v = u1 * hh;
+        let x = r.square() - hhh - v - v;
+        let y = r * (v - x) - s1 * hhh;
+        let z = self.z * other.z * h;
+        Self { x, y, z }
+    }
+}
@@ -0,0 +1,142 @@
+#![no_std]
+
+//! secp256k1 ECDSA verification and public-key recovery (`ecrecover`) for guests.
+//!
+//! Fixed-size limb arithmetic only: no allocation and no dependencies, so the cycle count is
+//! the arithmetic itself. Inputs are 32-byte message hashes; hashing the message is up to the
+//! caller. Every signature in a batch is independent, so [`recover_batch`] and
+//! [`verify_batch`] take slices that can be handed to separate threads.
+//!
+//! Nothing here is constant time. Verification and recovery only handle public data.
+
+pub mod arith;
+pub mod curve;
+
+use arith::U256;
+use curve::{Affine, Fe, Jacobian, OrderModulus, Scalar};
+
+/// `(r, s)` plus the recovery id `v`: bit 0 is the parity of `R.y`, bit 1 is set when `R.x`
+/// overflowed the group order. Ethereum's `v` of 27/28 is `27 + (v & 1)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    pub v: u8,
+}
+
+/// Uncompressed public key coordinates, big-endian.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    pub x: [u8; 32],
+    pub y: [u8; 32],
+}
+
+impl PublicKey {
+    fn to_point(self) -> Option<Affine> {
+        let x = Fe::new_canonical(U256::from_be_bytes(&self.x))?;
+        let y = Fe::new_canonical(U256::from_be_bytes(&self.y))?;
+        let point = Affine { x, y };
+        point.is_on_curve().then_some(point)
+    }
+
+    fn from_point(point: Affine) -> Self {
+        Self {
+            x: point.x.value().to_be_bytes(),
+            y: point.y.value().to_be_bytes(),
+        }
+    }
+
+    /// SEC1 uncompressed encoding, `0x04 || x || y`.
+    pub fn to_sec1(&self) -> [u8; 65] {
+        let mut out = [0u8; 65];
+        out[0] = 0x04;
+        out[1..33].copy_from_slice(&self.x);
+        out[33..].copy_from_slice(&self.y);
+        out
+    }
+}
+
+/// A hash and its signature, as stored in a batch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignedHash {
+    pub hash: [u8; 32],
+    pub sig: Signature,
+}
+
+/// `r` and `s` as scalars, both required in `[1, n)`.
+fn scalars(sig: &Signature) -> Option<(Scalar, Scalar)> {
+    let r = Scalar::new_canonical(U256::from_be_bytes(&sig.r))?;
+    let s = Scalar::new_canonical(U256::from_be_bytes(&sig.s))?;
+    (!r.is_zero() && !s.is_zero()).then_some((r, s))
+}
+
+/// The hash as an integer mod n (a 32-byte hash needs no truncation).
+fn message_scalar(hash: &[u8; 32]) -> Scalar {
+    Scalar::new(U256::from_be_bytes(hash))
+}
+
+/// Check `sig` over `hash` against `key`. The recovery id is ignored.
+pub fn verify(hash: &[u8; 32], sig: &Signature, key: &PublicKey) -> bool {
+    let (Some((r, s)), Some(q)) = (scalars(sig), key.to_point()) else {
+        return false;
+    };
+    let w = s.invert();
+    let z = message_scalar(hash);
+    let Some(point) = Jacobian::mul_add(z * w, Affine::generator(), r * w, q).to_affine() else {
+        return false;
+    };
+    // R.x is a field element; compare it reduced mod n.
+    Scalar::new(point.x.value()) == r
+}
+
+/// The key that produced `sig` over `hash`, or `None` if the signature is malformed.
+///
+/// High-`s` signatures are accepted, like the EVM `ecrecover` precompile; rejecting them is a
+/// transaction-validity rule, not part of recovery.
+pub fn recover(hash: &[u8; 32], sig: &Signature) -> Option<PublicKey> {
+    if sig.v > 3 {
+        return None;
+    }
+    let (r, s) = scalars(sig)?;
+    let mut x = r.value();
+    if sig.v & 2 != 0 {
+        // R.x was r + n, which only exists below p.
+        let (sum, carry) = x.overflowing_add(<OrderModulus as arith::Modulus>::M);
+        if carry {
+            return None;
+        }
+        x = sum;
+    }
+    let big_r = Affine::lift_x(Fe::new_canonical(x)?, sig.v & 1 != 0)?;
+
+    // Q = r^-1 (s R - z G)
+    let rinv = r.invert();
+    let z = message_scalar(hash);
+    let q = Jacobian::mul_add(-(z * rinv), Affine::generator(), s * rinv, big_r).to_affine()?;
+    Some(PublicKey::from_point(q))
+}
+
+/// [`recover`] for each item, into the matching slot of `out`.
+///
+/// # Panics
+/// If `out` is not as long as `items`.
+pub fn recover_batch(items: &[SignedHash], out: &mut [Option<PublicKey>]) {
+    assert_eq!(items.len(), out.len(), "recover_batch: length mismatch");
+    for (item, slot) in items.iter().zip(out) {
+        *slot = recover(&item.hash, &item.sig);
+    }
+}
+
+/// [`verify`] for each item against its key, into the matching slot of `out`.
+///
+/// # Panics
+/// If the slices differ in length.
+pub fn verify_batch(items: &[SignedHash], keys: &[PublicKey], out: &mut [bool]) {
+    assert!(
+        items.len() == keys.len() && keys.len() == out.len(),
+        "verify_batch: length mismatch"
+    );
+    for ((item, key), slot) in items.iter().zip(keys).zip(out) {
+        *slot = verify(&item.hash, &item.sig, key);
+    }
+}
@@ -0,0 +1,141 @@
+#![no_std]
+#![no_main]
+
+use platform::println;
+use secp256k1::{recover_batch, verify_batch, PublicKey, Signature, SignedHash};
+
+const fn hex32(s: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("bad hex digit"),
+        }
+    }
+    let s = s.as_bytes();
+    let mut out = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        out[i] = (nibble(s[2 * i]) << 4) | nibble(s[2 * i + 1]);
+        i += 1;
+    }
+    out
+}
+
+const fn item(hash: &str, r: &str, s: &str, v: u8) -> SignedHash {
+    SignedHash {
+        hash: hex32(hash),
+        sig: Signature {
+            r: hex32(r),
+            s: hex32(s),
+            v,
+        },
+    }
+}
+
+const fn key(x: &str, y: &str) -> PublicKey {
+    PublicKey {
+        x: hex32(x),
+        y: hex32(y),
+    }
+}
+
+/// SHA-256 message hashes signed with low-s signatures, one per key below.
+const ITEMS: [SignedHash; 4] = [
+    item(
+        "ef5426fda9197bbe2fbf91c2d927f9e393168a6e4b704cb705a76b3c9d1eccb0",
+        "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        "5aac5348f5837c95b0026918b7743b5e9a6f9de9948b749178c08b3494ac952a",
+        0,
+    ),
+    item(
+        "56a6f81506dedd9d1b611a594e88f2331d6ecc1ef1218157d27fc69cd2fe2ff5",
+        "cca6649424131300f1ff26543e27b7f1e20f7268d707e11210ea53a00171d198",
+        "21867292636c979fcf7a71ba07c7e058578903a8a622ebdccbfaf120229e01a9",
+        1,
+    ),
+    item(
+        "868e862b6ec69683592aff0d31e264f6487609ea9d9a33fe5d4372bc0fc21bfb",
+        "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        "543c0423129191b0b3e85e72228ffa5bb26ddb05cd0842d381185e2841340343",
+        0,
+    ),
+    item(
+        "ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb",
+        "f973a0b87062c389d125d8199e803b832b6ac6bf7867a4f6cd87506060fc4c58",
+        "628080c8ab79d0e721ee35587671e0a80839b2cea3c87a5ab9532a50d1757f61",
+        1,
+    ),
+];
+
+const KEYS: [PublicKey; 4] = [
+    // Private key 1: the generator itself.
+    key(
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+    ),
+    key(
+        "088e913ef452f8fc52689ed635a435bd347f69e041b120f3cca2a000cbf3dee4",
+        "80b4c2a31c04d98b5165d79317a81df657cc61507fc55fa4a7aa2332136e7d60",
+    ),
+    key(
+        "2f8bde4d1a07209355b4a7250a5c5128e88b84bddc619ab7cba8d569b240efe4",
+        "2753ddd9c91a1c292b24562259363bd90877d8e454f297bf235782c459539959",
+    ),
+    key(
+        "c2481cbd3425cbc695eb996e87ed7a0b738597c0f243df12d085b59a9508be2f",
+        "0d9b50140395d9157f8b3fa6acf106ab0b924513e70198b8044fc8d1eb85a6fb",
+    ),
+];
+
+/// Signatures per task.
+const CHUNK: usize = 2;
+
+#[no_mangle]
+fn main() -> ! {
+    debug::writeln!("[BOOT] secp256k1");
+
+    // One task per chunk: each signature is independent, so tasks share nothing but slices.
+    let mut recovered = [None; ITEMS.len()];
+    let mut verified = [false; ITEMS.len()];
+    zeroos::taskpool::scope(|s| {
+        for (items, out) in ITEMS.chunks(CHUNK).zip(recovered.chunks_mut(CHUNK)) {
+            s.spawn(move || recover_batch(items, out));
+        }
+        let chunks = ITEMS.chunks(CHUNK).zip(KEYS.chunks(CHUNK));
+        for ((items, keys), out) in chunks.zip(verified.chunks_mut(CHUNK)) {
+            s.spawn(move || verify_batch(items, keys, out));
+        }
+    });
+
+    let mut failed = false;
+    for (i, (got, want)) in recovered.iter().zip(&KEYS).enumerate() {
+        let ok = *got == Some(*want) && verified[i];
+        println!(
+            "signature {}: recovered={} verified={}",
+            i,
+            *got == Some(*want),
+            verified[i]
+        );
+        failed |= !ok;
+    }
+
+    // A tampered hash must neither verify nor recover the signer.
+    let mut tampered = ITEMS[1];
+    tampered.hash[0] ^= 1;
+    let mut out = [None];
+    recover_batch(&[tampered], &mut out);
+    let mut ok = [true];
+    verify_batch(&[tampered], &KEYS[1..2], &mut ok);
+    if ok[0] || out[0] == Some(KEYS[1]) {
+        println!("tampered hash accepted");
+        failed = true;
+    }
+
+    if failed {
+        println!("Test FAILED!");
+        platform::exit(1)
+    }
+    println!("Test PASSED!");
+    platform::exit(0)
+}
@@ -0,0 +1,117 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use platform::{println, putln};
+use zeroos::scheduler::{spawn, yield_now, JoinHandle, DEFAULT_STACK_SIZE};
+use zeroos::sync::{Barrier, Channel, Semaphore};
+
+const WORKERS: u64 = 4;
+const CHUNK: u64 = 250;
+
+/// Fewer slots than workers, so some senders block until main drains the channel.
+static RESULTS: Channel<u64, 2> = Channel::new();
+
+const STAGES: u32 = 3;
+
+/// Every stage adds each worker's right neighbour into its value, as an FFT butterfly would.
+static VALUES: [AtomicU32; WORKERS as usize] = [const { AtomicU32::new(0) }; WORKERS as usize];
+static STAGE: Barrier = Barrier::new(WORKERS as usize);
+/// At most two workers in a stage's compute step at a time.
+static SLOTS: Semaphore = Semaphore::new(2);
+static HOLDERS: AtomicUsize = AtomicUsize::new(0);
+static PEAK_HOLDERS: AtomicUsize = AtomicUsize::new(0);
+
+fn partial_sum(worker: u64) -> u64 {
+    let start = worker * CHUNK + 1;
+    let mut sum = 0;
+    for i in start..start + CHUNK {
+        sum += i;
+        if i % 64 == 0 {
+            // Cooperative: let the other workers interleave.
+            yield_now();
+        }
+    }
+    sum
+}
+
+fn run_stages(worker: usize) {
+    for _ in 0..STAGES {
+        SLOTS.acquire();
+        let holders = HOLDERS.fetch_add(1, Ordering::SeqCst) + 1;
+        PEAK_HOLDERS.fetch_max(holders, Ordering::SeqCst);
+        let right = VALUES[(worker + 1) % WORKERS as usize].load(Ordering::Acquire);
+        yield_now();
+        HOLDERS.fetch_sub(1, Ordering::SeqCst);
+        SLOTS.release();
+
+        // Everyone has read the previous stage before anyone overwrites it.
+        STAGE.wait();
+        VALUES[worker].fetch_add(right, Ordering::AcqRel);
+        STAGE.wait();
+    }
+}
+
+#[no_mangle]
+fn main() -> ! {
+    debug::writeln!("[BOOT] threads");
+
+    let (tx, rx) = RESULTS.split().unwrap();
+    let handles: [JoinHandle<()>; WORKERS as usize] = core::array::from_fn(|w| {
+        let tx = tx.clone();
+        spawn(
+            move || {
+                let _ = tx.send(partial_sum(w as u64));
+            },
+            DEFAULT_STACK_SIZE,
+        )
+    });
+    drop(tx);
+
+    // Ends once every worker has sent its sum and dropped its sender.
+    let total: u64 = rx.iter().sum();
+    handles.into_iter().for_each(JoinHandle::join);
+    let n = WORKERS * CHUNK;
+    putln!("threads: sum(1..=", n, ") = ", total);
+
+    // Same sum through the scoped task pool: tasks write straight into a borrowed array.
+    let mut partials = [0u64; WORKERS as usize];
+    zeroos::taskpool::scope(|s| {
+        for (w, slot) in partials.iter_mut().enumerate() {
+            s.spawn(move || *slot = partial_sum(w as u64));
+        }
+    });
+    let pooled: u64 = partials.iter().sum();
+    putln!("taskpool: sum(1..=", n, ") = ", pooled);
+
+    // Same start values, staged: each stage doubles the total.
+    for (w, value) in VALUES.iter().enumerate() {
+        value.store(w as u32 + 1, Ordering::Relaxed);
+    }
+    let stages: [JoinHandle<()>; WORKERS as usize] =
+        core::array::from_fn(|w| spawn(move || run_stages(w), DEFAULT_STACK_SIZE));
+    stages.into_iter().for_each(JoinHandle::join);
+    let staged: u64 = VALUES
+        .iter()
+        .map(|v| u64::from(v.load(Ordering::Acquire)))
+        .sum();
+    let peak = PEAK_HOLDERS.load(Ordering::SeqCst) as u64;
+    putln!(
+        "stages: ",
+        STAGES,
+        " stages -> ",
+        staged,
+        " (peak holders ",
+        peak,
+        ")"
+    );
+
+    let staged_ok = staged == (WORKERS * (WORKERS + 1) / 2) << STAGES && peak <= 2;
+    if total != n * (n + 1) / 2 || pooled != total || !staged_ok {
+        println!("Test FAILED!");
+        platform::exit(1)
+    }
+    println!("Test PASSED!");
+    platform::exit(0)
+}
@@ -2,7 +2,10 @@
 
 use zeroos_macros::require_exactly_one_feature;
 
-require_exactly_one_feature!("with-spike");
+require_exactly_one_feature!("with-spike", "with-qemu");
 
 #[cfg(feature = "with-spike")]
 pub use spike_platform::*;
+
+#[cfg(feature = "with-qemu")]
+pub use qemu_platform::*;
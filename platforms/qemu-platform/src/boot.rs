@@ -0,0 +1,105 @@
+extern "C" {
+    static __heap_start: u8;
+    static __heap_end: u8;
+}
+
+#[inline(always)]
+#[cfg(feature = "os-linux")]
+fn install_trap_vector() {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        core::arch::asm!("la      t0, _trap_handler", "csrw    mtvec, t0",);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn __platform_bootstrap() {
+    debug::writeln!("[BOOT] __platform_bootstrap");
+
+    zeroos::initialize();
+
+    #[cfg(feature = "memory")]
+    {
+        let heap_start = core::ptr::addr_of!(__heap_start) as usize;
+        let heap_end = core::ptr::addr_of!(__heap_end) as usize;
+        debug::writeln!("[BOOT] Heap start=0x{:x}, end=0x{:x}", heap_start, heap_end);
+        foundation::kfn::memory::kinit(heap_start, heap_end - heap_start);
+    }
+
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "none")] {
+            #[cfg(feature = "thread")]
+            {
+                // No trap vector and no libc TLS in no_std mode: every thread runs in kernel
+                // context and keeps tp = its own anchor.
+                let anchor = foundation::kfn::scheduler::kinit();
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                unsafe {
+                    core::arch::asm!("mv tp, {0}", in(reg) anchor);
+                }
+            }
+        } else {
+            #[cfg(feature = "os-linux")]
+            {
+                install_trap_vector();
+                debug::writeln!("[BOOT] Trap handler installed");
+            }
+
+            #[cfg(feature = "thread")]
+            let boot_thread_anchor: usize = {
+                let anchor = foundation::kfn::scheduler::kinit();
+
+                // Trap entry swaps tp <-> mscratch. In kernel, keep tp=anchor and mscratch=0 so
+                // traps are treated as kernel traps and the kernel can find the current anchor.
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                unsafe {
+                    core::arch::asm!("mv tp, {0}", in(reg) anchor);
+                    core::arch::asm!("csrw mscratch, x0");
+                }
+
+                anchor
+            };
+
+            #[cfg(feature = "vfs")]
+            {
+                foundation::kfn::vfs::kinit();
+
+                #[cfg(feature = "vfs-device-console")]
+                {
+                    use zeroos::vfs::devices::console;
+
+                    let _ = console::attach_sink(semihosting_console_sink);
+                    let _ = zeroos::vfs::register_fd(1, console::stdout_factory());
+                    let _ = zeroos::vfs::register_fd(2, console::stderr_factory());
+                    let _ = zeroos::vfs::register_device("/dev/stdout", console::stdout_factory);
+                    let _ = zeroos::vfs::register_device("/dev/stderr", console::stderr_factory);
+                }
+
+                #[cfg(feature = "vfs-device-stdin")]
+                {
+                    use zeroos::vfs::devices::stdin;
+                    let _ = zeroos::vfs::register_fd(0, stdin::stdin_factory());
+                    let _ = zeroos::vfs::register_device("/dev/stdin", stdin::stdin_factory);
+                    let _ = zeroos::vfs::register_device("/dev/input", stdin::tape::input_factory);
+                }
+            }
+
+            // Before entering libc: leave tp for TLS (musl owns it) and park anchor in mscratch,
+            // so a user trap swaps the anchor into tp on entry.
+            #[cfg(feature = "thread")]
+            {
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                unsafe {
+                    core::arch::asm!("csrw mscratch, {0}", in(reg) boot_thread_anchor);
+                    core::arch::asm!("mv tp, x0");
+                }
+            }
+        }
+    }
+}
+
+/// Each flushed chunk is one `SYS_WRITE` to the host console.
+#[cfg(feature = "vfs-device-console")]
+fn semihosting_console_sink(bytes: &[u8]) {
+    semihosting::write(semihosting::stdout(), bytes);
+}
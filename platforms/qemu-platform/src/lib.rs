@@ -0,0 +1,166 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! QEMU `virt` platform: console, input and exit go through RISC-V semihosting, so guests run
+//! under `qemu-system-riscv64 -machine virt -bios none -semihosting -kernel guest.elf`.
+//!
+//! RAM on `virt` starts at `0x8000_0000`, matching the default ZeroOS linker layout.
+
+mod boot;
+#[cfg(all(
+    not(target_os = "none"),
+    any(target_arch = "riscv32", target_arch = "riscv64")
+))]
+mod trap;
+
+extern crate zeroos;
+
+// Platform ABI symbols:
+// - Mandatory:
+//   - `__platform_bootstrap()` (in `boot.rs`): platform init hook called by arch bootstrap.
+//   - `trap_handler(..)` (in `trap.rs`): required on RISC-V targets.
+//   - `__platform_exit(..)`: used by `foundation::kfn::kexit` / platform `exit()`.
+//   - `__platform_stdout_write(..)`: fundamental output primitive, used by panic handler.
+// - Optional:
+//   - `__debug_write(..)`: only required when the `debug` crate is enabled/linked.
+//   - `__platform_input(..)`: host input buffer for the `vfs-device-stdin` feature.
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        pub use std::{eprintln, println};
+
+        pub fn exit(code: i32) -> ! {
+            std::process::exit(code)
+        }
+    } else {
+
+        pub use semihosting::{eprintln, println, putchar};
+
+        pub fn exit(code: i32) -> ! {
+            __platform_exit(code)
+        }
+
+        #[cfg(all(feature = "memory", target_os = "none"))]
+        #[global_allocator]
+        static ALLOCATOR: zeroos::alloc::System = zeroos::alloc::System;
+
+        #[cfg(all(target_os = "none", not(feature = "panic-report")))]
+        #[panic_handler]
+        fn panic(info: &core::panic::PanicInfo) -> ! {
+            eprintln!("PANIC: {}", info);
+
+            // SAFETY: called from the panic handler with a valid stack.
+            unsafe {
+                use zeroos::runtime_nostd::BacktraceCapture;
+                zeroos::runtime_nostd::Backtrace::print_backtrace();
+            }
+
+            __platform_abort(6)
+        }
+    }
+}
+
+pub use foundation::utils::{Hex, Put};
+
+/// `println!` for lines of strings and integers, formatted without `core::fmt` (see
+/// `foundation::utils::line`): `putln!("sum(1..=", n, ") = ", total)`. Integers print in
+/// decimal and [`Hex`] values as `0x..`; there is no padding.
+#[macro_export]
+macro_rules! putln {
+    ($($arg:expr),* $(,)?) => {{
+        let mut line = $crate::stdout_line();
+        $( $crate::Put::put(&$arg, &mut line); )*
+        line.end();
+    }};
+}
+
+/// A line on stdout, for [`putln!`].
+pub fn stdout_line() -> foundation::utils::Line {
+    fn sink(bytes: &[u8]) {
+        // SAFETY: `bytes` is a valid slice for the duration of the call.
+        unsafe { __platform_stdout_write(bytes.as_ptr(), bytes.len()) }
+    }
+    foundation::utils::Line::new(sink)
+}
+
+#[no_mangle]
+pub extern "C" fn __platform_exit(code: i32) -> ! {
+    #[cfg(feature = "vfs-device-console")]
+    zeroos::vfs::devices::console::flush_all();
+    semihosting::exit(code as u32)
+}
+
+/// Host input for `/dev/stdin`: semihosting stdin, read to EOF on first use.
+#[cfg(feature = "vfs-device-stdin")]
+pub const INPUT_CAPACITY: usize = 64 * 1024;
+
+#[cfg(feature = "vfs-device-stdin")]
+struct Input {
+    data: [u8; INPUT_CAPACITY],
+    len: usize,
+    loaded: bool,
+}
+
+#[cfg(feature = "vfs-device-stdin")]
+static INPUT: foundation::utils::GlobalCell<Input> = foundation::utils::GlobalCell::new(Input {
+    data: [0; INPUT_CAPACITY],
+    len: 0,
+    loaded: false,
+});
+
+/// Host input buffer start; its length is stored in `*len`.
+///
+/// Input longer than `INPUT_CAPACITY` is truncated.
+///
+/// # Safety
+/// `len` must be a valid pointer to writable `usize`.
+#[cfg(feature = "vfs-device-stdin")]
+#[no_mangle]
+pub unsafe extern "C" fn __platform_input(len: *mut usize) -> *const u8 {
+    INPUT.with_mut(|input| {
+        if !input.loaded {
+            let stdin = semihosting::stdin();
+            while input.len < INPUT_CAPACITY {
+                match semihosting::read(stdin, &mut input.data[input.len..]) {
+                    0 => break,
+                    n => input.len += n,
+                }
+            }
+            input.loaded = true;
+        }
+        // SAFETY: the caller guarantees `len` is writable.
+        unsafe { *len = input.len };
+        input.data.as_ptr()
+    })
+}
+
+/// Abort with the Linux signal exit code `128 + sig` (134 for SIGABRT).
+#[no_mangle]
+pub extern "C" fn __platform_abort(sig: i32) -> ! {
+    __platform_exit(128 + sig)
+}
+
+#[no_mangle]
+/// Platform stdout write - the fundamental output primitive.
+///
+/// # Safety
+/// - `msg` must be either null (in which case nothing is written) or a valid pointer to `len`
+///   bytes of readable memory.
+pub unsafe extern "C" fn __platform_stdout_write(msg: *const u8, len: usize) {
+    // Keep panic and debug output after whatever the guest already printed.
+    #[cfg(feature = "vfs-device-console")]
+    zeroos::vfs::devices::console::flush_all();
+    if !msg.is_null() && len > 0 {
+        let slice = core::slice::from_raw_parts(msg, len);
+        semihosting::write(semihosting::stdout(), slice);
+    }
+}
+
+#[no_mangle]
+/// Debug write - alias for __platform_stdout_write for zeroos-debug crate.
+///
+/// # Safety
+/// Same as `__platform_stdout_write`.
+#[cfg(feature = "debug")]
+pub unsafe extern "C" fn __debug_write(msg: *const u8, len: usize) {
+    __platform_stdout_write(msg, len);
+}
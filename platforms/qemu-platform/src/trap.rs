@@ -0,0 +1,76 @@
+extern crate zeroos;
+
+use zeroos::arch::riscv::{trap::dispatch_trap_hooks, TrapFrame};
+
+use riscv::register::mcause::Exception;
+
+#[inline(always)]
+fn mcause_is_interrupt(mcause: usize) -> bool {
+    mcause >> (usize::BITS as usize - 1) != 0
+}
+
+#[inline(always)]
+fn mcause_code(mcause: usize) -> usize {
+    // RISC-V encodes interrupts by setting the top bit of mcause; the rest is the code.
+    mcause & ((1usize << (usize::BITS as usize - 1)) - 1)
+}
+
+#[inline(always)]
+fn instr_len(addr: usize) -> usize {
+    let halfword = unsafe { core::ptr::read_unaligned(addr as *const u16) };
+    if (halfword & 0b11) == 0b11 {
+        4
+    } else {
+        2
+    }
+}
+
+/// # Safety
+/// `regs` must be a non-null pointer to a valid `TrapFrame` for the current CPU trap context.
+#[no_mangle]
+pub unsafe extern "C" fn trap_handler(regs: *mut u8) {
+    let regs = regs as *mut TrapFrame;
+    // Hooks registered by extensions get first refusal; everything below is the fallback.
+    if dispatch_trap_hooks(&mut *regs) {
+        return;
+    }
+    let mcause = (*regs).mcause;
+    if mcause_is_interrupt(mcause) {
+        // No interrupts are enabled on this platform.
+        return;
+    }
+
+    match mcause_code(mcause) {
+        // Handle envcalls (syscalls) from any privilege mode.
+        code if code == (Exception::UserEnvCall as usize)
+            || code == (Exception::SupervisorEnvCall as usize)
+            || code == (Exception::MachineEnvCall as usize) =>
+        {
+            let pc = (*regs).mepc;
+            (*regs).mepc = pc + 4;
+
+            #[cfg(feature = "debug")]
+            debug::writeln!("[syscall] {}", zeroos::os::linux::syscall_name((*regs).a7));
+
+            let ret = foundation::kfn::trap::ksyscall(
+                (*regs).a0,
+                (*regs).a1,
+                (*regs).a2,
+                (*regs).a3,
+                (*regs).a4,
+                (*regs).a5,
+                (*regs).a7,
+            );
+            (*regs).a0 = ret as usize;
+        }
+        // QEMU consumes semihosting `ebreak`s itself; this is a plain breakpoint, or a
+        // semihosting call made without `-semihosting`.
+        code if code == (Exception::Breakpoint as usize) => {
+            let pc = (*regs).mepc;
+            (*regs).mepc = pc.wrapping_add(instr_len(pc));
+        }
+        code => {
+            foundation::kfn::kexit(code as i32);
+        }
+    }
+}
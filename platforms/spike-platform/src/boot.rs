@@ -25,12 +25,33 @@ pub extern "C" fn __platform_bootstrap() {
 
     zeroos::initialize();
 
-    #[cfg(feature = "memory")]
+    // A segment continuing a checkpointed run picks it up here and never comes back, so this
+    // runs before anything below writes to memory the snapshot restores.
+    #[cfg(feature = "snapshot")]
+    {
+        #[cfg(feature = "os-linux")]
+        install_trap_vector();
+        crate::resume_snapshot();
+    }
+
+    // Before any source it journals (the RNG and clocks below) can be read.
+    #[cfg(feature = "journal")]
+    crate::start_journal();
+
+    #[cfg(feature = "args")]
+    crate::load_args();
+    #[cfg(feature = "env")]
+    crate::load_env();
+
+    #[cfg(any(feature = "memory", feature = "alloc-stats", feature = "alloc-tlsf"))]
     {
         let heap_start = core::ptr::addr_of!(__heap_start) as usize;
         let heap_end = core::ptr::addr_of!(__heap_end) as usize;
         debug::writeln!("[BOOT] Heap start=0x{:x}, end=0x{:x}", heap_start, heap_end);
         let heap_size = heap_end - heap_start;
+        // Same initial size, but moved down to where it has room to grow.
+        #[cfg(feature = "heap-expand")]
+        let (heap_start, heap_size) = crate::heap_expandable(heap_size);
         foundation::kfn::memory::kinit(heap_start, heap_size);
 
         let _stack_top = core::ptr::addr_of!(__stack_top) as usize;
@@ -42,12 +63,55 @@ pub extern "C" fn __platform_bootstrap() {
         );
     }
 
+    #[cfg(feature = "time")]
+    {
+        // Virtual time: clocks advance with executed cycles at `CLOCK_HZ`, starting at the Unix
+        // epoch, so timed code paths are reproducible across runs and hosts.
+        foundation::kfn::time::kinit(foundation::ops::TimeConfig {
+            cycles: crate::CLOCK_HZ,
+            nanos: 1_000_000_000,
+            realtime_base_ns: 0,
+        });
+    }
+
+    #[cfg(feature = "watchdog")]
+    if let Some(budget) = crate::CYCLE_BUDGET {
+        foundation::watchdog::arm(budget);
+        debug::writeln!("[BOOT] Watchdog armed at {} cycles", budget);
+    }
+
+    // Once the clock is latched; from here on the scheduler keeps the page's tid current.
+    #[cfg(feature = "vdso")]
+    {
+        #[cfg(feature = "time")]
+        let clock = Some(zeroos::time::virtual_time::clock());
+        #[cfg(not(feature = "time"))]
+        let clock = None;
+        #[cfg(feature = "os-linux")]
+        let cpus = zeroos::os::linux::online_cpus() as u32;
+        #[cfg(not(feature = "os-linux"))]
+        let cpus = 1;
+        zeroos::vdso::publish(1, cpus, clock);
+    }
+
     cfg_if::cfg_if! {
-        if #[cfg(not(target_os = "none"))] {
+        if #[cfg(target_os = "none")] {
+            #[cfg(feature = "thread")]
+            {
+                // No trap vector and no libc TLS in no_std mode: every thread runs in kernel
+                // context and keeps tp = its own anchor.
+                let anchor = foundation::kfn::scheduler::kinit();
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                unsafe {
+                    core::arch::asm!("mv tp, {0}", in(reg) anchor);
+                }
+            }
+        } else {
             #[cfg(feature = "os-linux")]
             {
                 install_trap_vector();
                 debug::writeln!("[BOOT] Trap handler installed");
+                register_guest_memory();
             }
 
             #[cfg(feature = "thread")]
@@ -71,17 +135,92 @@ pub extern "C" fn __platform_bootstrap() {
 
                 #[cfg(feature = "vfs-device-console")]
                 {
+                    use zeroos::vfs::devices::console;
+
                     debug::writeln!("[BOOT] Registering console file descriptors");
-                    register_console_fd(1, &STDOUT_FOPS);
-                    register_console_fd(2, &STDERR_FOPS);
+                    let _ = console::attach_sink(htif_console_sink);
+                    let _ = zeroos::vfs::register_fd(1, console::stdout_factory());
+                    let _ = zeroos::vfs::register_fd(2, console::stderr_factory());
+                    let _ = zeroos::vfs::register_device("/dev/stdout", console::stdout_factory);
+                    let _ = zeroos::vfs::register_device("/dev/stderr", console::stderr_factory);
+                }
+
+                #[cfg(feature = "vfs-device-stdin")]
+                {
+                    use zeroos::vfs::devices::stdin;
+                    let _ = zeroos::vfs::register_fd(0, stdin::stdin_factory());
+                    let _ = zeroos::vfs::register_device("/dev/stdin", stdin::stdin_factory);
+                    let _ = zeroos::vfs::register_device("/dev/input", stdin::tape::input_factory);
+                }
+
+                #[cfg(feature = "vfs-device-output")]
+                {
+                    use zeroos::vfs::devices::output;
+                    let _ = zeroos::vfs::register_fd(output::COMMIT_FD, output::output_factory());
+                    let _ = zeroos::vfs::register_device("/dev/commit", output::output_factory);
+                }
+
+                #[cfg(feature = "vfs-tmpfs")]
+                {
+                    // Back every path without a device with in-memory files.
+                    let _ = zeroos::vfs::fs::tmpfs::mount("/");
+                }
+
+                #[cfg(feature = "vfs-procfs")]
+                {
+                    use zeroos::vfs::fs::procfs::{self, Region};
+
+                    // One hart runs the guest; report the linker-placed heap and stack.
+                    #[cfg(not(feature = "heap-expand"))]
+                    let heap_floor = core::ptr::addr_of!(__heap_start) as usize;
+                    #[cfg(feature = "heap-expand")]
+                    let heap_floor = crate::heap_expandable_floor();
+                    let _ = procfs::add_region(Region {
+                        start: heap_floor,
+                        end: core::ptr::addr_of!(__heap_end) as usize,
+                        perms: "rw-p",
+                        name: "[heap]",
+                    });
+                    let _ = procfs::add_region(Region {
+                        start: core::ptr::addr_of!(__stack_bottom) as usize,
+                        end: core::ptr::addr_of!(__stack_top) as usize,
+                        perms: "rw-p",
+                        name: "[stack]",
+                    });
+                    let _ = procfs::mount("/proc");
                 }
             }
 
             #[cfg(feature = "random")]
             {
-                // SECURITY: RNG seed is fixed (0) for deterministic runs (e.g. sims/tests).
-                // Please Replace with a proper seed source for production/real entropy use.
-                foundation::kfn::random::kinit(0);
+                // SECURITY: the seed comes from the `.zeroos_seed` section and is 0 unless the host
+                // patches it. Runs with the same seed produce the same `getrandom` stream.
+                foundation::kfn::random::kinit(crate::random_seed());
+            }
+
+            #[cfg(feature = "runtime-musl")]
+            {
+                use zeroos::runtime::libc::musl::{set_startup_info, StartupInfo};
+
+                #[allow(unused_mut)]
+                let mut info = StartupInfo::new();
+                #[cfg(feature = "args")]
+                if !foundation::args::args().is_empty() {
+                    info.argv = foundation::args::args();
+                }
+                #[cfg(feature = "env")]
+                {
+                    info.envp = foundation::env::vars();
+                }
+                // AT_RANDOM seeds musl's stack protector; draw it from the seeded RNG.
+                #[cfg(feature = "random")]
+                {
+                    let mut random = [0u8; 16];
+                    // SAFETY: `random` is a writable 16-byte buffer.
+                    unsafe { foundation::kfn::random::krandom(random.as_mut_ptr(), random.len()) };
+                    info.random = Some(random);
+                }
+                set_startup_info(info);
             }
 
             // Before entering libc: leave tp for TLS (musl owns it) and park anchor in mscratch,
@@ -94,36 +233,64 @@ pub extern "C" fn __platform_bootstrap() {
                     core::arch::asm!("mv tp, x0");
                 }
             }
+
+            // Last step, so the kernel is never preempted mid-bootstrap. Traps clear MIE on
+            // entry and `mret` restores it, so kernel code always runs with interrupts off.
+            #[cfg(feature = "preempt")]
+            {
+                #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+                unsafe {
+                    crate::__platform_timer_arm(scheduler_cooperative::preempt::QUANTUM_TICKS);
+                    riscv::register::mie::set_mtimer();
+                    riscv::register::mstatus::set_mie();
+                }
+            }
         }
     }
 }
 
-cfg_if::cfg_if! {
-    if #[cfg(feature = "vfs-device-console")] {
-        use zeroos::vfs::{self};
+/// Memory the guest may pass to syscalls: the image, the heap (which also backs `mmap` and
+/// thread stacks) and the boot stack. Anything else, the guard page included, fails with EFAULT.
+#[cfg(feature = "os-linux")]
+fn register_guest_memory() {
+    use core::ptr::addr_of;
+    use foundation::uaccess::register_region;
 
-        fn htif_console_write(_file: *mut u8, buf: *const u8, count: usize) -> isize {
-            unsafe {
-                let slice = core::slice::from_raw_parts(buf, count);
-                for &byte in slice {
-                    htif::putchar(byte);
-                }
-            }
-            count as isize
-        }
+    extern "C" {
+        static __ehdr_start: u8;
+        static __free_start: u8;
+    }
 
-        fn register_console_fd(fd: i32, ops: &'static vfs::FileOps) {
-            debug::writeln!("[HTIF] register_console_fd fd={}", fd);
-            let _ = vfs::register_fd(
-                fd,
-                vfs::FdEntry {
-                    ops,
-                    private_data: core::ptr::null_mut(),
-                },
+    #[cfg(not(feature = "heap-expand"))]
+    let heap_floor = addr_of!(__heap_start) as usize;
+    #[cfg(feature = "heap-expand")]
+    let heap_floor = crate::heap_expandable_floor();
+    let regions = [
+        (
+            addr_of!(__ehdr_start) as usize,
+            addr_of!(__free_start) as usize,
+        ),
+        (heap_floor, addr_of!(__heap_end) as usize),
+        (
+            addr_of!(__stack_bottom) as usize,
+            addr_of!(__stack_top) as usize,
+        ),
+    ];
+    for (start, end) in regions {
+        if !register_region(start, end) {
+            debug::writeln!(
+                "[BOOT] Guest region 0x{:x}..0x{:x} not registered",
+                start,
+                end
             );
         }
+    }
+}
 
-        static STDOUT_FOPS: vfs::FileOps = vfs::devices::console::stdout_fops(htif_console_write);
-        static STDERR_FOPS: vfs::FileOps = vfs::devices::console::stderr_fops(htif_console_write);
+/// HTIF has no bulk write: the console buffers lines, the sink still goes byte by byte.
+#[cfg(feature = "vfs-device-console")]
+fn htif_console_sink(bytes: &[u8]) {
+    for &byte in bytes {
+        htif::putchar(byte);
     }
 }
@@ -17,6 +17,12 @@ extern crate zeroos;
 //   - `__platform_stdout_write(..)`: fundamental output primitive, used by panic handler.
 // - Optional:
 //   - `__debug_write(..)`: only required when the `debug` crate is enabled/linked.
+//   - `__platform_cycle_count()`: cycle source for the `time` feature.
+//   - `__platform_timer_arm(..)`: machine timer for the `preempt` feature.
+//   - `__platform_input(..)`: host input buffer for the `vfs-device-stdin` feature.
+//   - `__platform_output(..)`, `__platform_output_finalize(..)`: public output for the
+//     `vfs-device-output` feature.
+//   - `__platform_expand_heap(..)`: more heap on demand for the `heap-expand` feature.
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
@@ -33,11 +39,11 @@ cfg_if::cfg_if! {
             __platform_exit(code)
         }
 
-        #[cfg(all(feature = "memory", target_os = "none"))]
+        #[cfg(all(any(feature = "memory", feature = "alloc-stats", feature = "alloc-tlsf"), target_os = "none"))]
         #[global_allocator]
         static ALLOCATOR: zeroos::alloc::System = zeroos::alloc::System;
 
-        #[cfg(target_os = "none")]
+        #[cfg(all(target_os = "none", not(feature = "panic-report")))]
         #[panic_handler]
         fn panic(info: &core::panic::PanicInfo) -> ! {
             eprintln!("PANIC: {}", info);
@@ -55,11 +61,720 @@ cfg_if::cfg_if! {
     }
 }
 
+pub use foundation::utils::{Hex, Put};
+
+/// `println!` for lines of strings and integers, formatted without `core::fmt` (see
+/// `foundation::utils::line`): `putln!("sum(1..=", n, ") = ", total)`. Integers print in
+/// decimal and [`Hex`] values as `0x..`; there is no padding.
+#[macro_export]
+macro_rules! putln {
+    ($($arg:expr),* $(,)?) => {{
+        let mut line = $crate::stdout_line();
+        $( $crate::Put::put(&$arg, &mut line); )*
+        line.end();
+    }};
+}
+
+/// A line on stdout, for [`putln!`].
+pub fn stdout_line() -> foundation::utils::Line {
+    fn sink(bytes: &[u8]) {
+        // SAFETY: `bytes` is a valid slice for the duration of the call.
+        unsafe { __platform_stdout_write(bytes.as_ptr(), bytes.len()) }
+    }
+    foundation::utils::Line::new(sink)
+}
+
+#[cfg(feature = "memory")]
+pub use foundation::ops::HeapStats;
+
+/// Heap bytes used/remaining and the high-water mark, so guests can back off before OOM.
+#[cfg(feature = "memory")]
+pub fn heap_stats() -> HeapStats {
+    foundation::kfn::memory::kheap_stats()
+}
+
 #[no_mangle]
 pub extern "C" fn __platform_exit(code: i32) -> ! {
+    #[cfg(feature = "heap-guard")]
+    if !foundation::kfn::memory::kheap_guard_intact() {
+        let msg = b"heap guard corrupted: heap overflow or stack overflow into heap\n";
+        // SAFETY: `msg` is a valid static byte string.
+        unsafe { __platform_stdout_write(msg.as_ptr(), msg.len()) };
+    }
+    #[cfg(feature = "heap-canary")]
+    if !foundation::kfn::memory::kheap_canaries_intact() {
+        let msg = b"heap canary corrupted: a live heap block was overrun\n";
+        // SAFETY: `msg` is a valid static byte string.
+        unsafe { __platform_stdout_write(msg.as_ptr(), msg.len()) };
+    }
+    #[cfg(feature = "vfs-device-console")]
+    zeroos::vfs::devices::console::flush_all();
+    #[cfg(feature = "vfs-device-output")]
+    zeroos::vfs::devices::output::finalize();
+    #[cfg(feature = "perf")]
+    zeroos::perf::dump();
+    #[cfg(feature = "scheduler-stats")]
+    zeroos::scheduler::dump_stats();
+    #[cfg(feature = "alloc-stats")]
+    zeroos::alloc_stats::dump();
+    #[cfg(feature = "journal")]
+    dump_journal();
     htif::exit(code as u32)
 }
 
+/// Cycle counter backing virtual time.
+///
+/// Spike retires one instruction per `mcycle` tick, so this is deterministic for a given binary.
+#[cfg(feature = "time")]
+#[no_mangle]
+pub extern "C" fn __platform_cycle_count() -> u64 {
+    cfg_if::cfg_if! {
+        if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
+            riscv::register::mcycle::read64()
+        } else {
+            0
+        }
+    }
+}
+
+/// CLINT registers on Spike (hart 0).
+#[cfg(feature = "preempt")]
+const CLINT_MTIMECMP: usize = 0x0200_4000;
+#[cfg(feature = "preempt")]
+const CLINT_MTIME: usize = 0x0200_bff8;
+
+/// Schedule a machine timer interrupt `ticks` CLINT ticks from now.
+#[cfg(feature = "preempt")]
+#[no_mangle]
+pub extern "C" fn __platform_timer_arm(ticks: u64) {
+    // SAFETY: the CLINT is mapped at these fixed addresses on Spike; the volatile accesses only
+    // touch timer registers.
+    unsafe {
+        let now = core::ptr::read_volatile(CLINT_MTIME as *const u64);
+        let deadline = now.wrapping_add(ticks);
+        cfg_if::cfg_if! {
+            if #[cfg(target_pointer_width = "32")] {
+                // Raise the high word first so no spurious interrupt fires between the writes.
+                let cmp = CLINT_MTIMECMP as *mut u32;
+                core::ptr::write_volatile(cmp.add(1), u32::MAX);
+                core::ptr::write_volatile(cmp, deadline as u32);
+                core::ptr::write_volatile(cmp.add(1), (deadline >> 32) as u32);
+            } else {
+                core::ptr::write_volatile(CLINT_MTIMECMP as *mut u64, deadline);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "heap-expand")]
+extern "C" {
+    static __free_start: u8;
+    static __heap_end: u8;
+}
+
+/// Smallest growth step, so a run of small allocations does not come back here every time.
+#[cfg(feature = "heap-expand")]
+const HEAP_EXPAND_STEP: usize = 64 * 1024;
+
+/// End of the memory handed to the heap so far.
+#[cfg(feature = "heap-expand")]
+static HEAP_TOP: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+/// Lowest address the heap can occupy once it has grown.
+#[cfg(feature = "heap-expand")]
+pub(crate) fn heap_expandable_floor() -> usize {
+    core::ptr::addr_of!(__free_start) as usize
+}
+
+/// Place the initial heap of `size` bytes at the bottom of the RAM the image leaves free, so it
+/// can grow upward through the rest of it. Returns the heap start and the size actually given.
+#[cfg(feature = "heap-expand")]
+pub(crate) fn heap_expandable(size: usize) -> (usize, usize) {
+    let start = heap_expandable_floor();
+    let limit = core::ptr::addr_of!(__heap_end) as usize;
+    let size = size.min(limit.saturating_sub(start));
+    HEAP_TOP.store(start + size, core::sync::atomic::Ordering::Relaxed);
+    (start, size)
+}
+
+/// Hand the next block of free RAM above the heap to the allocator, up to the end of the
+/// linker's heap reservation below the stack.
+///
+/// # Safety
+/// `start` and `len` must be valid pointers to writable `usize`s.
+#[cfg(feature = "heap-expand")]
+#[no_mangle]
+pub unsafe extern "C" fn __platform_expand_heap(
+    min_bytes: usize,
+    start: *mut usize,
+    len: *mut usize,
+) -> bool {
+    use core::sync::atomic::Ordering;
+
+    let top = HEAP_TOP.load(Ordering::Relaxed);
+    let limit = core::ptr::addr_of!(__heap_end) as usize;
+    let grant = min_bytes
+        .max(HEAP_EXPAND_STEP)
+        .next_multiple_of(4096)
+        .min(limit.saturating_sub(top));
+    if grant < min_bytes {
+        return false;
+    }
+    HEAP_TOP.store(top + grant, Ordering::Relaxed);
+    debug::writeln!("[HEAP] expand by 0x{:x} at 0x{:x}", grant, top);
+    // SAFETY: the caller guarantees both pointers are writable.
+    unsafe {
+        *start = top;
+        *len = grant;
+    }
+    true
+}
+
+/// CLINT machine software interrupt pending registers, one `u32` per hart.
+#[cfg(feature = "smp")]
+const CLINT_MSIP: usize = 0x0200_0000;
+
+/// Wake a parked hart by raising its machine software interrupt.
+#[cfg(feature = "smp")]
+#[no_mangle]
+pub extern "C" fn __platform_hart_wake(hart: usize) {
+    // SAFETY: the CLINT is mapped at this fixed address on Spike; `start_hart` bounds `hart`.
+    unsafe {
+        core::ptr::write_volatile((CLINT_MSIP as *mut u32).add(hart), 1);
+    }
+}
+
+/// Acknowledge the wake-up on the woken hart, so its `msip` does not stay pending.
+#[cfg(feature = "smp")]
+#[no_mangle]
+pub extern "C" fn __platform_hart_woken(hart: usize) {
+    // SAFETY: as in `__platform_hart_wake`; each hart only clears its own register.
+    unsafe {
+        core::ptr::write_volatile((CLINT_MSIP as *mut u32).add(hart), 0);
+    }
+}
+
+#[cfg(feature = "os-linux")]
+pub use zeroos::os::linux::hostcall::Hostcall;
+
+/// Syscall number of Spike hostcall 0.
+#[cfg(feature = "os-linux")]
+pub const HOSTCALL_BASE: usize = zeroos::os::linux::hostcall::DEFAULT_HOSTCALL_BASE;
+
+/// Serve hostcall `id` with `calls[id]`. Call during bootstrap, before any [`hostcall`].
+#[cfg(feature = "os-linux")]
+pub fn register_hostcalls(calls: &'static [Hostcall]) {
+    zeroos::os::linux::hostcall::register_hostcalls(zeroos::os::linux::hostcall::HostcallTable {
+        base: HOSTCALL_BASE,
+        calls,
+    });
+}
+
+/// Issue hostcall `id` with up to six arguments; returns its result or a negative errno
+/// (`ENOSYS` if nothing serves `id`).
+#[cfg(feature = "os-linux")]
+pub fn hostcall<const N: usize>(id: usize, args: [usize; N]) -> isize {
+    const { assert!(N <= 6, "hostcalls take at most six arguments") };
+    let mut a = [0usize; 6];
+    a[..N].copy_from_slice(&args);
+    syscall6(HOSTCALL_BASE + id, a)
+}
+
+#[cfg(feature = "os-linux")]
+pub use zeroos::os::linux::batch::{BatchEntry, BATCH_STOP_ON_ERROR};
+
+/// Run every entry of `entries` with a single trap, storing each result in its `ret`.
+///
+/// Returns the number of entries run or a negative errno.
+#[cfg(feature = "os-linux")]
+pub fn syscall_batch(entries: &mut [BatchEntry], flags: usize) -> isize {
+    let a = [entries.as_mut_ptr() as usize, entries.len(), flags, 0, 0, 0];
+    syscall6(zeroos::os::linux::batch::SYS_BATCH, a)
+}
+
+#[cfg(feature = "os-linux")]
+fn syscall6(nr: usize, a: [usize; 6]) -> isize {
+    cfg_if::cfg_if! {
+        if #[cfg(all(not(target_os = "none"), any(target_arch = "riscv32", target_arch = "riscv64")))] {
+            let ret: isize;
+            // SAFETY: `ecall` traps into `trap_handler`, which dispatches `nr` through
+            // `linux_handle` and only writes the result back to a0.
+            unsafe {
+                core::arch::asm!(
+                    "ecall",
+                    inlateout("a0") a[0] => ret,
+                    in("a1") a[1],
+                    in("a2") a[2],
+                    in("a3") a[3],
+                    in("a4") a[4],
+                    in("a5") a[5],
+                    in("a7") nr,
+                );
+            }
+            ret
+        } else {
+            zeroos::os::linux::linux_handle(a[0], a[1], a[2], a[3], a[4], a[5], nr)
+        }
+    }
+}
+
+/// Host input for `/dev/stdin`, `INPUT_CAPACITY` bytes after a little-endian `u32` length.
+#[cfg(feature = "vfs-device-stdin")]
+#[repr(C)]
+pub struct InputBuffer {
+    len: u32,
+    data: [u8; INPUT_CAPACITY],
+}
+
+#[cfg(feature = "vfs-device-stdin")]
+pub const INPUT_CAPACITY: usize = 64 * 1024;
+
+/// Empty at build time; the host fills it in before running, e.g.
+/// `objcopy --update-section .zeroos_input=input.bin guest.elf` with `input.bin` padded to
+/// `4 + INPUT_CAPACITY` bytes.
+#[cfg(feature = "vfs-device-stdin")]
+#[no_mangle]
+#[used]
+#[link_section = ".zeroos_input"]
+static __zeroos_input: InputBuffer = InputBuffer {
+    len: 0,
+    data: [0; INPUT_CAPACITY],
+};
+
+/// Host input buffer start; its length is stored in `*len`.
+///
+/// # Safety
+/// `len` must be a valid pointer to writable `usize`.
+#[cfg(feature = "vfs-device-stdin")]
+#[no_mangle]
+pub unsafe extern "C" fn __platform_input(len: *mut usize) -> *const u8 {
+    let buf = core::ptr::addr_of!(__zeroos_input);
+    // SAFETY: the buffer is a static; the volatile read keeps the compiler from folding the
+    // build-time length of 0. The caller guarantees `len` is writable.
+    unsafe {
+        let n = core::ptr::read_volatile(core::ptr::addr_of!((*buf).len)) as usize;
+        *len = n.min(INPUT_CAPACITY);
+        core::ptr::addr_of!((*buf).data) as *const u8
+    }
+}
+
+/// Command-line arguments, `ARGS_CAPACITY` bytes after a little-endian `u32` length.
+#[cfg(feature = "args")]
+#[repr(C)]
+pub struct ArgsBuffer {
+    len: u32,
+    data: [u8; ARGS_CAPACITY],
+}
+
+#[cfg(feature = "args")]
+pub const ARGS_CAPACITY: usize = 4096;
+
+/// Empty at build time; the host fills it in like `.zeroos_env`, with the whole `argv`, program
+/// name first, each argument ending in a NUL byte.
+#[cfg(feature = "args")]
+#[no_mangle]
+#[used]
+#[link_section = ".zeroos_args"]
+static mut __zeroos_args: ArgsBuffer = ArgsBuffer {
+    len: 0,
+    data: [0; ARGS_CAPACITY],
+};
+
+/// Hand the arguments the host injected to `foundation::args`.
+#[cfg(feature = "args")]
+pub(crate) fn load_args() {
+    let buf = core::ptr::addr_of!(__zeroos_args);
+    // SAFETY: nothing writes the buffer at run time; the volatile read keeps the compiler from
+    // folding the build-time length of 0.
+    let blob: &'static [u8] = unsafe {
+        let len = core::ptr::read_volatile(core::ptr::addr_of!((*buf).len)) as usize;
+        &(&(*buf).data)[..len.min(ARGS_CAPACITY)]
+    };
+    let _count = foundation::args::init(blob);
+    debug::writeln!("[BOOT] {} arguments", _count);
+}
+
+/// Arguments from `.zeroos_args`, program name first, for guests without libc; under
+/// `runtime-musl` they are also the `argv` `main` gets. Empty if the host passed none.
+///
+/// # Panics
+/// On an argument that is not UTF-8, as `std::env::args` does.
+#[cfg(feature = "args")]
+pub fn args() -> impl ExactSizeIterator<Item = &'static str> {
+    foundation::args::args().iter().map(|arg| {
+        core::str::from_utf8(&arg[..arg.len() - 1]).expect("argument is not valid UTF-8")
+    })
+}
+
+/// Environment variables, `ENV_CAPACITY` bytes after a little-endian `u32` length.
+#[cfg(feature = "env")]
+#[repr(C)]
+pub struct EnvBuffer {
+    len: u32,
+    data: [u8; ENV_CAPACITY],
+}
+
+#[cfg(feature = "env")]
+pub const ENV_CAPACITY: usize = 4096;
+
+/// Empty at build time; the host fills it in like `.zeroos_input`, with `KEY=VALUE` entries
+/// that each end in a NUL byte: `objcopy --update-section .zeroos_env=env.bin guest.elf`.
+#[cfg(feature = "env")]
+#[no_mangle]
+#[used]
+#[link_section = ".zeroos_env"]
+static mut __zeroos_env: EnvBuffer = EnvBuffer {
+    len: 0,
+    data: [0; ENV_CAPACITY],
+};
+
+/// Hand the variables the host injected to `foundation::env`.
+#[cfg(feature = "env")]
+pub(crate) fn load_env() {
+    let buf = core::ptr::addr_of!(__zeroos_env);
+    // SAFETY: nothing writes the buffer at run time; the volatile read keeps the compiler from
+    // folding the build-time length of 0.
+    let blob: &'static [u8] = unsafe {
+        let len = core::ptr::read_volatile(core::ptr::addr_of!((*buf).len)) as usize;
+        &(&(*buf).data)[..len.min(ENV_CAPACITY)]
+    };
+    let _count = foundation::env::init(blob);
+    debug::writeln!("[BOOT] {} environment variables", _count);
+}
+
+/// Value of the environment variable `key` from `.zeroos_env`, if it is UTF-8. Under
+/// `runtime-musl` the same variables are in `envp`, so `std::env::var` sees them too.
+#[cfg(feature = "env")]
+pub fn getenv(key: &str) -> Option<&'static str> {
+    foundation::env::get(key).and_then(|value| core::str::from_utf8(value).ok())
+}
+
+/// Cycle budget the watchdog is armed with at boot, from `ZEROOS_CYCLE_BUDGET` in the build
+/// environment (decimal, `_` separators allowed); without it the watchdog stays disarmed until the
+/// guest calls `zeroos::foundation::watchdog::arm`.
+#[cfg(feature = "watchdog")]
+pub const CYCLE_BUDGET: Option<u64> = match option_env!("ZEROOS_CYCLE_BUDGET") {
+    Some(budget) => match parse_decimal(budget.as_bytes()) {
+        Some(cycles) => Some(cycles),
+        None => panic!("ZEROOS_CYCLE_BUDGET is not a decimal u64"),
+    },
+    None => None,
+};
+
+/// Cycles per second that virtual time assumes, from `ZEROOS_CLOCK_HZ` in the build environment
+/// (decimal, `_` separators allowed); 1 GHz without it.
+#[cfg(feature = "time")]
+pub const CLOCK_HZ: u64 = match option_env!("ZEROOS_CLOCK_HZ") {
+    Some(hz) => match parse_decimal(hz.as_bytes()) {
+        Some(0) => panic!("ZEROOS_CLOCK_HZ is 0"),
+        Some(hz) => hz,
+        None => panic!("ZEROOS_CLOCK_HZ is not a decimal u64"),
+    },
+    None => 1_000_000_000,
+};
+
+/// `digits` as a decimal number, skipping `_`; `None` if empty, not decimal or past `u64::MAX`.
+#[cfg(any(feature = "watchdog", feature = "time"))]
+const fn parse_decimal(digits: &[u8]) -> Option<u64> {
+    if digits.is_empty() {
+        return None;
+    }
+    let mut value: u64 = 0;
+    let mut i = 0;
+    while i < digits.len() {
+        let digit = digits[i];
+        i += 1;
+        if digit == b'_' {
+            continue;
+        }
+        if !digit.is_ascii_digit() {
+            return None;
+        }
+        value = match value.checked_mul(10) {
+            Some(v) => match v.checked_add((digit - b'0') as u64) {
+                Some(v) => v,
+                None => return None,
+            },
+            None => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Committed public output, `OUTPUT_CAPACITY` bytes after a little-endian `u32` length.
+#[cfg(feature = "vfs-device-output")]
+#[repr(C)]
+pub struct OutputBuffer {
+    len: u32,
+    data: [u8; OUTPUT_CAPACITY],
+}
+
+#[cfg(feature = "vfs-device-output")]
+pub const OUTPUT_CAPACITY: usize = 64 * 1024;
+
+/// Filled by writes to `/dev/commit`; its length is set at exit, so a host reading guest memory
+/// finds the output here as well as in the `[commit]` lines.
+#[cfg(feature = "vfs-device-output")]
+#[no_mangle]
+#[used]
+#[link_section = ".zeroos_output"]
+static mut __zeroos_output: OutputBuffer = OutputBuffer {
+    len: 0,
+    data: [0; OUTPUT_CAPACITY],
+};
+
+/// Output buffer start; its capacity is stored in `*cap`.
+///
+/// # Safety
+/// `cap` must be a valid pointer to writable `usize`.
+#[cfg(feature = "vfs-device-output")]
+#[no_mangle]
+pub unsafe extern "C" fn __platform_output(cap: *mut usize) -> *mut u8 {
+    // SAFETY: the caller guarantees `cap` is writable; only the output device writes the buffer.
+    unsafe {
+        *cap = OUTPUT_CAPACITY;
+        core::ptr::addr_of_mut!(__zeroos_output.data) as *mut u8
+    }
+}
+
+/// Record the output length and print the output as hex, led by that little-endian `u32`
+/// length, so the `[commit]` lines fed through `xxd -r -p` form a `.zeroos_output` image.
+///
+/// # Safety
+/// `buf` must point to `len` readable bytes.
+#[cfg(feature = "vfs-device-output")]
+#[no_mangle]
+pub unsafe extern "C" fn __platform_output_finalize(buf: *const u8, len: usize) {
+    let len = len.min(OUTPUT_CAPACITY);
+    // SAFETY: the header is only written here, once, at exit; the caller guarantees `buf`.
+    let bytes = unsafe {
+        core::ptr::write_volatile(core::ptr::addr_of_mut!(__zeroos_output.len), len as u32);
+        core::slice::from_raw_parts(buf, len)
+    };
+    htif::println!("[commit] {}", Hex(&(len as u32).to_le_bytes()[..]));
+    for chunk in bytes.chunks(32) {
+        htif::println!("[commit] {}", Hex(chunk));
+    }
+}
+
+/// Event journal, `JOURNAL_CAPACITY` bytes after a little-endian `u32` length.
+#[cfg(feature = "journal")]
+#[repr(C)]
+pub struct JournalBuffer {
+    len: u32,
+    data: [u8; JOURNAL_CAPACITY],
+}
+
+#[cfg(feature = "journal")]
+pub const JOURNAL_CAPACITY: usize = 64 * 1024;
+
+/// Empty at build time, so the run records into it. To replay a recorded run, the host fills it
+/// in like `.zeroos_input`: `objcopy --update-section .zeroos_journal=journal.bin guest.elf`.
+#[cfg(feature = "journal")]
+#[no_mangle]
+#[used]
+#[link_section = ".zeroos_journal"]
+static mut __zeroos_journal: JournalBuffer = JournalBuffer {
+    len: 0,
+    data: [0; JOURNAL_CAPACITY],
+};
+
+/// Replay the journal the host committed, or record into the buffer if there is none.
+#[cfg(feature = "journal")]
+pub(crate) fn start_journal() {
+    use foundation::journal;
+
+    let buf = core::ptr::addr_of_mut!(__zeroos_journal);
+    // SAFETY: called once during bootstrap, before anything else touches the buffer; the
+    // volatile read keeps the compiler from folding the build-time length of 0.
+    unsafe {
+        let len = core::ptr::read_volatile(core::ptr::addr_of!((*buf).len)) as usize;
+        let data = &mut *core::ptr::addr_of_mut!((*buf).data);
+        if len == 0 {
+            journal::start_recording(data);
+        } else {
+            journal::start_replay(&data[..len.min(JOURNAL_CAPACITY)]);
+        }
+    }
+}
+
+/// Print the recorded journal as hex, led by its little-endian `u32` length, so the `[journal]`
+/// lines fed through `xxd -r -p` form a `.zeroos_journal` image for a replay run.
+#[cfg(feature = "journal")]
+fn dump_journal() {
+    foundation::journal::with_recorded(|bytes, truncated| {
+        if bytes.is_empty() {
+            return;
+        }
+        if truncated {
+            debug::writeln!("[journal] truncated: later events were not recorded");
+        }
+        debug::writeln!("[journal] {}", Hex(&(bytes.len() as u32).to_le_bytes()[..]));
+        for chunk in bytes.chunks(32) {
+            debug::writeln!("[journal] {}", Hex(chunk));
+        }
+    });
+}
+
+/// Snapshot image, `SNAPSHOT_CAPACITY` bytes after a little-endian `u32` length.
+#[cfg(feature = "snapshot")]
+#[repr(C)]
+pub struct SnapshotBuffer {
+    len: u32,
+    data: [u8; SNAPSHOT_CAPACITY],
+}
+
+#[cfg(feature = "snapshot")]
+pub const SNAPSHOT_CAPACITY: usize = 1024 * 1024;
+
+/// Empty at build time, so the run starts from scratch. To continue a checkpointed run, the host
+/// fills it in like `.zeroos_journal`: `objcopy --update-section .zeroos_snapshot=snapshot.bin`.
+#[cfg(feature = "snapshot")]
+#[no_mangle]
+#[used]
+#[link_section = ".zeroos_snapshot"]
+static mut __zeroos_snapshot: SnapshotBuffer = SnapshotBuffer {
+    len: 0,
+    data: [0; SNAPSHOT_CAPACITY],
+};
+
+#[cfg(feature = "snapshot")]
+pub use foundation::snapshot::{Checkpoint, SnapshotError};
+
+/// The writable memory of a run: `.data`, `.bss`, the heap and the boot stack, plus the vDSO
+/// page. The platform's own buffers (input, journal, snapshot) and HTIF stay out.
+#[cfg(feature = "snapshot")]
+fn snapshot_regions() -> [foundation::snapshot::Region; 5] {
+    use core::ptr::addr_of;
+    use foundation::snapshot::Region;
+
+    extern "C" {
+        static __data_start: u8;
+        static __data_end: u8;
+        static __bss_start: u8;
+        static __bss_end: u8;
+        static __heap_start: u8;
+        static __heap_end: u8;
+        static __stack_bottom: u8;
+        static __stack_top: u8;
+    }
+
+    #[cfg(not(feature = "heap-expand"))]
+    let heap_floor = addr_of!(__heap_start) as usize;
+    #[cfg(feature = "heap-expand")]
+    let heap_floor = heap_expandable_floor();
+    #[cfg(feature = "vdso")]
+    let vdso = {
+        let page = zeroos::vdso::data() as *const zeroos::vdso::VdsoData as usize;
+        Region::new(page, page + core::mem::size_of::<zeroos::vdso::VdsoData>())
+    };
+    #[cfg(not(feature = "vdso"))]
+    let vdso = Region::new(0, 0);
+
+    [
+        Region::new(
+            addr_of!(__data_start) as usize,
+            addr_of!(__data_end) as usize,
+        ),
+        Region::new(addr_of!(__bss_start) as usize, addr_of!(__bss_end) as usize),
+        Region::new(heap_floor, addr_of!(__heap_end) as usize),
+        Region::new(
+            addr_of!(__stack_bottom) as usize,
+            addr_of!(__stack_top) as usize,
+        ),
+        vdso,
+    ]
+}
+
+/// Checkpoint the run into `.zeroos_snapshot` so a later segment can continue it.
+///
+/// Returns [`Checkpoint::Saved`] with the image length after printing the image as `[snapshot]`
+/// hex lines, led by its little-endian `u32` length (`xxd -r -p` turns them into a
+/// `.zeroos_snapshot` image); the caller usually exits next to end the segment. A run booted
+/// from that image comes back here with [`Checkpoint::Resumed`]. Saving scans every region, the
+/// whole heap reservation included.
+#[cfg(feature = "snapshot")]
+pub fn checkpoint() -> Result<Checkpoint, SnapshotError> {
+    let regions = snapshot_regions();
+    let buf = core::ptr::addr_of_mut!(__zeroos_snapshot);
+
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    let (mie, anchor) = {
+        let mie = riscv::register::mstatus::read().mie();
+        // SAFETY: interrupts come back on below; nothing may switch threads mid-copy.
+        unsafe { riscv::register::mstatus::clear_mie() };
+        (mie, riscv::register::mscratch::read())
+    };
+
+    // SAFETY: the regions are this image's writable memory and exclude the snapshot buffer.
+    let result = unsafe { foundation::snapshot::checkpoint(&regions, &mut (*buf).data) };
+    match result {
+        Ok(Checkpoint::Saved(len)) => {
+            // SAFETY: written once per checkpoint; volatile to pair with the read at resume.
+            unsafe { core::ptr::write_volatile(core::ptr::addr_of_mut!((*buf).len), len as u32) };
+            let bytes = unsafe { &(&(*buf).data)[..len] };
+            debug::writeln!("[snapshot] {}", Hex(&(len as u32).to_le_bytes()[..]));
+            for chunk in bytes.chunks(32) {
+                debug::writeln!("[snapshot] {}", Hex(chunk));
+            }
+        }
+        Ok(Checkpoint::Resumed) => {
+            // The boot that resumed us set up its own trap state; put back the thread's.
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            riscv::register::mscratch::write(anchor);
+            #[cfg(all(
+                feature = "preempt",
+                any(target_arch = "riscv32", target_arch = "riscv64")
+            ))]
+            unsafe {
+                __platform_timer_arm(scheduler_cooperative::preempt::QUANTUM_TICKS);
+                riscv::register::mie::set_mtimer();
+            }
+        }
+        Err(_) => {}
+    }
+
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    if mie {
+        // SAFETY: restores the interrupt enable found on entry.
+        unsafe { riscv::register::mstatus::set_mie() };
+    }
+    result
+}
+
+/// Continue the run checkpointed in `.zeroos_snapshot`, if the host put one there. Does not
+/// return in that case.
+#[cfg(feature = "snapshot")]
+pub(crate) fn resume_snapshot() {
+    let buf = core::ptr::addr_of!(__zeroos_snapshot);
+    // SAFETY: called during bootstrap before any region is written; the volatile read keeps the
+    // compiler from folding the build-time length of 0.
+    unsafe {
+        let len = core::ptr::read_volatile(core::ptr::addr_of!((*buf).len)) as usize;
+        if len == 0 {
+            return;
+        }
+        let image = &(&(*buf).data)[..len.min(SNAPSHOT_CAPACITY)];
+        let err = foundation::snapshot::resume(&snapshot_regions(), image);
+        panic!("cannot resume from .zeroos_snapshot: {:?}", err);
+    }
+}
+
+/// Per-run RNG seed, 0 at build time. The host injects one as 8 little-endian bytes, e.g.
+/// `objcopy --update-section .zeroos_seed=seed.bin guest.elf`.
+#[cfg(feature = "random")]
+#[no_mangle]
+#[used]
+#[link_section = ".zeroos_seed"]
+static __zeroos_seed: u64 = 0;
+
+/// The seed the RNG is initialized with at boot.
+#[cfg(feature = "random")]
+pub fn random_seed() -> u64 {
+    // SAFETY: reading a static; volatile so the build-time value is not folded in.
+    unsafe { core::ptr::read_volatile(core::ptr::addr_of!(__zeroos_seed)) }
+}
+
 /// Abort the program with Linux-standard signal exit code.
 ///
 /// This is called by the panic handler (via `zeroos-runtime-nostd`) or
@@ -85,6 +800,9 @@ pub extern "C" fn __platform_abort(sig: i32) -> ! {
 /// - `msg` must be either null (in which case nothing is written) or a valid pointer to `len`
 ///   bytes of readable memory.
 pub unsafe extern "C" fn __platform_stdout_write(msg: *const u8, len: usize) {
+    // Keep panic and debug output after whatever the guest already printed.
+    #[cfg(feature = "vfs-device-console")]
+    zeroos::vfs::devices::console::flush_all();
     if !msg.is_null() && len > 0 {
         let slice = core::slice::from_raw_parts(msg, len);
         for &byte in slice {
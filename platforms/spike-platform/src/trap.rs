@@ -1,8 +1,10 @@
 extern crate zeroos;
 
-use zeroos::arch::riscv::TrapFrame;
+use zeroos::arch::riscv::{trap::dispatch_trap_hooks, TrapFrame};
 
 use riscv::register::mcause::Exception;
+#[cfg(feature = "preempt")]
+use riscv::register::mcause::Interrupt;
 
 #[inline(always)]
 fn mcause_is_interrupt(mcause: usize) -> bool {
@@ -38,9 +40,20 @@ fn instr_len(addr: usize) -> usize {
 #[no_mangle]
 pub unsafe extern "C" fn trap_handler(regs: *mut u8) {
     let regs = regs as *mut TrapFrame;
+    #[cfg(feature = "watchdog")]
+    foundation::watchdog::check((*regs).mepc);
+    // Hooks registered by extensions get first refusal; everything below is the fallback.
+    if dispatch_trap_hooks(&mut *regs) {
+        return;
+    }
     let mcause = (*regs).mcause;
     if mcause_is_interrupt(mcause) {
-        // Interrupt handling is disabled
+        // Only the machine timer is enabled, and only for preemption. Interrupts do not advance
+        // `mepc`: the interrupted instruction resumes when this thread is scheduled again.
+        #[cfg(feature = "preempt")]
+        if mcause_code(mcause) == Interrupt::MachineTimer as usize {
+            foundation::kfn::scheduler::kpreempt();
+        }
         return;
     }
 
@@ -53,6 +66,15 @@ pub unsafe extern "C" fn trap_handler(regs: *mut u8) {
             let pc = (*regs).mepc;
             (*regs).mepc = pc + 4;
 
+            // Returning from a signal handler restores the whole interrupted frame, so it must
+            // not go through the dispatcher (which would overwrite a0).
+            #[cfg(feature = "signal")]
+            if (*regs).a7 == libc::SYS_rt_sigreturn as usize
+                && zeroos::os::linux::handlers::signal::sigreturn(regs as *mut u8)
+            {
+                return;
+            }
+
             #[cfg(feature = "debug")]
             debug::writeln!("[syscall] {}", zeroos::os::linux::syscall_name((*regs).a7));
 
@@ -71,7 +93,52 @@ pub unsafe extern "C" fn trap_handler(regs: *mut u8) {
             advance_mepc_for_breakpoint(regs);
         }
         code => {
+            #[cfg(feature = "signal")]
+            if let Some((sig, si_code)) = fault_signal(code) {
+                let addr = (*regs).mtval;
+                if zeroos::os::linux::handlers::signal::deliver_fault(
+                    regs as *mut u8,
+                    sig,
+                    si_code,
+                    addr,
+                ) {
+                    return;
+                }
+            }
             foundation::kfn::kexit(code as i32);
         }
     }
 }
+
+/// The synchronous signal (and `si_code`) Linux raises for exception `code`.
+#[cfg(feature = "signal")]
+fn fault_signal(code: usize) -> Option<(i32, i32)> {
+    const SEGV_MAPERR: i32 = 1;
+    const SEGV_ACCERR: i32 = 2;
+    const ILL_ILLOPC: i32 = 1;
+    const BUS_ADRALN: i32 = 1;
+
+    let signal = match code {
+        c if c == Exception::IllegalInstruction as usize => (libc::SIGILL, ILL_ILLOPC),
+        c if c == Exception::InstructionMisaligned as usize
+            || c == Exception::LoadMisaligned as usize
+            || c == Exception::StoreMisaligned as usize =>
+        {
+            (libc::SIGBUS, BUS_ADRALN)
+        }
+        c if c == Exception::InstructionFault as usize
+            || c == Exception::LoadFault as usize
+            || c == Exception::StoreFault as usize =>
+        {
+            (libc::SIGSEGV, SEGV_ACCERR)
+        }
+        c if c == Exception::InstructionPageFault as usize
+            || c == Exception::LoadPageFault as usize
+            || c == Exception::StorePageFault as usize =>
+        {
+            (libc::SIGSEGV, SEGV_MAPERR)
+        }
+        _ => return None,
+    };
+    Some(signal)
+}
@@ -0,0 +1,577 @@
+//! Guest no_std compliance audit.
+//!
+//! Two independent checks:
+//! - dependency graph: every crate reachable (normal edges) from a guest package on a bare-metal
+//!   target must be `#![no_std]` under the resolved features; violations print the full chain.
+//! - final binary: guest ELFs must not use a hard-float ABI or contain F/D instructions.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use object::{Object, ObjectSection, ObjectSymbol, SectionKind};
+
+#[derive(Args)]
+pub struct AuditArgs {
+    /// Only audit these packages (repeatable). Defaults to every `*-none-elf` entry in matrix.yaml.
+    #[arg(short = 'p', long = "package")]
+    packages: Vec<String>,
+
+    /// Bare-metal target used to resolve the dependency graph
+    #[arg(long, default_value = "riscv64imac-unknown-none-elf")]
+    target: String,
+
+    /// Guest ELF(s) to scan for floating-point usage (repeatable).
+    ///
+    /// Without this flag, `target/<target>/{debug,release}/<package>` is scanned when present.
+    #[arg(long = "elf")]
+    elfs: Vec<PathBuf>,
+
+    /// Skip the dependency graph check
+    #[arg(long)]
+    no_deps: bool,
+
+    /// Skip the floating-point binary scan
+    #[arg(long)]
+    no_float: bool,
+}
+
+/// A guest package plus the feature set it is checked with (mirrors `matrix.yaml`).
+struct GuestPackage {
+    name: String,
+    features: Vec<String>,
+}
+
+/// One node of a `cargo tree` walk.
+struct TreeNode {
+    depth: usize,
+    name: String,
+    version: String,
+    features: BTreeSet<String>,
+    proc_macro: bool,
+}
+
+pub fn run(args: AuditArgs) -> Result<()> {
+    let root = crate::findup::workspace_root().map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+    let mut guests = load_guest_packages(&root, &args.target)?;
+    if !args.packages.is_empty() {
+        guests.retain(|g| args.packages.contains(&g.name));
+        for p in &args.packages {
+            if !guests.iter().any(|g| &g.name == p) {
+                guests.push(GuestPackage {
+                    name: p.clone(),
+                    features: Vec::new(),
+                });
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+
+    if !args.no_deps {
+        let src_paths = load_crate_roots(&root, &args.target)?;
+        for g in &guests {
+            println!("audit: deps {} ({})", g.name, args.target);
+            errors.extend(rule_deps_are_no_std(&root, &args.target, g, &src_paths)?);
+        }
+    }
+
+    if !args.no_float {
+        let elfs = if args.elfs.is_empty() {
+            default_elfs(&root, &args.target, &guests)
+        } else {
+            args.elfs.clone()
+        };
+        for elf in &elfs {
+            println!("audit: float {}", elf.display());
+            errors.extend(rule_no_float_instructions(elf)?);
+        }
+    }
+
+    finish(errors)
+}
+
+fn load_guest_packages(root: &Path, target: &str) -> Result<Vec<GuestPackage>> {
+    let path = root.join("matrix.yaml");
+    let raw = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let doc: serde_yaml::Value = serde_yaml::from_slice(&raw)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let mut out: Vec<GuestPackage> = Vec::new();
+    let entries = doc
+        .get("entries")
+        .and_then(|v| v.as_sequence())
+        .cloned()
+        .unwrap_or_default();
+
+    for entry in entries {
+        let mut targets = Vec::new();
+        if let Some(t) = entry.get("target") {
+            flatten_strings(t, &mut targets);
+        }
+        if !targets.iter().any(|t| t == target) {
+            continue;
+        }
+
+        let mut packages = Vec::new();
+        if let Some(p) = entry.get("package") {
+            flatten_strings(p, &mut packages);
+        }
+
+        // Alternatives (`[a, b]`) are mutually exclusive; audit the first one.
+        let mut features = Vec::new();
+        for f in entry
+            .get("features")
+            .and_then(|v| v.as_sequence())
+            .into_iter()
+            .flatten()
+        {
+            match f {
+                serde_yaml::Value::String(s) => features.push(s.clone()),
+                serde_yaml::Value::Sequence(alts) => {
+                    if let Some(s) = alts.first().and_then(|v| v.as_str()) {
+                        features.push(s.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for name in packages {
+            if out.iter().any(|g| g.name == name) {
+                continue;
+            }
+            out.push(GuestPackage {
+                name,
+                features: features.clone(),
+            });
+        }
+    }
+
+    Ok(out)
+}
+
+fn flatten_strings(v: &serde_yaml::Value, out: &mut Vec<String>) {
+    match v {
+        serde_yaml::Value::String(s) => out.push(s.clone()),
+        serde_yaml::Value::Sequence(seq) => seq.iter().for_each(|x| flatten_strings(x, out)),
+        _ => {}
+    }
+}
+
+/// Map `name@version` to the crate root source file.
+fn load_crate_roots(root: &Path, target: &str) -> Result<BTreeMap<String, PathBuf>> {
+    let out = Command::new("cargo")
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--filter-platform",
+            target,
+        ])
+        .current_dir(root)
+        .output()
+        .context("Failed to run cargo metadata")?;
+    if !out.status.success() {
+        bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+
+    let doc: serde_json::Value =
+        serde_json::from_slice(&out.stdout).context("Failed to parse cargo metadata output")?;
+
+    let mut roots = BTreeMap::new();
+    for pkg in doc["packages"].as_array().into_iter().flatten() {
+        let (Some(name), Some(version)) = (pkg["name"].as_str(), pkg["version"].as_str()) else {
+            continue;
+        };
+        let targets: Vec<&serde_json::Value> =
+            pkg["targets"].as_array().into_iter().flatten().collect();
+
+        let has_kind = |t: &serde_json::Value, kind: &str| {
+            t["kind"]
+                .as_array()
+                .is_some_and(|k| k.iter().any(|x| x.as_str() == Some(kind)))
+        };
+
+        // Prefer the library; fall back to the first binary for leaf (example) packages.
+        let chosen = targets
+            .iter()
+            .find(|t| has_kind(t, "lib") || has_kind(t, "rlib") || has_kind(t, "staticlib"))
+            .or_else(|| targets.iter().find(|t| has_kind(t, "bin")));
+        if let Some(src) = chosen.and_then(|t| t["src_path"].as_str()) {
+            roots.insert(format!("{name}@{version}"), PathBuf::from(src));
+        }
+    }
+
+    Ok(roots)
+}
+
+fn cargo_tree(root: &Path, target: &str, guest: &GuestPackage) -> Result<Vec<TreeNode>> {
+    let mut cmd = Command::new("cargo");
+    cmd.args([
+        "tree",
+        "-q",
+        "-p",
+        &guest.name,
+        "--target",
+        target,
+        "-e",
+        "normal",
+        "--prefix",
+        "depth",
+        "--no-default-features",
+        "-f",
+        "{p}|{f}",
+    ]);
+    if !guest.features.is_empty() {
+        cmd.arg("--features").arg(guest.features.join(","));
+    }
+
+    let out = cmd
+        .current_dir(root)
+        .output()
+        .context("Failed to run cargo tree")?;
+    if !out.status.success() {
+        bail!(
+            "cargo tree -p {} failed: {}",
+            guest.name,
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(parse_tree_line)
+        .collect())
+}
+
+/// Parse `<depth><name> v<version> [(...)]|<features> [(*)]`.
+fn parse_tree_line(line: &str) -> Option<TreeNode> {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    let depth = line[..digits].parse().ok()?;
+    let (pkg, features) = line[digits..].split_once('|')?;
+
+    let mut words = pkg.split_whitespace();
+    let name = words.next()?.to_string();
+    let version = words.next()?.trim_start_matches('v').to_string();
+
+    Some(TreeNode {
+        depth,
+        name,
+        version,
+        features: features
+            .trim_end_matches("(*)")
+            .split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(str::to_string)
+            .collect(),
+        proc_macro: pkg.contains("(proc-macro)"),
+    })
+}
+
+fn rule_deps_are_no_std(
+    root: &Path,
+    target: &str,
+    guest: &GuestPackage,
+    src_paths: &BTreeMap<String, PathBuf>,
+) -> Result<Vec<String>> {
+    let nodes = cargo_tree(root, target, guest)?;
+
+    let mut errors = Vec::new();
+    let mut seen = BTreeSet::new();
+    let mut chain: Vec<String> = Vec::new();
+    let mut host_only_depth: Option<usize> = None;
+
+    for node in &nodes {
+        chain.truncate(node.depth);
+        chain.push(node.name.clone());
+
+        // Proc-macros run on the host; their subtree is not linked into the guest.
+        match host_only_depth {
+            Some(d) if node.depth > d => continue,
+            _ => host_only_depth = None,
+        }
+        if node.proc_macro {
+            host_only_depth = Some(node.depth);
+            continue;
+        }
+
+        let key = format!("{}@{}", node.name, node.version);
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+
+        let Some(src) = src_paths.get(&key) else {
+            continue;
+        };
+        let source =
+            fs::read_to_string(src).with_context(|| format!("Failed to read {}", src.display()))?;
+
+        if let Some(reason) = std_requirement(&source, &node.features, target) {
+            errors.push(format!(
+                "[{}] ({}) {} {}: {}",
+                guest.name,
+                target,
+                key,
+                reason,
+                chain.join(" -> ")
+            ));
+        }
+    }
+
+    Ok(errors)
+}
+
+/// Returns why the crate links `std` under `features`, or `None` if it is `no_std`.
+fn std_requirement(source: &str, features: &BTreeSet<String>, target: &str) -> Option<String> {
+    let mut conditional = false;
+
+    for attr in inner_attributes(source) {
+        let attr: String = attr.chars().filter(|c| !c.is_whitespace()).collect();
+        if attr == "no_std" {
+            return None;
+        }
+        let Some(body) = attr
+            .strip_prefix("cfg_attr(")
+            .and_then(|s| s.strip_suffix(')'))
+        else {
+            continue;
+        };
+        let Some((pred, attrs)) = split_top_level_comma(body) else {
+            continue;
+        };
+        if !attrs.split(',').any(|a| a == "no_std") {
+            continue;
+        }
+        conditional = true;
+        if eval_cfg(pred, features, target) {
+            return None;
+        }
+    }
+
+    Some(if conditional {
+        format!(
+            "is std under features [{}]",
+            features.iter().cloned().collect::<Vec<_>>().join(",")
+        )
+    } else {
+        "is not #![no_std]".to_string()
+    })
+}
+
+/// Contents of every `#![...]` in `source`, line comments skipped.
+fn inner_attributes(source: &str) -> Vec<String> {
+    let stripped: String = source
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut out = Vec::new();
+    let mut rest = stripped.as_str();
+    while let Some(start) = rest.find("#![") {
+        rest = &rest[start + 3..];
+        let mut depth = 1usize;
+        let end = rest.char_indices().find_map(|(i, c)| {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => {}
+            }
+            (depth == 0).then_some(i)
+        });
+        let Some(end) = end else { break };
+        out.push(rest[..end].to_string());
+        rest = &rest[end + 1..];
+    }
+    out
+}
+
+fn split_top_level_comma(s: &str) -> Option<(&str, &str)> {
+    let mut depth = 0i32;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => return Some((&s[..i], &s[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Minimal cfg predicate evaluator (`all`/`any`/`not`, `feature = ".."`, `target_os = ".."`).
+///
+/// Unknown keys evaluate to true so unsupported predicates never produce false positives.
+fn eval_cfg(pred: &str, features: &BTreeSet<String>, target: &str) -> bool {
+    let args = |s: &str| -> Vec<String> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some((head, tail)) = split_top_level_comma(rest) {
+            parts.push(head.to_string());
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            parts.push(rest.to_string());
+        }
+        parts
+    };
+
+    if let Some(inner) = pred.strip_prefix("not(").and_then(|s| s.strip_suffix(')')) {
+        return !eval_cfg(inner, features, target);
+    }
+    if let Some(inner) = pred.strip_prefix("all(").and_then(|s| s.strip_suffix(')')) {
+        return args(inner).iter().all(|p| eval_cfg(p, features, target));
+    }
+    if let Some(inner) = pred.strip_prefix("any(").and_then(|s| s.strip_suffix(')')) {
+        return args(inner).iter().any(|p| eval_cfg(p, features, target));
+    }
+    if pred == "test" || pred == "doc" {
+        return false;
+    }
+
+    match pred.split_once('=') {
+        Some(("feature", v)) => features.contains(v.trim_matches('"')),
+        Some(("target_os", v)) => {
+            let os = target.split('-').nth(2).unwrap_or("");
+            os == v.trim_matches('"')
+        }
+        _ => true,
+    }
+}
+
+fn default_elfs(root: &Path, target: &str, guests: &[GuestPackage]) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    for g in guests {
+        for profile in ["debug", "release"] {
+            let p = root.join("target").join(target).join(profile).join(&g.name);
+            if p.is_file() {
+                out.push(p);
+            }
+        }
+    }
+    out
+}
+
+// RISC-V ELF `e_flags` float ABI bits.
+const EF_RISCV_FLOAT_ABI: u32 = 0x6;
+
+fn rule_no_float_instructions(path: &Path) -> Result<Vec<String>> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let file = object::File::parse(&*bytes)
+        .with_context(|| format!("Failed to parse ELF {}", path.display()))?;
+
+    let is_rv32 = match file.architecture() {
+        object::Architecture::Riscv32 => true,
+        object::Architecture::Riscv64 => false,
+        other => bail!("{}: unsupported architecture {:?}", path.display(), other),
+    };
+
+    let mut errors = Vec::new();
+
+    if let object::FileFlags::Elf { e_flags, .. } = file.flags() {
+        if e_flags & EF_RISCV_FLOAT_ABI != 0 {
+            errors.push(format!(
+                "[{}] uses a hard-float ABI (e_flags={:#x})",
+                path.display(),
+                e_flags
+            ));
+        }
+    }
+
+    let mut symbols: Vec<(u64, String)> = file
+        .symbols()
+        .filter(|s| s.kind() == object::SymbolKind::Text)
+        .filter_map(|s| Some((s.address(), s.name().ok()?.to_string())))
+        .collect();
+    symbols.sort();
+
+    // Report each offending function once rather than every instruction.
+    let mut hits: BTreeMap<String, (u64, usize)> = BTreeMap::new();
+
+    for section in file.sections().filter(|s| s.kind() == SectionKind::Text) {
+        let data = section.data()?;
+        let base = section.address();
+        let mut off = 0usize;
+        while off + 2 <= data.len() {
+            let lo = u16::from_le_bytes([data[off], data[off + 1]]);
+            let (len, is_float) = if lo & 0b11 == 0b11 {
+                let Some(hi) = data.get(off + 2..off + 4) else {
+                    break;
+                };
+                let insn = lo as u32 | (u16::from_le_bytes([hi[0], hi[1]]) as u32) << 16;
+                (4, is_float_insn(insn))
+            } else {
+                (2, is_float_cinsn(lo, is_rv32))
+            };
+
+            if is_float {
+                let addr = base + off as u64;
+                let idx = symbols.partition_point(|(a, _)| *a <= addr);
+                let func = idx
+                    .checked_sub(1)
+                    .map(|i| format!("{:#}", rustc_demangle::demangle(&symbols[i].1)))
+                    .unwrap_or_else(|| "<unknown>".to_string());
+                hits.entry(func).or_insert((addr, 0)).1 += 1;
+            }
+            off += len;
+        }
+    }
+
+    for (func, (addr, count)) in hits {
+        errors.push(format!(
+            "[{}] {} floating-point instruction(s) in {} (first at {:#x})",
+            path.display(),
+            count,
+            func,
+            addr
+        ));
+    }
+
+    Ok(errors)
+}
+
+/// 32-bit encodings from the F/D/Q extensions.
+fn is_float_insn(insn: u32) -> bool {
+    let opcode = insn & 0x7f;
+    let funct3 = (insn >> 12) & 0x7;
+    match opcode {
+        // LOAD-FP / STORE-FP; widths 0 and 5..=7 are vector memory ops, not FP.
+        0x07 | 0x27 => matches!(funct3, 0b001..=0b100),
+        // FMADD / FMSUB / FNMSUB / FNMADD / OP-FP
+        0x43 | 0x47 | 0x4b | 0x4f | 0x53 => true,
+        _ => false,
+    }
+}
+
+/// Compressed FP loads/stores (C.FLD/C.FSD[SP], plus C.FLW/C.FSW[SP] on RV32).
+fn is_float_cinsn(insn: u16, is_rv32: bool) -> bool {
+    let quadrant = insn & 0b11;
+    let funct3 = insn >> 13;
+    match (quadrant, funct3) {
+        (0b00 | 0b10, 0b001 | 0b101) => true,
+        (0b00 | 0b10, 0b011 | 0b111) => is_rv32,
+        _ => false,
+    }
+}
+
+fn finish(errors: Vec<String>) -> Result<()> {
+    if errors.is_empty() {
+        println!("No no_std violations found.");
+        return Ok(());
+    }
+
+    eprintln!("Found {} no_std violations:", errors.len());
+    for err in errors {
+        eprintln!("  - {}", err);
+    }
+    bail!("no_std audit failed");
+}
@@ -2,6 +2,7 @@
 
 pub mod act;
 pub mod analyze_backtrace;
+pub mod audit;
 pub mod check_workspace;
 pub mod massage;
 pub mod spike_syscall_instcount;
@@ -31,6 +31,8 @@ enum Command {
     /// Analyze binary sizes for different backtrace modes
     #[command(name = "analyze-backtrace")]
     AnalyzeBacktrace(cmds::analyze_backtrace::AnalyzeBacktraceArgs),
+    /// Audit guest crates for std dependencies and floating-point instructions
+    Audit(cmds::audit::AuditArgs),
 }
 
 fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
@@ -41,6 +43,7 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         Command::SpikeSyscallInstCount(args) => cmds::spike_syscall_instcount::run(args),
         Command::CheckWorkspace(args) => cmds::check_workspace::run(args).map_err(|e| e.into()),
         Command::AnalyzeBacktrace(args) => cmds::analyze_backtrace::run(args).map_err(|e| e.into()),
+        Command::Audit(args) => cmds::audit::run(args).map_err(|e| e.into()),
     }
 }
 